@@ -0,0 +1,378 @@
+//! Settings shared by the manos apps, stored as TOML.
+//!
+//! All apps keep their settings in one `settings.toml` under the platform config directory,
+//! each in a table of its own (`[git-viewer]`, `[story]`, …). A [`Namespace`] reads and
+//! writes one table as a typed struct; missing keys fall back to the struct's defaults, so
+//! a struct should use `#[serde(default)]`. Listeners registered with
+//! [`Namespace::on_change`] run when the table changes, whether through this process or,
+//! with [`ConfigStore::watch`], through an edit of the file.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use toml::{Table, Value};
+
+const DIR_ENV: &str = "MANOS_CONFIG_DIR";
+const FILE_NAME: &str = "settings.toml";
+
+/// The directory holding `settings.toml`: `manos` under `~/Library/Application Support`
+/// on macOS, `%APPDATA%` on Windows and `$XDG_CONFIG_HOME` elsewhere. `MANOS_CONFIG_DIR`
+/// overrides it.
+pub fn config_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os(DIR_ENV).filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    dirs::config_dir().map(|dir| dir.join("manos"))
+}
+
+/// A directory for files an app keeps next to its settings, such as saved layouts.
+pub fn app_dir(app_name: &str) -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(app_name))
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+    /// A table holds values that do not fit the settings struct.
+    Invalid(String, toml::de::Error),
+    Serialize(String, toml::ser::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, err) => write!(f, "{}: {err}", path.display()),
+            ConfigError::Parse(path, err) => write!(f, "{}: {err}", path.display()),
+            ConfigError::Invalid(namespace, err) => write!(f, "[{namespace}]: {err}"),
+            ConfigError::Serialize(namespace, err) => write!(f, "[{namespace}]: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+type Listener = Arc<dyn Fn(Option<&Value>) + Send + Sync>;
+
+struct ListenerEntry {
+    id: u64,
+    namespace: String,
+    listener: Listener,
+}
+
+struct Inner {
+    /// `None` for a store that only lives in memory.
+    path: Option<PathBuf>,
+    table: Table,
+    /// Modification time of the file when it was last read or written.
+    modified: Option<SystemTime>,
+    listeners: Vec<ListenerEntry>,
+    next_listener_id: u64,
+}
+
+impl Inner {
+    /// Collects the listeners of `namespace` so they can run after the lock is released.
+    fn listeners_of(&self, namespace: &str) -> Vec<Listener> {
+        self.listeners
+            .iter()
+            .filter(|entry| entry.namespace == namespace)
+            .map(|entry| entry.listener.clone())
+            .collect()
+    }
+}
+
+/// The parsed settings file. Cloning is cheap and clones share the same state.
+#[derive(Clone)]
+pub struct ConfigStore {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl ConfigStore {
+    /// Opens `settings.toml` in [`config_dir`]; without a config directory the store only
+    /// lives in memory.
+    pub fn open_default() -> Result<Self, ConfigError> {
+        match config_dir() {
+            Some(dir) => Self::open(dir.join(FILE_NAME)),
+            None => Ok(Self::in_memory()),
+        }
+    }
+
+    /// Opens the settings file at `path`. A missing file is an empty one; it is created on
+    /// the first write.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, ConfigError> {
+        let path = path.into();
+        let (table, modified) = read_table(&path)?;
+        Ok(Self::with_state(Some(path), table, modified))
+    }
+
+    pub fn in_memory() -> Self {
+        Self::with_state(None, Table::new(), None)
+    }
+
+    fn with_state(path: Option<PathBuf>, table: Table, modified: Option<SystemTime>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                path,
+                table,
+                modified,
+                listeners: Vec::new(),
+                next_listener_id: 0,
+            })),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        // A listener that panicked cannot leave the table half written.
+        self.inner.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    pub fn path(&self) -> Option<PathBuf> {
+        self.lock().path.clone()
+    }
+
+    /// The table `name` read and written as `T`.
+    pub fn namespace<T>(&self, name: impl Into<String>) -> Namespace<T> {
+        Namespace {
+            store: self.clone(),
+            name: name.into(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Re-reads the file if it changed on disk and notifies the listeners of every table
+    /// that differs. Returns whether anything changed.
+    pub fn reload(&self) -> Result<bool, ConfigError> {
+        let Some(path) = self.path() else {
+            return Ok(false);
+        };
+        let modified = file_modified(&path);
+        if modified == self.lock().modified {
+            return Ok(false);
+        }
+
+        let (table, modified) = read_table(&path)?;
+        let notify = {
+            let mut inner = self.lock();
+            let mut names: Vec<&String> = inner.table.keys().chain(table.keys()).collect();
+            names.sort();
+            names.dedup();
+            let notify: Vec<(Option<Value>, Vec<Listener>)> = names
+                .into_iter()
+                .filter(|name| inner.table.get(*name) != table.get(*name))
+                .map(|name| (table.get(name).cloned(), inner.listeners_of(name)))
+                .collect();
+            inner.table = table;
+            inner.modified = modified;
+            notify
+        };
+
+        let changed = !notify.is_empty();
+        for (value, listeners) in notify {
+            for listener in listeners {
+                listener(value.as_ref());
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Polls the file every `interval` on a background thread and reloads it when it
+    /// changes. Polling stops when the returned handle is dropped.
+    pub fn watch(&self, interval: Duration) -> ConfigWatcher {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let store = Arc::downgrade(&self.inner);
+        let stop = stopped.clone();
+        thread::Builder::new()
+            .name("manos-config-watch".into())
+            .spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    thread::sleep(interval);
+                    let Some(inner) = store.upgrade() else {
+                        return;
+                    };
+                    // A file caught mid-write fails to parse; the next poll sees it whole.
+                    let _ = ConfigStore { inner }.reload();
+                }
+            })
+            .ok();
+        ConfigWatcher { stopped }
+    }
+
+    fn get_value(&self, namespace: &str) -> Option<Value> {
+        self.lock().table.get(namespace).cloned()
+    }
+
+    fn set_value(&self, namespace: &str, value: Value) -> Result<(), ConfigError> {
+        let listeners = {
+            let mut inner = self.lock();
+            if inner.table.get(namespace) == Some(&value) {
+                return Ok(());
+            }
+            inner.table.insert(namespace.to_string(), value.clone());
+            if let Some(path) = inner.path.clone() {
+                write_table(&path, &inner.table)?;
+                inner.modified = file_modified(&path);
+            }
+            inner.listeners_of(namespace)
+        };
+
+        for listener in listeners {
+            listener(Some(&value));
+        }
+        Ok(())
+    }
+
+    fn add_listener(&self, namespace: &str, listener: Listener) -> Subscription {
+        let mut inner = self.lock();
+        let id = inner.next_listener_id;
+        inner.next_listener_id += 1;
+        inner.listeners.push(ListenerEntry {
+            id,
+            namespace: namespace.to_string(),
+            listener,
+        });
+        Subscription {
+            store: Arc::downgrade(&self.inner),
+            id,
+        }
+    }
+}
+
+/// One table of the settings file, read and written as `T`.
+pub struct Namespace<T> {
+    store: ConfigStore,
+    name: String,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Namespace<T> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            name: self.name.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned + Default> Namespace<T> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The current settings; a missing table gives `T::default()`.
+    pub fn try_get(&self) -> Result<T, ConfigError> {
+        match self.store.get_value(&self.name) {
+            Some(value) => value
+                .try_into()
+                .map_err(|err| ConfigError::Invalid(self.name.clone(), err)),
+            None => Ok(T::default()),
+        }
+    }
+
+    /// Like [`try_get`](Self::try_get), falling back to `T::default()` when the table does
+    /// not fit `T`.
+    pub fn get(&self) -> T {
+        self.try_get().unwrap_or_default()
+    }
+
+    /// Replaces the table and writes the file.
+    pub fn set(&self, settings: &T) -> Result<(), ConfigError> {
+        let value = Value::try_from(settings)
+            .map_err(|err| ConfigError::Serialize(self.name.clone(), err))?;
+        self.store.set_value(&self.name, value)
+    }
+
+    pub fn update(&self, update: impl FnOnce(&mut T)) -> Result<(), ConfigError> {
+        let mut settings = self.get();
+        update(&mut settings);
+        self.set(&settings)
+    }
+
+    /// Calls `listener` with the new settings whenever the table changes. Values that do not
+    /// fit `T` are reported as `T::default()`.
+    pub fn on_change(&self, listener: impl Fn(T) + Send + Sync + 'static) -> Subscription {
+        self.store.add_listener(
+            &self.name,
+            Arc::new(move |value: Option<&Value>| {
+                let settings = value
+                    .and_then(|value| value.clone().try_into().ok())
+                    .unwrap_or_default();
+                listener(settings);
+            }),
+        )
+    }
+}
+
+/// Unregisters a change listener when dropped.
+#[must_use = "the listener is removed when the subscription is dropped"]
+pub struct Subscription {
+    store: Weak<Mutex<Inner>>,
+    id: u64,
+}
+
+impl Subscription {
+    /// Keeps the listener for as long as the store lives.
+    pub fn detach(self) {
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(inner) = self.store.upgrade() {
+            let mut inner = inner.lock().unwrap_or_else(|err| err.into_inner());
+            inner.listeners.retain(|entry| entry.id != self.id);
+        }
+    }
+}
+
+/// Stops [`ConfigStore::watch`] polling when dropped.
+pub struct ConfigWatcher {
+    stopped: Arc<AtomicBool>,
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+fn file_modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+fn read_table(path: &Path) -> Result<(Table, Option<SystemTime>), ConfigError> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Ok((Table::new(), None));
+        }
+        Err(err) => return Err(ConfigError::Io(path.to_path_buf(), err)),
+    };
+    let table = text
+        .parse::<Table>()
+        .map_err(|err| ConfigError::Parse(path.to_path_buf(), err))?;
+    Ok((table, file_modified(path)))
+}
+
+/// Writes through a temporary file so a crash never leaves a truncated settings file.
+fn write_table(path: &Path, table: &Table) -> Result<(), ConfigError> {
+    let io_err = |err| ConfigError::Io(path.to_path_buf(), err);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(io_err)?;
+    }
+    let text =
+        toml::to_string_pretty(table).map_err(|err| ConfigError::Serialize(String::new(), err))?;
+    let temp = path.with_extension("toml.tmp");
+    std::fs::write(&temp, text).map_err(io_err)?;
+    std::fs::rename(&temp, path).map_err(io_err)
+}
@@ -0,0 +1,107 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use manos_config::ConfigStore;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct Settings {
+    theme: Option<String>,
+    font_size: u32,
+}
+
+fn temp_file(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("manos-config-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir.join("settings.toml")
+}
+
+#[test]
+fn namespaces_round_trip_through_the_file() {
+    let path = temp_file("round-trip");
+    let store = ConfigStore::open(&path).unwrap();
+    let settings = store.namespace::<Settings>("story");
+    assert_eq!(settings.get(), Settings::default());
+
+    settings
+        .update(|settings| settings.theme = Some("Ayu".to_string()))
+        .unwrap();
+    store
+        .namespace::<Settings>("git-viewer")
+        .set(&Settings {
+            theme: None,
+            font_size: 13,
+        })
+        .unwrap();
+
+    let reopened = ConfigStore::open(&path).unwrap();
+    assert_eq!(
+        reopened
+            .namespace::<Settings>("story")
+            .get()
+            .theme
+            .as_deref(),
+        Some("Ayu")
+    );
+    assert_eq!(
+        reopened.namespace::<Settings>("git-viewer").get().font_size,
+        13
+    );
+}
+
+#[test]
+fn listeners_see_their_namespace_only() {
+    let store = ConfigStore::in_memory();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let subscription = store.namespace::<Settings>("story").on_change({
+        let seen = seen.clone();
+        move |settings| seen.lock().unwrap().push(settings.font_size)
+    });
+
+    store
+        .namespace::<Settings>("story")
+        .update(|settings| settings.font_size = 14)
+        .unwrap();
+    store
+        .namespace::<Settings>("git-viewer")
+        .update(|settings| settings.font_size = 20)
+        .unwrap();
+    drop(subscription);
+    store
+        .namespace::<Settings>("story")
+        .update(|settings| settings.font_size = 16)
+        .unwrap();
+
+    assert_eq!(*seen.lock().unwrap(), [14]);
+}
+
+#[test]
+fn reload_notifies_about_edits_on_disk() {
+    let path = temp_file("reload");
+    let store = ConfigStore::open(&path).unwrap();
+    store
+        .namespace::<Settings>("story")
+        .update(|settings| settings.font_size = 12)
+        .unwrap();
+
+    let seen = Arc::new(Mutex::new(None));
+    store
+        .namespace::<Settings>("story")
+        .on_change({
+            let seen = seen.clone();
+            move |settings| *seen.lock().unwrap() = Some(settings)
+        })
+        .detach();
+
+    std::fs::write(&path, "[story]\ntheme = \"Nord\"\nfont_size = 15\n").unwrap();
+    // Make sure the modification time moves even on coarse file systems.
+    let file = std::fs::File::options().append(true).open(&path).unwrap();
+    file.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(5))
+        .unwrap();
+
+    assert!(store.reload().unwrap());
+    let seen = seen.lock().unwrap().clone().unwrap();
+    assert_eq!(seen.theme.as_deref(), Some("Nord"));
+    assert_eq!(seen.font_size, 15);
+}
@@ -21,6 +21,7 @@ fn main() {
         let options = DiffOptions {
             context_lines: 3,
             ignore_whitespace,
+            ignore_eol: false,
         };
         let model = diff_documents(&old_doc, &new_doc, options);
 
@@ -0,0 +1,224 @@
+//! 行级附加状态：测试覆盖率、lint 诊断等由外部工具给出的信息。
+//!
+//! 状态按文件的行号（从 0 开始，与 [`SideLine::line_index`] 一致）提供，
+//! [`DiffModel::annotate`] 把它挂到 hunk 中对应的行上；不在任何 hunk 里的行（折叠掉的
+//! 未变更区域）不会被记录。
+
+use std::collections::BTreeMap;
+
+use crate::model::{DiffModel, DiffSide, SideLine};
+
+/// 一行的测试覆盖情况。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Coverage {
+    Covered,
+    /// 行被执行过，但其中有分支没有走到。
+    Partial,
+    Uncovered,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Hint,
+    Info,
+    Warning,
+    Error,
+}
+
+/// lint、编译器等工具对一行给出的一条诊断。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineDiagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// 给出诊断的工具，例如 `clippy`。
+    pub source: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LineAnnotation {
+    pub coverage: Option<Coverage>,
+    pub diagnostics: Vec<LineDiagnostic>,
+}
+
+impl LineAnnotation {
+    pub fn coverage(coverage: Coverage) -> Self {
+        Self {
+            coverage: Some(coverage),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    pub fn diagnostic(
+        severity: Severity,
+        message: impl Into<String>,
+        source: Option<impl Into<String>>,
+    ) -> Self {
+        Self {
+            coverage: None,
+            diagnostics: vec![LineDiagnostic {
+                severity,
+                message: message.into(),
+                source: source.map(Into::into),
+            }],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.coverage.is_none() && self.diagnostics.is_empty()
+    }
+
+    /// 诊断中最严重的级别，用于决定 gutter 的颜色。
+    pub fn severity(&self) -> Option<Severity> {
+        self.diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.severity)
+            .max()
+    }
+
+    /// 合并另一份状态：覆盖率以后来的为准，诊断追加在后面。
+    pub fn merge(&mut self, other: LineAnnotation) {
+        if other.coverage.is_some() {
+            self.coverage = other.coverage;
+        }
+        self.diagnostics.extend(other.diagnostics);
+    }
+}
+
+impl DiffModel {
+    /// 把按行号给出的状态合并到 `side` 一侧对应的行上，可以多次调用叠加不同来源。
+    pub fn annotate(&mut self, side: DiffSide, annotations: &BTreeMap<usize, LineAnnotation>) {
+        if annotations.is_empty() {
+            return;
+        }
+        for line in self.side_lines_mut(side) {
+            if let Some(annotation) = annotations.get(&line.line_index) {
+                merge_into(line, annotation.clone());
+            }
+        }
+    }
+
+    /// 给 `side` 一侧的第 `line_index` 行合并一份状态；该行不在任何 hunk 中时返回 false。
+    pub fn annotate_line(
+        &mut self,
+        side: DiffSide,
+        line_index: usize,
+        annotation: LineAnnotation,
+    ) -> bool {
+        let Some(line) = self
+            .side_lines_mut(side)
+            .find(|line| line.line_index == line_index)
+        else {
+            return false;
+        };
+        merge_into(line, annotation);
+        true
+    }
+
+    /// 去掉 `side` 一侧所有行的状态。
+    pub fn clear_annotations(&mut self, side: DiffSide) {
+        for line in self.side_lines_mut(side) {
+            line.annotation = None;
+        }
+    }
+
+    /// `side` 一侧带状态的行，按行号升序。
+    pub fn annotations(&self, side: DiffSide) -> impl Iterator<Item = (usize, &LineAnnotation)> {
+        self.hunks
+            .iter()
+            .flat_map(|hunk| &hunk.rows)
+            .filter_map(move |row| {
+                let line = row.side(side)?;
+                Some((line.line_index, line.annotation.as_ref()?))
+            })
+    }
+
+    fn side_lines_mut(&mut self, side: DiffSide) -> impl Iterator<Item = &mut SideLine> {
+        self.hunks
+            .iter_mut()
+            .flat_map(|hunk| &mut hunk.rows)
+            .filter_map(move |row| row.side_mut(side))
+    }
+}
+
+fn merge_into(line: &mut SideLine, annotation: LineAnnotation) {
+    if annotation.is_empty() {
+        return;
+    }
+    line.annotation
+        .get_or_insert_with(LineAnnotation::default)
+        .merge(annotation);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DiffOptions, Document, diff_documents};
+
+    fn diff(old: &str, new: &str) -> DiffModel {
+        diff_documents(
+            &Document::from_str(old),
+            &Document::from_str(new),
+            DiffOptions::default(),
+        )
+    }
+
+    #[test]
+    fn annotations_attach_to_lines_of_one_side() {
+        let mut model = diff("a\nb\nc\n", "a\nx\nc\nd\n");
+        let annotations = BTreeMap::from([
+            (1, LineAnnotation::coverage(Coverage::Uncovered)),
+            (3, LineAnnotation::coverage(Coverage::Covered)),
+        ]);
+        model.annotate(DiffSide::New, &annotations);
+
+        let annotated: Vec<_> = model
+            .annotations(DiffSide::New)
+            .map(|(line, annotation)| (line, annotation.coverage))
+            .collect();
+        assert_eq!(
+            annotated,
+            vec![(1, Some(Coverage::Uncovered)), (3, Some(Coverage::Covered))]
+        );
+        assert_eq!(model.annotations(DiffSide::Old).count(), 0);
+
+        let row = model.hunks[0]
+            .rows
+            .iter()
+            .find(|row| row.new.as_ref().is_some_and(|line| line.text == "x"))
+            .unwrap();
+        assert!(row.annotation(DiffSide::Old).is_none());
+        assert_eq!(
+            row.annotation(DiffSide::New).and_then(|a| a.coverage),
+            Some(Coverage::Uncovered)
+        );
+    }
+
+    #[test]
+    fn annotations_from_several_sources_merge() {
+        let mut model = diff("a\n", "b\n");
+        assert!(model.annotate_line(
+            DiffSide::New,
+            0,
+            LineAnnotation::coverage(Coverage::Partial)
+        ));
+        assert!(model.annotate_line(
+            DiffSide::New,
+            0,
+            LineAnnotation::diagnostic(Severity::Warning, "unused variable", Some("clippy")),
+        ));
+        assert!(model.annotate_line(
+            DiffSide::New,
+            0,
+            LineAnnotation::diagnostic(Severity::Error, "mismatched types", None::<String>),
+        ));
+        assert!(!model.annotate_line(DiffSide::New, 5, LineAnnotation::default()));
+
+        let (_, annotation) = model.annotations(DiffSide::New).next().unwrap();
+        assert_eq!(annotation.coverage, Some(Coverage::Partial));
+        assert_eq!(annotation.diagnostics.len(), 2);
+        assert_eq!(annotation.severity(), Some(Severity::Error));
+
+        model.clear_annotations(DiffSide::New);
+        assert_eq!(model.annotations(DiffSide::New).count(), 0);
+    }
+}
@@ -1,43 +1,54 @@
+use std::borrow::Cow;
+
 use similar::{DiffOp, TextDiff};
 
 use crate::document::Document;
 use crate::model::{
-    DiffHunk, DiffModel, DiffOptions, DiffRow, DiffSegment, DiffSegmentKind, SideLine,
+    DiffHunk, DiffModel, DiffOptions, DiffRow, DiffSegment, DiffSegmentKind, DiffSide, SideLine,
 };
+#[cfg(feature = "instrumentation")]
+use crate::timing::DiffTimings;
+use crate::timing::{Phase, Recorder};
 
 pub fn diff_documents(old: &Document, new: &Document, options: DiffOptions) -> DiffModel {
+    diff_documents_with(old, new, options, &mut Recorder::new(Phase::LineSplit))
+}
+
+/// 同 [`diff_documents`]，并返回各阶段的耗时。
+#[cfg(feature = "instrumentation")]
+pub fn diff_documents_timed(
+    old: &Document,
+    new: &Document,
+    options: DiffOptions,
+) -> (DiffModel, DiffTimings) {
+    let mut recorder = Recorder::new(Phase::LineSplit);
+    let model = diff_documents_with(old, new, options, &mut recorder);
+    (model, recorder.finish())
+}
+
+fn diff_documents_with(
+    old: &Document,
+    new: &Document,
+    options: DiffOptions,
+    recorder: &mut Recorder,
+) -> DiffModel {
     let old_lines = old.lines();
     let new_lines = new.lines();
-
-    let old_key_storage = if options.ignore_whitespace {
-        old_lines
-            .iter()
-            .map(|line| normalize_line_for_diff(line))
-            .collect::<Vec<_>>()
+    let compare_eol = !options.ignore_whitespace && !options.ignore_eol;
+    let (old_crlf, new_crlf) = if compare_eol {
+        (old.crlf_lines(), new.crlf_lines())
     } else {
-        Vec::new()
-    };
-    let new_key_storage = if options.ignore_whitespace {
-        new_lines
-            .iter()
-            .map(|line| normalize_line_for_diff(line))
-            .collect::<Vec<_>>()
-    } else {
-        Vec::new()
+        (Vec::new(), Vec::new())
     };
 
-    let old_key_refs: Vec<&str> = if options.ignore_whitespace {
-        old_key_storage.iter().map(|line| line.as_str()).collect()
-    } else {
-        old_lines.iter().map(|line| line.as_str()).collect()
-    };
-    let new_key_refs: Vec<&str> = if options.ignore_whitespace {
-        new_key_storage.iter().map(|line| line.as_str()).collect()
-    } else {
-        new_lines.iter().map(|line| line.as_str()).collect()
-    };
+    let old_keys = line_keys(&old_lines, &old_crlf, options.ignore_whitespace);
+    let new_keys = line_keys(&new_lines, &new_crlf, options.ignore_whitespace);
+    let old_key_refs: Vec<&str> = old_keys.iter().map(|key| key.as_ref()).collect();
+    let new_key_refs: Vec<&str> = new_keys.iter().map(|key| key.as_ref()).collect();
 
+    recorder.enter(Phase::Myers);
     let diff = TextDiff::from_slices(&old_key_refs, &new_key_refs);
+    recorder.enter(Phase::HunkBuilding);
     let mut hunks = Vec::new();
 
     for group in diff.grouped_ops(options.context_lines) {
@@ -47,7 +58,9 @@ pub fn diff_documents(old: &Document, new: &Document, options: DiffOptions) -> D
                 &op,
                 &old_lines,
                 &new_lines,
-                options.ignore_whitespace,
+                &old_key_refs,
+                &new_key_refs,
+                recorder,
             ));
         }
 
@@ -55,8 +68,19 @@ pub fn diff_documents(old: &Document, new: &Document, options: DiffOptions) -> D
             continue;
         }
 
-        let (old_start, old_len) = compute_side_range(&rows, Side::Old);
-        let (new_start, new_len) = compute_side_range(&rows, Side::New);
+        if compare_eol {
+            for row in &mut rows {
+                if let Some(line) = row.old.as_mut() {
+                    line.crlf = old_crlf.get(line.line_index).copied().unwrap_or(false);
+                }
+                if let Some(line) = row.new.as_mut() {
+                    line.crlf = new_crlf.get(line.line_index).copied().unwrap_or(false);
+                }
+            }
+        }
+
+        let (old_start, old_len) = compute_side_range(&rows, DiffSide::Old);
+        let (new_start, new_len) = compute_side_range(&rows, DiffSide::New);
 
         hunks.push(DiffHunk {
             old_start,
@@ -70,6 +94,24 @@ pub fn diff_documents(old: &Document, new: &Document, options: DiffOptions) -> D
     DiffModel { hunks }
 }
 
+/// 参与行级 diff 的比较键：忽略空白时去掉所有空白；比较换行符时给 CRLF 行补上 `\r`，
+/// 使仅换行符不同的行不再被视为相同。
+fn line_keys<'a>(lines: &'a [String], crlf: &[bool], ignore_whitespace: bool) -> Vec<Cow<'a, str>> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            if ignore_whitespace {
+                Cow::Owned(normalize_line_for_diff(line))
+            } else if crlf.get(index).copied().unwrap_or(false) {
+                Cow::Owned(format!("{line}\r"))
+            } else {
+                Cow::Borrowed(line.as_str())
+            }
+        })
+        .collect()
+}
+
 fn normalize_line_for_diff(line: &str) -> String {
     let mut out = String::with_capacity(line.len());
     for ch in line.chars() {
@@ -85,7 +127,9 @@ fn rows_for_op(
     op: &DiffOp,
     old_lines: &[String],
     new_lines: &[String],
-    ignore_whitespace: bool,
+    old_keys: &[&str],
+    new_keys: &[&str],
+    recorder: &mut Recorder,
 ) -> Vec<DiffRow> {
     match op.tag() {
         similar::DiffTag::Equal => op
@@ -120,7 +164,9 @@ fn rows_for_op(
                 })
             })
             .collect(),
-        similar::DiffTag::Replace => rows_for_replace(op, old_lines, new_lines, ignore_whitespace),
+        similar::DiffTag::Replace => {
+            rows_for_replace(op, old_lines, new_lines, old_keys, new_keys, recorder)
+        }
     }
 }
 
@@ -128,7 +174,9 @@ fn rows_for_replace(
     op: &DiffOp,
     old_lines: &[String],
     new_lines: &[String],
-    ignore_whitespace: bool,
+    old_keys: &[&str],
+    new_keys: &[&str],
+    recorder: &mut Recorder,
 ) -> Vec<DiffRow> {
     let old_range = op.old_range();
     let new_range = op.new_range();
@@ -143,38 +191,13 @@ fn rows_for_replace(
             new_range.len(),
             old_lines,
             new_lines,
+            recorder,
         );
     }
 
-    let old_key_storage = if ignore_whitespace {
-        old_slice
-            .iter()
-            .map(|line| normalize_line_for_diff(line))
-            .collect::<Vec<_>>()
-    } else {
-        Vec::new()
-    };
-    let new_key_storage = if ignore_whitespace {
-        new_slice
-            .iter()
-            .map(|line| normalize_line_for_diff(line))
-            .collect::<Vec<_>>()
-    } else {
-        Vec::new()
-    };
-
-    let old_key_refs: Vec<&str> = if ignore_whitespace {
-        old_key_storage.iter().map(|line| line.as_str()).collect()
-    } else {
-        old_slice.iter().map(|line| line.as_str()).collect()
-    };
-    let new_key_refs: Vec<&str> = if ignore_whitespace {
-        new_key_storage.iter().map(|line| line.as_str()).collect()
-    } else {
-        new_slice.iter().map(|line| line.as_str()).collect()
-    };
-
-    let diff = TextDiff::from_slices(&old_key_refs, &new_key_refs);
+    let previous = recorder.enter(Phase::Myers);
+    let diff = TextDiff::from_slices(&old_keys[old_range.clone()], &new_keys[new_range.clone()]);
+    recorder.enter(previous);
     let ops = diff.ops();
 
     if ops.len() == 1 {
@@ -192,6 +215,7 @@ fn rows_for_replace(
                 new_range.len(),
                 old_lines,
                 new_lines,
+                recorder,
             );
         }
     }
@@ -204,6 +228,7 @@ fn rows_for_replace(
             new_range.start,
             old_lines,
             new_lines,
+            recorder,
         ));
     }
 
@@ -216,6 +241,7 @@ fn rows_for_op_with_offsets(
     new_offset: usize,
     old_lines: &[String],
     new_lines: &[String],
+    recorder: &mut Recorder,
 ) -> Vec<DiffRow> {
     match op.tag() {
         similar::DiffTag::Equal => op
@@ -264,6 +290,7 @@ fn rows_for_op_with_offsets(
                 new_range.len(),
                 old_lines,
                 new_lines,
+                recorder,
             )
         }
     }
@@ -276,6 +303,7 @@ fn rows_for_replace_by_index(
     new_len: usize,
     old_lines: &[String],
     new_lines: &[String],
+    recorder: &mut Recorder,
 ) -> Vec<DiffRow> {
     let row_len = old_len.max(new_len);
     let mut rows = Vec::with_capacity(row_len);
@@ -293,17 +321,23 @@ fn rows_for_replace_by_index(
 
         let (old, new) = match (old_text, new_text) {
             (Some(old_text), Some(new_text)) => {
+                let previous = recorder.enter(Phase::Intraline);
                 let (old_segments, new_segments) = intraline_segments(&old_text, &new_text);
+                recorder.enter(previous);
                 (
                     Some(SideLine {
                         line_index: old_index,
                         text: old_text,
                         segments: old_segments,
+                        crlf: false,
+                        annotation: None,
                     }),
                     Some(SideLine {
                         line_index: new_index,
                         text: new_text,
                         segments: new_segments,
+                        crlf: false,
+                        annotation: None,
                     }),
                 )
             }
@@ -383,25 +417,17 @@ fn side_line(line_index: usize, text: String, kind: DiffSegmentKind) -> SideLine
         line_index,
         text: text.clone(),
         segments: vec![DiffSegment { kind, text }],
+        crlf: false,
+        annotation: None,
     }
 }
 
-#[derive(Clone, Copy, Debug)]
-enum Side {
-    Old,
-    New,
-}
-
-fn compute_side_range(rows: &[DiffRow], side: Side) -> (usize, usize) {
+fn compute_side_range(rows: &[DiffRow], side: DiffSide) -> (usize, usize) {
     let mut min: Option<usize> = None;
     let mut max: Option<usize> = None;
 
     for row in rows {
-        let line = match side {
-            Side::Old => row.old.as_ref(),
-            Side::New => row.new.as_ref(),
-        };
-        let Some(line) = line else { continue };
+        let Some(line) = row.side(side) else { continue };
 
         min = Some(min.map_or(line.line_index, |m| m.min(line.line_index)));
         max = Some(max.map_or(line.line_index, |m| m.max(line.line_index)));
@@ -412,3 +438,74 @@ fn compute_side_range(rows: &[DiffRow], side: Side) -> (usize, usize) {
 
     (min, max - min + 1)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::DiffRowKind;
+
+    fn diff(old: &str, new: &str, options: DiffOptions) -> DiffModel {
+        diff_documents(&Document::from_str(old), &Document::from_str(new), options)
+    }
+
+    #[test]
+    fn eol_only_change_is_reported() {
+        let model = diff("a\r\nb\r\nc\r\n", "a\r\nb\nc\r\n", DiffOptions::default());
+        let rows: Vec<&DiffRow> = model
+            .hunks
+            .iter()
+            .flat_map(|hunk| &hunk.rows)
+            .filter(|row| row.kind() != DiffRowKind::Unchanged)
+            .collect();
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].kind(), DiffRowKind::Modified);
+        assert!(rows[0].eol_changed());
+        assert_eq!(rows[0].old.as_ref().unwrap().text, "b");
+        assert!(rows[0].old.as_ref().unwrap().crlf);
+        assert!(!rows[0].new.as_ref().unwrap().crlf);
+    }
+
+    #[test]
+    fn ignore_eol_hides_eol_only_changes() {
+        let options = DiffOptions {
+            ignore_eol: true,
+            ..DiffOptions::default()
+        };
+        assert!(diff("a\r\nb\r\n", "a\nb\n", options).hunks.is_empty());
+
+        let options = DiffOptions {
+            ignore_whitespace: true,
+            ..DiffOptions::default()
+        };
+        assert!(diff("a\r\nb\r\n", "a\nb\n", options).hunks.is_empty());
+    }
+
+    #[test]
+    fn ignore_eol_keeps_content_changes() {
+        let options = DiffOptions {
+            ignore_eol: true,
+            ..DiffOptions::default()
+        };
+        let model = diff("a\r\nb\r\n", "a\nx\n", options);
+        let row = model.hunks[0]
+            .rows
+            .iter()
+            .find(|row| row.kind() != DiffRowKind::Unchanged)
+            .unwrap();
+
+        assert!(!row.eol_changed());
+        assert!(!row.old.as_ref().unwrap().crlf);
+    }
+
+    #[cfg(feature = "instrumentation")]
+    #[test]
+    fn timed_diff_matches_untimed_diff() {
+        let old = Document::from_str("fn a() {\n    1\n}\nfn b() {}\n");
+        let new = Document::from_str("fn a() {\n    2\n}\nfn c() {}\n");
+        let (model, timings) = diff_documents_timed(&old, &new, DiffOptions::default());
+
+        assert_eq!(model, diff_documents(&old, &new, DiffOptions::default()));
+        assert!(timings.total() > std::time::Duration::ZERO);
+    }
+}
@@ -52,6 +52,17 @@ impl Document {
             .collect()
     }
 
+    /// 每一行是否以 `\r\n` 结尾，与 [`Document::lines`] 一一对应。
+    pub fn crlf_lines(&self) -> Vec<bool> {
+        let count = self.line_count();
+        (0..count)
+            .map(|index| {
+                let slice = self.rope.line(index, LineType::LF);
+                slice.as_str().unwrap_or_default().ends_with("\r\n")
+            })
+            .collect()
+    }
+
     pub fn to_string(&self) -> String {
         self.rope.to_string()
     }
@@ -1,11 +1,23 @@
+pub mod annotation;
 pub mod conflict;
 pub mod diff;
 pub mod document;
+pub mod merge;
 pub mod model;
+pub mod patch;
+pub mod timing;
 
+pub use annotation::{Coverage, LineAnnotation, LineDiagnostic, Severity};
 pub use conflict::{ConflictRegion, parse_conflicts};
 pub use diff::diff_documents;
+#[cfg(feature = "instrumentation")]
+pub use diff::diff_documents_timed;
 pub use document::Document;
+pub use merge::{AutoResolveReport, AutoResolveStrategy, MergeChunk, MergeModel, merge3};
 pub use model::{
-    DiffHunk, DiffModel, DiffOptions, DiffRow, DiffRowKind, DiffSegment, DiffSegmentKind, SideLine,
+    DiffHunk, DiffModel, DiffOptions, DiffRow, DiffRowKind, DiffSegment, DiffSegmentKind, DiffSide,
+    SideLine,
 };
+pub use patch::{FilePatch, PatchApplyResult, PatchHunk, PatchLine, parse_patch};
+#[cfg(feature = "instrumentation")]
+pub use timing::DiffTimings;
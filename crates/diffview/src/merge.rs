@@ -0,0 +1,484 @@
+use std::fmt;
+use std::str::FromStr;
+
+use similar::{Algorithm, DiffOp, capture_diff_slices};
+
+use crate::conflict::ConflictRegion;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MergeChunk {
+    Unchanged(String),
+    Ours(String),
+    Theirs(String),
+    Both(String),
+    Conflict {
+        base: String,
+        ours: String,
+        theirs: String,
+    },
+    /// 由 [`MergeModel::auto_resolve`] 自动解决的冲突。
+    Resolved {
+        content: String,
+        strategy: AutoResolveStrategy,
+    },
+    /// 在冲突视图里手动选定的结果，见 [`MergeModel::resolve`]。
+    Manual(String),
+}
+
+/// [`MergeModel::auto_resolve`] 不经询问解决冲突的方式。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AutoResolveStrategy {
+    /// 保留我方。
+    PreferOurs,
+    /// 保留对方。
+    PreferTheirs,
+    /// 两侧都保留，我方在前，同 git 的 `merge=union`。
+    Union,
+    /// 只解决仅有空白差异的冲突：两侧只差空白时保留我方；一侧相对 base 只改了空白时
+    /// 采用另一侧的改动。
+    WhitespaceOnly,
+}
+
+impl AutoResolveStrategy {
+    pub const ALL: [AutoResolveStrategy; 4] = [
+        AutoResolveStrategy::PreferOurs,
+        AutoResolveStrategy::PreferTheirs,
+        AutoResolveStrategy::Union,
+        AutoResolveStrategy::WhitespaceOnly,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AutoResolveStrategy::PreferOurs => "prefer-ours",
+            AutoResolveStrategy::PreferTheirs => "prefer-theirs",
+            AutoResolveStrategy::Union => "union",
+            AutoResolveStrategy::WhitespaceOnly => "whitespace-only",
+        }
+    }
+
+    /// 按此策略解决冲突后的内容；该策略不处理这个冲突时返回 `None`。
+    pub fn resolve(self, base: &str, ours: &str, theirs: &str) -> Option<String> {
+        match self {
+            AutoResolveStrategy::PreferOurs => Some(ours.to_string()),
+            AutoResolveStrategy::PreferTheirs => Some(theirs.to_string()),
+            AutoResolveStrategy::Union => {
+                let mut content = ours.to_string();
+                if !content.is_empty() && !content.ends_with('\n') && !theirs.is_empty() {
+                    content.push('\n');
+                }
+                content.push_str(theirs);
+                Some(content)
+            }
+            AutoResolveStrategy::WhitespaceOnly => {
+                if same_ignoring_whitespace(ours, theirs) {
+                    Some(ours.to_string())
+                } else if same_ignoring_whitespace(ours, base) {
+                    Some(theirs.to_string())
+                } else if same_ignoring_whitespace(theirs, base) {
+                    Some(ours.to_string())
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for AutoResolveStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for AutoResolveStrategy {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|strategy| strategy.name() == name)
+            .ok_or_else(|| format!("未知的自动解决策略 `{name}`"))
+    }
+}
+
+/// [`MergeModel::auto_resolve`] 的结果，下标指向 [`MergeModel::chunks`]。
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AutoResolveReport {
+    pub resolved: Vec<(usize, AutoResolveStrategy)>,
+    /// 仍需手动解决的冲突。
+    pub unresolved: Vec<usize>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MergeModel {
+    pub chunks: Vec<MergeChunk>,
+}
+
+impl MergeModel {
+    pub fn conflict_count(&self) -> usize {
+        self.chunks
+            .iter()
+            .filter(|chunk| matches!(chunk, MergeChunk::Conflict { .. }))
+            .count()
+    }
+
+    pub fn auto_merged_count(&self) -> usize {
+        self.chunks
+            .iter()
+            .filter(|chunk| {
+                matches!(
+                    chunk,
+                    MergeChunk::Ours(_) | MergeChunk::Theirs(_) | MergeChunk::Both(_)
+                )
+            })
+            .count()
+    }
+
+    pub fn auto_resolved_count(&self) -> usize {
+        self.chunks
+            .iter()
+            .filter(|chunk| matches!(chunk, MergeChunk::Resolved { .. }))
+            .count()
+    }
+
+    /// 对每个冲突依次尝试 `strategies`，用第一个适用的策略解决；没有策略适用的冲突
+    /// 记录在返回值的 `unresolved` 中。
+    pub fn auto_resolve(&mut self, strategies: &[AutoResolveStrategy]) -> AutoResolveReport {
+        let mut report = AutoResolveReport::default();
+        for (index, chunk) in self.chunks.iter_mut().enumerate() {
+            let MergeChunk::Conflict { base, ours, theirs } = chunk else {
+                continue;
+            };
+            let resolution = strategies
+                .iter()
+                .find_map(|strategy| Some((strategy.resolve(base, ours, theirs)?, *strategy)));
+            match resolution {
+                Some((content, strategy)) => {
+                    *chunk = MergeChunk::Resolved { content, strategy };
+                    report.resolved.push((index, strategy));
+                }
+                None => report.unresolved.push(index),
+            }
+        }
+        report
+    }
+
+    /// 用 `content` 解决第 `index` 个块；该块不是未解决的冲突时什么也不做，返回 `false`。
+    pub fn resolve(&mut self, index: usize, content: String) -> bool {
+        match self.chunks.get_mut(index) {
+            Some(chunk @ MergeChunk::Conflict { .. }) => {
+                *chunk = MergeChunk::Manual(content);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn has_conflicts(&self) -> bool {
+        self.chunks
+            .iter()
+            .any(|chunk| matches!(chunk, MergeChunk::Conflict { .. }))
+    }
+
+    /// 输出合并结果，未解决的块用 diff3 风格的冲突标记包起来。
+    ///
+    /// 返回的区域是结果文本中的字节范围，与 `parse_conflicts` 解析这段文本的结果一致。
+    pub fn to_conflict_text(
+        &self,
+        ours_branch_name: &str,
+        theirs_branch_name: &str,
+    ) -> (String, Vec<ConflictRegion>) {
+        let mut text = String::new();
+        let mut conflicts = Vec::new();
+
+        for chunk in &self.chunks {
+            match chunk {
+                MergeChunk::Unchanged(content)
+                | MergeChunk::Ours(content)
+                | MergeChunk::Theirs(content)
+                | MergeChunk::Both(content)
+                | MergeChunk::Resolved { content, .. }
+                | MergeChunk::Manual(content) => text.push_str(content),
+                MergeChunk::Conflict { base, ours, theirs } => {
+                    let start = text.len();
+
+                    push_marker_line(&mut text, "<<<<<<<", ours_branch_name);
+                    let ours_range = push_section(&mut text, ours);
+                    push_marker_line(&mut text, "|||||||", "base");
+                    let base_range = push_section(&mut text, base);
+                    text.push_str("=======\n");
+                    let theirs_range = push_section(&mut text, theirs);
+                    push_marker_line(&mut text, ">>>>>>>", theirs_branch_name);
+
+                    conflicts.push(ConflictRegion {
+                        ours_branch_name: ours_branch_name.to_string(),
+                        theirs_branch_name: theirs_branch_name.to_string(),
+                        range: start..text.len(),
+                        ours: ours_range,
+                        theirs: theirs_range,
+                        base: Some(base_range),
+                    });
+                }
+            }
+        }
+
+        (text, conflicts)
+    }
+}
+
+/// 以共同祖先 `base` 为基准，对 `ours` 和 `theirs` 做按行的三方合并。
+///
+/// 只有一侧改动（或两侧改得完全相同）的块自动合并；两侧改动重叠的块成为
+/// `MergeChunk::Conflict`。
+pub fn merge3(base: &str, ours: &str, theirs: &str) -> MergeModel {
+    let base_lines = split_lines(base);
+    let ours_lines = split_lines(ours);
+    let theirs_lines = split_lines(theirs);
+
+    let ours_matches = match_lines(&base_lines, &ours_lines);
+    let theirs_matches = match_lines(&base_lines, &theirs_lines);
+
+    let mut chunks = Vec::new();
+    let (mut base_ix, mut ours_ix, mut theirs_ix) = (0usize, 0usize, 0usize);
+
+    while base_ix < base_lines.len() || ours_ix < ours_lines.len() || theirs_ix < theirs_lines.len()
+    {
+        let mut stable_len = 0usize;
+        while base_ix + stable_len < base_lines.len()
+            && ours_matches[base_ix + stable_len] == Some(ours_ix + stable_len)
+            && theirs_matches[base_ix + stable_len] == Some(theirs_ix + stable_len)
+        {
+            stable_len += 1;
+        }
+
+        if stable_len > 0 {
+            push_chunk(
+                &mut chunks,
+                MergeChunk::Unchanged(base_lines[base_ix..base_ix + stable_len].concat()),
+            );
+            base_ix += stable_len;
+            ours_ix += stable_len;
+            theirs_ix += stable_len;
+            continue;
+        }
+
+        let (next_base, next_ours, next_theirs) = (base_ix..base_lines.len())
+            .find_map(|index| {
+                let ours = ours_matches[index]?;
+                let theirs = theirs_matches[index]?;
+                Some((index, ours, theirs))
+            })
+            .unwrap_or((base_lines.len(), ours_lines.len(), theirs_lines.len()));
+
+        let base_chunk = &base_lines[base_ix..next_base];
+        let ours_chunk = &ours_lines[ours_ix..next_ours];
+        let theirs_chunk = &theirs_lines[theirs_ix..next_theirs];
+
+        let chunk = if ours_chunk == base_chunk {
+            MergeChunk::Theirs(theirs_chunk.concat())
+        } else if theirs_chunk == base_chunk {
+            MergeChunk::Ours(ours_chunk.concat())
+        } else if ours_chunk == theirs_chunk {
+            MergeChunk::Both(ours_chunk.concat())
+        } else {
+            MergeChunk::Conflict {
+                base: base_chunk.concat(),
+                ours: ours_chunk.concat(),
+                theirs: theirs_chunk.concat(),
+            }
+        };
+        push_chunk(&mut chunks, chunk);
+
+        base_ix = next_base;
+        ours_ix = next_ours;
+        theirs_ix = next_theirs;
+    }
+
+    MergeModel { chunks }
+}
+
+fn split_lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+fn match_lines(base: &[&str], side: &[&str]) -> Vec<Option<usize>> {
+    let mut matches = vec![None; base.len()];
+    for op in capture_diff_slices(Algorithm::Myers, base, side) {
+        if let DiffOp::Equal {
+            old_index,
+            new_index,
+            len,
+        } = op
+        {
+            for offset in 0..len {
+                matches[old_index + offset] = Some(new_index + offset);
+            }
+        }
+    }
+    matches
+}
+
+fn push_chunk(chunks: &mut Vec<MergeChunk>, chunk: MergeChunk) {
+    let is_empty = match &chunk {
+        MergeChunk::Unchanged(content)
+        | MergeChunk::Ours(content)
+        | MergeChunk::Theirs(content)
+        | MergeChunk::Both(content) => content.is_empty(),
+        MergeChunk::Conflict { .. } | MergeChunk::Resolved { .. } | MergeChunk::Manual(_) => false,
+    };
+    if !is_empty {
+        chunks.push(chunk);
+    }
+}
+
+fn same_ignoring_whitespace(a: &str, b: &str) -> bool {
+    a.split_whitespace().eq(b.split_whitespace())
+}
+
+fn push_marker_line(text: &mut String, marker: &str, label: &str) {
+    text.push_str(marker);
+    text.push(' ');
+    text.push_str(label);
+    text.push('\n');
+}
+
+fn push_section(text: &mut String, content: &str) -> std::ops::Range<usize> {
+    let start = text.len();
+    text.push_str(content);
+    if !content.is_empty() && !content.ends_with('\n') {
+        text.push('\n');
+    }
+    start..text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conflict::parse_conflicts;
+
+    #[test]
+    fn merges_non_overlapping_changes() {
+        let base = "a\nb\nc\nd\ne\n";
+        let ours = "a\nB\nc\nd\ne\n";
+        let theirs = "a\nb\nc\nd\nE\n";
+
+        let model = merge3(base, ours, theirs);
+        assert!(!model.has_conflicts());
+        assert_eq!(model.auto_merged_count(), 2);
+
+        let (text, conflicts) = model.to_conflict_text("ours", "theirs");
+        assert_eq!(text, "a\nB\nc\nd\nE\n");
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn identical_changes_merge_cleanly() {
+        let model = merge3("a\nb\nc\n", "a\nx\nc\n", "a\nx\nc\n");
+        assert_eq!(model.chunks[1], MergeChunk::Both("x\n".to_string()));
+        assert!(!model.has_conflicts());
+    }
+
+    #[test]
+    fn overlapping_changes_conflict() {
+        let base = "a\nb\nc\n";
+        let model = merge3(base, "a\nours\nc\n", "a\ntheirs\nc\n");
+        assert_eq!(model.conflict_count(), 1);
+        assert_eq!(
+            model.chunks[1],
+            MergeChunk::Conflict {
+                base: "b\n".to_string(),
+                ours: "ours\n".to_string(),
+                theirs: "theirs\n".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn conflict_text_round_trips_through_parser() {
+        let model = merge3("a\nb\nc\n", "a\nours\nc\n", "a\ntheirs\nc\n");
+        let (text, conflicts) = model.to_conflict_text("HEAD", "feature");
+
+        assert_eq!(conflicts, parse_conflicts(&text));
+        let conflict = &conflicts[0];
+        assert_eq!(&text[conflict.ours.clone()], "ours\n");
+        assert_eq!(&text[conflict.theirs.clone()], "theirs\n");
+        assert_eq!(&text[conflict.base.clone().unwrap()], "b\n");
+    }
+
+    #[test]
+    fn conflict_text_keeps_markers_on_their_own_lines() {
+        let model = merge3("a\nb", "a\nours", "a\ntheirs");
+        let (text, conflicts) = model.to_conflict_text("HEAD", "feature");
+
+        assert_eq!(conflicts, parse_conflicts(&text));
+        assert_eq!(&text[conflicts[0].ours.clone()], "ours\n");
+        assert!(text.ends_with(">>>>>>> feature\n"));
+    }
+
+    #[test]
+    fn whitespace_only_resolves_reformatting_and_reports_the_rest() {
+        let base = "a\nb\nc\nd\ne\n";
+        let ours = "a\n  b\nc\nours\ne\n";
+        let theirs = "a\nB\nc\ntheirs\ne\n";
+        let mut model = merge3(base, ours, theirs);
+        assert_eq!(model.conflict_count(), 2);
+
+        let report = model.auto_resolve(&[AutoResolveStrategy::WhitespaceOnly]);
+        assert_eq!(
+            report.resolved,
+            vec![(1, AutoResolveStrategy::WhitespaceOnly)]
+        );
+        assert_eq!(report.unresolved, vec![3]);
+        assert_eq!(model.auto_resolved_count(), 1);
+
+        let (text, conflicts) = model.to_conflict_text("ours", "theirs");
+        assert!(text.starts_with("a\nB\nc\n<<<<<<< ours\n"));
+        assert_eq!(conflicts.len(), 1);
+    }
+
+    #[test]
+    fn strategies_apply_in_order() {
+        let mut model = merge3("a\nb\n", "a\nours\n", "a\ntheirs\n");
+        let report = model.auto_resolve(&[
+            AutoResolveStrategy::WhitespaceOnly,
+            AutoResolveStrategy::Union,
+            AutoResolveStrategy::PreferOurs,
+        ]);
+        assert_eq!(report.resolved, vec![(1, AutoResolveStrategy::Union)]);
+        assert!(report.unresolved.is_empty());
+        assert!(!model.has_conflicts());
+        assert_eq!(
+            model.to_conflict_text("ours", "theirs").0,
+            "a\nours\ntheirs\n"
+        );
+
+        let mut model = merge3("a\nb\n", "a\nours\n", "a\ntheirs\n");
+        model.auto_resolve(&[AutoResolveStrategy::PreferTheirs]);
+        assert_eq!(model.to_conflict_text("ours", "theirs").0, "a\ntheirs\n");
+    }
+
+    #[test]
+    fn strategy_names_round_trip() {
+        for strategy in AutoResolveStrategy::ALL {
+            assert_eq!(strategy.name().parse(), Ok(strategy));
+        }
+        assert!("ours".parse::<AutoResolveStrategy>().is_err());
+    }
+
+    #[test]
+    fn resolve_settles_only_conflicts() {
+        let mut model = merge3("a\nb\nc\n", "a\nours\nc\n", "a\ntheirs\nc\n");
+        assert!(!model.resolve(0, "x\n".to_string()));
+        assert!(model.resolve(1, "both\n".to_string()));
+        assert!(!model.resolve(1, "again\n".to_string()));
+
+        assert!(!model.has_conflicts());
+        assert_eq!(model.to_conflict_text("ours", "theirs").0, "a\nboth\nc\n");
+    }
+
+    #[test]
+    fn handles_missing_base() {
+        let model = merge3("", "added by us\n", "added by them\n");
+        assert_eq!(model.conflict_count(), 1);
+    }
+}
@@ -1,7 +1,11 @@
+use crate::annotation::LineAnnotation;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct DiffOptions {
     pub context_lines: usize,
     pub ignore_whitespace: bool,
+    /// 忽略仅换行符（CRLF/LF）不同的行。`ignore_whitespace` 打开时换行符也一并忽略。
+    pub ignore_eol: bool,
 }
 
 impl Default for DiffOptions {
@@ -9,6 +13,7 @@ impl Default for DiffOptions {
         Self {
             context_lines: 3,
             ignore_whitespace: false,
+            ignore_eol: false,
         }
     }
 }
@@ -27,6 +32,13 @@ pub struct DiffHunk {
     pub rows: Vec<DiffRow>,
 }
 
+/// diff 的一侧：旧文件或新文件。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DiffSide {
+    Old,
+    New,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct DiffRow {
     pub old: Option<SideLine>,
@@ -34,6 +46,25 @@ pub struct DiffRow {
 }
 
 impl DiffRow {
+    pub fn side(&self, side: DiffSide) -> Option<&SideLine> {
+        match side {
+            DiffSide::Old => self.old.as_ref(),
+            DiffSide::New => self.new.as_ref(),
+        }
+    }
+
+    pub fn side_mut(&mut self, side: DiffSide) -> Option<&mut SideLine> {
+        match side {
+            DiffSide::Old => self.old.as_mut(),
+            DiffSide::New => self.new.as_mut(),
+        }
+    }
+
+    /// 该行 `side` 一侧附加的状态，见 [`DiffModel::annotate`]。
+    pub fn annotation(&self, side: DiffSide) -> Option<&LineAnnotation> {
+        self.side(side)?.annotation.as_ref()
+    }
+
     pub fn kind(&self) -> DiffRowKind {
         match (&self.old, &self.new) {
             (Some(old), Some(new)) => {
@@ -41,6 +72,7 @@ impl DiffRow {
                     && new.segments.len() == 1
                     && old.segments[0].kind == DiffSegmentKind::Unchanged
                     && new.segments[0].kind == DiffSegmentKind::Unchanged
+                    && old.crlf == new.crlf
                 {
                     DiffRowKind::Unchanged
                 } else {
@@ -52,6 +84,14 @@ impl DiffRow {
             (None, None) => DiffRowKind::Unchanged,
         }
     }
+
+    /// 两侧文本相同、只有行尾 CRLF/LF 不同。
+    pub fn eol_changed(&self) -> bool {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => old.crlf != new.crlf && old.text == new.text,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -67,6 +107,10 @@ pub struct SideLine {
     pub line_index: usize,
     pub text: String,
     pub segments: Vec<DiffSegment>,
+    /// 该行以 CRLF 结尾。只在比较换行符时填写，忽略换行符时恒为 false。
+    pub crlf: bool,
+    /// 覆盖率、lint 等附加状态，由 [`DiffModel::annotate`] 填写，diff 本身不产生。
+    pub annotation: Option<LineAnnotation>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
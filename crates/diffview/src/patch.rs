@@ -0,0 +1,393 @@
+/// unified diff 中 hunk 正文的一行。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatchLine {
+    Context(String),
+    Removed(String),
+    Added(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatchHunk {
+    /// 旧版本一侧的起始行号（从 1 开始），即 `@@` 头里写的值。
+    pub old_start: usize,
+    pub old_len: usize,
+    /// 新版本一侧的起始行号（从 1 开始），即 `@@` 头里写的值。
+    pub new_start: usize,
+    pub new_len: usize,
+    /// 第二个 `@@` 之后的文本，通常是所在的函数。
+    pub section: String,
+    pub lines: Vec<PatchLine>,
+}
+
+impl PatchHunk {
+    pub fn header(&self) -> String {
+        let header = format!(
+            "@@ -{},{} +{},{} @@",
+            self.old_start, self.old_len, self.new_start, self.new_len
+        );
+        if self.section.is_empty() {
+            header
+        } else {
+            format!("{header} {}", self.section)
+        }
+    }
+
+    pub fn added_count(&self) -> usize {
+        self.lines
+            .iter()
+            .filter(|line| matches!(line, PatchLine::Added(_)))
+            .count()
+    }
+
+    pub fn removed_count(&self) -> usize {
+        self.lines
+            .iter()
+            .filter(|line| matches!(line, PatchLine::Removed(_)))
+            .count()
+    }
+
+    fn old_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                PatchLine::Context(text) | PatchLine::Removed(text) => Some(text.as_str()),
+                PatchLine::Added(_) => None,
+            })
+            .collect()
+    }
+
+    fn new_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                PatchLine::Context(text) | PatchLine::Added(text) => Some(text.as_str()),
+                PatchLine::Removed(_) => None,
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FilePatch {
+    /// patch 新建文件（`--- /dev/null`）时为 `None`。
+    pub old_path: Option<String>,
+    /// patch 删除文件（`+++ /dev/null`）时为 `None`。
+    pub new_path: Option<String>,
+    pub hunks: Vec<PatchHunk>,
+    /// 二进制改动（`GIT binary patch` 或 `Binary files ... differ`）没有文本 hunk。
+    pub binary: bool,
+}
+
+/// 在内存中把 [`FilePatch`] 应用到文本上的结果。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatchApplyResult {
+    pub text: String,
+    /// 找不到旧版本内容的 hunk，为 [`FilePatch::hunks`] 中的下标。
+    pub rejected: Vec<usize>,
+}
+
+impl FilePatch {
+    pub fn path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or_default()
+    }
+
+    pub fn is_new_file(&self) -> bool {
+        self.old_path.is_none() && self.new_path.is_some()
+    }
+
+    pub fn is_deleted_file(&self) -> bool {
+        self.new_path.is_none() && self.old_path.is_some()
+    }
+
+    /// 把各 hunk 应用到 `text` 上，像 `git apply` 一样容忍行号偏移。
+    ///
+    /// 找不到旧版本内容的 hunk 会被跳过并记在 [`PatchApplyResult::rejected`] 里，其余 hunk
+    /// 照常应用。
+    pub fn apply(&self, text: &str) -> PatchApplyResult {
+        let source: Vec<&str> = text.lines().collect();
+        let mut out: Vec<&str> = Vec::with_capacity(source.len());
+        let mut rejected = Vec::new();
+        let mut cursor = 0usize;
+        let mut drift = 0isize;
+
+        for (index, hunk) in self.hunks.iter().enumerate() {
+            let old = hunk.old_lines();
+            let expected = if hunk.old_len == 0 {
+                hunk.old_start
+            } else {
+                hunk.old_start.saturating_sub(1)
+            };
+            let expected = (expected as isize + drift).max(cursor as isize) as usize;
+
+            let Some(position) = find_lines(&source, &old, cursor, expected) else {
+                rejected.push(index);
+                continue;
+            };
+
+            out.extend_from_slice(&source[cursor..position]);
+            out.extend(hunk.new_lines());
+            cursor = position + old.len();
+            drift += position as isize - expected as isize;
+        }
+        out.extend_from_slice(&source[cursor.min(source.len())..]);
+
+        let mut text_out = out.join("\n");
+        if !text_out.is_empty() && (text.is_empty() || text.ends_with('\n')) {
+            text_out.push('\n');
+        }
+        PatchApplyResult {
+            text: text_out,
+            rejected,
+        }
+    }
+}
+
+/// 在 `haystack[from..]` 中查找 `needle`，优先取离 `expected` 最近的位置。
+fn find_lines(haystack: &[&str], needle: &[&str], from: usize, expected: usize) -> Option<usize> {
+    if haystack.len() < needle.len() || from > haystack.len() - needle.len() {
+        return None;
+    }
+    let last = haystack.len() - needle.len();
+    let expected = expected.clamp(from, last);
+    let matches_at = |position: usize| haystack[position..position + needle.len()] == *needle;
+
+    (0..=(last - from)).find_map(|distance| {
+        let after = Some(expected + distance).filter(|position| *position <= last);
+        let before = expected
+            .checked_sub(distance)
+            .filter(|position| *position >= from);
+        after
+            .into_iter()
+            .chain(before)
+            .find(|position| matches_at(*position))
+    })
+}
+
+/// 解析 `git diff` 或 `git format-patch` 生成的 unified diff。
+///
+/// diff 前后的邮件头、提交说明和签名会被忽略。
+pub fn parse_patch(text: &str) -> Vec<FilePatch> {
+    let mut files: Vec<FilePatch> = Vec::new();
+    let mut current: Option<FilePatch> = None;
+    // 当前文件是否由 `diff --git` 开始；是的话，后面的 `---`/`+++` 行属于这个文件，
+    // 而不是开始一个新文件。
+    let mut in_git_header = false;
+    let mut lines = text.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            files.extend(current.take());
+            let (old_path, new_path) = parse_git_header_paths(rest);
+            current = Some(FilePatch {
+                old_path,
+                new_path,
+                ..FilePatch::default()
+            });
+            in_git_header = true;
+            continue;
+        }
+
+        let starts_file_header =
+            line.starts_with("--- ") && lines.peek().is_some_and(|next| next.starts_with("+++ "));
+        if starts_file_header {
+            if !in_git_header {
+                files.extend(current.take());
+                current = Some(FilePatch::default());
+            }
+            let new_line = lines.next().unwrap_or_default();
+            if let Some(file) = current.as_mut() {
+                file.old_path = parse_marker_path(&line[4..], "a/");
+                file.new_path = parse_marker_path(&new_line[4..], "b/");
+            }
+            in_git_header = false;
+            continue;
+        }
+
+        let Some(file) = current.as_mut() else {
+            continue;
+        };
+
+        if line.starts_with("new file mode") {
+            file.old_path = None;
+        } else if line.starts_with("deleted file mode") {
+            file.new_path = None;
+        } else if line == "GIT binary patch"
+            || (line.starts_with("Binary files ") && line.ends_with(" differ"))
+        {
+            file.binary = true;
+        } else if let Some(mut hunk) = parse_hunk_header(line) {
+            in_git_header = false;
+            let (mut old_remaining, mut new_remaining) = (hunk.old_len, hunk.new_len);
+            while old_remaining > 0 || new_remaining > 0 {
+                let Some(body) = lines.peek() else {
+                    break;
+                };
+                let patch_line = match body.chars().next() {
+                    Some(' ') => PatchLine::Context(body[1..].to_string()),
+                    // 有些工具会去掉空上下文行行尾的空格。
+                    None => PatchLine::Context(String::new()),
+                    Some('-') => PatchLine::Removed(body[1..].to_string()),
+                    Some('+') => PatchLine::Added(body[1..].to_string()),
+                    Some('\\') => {
+                        lines.next();
+                        continue;
+                    }
+                    Some(_) => break,
+                };
+                match &patch_line {
+                    PatchLine::Context(_) => {
+                        old_remaining = old_remaining.saturating_sub(1);
+                        new_remaining = new_remaining.saturating_sub(1);
+                    }
+                    PatchLine::Removed(_) => old_remaining = old_remaining.saturating_sub(1),
+                    PatchLine::Added(_) => new_remaining = new_remaining.saturating_sub(1),
+                }
+                hunk.lines.push(patch_line);
+                lines.next();
+            }
+            file.hunks.push(hunk);
+        }
+    }
+
+    files.extend(current);
+    files.retain(|file| file.old_path.is_some() || file.new_path.is_some());
+    files
+}
+
+fn parse_git_header_paths(rest: &str) -> (Option<String>, Option<String>) {
+    match rest.split_once(" b/") {
+        Some((old, new)) => (
+            Some(old.strip_prefix("a/").unwrap_or(old).to_string()),
+            Some(new.to_string()),
+        ),
+        None => (None, None),
+    }
+}
+
+fn parse_marker_path(rest: &str, prefix: &str) -> Option<String> {
+    // `diff -u` 会在路径后面加一个制表符和时间戳。
+    let path = rest.split('\t').next().unwrap_or(rest).trim_end();
+    if path == "/dev/null" {
+        return None;
+    }
+    Some(path.strip_prefix(prefix).unwrap_or(path).to_string())
+}
+
+fn parse_hunk_header(line: &str) -> Option<PatchHunk> {
+    let rest = line.strip_prefix("@@ -")?;
+    let (ranges, section) = rest.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+    let (old_start, old_len) = parse_range(old)?;
+    let (new_start, new_len) = parse_range(new)?;
+
+    Some(PatchHunk {
+        old_start,
+        old_len,
+        new_start,
+        new_len,
+        section: section.trim().to_string(),
+        lines: Vec::new(),
+    })
+}
+
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIT_DIFF: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@ fn main() {
+ a
+-b
++B
+ c
+diff --git a/new.txt b/new.txt
+new file mode 100644
+index 0000000..3333333
+--- /dev/null
++++ b/new.txt
+@@ -0,0 +1,2 @@
++hello
++world
+";
+
+    #[test]
+    fn parses_files_and_hunks() {
+        let files = parse_patch(GIT_DIFF);
+        assert_eq!(files.len(), 2);
+
+        assert_eq!(files[0].path(), "src/lib.rs");
+        assert_eq!(files[0].hunks.len(), 1);
+        let hunk = &files[0].hunks[0];
+        assert_eq!(hunk.header(), "@@ -1,3 +1,3 @@ fn main() {");
+        assert_eq!((hunk.added_count(), hunk.removed_count()), (1, 1));
+
+        assert!(files[1].is_new_file());
+        assert_eq!(files[1].path(), "new.txt");
+        assert_eq!(files[1].hunks[0].lines.len(), 2);
+    }
+
+    #[test]
+    fn ignores_mailbox_headers_and_signature() {
+        let mailbox = format!(
+            "From 1234 Mon Sep 17 00:00:00 2001\nSubject: [PATCH] change\n\n---\n src/lib.rs | 2 +-\n\n{GIT_DIFF}-- \n2.45.0\n"
+        );
+        let files = parse_patch(&mailbox);
+        assert_eq!(files, parse_patch(GIT_DIFF));
+    }
+
+    #[test]
+    fn parses_plain_unified_diff_with_timestamps() {
+        let diff = "--- old/a.txt\t2024-01-01 00:00:00\n+++ new/a.txt\t2024-01-02 00:00:00\n@@ -1 +1 @@\n-x\n+y\n";
+        let files = parse_patch(diff);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].old_path.as_deref(), Some("old/a.txt"));
+        assert_eq!(files[0].new_path.as_deref(), Some("new/a.txt"));
+        assert_eq!(
+            (files[0].hunks[0].old_len, files[0].hunks[0].new_len),
+            (1, 1)
+        );
+    }
+
+    #[test]
+    fn applies_hunks_with_shifted_lines() {
+        let files = parse_patch(GIT_DIFF);
+        let result = files[0].apply("header\nextra\na\nb\nc\n");
+        assert_eq!(result.text, "header\nextra\na\nB\nc\n");
+        assert!(result.rejected.is_empty());
+
+        let created = files[1].apply("");
+        assert_eq!(created.text, "hello\nworld\n");
+    }
+
+    #[test]
+    fn reports_rejected_hunks_and_applies_the_rest() {
+        let patch = "--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n-1\n+one\n 2\n@@ -5,2 +5,2 @@\n 5\n-missing\n+six\n";
+        let files = parse_patch(patch);
+        let result = files[0].apply("1\n2\n3\n4\n5\n6\n");
+        assert_eq!(result.text, "one\n2\n3\n4\n5\n6\n");
+        assert_eq!(result.rejected, vec![1]);
+    }
+
+    #[test]
+    fn binary_changes_have_no_hunks() {
+        let patch = "diff --git a/logo.png b/logo.png\nindex 1..2 100644\nBinary files a/logo.png and b/logo.png differ\n";
+        let files = parse_patch(patch);
+        assert_eq!(files.len(), 1);
+        assert!(files[0].binary);
+        assert!(files[0].hunks.is_empty());
+    }
+}
@@ -0,0 +1,90 @@
+//! 按阶段统计 [`diff_documents`](crate::diff_documents) 的耗时。
+//!
+//! 计时只在打开 `instrumentation` feature 时编译进来；关闭时记录器不做任何事，
+//! 也不调用 `Instant::now`。
+
+#[cfg(feature = "instrumentation")]
+use std::time::{Duration, Instant};
+
+/// 一次 diff 各阶段的耗时，由 [`diff_documents_timed`](crate::diff::diff_documents_timed) 返回。
+#[cfg(feature = "instrumentation")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DiffTimings {
+    /// 拆行并生成比较键（忽略空白、补 CRLF）。
+    pub line_split: Duration,
+    /// 行级 Myers diff，包括替换块内部的二次对齐。
+    pub myers: Duration,
+    /// 修改行的字符级分段。
+    pub intraline: Duration,
+    /// 分组、生成行与 hunk，不含其中的行内分段。
+    pub hunk_building: Duration,
+}
+
+#[cfg(feature = "instrumentation")]
+impl DiffTimings {
+    pub fn total(&self) -> Duration {
+        self.line_split + self.myers + self.intraline + self.hunk_building
+    }
+
+    fn phase_mut(&mut self, phase: Phase) -> &mut Duration {
+        match phase {
+            Phase::LineSplit => &mut self.line_split,
+            Phase::Myers => &mut self.myers,
+            Phase::Intraline => &mut self.intraline,
+            Phase::HunkBuilding => &mut self.hunk_building,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Phase {
+    LineSplit,
+    Myers,
+    Intraline,
+    HunkBuilding,
+}
+
+/// 把时间记到当前阶段；[`Recorder::enter`] 切换阶段并返回之前的阶段，以便嵌套后切回。
+#[cfg(feature = "instrumentation")]
+pub(crate) struct Recorder {
+    timings: DiffTimings,
+    phase: Phase,
+    since: Instant,
+}
+
+#[cfg(feature = "instrumentation")]
+impl Recorder {
+    pub(crate) fn new(phase: Phase) -> Self {
+        Self {
+            timings: DiffTimings::default(),
+            phase,
+            since: Instant::now(),
+        }
+    }
+
+    pub(crate) fn enter(&mut self, phase: Phase) -> Phase {
+        let now = Instant::now();
+        *self.timings.phase_mut(self.phase) += now - self.since;
+        self.since = now;
+        std::mem::replace(&mut self.phase, phase)
+    }
+
+    pub(crate) fn finish(mut self) -> DiffTimings {
+        self.enter(self.phase);
+        self.timings
+    }
+}
+
+#[cfg(not(feature = "instrumentation"))]
+pub(crate) struct Recorder;
+
+#[cfg(not(feature = "instrumentation"))]
+impl Recorder {
+    pub(crate) fn new(_phase: Phase) -> Self {
+        Self
+    }
+
+    pub(crate) fn enter(&mut self, phase: Phase) -> Phase {
+        phase
+    }
+}
@@ -1,14 +1,18 @@
+use std::rc::Rc;
+
 use gpui::{
-    Context, IntoElement, ParentElement as _, Render, SharedString, Styled as _, Window, div, px,
+    Context, IntoElement, ParentElement as _, Pixels, Render, SharedString, Styled as _,
+    UniformListScrollHandle, Window, div, px,
 };
 use gpui_component::ActiveTheme as _;
 
-pub(crate) struct DragGhost {
+/// Drag preview showing a label, as used by the lists and trees of this crate.
+pub struct DragGhost {
     label: SharedString,
 }
 
 impl DragGhost {
-    pub(crate) fn new(label: SharedString) -> Self {
+    pub fn new(label: SharedString) -> Self {
         Self { label }
     }
 }
@@ -30,7 +34,120 @@ impl Render for DragGhost {
     }
 }
 
-pub(crate) fn reorder_to_index_for_drop_on_row(
+/// Where dropping on a hovered row puts the dragged item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DndDropPosition {
+    Before,
+    After,
+    /// Into the row: as a child in a tree, or handed to the host's `on_drop_inside` in a list.
+    Inside,
+}
+
+/// What a [`DndDropStrategy`] sees while the pointer is over a row.
+#[derive(Clone, Copy, Debug)]
+pub struct DndDropProbe {
+    /// Pointer offset from the top of the hovered row.
+    pub offset_y: Pixels,
+    pub row_height: Pixels,
+    /// Index of the dragged item.
+    pub from_ix: usize,
+    /// Index of the hovered row.
+    pub target_ix: usize,
+    /// Whether the hovered row can take the dragged item inside.
+    pub can_nest: bool,
+}
+
+/// Decides when a hovered row becomes a "before", "after" or "inside" target.
+#[derive(Clone)]
+pub enum DndDropStrategy {
+    /// Before or after, whichever edge of the row is closer to the pointer.
+    ClosestEdge,
+    /// Past the hovered row in the drag direction, wherever the pointer is on it: before rows
+    /// above the dragged item, after rows below it. This is the usual sortable-list feel.
+    PointerCenter,
+    /// The top fraction of the row drops before, the bottom fraction after, and the band in
+    /// between inside; rows that cannot nest fall back to the closest edge. Clamped to
+    /// `0.0..=0.5`, so `Threshold(0.25)` gives the middle half of the row to "inside".
+    Threshold(f32),
+    /// Host-defined; returning `None` rejects the drop.
+    Custom(Rc<dyn Fn(&DndDropProbe) -> Option<DndDropPosition>>),
+}
+
+impl DndDropStrategy {
+    pub fn custom(resolve: impl Fn(&DndDropProbe) -> Option<DndDropPosition> + 'static) -> Self {
+        Self::Custom(Rc::new(resolve))
+    }
+
+    /// The drop position for `probe`, or `None` when the row rejects the drop.
+    pub fn resolve(&self, probe: &DndDropProbe) -> Option<DndDropPosition> {
+        let closest_edge = || {
+            if probe.offset_y < probe.row_height / 2.0 {
+                DndDropPosition::Before
+            } else {
+                DndDropPosition::After
+            }
+        };
+
+        match self {
+            Self::ClosestEdge => Some(closest_edge()),
+            Self::PointerCenter => Some(if probe.target_ix > probe.from_ix {
+                DndDropPosition::After
+            } else if probe.target_ix < probe.from_ix {
+                DndDropPosition::Before
+            } else {
+                closest_edge()
+            }),
+            Self::Threshold(fraction) => {
+                let fraction = if fraction.is_finite() {
+                    fraction.clamp(0.0, 0.5)
+                } else {
+                    0.5
+                };
+                let band = probe.row_height * fraction;
+                Some(if !probe.can_nest {
+                    closest_edge()
+                } else if probe.offset_y < band {
+                    DndDropPosition::Before
+                } else if probe.offset_y > probe.row_height - band {
+                    DndDropPosition::After
+                } else {
+                    DndDropPosition::Inside
+                })
+            }
+            Self::Custom(resolve) => resolve(probe),
+        }
+    }
+}
+
+/// Row height of a uniform list with `item_count` rows, from its last layout.
+pub(crate) fn uniform_row_height(
+    scroll_handle: &UniformListScrollHandle,
+    item_count: usize,
+) -> Pixels {
+    const FALLBACK: Pixels = px(28.);
+    if item_count == 0 {
+        return FALLBACK;
+    }
+
+    let Some(last_size) = scroll_handle.0.borrow().last_item_size else {
+        return FALLBACK;
+    };
+
+    // `UniformListScrollHandle::last_item_size.item` is the viewport size, not the row height.
+    // Row height is uniform and can be derived from the total content height.
+    let height = last_size.contents.height * (1.0 / item_count as f32);
+    let height_f32: f32 = height.into();
+    if height_f32.is_finite() && height_f32 > 0.0 {
+        height
+    } else {
+        FALLBACK
+    }
+}
+
+/// Destination index after dropping the item at `from_ix` onto the item at `target_ix`.
+///
+/// Dropping onto an earlier item inserts before it, onto a later item after it.
+pub fn reorder_to_index_for_drop_on_row(
     from_ix: usize,
     target_ix: usize,
     item_count: usize,
@@ -51,10 +168,88 @@ pub(crate) fn reorder_to_index_for_drop_after_last(from_ix: usize, item_count: u
     reorder_to_index_from_gap(from_ix, gap_index, item_count)
 }
 
-fn reorder_to_index_from_gap(from_ix: usize, gap_index: usize, item_count: usize) -> usize {
+pub(crate) fn reorder_to_index_from_gap(
+    from_ix: usize,
+    gap_index: usize,
+    item_count: usize,
+) -> usize {
     let mut to_ix = gap_index;
     if to_ix > from_ix {
         to_ix = to_ix.saturating_sub(1);
     }
     to_ix.min(item_count.saturating_sub(1))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe(offset_y: f32, from_ix: usize, target_ix: usize, can_nest: bool) -> DndDropProbe {
+        DndDropProbe {
+            offset_y: px(offset_y),
+            row_height: px(20.),
+            from_ix,
+            target_ix,
+            can_nest,
+        }
+    }
+
+    #[test]
+    fn closest_edge_splits_the_row_in_half() {
+        let strategy = DndDropStrategy::ClosestEdge;
+        assert_eq!(
+            strategy.resolve(&probe(4., 0, 3, true)),
+            Some(DndDropPosition::Before)
+        );
+        assert_eq!(
+            strategy.resolve(&probe(16., 5, 3, true)),
+            Some(DndDropPosition::After)
+        );
+    }
+
+    #[test]
+    fn pointer_center_follows_the_drag_direction() {
+        let strategy = DndDropStrategy::PointerCenter;
+        assert_eq!(
+            strategy.resolve(&probe(2., 0, 3, false)),
+            Some(DndDropPosition::After)
+        );
+        assert_eq!(
+            strategy.resolve(&probe(18., 5, 3, false)),
+            Some(DndDropPosition::Before)
+        );
+    }
+
+    #[test]
+    fn threshold_reserves_a_middle_band_for_nesting() {
+        let strategy = DndDropStrategy::Threshold(0.25);
+        assert_eq!(
+            strategy.resolve(&probe(4., 0, 3, true)),
+            Some(DndDropPosition::Before)
+        );
+        assert_eq!(
+            strategy.resolve(&probe(10., 0, 3, true)),
+            Some(DndDropPosition::Inside)
+        );
+        assert_eq!(
+            strategy.resolve(&probe(16., 0, 3, true)),
+            Some(DndDropPosition::After)
+        );
+        assert_eq!(
+            strategy.resolve(&probe(9., 0, 3, false)),
+            Some(DndDropPosition::Before)
+        );
+    }
+
+    #[test]
+    fn custom_strategy_can_reject() {
+        let strategy = DndDropStrategy::custom(|probe| {
+            (probe.target_ix != 1).then_some(DndDropPosition::Inside)
+        });
+        assert_eq!(strategy.resolve(&probe(0., 0, 1, false)), None);
+        assert_eq!(
+            strategy.resolve(&probe(0., 0, 2, false)),
+            Some(DndDropPosition::Inside)
+        );
+    }
+}
@@ -0,0 +1,315 @@
+//! Deciding when a press on a row becomes a drag.
+//!
+//! gpui starts a drag as soon as the pointer moves two pixels with the button held. That suits
+//! a mouse, but on a touch screen every swipe becomes a drag and a pen tap drags whenever the
+//! tip wobbles. A [`DndActivation`] other than the default holds the drag back until the press
+//! qualifies: until then the pointer moves are swallowed before gpui sees them, and a touch
+//! press that moves too early pans the list instead. The tracker listens on the whole window,
+//! so a press keeps being followed after the pointer leaves the row it started on.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use gpui::{
+    App, DispatchPhase, IntoElement, MouseDownEvent, MouseMoveEvent, MouseUpEvent, Pixels, Point,
+    ScrollHandle, ScrollWheelEvent, Styled as _, Window, canvas, point, px,
+};
+
+/// How a press on a row turns into a drag.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DndActivation {
+    /// gpui's own behavior: the drag starts as soon as the pointer moves.
+    #[default]
+    Immediate,
+    /// The drag starts once the pointer is further than this from the press.
+    Distance(Pixels),
+    /// The drag starts once the pointer has stayed within `tolerance` of the press for `delay`.
+    /// Moving further before that pans the list, so a swipe scrolls instead of dragging.
+    LongPress { delay: Duration, tolerance: Pixels },
+}
+
+impl DndActivation {
+    /// Long-press activation with the timing usual on touch screens.
+    pub fn touch() -> Self {
+        Self::LongPress {
+            delay: Duration::from_millis(250),
+            tolerance: px(8.),
+        }
+    }
+
+    /// A slop threshold wide enough to absorb the wobble of a pen tap.
+    pub fn pen() -> Self {
+        Self::Distance(px(8.))
+    }
+}
+
+/// What to do with a pointer move while a press is tracked.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum PointerDecision {
+    /// Let the move through; gpui starts the drag once the pointer is far enough.
+    Pass,
+    /// Swallow the move so the drag does not start yet.
+    Hold,
+    /// Swallow the move and scroll the list by the given delta.
+    Pan(Point<Pixels>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Phase {
+    Idle,
+    Pressed {
+        origin: Point<Pixels>,
+        at: Instant,
+    },
+    /// The press qualified; the drag starts with the next move.
+    Armed,
+    Panning {
+        last: Point<Pixels>,
+    },
+    /// A wheel or trackpad scroll took over the press.
+    Cancelled,
+}
+
+#[derive(Debug)]
+pub(crate) struct PointerGesture {
+    activation: DndActivation,
+    phase: Phase,
+    /// Set when the last press ended up scrolling, so the click that ends it is ignored.
+    suppress_click: bool,
+}
+
+impl PointerGesture {
+    pub(crate) fn new(activation: DndActivation) -> Self {
+        Self {
+            activation,
+            phase: Phase::Idle,
+            suppress_click: false,
+        }
+    }
+
+    pub(crate) fn press(&mut self, position: Point<Pixels>, now: Instant) {
+        self.suppress_click = false;
+        self.phase = match self.activation {
+            DndActivation::Immediate => Phase::Idle,
+            _ => Phase::Pressed {
+                origin: position,
+                at: now,
+            },
+        };
+    }
+
+    pub(crate) fn move_to(&mut self, position: Point<Pixels>, now: Instant) -> PointerDecision {
+        match self.phase {
+            Phase::Idle | Phase::Armed => PointerDecision::Pass,
+            Phase::Cancelled => PointerDecision::Hold,
+            Phase::Panning { last } => {
+                self.phase = Phase::Panning { last: position };
+                PointerDecision::Pan(position - last)
+            }
+            Phase::Pressed { origin, at } => {
+                let distance = (position - origin).magnitude();
+                match self.activation {
+                    DndActivation::Immediate => PointerDecision::Pass,
+                    DndActivation::Distance(slop) => {
+                        if distance > f64::from(slop) {
+                            self.phase = Phase::Armed;
+                            PointerDecision::Pass
+                        } else {
+                            PointerDecision::Hold
+                        }
+                    }
+                    DndActivation::LongPress { delay, tolerance } => {
+                        if now.duration_since(at) >= delay {
+                            self.phase = Phase::Armed;
+                            PointerDecision::Pass
+                        } else if distance > f64::from(tolerance) {
+                            self.phase = Phase::Panning { last: position };
+                            self.suppress_click = true;
+                            PointerDecision::Pan(position - origin)
+                        } else {
+                            PointerDecision::Hold
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// A scroll while the press has not turned into a drag yet means the user is scrolling.
+    pub(crate) fn scroll(&mut self) {
+        if matches!(self.phase, Phase::Pressed { .. }) {
+            self.phase = Phase::Cancelled;
+            self.suppress_click = true;
+        }
+    }
+
+    pub(crate) fn release(&mut self) {
+        self.phase = Phase::Idle;
+    }
+
+    pub(crate) fn take_suppressed_click(&mut self) -> bool {
+        std::mem::take(&mut self.suppress_click)
+    }
+}
+
+/// A [`PointerGesture`] shared between the rows of a list, which report presses, and its
+/// tracker, which follows the pointer.
+#[derive(Clone)]
+pub(crate) struct DragGesture(Rc<RefCell<PointerGesture>>);
+
+impl DragGesture {
+    pub(crate) fn new(activation: DndActivation) -> Self {
+        Self(Rc::new(RefCell::new(PointerGesture::new(activation))))
+    }
+
+    pub(crate) fn set_activation(&self, activation: DndActivation) {
+        *self.0.borrow_mut() = PointerGesture::new(activation);
+    }
+
+    /// Whether presses need tracking at all; with [`DndActivation::Immediate`] gpui handles
+    /// everything.
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.0.borrow().activation != DndActivation::Immediate
+    }
+
+    pub(crate) fn take_suppressed_click(&self) -> bool {
+        self.0.borrow_mut().take_suppressed_click()
+    }
+
+    /// Mouse-down listener for a row that can be dragged.
+    pub(crate) fn on_press(&self) -> impl Fn(&MouseDownEvent, &mut Window, &mut App) + 'static {
+        let gesture = self.clone();
+        move |event, _window, _cx| {
+            gesture.0.borrow_mut().press(event.position, Instant::now());
+        }
+    }
+
+    /// An invisible overlay that follows a press through the window and scrolls
+    /// `scroll_handle` when the press pans.
+    pub(crate) fn tracker(&self, scroll_handle: ScrollHandle) -> impl IntoElement {
+        let gesture = self.clone();
+        canvas(
+            |_, _, _| {},
+            move |_, _, window, _| {
+                window.on_mouse_event({
+                    let gesture = gesture.clone();
+                    move |event: &MouseMoveEvent, phase, window, cx| {
+                        if phase != DispatchPhase::Capture {
+                            return;
+                        }
+                        let decision = gesture
+                            .0
+                            .borrow_mut()
+                            .move_to(event.position, Instant::now());
+                        match decision {
+                            PointerDecision::Pass => {}
+                            PointerDecision::Hold => cx.stop_propagation(),
+                            PointerDecision::Pan(delta) => {
+                                pan(&scroll_handle, delta);
+                                window.refresh();
+                                cx.stop_propagation();
+                            }
+                        }
+                    }
+                });
+                window.on_mouse_event({
+                    let gesture = gesture.clone();
+                    move |_: &MouseUpEvent, phase, _, _| {
+                        if phase == DispatchPhase::Capture {
+                            gesture.0.borrow_mut().release();
+                        }
+                    }
+                });
+                window.on_mouse_event(move |_: &ScrollWheelEvent, phase, _, _| {
+                    if phase == DispatchPhase::Capture {
+                        gesture.0.borrow_mut().scroll();
+                    }
+                });
+            },
+        )
+        .absolute()
+        .size_full()
+    }
+}
+
+fn pan(scroll_handle: &ScrollHandle, delta: Point<Pixels>) {
+    let max = scroll_handle.max_offset();
+    let offset = scroll_handle.offset();
+    let y = (offset.y + delta.y).clamp(-max.height, px(0.));
+    scroll_handle.set_offset(point(offset.x, y));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(x: f32, y: f32) -> Point<Pixels> {
+        point(px(x), px(y))
+    }
+
+    #[test]
+    fn distance_holds_the_drag_within_the_slop() {
+        let now = Instant::now();
+        let mut gesture = PointerGesture::new(DndActivation::Distance(px(8.)));
+        gesture.press(at(10., 10.), now);
+
+        assert_eq!(gesture.move_to(at(14., 10.), now), PointerDecision::Hold);
+        assert_eq!(gesture.move_to(at(19., 10.), now), PointerDecision::Pass);
+        // Once armed the drag may follow the pointer back towards the press.
+        assert_eq!(gesture.move_to(at(12., 10.), now), PointerDecision::Pass);
+    }
+
+    #[test]
+    fn long_press_arms_after_the_delay() {
+        let now = Instant::now();
+        let mut gesture = PointerGesture::new(DndActivation::touch());
+        gesture.press(at(0., 0.), now);
+
+        assert_eq!(gesture.move_to(at(3., 3.), now), PointerDecision::Hold);
+        let later = now + Duration::from_millis(300);
+        assert_eq!(gesture.move_to(at(40., 0.), later), PointerDecision::Pass);
+        assert!(!gesture.take_suppressed_click());
+    }
+
+    #[test]
+    fn early_movement_pans_instead_of_dragging() {
+        let now = Instant::now();
+        let mut gesture = PointerGesture::new(DndActivation::touch());
+        gesture.press(at(0., 100.), now);
+
+        assert_eq!(
+            gesture.move_to(at(0., 80.), now),
+            PointerDecision::Pan(at(0., -20.))
+        );
+        let later = now + Duration::from_secs(1);
+        assert_eq!(
+            gesture.move_to(at(0., 70.), later),
+            PointerDecision::Pan(at(0., -10.))
+        );
+
+        gesture.release();
+        assert!(gesture.take_suppressed_click());
+        assert_eq!(gesture.move_to(at(0., 0.), later), PointerDecision::Pass);
+    }
+
+    #[test]
+    fn wheel_scroll_cancels_a_pending_press() {
+        let now = Instant::now();
+        let mut gesture = PointerGesture::new(DndActivation::pen());
+        gesture.press(at(0., 0.), now);
+        gesture.scroll();
+
+        assert_eq!(gesture.move_to(at(50., 0.), now), PointerDecision::Hold);
+        gesture.release();
+        assert!(gesture.take_suppressed_click());
+    }
+
+    #[test]
+    fn immediate_never_holds() {
+        let now = Instant::now();
+        let mut gesture = PointerGesture::new(DndActivation::Immediate);
+        gesture.press(at(0., 0.), now);
+        assert_eq!(gesture.move_to(at(1., 0.), now), PointerDecision::Pass);
+    }
+}
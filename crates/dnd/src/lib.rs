@@ -1,12 +1,17 @@
 mod common;
+mod gesture;
 mod list;
 mod tree;
 mod vlist;
 mod vtree;
 
+pub use common::{
+    DndDropPosition, DndDropProbe, DndDropStrategy, DragGhost, reorder_to_index_for_drop_on_row,
+};
+pub use gesture::DndActivation;
 pub use list::{
-    DndList, DndListDropTarget, DndListItem, DndListReorder, DndListRowState, DndListState,
-    dnd_list,
+    DndList, DndListDropInto, DndListDropTarget, DndListItem, DndListReorder, DndListRowState,
+    DndListState, dnd_list,
 };
 pub use tree::{
     DndTree, DndTreeDropTarget, DndTreeEntry, DndTreeIndicatorCap, DndTreeIndicatorStyle,
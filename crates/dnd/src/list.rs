@@ -2,17 +2,21 @@ use std::{ops::Range, rc::Rc};
 
 use gpui::{
     App, AppContext as _, Context, CursorStyle, ElementId, Entity, EntityId, FocusHandle,
-    InteractiveElement as _, IntoElement, ListSizingBehavior, Modifiers, ParentElement as _,
-    Pixels, Render, RenderOnce, SharedString, StatefulInteractiveElement as _, StyleRefinement,
-    Styled, UniformListScrollHandle, Window, div, prelude::FluentBuilder as _, px, uniform_list,
+    InteractiveElement as _, IntoElement, ListSizingBehavior, Modifiers, MouseButton,
+    ParentElement as _, Pixels, Render, RenderOnce, SharedString, StatefulInteractiveElement as _,
+    StyleRefinement, Styled, UniformListScrollHandle, Window, div, prelude::FluentBuilder as _, px,
+    uniform_list,
 };
 use gpui_component::list::ListItem;
 use gpui_component::scroll::{Scrollbar, ScrollbarState};
 use gpui_component::{ActiveTheme as _, StyledExt as _};
 
 use crate::common::{
-    DragGhost, reorder_to_index_for_drop_after_last, reorder_to_index_for_drop_on_row,
+    DndDropPosition, DndDropProbe, DndDropStrategy, DragGhost,
+    reorder_to_index_for_drop_after_last, reorder_to_index_for_drop_on_row,
+    reorder_to_index_from_gap, uniform_row_height,
 };
+use crate::gesture::{DndActivation, DragGesture};
 
 const CONTEXT: &str = "DndList";
 
@@ -66,6 +70,8 @@ struct DndListDrag {
 pub enum DndListDropTarget {
     Before,
     After,
+    /// Onto the row itself; see [`DndListState::on_drop_into`].
+    Inside,
 }
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -82,9 +88,27 @@ pub struct DndListReorder {
     pub to: usize,
 }
 
+/// An item dropped onto another row instead of between rows.
+#[derive(Clone, Debug)]
+pub struct DndListDropInto {
+    pub item_id: SharedString,
+    pub from: usize,
+    /// Index of the row the item was dropped onto.
+    pub into: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct DropPreview {
+    ix: usize,
+    /// `None` when the strategy rejects the hovered row.
+    target: Option<DndListDropTarget>,
+}
+
 struct DndListStateCallbacks<T> {
     can_drop: Option<Rc<dyn Fn(&DndListReorder, &[DndListItem<T>], Modifiers) -> bool>>,
     on_reorder: Option<Rc<dyn Fn(&DndListReorder, &[DndListItem<T>])>>,
+    can_drop_into: Option<Rc<dyn Fn(&DndListDropInto, &[DndListItem<T>], Modifiers) -> bool>>,
+    on_drop_into: Option<Rc<dyn Fn(&DndListDropInto, &mut Vec<DndListItem<T>>)>>,
 }
 
 impl<T> Default for DndListStateCallbacks<T> {
@@ -92,6 +116,8 @@ impl<T> Default for DndListStateCallbacks<T> {
         Self {
             can_drop: None,
             on_reorder: None,
+            can_drop_into: None,
+            on_drop_into: None,
         }
     }
 }
@@ -103,9 +129,12 @@ pub struct DndListState<T> {
     scrollbar_state: ScrollbarState,
     scroll_handle: UniformListScrollHandle,
     drag_handle_width: Option<Pixels>,
+    gesture: DragGesture,
     selected_ix: Option<usize>,
     dragged_id: Option<SharedString>,
     dragged_ix: Option<usize>,
+    drop_preview: Option<DropPreview>,
+    drop_strategy: DndDropStrategy,
     callbacks: DndListStateCallbacks<T>,
     render_item:
         Rc<dyn Fn(usize, &DndListItem<T>, DndListRowState, &mut Window, &mut App) -> ListItem>,
@@ -119,9 +148,12 @@ impl<T: 'static> DndListState<T> {
             scrollbar_state: ScrollbarState::default(),
             scroll_handle: UniformListScrollHandle::default(),
             drag_handle_width: Some(px(32.)),
+            gesture: DragGesture::new(DndActivation::default()),
             selected_ix: None,
             dragged_id: None,
             dragged_ix: None,
+            drop_preview: None,
+            drop_strategy: DndDropStrategy::PointerCenter,
             callbacks: DndListStateCallbacks::default(),
             render_item: Rc::new(|_, _, _, _, _| ListItem::new("dnd-list-empty")),
         }
@@ -146,11 +178,27 @@ impl<T: 'static> DndListState<T> {
         self
     }
 
+    /// Set how a press turns into a drag; see [`DndActivation`] for touch and pen input.
+    pub fn activation(self, activation: DndActivation) -> Self {
+        self.gesture.set_activation(activation);
+        self
+    }
+
+    /// Set how the pointer position over a row picks before, after or inside.
+    ///
+    /// Defaults to [`DndDropStrategy::PointerCenter`]. Rows only become "inside" targets when
+    /// [`Self::on_drop_into`] is set and [`Self::can_drop_into`] allows it.
+    pub fn drop_strategy(mut self, strategy: DndDropStrategy) -> Self {
+        self.drop_strategy = strategy;
+        self
+    }
+
     pub fn set_items(&mut self, items: impl Into<Vec<DndListItem<T>>>, cx: &mut Context<Self>) {
         self.items = items.into();
         self.selected_ix = None;
         self.dragged_id = None;
         self.dragged_ix = None;
+        self.drop_preview = None;
         cx.notify();
     }
 
@@ -191,6 +239,27 @@ impl<T: 'static> DndListState<T> {
         self
     }
 
+    /// Provide a predicate to control which rows an item may be dropped into.
+    pub fn can_drop_into(
+        mut self,
+        can_drop_into: impl Fn(&DndListDropInto, &[DndListItem<T>], Modifiers) -> bool + 'static,
+    ) -> Self {
+        self.callbacks.can_drop_into = Some(Rc::new(can_drop_into));
+        self
+    }
+
+    /// Provide a callback invoked when an item is dropped onto another row.
+    ///
+    /// The host decides what dropping "into" means and may edit the items in place, e.g. to
+    /// merge the dragged item into the target. The dragged item stays selected if it remains.
+    pub fn on_drop_into(
+        mut self,
+        on_drop_into: impl Fn(&DndListDropInto, &mut Vec<DndListItem<T>>) + 'static,
+    ) -> Self {
+        self.callbacks.on_drop_into = Some(Rc::new(on_drop_into));
+        self
+    }
+
     fn on_entry_click(
         &mut self,
         ix: usize,
@@ -198,6 +267,9 @@ impl<T: 'static> DndListState<T> {
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) {
+        if self.gesture.take_suppressed_click() {
+            return;
+        }
         self.selected_ix = Some(ix);
         cx.notify();
     }
@@ -221,6 +293,87 @@ impl<T: 'static> DndListState<T> {
             .unwrap_or(true)
     }
 
+    fn drop_into_is_allowed(&self, drop_into: &DndListDropInto, modifiers: Modifiers) -> bool {
+        self.callbacks.on_drop_into.is_some()
+            && drop_into.from != drop_into.into
+            && self
+                .callbacks
+                .can_drop_into
+                .as_ref()
+                .map(|f| f(drop_into, &self.items, modifiers))
+                .unwrap_or(true)
+    }
+
+    fn on_drag_move(
+        &mut self,
+        event: &gpui::DragMoveEvent<DndListDrag>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if !cx.has_active_drag() {
+            return;
+        }
+
+        let mouse_position = event.event.position;
+        let list_bounds = event.bounds;
+        let drag = event.drag(cx);
+        let new_preview = if list_bounds.contains(&mouse_position) && drag.list_id == cx.entity_id()
+        {
+            let y_in_list = mouse_position.y - list_bounds.origin.y;
+            self.compute_drop_preview(drag, y_in_list, window.modifiers())
+        } else {
+            None
+        };
+
+        if self.drop_preview != new_preview {
+            self.drop_preview = new_preview;
+            cx.notify();
+        }
+    }
+
+    fn compute_drop_preview(
+        &self,
+        drag: &DndListDrag,
+        y_in_list: Pixels,
+        modifiers: Modifiers,
+    ) -> Option<DropPreview> {
+        let from_ix = self.items.iter().position(|item| item.id == drag.item_id)?;
+        let scroll_y = self.scroll_handle.0.borrow().base_handle.offset().y;
+        let row_height = uniform_row_height(&self.scroll_handle, self.items.len());
+        let y_in_content = y_in_list - scroll_y;
+        let target_ix = (y_in_content / row_height).floor().max(0.0) as usize;
+        if target_ix >= self.items.len() {
+            return None;
+        }
+
+        let drop_into = DndListDropInto {
+            item_id: drag.item_id.clone(),
+            from: from_ix,
+            into: target_ix,
+        };
+        let can_nest = self.drop_into_is_allowed(&drop_into, modifiers);
+        let position = self.drop_strategy.resolve(&DndDropProbe {
+            offset_y: (y_in_content - row_height * target_ix).max(px(0.)),
+            row_height,
+            from_ix,
+            target_ix,
+            can_nest,
+        });
+
+        let target = match position {
+            Some(DndDropPosition::Inside) if can_nest => Some(DndListDropTarget::Inside),
+            // Before or after the dragged row itself leaves the list unchanged.
+            _ if target_ix == from_ix => None,
+            Some(DndDropPosition::Before) => Some(DndListDropTarget::Before),
+            Some(DndDropPosition::After) => Some(DndListDropTarget::After),
+            _ => None,
+        };
+        Some(DropPreview {
+            ix: target_ix,
+            target,
+        })
+    }
+
     fn on_drop_on_row(
         &mut self,
         drag: &DndListDrag,
@@ -228,6 +381,7 @@ impl<T: 'static> DndListState<T> {
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
+        let preview = self.drop_preview.take();
         if drag.list_id != cx.entity_id() {
             return;
         }
@@ -240,7 +394,26 @@ impl<T: 'static> DndListState<T> {
         if item_count == 0 {
             return;
         }
-        let to_ix = reorder_to_index_for_drop_on_row(from_ix, target_ix, item_count);
+        let preview_target = preview
+            .filter(|preview| preview.ix == target_ix)
+            .map(|preview| preview.target);
+        let to_ix = match preview_target {
+            Some(None) => {
+                cx.notify();
+                return;
+            }
+            Some(Some(DndListDropTarget::Before)) => {
+                reorder_to_index_from_gap(from_ix, target_ix, item_count)
+            }
+            Some(Some(DndListDropTarget::After)) => {
+                reorder_to_index_from_gap(from_ix, target_ix.saturating_add(1), item_count)
+            }
+            Some(Some(DndListDropTarget::Inside)) => {
+                self.drop_into(drag, from_ix, target_ix, window, cx);
+                return;
+            }
+            None => reorder_to_index_for_drop_on_row(from_ix, target_ix, item_count),
+        };
 
         let reorder = DndListReorder {
             item_id: drag.item_id.clone(),
@@ -271,12 +444,38 @@ impl<T: 'static> DndListState<T> {
         }
     }
 
+    fn drop_into(
+        &mut self,
+        drag: &DndListDrag,
+        from_ix: usize,
+        target_ix: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let drop_into = DndListDropInto {
+            item_id: drag.item_id.clone(),
+            from: from_ix,
+            into: target_ix,
+        };
+        cx.notify();
+        if !self.drop_into_is_allowed(&drop_into, window.modifiers()) {
+            return;
+        }
+
+        if let Some(on_drop_into) = self.callbacks.on_drop_into.clone() {
+            on_drop_into(&drop_into, &mut self.items);
+        }
+        self.selected_ix = self.items.iter().position(|item| item.id == drag.item_id);
+        self.dragged_ix = self.selected_ix;
+    }
+
     fn on_drop_after_last(
         &mut self,
         drag: &DndListDrag,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
+        self.drop_preview = None;
         if drag.list_id != cx.entity_id() {
             return;
         }
@@ -324,12 +523,15 @@ impl<T: 'static> Render for DndListState<T> {
         if !cx.has_active_drag() {
             self.dragged_id = None;
             self.dragged_ix = None;
+            self.drop_preview = None;
         }
 
         let render_item = Rc::clone(&self.render_item);
         let state_entity = cx.entity();
         let dragged_id = self.dragged_id.clone();
         let drag_handle_width = self.drag_handle_width;
+        let gesture = self.gesture.clone();
+        let gesture_enabled = gesture.is_enabled();
         let list_id = cx.entity_id();
 
         div()
@@ -349,10 +551,15 @@ impl<T: 'static> Render for DndListState<T> {
                             let dragging = dragged_id.as_ref().is_some_and(|id| *id == item.id)
                                 && cx.has_active_drag();
 
+                            let drop_target = state
+                                .drop_preview
+                                .filter(|preview| preview.ix == ix && cx.has_active_drag())
+                                .and_then(|preview| preview.target);
+
                             let row_state = DndListRowState {
                                 selected,
                                 dragging,
-                                drop_target: None,
+                                drop_target,
                             };
 
                             let list_item = (render_item)(ix, item, row_state, window, cx);
@@ -368,26 +575,24 @@ impl<T: 'static> Render for DndListState<T> {
                                 .id(ix)
                                 .relative()
                                 .child(list_item.disabled(is_disabled).selected(selected))
-                                .drag_over::<DndListDrag>(move |style, drag, _window, _cx| {
-                                    if drag.list_id != list_id {
-                                        return style;
-                                    }
-
-                                    let mut style =
-                                        style.bg(drop_target_bg.alpha(drop_target_bg.a.max(0.2)));
-                                    style = style.border_color(drag_border);
-                                    if ix < drag.ix {
-                                        style = style.border_t_2();
-                                    } else if ix > drag.ix {
-                                        style = style.border_b_2();
+                                .when_some(drop_target, |this, target| {
+                                    let this = this
+                                        .bg(drop_target_bg.alpha(drop_target_bg.a.max(0.2)))
+                                        .border_color(drag_border);
+                                    match target {
+                                        DndListDropTarget::Before => this.border_t_2(),
+                                        DndListDropTarget::After => this.border_b_2(),
+                                        DndListDropTarget::Inside => this,
                                     }
-                                    style
                                 })
                                 .on_drop::<DndListDrag>(cx.listener(
                                     move |this, drag, window, cx| {
                                         this.on_drop_on_row(drag, ix, window, cx);
                                     },
                                 ))
+                                .when(gesture_enabled, |this| {
+                                    this.on_mouse_down(MouseButton::Left, gesture.on_press())
+                                })
                                 .when(!is_disabled, |this| {
                                     this.on_click(cx.listener(
                                         move |this, click_event, window, cx| {
@@ -436,6 +641,7 @@ impl<T: 'static> Render for DndListState<T> {
                         rows
                     })
                 })
+                .on_drag_move::<DndListDrag>(cx.listener(Self::on_drag_move))
                 .on_drop::<DndListDrag>(cx.listener(Self::on_drop_after_last))
                 .flex_grow()
                 .size_full()
@@ -455,6 +661,12 @@ impl<T: 'static> Render for DndListState<T> {
                         &self.scroll_handle,
                     )),
             )
+            .when(gesture_enabled, |this| {
+                this.child(
+                    self.gesture
+                        .tracker(self.scroll_handle.0.borrow().base_handle.clone()),
+                )
+            })
     }
 }
 
@@ -2,16 +2,19 @@ use std::{cell::RefCell, ops::Range, rc::Rc};
 
 use gpui::{
     App, AppContext as _, Context, CursorStyle, ElementId, Entity, EntityId, FocusHandle, Hsla,
-    InteractiveElement as _, IntoElement, ListSizingBehavior, ParentElement as _, Pixels, Point,
-    Render, RenderOnce, ScrollStrategy, SharedString, StatefulInteractiveElement as _,
-    StyleRefinement, Styled, UniformListScrollHandle, Window, div, prelude::FluentBuilder as _, px,
-    uniform_list,
+    InteractiveElement as _, IntoElement, ListSizingBehavior, MouseButton, ParentElement as _,
+    Pixels, Point, Render, RenderOnce, ScrollStrategy, SharedString,
+    StatefulInteractiveElement as _, StyleRefinement, Styled, UniformListScrollHandle, Window, div,
+    prelude::FluentBuilder as _, px, uniform_list,
 };
 use gpui_component::list::ListItem;
 use gpui_component::scroll::{Scrollbar, ScrollbarState};
 use gpui_component::{ActiveTheme as _, StyledExt as _};
 
-use crate::common::DragGhost;
+use crate::common::{
+    DndDropPosition, DndDropProbe, DndDropStrategy, DragGhost, uniform_row_height,
+};
+use crate::gesture::{DndActivation, DragGesture};
 
 const CONTEXT: &str = "DndTree";
 const HORIZONTAL_GESTURE_THRESHOLD_PX: f32 = 24.0;
@@ -158,6 +161,8 @@ impl DndTreeEntry {
 pub enum DndTreeDropTarget {
     Before,
     After,
+    /// As the first child of the row.
+    Inside,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -222,10 +227,12 @@ pub struct DndTreeState {
     scrollbar_state: ScrollbarState,
     scroll_handle: UniformListScrollHandle,
     drag_handle_width: Option<Pixels>,
+    gesture: DragGesture,
     selected_ix: Option<usize>,
     dragged_id: Option<SharedString>,
     dragged_ix: Option<usize>,
     drop_preview: Option<DropPreview>,
+    drop_strategy: DndDropStrategy,
     drag_start_mouse_position: Option<Point<Pixels>>,
     render_item:
         Rc<dyn Fn(usize, &DndTreeEntry, DndTreeRowState, &mut Window, &mut App) -> ListItem>,
@@ -243,10 +250,12 @@ impl DndTreeState {
             scrollbar_state: ScrollbarState::default(),
             scroll_handle: UniformListScrollHandle::default(),
             drag_handle_width: None,
+            gesture: DragGesture::new(DndActivation::default()),
             selected_ix: None,
             dragged_id: None,
             dragged_ix: None,
             drop_preview: None,
+            drop_strategy: DndDropStrategy::ClosestEdge,
             drag_start_mouse_position: None,
             render_item: Rc::new(|_, _, _, _, _| ListItem::new("dnd-tree-empty")),
         }
@@ -282,6 +291,12 @@ impl DndTreeState {
         self
     }
 
+    /// Set how a press turns into a drag; see [`DndActivation`] for touch and pen input.
+    pub fn activation(self, activation: DndActivation) -> Self {
+        self.gesture.set_activation(activation);
+        self
+    }
+
     /// Configure the drop indicator line style (color/thickness/caps).
     pub fn indicator_style(mut self, style: DndTreeIndicatorStyle) -> Self {
         self.indicator_style = style;
@@ -306,6 +321,16 @@ impl DndTreeState {
         self
     }
 
+    /// Set how the pointer position over a row picks before, after or inside.
+    ///
+    /// Defaults to [`DndDropStrategy::ClosestEdge`]; nesting then comes from dragging
+    /// horizontally. [`DndDropStrategy::Threshold`] also lets rows that accept children take
+    /// the item when it is dropped on their middle band.
+    pub fn drop_strategy(mut self, strategy: DndDropStrategy) -> Self {
+        self.drop_strategy = strategy;
+        self
+    }
+
     pub fn items(mut self, items: impl Into<Vec<DndTreeItem>>) -> Self {
         self.root_items = items.into();
         self.rebuild_entries();
@@ -513,6 +538,9 @@ impl DndTreeState {
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) {
+        if self.gesture.take_suppressed_click() {
+            return;
+        }
         self.selected_ix = Some(ix);
         self.toggle_expand(ix);
         cx.notify();
@@ -611,15 +639,36 @@ impl DndTreeState {
             }
 
             let y_in_row = (y_in_content - item_height * hovered_ix).max(px(0.));
-            let drop_target = if y_in_row < item_height / 2.0 {
-                DndTreeDropTarget::Before
-            } else {
-                DndTreeDropTarget::After
+            let hovered = &self.entries[hovered_ix];
+            let position = self.drop_strategy.resolve(&DndDropProbe {
+                offset_y: y_in_row,
+                row_height: item_height,
+                from_ix: dragged_ix,
+                target_ix: hovered_ix,
+                can_nest: hovered.can_accept_children(),
+            })?;
+            let drop_target = match position {
+                DndDropPosition::Before => DndTreeDropTarget::Before,
+                DndDropPosition::After => DndTreeDropTarget::After,
+                DndDropPosition::Inside => {
+                    if !hovered.can_accept_children() {
+                        return None;
+                    }
+                    let mut preview =
+                        self.compute_drop_preview_for_gap(hovered_ix + 1, hovered.depth() + 1)?;
+                    if preview.highlight_ix != Some(hovered_ix) {
+                        return None;
+                    }
+                    preview.highlight_target = Some(DndTreeDropTarget::Inside);
+                    preview.line_y = None;
+                    preview.line_x = None;
+                    return Some(preview);
+                }
             };
 
             let gap_index = match drop_target {
                 DndTreeDropTarget::Before => hovered_ix,
-                DndTreeDropTarget::After => hovered_ix.saturating_add(1),
+                _ => hovered_ix.saturating_add(1),
             };
 
             let mut preview = self.compute_drop_preview_for_gap(gap_index, desired_depth)?;
@@ -750,25 +799,7 @@ impl DndTreeState {
     }
 
     fn row_height(&self) -> Pixels {
-        const FALLBACK: Pixels = px(28.);
-        let item_count = self.entries.len();
-        if item_count == 0 {
-            return FALLBACK;
-        }
-
-        let Some(last_size) = self.scroll_handle.0.borrow().last_item_size else {
-            return FALLBACK;
-        };
-
-        // `UniformListScrollHandle::last_item_size.item` is the viewport size, not the row height.
-        // Row height is uniform and can be derived from the total content height.
-        let height = last_size.contents.height * (1.0 / item_count as f32);
-        let height_f32: f32 = height.into();
-        if height_f32.is_finite() && height_f32 > 0.0 {
-            height
-        } else {
-            FALLBACK
-        }
+        uniform_row_height(&self.scroll_handle, self.entries.len())
     }
 
     fn compute_drop_preview_for_gap(
@@ -1025,6 +1056,8 @@ impl Render for DndTreeState {
         let drop_preview = self.drop_preview.clone();
         let indicator_style = self.indicator_style;
         let drag_handle_width = self.drag_handle_width;
+        let gesture = self.gesture.clone();
+        let gesture_enabled = gesture.is_enabled();
 
         let line = drop_preview
             .as_ref()
@@ -1137,6 +1170,9 @@ impl Render for DndTreeState {
                                         this.on_drop_on_row(drag, ix, window, cx);
                                     },
                                 ))
+                                .when(gesture_enabled, |this| {
+                                    this.on_mouse_down(MouseButton::Left, gesture.on_press())
+                                })
                                 .when(!is_disabled, |this| {
                                     this.on_click(cx.listener(
                                         move |this, click_event, window, cx| {
@@ -1205,6 +1241,12 @@ impl Render for DndTreeState {
                         &self.scroll_handle,
                     )),
             )
+            .when(gesture_enabled, |this| {
+                this.child(
+                    self.gesture
+                        .tracker(self.scroll_handle.0.borrow().base_handle.clone()),
+                )
+            })
             .when_some(line, |this, line| this.child(line))
     }
 }
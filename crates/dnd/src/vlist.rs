@@ -2,9 +2,10 @@ use std::{ops::Range, rc::Rc};
 
 use gpui::{
     App, AppContext as _, Context, CursorStyle, ElementId, Entity, EntityId, FocusHandle,
-    InteractiveElement as _, IntoElement, ListSizingBehavior, Modifiers, ParentElement as _,
-    Pixels, Render, RenderOnce, SharedString, Size, StatefulInteractiveElement as _,
-    StyleRefinement, Styled, Window, div, prelude::FluentBuilder as _, px, size,
+    InteractiveElement as _, IntoElement, ListSizingBehavior, Modifiers, MouseButton,
+    ParentElement as _, Pixels, Render, RenderOnce, SharedString, Size,
+    StatefulInteractiveElement as _, StyleRefinement, Styled, Window, div,
+    prelude::FluentBuilder as _, px, size,
 };
 use gpui_component::list::ListItem;
 use gpui_component::scroll::{Scrollbar, ScrollbarState};
@@ -13,6 +14,7 @@ use gpui_component::{ActiveTheme as _, StyledExt as _, VirtualListScrollHandle,
 use crate::common::{
     DragGhost, reorder_to_index_for_drop_after_last, reorder_to_index_for_drop_on_row,
 };
+use crate::gesture::{DndActivation, DragGesture};
 
 const CONTEXT: &str = "DndVList";
 const DEFAULT_ROW_HEIGHT: Pixels = px(28.);
@@ -125,6 +127,7 @@ pub struct DndVListState<T> {
     scrollbar_state: ScrollbarState,
     scroll_handle: VirtualListScrollHandle,
     drag_handle_width: Option<Pixels>,
+    gesture: DragGesture,
     selected_ix: Option<usize>,
     dragged_id: Option<SharedString>,
     dragged_ix: Option<usize>,
@@ -142,6 +145,7 @@ impl<T: 'static> DndVListState<T> {
             scrollbar_state: ScrollbarState::default(),
             scroll_handle: VirtualListScrollHandle::new(),
             drag_handle_width: Some(px(32.)),
+            gesture: DragGesture::new(DndActivation::default()),
             selected_ix: None,
             dragged_id: None,
             dragged_ix: None,
@@ -170,6 +174,12 @@ impl<T: 'static> DndVListState<T> {
         self
     }
 
+    /// Set how a press turns into a drag; see [`DndActivation`] for touch and pen input.
+    pub fn activation(self, activation: DndActivation) -> Self {
+        self.gesture.set_activation(activation);
+        self
+    }
+
     pub fn set_items(&mut self, items: impl Into<Vec<DndVListItem<T>>>, cx: &mut Context<Self>) {
         self.items = items.into();
         self.rebuild_item_sizes();
@@ -252,6 +262,9 @@ impl<T: 'static> DndVListState<T> {
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) {
+        if self.gesture.take_suppressed_click() {
+            return;
+        }
         self.selected_ix = Some(ix);
         cx.notify();
     }
@@ -386,6 +399,8 @@ impl<T: 'static> Render for DndVListState<T> {
         let state_entity = cx.entity();
         let dragged_id = self.dragged_id.clone();
         let drag_handle_width = self.drag_handle_width;
+        let gesture = self.gesture.clone();
+        let gesture_enabled = gesture.is_enabled();
         let list_id = cx.entity_id();
         let item_sizes = self.item_sizes.clone();
         let scroll_handle = self.scroll_handle.clone();
@@ -467,6 +482,9 @@ impl<T: 'static> Render for DndVListState<T> {
                                                 this.on_drop_on_row(drag, ix, window, cx);
                                             },
                                         ))
+                                        .when(gesture_enabled, |this| {
+                                            this.on_mouse_down(MouseButton::Left, gesture.on_press())
+                                        })
                                         .when(!is_disabled, |this| {
                                             this.on_click(cx.listener(
                                                 move |this, click_event, window, cx| {
@@ -544,6 +562,9 @@ impl<T: 'static> Render for DndVListState<T> {
                         &self.scroll_handle,
                     )),
             )
+            .when(gesture_enabled, |this| {
+                this.child(self.gesture.tracker((*self.scroll_handle).clone()))
+            })
     }
 }
 
@@ -2,15 +2,17 @@ use std::{cell::RefCell, ops::Range, rc::Rc};
 
 use gpui::{
     App, AppContext as _, Context, CursorStyle, ElementId, Entity, EntityId, FocusHandle, Hsla,
-    InteractiveElement as _, IntoElement, ListSizingBehavior, ParentElement as _, Pixels, Point,
-    Render, RenderOnce, ScrollStrategy, SharedString, Size, StatefulInteractiveElement as _,
-    StyleRefinement, Styled, Window, div, prelude::FluentBuilder as _, px, size,
+    InteractiveElement as _, IntoElement, ListSizingBehavior, MouseButton, ParentElement as _,
+    Pixels, Point, Render, RenderOnce, ScrollStrategy, SharedString, Size,
+    StatefulInteractiveElement as _, StyleRefinement, Styled, Window, div,
+    prelude::FluentBuilder as _, px, size,
 };
 use gpui_component::list::ListItem;
 use gpui_component::scroll::{Scrollbar, ScrollbarState};
 use gpui_component::{ActiveTheme as _, StyledExt as _, VirtualListScrollHandle, v_virtual_list};
 
 use crate::common::DragGhost;
+use crate::gesture::{DndActivation, DragGesture};
 
 const CONTEXT: &str = "DndVTree";
 const HORIZONTAL_GESTURE_THRESHOLD_PX: f32 = 24.0;
@@ -250,6 +252,7 @@ pub struct DndVTreeState {
     scrollbar_state: ScrollbarState,
     scroll_handle: VirtualListScrollHandle,
     drag_handle_width: Option<Pixels>,
+    gesture: DragGesture,
     selected_ix: Option<usize>,
     dragged_id: Option<SharedString>,
     dragged_ix: Option<usize>,
@@ -274,6 +277,7 @@ impl DndVTreeState {
             scrollbar_state: ScrollbarState::default(),
             scroll_handle: VirtualListScrollHandle::new(),
             drag_handle_width: None,
+            gesture: DragGesture::new(DndActivation::default()),
             selected_ix: None,
             dragged_id: None,
             dragged_ix: None,
@@ -313,6 +317,12 @@ impl DndVTreeState {
         self
     }
 
+    /// Set how a press turns into a drag; see [`DndActivation`] for touch and pen input.
+    pub fn activation(self, activation: DndActivation) -> Self {
+        self.gesture.set_activation(activation);
+        self
+    }
+
     /// Configure the drop indicator line style (color/thickness/caps).
     pub fn indicator_style(mut self, style: DndVTreeIndicatorStyle) -> Self {
         self.indicator_style = style;
@@ -614,6 +624,9 @@ impl DndVTreeState {
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) {
+        if self.gesture.take_suppressed_click() {
+            return;
+        }
         self.selected_ix = Some(ix);
         self.toggle_expand(ix);
         cx.notify();
@@ -1272,6 +1285,8 @@ impl Render for DndVTreeState {
         let drop_preview = self.drop_preview.clone();
         let indicator_style = self.indicator_style;
         let drag_handle_width = self.drag_handle_width;
+        let gesture = self.gesture.clone();
+        let gesture_enabled = gesture.is_enabled();
         let entry_sizes = self.entry_sizes.clone();
         let scroll_handle = self.scroll_handle.clone();
 
@@ -1404,6 +1419,9 @@ impl Render for DndVTreeState {
                                             this.on_drop_on_row(drag, ix, window, cx);
                                         },
                                     ))
+                                    .when(gesture_enabled, |this| {
+                                        this.on_mouse_down(MouseButton::Left, gesture.on_press())
+                                    })
                                     .when(!is_disabled, |this| {
                                         this.on_click(cx.listener(
                                             move |this, click_event, window, cx| {
@@ -1467,6 +1485,9 @@ impl Render for DndVTreeState {
                     .w(px(12.))
                     .child(Scrollbar::uniform_scroll(&self.scrollbar_state, &self.scroll_handle)),
             )
+            .when(gesture_enabled, |this| {
+                this.child(self.gesture.tracker((*self.scroll_handle).clone()))
+            })
             .when_some(line, |this, line| this.child(line))
     }
 }
@@ -1,15 +1,148 @@
 use std::borrow::Cow;
+use std::path::{Path, PathBuf};
 
 use gpui::{AssetSource, Result, SharedString};
 
+/// Priority of the icon set bundled with this crate.
+const BUILTIN_PRIORITY: i32 = 0;
+/// Priority used by [`ExtrasAssetSource::with_icons`]; above the bundled icons.
+const DEFAULT_PRIORITY: i32 = 10;
+
 /// Asset source for `gpui-manos-components`.
 ///
-/// Includes the SVG icon set used by `plate_toolbar` and story demos (e.g. DnD tree).
-pub struct ExtrasAssetSource;
+/// Includes the SVG icon set used by `plate_toolbar` and story demos (e.g. DnD tree). Apps
+/// can layer their own icon packs on top with [`ExtrasAssetSource::with_icons`], so custom
+/// `IconNamed` enums resolve through the same source.
+pub struct ExtrasAssetSource {
+    /// Highest priority first; among equal priorities the most recently added comes first.
+    layers: Vec<AssetLayer>,
+}
+
+/// Where a layer of assets comes from.
+pub enum IconSource {
+    /// A directory on disk; asset paths such as `icons/foo.svg` are resolved inside it.
+    Directory(PathBuf),
+    /// Assets compiled into the binary, keyed by asset path.
+    Embedded(&'static [(&'static str, &'static [u8])]),
+}
+
+impl From<PathBuf> for IconSource {
+    fn from(path: PathBuf) -> Self {
+        Self::Directory(path)
+    }
+}
+
+impl From<&Path> for IconSource {
+    fn from(path: &Path) -> Self {
+        Self::Directory(path.to_path_buf())
+    }
+}
+
+impl From<&'static [(&'static str, &'static [u8])]> for IconSource {
+    fn from(assets: &'static [(&'static str, &'static [u8])]) -> Self {
+        Self::Embedded(assets)
+    }
+}
+
+struct AssetLayer {
+    priority: i32,
+    source: IconSource,
+}
 
 impl ExtrasAssetSource {
     pub fn new() -> Self {
-        Self
+        Self {
+            layers: vec![AssetLayer {
+                priority: BUILTIN_PRIORITY,
+                source: IconSource::Embedded(ASSETS),
+            }],
+        }
+    }
+
+    /// Adds an icon pack that overrides the bundled icons and earlier packs of the same name.
+    pub fn with_icons(self, source: impl Into<IconSource>) -> Self {
+        self.with_icons_priority(source, DEFAULT_PRIORITY)
+    }
+
+    /// Adds an icon pack at an explicit priority; the bundled icons sit at priority 0.
+    pub fn with_icons_priority(mut self, source: impl Into<IconSource>, priority: i32) -> Self {
+        let index = self
+            .layers
+            .iter()
+            .position(|layer| layer.priority <= priority)
+            .unwrap_or(self.layers.len());
+        self.layers.insert(
+            index,
+            AssetLayer {
+                priority,
+                source: source.into(),
+            },
+        );
+        self
+    }
+}
+
+impl Default for ExtrasAssetSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IconSource {
+    fn load(&self, path: &str) -> Result<Option<Cow<'static, [u8]>>> {
+        match self {
+            Self::Directory(root) => {
+                let file = root.join(path);
+                if !file.is_file() {
+                    return Ok(None);
+                }
+                Ok(Some(Cow::Owned(std::fs::read(file)?)))
+            }
+            Self::Embedded(assets) => Ok(assets
+                .iter()
+                .find(|(asset_path, _)| *asset_path == path)
+                .map(|(_, bytes)| Cow::Borrowed(*bytes))),
+        }
+    }
+
+    fn list(&self, path: &str, children: &mut Vec<SharedString>) -> Result<()> {
+        let mut push = |name: &str| {
+            if !children.iter().any(|item| item.as_ref() == name) {
+                children.push(SharedString::from(name.to_string()));
+            }
+        };
+
+        match self {
+            Self::Directory(root) => {
+                let dir = root.join(path);
+                if !dir.is_dir() {
+                    return Ok(());
+                }
+                for entry in std::fs::read_dir(dir)? {
+                    if let Some(name) = entry?.file_name().to_str() {
+                        push(name);
+                    }
+                }
+            }
+            Self::Embedded(assets) => {
+                let prefix = if path.is_empty() {
+                    String::new()
+                } else {
+                    format!("{path}/")
+                };
+
+                for (asset_path, _) in assets.iter() {
+                    let Some(rest) = asset_path.strip_prefix(&prefix) else {
+                        continue;
+                    };
+                    match rest.split_once('/') {
+                        Some((first, _)) => push(first),
+                        None => push(rest),
+                    }
+                }
+            }
+        }
+        Ok(())
     }
 }
 
@@ -123,6 +256,14 @@ const ASSETS: &[(&str, &[u8])] = &[
         "icons/pen-line.svg",
         include_bytes!("../assets/icons/pen-line.svg"),
     ),
+    (
+        "icons/pilcrow-left.svg",
+        include_bytes!("../assets/icons/pilcrow-left.svg"),
+    ),
+    (
+        "icons/pilcrow-right.svg",
+        include_bytes!("../assets/icons/pilcrow-right.svg"),
+    ),
     ("icons/plus.svg", include_bytes!("../assets/icons/plus.svg")),
     (
         "icons/redo-2.svg",
@@ -172,9 +313,9 @@ const ASSETS: &[(&str, &[u8])] = &[
 
 impl AssetSource for ExtrasAssetSource {
     fn load(&self, path: &str) -> Result<Option<Cow<'static, [u8]>>> {
-        for (asset_path, bytes) in ASSETS {
-            if *asset_path == path {
-                return Ok(Some(Cow::Borrowed(*bytes)));
+        for layer in &self.layers {
+            if let Some(bytes) = layer.source.load(path)? {
+                return Ok(Some(bytes));
             }
         }
 
@@ -183,27 +324,9 @@ impl AssetSource for ExtrasAssetSource {
 
     fn list(&self, path: &str) -> Result<Vec<SharedString>> {
         let path = path.trim_matches('/');
-        let prefix = if path.is_empty() {
-            String::new()
-        } else {
-            format!("{path}/")
-        };
-
         let mut children: Vec<SharedString> = Vec::new();
-        for (asset_path, _) in ASSETS {
-            if !asset_path.starts_with(&prefix) {
-                continue;
-            }
-
-            let rest = &asset_path[prefix.len()..];
-            let Some((first, _)) = rest.split_once('/') else {
-                children.push(rest.into());
-                continue;
-            };
-
-            if !children.iter().any(|item| item.as_ref() == first) {
-                children.push(first.into());
-            }
+        for layer in &self.layers {
+            layer.source.list(path, &mut children)?;
         }
 
         Ok(children)
@@ -0,0 +1,283 @@
+use std::rc::Rc;
+
+use gpui::InteractiveElement as _;
+use gpui::StatefulInteractiveElement as _;
+use gpui::prelude::FluentBuilder as _;
+use gpui::{
+    AnyElement, App, ElementId, IntoElement, MouseButton, ParentElement, RenderOnce, SharedString,
+    StyleRefinement, Styled, Window, div, px,
+};
+use gpui_component::button::{Button, ButtonVariants as _};
+use gpui_component::popover::Popover;
+use gpui_component::{
+    ActiveTheme as _, Icon, IconName, Selectable as _, Sizable as _, StyledExt as _,
+};
+
+type NavigateHandler = Rc<dyn Fn(&mut Window, &mut App)>;
+
+#[derive(Clone)]
+struct BreadcrumbMenuItem {
+    label: SharedString,
+    selected: bool,
+    on_click: NavigateHandler,
+}
+
+/// One segment of a [`Breadcrumb`].
+///
+/// A segment navigates when clicked and may carry a dropdown of places next to it, such as
+/// the siblings of a directory. A segment with a dropdown but no click handler opens the
+/// dropdown when clicked.
+#[derive(Clone)]
+pub struct BreadcrumbItem {
+    label: SharedString,
+    icon: Option<IconName>,
+    on_click: Option<NavigateHandler>,
+    menu: Vec<BreadcrumbMenuItem>,
+}
+
+impl BreadcrumbItem {
+    pub fn new(label: impl Into<SharedString>) -> Self {
+        Self {
+            label: label.into(),
+            icon: None,
+            on_click: None,
+            menu: Vec::new(),
+        }
+    }
+
+    pub fn icon(mut self, icon: IconName) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
+    pub fn on_click(mut self, on_click: impl Fn(&mut Window, &mut App) + 'static) -> Self {
+        self.on_click = Some(Rc::new(on_click));
+        self
+    }
+
+    /// Adds an entry to the segment's dropdown.
+    pub fn menu_item(
+        self,
+        label: impl Into<SharedString>,
+        on_click: impl Fn(&mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.menu_item_selected(label, false, on_click)
+    }
+
+    /// Adds an entry to the segment's dropdown, highlighted when `selected`.
+    pub fn menu_item_selected(
+        mut self,
+        label: impl Into<SharedString>,
+        selected: bool,
+        on_click: impl Fn(&mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.menu.push(BreadcrumbMenuItem {
+            label: label.into(),
+            selected,
+            on_click: Rc::new(on_click),
+        });
+        self
+    }
+}
+
+/// A path shown as clickable segments separated by chevrons.
+///
+/// When there are more segments than `max_items`, the middle ones collapse into an ellipsis
+/// whose dropdown lists them; the first segment and the last few stay visible.
+#[derive(IntoElement)]
+pub struct Breadcrumb {
+    id: ElementId,
+    style: StyleRefinement,
+    items: Vec<BreadcrumbItem>,
+    max_items: Option<usize>,
+}
+
+impl Breadcrumb {
+    pub fn new(id: impl Into<ElementId>) -> Self {
+        Self {
+            id: id.into(),
+            style: StyleRefinement::default(),
+            items: Vec::new(),
+            max_items: None,
+        }
+    }
+
+    pub fn child(mut self, item: BreadcrumbItem) -> Self {
+        self.items.push(item);
+        self
+    }
+
+    pub fn children(mut self, items: impl IntoIterator<Item = BreadcrumbItem>) -> Self {
+        self.items.extend(items);
+        self
+    }
+
+    /// Collapses the middle segments once there are more than `max_items` (at least 2).
+    pub fn max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items.max(2));
+        self
+    }
+}
+
+impl Styled for Breadcrumb {
+    fn style(&mut self) -> &mut StyleRefinement {
+        &mut self.style
+    }
+}
+
+/// Segments to show, with the indices hidden behind the ellipsis inserted after the first.
+fn visible_layout(len: usize, max_items: Option<usize>) -> (Vec<usize>, Vec<usize>) {
+    match max_items {
+        Some(max_items) if len > max_items => {
+            let tail_start = len - (max_items - 1);
+            let mut visible = vec![0];
+            visible.extend(tail_start..len);
+            (visible, (1..tail_start).collect())
+        }
+        _ => ((0..len).collect(), Vec::new()),
+    }
+}
+
+fn menu_popover(
+    id: impl Into<ElementId>,
+    trigger: Button,
+    items: Vec<BreadcrumbMenuItem>,
+) -> AnyElement {
+    Popover::new(id)
+        .appearance(false)
+        .trigger(trigger.on_click(|_, _, _| {}))
+        .content(move |_, _window, cx| {
+            let theme = cx.theme();
+            let popover = cx.entity();
+
+            div()
+                .id("breadcrumb-menu")
+                .p(px(4.))
+                .min_w(px(160.))
+                .max_w(px(420.))
+                .max_h(px(360.))
+                .overflow_y_scroll()
+                .bg(theme.popover)
+                .border_1()
+                .border_color(theme.border)
+                .rounded(theme.radius)
+                .shadow_md()
+                .flex()
+                .flex_col()
+                .children(items.iter().enumerate().map(|(ix, item)| {
+                    let on_click = item.on_click.clone();
+                    let popover = popover.clone();
+                    div()
+                        .id(ix)
+                        .flex()
+                        .items_center()
+                        .h(px(28.))
+                        .px(px(8.))
+                        .rounded(px(4.))
+                        .text_sm()
+                        .cursor_pointer()
+                        .when(item.selected, |this| {
+                            this.bg(theme.accent).text_color(theme.accent_foreground)
+                        })
+                        .when(!item.selected, |this| {
+                            this.text_color(theme.popover_foreground)
+                                .hover(|this| this.bg(theme.accent.alpha(0.4)))
+                        })
+                        .on_mouse_down(MouseButton::Left, move |_, window, cx| {
+                            window.prevent_default();
+                            popover.update(cx, |state, cx| state.dismiss(window, cx));
+                            on_click(window, cx);
+                        })
+                        .child(div().truncate().child(item.label.clone()))
+                }))
+        })
+        .into_any_element()
+}
+
+fn render_item(ix: usize, item: BreadcrumbItem, current: bool) -> AnyElement {
+    let button = Button::new(("breadcrumb-item", ix))
+        .label(item.label)
+        .ghost()
+        .xsmall()
+        .selected(current)
+        .when_some(item.icon, |this, icon| this.icon(icon));
+
+    match (item.on_click, item.menu.is_empty()) {
+        (Some(on_click), true) => button
+            .on_click(move |_, window, cx| on_click(window, cx))
+            .into_any_element(),
+        (Some(on_click), false) => div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .child(button.on_click(move |_, window, cx| on_click(window, cx)))
+            .child(menu_popover(
+                ("breadcrumb-menu", ix),
+                Button::new(("breadcrumb-menu-trigger", ix))
+                    .icon(IconName::ChevronDown)
+                    .ghost()
+                    .xsmall(),
+                item.menu,
+            ))
+            .into_any_element(),
+        (None, false) => menu_popover(("breadcrumb-menu", ix), button, item.menu),
+        (None, true) => button.into_any_element(),
+    }
+}
+
+impl RenderOnce for Breadcrumb {
+    fn render(self, _window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let muted = cx.theme().muted_foreground;
+        let last = self.items.len().saturating_sub(1);
+        let (visible, collapsed) = visible_layout(self.items.len(), self.max_items);
+
+        let collapsed_menu: Vec<BreadcrumbMenuItem> = collapsed
+            .iter()
+            .filter_map(|&ix| {
+                let item = &self.items[ix];
+                Some(BreadcrumbMenuItem {
+                    label: item.label.clone(),
+                    selected: false,
+                    on_click: item.on_click.clone()?,
+                })
+            })
+            .collect();
+
+        let separator = || {
+            Icon::new(IconName::ChevronRight)
+                .size_3()
+                .flex_none()
+                .text_color(muted)
+        };
+
+        let mut segments: Vec<AnyElement> = Vec::new();
+        for (position, ix) in visible.into_iter().enumerate() {
+            if position > 0 {
+                segments.push(separator().into_any_element());
+            }
+            segments.push(render_item(ix, self.items[ix].clone(), ix == last));
+
+            if position == 0 && !collapsed.is_empty() {
+                segments.push(separator().into_any_element());
+                let ellipsis = Button::new("breadcrumb-ellipsis")
+                    .label("…")
+                    .ghost()
+                    .xsmall();
+                segments.push(if collapsed_menu.is_empty() {
+                    ellipsis.disabled(true).into_any_element()
+                } else {
+                    menu_popover("breadcrumb-collapsed", ellipsis, collapsed_menu.clone())
+                });
+            }
+        }
+
+        div()
+            .id(self.id)
+            .flex()
+            .flex_row()
+            .items_center()
+            .min_w(px(0.))
+            .refine_style(&self.style)
+            .children(segments)
+    }
+}
@@ -0,0 +1,513 @@
+use std::rc::Rc;
+use std::time::Duration;
+
+use gpui::InteractiveElement as _;
+use gpui::StatefulInteractiveElement as _;
+use gpui::prelude::FluentBuilder as _;
+use gpui::{
+    AnyElement, App, AppContext as _, Context, Corner, Entity, IntoElement, KeyDownEvent,
+    MouseButton, ParentElement, Pixels, Render, ScrollStrategy, SharedString, Styled, Subscription,
+    Task, UniformListScrollHandle, Window, anchored, canvas, deferred, div, px, uniform_list,
+};
+use gpui_component::button::{Button, ButtonVariants as _};
+use gpui_component::input::{Input, InputEvent, InputState};
+use gpui_component::{ActiveTheme as _, Icon, IconName, Sizable as _};
+
+const ROW_HEIGHT: f32 = 28.;
+const MAX_LIST_HEIGHT: f32 = 280.;
+/// Typing pauses this long before the loader is asked for new items.
+const LOAD_DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// One choice in a [`Combobox`]. Items are told apart by `value`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComboboxItem {
+    pub value: SharedString,
+    pub label: SharedString,
+    /// Secondary text shown after the label, such as a commit hash next to a branch.
+    pub detail: Option<SharedString>,
+}
+
+impl ComboboxItem {
+    pub fn new(value: impl Into<SharedString>, label: impl Into<SharedString>) -> Self {
+        Self {
+            value: value.into(),
+            label: label.into(),
+            detail: None,
+        }
+    }
+
+    pub fn detail(mut self, detail: impl Into<SharedString>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Ranks the item against a lowercase query: `Some(0)` when the label starts with it,
+    /// `Some(1)` when every term occurs in the label, value or detail, `None` otherwise.
+    fn rank(&self, query: &str) -> Option<u8> {
+        if query.is_empty() {
+            return Some(0);
+        }
+        let label = self.label.to_lowercase();
+        if label.starts_with(query) {
+            return Some(0);
+        }
+        let value = self.value.to_lowercase();
+        let detail = self
+            .detail
+            .as_ref()
+            .map(|detail| detail.to_lowercase())
+            .unwrap_or_default();
+        query
+            .split_whitespace()
+            .all(|term| label.contains(term) || value.contains(term) || detail.contains(term))
+            .then_some(1)
+    }
+}
+
+type ComboboxLoader = Rc<dyn Fn(&str, &mut App) -> Task<Result<Vec<ComboboxItem>, SharedString>>>;
+type ComboboxChangeHandler = Rc<dyn Fn(&[ComboboxItem], &mut Window, &mut App)>;
+
+/// A text field that filters a list of choices as the user types.
+///
+/// Choices come either from a fixed list, filtered here, or from a loader that is called
+/// with the query and returns the matching items asynchronously. With `multiple`, picked
+/// items are shown as removable chips in front of the query; otherwise the one picked item
+/// is. Arrow keys move through the list, Enter picks, Esc closes it.
+pub struct Combobox {
+    input: Entity<InputState>,
+    items: Vec<ComboboxItem>,
+    loader: Option<ComboboxLoader>,
+    /// Dropping the task cancels a load that a newer query made obsolete.
+    load_task: Option<Task<()>>,
+    loading: bool,
+    load_error: Option<SharedString>,
+    /// Items matching the query, rebuilt when either changes.
+    matches: Vec<ComboboxItem>,
+    multiple: bool,
+    selected: Vec<ComboboxItem>,
+    highlighted: usize,
+    open: bool,
+    field_width: Option<Pixels>,
+    scroll_handle: UniformListScrollHandle,
+    empty_text: SharedString,
+    loading_text: SharedString,
+    on_change: Option<ComboboxChangeHandler>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl Combobox {
+    pub fn new(
+        placeholder: impl Into<SharedString>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let placeholder = placeholder.into();
+        let input = cx.new(|cx| InputState::new(window, cx).placeholder(placeholder));
+        let subscriptions = vec![cx.subscribe_in(
+            &input,
+            window,
+            |this, _, event: &InputEvent, _window, cx| match event {
+                InputEvent::Change { .. } => {
+                    this.open = true;
+                    this.highlighted = 0;
+                    this.refresh_matches(cx);
+                }
+                InputEvent::Focus => this.set_open(true, cx),
+                InputEvent::Blur => this.set_open(false, cx),
+                _ => {}
+            },
+        )];
+
+        Self {
+            input,
+            items: Vec::new(),
+            loader: None,
+            load_task: None,
+            loading: false,
+            load_error: None,
+            matches: Vec::new(),
+            multiple: false,
+            selected: Vec::new(),
+            highlighted: 0,
+            open: false,
+            field_width: None,
+            scroll_handle: UniformListScrollHandle::default(),
+            empty_text: "No matches".into(),
+            loading_text: "Loading…".into(),
+            on_change: None,
+            _subscriptions: subscriptions,
+        }
+    }
+
+    /// Fixed choices, filtered by the query.
+    pub fn items(mut self, items: impl IntoIterator<Item = ComboboxItem>) -> Self {
+        self.items = items.into_iter().collect();
+        self.matches = self.items.clone();
+        self
+    }
+
+    /// Loads the choices for a query instead of filtering fixed ones. The returned items are
+    /// shown as they are, so the loader does its own matching.
+    pub fn loader(
+        mut self,
+        loader: impl Fn(&str, &mut App) -> Task<Result<Vec<ComboboxItem>, SharedString>> + 'static,
+    ) -> Self {
+        self.loader = Some(Rc::new(loader));
+        self
+    }
+
+    /// Lets several items be picked; they are shown as chips.
+    pub fn multiple(mut self, multiple: bool) -> Self {
+        self.multiple = multiple;
+        self
+    }
+
+    /// Shown in the list when nothing matches the query.
+    pub fn empty_text(mut self, text: impl Into<SharedString>) -> Self {
+        self.empty_text = text.into();
+        self
+    }
+
+    /// Shown in the list while the loader runs.
+    pub fn loading_text(mut self, text: impl Into<SharedString>) -> Self {
+        self.loading_text = text.into();
+        self
+    }
+
+    /// Called with every picked item whenever the selection changes.
+    pub fn on_change(
+        mut self,
+        on_change: impl Fn(&[ComboboxItem], &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.on_change = Some(Rc::new(on_change));
+        self
+    }
+
+    pub fn focus(&self, window: &mut Window, cx: &mut App) {
+        self.input.update(cx, |state, cx| state.focus(window, cx));
+    }
+
+    pub fn query(&self, cx: &App) -> String {
+        self.input.read(cx).value().to_string()
+    }
+
+    pub fn selected(&self) -> &[ComboboxItem] {
+        &self.selected
+    }
+
+    /// Replaces the fixed choices, keeping the query.
+    pub fn set_items(&mut self, items: Vec<ComboboxItem>, cx: &mut Context<Self>) {
+        self.items = items;
+        self.refresh_matches(cx);
+    }
+
+    /// Replaces the selection without calling `on_change`.
+    pub fn set_selected(&mut self, selected: Vec<ComboboxItem>, cx: &mut Context<Self>) {
+        self.selected = selected;
+        if !self.multiple {
+            self.selected.truncate(1);
+        }
+        cx.notify();
+    }
+
+    fn set_open(&mut self, open: bool, cx: &mut Context<Self>) {
+        if self.open == open {
+            return;
+        }
+        self.open = open;
+        if open {
+            self.highlighted = 0;
+            self.refresh_matches(cx);
+        }
+        cx.notify();
+    }
+
+    fn refresh_matches(&mut self, cx: &mut Context<Self>) {
+        let query = self.query(cx).trim().to_lowercase();
+        match self.loader.clone() {
+            Some(loader) => self.load(loader, query, cx),
+            None => {
+                let mut ranked: Vec<(u8, &ComboboxItem)> = self
+                    .items
+                    .iter()
+                    .filter_map(|item| Some((item.rank(&query)?, item)))
+                    .collect();
+                // Stable, so items of the same rank keep their order.
+                ranked.sort_by_key(|(rank, _)| *rank);
+                self.matches = ranked.into_iter().map(|(_, item)| item.clone()).collect();
+            }
+        }
+        cx.notify();
+    }
+
+    fn load(&mut self, loader: ComboboxLoader, query: String, cx: &mut Context<Self>) {
+        self.loading = true;
+        self.load_error = None;
+        self.load_task = Some(cx.spawn(async move |this, cx| {
+            cx.background_executor().timer(LOAD_DEBOUNCE).await;
+            let Ok(task) = this.update(cx, |_, cx| loader(&query, cx)) else {
+                return;
+            };
+            let result = task.await;
+            this.update(cx, |this, cx| {
+                this.loading = false;
+                match result {
+                    Ok(items) => this.matches = items,
+                    Err(err) => {
+                        this.matches.clear();
+                        this.load_error = Some(err);
+                    }
+                }
+                this.highlighted = this.highlighted.min(this.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .ok();
+        }));
+    }
+
+    fn is_selected(&self, item: &ComboboxItem) -> bool {
+        self.selected
+            .iter()
+            .any(|selected| selected.value == item.value)
+    }
+
+    fn pick(&mut self, ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(item) = self.matches.get(ix).cloned() else {
+            return;
+        };
+        if self.multiple {
+            if self.is_selected(&item) {
+                self.selected
+                    .retain(|selected| selected.value != item.value);
+            } else {
+                self.selected.push(item);
+            }
+        } else {
+            self.selected = vec![item];
+            self.open = false;
+        }
+        self.highlighted = ix;
+        self.input
+            .update(cx, |state, cx| state.set_value("", window, cx));
+        self.notify_change(window, cx);
+    }
+
+    fn remove(&mut self, value: &SharedString, window: &mut Window, cx: &mut Context<Self>) {
+        self.selected.retain(|selected| selected.value != *value);
+        self.notify_change(window, cx);
+    }
+
+    fn notify_change(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(on_change) = self.on_change.clone() {
+            on_change(&self.selected, window, cx);
+        }
+        cx.notify();
+    }
+
+    fn on_key_down(&mut self, event: &KeyDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+        let last = self.matches.len().saturating_sub(1);
+        match event.keystroke.key.as_str() {
+            "down" if !self.open => self.set_open(true, cx),
+            "up" => self.highlighted = self.highlighted.saturating_sub(1),
+            "down" => self.highlighted = (self.highlighted + 1).min(last),
+            "enter" if self.open => self.pick(self.highlighted, window, cx),
+            "escape" if self.open => self.set_open(false, cx),
+            "backspace" if self.query(cx).is_empty() && !self.selected.is_empty() => {
+                if let Some(item) = self.selected.last().map(|item| item.value.clone()) {
+                    self.remove(&item, window, cx);
+                }
+            }
+            _ => return,
+        }
+
+        self.scroll_handle
+            .scroll_to_item(self.highlighted, ScrollStrategy::Center);
+        window.prevent_default();
+        cx.stop_propagation();
+        cx.notify();
+    }
+
+    fn render_chip(&self, ix: usize, item: &ComboboxItem, cx: &Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let value = item.value.clone();
+        div()
+            .flex()
+            .flex_row()
+            .flex_none()
+            .items_center()
+            .gap(px(2.))
+            .pl(px(6.))
+            .rounded(px(4.))
+            .bg(theme.secondary)
+            .text_xs()
+            .text_color(theme.secondary_foreground)
+            .child(item.label.clone())
+            .child(
+                Button::new(("combobox-chip", ix))
+                    .icon(IconName::Close)
+                    .ghost()
+                    .xsmall()
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        this.remove(&value, window, cx);
+                    })),
+            )
+    }
+
+    fn render_list(&self, cx: &Context<Self>) -> AnyElement {
+        let theme = cx.theme();
+        let status = if self.loading && self.matches.is_empty() {
+            Some(self.loading_text.clone())
+        } else if let Some(err) = self.load_error.clone() {
+            Some(err)
+        } else if self.matches.is_empty() {
+            Some(self.empty_text.clone())
+        } else {
+            None
+        };
+        if let Some(status) = status {
+            return div()
+                .px(px(10.))
+                .py(px(8.))
+                .text_sm()
+                .text_color(theme.muted_foreground)
+                .child(status)
+                .into_any_element();
+        }
+
+        let height = px(ROW_HEIGHT * self.matches.len() as f32).min(px(MAX_LIST_HEIGHT));
+        uniform_list(
+            "combobox-items",
+            self.matches.len(),
+            cx.processor(|this, range: std::ops::Range<usize>, _window, cx| {
+                let theme = cx.theme();
+                range
+                    .map(|ix| {
+                        let item = &this.matches[ix];
+                        let highlighted = ix == this.highlighted;
+                        let selected = this.is_selected(item);
+                        div()
+                            .id(ix)
+                            .flex()
+                            .flex_row()
+                            .items_center()
+                            .gap(px(6.))
+                            .h(px(ROW_HEIGHT))
+                            .px(px(8.))
+                            .rounded(px(4.))
+                            .text_sm()
+                            .cursor_pointer()
+                            .when(highlighted, |this| {
+                                this.bg(theme.accent).text_color(theme.accent_foreground)
+                            })
+                            .when(!highlighted, |this| {
+                                this.text_color(theme.popover_foreground)
+                                    .hover(|this| this.bg(theme.accent.alpha(0.4)))
+                            })
+                            .child(div().w(px(14.)).flex_none().when(selected, |this| {
+                                this.child(Icon::new(IconName::Check).size_3p5())
+                            }))
+                            .child(div().flex_1().truncate().child(item.label.clone()))
+                            .when_some(item.detail.clone(), |this, detail| {
+                                this.child(
+                                    div()
+                                        .flex_none()
+                                        .text_xs()
+                                        .text_color(theme.muted_foreground)
+                                        .child(detail),
+                                )
+                            })
+                            // Keep the focus in the input so typing carries on.
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |this, _, window, cx| {
+                                    window.prevent_default();
+                                    this.pick(ix, window, cx);
+                                }),
+                            )
+                    })
+                    .collect()
+            }),
+        )
+        .track_scroll(self.scroll_handle.clone())
+        .h(height)
+        .into_any_element()
+    }
+}
+
+impl Render for Combobox {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let entity = cx.entity();
+
+        let measure = canvas(
+            move |bounds, _, cx| {
+                entity.update(cx, |this, _| this.field_width = Some(bounds.size.width));
+            },
+            |_, _, _, _| {},
+        )
+        .absolute()
+        .size_full();
+
+        let dropdown = self.open.then(|| {
+            div().absolute().top_full().left_0().child(deferred(
+                anchored()
+                    .anchor(Corner::TopLeft)
+                    .snap_to_window_with_margin(px(8.))
+                    .child(
+                        div()
+                            .id("combobox-dropdown")
+                            .occlude()
+                            .mt(px(4.))
+                            .p(px(4.))
+                            .min_w(px(200.))
+                            .when_some(self.field_width, |this, width| this.w(width))
+                            .bg(theme.popover)
+                            .border_1()
+                            .border_color(theme.border)
+                            .rounded(theme.radius)
+                            .shadow_md()
+                            .child(self.render_list(cx)),
+                    ),
+            ))
+        });
+
+        div()
+            .id("combobox")
+            .relative()
+            .flex()
+            .flex_row()
+            .flex_wrap()
+            .items_center()
+            .gap(px(4.))
+            .min_h(px(32.))
+            .px(px(4.))
+            .border_1()
+            .border_color(theme.input)
+            .rounded(theme.radius)
+            .bg(theme.background)
+            .on_key_down(cx.listener(Self::on_key_down))
+            .child(measure)
+            .children(
+                self.selected
+                    .iter()
+                    .enumerate()
+                    .map(|(ix, item)| self.render_chip(ix, item, cx))
+                    .collect::<Vec<_>>(),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .min_w(px(80.))
+                    .child(Input::new(&self.input).appearance(false).xsmall()),
+            )
+            .when(self.loading, |this| {
+                this.child(
+                    Icon::new(IconName::Loader)
+                        .size_3p5()
+                        .flex_none()
+                        .text_color(theme.muted_foreground),
+                )
+            })
+            .children(dropdown)
+    }
+}
@@ -0,0 +1,397 @@
+use gpui::InteractiveElement as _;
+use gpui::StatefulInteractiveElement as _;
+use gpui::prelude::FluentBuilder as _;
+use gpui::{
+    AnyElement, App, AppContext as _, Context, Entity, IntoElement, KeyDownEvent, MouseButton,
+    ParentElement, SharedString, Styled, Window, div, px, relative,
+};
+use gpui_component::ActiveTheme as _;
+use gpui_component::button::{Button, ButtonVariants as _};
+use gpui_component::input::{Input, InputState};
+
+/// Maximum number of rows rendered for one query.
+const MAX_VISIBLE_ITEMS: usize = 80;
+const PAGE_STEP: usize = 10;
+
+#[derive(Clone, Debug)]
+pub struct CommandPaletteItem<A> {
+    pub action: A,
+    pub title: SharedString,
+    /// Extra words matched by the filter besides the title.
+    pub keywords: SharedString,
+}
+
+impl<A> CommandPaletteItem<A> {
+    pub const fn new(action: A, title: &'static str, keywords: &'static str) -> Self {
+        Self {
+            action,
+            title: SharedString::new_static(title),
+            keywords: SharedString::new_static(keywords),
+        }
+    }
+
+    /// Case-insensitive match of every whitespace-separated term against the title and keywords.
+    pub fn matches(&self, query: &str) -> bool {
+        let title = self.title.to_lowercase();
+        let keywords = self.keywords.to_lowercase();
+        query
+            .to_lowercase()
+            .split_whitespace()
+            .all(|term| title.contains(term) || keywords.contains(term))
+    }
+}
+
+/// State of an open command palette: the query input, the selected row and the items to
+/// choose from. The host view keeps it in an `Option` and implements
+/// [`CommandPaletteProvider`] to decide which actions are enabled and to run them.
+pub struct CommandPalette<A> {
+    input: Entity<InputState>,
+    items: Vec<CommandPaletteItem<A>>,
+    selected: usize,
+    title: SharedString,
+    empty_text: SharedString,
+    hint: SharedString,
+    close_label: SharedString,
+}
+
+impl<A: Copy + 'static> CommandPalette<A> {
+    /// Creates the palette and focuses its input.
+    pub fn new(
+        items: Vec<CommandPaletteItem<A>>,
+        placeholder: impl Into<SharedString>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Self {
+        let placeholder = placeholder.into();
+        let input = cx.new(|cx| InputState::new(window, cx).placeholder(placeholder));
+        input.update(cx, |state, cx| state.focus(window, cx));
+
+        Self {
+            input,
+            items,
+            selected: 0,
+            title: "Commands".into(),
+            empty_text: "No matching commands".into(),
+            hint: "↑↓ to select · Enter to run · Esc to close".into(),
+            close_label: "Close (Esc)".into(),
+        }
+    }
+
+    pub fn title(mut self, title: impl Into<SharedString>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Shown instead of the list when nothing matches the query.
+    pub fn empty_text(mut self, text: impl Into<SharedString>) -> Self {
+        self.empty_text = text.into();
+        self
+    }
+
+    /// Footer line describing the keyboard shortcuts.
+    pub fn hint(mut self, hint: impl Into<SharedString>) -> Self {
+        self.hint = hint.into();
+        self
+    }
+
+    pub fn close_label(mut self, label: impl Into<SharedString>) -> Self {
+        self.close_label = label.into();
+        self
+    }
+
+    pub fn focus(&self, window: &mut Window, cx: &mut App) {
+        self.input.update(cx, |state, cx| state.focus(window, cx));
+    }
+
+    pub fn query(&self, cx: &App) -> String {
+        self.input.read(cx).value().to_string()
+    }
+
+    /// Items matching the current query, in their original order.
+    pub fn filtered_items(&self, cx: &App) -> Vec<&CommandPaletteItem<A>> {
+        let query = self.query(cx);
+        self.items
+            .iter()
+            .filter(|item| item.matches(&query))
+            .collect()
+    }
+
+    /// Index of the selected row, clamped to the filtered list.
+    pub fn selected_index(&self, cx: &App) -> usize {
+        self.selected
+            .min(self.filtered_items(cx).len().saturating_sub(1))
+    }
+
+    pub fn selected_action(&self, cx: &App) -> Option<A> {
+        self.filtered_items(cx)
+            .get(self.selected_index(cx))
+            .map(|item| item.action)
+    }
+
+    fn move_selection(&mut self, key: &str, cx: &App) -> bool {
+        let last = match self.filtered_items(cx).len() {
+            0 => return false,
+            len => len - 1,
+        };
+        let selected = self.selected.min(last);
+        self.selected = match key {
+            "up" => selected.saturating_sub(1),
+            "pageup" => selected.saturating_sub(PAGE_STEP),
+            "down" => (selected + 1).min(last),
+            "pagedown" => (selected + PAGE_STEP).min(last),
+            _ => selected,
+        };
+        true
+    }
+}
+
+/// Connects a view to the [`CommandPalette`] it hosts.
+///
+/// The required methods give access to the palette and run actions; the provided ones
+/// implement keyboard handling and rendering on top of them.
+pub trait CommandPaletteProvider: Sized + 'static {
+    type Action: Copy + 'static;
+
+    fn command_palette(&self) -> Option<&CommandPalette<Self::Action>>;
+
+    fn command_palette_mut(&mut self) -> Option<&mut CommandPalette<Self::Action>>;
+
+    /// Disabled actions are listed but greyed out and cannot be run.
+    fn command_enabled(&self, _action: Self::Action) -> bool {
+        true
+    }
+
+    fn run_command(&mut self, action: Self::Action, window: &mut Window, cx: &mut Context<Self>);
+
+    /// Drops the palette and gives focus back to the view.
+    fn close_command_palette(&mut self, window: &mut Window, cx: &mut Context<Self>);
+
+    /// Runs the selected action, if enabled, and closes the palette.
+    fn confirm_command_palette(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(action) = self
+            .command_palette()
+            .and_then(|palette| palette.selected_action(cx))
+        else {
+            return;
+        };
+        if !self.command_enabled(action) {
+            return;
+        }
+
+        self.run_command(action, window, cx);
+        self.close_command_palette(window, cx);
+    }
+
+    /// Handles Esc, Enter and selection keys; returns whether the event was consumed.
+    fn handle_command_palette_key(
+        &mut self,
+        event: &KeyDownEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        if self.command_palette().is_none() {
+            return false;
+        }
+
+        match event.keystroke.key.as_str() {
+            "escape" => {
+                self.close_command_palette(window, cx);
+                true
+            }
+            "enter" => {
+                self.confirm_command_palette(window, cx);
+                true
+            }
+            key @ ("up" | "down" | "pageup" | "pagedown") => {
+                if let Some(palette) = self.command_palette_mut()
+                    && palette.move_selection(key, cx)
+                {
+                    cx.notify();
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Renders the palette as a backdrop covering the view; `None` while it is closed.
+    fn render_command_palette(
+        &self,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<AnyElement> {
+        let palette = self.command_palette()?;
+        let theme = cx.theme();
+        let view = cx.entity();
+
+        let filtered = palette.filtered_items(cx);
+        let selected = palette.selected.min(filtered.len().saturating_sub(1));
+
+        let mut list: Vec<AnyElement> = Vec::new();
+        if filtered.is_empty() {
+            list.push(
+                div()
+                    .px(px(12.))
+                    .py(px(10.))
+                    .text_sm()
+                    .text_color(theme.muted_foreground)
+                    .child(palette.empty_text.clone())
+                    .into_any_element(),
+            );
+        } else {
+            for (index, item) in filtered.iter().take(MAX_VISIBLE_ITEMS).enumerate() {
+                let is_selected = index == selected;
+                let enabled = self.command_enabled(item.action);
+                let action = item.action;
+
+                let mut row = div()
+                    .id(("command-palette-item", index))
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .h(px(32.))
+                    .px(px(10.))
+                    .rounded(px(6.))
+                    .text_sm()
+                    .child(div().truncate().child(item.title.clone()));
+
+                if enabled {
+                    row = row
+                        .when(is_selected, |this| {
+                            this.bg(theme.accent)
+                                .text_color(theme.accent_foreground)
+                                .cursor_default()
+                        })
+                        .when(!is_selected, |this| {
+                            let view = view.clone();
+                            this.bg(theme.transparent)
+                                .text_color(theme.popover_foreground)
+                                .cursor_pointer()
+                                .hover(|this| {
+                                    this.bg(theme.accent.alpha(0.4))
+                                        .text_color(theme.accent_foreground)
+                                })
+                                .active(|this| {
+                                    this.bg(theme.accent).text_color(theme.accent_foreground)
+                                })
+                                .on_mouse_down(MouseButton::Left, move |_, window, cx| {
+                                    window.prevent_default();
+                                    view.update(cx, |this, cx| {
+                                        if let Some(palette) = this.command_palette_mut() {
+                                            palette.selected = index;
+                                        }
+                                        this.run_command(action, window, cx);
+                                        this.close_command_palette(window, cx);
+                                    });
+                                })
+                        });
+                } else {
+                    row = row
+                        .bg(theme.transparent)
+                        .text_color(theme.muted_foreground)
+                        .cursor_default();
+                }
+
+                list.push(row.into_any_element());
+            }
+        }
+
+        let container = div()
+            .id("command-palette-overlay")
+            .w(px(640.))
+            .max_w(relative(0.92))
+            .bg(theme.popover)
+            .border_1()
+            .border_color(theme.border)
+            .rounded(theme.radius)
+            .shadow_lg()
+            .flex()
+            .flex_col()
+            .gap(px(10.))
+            .p(px(12.))
+            .on_mouse_down(MouseButton::Left, |_, window, cx| {
+                window.prevent_default();
+                cx.stop_propagation();
+            })
+            .on_key_down({
+                let view = view.clone();
+                move |event, window, cx| {
+                    let handled = view.update(cx, |this, cx| {
+                        this.handle_command_palette_key(event, window, cx)
+                    });
+                    if handled {
+                        window.prevent_default();
+                        cx.stop_propagation();
+                    }
+                }
+            })
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .gap(px(12.))
+                    .child(palette.title.clone())
+                    .child(
+                        Button::new("command-palette-close")
+                            .label(palette.close_label.clone())
+                            .ghost()
+                            .on_click({
+                                let view = view.clone();
+                                move |_, window, cx| {
+                                    view.update(cx, |this, cx| {
+                                        this.close_command_palette(window, cx);
+                                    });
+                                }
+                            }),
+                    ),
+            )
+            .child(Input::new(&palette.input).w_full())
+            .child(
+                div()
+                    .id("command-palette-list")
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.))
+                    .min_h(px(0.))
+                    .max_h(px(360.))
+                    .overflow_y_scroll()
+                    .border_1()
+                    .border_color(theme.border.alpha(0.5))
+                    .rounded(theme.radius)
+                    .p(px(6.))
+                    .children(list),
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(theme.muted_foreground)
+                    .child(palette.hint.clone()),
+            );
+
+        Some(
+            div()
+                .id("command-palette-overlay-backdrop")
+                .absolute()
+                .top(px(0.))
+                .bottom(px(0.))
+                .left(px(0.))
+                .right(px(0.))
+                .bg(theme.background.alpha(0.75))
+                .flex()
+                .flex_row()
+                .justify_center()
+                .pt(px(72.))
+                .on_mouse_down(MouseButton::Left, move |_, window, cx| {
+                    window.prevent_default();
+                    view.update(cx, |this, cx| {
+                        this.close_command_palette(window, cx);
+                    });
+                })
+                .child(container)
+                .into_any_element(),
+        )
+    }
+}
@@ -0,0 +1,740 @@
+use std::cmp::Ordering;
+use std::ops::Range;
+use std::rc::Rc;
+
+use gpui::prelude::FluentBuilder as _;
+use gpui::{
+    AnyElement, App, AppContext as _, ClickEvent, Context, CursorStyle, DragMoveEvent, ElementId,
+    Empty, Entity, EntityId, FocusHandle, InteractiveElement as _, IntoElement, ListSizingBehavior,
+    ParentElement as _, Pixels, Render, RenderOnce, ScrollStrategy, SharedString,
+    StatefulInteractiveElement as _, StyleRefinement, Styled, UniformListScrollHandle, Window, div,
+    px, uniform_list,
+};
+use gpui_component::scroll::{Scrollbar, ScrollbarState};
+use gpui_component::{ActiveTheme as _, Icon, IconName, StyledExt as _};
+use gpui_manos_dnd::{DragGhost, reorder_to_index_for_drop_on_row};
+
+const DEFAULT_COLUMN_WIDTH: Pixels = px(120.);
+const DEFAULT_MIN_COLUMN_WIDTH: Pixels = px(40.);
+const DEFAULT_ROW_HEIGHT: Pixels = px(28.);
+const HEADER_HEIGHT: Pixels = px(30.);
+const RESIZE_HANDLE_WIDTH: Pixels = px(6.);
+
+/// A column of a [`DataTableState`]: its header, width and how cells are rendered and compared.
+pub struct DataTableColumn<T> {
+    pub id: SharedString,
+    pub title: SharedString,
+    width: Pixels,
+    min_width: Pixels,
+    resizable: bool,
+    render_cell: Rc<dyn Fn(&T, &mut Window, &mut App) -> AnyElement>,
+    compare: Option<Rc<dyn Fn(&T, &T) -> Ordering>>,
+}
+
+impl<T> DataTableColumn<T> {
+    pub fn new<E: IntoElement>(
+        id: impl Into<SharedString>,
+        title: impl Into<SharedString>,
+        render_cell: impl Fn(&T, &mut Window, &mut App) -> E + 'static,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            title: title.into(),
+            width: DEFAULT_COLUMN_WIDTH,
+            min_width: DEFAULT_MIN_COLUMN_WIDTH,
+            resizable: true,
+            render_cell: Rc::new(move |row, window, cx| {
+                render_cell(row, window, cx).into_any_element()
+            }),
+            compare: None,
+        }
+    }
+
+    pub fn width(mut self, width: Pixels) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Lower bound applied while the column is resized.
+    pub fn min_width(mut self, min_width: Pixels) -> Self {
+        self.min_width = min_width;
+        self
+    }
+
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    /// Makes the column sortable: clicking its header cycles ascending, descending and unsorted.
+    pub fn sortable_by(mut self, compare: impl Fn(&T, &T) -> Ordering + 'static) -> Self {
+        self.compare = Some(Rc::new(compare));
+        self
+    }
+
+    pub fn current_width(&self) -> Pixels {
+        self.width
+    }
+
+    pub fn is_sortable(&self) -> bool {
+        self.compare.is_some()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataTableSortDirection {
+    Ascending,
+    Descending,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DataTableSort {
+    pub column_id: SharedString,
+    pub direction: DataTableSortDirection,
+}
+
+#[derive(Clone, Debug)]
+pub struct DataTableColumnReorder {
+    pub column_id: SharedString,
+    pub from: usize,
+    pub to: usize,
+}
+
+#[derive(Clone)]
+struct DataTableColumnDrag {
+    table_id: EntityId,
+    column_id: SharedString,
+    title: SharedString,
+}
+
+#[derive(Clone)]
+struct DataTableColumnResize {
+    table_id: EntityId,
+    column_ix: usize,
+}
+
+/// Mouse position and width when a resize drag started.
+struct ResizeStart {
+    column_ix: usize,
+    mouse_x: Pixels,
+    width: Pixels,
+}
+
+struct DataTableStateCallbacks<T> {
+    on_select: Option<Rc<dyn Fn(usize, &T, &mut Window, &mut App)>>,
+    on_sort: Option<Rc<dyn Fn(Option<&DataTableSort>, &mut Window, &mut App)>>,
+    on_column_reorder: Option<Rc<dyn Fn(&DataTableColumnReorder, &[DataTableColumn<T>])>>,
+    on_column_resize: Option<Rc<dyn Fn(&DataTableColumn<T>)>>,
+}
+
+impl<T> Default for DataTableStateCallbacks<T> {
+    fn default() -> Self {
+        Self {
+            on_select: None,
+            on_sort: None,
+            on_column_reorder: None,
+            on_column_resize: None,
+        }
+    }
+}
+
+/// State for a virtualized table with a sticky header and sortable, resizable and
+/// reorderable columns.
+///
+/// Only the visible rows are rendered, so the table stays cheap with many thousands of rows.
+/// Sorting never reorders `rows` itself; indices passed to callbacks refer to `rows`.
+pub struct DataTableState<T> {
+    focus_handle: FocusHandle,
+    columns: Vec<DataTableColumn<T>>,
+    rows: Vec<T>,
+    /// Display order as indices into `rows`.
+    order: Vec<usize>,
+    sort: Option<DataTableSort>,
+    selected_row: Option<usize>,
+    row_height: Pixels,
+    striped: bool,
+    scroll_handle: UniformListScrollHandle,
+    scrollbar_state: ScrollbarState,
+    resizing: Option<ResizeStart>,
+    dragged_column: Option<SharedString>,
+    callbacks: DataTableStateCallbacks<T>,
+}
+
+impl<T: 'static> DataTableState<T> {
+    pub fn new(cx: &mut App) -> Self {
+        Self {
+            focus_handle: cx.focus_handle(),
+            columns: Vec::new(),
+            rows: Vec::new(),
+            order: Vec::new(),
+            sort: None,
+            selected_row: None,
+            row_height: DEFAULT_ROW_HEIGHT,
+            striped: false,
+            scroll_handle: UniformListScrollHandle::default(),
+            scrollbar_state: ScrollbarState::default(),
+            resizing: None,
+            dragged_column: None,
+            callbacks: DataTableStateCallbacks::default(),
+        }
+    }
+
+    pub fn columns(mut self, columns: impl Into<Vec<DataTableColumn<T>>>) -> Self {
+        self.columns = columns.into();
+        self
+    }
+
+    pub fn rows(mut self, rows: impl Into<Vec<T>>) -> Self {
+        self.rows = rows.into();
+        self.apply_sort();
+        self
+    }
+
+    pub fn row_height(mut self, row_height: Pixels) -> Self {
+        self.row_height = row_height;
+        self
+    }
+
+    /// Alternate the background of every other row.
+    pub fn striped(mut self, striped: bool) -> Self {
+        self.striped = striped;
+        self
+    }
+
+    /// Provide a callback invoked when a row is clicked, with its index in `rows`.
+    pub fn on_select(
+        mut self,
+        on_select: impl Fn(usize, &T, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.callbacks.on_select = Some(Rc::new(on_select));
+        self
+    }
+
+    /// Provide a callback invoked after the sort changed from a header click.
+    pub fn on_sort(
+        mut self,
+        on_sort: impl Fn(Option<&DataTableSort>, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.callbacks.on_sort = Some(Rc::new(on_sort));
+        self
+    }
+
+    /// Provide a callback invoked after a column was dragged to a new position.
+    pub fn on_column_reorder(
+        mut self,
+        on_column_reorder: impl Fn(&DataTableColumnReorder, &[DataTableColumn<T>]) + 'static,
+    ) -> Self {
+        self.callbacks.on_column_reorder = Some(Rc::new(on_column_reorder));
+        self
+    }
+
+    /// Provide a callback invoked when a column resize ends.
+    pub fn on_column_resize(
+        mut self,
+        on_column_resize: impl Fn(&DataTableColumn<T>) + 'static,
+    ) -> Self {
+        self.callbacks.on_column_resize = Some(Rc::new(on_column_resize));
+        self
+    }
+
+    /// Replaces the rows, keeping the current sort. The selection is cleared.
+    pub fn set_rows(&mut self, rows: impl Into<Vec<T>>, cx: &mut Context<Self>) {
+        self.rows = rows.into();
+        self.selected_row = None;
+        self.apply_sort();
+        cx.notify();
+    }
+
+    pub fn rows_ref(&self) -> &[T] {
+        &self.rows
+    }
+
+    pub fn columns_ref(&self) -> &[DataTableColumn<T>] {
+        &self.columns
+    }
+
+    pub fn sort_ref(&self) -> Option<&DataTableSort> {
+        self.sort.as_ref()
+    }
+
+    /// Sorts by the given column; `None`, or a column that is not sortable, restores the
+    /// original row order.
+    pub fn set_sort(&mut self, sort: Option<DataTableSort>, cx: &mut Context<Self>) {
+        self.sort = sort.filter(|sort| {
+            self.columns
+                .iter()
+                .any(|column| column.id == sort.column_id && column.is_sortable())
+        });
+        self.apply_sort();
+        cx.notify();
+    }
+
+    pub fn set_column_width(&mut self, column_id: &str, width: Pixels, cx: &mut Context<Self>) {
+        if let Some(column) = self
+            .columns
+            .iter_mut()
+            .find(|column| column.id == column_id)
+        {
+            column.width = width.max(column.min_width);
+            cx.notify();
+        }
+    }
+
+    /// Index into `rows` of the selected row.
+    pub fn selected_index(&self) -> Option<usize> {
+        self.selected_row
+    }
+
+    pub fn set_selected_index(&mut self, ix: Option<usize>, cx: &mut Context<Self>) {
+        self.selected_row = ix.filter(|ix| *ix < self.rows.len());
+        if let Some(display_ix) = self
+            .selected_row
+            .and_then(|row| self.order.iter().position(|ix| *ix == row))
+        {
+            self.scroll_handle
+                .scroll_to_item(display_ix, ScrollStrategy::Top);
+        }
+        cx.notify();
+    }
+
+    fn apply_sort(&mut self) {
+        self.order = (0..self.rows.len()).collect();
+        let Some(sort) = self.sort.as_ref() else {
+            return;
+        };
+        let Some(compare) = self
+            .columns
+            .iter()
+            .find(|column| column.id == sort.column_id)
+            .and_then(|column| column.compare.clone())
+        else {
+            return;
+        };
+
+        let rows = &self.rows;
+        self.order.sort_by(|a, b| {
+            let ordering = compare(&rows[*a], &rows[*b]);
+            match sort.direction {
+                DataTableSortDirection::Ascending => ordering,
+                DataTableSortDirection::Descending => ordering.reverse(),
+            }
+        });
+    }
+
+    fn on_header_click(
+        &mut self,
+        column_ix: usize,
+        _event: &ClickEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(column) = self.columns.get(column_ix).filter(|c| c.is_sortable()) else {
+            return;
+        };
+
+        let direction = match self.sort.as_ref() {
+            Some(sort) if sort.column_id == column.id => match sort.direction {
+                DataTableSortDirection::Ascending => Some(DataTableSortDirection::Descending),
+                DataTableSortDirection::Descending => None,
+            },
+            _ => Some(DataTableSortDirection::Ascending),
+        };
+        self.sort = direction.map(|direction| DataTableSort {
+            column_id: column.id.clone(),
+            direction,
+        });
+        self.apply_sort();
+        cx.notify();
+
+        if let Some(on_sort) = self.callbacks.on_sort.clone() {
+            on_sort(self.sort.as_ref(), window, cx);
+        }
+    }
+
+    fn on_row_click(
+        &mut self,
+        row_ix: usize,
+        _event: &ClickEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.selected_row = Some(row_ix);
+        cx.notify();
+
+        if let Some(on_select) = self.callbacks.on_select.clone() {
+            on_select(row_ix, &self.rows[row_ix], window, cx);
+        }
+    }
+
+    fn on_resize_start(&mut self, column_ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(column) = self.columns.get(column_ix) else {
+            return;
+        };
+        self.resizing = Some(ResizeStart {
+            column_ix,
+            mouse_x: window.mouse_position().x,
+            width: column.width,
+        });
+        cx.notify();
+    }
+
+    fn on_resize_move(
+        &mut self,
+        event: &DragMoveEvent<DataTableColumnResize>,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let resize = event.drag(cx);
+        if resize.table_id != cx.entity_id() {
+            return;
+        }
+        let Some(start) = self
+            .resizing
+            .as_ref()
+            .filter(|start| start.column_ix == resize.column_ix)
+        else {
+            return;
+        };
+        let Some(column) = self.columns.get_mut(start.column_ix) else {
+            return;
+        };
+
+        let width = (start.width + (event.event.position.x - start.mouse_x)).max(column.min_width);
+        if width != column.width {
+            column.width = width;
+            cx.notify();
+        }
+    }
+
+    fn finish_resize(&mut self) {
+        let Some(start) = self.resizing.take() else {
+            return;
+        };
+        if let (Some(on_column_resize), Some(column)) = (
+            self.callbacks.on_column_resize.as_ref(),
+            self.columns.get(start.column_ix),
+        ) && column.width != start.width
+        {
+            on_column_resize(column);
+        }
+    }
+
+    fn on_drop_on_column(
+        &mut self,
+        drag: &DataTableColumnDrag,
+        target_ix: usize,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if drag.table_id != cx.entity_id() {
+            return;
+        }
+        let Some(from_ix) = self
+            .columns
+            .iter()
+            .position(|column| column.id == drag.column_id)
+        else {
+            return;
+        };
+
+        let to_ix = reorder_to_index_for_drop_on_row(from_ix, target_ix, self.columns.len());
+        if from_ix == to_ix {
+            return;
+        }
+
+        let column = self.columns.remove(from_ix);
+        self.columns.insert(to_ix, column);
+        self.dragged_column = None;
+        cx.notify();
+
+        if let Some(on_column_reorder) = self.callbacks.on_column_reorder.as_ref() {
+            let reorder = DataTableColumnReorder {
+                column_id: drag.column_id.clone(),
+                from: from_ix,
+                to: to_ix,
+            };
+            on_column_reorder(&reorder, &self.columns);
+        }
+    }
+
+    fn render_header(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let table_id = cx.entity_id();
+        let state_entity = cx.entity();
+        let drop_target_bg = theme.drop_target;
+        let drag_border = theme.drag_border;
+
+        let cells = self.columns.iter().enumerate().map(|(ix, column)| {
+            let sort_icon = self
+                .sort
+                .as_ref()
+                .filter(|sort| sort.column_id == column.id)
+                .map(|sort| match sort.direction {
+                    DataTableSortDirection::Ascending => IconName::ChevronUp,
+                    DataTableSortDirection::Descending => IconName::ChevronDown,
+                });
+            let dragging = self.dragged_column.as_ref() == Some(&column.id);
+            let drag_value = DataTableColumnDrag {
+                table_id,
+                column_id: column.id.clone(),
+                title: column.title.clone(),
+            };
+
+            let cell = div()
+                .id(("data-table-header", ix))
+                .size_full()
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap(px(4.))
+                .px(px(8.))
+                .text_xs()
+                .font_weight(gpui::FontWeight::SEMIBOLD)
+                .text_color(theme.muted_foreground)
+                .when(dragging, |this| this.opacity(0.5))
+                .when(column.is_sortable(), |this| {
+                    this.cursor_pointer()
+                        .hover(|this| this.text_color(theme.foreground))
+                        .on_click(cx.listener(move |this, event, window, cx| {
+                            this.on_header_click(ix, event, window, cx);
+                        }))
+                })
+                .child(div().flex_1().truncate().child(column.title.clone()))
+                .when_some(sort_icon, |this, icon| {
+                    this.child(Icon::new(icon).size_3().text_color(theme.foreground))
+                })
+                .on_drag(drag_value, {
+                    let state_entity = state_entity.clone();
+                    move |drag, _offset, _window, cx| {
+                        state_entity.update(cx, |state, cx| {
+                            state.dragged_column = Some(drag.column_id.clone());
+                            cx.notify();
+                        });
+                        let label = drag.title.clone();
+                        cx.new(|_| DragGhost::new(label))
+                    }
+                })
+                .drag_over::<DataTableColumnDrag>(move |style, drag, _window, _cx| {
+                    if drag.table_id != table_id {
+                        return style;
+                    }
+                    style
+                        .bg(drop_target_bg.alpha(drop_target_bg.a.max(0.2)))
+                        .border_color(drag_border)
+                        .border_x_2()
+                })
+                .on_drop::<DataTableColumnDrag>(cx.listener(move |this, drag, window, cx| {
+                    this.on_drop_on_column(drag, ix, window, cx);
+                }));
+
+            // The handle is a sibling of the header cell rather than a child, so ending a
+            // resize over the header does not count as a click that changes the sort.
+            let handle = column.resizable.then(|| {
+                let state_entity = state_entity.clone();
+                div()
+                    .id(("data-table-resize", ix))
+                    .absolute()
+                    .top_0()
+                    .bottom_0()
+                    .right_0()
+                    .w(RESIZE_HANDLE_WIDTH)
+                    .cursor(CursorStyle::ResizeLeftRight)
+                    .hover(|this| this.bg(drag_border.alpha(0.5)))
+                    .on_drag(
+                        DataTableColumnResize {
+                            table_id,
+                            column_ix: ix,
+                        },
+                        move |resize, _offset, window, cx| {
+                            state_entity.update(cx, |state, cx| {
+                                state.on_resize_start(resize.column_ix, window, cx);
+                            });
+                            cx.new(|_| Empty)
+                        },
+                    )
+            });
+
+            div()
+                .relative()
+                .flex_none()
+                .h_full()
+                .w(column.width)
+                .border_r_1()
+                .border_color(theme.border)
+                .child(cell)
+                .children(handle)
+        });
+
+        div()
+            .flex()
+            .flex_row()
+            .flex_none()
+            .h(HEADER_HEIGHT)
+            .bg(theme.table_head)
+            .border_b_1()
+            .border_color(theme.border)
+            .children(cells)
+    }
+}
+
+impl<T: 'static> Render for DataTableState<T> {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        if !cx.has_active_drag() {
+            self.finish_resize();
+            self.dragged_column = None;
+        }
+
+        let total_width = self
+            .columns
+            .iter()
+            .fold(px(0.), |width, column| width + column.width);
+        let row_height = self.row_height;
+        let striped = self.striped;
+
+        div()
+            .id("data-table-state")
+            .size_full()
+            .relative()
+            .on_drag_move(cx.listener(Self::on_resize_move))
+            .child(
+                div()
+                    .id("data-table-scroll-x")
+                    .size_full()
+                    .overflow_x_scroll()
+                    .child(
+                        // The header sits outside the virtual list, so it stays put while the
+                        // rows scroll vertically and scrolls along with them horizontally.
+                        div()
+                            .flex()
+                            .flex_col()
+                            .h_full()
+                            .min_w_full()
+                            .w(total_width)
+                            .child(self.render_header(cx))
+                            .child(
+                                uniform_list("rows", self.order.len(), {
+                                    cx.processor(
+                                        move |state, visible_range: Range<usize>, window, cx| {
+                                            let theme = cx.theme();
+                                            let selected_bg = theme.table_active;
+                                            let even_bg = theme.table_even;
+                                            let hover_bg = theme.table_hover;
+                                            let border = theme.table_row_border;
+
+                                            let mut rows = Vec::with_capacity(visible_range.len());
+                                            for display_ix in visible_range {
+                                                let row_ix = state.order[display_ix];
+                                                let selected = state.selected_row == Some(row_ix);
+                                                let row = &state.rows[row_ix];
+
+                                                let cells = state
+                                                    .columns
+                                                    .iter()
+                                                    .map(|column| {
+                                                        div()
+                                                            .flex_none()
+                                                            .flex()
+                                                            .items_center()
+                                                            .h_full()
+                                                            .w(column.width)
+                                                            .px(px(8.))
+                                                            .overflow_hidden()
+                                                            .child((column.render_cell)(
+                                                                row, window, cx,
+                                                            ))
+                                                    })
+                                                    .collect::<Vec<_>>();
+
+                                                rows.push(
+                                                    div()
+                                                        .id(display_ix)
+                                                        .flex()
+                                                        .flex_row()
+                                                        .h(row_height)
+                                                        .text_sm()
+                                                        .border_b_1()
+                                                        .border_color(border)
+                                                        .when(
+                                                            striped && display_ix % 2 == 1,
+                                                            |this| this.bg(even_bg),
+                                                        )
+                                                        .when(selected, |this| this.bg(selected_bg))
+                                                        .when(!selected, |this| {
+                                                            this.hover(|this| this.bg(hover_bg))
+                                                        })
+                                                        .on_click(cx.listener(
+                                                            move |this, event, window, cx| {
+                                                                this.on_row_click(
+                                                                    row_ix, event, window, cx,
+                                                                );
+                                                            },
+                                                        ))
+                                                        .children(cells),
+                                                );
+                                            }
+                                            rows
+                                        },
+                                    )
+                                })
+                                .flex_grow()
+                                .track_scroll(self.scroll_handle.clone())
+                                .with_sizing_behavior(ListSizingBehavior::Auto),
+                            ),
+                    ),
+            )
+            .child(
+                div()
+                    .absolute()
+                    .top(HEADER_HEIGHT)
+                    .right_0()
+                    .bottom_0()
+                    .w(px(12.))
+                    .child(Scrollbar::uniform_scroll(
+                        &self.scrollbar_state,
+                        &self.scroll_handle,
+                    )),
+            )
+    }
+}
+
+/// A table element rendering a [`DataTableState`].
+#[derive(IntoElement)]
+pub struct DataTable<T: 'static> {
+    id: ElementId,
+    state: Entity<DataTableState<T>>,
+    style: StyleRefinement,
+}
+
+impl<T: 'static> DataTable<T> {
+    pub fn new(state: &Entity<DataTableState<T>>) -> Self {
+        Self {
+            id: ElementId::Name(format!("data-table-{}", state.entity_id()).into()),
+            state: state.clone(),
+            style: StyleRefinement::default(),
+        }
+    }
+}
+
+impl<T: 'static> Styled for DataTable<T> {
+    fn style(&mut self) -> &mut StyleRefinement {
+        &mut self.style
+    }
+}
+
+impl<T: 'static> RenderOnce for DataTable<T> {
+    fn render(self, _window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let focus_handle = self.state.read(cx).focus_handle.clone();
+        let theme = cx.theme();
+
+        div()
+            .id(self.id)
+            .track_focus(&focus_handle)
+            .size_full()
+            .overflow_hidden()
+            .border_1()
+            .border_color(theme.border)
+            .rounded(theme.radius)
+            .child(self.state)
+            .refine_style(&self.style)
+    }
+}
@@ -0,0 +1,752 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use gpui::InteractiveElement as _;
+use gpui::StatefulInteractiveElement as _;
+use gpui::prelude::FluentBuilder as _;
+use gpui::{
+    App, AppContext as _, ClickEvent, Context, Entity, FocusHandle, Global, IntoElement,
+    KeyDownEvent, ListSizingBehavior, MouseButton, ParentElement, Render, RenderOnce,
+    ScrollStrategy, SharedString, Styled, UniformListScrollHandle, Window, div, px, relative,
+    uniform_list,
+};
+use gpui_component::button::{Button, ButtonVariants as _};
+use gpui_component::input::{Input, InputEvent, InputState};
+use gpui_component::scroll::{Scrollbar, ScrollbarState};
+use gpui_component::{ActiveTheme as _, Icon, IconName, Selectable as _, Sizable as _};
+
+const RECENT_LIMIT: usize = 8;
+const ROW_HEIGHT: f32 = 26.;
+const INDENT: f32 = 16.;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FilePickerMode {
+    #[default]
+    File,
+    /// Only directories are listed and one of them is picked.
+    Directory,
+}
+
+#[derive(Clone)]
+struct FileEntry {
+    name: SharedString,
+    path: PathBuf,
+    is_dir: bool,
+}
+
+struct VisibleRow {
+    entry: FileEntry,
+    depth: usize,
+    expanded: bool,
+}
+
+/// Directories picked from recently, most recent first, shared by every picker in the app.
+#[derive(Default)]
+struct RecentLocations(Vec<PathBuf>);
+
+impl Global for RecentLocations {}
+
+/// A file or directory picker drawn with GPUI, for when native dialogs are unavailable or
+/// out of place.
+///
+/// Render the entity directly to embed it, or wrap it in a [`FilePickerModal`]. The current
+/// directory is listed as a tree; directories expand in place and can be entered, the
+/// breadcrumb leads back up, and the filter narrows every listed level by name.
+pub struct FilePicker {
+    focus_handle: FocusHandle,
+    mode: FilePickerMode,
+    current_dir: PathBuf,
+    filter: Entity<InputState>,
+    show_hidden: bool,
+    /// Directory listings read so far; an error is shown in place of the children.
+    listings: HashMap<PathBuf, Result<Vec<FileEntry>, SharedString>>,
+    expanded: HashSet<PathBuf>,
+    rows: Vec<VisibleRow>,
+    /// Set when the tree or the filter changed; rows are rebuilt on the next render.
+    rows_stale: bool,
+    selected: Option<usize>,
+    scroll_handle: UniformListScrollHandle,
+    scrollbar_state: ScrollbarState,
+    confirm_label: SharedString,
+    cancel_label: SharedString,
+    recent_label: SharedString,
+    home_label: SharedString,
+    on_confirm: Option<Rc<dyn Fn(&Path, &mut Window, &mut App)>>,
+    on_cancel: Option<Rc<dyn Fn(&mut Window, &mut App)>>,
+}
+
+impl FilePicker {
+    pub fn new(
+        start_dir: impl Into<PathBuf>,
+        filter_placeholder: impl Into<SharedString>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let filter_placeholder = filter_placeholder.into();
+        let filter = cx.new(|cx| InputState::new(window, cx).placeholder(filter_placeholder));
+        cx.subscribe(&filter, |this, _, event: &InputEvent, cx| {
+            if matches!(event, InputEvent::Change { .. }) {
+                this.selected = None;
+                this.rows_stale = true;
+                cx.notify();
+            }
+        })
+        .detach();
+
+        Self {
+            focus_handle: cx.focus_handle(),
+            mode: FilePickerMode::File,
+            current_dir: start_dir.into(),
+            filter,
+            show_hidden: false,
+            listings: HashMap::new(),
+            expanded: HashSet::new(),
+            rows: Vec::new(),
+            rows_stale: true,
+            selected: None,
+            scroll_handle: UniformListScrollHandle::default(),
+            scrollbar_state: ScrollbarState::default(),
+            confirm_label: "Open".into(),
+            cancel_label: "Cancel".into(),
+            recent_label: "Recent".into(),
+            home_label: "Home".into(),
+            on_confirm: None,
+            on_cancel: None,
+        }
+    }
+
+    pub fn mode(mut self, mode: FilePickerMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn directory(self) -> Self {
+        self.mode(FilePickerMode::Directory)
+    }
+
+    /// List entries whose name starts with a dot.
+    pub fn show_hidden(mut self, show_hidden: bool) -> Self {
+        self.show_hidden = show_hidden;
+        self
+    }
+
+    pub fn confirm_label(mut self, label: impl Into<SharedString>) -> Self {
+        self.confirm_label = label.into();
+        self
+    }
+
+    pub fn cancel_label(mut self, label: impl Into<SharedString>) -> Self {
+        self.cancel_label = label.into();
+        self
+    }
+
+    /// Headings of the sidebar: the recent locations and the home directory.
+    pub fn sidebar_labels(
+        mut self,
+        recent: impl Into<SharedString>,
+        home: impl Into<SharedString>,
+    ) -> Self {
+        self.recent_label = recent.into();
+        self.home_label = home.into();
+        self
+    }
+
+    /// Called with the picked path; the picker remembers its directory as a recent location.
+    pub fn on_confirm(
+        mut self,
+        on_confirm: impl Fn(&Path, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.on_confirm = Some(Rc::new(on_confirm));
+        self
+    }
+
+    pub fn on_cancel(mut self, on_cancel: impl Fn(&mut Window, &mut App) + 'static) -> Self {
+        self.on_cancel = Some(Rc::new(on_cancel));
+        self
+    }
+
+    pub fn focus(&self, window: &mut Window, cx: &mut App) {
+        self.filter.update(cx, |state, cx| state.focus(window, cx));
+    }
+
+    pub fn current_dir(&self) -> &Path {
+        &self.current_dir
+    }
+
+    /// The path `confirm` would pick: the selected row, or in directory mode the current
+    /// directory when nothing is selected.
+    pub fn selected_path(&self) -> Option<&Path> {
+        match self.selected.and_then(|ix| self.rows.get(ix)) {
+            Some(row) if self.mode == FilePickerMode::File && row.entry.is_dir => None,
+            Some(row) => Some(&row.entry.path),
+            None if self.mode == FilePickerMode::Directory => Some(&self.current_dir),
+            None => None,
+        }
+    }
+
+    /// Lists `dir` as the root of the tree.
+    pub fn navigate(&mut self, dir: impl Into<PathBuf>, cx: &mut Context<Self>) {
+        self.current_dir = dir.into();
+        // Listings are re-read so that navigating doubles as a refresh.
+        self.listings.clear();
+        self.expanded.clear();
+        self.selected = None;
+        self.rows_stale = true;
+        self.scroll_handle.scroll_to_item(0, ScrollStrategy::Top);
+        cx.notify();
+    }
+
+    pub fn confirm(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.mode == FilePickerMode::File
+            && let Some(row) = self.selected.and_then(|ix| self.rows.get(ix))
+            && row.entry.is_dir
+        {
+            let dir = row.entry.path.clone();
+            self.navigate(dir, cx);
+            return;
+        }
+
+        let Some(path) = self.selected_path().map(Path::to_path_buf) else {
+            return;
+        };
+        let location = if path.is_dir() {
+            path.clone()
+        } else {
+            path.parent()
+                .map_or_else(|| path.clone(), Path::to_path_buf)
+        };
+        let recents = &mut cx.default_global::<RecentLocations>().0;
+        recents.retain(|recent| *recent != location);
+        recents.insert(0, location);
+        recents.truncate(RECENT_LIMIT);
+
+        if let Some(on_confirm) = self.on_confirm.clone() {
+            on_confirm(&path, window, cx);
+        }
+    }
+
+    pub fn cancel(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(on_cancel) = self.on_cancel.clone() {
+            on_cancel(window, cx);
+        }
+    }
+
+    fn listing(&mut self, dir: &Path) -> &Result<Vec<FileEntry>, SharedString> {
+        self.listings
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| read_entries(dir))
+    }
+
+    fn rebuild_rows(&mut self, cx: &App) {
+        let query = self.filter.read(cx).value().to_lowercase();
+        let mut rows = Vec::new();
+        let root = self.current_dir.clone();
+        self.push_rows(&root, 0, &query, &mut rows);
+        self.rows = rows;
+        self.rows_stale = false;
+    }
+
+    fn push_rows(&mut self, dir: &Path, depth: usize, query: &str, rows: &mut Vec<VisibleRow>) {
+        let Ok(entries) = self.listing(dir).clone() else {
+            return;
+        };
+
+        for entry in entries {
+            if !self.show_hidden && entry.name.starts_with('.') {
+                continue;
+            }
+            if self.mode == FilePickerMode::Directory && !entry.is_dir {
+                continue;
+            }
+
+            let expanded = entry.is_dir && self.expanded.contains(&entry.path);
+            // An expanded directory stays visible so that matches inside it can be shown.
+            let matches = query.is_empty() || entry.name.to_lowercase().contains(query);
+            if !matches && !expanded {
+                continue;
+            }
+
+            let path = entry.path.clone();
+            rows.push(VisibleRow {
+                entry,
+                depth,
+                expanded,
+            });
+            if expanded {
+                self.push_rows(&path, depth + 1, query, rows);
+            }
+        }
+    }
+
+    fn toggle_expanded(&mut self, path: &Path, cx: &mut Context<Self>) {
+        if !self.expanded.remove(path) {
+            self.expanded.insert(path.to_path_buf());
+        }
+        self.rows_stale = true;
+        cx.notify();
+    }
+
+    fn on_row_click(
+        &mut self,
+        ix: usize,
+        event: &ClickEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.selected = Some(ix);
+        cx.notify();
+
+        if event.click_count() >= 2 {
+            match self.rows.get(ix) {
+                Some(row) if row.entry.is_dir => {
+                    let dir = row.entry.path.clone();
+                    self.navigate(dir, cx);
+                }
+                Some(_) => self.confirm(window, cx),
+                None => {}
+            }
+        }
+    }
+
+    fn on_key_down(&mut self, event: &KeyDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+        let last = self.rows.len().saturating_sub(1);
+        match event.keystroke.key.as_str() {
+            "up" => {
+                self.selected = Some(self.selected.map_or(last, |ix| ix.saturating_sub(1)));
+            }
+            "down" => {
+                self.selected = Some(self.selected.map_or(0, |ix| (ix + 1).min(last)));
+            }
+            // Plain arrows move the cursor in the filter input, so expanding takes alt.
+            "right" | "left" if event.keystroke.modifiers.alt => {
+                let expand = event.keystroke.key == "right";
+                let Some(row) = self.selected.and_then(|ix| self.rows.get(ix)) else {
+                    return;
+                };
+                if !row.entry.is_dir || row.expanded == expand {
+                    return;
+                }
+                let path = row.entry.path.clone();
+                self.toggle_expanded(&path, cx);
+            }
+            "enter" => self.confirm(window, cx),
+            "escape" => self.cancel(window, cx),
+            _ => return,
+        }
+
+        if self.rows.is_empty() {
+            self.selected = None;
+        } else if let Some(ix) = self.selected {
+            self.scroll_handle
+                .scroll_to_item(ix, ScrollStrategy::Center);
+        }
+        window.prevent_default();
+        cx.stop_propagation();
+        cx.notify();
+    }
+
+    fn render_breadcrumb(&self, cx: &Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let mut ancestors: Vec<&Path> = self.current_dir.ancestors().collect();
+        ancestors.reverse();
+        let last = ancestors.len().saturating_sub(1);
+
+        let crumbs = ancestors.into_iter().enumerate().map(|(ix, dir)| {
+            let label: SharedString = dir
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| dir.display().to_string())
+                .into();
+            let target = dir.to_path_buf();
+            div()
+                .flex()
+                .flex_row()
+                .items_center()
+                .flex_none()
+                .when(ix > 0, |this| {
+                    this.child(
+                        Icon::new(IconName::ChevronRight)
+                            .size_3()
+                            .text_color(theme.muted_foreground),
+                    )
+                })
+                .child(
+                    Button::new(("file-picker-crumb", ix))
+                        .label(label)
+                        .ghost()
+                        .xsmall()
+                        .selected(ix == last)
+                        .on_click(cx.listener(move |this, _, _, cx| {
+                            this.navigate(target.clone(), cx);
+                        })),
+                )
+        });
+
+        div()
+            .id("file-picker-breadcrumb")
+            .flex()
+            .flex_row()
+            .items_center()
+            .overflow_x_scroll()
+            .children(crumbs)
+    }
+
+    fn render_sidebar(&self, cx: &Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let recents = cx
+            .try_global::<RecentLocations>()
+            .map(|recents| recents.0.clone())
+            .unwrap_or_default();
+        let home = std::env::var_os("HOME")
+            .or_else(|| std::env::var_os("USERPROFILE"))
+            .map(PathBuf::from);
+
+        let location =
+            |id: (&'static str, usize), icon: IconName, label: SharedString, path: PathBuf| {
+                let current = path == self.current_dir;
+                div()
+                    .id(id)
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap(px(6.))
+                    .px(px(8.))
+                    .h(px(ROW_HEIGHT))
+                    .rounded(px(4.))
+                    .text_sm()
+                    .cursor_pointer()
+                    .when(current, |this| {
+                        this.bg(theme.accent).text_color(theme.accent_foreground)
+                    })
+                    .when(!current, |this| {
+                        this.hover(|this| this.bg(theme.accent.alpha(0.4)))
+                    })
+                    .child(Icon::new(icon).size_3p5().flex_none())
+                    .child(div().truncate().child(label))
+                    .on_click(cx.listener(move |this, _, _, cx| this.navigate(path.clone(), cx)))
+            };
+
+        let heading = |label: SharedString| {
+            div()
+                .px(px(8.))
+                .pt(px(8.))
+                .pb(px(2.))
+                .text_xs()
+                .text_color(theme.muted_foreground)
+                .child(label)
+        };
+
+        div()
+            .id("file-picker-sidebar")
+            .flex()
+            .flex_col()
+            .flex_none()
+            .w(px(180.))
+            .p(px(4.))
+            .border_r_1()
+            .border_color(theme.border)
+            .overflow_y_scroll()
+            .when_some(home, |this, home| {
+                this.child(location(
+                    ("file-picker-home", 0),
+                    IconName::Folder,
+                    self.home_label.clone(),
+                    home,
+                ))
+            })
+            .when(!recents.is_empty(), |this| {
+                this.child(heading(self.recent_label.clone()))
+            })
+            .children(recents.into_iter().enumerate().map(|(ix, path)| {
+                let label: SharedString = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| path.display().to_string())
+                    .into();
+                location(
+                    ("file-picker-recent", ix),
+                    IconName::FolderOpen,
+                    label,
+                    path,
+                )
+            }))
+    }
+}
+
+impl Render for FilePicker {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        if self.rows_stale {
+            self.rebuild_rows(cx);
+        }
+
+        let theme = cx.theme();
+        let error = match self.listings.get(&self.current_dir) {
+            Some(Err(err)) => Some(err.clone()),
+            _ => None,
+        };
+        let selected_label: SharedString = self
+            .selected_path()
+            .map(|path| path.display().to_string().into())
+            .unwrap_or_default();
+        let can_confirm = self.selected.is_some() || self.mode == FilePickerMode::Directory;
+        let muted = theme.muted_foreground;
+
+        let list = uniform_list("file-picker-rows", self.rows.len(), {
+            cx.processor(move |this, visible_range: Range<usize>, _window, cx| {
+                let theme = cx.theme();
+                let selected_bg = theme.accent;
+                let selected_fg = theme.accent_foreground;
+                let hover_bg = theme.accent.alpha(0.4);
+
+                let mut rows = Vec::with_capacity(visible_range.len());
+                for ix in visible_range {
+                    let row = &this.rows[ix];
+                    let selected = this.selected == Some(ix);
+                    let icon = match (row.entry.is_dir, row.expanded) {
+                        (true, true) => IconName::FolderOpen,
+                        (true, false) => IconName::Folder,
+                        (false, _) => IconName::File,
+                    };
+                    let toggle_path = row.entry.path.clone();
+
+                    rows.push(
+                        div()
+                            .id(ix)
+                            .flex()
+                            .flex_row()
+                            .items_center()
+                            .gap(px(4.))
+                            .h(px(ROW_HEIGHT))
+                            .pl(px(4. + INDENT * row.depth as f32))
+                            .pr(px(8.))
+                            .rounded(px(4.))
+                            .text_sm()
+                            .when(selected, |this| {
+                                this.bg(selected_bg).text_color(selected_fg)
+                            })
+                            .when(!selected, |this| this.hover(|this| this.bg(hover_bg)))
+                            .child(
+                                div()
+                                    .id(("file-picker-toggle", ix))
+                                    .flex_none()
+                                    .size(px(16.))
+                                    .flex()
+                                    .items_center()
+                                    .justify_center()
+                                    .when(row.entry.is_dir, |this| {
+                                        this.child(
+                                            Icon::new(if row.expanded {
+                                                IconName::ChevronDown
+                                            } else {
+                                                IconName::ChevronRight
+                                            })
+                                            .size_3(),
+                                        )
+                                        .on_mouse_down(MouseButton::Left, |_, _, cx| {
+                                            cx.stop_propagation()
+                                        })
+                                        .on_click(
+                                            cx.listener(move |this, _, _, cx| {
+                                                this.toggle_expanded(&toggle_path, cx);
+                                            }),
+                                        )
+                                    }),
+                            )
+                            .child(Icon::new(icon).size_3p5().flex_none())
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .min_w(px(0.))
+                                    .truncate()
+                                    .child(row.entry.name.clone()),
+                            )
+                            .on_click(cx.listener(move |this, event, window, cx| {
+                                this.on_row_click(ix, event, window, cx);
+                            })),
+                    );
+                }
+                rows
+            })
+        })
+        .size_full()
+        .p(px(4.))
+        .track_scroll(self.scroll_handle.clone())
+        .with_sizing_behavior(ListSizingBehavior::Auto);
+
+        div()
+            .id("file-picker")
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .flex()
+            .flex_col()
+            .bg(theme.background)
+            .on_key_down(cx.listener(Self::on_key_down))
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(6.))
+                    .p(px(8.))
+                    .border_b_1()
+                    .border_color(theme.border)
+                    .child(self.render_breadcrumb(cx))
+                    .child(Input::new(&self.filter).small()),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .flex_1()
+                    .min_h(px(0.))
+                    .child(self.render_sidebar(cx))
+                    .child(
+                        div()
+                            .relative()
+                            .flex_1()
+                            .min_w(px(0.))
+                            .when_some(error, |this, error| {
+                                this.child(
+                                    div().p(px(12.)).text_sm().text_color(muted).child(error),
+                                )
+                            })
+                            .child(list)
+                            .child(
+                                div()
+                                    .absolute()
+                                    .top_0()
+                                    .right_0()
+                                    .bottom_0()
+                                    .w(px(12.))
+                                    .child(Scrollbar::uniform_scroll(
+                                        &self.scrollbar_state,
+                                        &self.scroll_handle,
+                                    )),
+                            ),
+                    ),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap(px(8.))
+                    .p(px(8.))
+                    .border_t_1()
+                    .border_color(theme.border)
+                    .child(
+                        div()
+                            .flex_1()
+                            .min_w(px(0.))
+                            .truncate()
+                            .text_xs()
+                            .text_color(muted)
+                            .child(selected_label),
+                    )
+                    .child(
+                        Button::new("file-picker-cancel")
+                            .label(self.cancel_label.clone())
+                            .ghost()
+                            .on_click(cx.listener(|this, _, window, cx| this.cancel(window, cx))),
+                    )
+                    .child(
+                        Button::new("file-picker-confirm")
+                            .label(self.confirm_label.clone())
+                            .primary()
+                            .disabled(!can_confirm)
+                            .on_click(cx.listener(|this, _, window, cx| this.confirm(window, cx))),
+                    ),
+            )
+    }
+}
+
+fn read_entries(dir: &Path) -> Result<Vec<FileEntry>, SharedString> {
+    let mut entries: Vec<FileEntry> = std::fs::read_dir(dir)
+        .map_err(|err| SharedString::from(format!("{}: {err}", dir.display())))?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let path = entry.path();
+            Some(FileEntry {
+                name: entry.file_name().to_string_lossy().to_string().into(),
+                // Follows symlinks, so a link to a directory can be expanded like one.
+                is_dir: path.is_dir(),
+                path,
+            })
+        })
+        .collect();
+    entries.sort_by_cached_key(|entry| (!entry.is_dir, entry.name.to_lowercase()));
+    Ok(entries)
+}
+
+/// Shows a [`FilePicker`] in a centered dialog over a backdrop; clicking the backdrop
+/// cancels the picker.
+#[derive(IntoElement)]
+pub struct FilePickerModal {
+    picker: Entity<FilePicker>,
+    title: Option<SharedString>,
+}
+
+impl FilePickerModal {
+    pub fn new(picker: &Entity<FilePicker>) -> Self {
+        Self {
+            picker: picker.clone(),
+            title: None,
+        }
+    }
+
+    pub fn title(mut self, title: impl Into<SharedString>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+}
+
+impl RenderOnce for FilePickerModal {
+    fn render(self, _window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let theme = cx.theme();
+        let picker = self.picker.clone();
+
+        div()
+            .id("file-picker-backdrop")
+            .absolute()
+            .top_0()
+            .left_0()
+            .size_full()
+            .flex()
+            .items_center()
+            .justify_center()
+            .bg(theme.background.alpha(0.75))
+            .occlude()
+            .on_mouse_down(MouseButton::Left, move |_, window, cx| {
+                picker.update(cx, |picker, cx| picker.cancel(window, cx));
+            })
+            .child(
+                div()
+                    .w(px(760.))
+                    .max_w(relative(0.92))
+                    .h(px(520.))
+                    .max_h(relative(0.9))
+                    .flex()
+                    .flex_col()
+                    .overflow_hidden()
+                    .bg(theme.popover)
+                    .border_1()
+                    .border_color(theme.border)
+                    .rounded(theme.radius)
+                    .shadow_lg()
+                    .on_mouse_down(MouseButton::Left, |_, _, cx| cx.stop_propagation())
+                    .when_some(self.title, |this, title| {
+                        this.child(
+                            div()
+                                .px(px(12.))
+                                .py(px(8.))
+                                .border_b_1()
+                                .border_color(theme.border)
+                                .font_weight(gpui::FontWeight::SEMIBOLD)
+                                .child(title),
+                        )
+                    })
+                    .child(div().flex_1().min_h(px(0.)).child(self.picker)),
+            )
+    }
+}
@@ -1,2 +1,11 @@
 pub mod assets;
+pub mod breadcrumb;
+pub mod combobox;
+pub mod command_palette;
+pub mod data_table;
+pub mod file_picker;
+pub mod notification_center;
 pub mod plate_toolbar;
+pub mod resizable;
+pub mod segmented;
+pub mod status_bar;
@@ -0,0 +1,652 @@
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use std::time::Duration;
+
+use gpui::InteractiveElement as _;
+use gpui::StatefulInteractiveElement as _;
+use gpui::prelude::FluentBuilder as _;
+use gpui::{
+    AnyWindowHandle, App, Context, Entity, Global, Hsla, IntoElement, MouseButton, ParentElement,
+    Render, RenderOnce, SharedString, Styled, Task, WeakEntity, Window, div, px,
+};
+use gpui_component::button::{Button, ButtonVariants as _};
+use gpui_component::notification::Notification;
+use gpui_component::{ActiveTheme as _, Icon, IconName, Sizable as _, Theme, WindowExt as _};
+
+const AUTO_TIMEOUT: Duration = Duration::from_secs(4);
+const AUTO_WARNING_TIMEOUT: Duration = Duration::from_secs(8);
+const DEFAULT_MAX_VISIBLE: usize = 4;
+const DEFAULT_HISTORY_LIMIT: usize = 200;
+const TOAST_WIDTH: f32 = 360.;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ToastSeverity {
+    #[default]
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl ToastSeverity {
+    fn icon(self) -> IconName {
+        match self {
+            Self::Info => IconName::Info,
+            Self::Success => IconName::CircleCheck,
+            Self::Warning => IconName::TriangleAlert,
+            Self::Error => IconName::CircleX,
+        }
+    }
+
+    fn color(self, theme: &Theme) -> Hsla {
+        match self {
+            Self::Info => theme.blue,
+            Self::Success => theme.green,
+            Self::Warning => theme.yellow,
+            Self::Error => theme.red,
+        }
+    }
+}
+
+/// How long a toast stays on screen once it is shown.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ToastTimeout {
+    /// Depends on the severity: errors stay until dismissed, warnings linger longer.
+    #[default]
+    Auto,
+    After(Duration),
+    /// Stays until dismissed.
+    Sticky,
+}
+
+#[derive(Clone)]
+pub struct ToastAction {
+    label: SharedString,
+    on_click: Rc<dyn Fn(&mut Window, &mut App)>,
+}
+
+/// A notification pushed to a [`NotificationCenter`].
+#[derive(Clone)]
+pub struct Toast {
+    title: Option<SharedString>,
+    message: SharedString,
+    severity: ToastSeverity,
+    timeout: ToastTimeout,
+    actions: Vec<ToastAction>,
+}
+
+impl Toast {
+    pub fn new(severity: ToastSeverity, message: impl Into<SharedString>) -> Self {
+        Self {
+            title: None,
+            message: message.into(),
+            severity,
+            timeout: ToastTimeout::Auto,
+            actions: Vec::new(),
+        }
+    }
+
+    pub fn info(message: impl Into<SharedString>) -> Self {
+        Self::new(ToastSeverity::Info, message)
+    }
+
+    pub fn success(message: impl Into<SharedString>) -> Self {
+        Self::new(ToastSeverity::Success, message)
+    }
+
+    pub fn warning(message: impl Into<SharedString>) -> Self {
+        Self::new(ToastSeverity::Warning, message)
+    }
+
+    pub fn error(message: impl Into<SharedString>) -> Self {
+        Self::new(ToastSeverity::Error, message)
+    }
+
+    pub fn title(mut self, title: impl Into<SharedString>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn timeout(mut self, timeout: ToastTimeout) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn sticky(self) -> Self {
+        self.timeout(ToastTimeout::Sticky)
+    }
+
+    /// Adds a button to the toast; clicking it runs `on_click` and dismisses the toast.
+    pub fn action(
+        mut self,
+        label: impl Into<SharedString>,
+        on_click: impl Fn(&mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.actions.push(ToastAction {
+            label: label.into(),
+            on_click: Rc::new(on_click),
+        });
+        self
+    }
+
+    pub fn message(&self) -> &SharedString {
+        &self.message
+    }
+
+    pub fn severity(&self) -> ToastSeverity {
+        self.severity
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        match self.timeout {
+            ToastTimeout::After(duration) => Some(duration),
+            ToastTimeout::Sticky => None,
+            ToastTimeout::Auto => match self.severity {
+                ToastSeverity::Info | ToastSeverity::Success => Some(AUTO_TIMEOUT),
+                ToastSeverity::Warning => Some(AUTO_WARNING_TIMEOUT),
+                ToastSeverity::Error => None,
+            },
+        }
+    }
+}
+
+struct ActiveToast {
+    id: u64,
+    toast: Toast,
+    _dismiss_task: Option<Task<()>>,
+}
+
+/// A toast that was pushed, kept for the history flyout.
+#[derive(Clone)]
+pub struct ToastRecord {
+    pub title: Option<SharedString>,
+    pub message: SharedString,
+    pub severity: ToastSeverity,
+}
+
+/// The center of the window each [`NotificationCenter`] was created in.
+#[derive(Default)]
+struct NotificationCenters(HashMap<AnyWindowHandle, WeakEntity<NotificationCenter>>);
+
+impl Global for NotificationCenters {}
+
+/// Shows toasts stacked in the bottom-right corner of a window and keeps a history of them.
+///
+/// The host view renders the center as the last child of its root, which must be
+/// `relative()`. Toasts beyond [`max_visible`](Self::max_visible) wait in a queue, and their
+/// timeout only starts once they are shown. Anything in the window can push toasts through
+/// [`WindowToastExt::push_toast`].
+pub struct NotificationCenter {
+    next_id: u64,
+    visible: Vec<ActiveToast>,
+    queued: VecDeque<Toast>,
+    history: VecDeque<ToastRecord>,
+    unread: usize,
+    history_open: bool,
+    max_visible: usize,
+    history_limit: usize,
+    history_title: SharedString,
+    empty_text: SharedString,
+    clear_label: SharedString,
+}
+
+impl NotificationCenter {
+    /// Creates the center and makes it the target of `push_toast` in `window`.
+    pub fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
+        cx.default_global::<NotificationCenters>()
+            .0
+            .insert(window.window_handle(), cx.weak_entity());
+
+        Self {
+            next_id: 0,
+            visible: Vec::new(),
+            queued: VecDeque::new(),
+            history: VecDeque::new(),
+            unread: 0,
+            history_open: false,
+            max_visible: DEFAULT_MAX_VISIBLE,
+            history_limit: DEFAULT_HISTORY_LIMIT,
+            history_title: "Notifications".into(),
+            empty_text: "No notifications".into(),
+            clear_label: "Clear".into(),
+        }
+    }
+
+    /// The center created in `window`, if any.
+    pub fn for_window(window: &Window, cx: &App) -> Option<Entity<Self>> {
+        cx.try_global::<NotificationCenters>()?
+            .0
+            .get(&window.window_handle())?
+            .upgrade()
+    }
+
+    /// Number of toasts shown at once; later ones are queued.
+    pub fn max_visible(mut self, max_visible: usize) -> Self {
+        self.max_visible = max_visible.max(1);
+        self
+    }
+
+    /// Number of toasts kept in the history.
+    pub fn history_limit(mut self, history_limit: usize) -> Self {
+        self.history_limit = history_limit;
+        self
+    }
+
+    pub fn history_title(mut self, title: impl Into<SharedString>) -> Self {
+        self.history_title = title.into();
+        self
+    }
+
+    /// Shown in the history flyout when nothing has been pushed yet.
+    pub fn empty_text(mut self, text: impl Into<SharedString>) -> Self {
+        self.empty_text = text.into();
+        self
+    }
+
+    pub fn clear_label(mut self, label: impl Into<SharedString>) -> Self {
+        self.clear_label = label.into();
+        self
+    }
+
+    pub fn push(&mut self, toast: Toast, cx: &mut Context<Self>) {
+        self.history.push_front(ToastRecord {
+            title: toast.title.clone(),
+            message: toast.message.clone(),
+            severity: toast.severity,
+        });
+        self.history.truncate(self.history_limit);
+        if !self.history_open {
+            self.unread += 1;
+        }
+
+        self.queued.push_back(toast);
+        self.show_queued(cx);
+        cx.notify();
+    }
+
+    /// Hides a visible toast and shows the next queued one.
+    pub fn dismiss(&mut self, id: u64, cx: &mut Context<Self>) {
+        let len = self.visible.len();
+        self.visible.retain(|toast| toast.id != id);
+        if self.visible.len() != len {
+            self.show_queued(cx);
+            cx.notify();
+        }
+    }
+
+    /// Hides every visible and queued toast; the history is kept.
+    pub fn dismiss_all(&mut self, cx: &mut Context<Self>) {
+        self.visible.clear();
+        self.queued.clear();
+        cx.notify();
+    }
+
+    pub fn history(&self) -> impl Iterator<Item = &ToastRecord> {
+        self.history.iter()
+    }
+
+    pub fn clear_history(&mut self, cx: &mut Context<Self>) {
+        self.history.clear();
+        self.unread = 0;
+        cx.notify();
+    }
+
+    /// Toasts pushed since the history flyout was last opened.
+    pub fn unread_count(&self) -> usize {
+        self.unread
+    }
+
+    pub fn is_history_open(&self) -> bool {
+        self.history_open
+    }
+
+    pub fn set_history_open(&mut self, open: bool, cx: &mut Context<Self>) {
+        self.history_open = open;
+        if open {
+            self.unread = 0;
+        }
+        cx.notify();
+    }
+
+    pub fn toggle_history(&mut self, cx: &mut Context<Self>) {
+        self.set_history_open(!self.history_open, cx);
+    }
+
+    fn show_queued(&mut self, cx: &mut Context<Self>) {
+        while self.visible.len() < self.max_visible {
+            let Some(toast) = self.queued.pop_front() else {
+                break;
+            };
+
+            let id = self.next_id;
+            self.next_id += 1;
+            let dismiss_task = toast.duration().map(|duration| {
+                cx.spawn(async move |this, cx| {
+                    cx.background_executor().timer(duration).await;
+                    _ = this.update(cx, |this, cx| this.dismiss(id, cx));
+                })
+            });
+            self.visible.push(ActiveToast {
+                id,
+                toast,
+                _dismiss_task: dismiss_task,
+            });
+        }
+    }
+
+    fn render_toast(&self, toast: &ActiveToast, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let id = toast.id;
+        let accent = toast.toast.severity.color(theme);
+
+        let actions = toast
+            .toast
+            .actions
+            .iter()
+            .enumerate()
+            .map(|(ix, action)| {
+                let on_click = action.on_click.clone();
+                Button::new(("toast-action", ix))
+                    .label(action.label.clone())
+                    .small()
+                    .when(ix == 0, |this| this.primary())
+                    .when(ix > 0, |this| this.ghost())
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        on_click(window, cx);
+                        this.dismiss(id, cx);
+                    }))
+            })
+            .collect::<Vec<_>>();
+
+        div()
+            .id(("toast", id as usize))
+            .w(px(TOAST_WIDTH))
+            .flex()
+            .flex_row()
+            .gap(px(10.))
+            .p(px(12.))
+            .bg(theme.popover)
+            .text_color(theme.popover_foreground)
+            .border_1()
+            .border_color(theme.border)
+            .rounded(theme.radius)
+            .shadow_lg()
+            .on_mouse_down(MouseButton::Left, |_, _, cx| cx.stop_propagation())
+            .child(
+                Icon::new(toast.toast.severity.icon())
+                    .size_4()
+                    .flex_none()
+                    .mt(px(2.))
+                    .text_color(accent),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .min_w(px(0.))
+                    .flex()
+                    .flex_col()
+                    .gap(px(4.))
+                    .when_some(toast.toast.title.clone(), |this, title| {
+                        this.child(
+                            div()
+                                .text_sm()
+                                .font_weight(gpui::FontWeight::SEMIBOLD)
+                                .child(title),
+                        )
+                    })
+                    .child(div().text_sm().child(toast.toast.message.clone()))
+                    .when(!actions.is_empty(), |this| {
+                        this.child(
+                            div()
+                                .flex()
+                                .flex_row()
+                                .gap(px(6.))
+                                .pt(px(4.))
+                                .children(actions),
+                        )
+                    }),
+            )
+            .child(
+                Button::new("toast-close")
+                    .icon(IconName::Close)
+                    .ghost()
+                    .xsmall()
+                    .on_click(cx.listener(move |this, _, _, cx| this.dismiss(id, cx))),
+            )
+    }
+
+    fn render_history(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+
+        let records = self.history.iter().enumerate().map(|(ix, record)| {
+            div()
+                .id(("toast-history", ix))
+                .flex()
+                .flex_row()
+                .gap(px(8.))
+                .px(px(10.))
+                .py(px(8.))
+                .rounded(px(6.))
+                .hover(|this| this.bg(theme.accent.alpha(0.4)))
+                .child(
+                    Icon::new(record.severity.icon())
+                        .size_3p5()
+                        .flex_none()
+                        .mt(px(2.))
+                        .text_color(record.severity.color(theme)),
+                )
+                .child(
+                    div()
+                        .flex_1()
+                        .min_w(px(0.))
+                        .text_sm()
+                        .when_some(record.title.clone(), |this, title| {
+                            this.child(div().font_weight(gpui::FontWeight::SEMIBOLD).child(title))
+                        })
+                        .child(record.message.clone()),
+                )
+        });
+
+        div()
+            .id("toast-history-flyout")
+            .absolute()
+            .top(px(12.))
+            .right(px(12.))
+            .w(px(TOAST_WIDTH + 40.))
+            .max_h(px(480.))
+            .flex()
+            .flex_col()
+            .bg(theme.popover)
+            .text_color(theme.popover_foreground)
+            .border_1()
+            .border_color(theme.border)
+            .rounded(theme.radius)
+            .shadow_lg()
+            .on_mouse_down(MouseButton::Left, |_, _, cx| cx.stop_propagation())
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .px(px(12.))
+                    .py(px(8.))
+                    .border_b_1()
+                    .border_color(theme.border)
+                    .child(
+                        div()
+                            .text_sm()
+                            .font_weight(gpui::FontWeight::SEMIBOLD)
+                            .child(self.history_title.clone()),
+                    )
+                    .child(
+                        Button::new("toast-history-clear")
+                            .label(self.clear_label.clone())
+                            .ghost()
+                            .xsmall()
+                            .disabled(self.history.is_empty())
+                            .on_click(cx.listener(|this, _, _, cx| this.clear_history(cx))),
+                    ),
+            )
+            .child(
+                div()
+                    .id("toast-history-list")
+                    .flex_1()
+                    .min_h(px(0.))
+                    .overflow_y_scroll()
+                    .p(px(6.))
+                    .when(self.history.is_empty(), |this| {
+                        this.child(
+                            div()
+                                .px(px(10.))
+                                .py(px(8.))
+                                .text_sm()
+                                .text_color(theme.muted_foreground)
+                                .child(self.empty_text.clone()),
+                        )
+                    })
+                    .children(records),
+            )
+    }
+}
+
+impl Render for NotificationCenter {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let queued = self.queued.len();
+        let more = (queued > 0).then(|| {
+            div()
+                .px(px(10.))
+                .py(px(2.))
+                .rounded_full()
+                .bg(theme.muted)
+                .text_xs()
+                .text_color(theme.muted_foreground)
+                .child(format!("+{queued}"))
+        });
+        let toasts = self
+            .visible
+            .iter()
+            .map(|toast| self.render_toast(toast, cx).into_any_element())
+            .collect::<Vec<_>>();
+
+        // Only the toasts and the flyout take mouse events; the rest of the layer lets clicks
+        // through to the view below.
+        div()
+            .absolute()
+            .top_0()
+            .left_0()
+            .size_full()
+            .child(
+                div()
+                    .absolute()
+                    .bottom(px(16.))
+                    .right(px(16.))
+                    .flex()
+                    .flex_col()
+                    .items_end()
+                    .gap(px(8.))
+                    .children(more)
+                    .children(toasts),
+            )
+            .when(self.history_open, |this| {
+                // Covers the bell as well, so clicking it while open only closes the flyout.
+                this.child(
+                    div()
+                        .id("toast-history-backdrop")
+                        .absolute()
+                        .top_0()
+                        .left_0()
+                        .size_full()
+                        .occlude()
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(|this, _, _, cx| this.set_history_open(false, cx)),
+                        ),
+                )
+                .child(self.render_history(cx))
+            })
+    }
+}
+
+/// A bell button showing the unread count of a [`NotificationCenter`] and toggling its
+/// history flyout.
+#[derive(IntoElement)]
+pub struct NotificationBell {
+    center: Entity<NotificationCenter>,
+    tooltip: Option<SharedString>,
+}
+
+impl NotificationBell {
+    pub fn new(center: &Entity<NotificationCenter>) -> Self {
+        Self {
+            center: center.clone(),
+            tooltip: None,
+        }
+    }
+
+    pub fn tooltip(mut self, tooltip: impl Into<SharedString>) -> Self {
+        self.tooltip = Some(tooltip.into());
+        self
+    }
+}
+
+impl RenderOnce for NotificationBell {
+    fn render(self, _window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let theme = cx.theme();
+        let unread = self.center.read(cx).unread_count();
+        let center = self.center.clone();
+        let badge: Option<SharedString> = match unread {
+            0 => None,
+            1..=99 => Some(unread.to_string().into()),
+            _ => Some("99+".into()),
+        };
+
+        div()
+            .relative()
+            .flex_none()
+            .child(
+                Button::new("notification-bell")
+                    .icon(IconName::Bell)
+                    .ghost()
+                    .when_some(self.tooltip, |this, tooltip| this.tooltip(tooltip))
+                    .on_click(move |_, _, cx| {
+                        center.update(cx, |center, cx| center.toggle_history(cx));
+                    }),
+            )
+            .when_some(badge, |this, badge| {
+                this.child(
+                    div()
+                        .absolute()
+                        .top(px(-2.))
+                        .right(px(-2.))
+                        .min_w(px(16.))
+                        .h(px(16.))
+                        .px(px(4.))
+                        .rounded_full()
+                        .flex()
+                        .items_center()
+                        .justify_center()
+                        .bg(theme.danger)
+                        .text_color(theme.danger_foreground)
+                        .text_size(px(10.))
+                        .child(badge),
+                )
+            })
+    }
+}
+
+pub trait WindowToastExt {
+    /// Pushes a toast to the window's [`NotificationCenter`], or shows it as a plain
+    /// notification when the window has none.
+    fn push_toast(&mut self, toast: Toast, cx: &mut App);
+}
+
+impl WindowToastExt for Window {
+    fn push_toast(&mut self, toast: Toast, cx: &mut App) {
+        match NotificationCenter::for_window(self, cx) {
+            Some(center) => center.update(cx, |center, cx| center.push(toast, cx)),
+            None => self.push_notification(Notification::new().message(toast.message), cx),
+        }
+    }
+}
@@ -4,8 +4,9 @@ use gpui::InteractiveElement as _;
 use gpui::StatefulInteractiveElement as _;
 use gpui::prelude::FluentBuilder as _;
 use gpui::{
-    App, AppContext as _, ClickEvent, ElementId, Hsla, IntoElement, MouseButton, ParentElement,
-    RenderOnce, SharedString, StyleRefinement, Styled, Window, div, px,
+    AnyElement, App, AppContext as _, Bounds, ClickEvent, Corner, ElementId, Hsla, IntoElement,
+    MouseButton, ParentElement, Pixels, RenderOnce, SharedString, StyleRefinement, Styled, Window,
+    anchored, canvas, deferred, div, px,
 };
 use gpui_component::ActiveTheme as _;
 use gpui_component::Colorize as _;
@@ -49,6 +50,8 @@ pub enum PlateIconName {
     Minus,
     PaintBucket,
     Pen,
+    PilcrowLeft,
+    PilcrowRight,
     Plus,
     Redo2,
     Smile,
@@ -92,6 +95,8 @@ impl IconNamed for PlateIconName {
             Self::Minus => "icons/minus.svg",
             Self::PaintBucket => "icons/paint-bucket.svg",
             Self::Pen => "icons/pen.svg",
+            Self::PilcrowLeft => "icons/pilcrow-left.svg",
+            Self::PilcrowRight => "icons/pilcrow-right.svg",
             Self::Plus => "icons/plus.svg",
             Self::Redo2 => "icons/redo-2.svg",
             Self::Smile => "icons/smile.svg",
@@ -506,6 +511,323 @@ impl RenderOnce for PlateToolbarSeparator {
     }
 }
 
+const TOOLBAR_GAP: f32 = 6.;
+/// Width of a [`PlateToolbarSeparator`] including its margins.
+const SEPARATOR_WIDTH: f32 = 13.;
+const OVERFLOW_BUTTON_WIDTH: f32 = 32.;
+
+/// An entry of a [`PlateToolbar`].
+pub struct PlateToolbarItem {
+    id: SharedString,
+    width: Pixels,
+    element: AnyElement,
+}
+
+impl PlateToolbarItem {
+    pub fn new(id: impl Into<SharedString>, element: impl IntoElement) -> Self {
+        Self {
+            id: id.into(),
+            width: px(32.),
+            element: element.into_any_element(),
+        }
+    }
+
+    /// Width the overflow layout reserves for the item; defaults to one icon button.
+    pub fn width(mut self, width: Pixels) -> Self {
+        self.width = width;
+        self
+    }
+}
+
+struct PlateToolbarGroup {
+    id: SharedString,
+    items: Vec<PlateToolbarItem>,
+}
+
+#[derive(Default)]
+struct PlateToolbarOverflowState {
+    width: Option<Pixels>,
+    trigger_bounds: Option<Bounds<Pixels>>,
+    open: bool,
+}
+
+/// A row of toolbar items, with a [`PlateToolbarSeparator`] between groups.
+///
+/// Items that do not fit the available width collapse, in order, into a "…" popup. Hosts
+/// assemble the layout with the builder methods and can drop, insert or regroup items on
+/// top of a shared default before rendering it.
+#[derive(IntoElement)]
+pub struct PlateToolbar {
+    id: ElementId,
+    style: StyleRefinement,
+    groups: Vec<PlateToolbarGroup>,
+    suffix: Vec<AnyElement>,
+}
+
+impl PlateToolbar {
+    pub fn new(id: impl Into<ElementId>) -> Self {
+        Self {
+            id: id.into(),
+            style: StyleRefinement::default(),
+            groups: Vec::new(),
+            suffix: Vec::new(),
+        }
+    }
+
+    /// Appends items to a group, adding the group at the end if it does not exist yet.
+    pub fn group(
+        mut self,
+        group: impl Into<SharedString>,
+        items: impl IntoIterator<Item = PlateToolbarItem>,
+    ) -> Self {
+        let group = group.into();
+        match self.groups.iter_mut().find(|existing| existing.id == group) {
+            Some(existing) => existing.items.extend(items),
+            None => self.groups.push(PlateToolbarGroup {
+                id: group,
+                items: items.into_iter().collect(),
+            }),
+        }
+        self
+    }
+
+    pub fn item(self, group: impl Into<SharedString>, item: PlateToolbarItem) -> Self {
+        self.group(group, [item])
+    }
+
+    /// Inserts an item at `index` within a group; the index is clamped to the group's length.
+    pub fn insert_item(
+        mut self,
+        group: impl Into<SharedString>,
+        index: usize,
+        item: PlateToolbarItem,
+    ) -> Self {
+        let group = group.into();
+        match self.groups.iter_mut().find(|existing| existing.id == group) {
+            Some(existing) => {
+                let index = index.min(existing.items.len());
+                existing.items.insert(index, item);
+            }
+            None => self.groups.push(PlateToolbarGroup {
+                id: group,
+                items: vec![item],
+            }),
+        }
+        self
+    }
+
+    pub fn remove_item(mut self, id: &str) -> Self {
+        self.take_item(id);
+        self
+    }
+
+    pub fn remove_group(mut self, group: &str) -> Self {
+        self.groups.retain(|existing| existing.id != group);
+        self
+    }
+
+    /// Moves an item to the end of another group.
+    pub fn move_item(mut self, id: &str, group: impl Into<SharedString>) -> Self {
+        match self.take_item(id) {
+            Some(item) => self.item(group, item),
+            None => self,
+        }
+    }
+
+    /// Adds an element after the overflow button; suffix elements never collapse.
+    pub fn suffix(mut self, element: impl IntoElement) -> Self {
+        self.suffix.push(element.into_any_element());
+        self
+    }
+
+    fn take_item(&mut self, id: &str) -> Option<PlateToolbarItem> {
+        self.groups.iter_mut().find_map(|group| {
+            let index = group.items.iter().position(|item| item.id == id)?;
+            Some(group.items.remove(index))
+        })
+    }
+}
+
+impl Styled for PlateToolbar {
+    fn style(&mut self) -> &mut StyleRefinement {
+        &mut self.style
+    }
+}
+
+impl RenderOnce for PlateToolbar {
+    fn render(self, window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let state = window.use_keyed_state(self.id.clone(), cx, |_, _| {
+            PlateToolbarOverflowState::default()
+        });
+        let (available, open) = {
+            let state = state.read(cx);
+            (state.width, state.open)
+        };
+
+        let groups: Vec<Vec<PlateToolbarItem>> = self
+            .groups
+            .into_iter()
+            .map(|group| group.items)
+            .filter(|items| !items.is_empty())
+            .collect();
+        let item_count: usize = groups.iter().map(Vec::len).sum();
+        let total = groups
+            .iter()
+            .flatten()
+            .fold(px(0.), |total, item| total + item.width)
+            + px(TOOLBAR_GAP) * item_count.saturating_sub(1) as f32
+            + px(SEPARATOR_WIDTH + TOOLBAR_GAP) * groups.len().saturating_sub(1) as f32;
+        // Before the first measurement everything is shown; the next frame corrects it.
+        let budget = available
+            .filter(|available| total > *available)
+            .map(|available| available - px(OVERFLOW_BUTTON_WIDTH + TOOLBAR_GAP));
+
+        let mut visible: Vec<AnyElement> = Vec::new();
+        let mut overflow: Vec<AnyElement> = Vec::new();
+        let mut used = px(0.);
+        for (group_ix, items) in groups.into_iter().enumerate() {
+            for (item_ix, item) in items.into_iter().enumerate() {
+                let starts_group = group_ix > 0 && item_ix == 0;
+                if overflow.is_empty() {
+                    let mut needed = item.width;
+                    if !visible.is_empty() {
+                        needed += px(TOOLBAR_GAP);
+                    }
+                    if starts_group {
+                        needed += px(SEPARATOR_WIDTH + TOOLBAR_GAP);
+                    }
+                    if budget.is_none_or(|budget| used + needed <= budget) {
+                        used += needed;
+                        if starts_group {
+                            visible.push(PlateToolbarSeparator.into_any_element());
+                        }
+                        visible.push(item.element);
+                        continue;
+                    }
+                }
+                if starts_group && !overflow.is_empty() {
+                    overflow.push(PlateToolbarSeparator.into_any_element());
+                }
+                overflow.push(item.element);
+            }
+        }
+
+        let measure = {
+            let state = state.clone();
+            canvas(
+                move |bounds, _, cx| {
+                    state.update(cx, |state, cx| {
+                        if state.width != Some(bounds.size.width) {
+                            state.width = Some(bounds.size.width);
+                            cx.notify();
+                        }
+                    });
+                },
+                |_, _, _, _| {},
+            )
+            .absolute()
+            .size_full()
+        };
+
+        let theme = cx.theme();
+        let overflow_button = (!overflow.is_empty()).then(|| {
+            let trigger_id = ElementId::NamedChild(Box::new(self.id.clone()), "overflow".into());
+            let toggle_state = state.clone();
+            let bounds_state = state.clone();
+            let dismiss_state = state.clone();
+
+            div()
+                .relative()
+                .flex_none()
+                .child(
+                    PlateToolbarIconButton::new(trigger_id, PlateIconName::Ellipsis)
+                        .selected(open)
+                        .tooltip("More")
+                        .on_click(move |_, _, cx| {
+                            toggle_state.update(cx, |state, cx| {
+                                state.open = !state.open;
+                                cx.notify();
+                            });
+                        }),
+                )
+                .child(
+                    canvas(
+                        move |bounds, _, cx| {
+                            bounds_state.update(cx, |state, _| state.trigger_bounds = Some(bounds));
+                        },
+                        |_, _, _, _| {},
+                    )
+                    .absolute()
+                    .size_full(),
+                )
+                .when(open, |this| {
+                    this.child(
+                        div().absolute().top_full().right_0().child(deferred(
+                            anchored()
+                                .anchor(Corner::TopRight)
+                                .snap_to_window_with_margin(px(8.))
+                                .child(
+                                    div()
+                                        .id("plate-toolbar-overflow")
+                                        .occlude()
+                                        .mt(px(4.))
+                                        .p(px(4.))
+                                        .max_w(px(320.))
+                                        .flex()
+                                        .flex_wrap()
+                                        .items_center()
+                                        .gap(px(TOOLBAR_GAP))
+                                        .bg(theme.popover)
+                                        .border_1()
+                                        .border_color(theme.border)
+                                        .rounded(theme.radius)
+                                        .shadow_md()
+                                        .text_color(theme.popover_foreground)
+                                        // The trigger toggles the popup itself; closing here too
+                                        // would reopen it on the same click.
+                                        .on_mouse_down_out(move |event, _, cx| {
+                                            dismiss_state.update(cx, |state, cx| {
+                                                let on_trigger =
+                                                    state.trigger_bounds.is_some_and(|bounds| {
+                                                        bounds.contains(&event.position)
+                                                    });
+                                                if state.open && !on_trigger {
+                                                    state.open = false;
+                                                    cx.notify();
+                                                }
+                                            });
+                                        })
+                                        .children(overflow),
+                                ),
+                        )),
+                    )
+                })
+        });
+
+        div()
+            .id(self.id)
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap(px(TOOLBAR_GAP))
+            .refine_style(&self.style)
+            .child(
+                div()
+                    .relative()
+                    .flex_1()
+                    .min_w(px(0.))
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap(px(TOOLBAR_GAP))
+                    .child(measure)
+                    .children(visible)
+                    .children(overflow_button),
+            )
+            .children(self.suffix)
+    }
+}
+
 #[derive(IntoElement)]
 pub struct PlateToolbarSplitButton {
     id: ElementId,
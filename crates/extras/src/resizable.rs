@@ -0,0 +1,171 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use gpui::{Pixels, SharedString, px};
+use gpui_component::resizable::{ResizablePanel, ResizablePanelGroup, resizable_panel};
+
+/// Where the split ratios of resizable panel groups are kept between runs.
+pub trait PanelSizeStorage {
+    /// Ratios last saved under `key`, one per panel.
+    fn load(&self, key: &str) -> Option<Vec<f32>>;
+
+    fn save(&self, key: &str, ratios: &[f32]);
+}
+
+/// Keeps ratios for the lifetime of the process only.
+#[derive(Default)]
+pub struct MemoryPanelSizeStorage {
+    entries: RefCell<BTreeMap<String, Vec<f32>>>,
+}
+
+impl PanelSizeStorage for MemoryPanelSizeStorage {
+    fn load(&self, key: &str) -> Option<Vec<f32>> {
+        self.entries.borrow().get(key).cloned()
+    }
+
+    fn save(&self, key: &str, ratios: &[f32]) {
+        self.entries
+            .borrow_mut()
+            .insert(key.to_string(), ratios.to_vec());
+    }
+}
+
+/// Keeps ratios in a small text file with one `key=ratio,ratio,...` line per group.
+///
+/// The file is read once when the storage is created and rewritten on every save. Saving is
+/// best-effort: a layout that cannot be written is simply not restored next time.
+pub struct FilePanelSizeStorage {
+    path: PathBuf,
+    entries: RefCell<BTreeMap<String, Vec<f32>>>,
+}
+
+impl FilePanelSizeStorage {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let entries = std::fs::read_to_string(&path)
+            .map(|text| parse_entries(&text))
+            .unwrap_or_default();
+        Self {
+            path,
+            entries: RefCell::new(entries),
+        }
+    }
+}
+
+impl PanelSizeStorage for FilePanelSizeStorage {
+    fn load(&self, key: &str) -> Option<Vec<f32>> {
+        self.entries.borrow().get(key).cloned()
+    }
+
+    fn save(&self, key: &str, ratios: &[f32]) {
+        let mut entries = self.entries.borrow_mut();
+        if entries.get(key).is_some_and(|saved| saved == ratios) {
+            return;
+        }
+        entries.insert(key.to_string(), ratios.to_vec());
+
+        if let Some(dir) = self.path.parent() {
+            _ = std::fs::create_dir_all(dir);
+        }
+        _ = std::fs::write(&self.path, format_entries(&entries));
+    }
+}
+
+fn parse_entries(text: &str) -> BTreeMap<String, Vec<f32>> {
+    text.lines()
+        .filter_map(|line| {
+            let (key, ratios) = line.split_once('=')?;
+            let ratios = ratios
+                .split(',')
+                .map(|ratio| ratio.trim().parse().ok())
+                .collect::<Option<Vec<f32>>>()?;
+            Some((key.trim().to_string(), ratios))
+        })
+        .collect()
+}
+
+fn format_entries(entries: &BTreeMap<String, Vec<f32>>) -> String {
+    let mut text = String::new();
+    for (key, ratios) in entries {
+        let ratios: Vec<String> = ratios.iter().map(|ratio| format!("{ratio:.4}")).collect();
+        text.push_str(&format!("{key}={}\n", ratios.join(",")));
+    }
+    text
+}
+
+/// Restores and records the split of one `h_resizable`/`v_resizable` group under a key.
+///
+/// Sizes are stored as ratios of the group's extent, so a split survives window resizes as
+/// well as restarts:
+///
+/// ```ignore
+/// let sizes = PersistedPanelSizes::new("diff-panes", storage.clone());
+/// h_resizable("diff-panes")
+///     .persist(&sizes)
+///     .child(sizes.panel(0, 2, total).child(left))
+///     .child(sizes.panel(1, 2, total).child(right))
+/// ```
+#[derive(Clone)]
+pub struct PersistedPanelSizes {
+    key: SharedString,
+    storage: Rc<dyn PanelSizeStorage>,
+}
+
+impl PersistedPanelSizes {
+    pub fn new(key: impl Into<SharedString>, storage: Rc<dyn PanelSizeStorage>) -> Self {
+        Self {
+            key: key.into(),
+            storage,
+        }
+    }
+
+    /// Saved ratios, if there are exactly `count` of them and they are all usable.
+    pub fn ratios(&self, count: usize) -> Option<Vec<f32>> {
+        self.storage.load(&self.key).filter(|ratios| {
+            ratios.len() == count && ratios.iter().all(|ratio| ratio.is_finite() && *ratio > 0.0)
+        })
+    }
+
+    /// Saved size of panel `ix` out of `count` when the group spans `total`.
+    pub fn size(&self, ix: usize, count: usize, total: Pixels) -> Option<Pixels> {
+        let ratio = *self.ratios(count)?.get(ix)?;
+        Some(px(f32::from(total) * ratio))
+    }
+
+    /// A `resizable_panel()` starting at its saved size, if any. `total` only needs to be
+    /// roughly the group's extent, since the group scales its panels to fit.
+    pub fn panel(&self, ix: usize, count: usize, total: Pixels) -> ResizablePanel {
+        match self.size(ix, count, total) {
+            Some(size) => resizable_panel().size(size),
+            None => resizable_panel(),
+        }
+    }
+
+    /// Records the current panel sizes as ratios.
+    pub fn save_sizes(&self, sizes: &[Pixels]) {
+        let total: f32 = sizes.iter().map(|size| f32::from(*size)).sum();
+        if sizes.is_empty() || total <= 0.0 {
+            return;
+        }
+        let ratios: Vec<f32> = sizes.iter().map(|size| f32::from(*size) / total).collect();
+        self.storage.save(&self.key, &ratios);
+    }
+}
+
+pub trait ResizablePanelGroupExt {
+    /// Saves the split under the key of `sizes` whenever the user resizes a panel.
+    ///
+    /// This installs the group's `on_resize` callback, replacing any set before.
+    fn persist(self, sizes: &PersistedPanelSizes) -> Self;
+}
+
+impl ResizablePanelGroupExt for ResizablePanelGroup {
+    fn persist(self, sizes: &PersistedPanelSizes) -> Self {
+        let sizes = sizes.clone();
+        self.on_resize(move |state, _window, cx| {
+            sizes.save_sizes(state.read(cx).sizes());
+        })
+    }
+}
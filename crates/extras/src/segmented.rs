@@ -0,0 +1,247 @@
+use std::rc::Rc;
+use std::time::Duration;
+
+use gpui::InteractiveElement as _;
+use gpui::StatefulInteractiveElement as _;
+use gpui::prelude::FluentBuilder as _;
+use gpui::{
+    Animation, AnimationExt as _, App, ElementId, IntoElement, MouseButton, ParentElement,
+    RenderOnce, SharedString, StyleRefinement, Styled, Window, div, ease_in_out, px,
+};
+use gpui_component::ActiveTheme as _;
+use gpui_component::Disableable;
+use gpui_component::StyledExt as _;
+use gpui_component::tooltip::Tooltip;
+use gpui_component::{Icon, IconNamed};
+
+const SELECT_ANIMATION: Duration = Duration::from_millis(150);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SegmentedOrientation {
+    #[default]
+    Horizontal,
+    /// Items are stacked full-width, e.g. as a sidebar switcher.
+    Vertical,
+}
+
+/// One segment of an [`AnimatedSegmented`] control.
+pub struct AnimatedSegmentedItem {
+    label: SharedString,
+    icon: Option<Icon>,
+    badge: Option<usize>,
+    tooltip: Option<SharedString>,
+    disabled: bool,
+}
+
+impl AnimatedSegmentedItem {
+    pub fn new(label: impl Into<SharedString>) -> Self {
+        Self {
+            label: label.into(),
+            icon: None,
+            badge: None,
+            tooltip: None,
+            disabled: false,
+        }
+    }
+
+    /// Leading icon shown before the label.
+    pub fn icon(mut self, icon: impl IconNamed) -> Self {
+        self.icon = Some(Icon::new(icon));
+        self
+    }
+
+    /// Count shown as a pill after the label; zero hides it.
+    pub fn badge(mut self, count: usize) -> Self {
+        self.badge = (count > 0).then_some(count);
+        self
+    }
+
+    pub fn tooltip(mut self, tooltip: impl Into<SharedString>) -> Self {
+        self.tooltip = Some(tooltip.into());
+        self
+    }
+}
+
+impl Disableable for AnimatedSegmentedItem {
+    fn disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
+    }
+}
+
+/// A segmented switcher whose selected segment fades in when the selection changes.
+#[derive(IntoElement)]
+pub struct AnimatedSegmented {
+    id: ElementId,
+    style: StyleRefinement,
+    items: Vec<AnimatedSegmentedItem>,
+    selected_index: usize,
+    orientation: SegmentedOrientation,
+    on_change: Option<Rc<dyn Fn(&usize, &mut Window, &mut App)>>,
+}
+
+impl AnimatedSegmented {
+    pub fn new(id: impl Into<ElementId>) -> Self {
+        Self {
+            id: id.into(),
+            style: StyleRefinement::default(),
+            items: Vec::new(),
+            selected_index: 0,
+            orientation: SegmentedOrientation::Horizontal,
+            on_change: None,
+        }
+    }
+
+    pub fn item(mut self, item: AnimatedSegmentedItem) -> Self {
+        self.items.push(item);
+        self
+    }
+
+    pub fn items(mut self, items: impl IntoIterator<Item = AnimatedSegmentedItem>) -> Self {
+        self.items.extend(items);
+        self
+    }
+
+    pub fn selected_index(mut self, index: usize) -> Self {
+        self.selected_index = index;
+        self
+    }
+
+    pub fn orientation(mut self, orientation: SegmentedOrientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    pub fn vertical(self) -> Self {
+        self.orientation(SegmentedOrientation::Vertical)
+    }
+
+    /// Called with the index of a newly selected, enabled segment.
+    pub fn on_change(
+        mut self,
+        on_change: impl Fn(&usize, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.on_change = Some(Rc::new(on_change));
+        self
+    }
+}
+
+impl Styled for AnimatedSegmented {
+    fn style(&mut self) -> &mut StyleRefinement {
+        &mut self.style
+    }
+}
+
+impl RenderOnce for AnimatedSegmented {
+    fn render(self, _window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let theme = cx.theme();
+        let vertical = self.orientation == SegmentedOrientation::Vertical;
+        let selected_index = self.selected_index;
+
+        let mut disabled_fg = theme.muted_foreground;
+        disabled_fg.a *= 0.6;
+
+        let segments = self.items.into_iter().enumerate().map(|(index, item)| {
+            let selected = index == selected_index;
+            let clickable = !item.disabled && !selected;
+            let selected_bg = theme.background;
+
+            let badge = item.badge.map(|count| {
+                let text: SharedString = if count > 99 {
+                    "99+".into()
+                } else {
+                    count.to_string().into()
+                };
+                div()
+                    .flex_none()
+                    .min_w(px(16.))
+                    .h(px(16.))
+                    .px(px(4.))
+                    .rounded_full()
+                    .flex()
+                    .items_center()
+                    .justify_center()
+                    .text_size(px(10.))
+                    .bg(if selected { theme.primary } else { theme.muted })
+                    .text_color(if selected {
+                        theme.primary_foreground
+                    } else {
+                        theme.muted_foreground
+                    })
+                    .child(text)
+            });
+
+            let segment = div()
+                .id(("segment", index))
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap(px(6.))
+                .h(px(28.))
+                .px(px(10.))
+                .rounded(px(6.))
+                .text_size(px(12.))
+                .font_weight(gpui::FontWeight::MEDIUM)
+                .when(vertical, |this| this.w_full().justify_start())
+                .when(!vertical, |this| this.flex_1().justify_center())
+                .text_color(if item.disabled {
+                    disabled_fg
+                } else if selected {
+                    theme.foreground
+                } else {
+                    theme.muted_foreground
+                })
+                .when(selected, |this| this.shadow_sm())
+                .when(clickable, |this| {
+                    this.cursor_pointer()
+                        .hover(|this| this.text_color(theme.foreground))
+                })
+                .when_some(item.icon, |this, icon| this.child(icon.size_3p5()))
+                .child(
+                    div()
+                        .when(vertical, |this| this.flex_1())
+                        .truncate()
+                        .child(item.label),
+                )
+                .children(badge)
+                .on_mouse_down(MouseButton::Left, |_, window, _| {
+                    // Keep focus where it was, as toolbar buttons do.
+                    window.prevent_default();
+                })
+                .when_some(
+                    self.on_change.clone().filter(|_| clickable),
+                    |this, on_change| {
+                        this.on_click(move |_, window, cx| (on_change)(&index, window, cx))
+                    },
+                )
+                .when_some(item.tooltip, |this, tooltip| {
+                    this.tooltip(move |window, cx| Tooltip::new(tooltip.clone()).build(window, cx))
+                });
+
+            if selected {
+                // Keyed by the index so the fade restarts whenever the selection moves.
+                segment
+                    .with_animation(
+                        ("segment-selected", index),
+                        Animation::new(SELECT_ANIMATION).with_easing(ease_in_out),
+                        move |this, delta| this.bg(selected_bg.opacity(delta)),
+                    )
+                    .into_any_element()
+            } else {
+                segment.into_any_element()
+            }
+        });
+
+        div()
+            .id(self.id)
+            .flex()
+            .when(vertical, |this| this.flex_col())
+            .when(!vertical, |this| this.flex_row().items_center())
+            .gap(px(2.))
+            .p(px(2.))
+            .rounded(px(8.))
+            .bg(theme.muted)
+            .refine_style(&self.style)
+            .children(segments)
+    }
+}
@@ -0,0 +1,303 @@
+use std::rc::Rc;
+
+use gpui::InteractiveElement as _;
+use gpui::StatefulInteractiveElement as _;
+use gpui::prelude::FluentBuilder as _;
+use gpui::{
+    AnyElement, App, ClickEvent, ElementId, Hsla, IntoElement, ParentElement, Pixels, RenderOnce,
+    SharedString, StyleRefinement, Styled, Window, canvas, div, px, rems,
+};
+use gpui_component::tooltip::Tooltip;
+use gpui_component::{ActiveTheme as _, Icon, IconName, StyledExt as _};
+
+const BAR_HEIGHT: f32 = 24.;
+const SEGMENT_PADDING: f32 = 6.;
+const SEGMENT_GAP: f32 = 2.;
+/// Matches `size_3p5` on the icon.
+const ICON_SIZE: f32 = 14.;
+const ICON_GAP: f32 = 4.;
+/// Space kept free between the left and right segments.
+const SIDE_GAP: f32 = 16.;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+}
+
+/// One indicator in a [`StatusBar`]: an optional icon followed by a single line of text.
+pub struct StatusBarSegment {
+    id: ElementId,
+    icon: Option<IconName>,
+    label: SharedString,
+    tooltip: Option<SharedString>,
+    text_color: Option<Hsla>,
+    priority: i32,
+    on_click: Option<Rc<dyn Fn(&ClickEvent, &mut Window, &mut App)>>,
+}
+
+impl StatusBarSegment {
+    /// `label` is shown on one line; it must not contain newlines.
+    pub fn new(id: impl Into<ElementId>, label: impl Into<SharedString>) -> Self {
+        Self {
+            id: id.into(),
+            icon: None,
+            label: label.into(),
+            tooltip: None,
+            text_color: None,
+            priority: 0,
+            on_click: None,
+        }
+    }
+
+    pub fn icon(mut self, icon: IconName) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
+    pub fn tooltip(mut self, tooltip: impl Into<SharedString>) -> Self {
+        self.tooltip = Some(tooltip.into());
+        self
+    }
+
+    pub fn text_color(mut self, color: impl Into<Hsla>) -> Self {
+        self.text_color = Some(color.into());
+        self
+    }
+
+    /// When the bar is too narrow, segments with the lowest priority are hidden first.
+    /// Defaults to 0.
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn on_click(
+        mut self,
+        on_click: impl Fn(&ClickEvent, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.on_click = Some(Rc::new(on_click));
+        self
+    }
+
+    fn measure(&self, window: &Window) -> Pixels {
+        let font_size = rems(0.75).to_pixels(window.rem_size());
+        let run = window.text_style().to_run(self.label.len());
+        let text = window
+            .text_system()
+            .shape_line(self.label.clone(), font_size, &[run], None)
+            .width;
+        let icon = if self.icon.is_some() {
+            px(ICON_SIZE + ICON_GAP)
+        } else {
+            px(0.)
+        };
+        text + icon + px(SEGMENT_PADDING * 2.)
+    }
+
+    fn render(self, cx: &App) -> AnyElement {
+        let theme = cx.theme();
+        let on_click = self.on_click;
+
+        div()
+            .id(self.id)
+            .flex()
+            .flex_row()
+            .flex_none()
+            .items_center()
+            .gap(px(ICON_GAP))
+            .h_full()
+            .px(px(SEGMENT_PADDING))
+            .whitespace_nowrap()
+            .when_some(self.text_color, |this, color| this.text_color(color))
+            .when_some(self.icon, |this, icon| {
+                this.child(Icon::new(icon).size_3p5())
+            })
+            .child(self.label)
+            .when_some(self.tooltip, |this, tooltip| {
+                this.tooltip(move |window, cx| Tooltip::new(tooltip.clone()).build(window, cx))
+            })
+            .when_some(on_click, |this, on_click| {
+                this.cursor_pointer()
+                    .hover(|this| this.bg(theme.accent.alpha(0.5)))
+                    .on_click(move |event, window, cx| on_click(event, window, cx))
+            })
+            .into_any_element()
+    }
+}
+
+#[derive(Default)]
+struct StatusBarLayout {
+    width: Option<Pixels>,
+}
+
+/// A bar of indicators along the bottom of a window, with segments aligned to either edge.
+///
+/// Segments are added in order, outside in: the first left segment sits at the left edge and
+/// the first right segment at the right edge. When they do not all fit, whole segments are
+/// hidden in order of priority; among equal priorities the one nearest the middle goes first.
+#[derive(IntoElement)]
+pub struct StatusBar {
+    id: ElementId,
+    style: StyleRefinement,
+    left: Vec<StatusBarSegment>,
+    right: Vec<StatusBarSegment>,
+}
+
+impl StatusBar {
+    pub fn new(id: impl Into<ElementId>) -> Self {
+        Self {
+            id: id.into(),
+            style: StyleRefinement::default(),
+            left: Vec::new(),
+            right: Vec::new(),
+        }
+    }
+
+    pub fn left(mut self, segment: StatusBarSegment) -> Self {
+        self.left.push(segment);
+        self
+    }
+
+    pub fn right(mut self, segment: StatusBarSegment) -> Self {
+        self.right.push(segment);
+        self
+    }
+
+    pub fn left_segments(mut self, segments: impl IntoIterator<Item = StatusBarSegment>) -> Self {
+        self.left.extend(segments);
+        self
+    }
+
+    pub fn right_segments(mut self, segments: impl IntoIterator<Item = StatusBarSegment>) -> Self {
+        self.right.extend(segments);
+        self
+    }
+}
+
+impl Styled for StatusBar {
+    fn style(&mut self) -> &mut StyleRefinement {
+        &mut self.style
+    }
+}
+
+/// Picks the segments to hide so that the rest fit in `available`.
+fn hidden_segments(
+    widths: &[(Side, usize, i32, Pixels)],
+    available: Option<Pixels>,
+) -> Vec<(Side, usize)> {
+    let Some(available) = available else {
+        // Before the first measurement everything is shown; the next frame corrects it.
+        return Vec::new();
+    };
+
+    let mut shown: Vec<&(Side, usize, i32, Pixels)> = widths.iter().collect();
+    let total = |shown: &[&(Side, usize, i32, Pixels)]| {
+        shown
+            .iter()
+            .fold(px(0.), |total, (_, _, _, width)| total + *width)
+            + px(SEGMENT_GAP) * shown.len().saturating_sub(1) as f32
+            + px(SIDE_GAP)
+    };
+
+    let mut hidden = Vec::new();
+    while !shown.is_empty() && total(&shown) > available {
+        let Some(victim) = shown
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, (_, ix, priority, _))| (*priority, std::cmp::Reverse(*ix)))
+            .map(|(position, _)| position)
+        else {
+            break;
+        };
+        let (side, ix, _, _) = shown.remove(victim);
+        hidden.push((*side, *ix));
+    }
+    hidden
+}
+
+impl RenderOnce for StatusBar {
+    fn render(self, window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let state = window.use_keyed_state(self.id.clone(), cx, |_, _| StatusBarLayout::default());
+        let available = state.read(cx).width;
+
+        let widths: Vec<(Side, usize, i32, Pixels)> =
+            self.left
+                .iter()
+                .enumerate()
+                .map(|(ix, segment)| (Side::Left, ix, segment.priority, segment.measure(window)))
+                .chain(self.right.iter().enumerate().map(|(ix, segment)| {
+                    (Side::Right, ix, segment.priority, segment.measure(window))
+                }))
+                .collect();
+        let hidden = hidden_segments(&widths, available);
+
+        let visible = |side: Side, segments: Vec<StatusBarSegment>, cx: &App| {
+            segments
+                .into_iter()
+                .enumerate()
+                .filter(|(ix, _)| !hidden.contains(&(side, *ix)))
+                .map(|(_, segment)| segment.render(cx))
+                .collect::<Vec<_>>()
+        };
+        let left = visible(Side::Left, self.left, cx);
+        // Right segments are listed from the edge inwards but laid out left to right.
+        let mut right = visible(Side::Right, self.right, cx);
+        right.reverse();
+
+        let measure = {
+            let state = state.clone();
+            canvas(
+                move |bounds, _, cx| {
+                    state.update(cx, |state, cx| {
+                        if state.width != Some(bounds.size.width) {
+                            state.width = Some(bounds.size.width);
+                            cx.notify();
+                        }
+                    });
+                },
+                |_, _, _, _| {},
+            )
+            .absolute()
+            .size_full()
+        };
+
+        let theme = cx.theme();
+        div()
+            .id(self.id)
+            .relative()
+            .flex()
+            .flex_row()
+            .items_center()
+            .justify_between()
+            .flex_none()
+            .w_full()
+            .h(px(BAR_HEIGHT))
+            .overflow_hidden()
+            .border_t_1()
+            .border_color(theme.border)
+            .bg(theme.background)
+            .text_xs()
+            .text_color(theme.muted_foreground)
+            .child(measure)
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .h_full()
+                    .gap(px(SEGMENT_GAP))
+                    .children(left),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .h_full()
+                    .gap(px(SEGMENT_GAP))
+                    .children(right),
+            )
+            .refine_style(&self.style)
+    }
+}
@@ -0,0 +1,155 @@
+//! GIT_ASKPASS / SSH_ASKPASS 桥接。
+//!
+//! 启动时在 127.0.0.1 上监听一个随机端口，并把 `GIT_ASKPASS`/`SSH_ASKPASS` 指向当前可执行文件。
+//! git/ssh 需要凭据时会以 askpass 模式重新启动 git-viewer，后者通过环境变量找到监听地址，
+//! 把提示发给正在运行的 UI，再把用户输入写到 stdout 交还给 git。
+
+use std::io::{BufRead as _, BufReader, Write as _};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+
+use anyhow::{Context as _, Result, anyhow};
+
+const ADDR_ENV: &str = "GIT_VIEWER_ASKPASS_ADDR";
+const TOKEN_ENV: &str = "GIT_VIEWER_ASKPASS_TOKEN";
+
+/// 一次来自 git/ssh 的凭据请求；调用 [`AskpassRequest::respond`] 把结果交还给 askpass 进程。
+/// 直接 drop 等同于取消。
+pub struct AskpassRequest {
+    pub prompt: String,
+    reply: mpsc::Sender<Option<String>>,
+}
+
+impl AskpassRequest {
+    /// 密码、口令、token 之类的提示需要遮挡输入。
+    pub fn is_secret(&self) -> bool {
+        let prompt = self.prompt.to_lowercase();
+        ["password", "passphrase", "token", "pin", "密码", "口令"]
+            .iter()
+            .any(|keyword| prompt.contains(keyword))
+    }
+
+    pub fn respond(self, answer: Option<String>) {
+        self.reply.send(answer).ok();
+    }
+}
+
+/// 当前进程是被 git/ssh 当作 askpass 程序启动时，转发提示并返回退出码。
+pub fn run_client_if_requested() -> Option<i32> {
+    let addr = std::env::var(ADDR_ENV).ok()?;
+    let token = std::env::var(TOKEN_ENV).unwrap_or_default();
+    let prompt = std::env::args().skip(1).collect::<Vec<_>>().join(" ");
+
+    match request_answer(&addr, &token, &prompt) {
+        Ok(Some(answer)) => {
+            println!("{answer}");
+            Some(0)
+        }
+        Ok(None) => Some(1),
+        Err(err) => {
+            eprintln!("git-viewer askpass: {err:#}");
+            Some(1)
+        }
+    }
+}
+
+/// 启动 askpass 服务并设置子进程继承的环境变量。
+///
+/// 必须在创建其它线程之前调用（会修改进程环境变量）。
+pub fn start_server() -> Result<mpsc::Receiver<AskpassRequest>> {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).context("启动 askpass 服务失败")?;
+    let addr = listener.local_addr().context("读取 askpass 服务地址失败")?;
+    let exe = std::env::current_exe().context("读取当前可执行文件路径失败")?;
+    let token = generate_token()?;
+
+    // SAFETY: 由 main 在启动任何其它线程之前调用，此时没有并发读取环境变量的线程。
+    unsafe {
+        std::env::set_var(ADDR_ENV, addr.to_string());
+        std::env::set_var(TOKEN_ENV, &token);
+        std::env::set_var("GIT_ASKPASS", &exe);
+        std::env::set_var("SSH_ASKPASS", &exe);
+        std::env::set_var("SSH_ASKPASS_REQUIRE", "prefer");
+        std::env::set_var("GIT_TERMINAL_PROMPT", "0");
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    thread::Builder::new()
+        .name("git-viewer-askpass".into())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let sender = sender.clone();
+                let token = token.clone();
+                thread::spawn(move || {
+                    if let Err(err) = handle_connection(stream, &token, sender) {
+                        tracing::warn!("askpass 连接失败：{err:#}");
+                    }
+                });
+            }
+        })
+        .context("启动 askpass 线程失败")?;
+
+    Ok(receiver)
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    token: &str,
+    sender: mpsc::Sender<AskpassRequest>,
+) -> Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut received_token = String::new();
+    reader.read_line(&mut received_token)?;
+    if received_token.trim_end() != token {
+        return Err(anyhow!("token 不匹配"));
+    }
+
+    let mut prompt = String::new();
+    reader.read_line(&mut prompt)?;
+
+    let (reply, answer) = mpsc::channel();
+    sender
+        .send(AskpassRequest {
+            prompt: prompt.trim_end().to_string(),
+            reply,
+        })
+        .map_err(|_| anyhow!("UI 已退出"))?;
+
+    let mut stream = stream;
+    match answer.recv().ok().flatten() {
+        Some(answer) => writeln!(stream, "ok\n{answer}")?,
+        None => writeln!(stream, "cancel")?,
+    }
+    Ok(())
+}
+
+fn request_answer(addr: &str, token: &str, prompt: &str) -> Result<Option<String>> {
+    let mut stream = TcpStream::connect(addr).context("连接 git-viewer 失败")?;
+    writeln!(stream, "{token}")?;
+    writeln!(stream, "{}", prompt.replace(['\r', '\n'], " "))?;
+
+    let mut reader = BufReader::new(stream);
+    let mut status = String::new();
+    reader.read_line(&mut status)?;
+    if status.trim_end() != "ok" {
+        return Ok(None);
+    }
+
+    let mut answer = String::new();
+    reader.read_line(&mut answer)?;
+    Ok(Some(answer.trim_end_matches(['\r', '\n']).to_string()))
+}
+
+/// 用系统的安全随机数生成 128 位 token，十六进制编码。
+fn generate_token() -> Result<String> {
+    use ring::rand::{SecureRandom as _, SystemRandom};
+
+    let mut bytes = [0u8; 16];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| anyhow!("生成 askpass token 失败"))?;
+    Ok(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+}
@@ -0,0 +1,147 @@
+//! 文本编码识别与转换。
+//!
+//! 依次看 BOM、是否为合法 UTF-8；都不是时交给 chardetng 猜测编码。
+//! 含 NUL 的内容视为二进制，仍按 UTF-8 有损解码。
+
+use anyhow::{Result, anyhow};
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextEncoding {
+    pub encoding: &'static Encoding,
+    pub bom: bool,
+}
+
+impl TextEncoding {
+    pub const UTF8: Self = Self {
+        encoding: UTF_8,
+        bom: false,
+    };
+
+    /// 不带 BOM 的 UTF-8，界面上不需要特别提示。
+    pub fn is_plain_utf8(&self) -> bool {
+        *self == Self::UTF8
+    }
+
+    pub fn label(&self) -> String {
+        if self.bom {
+            format!("{} BOM", self.encoding.name())
+        } else {
+            self.encoding.name().to_string()
+        }
+    }
+
+    /// 按原编码写回；有字符无法用该编码表示时报错，而不是像 encoding_rs 默认那样写入 `&#NNNN;`。
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(text.len() + 3);
+        if self.encoding == UTF_16LE || self.encoding == UTF_16BE {
+            if self.bom {
+                bytes.extend_from_slice(if self.encoding == UTF_16LE {
+                    b"\xff\xfe"
+                } else {
+                    b"\xfe\xff"
+                });
+            }
+            for unit in text.encode_utf16() {
+                bytes.extend_from_slice(&if self.encoding == UTF_16LE {
+                    unit.to_le_bytes()
+                } else {
+                    unit.to_be_bytes()
+                });
+            }
+            return Ok(bytes);
+        }
+
+        if self.bom {
+            bytes.extend_from_slice(b"\xef\xbb\xbf");
+        }
+        let (encoded, _, unmappable) = self.encoding.encode(text);
+        if unmappable {
+            return Err(anyhow!("部分字符无法用 {} 表示", self.encoding.name()));
+        }
+        bytes.extend_from_slice(&encoded);
+        Ok(bytes)
+    }
+}
+
+pub fn decode(bytes: &[u8]) -> (String, TextEncoding) {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return (
+            text.into_owned(),
+            TextEncoding {
+                encoding,
+                bom: true,
+            },
+        );
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return (text.to_string(), TextEncoding::UTF8);
+    }
+    if bytes.contains(&0) {
+        return (
+            String::from_utf8_lossy(bytes).into_owned(),
+            TextEncoding::UTF8,
+        );
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, false);
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    (
+        text.into_owned(),
+        TextEncoding {
+            encoding,
+            bom: false,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding_rs::{BIG5, EUC_KR, GBK, SHIFT_JIS, WINDOWS_1252};
+
+    use super::*;
+
+    #[test]
+    fn detects_legacy_encodings() {
+        let samples: [(&str, &'static Encoding); 5] = [
+            (
+                "// 注释：读取配置文件，失败时使用默认值。\nfn main() {}\n",
+                GBK,
+            ),
+            (
+                "// 設定ファイルを読み込み、失敗したら既定値を使います。\n",
+                SHIFT_JIS,
+            ),
+            (
+                "// 설정 파일을 읽고 실패하면 기본값을 사용합니다.\n",
+                EUC_KR,
+            ),
+            ("// 讀取設定檔，失敗時使用預設值。\nfn main() {}\n", BIG5),
+            ("// Café crème, naïve façade, déjà vu.\n", WINDOWS_1252),
+        ];
+        for (text, encoding) in samples {
+            let (bytes, _, unmappable) = encoding.encode(text);
+            assert!(!unmappable);
+            let (decoded, detected) = decode(&bytes);
+            assert_eq!(detected.encoding, encoding, "{text}");
+            assert_eq!(decoded, text);
+            assert_eq!(detected.encode(&decoded).unwrap(), bytes.into_owned());
+        }
+    }
+
+    #[test]
+    fn keeps_boms() {
+        let (text, detected) = decode(b"\xef\xbb\xbfhi");
+        assert_eq!(text, "hi");
+        assert_eq!(detected.label(), "UTF-8 BOM");
+
+        let (text, detected) = decode(b"\xff\xfe\x2d\x4e\x61\x00");
+        assert_eq!(text, "中a");
+        assert_eq!(detected.encoding, UTF_16LE);
+        assert_eq!(detected.encode(&text).unwrap(), b"\xff\xfe\x2d\x4e\x61\x00");
+    }
+}
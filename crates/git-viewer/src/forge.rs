@@ -0,0 +1,539 @@
+//! GitHub pull request / GitLab merge request 集成。
+//!
+//! 根据 remote 的 URL 识别托管平台，通过 `curl` 子进程调用 REST API，和 git 操作一样走子进程，
+//! 不引入 HTTP 客户端。token 依次取自 `GIT_VIEWER_FORGE_TOKEN`、`GITHUB_TOKEN` / `GITLAB_TOKEN`
+//! 和配置文件 `[git-viewer]` 表的 `forge_tokens`（按主机名）；没有 token 时匿名访问，只能读取公开仓库。
+
+use std::io::Write as _;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context as _, Result, anyhow};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+use crate::{GitViewerSettings, run_git, run_git_output};
+
+const TOKEN_ENV: &str = "GIT_VIEWER_FORGE_TOKEN";
+/// 一次列表请求最多返回的条数（两个平台的单页上限）。
+const PAGE_SIZE: usize = 100;
+const REQUEST_TIMEOUT_SECS: &str = "30";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ForgeKind {
+    GitHub,
+    GitLab,
+}
+
+impl ForgeKind {
+    /// 界面上对一个变更请求的称呼。
+    pub fn request_label(self) -> &'static str {
+        match self {
+            ForgeKind::GitHub => "PR",
+            ForgeKind::GitLab => "MR",
+        }
+    }
+
+    fn token_env(self) -> &'static str {
+        match self {
+            ForgeKind::GitHub => "GITHUB_TOKEN",
+            ForgeKind::GitLab => "GITLAB_TOKEN",
+        }
+    }
+}
+
+/// 托管在 GitHub / GitLab 上的 remote。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForgeRemote {
+    pub kind: ForgeKind,
+    /// remote 名，例如 `origin`。
+    pub name: String,
+    /// 主机名，https 地址带端口时包含端口。
+    pub host: String,
+    /// `owner/repo`；GitLab 可以有多级 group。
+    pub project: String,
+}
+
+impl ForgeRemote {
+    /// 优先使用 `origin`，没有时取第一个能识别的 remote；都不是 GitHub / GitLab 时返回 `None`。
+    pub fn detect(repo_root: &Path) -> Result<Option<Self>> {
+        let output = run_git_output(repo_root, ["remote"])?;
+        let mut names: Vec<&str> = output
+            .lines()
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .collect();
+        names.sort_by_key(|name| *name != "origin");
+
+        for name in names {
+            let url = run_git_output(repo_root, ["remote", "get-url", name])?;
+            if let Some(remote) = Self::from_url(name, url.trim()) {
+                return Ok(Some(remote));
+            }
+        }
+        Ok(None)
+    }
+
+    fn from_url(name: &str, url: &str) -> Option<Self> {
+        let (host, project) = parse_remote_url(url)?;
+        let host_name = host.split(':').next().unwrap_or(&host).to_lowercase();
+        let kind = if host_name.contains("github") {
+            ForgeKind::GitHub
+        } else if host_name.contains("gitlab") {
+            ForgeKind::GitLab
+        } else {
+            return None;
+        };
+        Some(Self {
+            kind,
+            name: name.to_string(),
+            host,
+            project,
+        })
+    }
+
+    fn api_base(&self) -> String {
+        match self.kind {
+            ForgeKind::GitHub if self.host == "github.com" => "https://api.github.com".to_string(),
+            // GitHub Enterprise Server
+            ForgeKind::GitHub => format!("https://{}/api/v3", self.host),
+            ForgeKind::GitLab => format!("https://{}/api/v4", self.host),
+        }
+    }
+
+    fn project_url(&self) -> String {
+        match self.kind {
+            ForgeKind::GitHub => format!("{}/repos/{}", self.api_base(), self.project),
+            ForgeKind::GitLab => format!(
+                "{}/projects/{}",
+                self.api_base(),
+                self.project.replace('/', "%2F")
+            ),
+        }
+    }
+
+    /// 平台为变更请求保留的 head ref。
+    fn pull_ref(&self, number: u64) -> String {
+        match self.kind {
+            ForgeKind::GitHub => format!("refs/pull/{number}/head"),
+            ForgeKind::GitLab => format!("refs/merge-requests/{number}/head"),
+        }
+    }
+
+    /// 抓取到本地的 head 所在 ref；放在单独的命名空间里，不出现在分支列表中。
+    pub fn local_ref(&self, number: u64) -> String {
+        format!(
+            "refs/git-viewer/{}/{number}",
+            self.kind.request_label().to_lowercase()
+        )
+    }
+
+    /// 目标分支对应的远程跟踪分支。
+    pub fn target_ref(&self, request: &PullRequest) -> String {
+        format!("{}/{}", self.name, request.target_branch)
+    }
+
+    /// 依次查找环境变量和配置文件里的 token。
+    pub fn token(&self) -> Option<String> {
+        [TOKEN_ENV, self.kind.token_env()]
+            .into_iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .map(|token| token.trim().to_string())
+            .find(|token| !token.is_empty())
+            .or_else(|| {
+                GitViewerSettings::get()
+                    .forge_tokens
+                    .get(&self.host)
+                    .cloned()
+            })
+    }
+}
+
+/// 从 remote URL 中取出 `(主机, 项目路径)`，支持 https、`ssh://` 和 scp 风格（`git@host:owner/repo`）。
+fn parse_remote_url(url: &str) -> Option<(String, String)> {
+    let (host, path) = if let Some((scheme, rest)) = url.split_once("://") {
+        let (authority, path) = rest.split_once('/')?;
+        let host = authority.rsplit('@').next()?;
+        // ssh 地址里的端口是 ssh 端口，和 API 无关。
+        let host = if scheme.starts_with("http") {
+            host
+        } else {
+            host.split(':').next()?
+        };
+        (host, path)
+    } else {
+        let (authority, path) = url.split_once(':')?;
+        (authority.rsplit('@').next()?, path)
+    };
+
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    if host.is_empty() || !path.contains('/') {
+        return None;
+    }
+    Some((host.to_string(), path.to_string()))
+}
+
+/// 保存某个主机的 token；空字符串表示删除。
+pub fn save_token(host: &str, token: &str) -> Result<()> {
+    let store = manos_config::ConfigStore::open_default()?;
+    store
+        .namespace::<GitViewerSettings>("git-viewer")
+        .update(|settings| {
+            if token.is_empty() {
+                settings.forge_tokens.remove(host);
+            } else {
+                settings
+                    .forge_tokens
+                    .insert(host.to_string(), token.to_string());
+            }
+        })?;
+    Ok(())
+}
+
+#[derive(Clone, Debug)]
+pub struct PullRequest {
+    /// GitHub 的 PR 编号 / GitLab 的 MR iid。
+    pub number: u64,
+    pub title: String,
+    pub author: String,
+    pub draft: bool,
+    pub source_branch: String,
+    pub target_branch: String,
+    pub url: String,
+    pub body: String,
+    pub updated_at: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct PullRequestComment {
+    pub author: String,
+    pub body: String,
+    pub created_at: String,
+    /// 行内评论所在的文件；整体评论为 `None`。
+    pub path: Option<String>,
+    /// 行内评论在新文件中的行号（从 1 开始）；评论针对已删除的行时为 `None`。
+    pub line: Option<usize>,
+}
+
+/// 打开状态的变更请求，按更新时间从新到旧。
+pub fn list_pull_requests(remote: &ForgeRemote, token: Option<&str>) -> Result<Vec<PullRequest>> {
+    let url = remote.project_url();
+    match remote.kind {
+        ForgeKind::GitHub => {
+            let pulls: Vec<GitHubPull> = get_json(
+                remote,
+                token,
+                &format!("{url}/pulls?state=open&sort=updated&direction=desc&per_page={PAGE_SIZE}"),
+            )?;
+            Ok(pulls.into_iter().map(PullRequest::from).collect())
+        }
+        ForgeKind::GitLab => {
+            let requests: Vec<GitLabMergeRequest> = get_json(
+                remote,
+                token,
+                &format!(
+                    "{url}/merge_requests?state=opened&order_by=updated_at&per_page={PAGE_SIZE}"
+                ),
+            )?;
+            Ok(requests.into_iter().map(PullRequest::from).collect())
+        }
+    }
+}
+
+/// 整体评论和行内评论，按时间排序。
+pub fn pull_request_comments(
+    remote: &ForgeRemote,
+    token: Option<&str>,
+    number: u64,
+) -> Result<Vec<PullRequestComment>> {
+    let url = remote.project_url();
+    let mut comments = match remote.kind {
+        ForgeKind::GitHub => {
+            let issue: Vec<GitHubComment> = get_json(
+                remote,
+                token,
+                &format!("{url}/issues/{number}/comments?per_page={PAGE_SIZE}"),
+            )?;
+            let review: Vec<GitHubComment> = get_json(
+                remote,
+                token,
+                &format!("{url}/pulls/{number}/comments?per_page={PAGE_SIZE}"),
+            )?;
+            issue
+                .into_iter()
+                .chain(review)
+                .map(PullRequestComment::from)
+                .collect::<Vec<_>>()
+        }
+        ForgeKind::GitLab => {
+            let notes: Vec<GitLabNote> = get_json(
+                remote,
+                token,
+                &format!("{url}/merge_requests/{number}/notes?sort=asc&per_page={PAGE_SIZE}"),
+            )?;
+            notes
+                .into_iter()
+                .filter(|note| !note.system)
+                .map(PullRequestComment::from)
+                .collect()
+        }
+    };
+    // 两个平台都返回 ISO 8601 时间，按字符串排序即按时间排序。
+    comments.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    Ok(comments)
+}
+
+/// 把变更请求的 head 抓取到 [`ForgeRemote::local_ref`]，并更新目标分支的远程跟踪分支。
+pub fn fetch_pull_request(
+    repo_root: &Path,
+    remote: &ForgeRemote,
+    request: &PullRequest,
+) -> Result<()> {
+    let head = format!(
+        "+{}:{}",
+        remote.pull_ref(request.number),
+        remote.local_ref(request.number)
+    );
+    let target = format!(
+        "+refs/heads/{}:refs/remotes/{}",
+        request.target_branch,
+        remote.target_ref(request)
+    );
+    run_git(
+        repo_root,
+        [
+            "fetch",
+            remote.name.as_str(),
+            head.as_str(),
+            target.as_str(),
+        ],
+    )
+}
+
+/// 抓取后切换到本地分支 `pr/<编号>`（GitLab 为 `mr/<编号>`），返回分支名。
+///
+/// 分支已存在时直接切换过去，不会覆盖其中的本地提交。
+pub fn checkout_pull_request(
+    repo_root: &Path,
+    remote: &ForgeRemote,
+    request: &PullRequest,
+) -> Result<String> {
+    fetch_pull_request(repo_root, remote, request)?;
+
+    let branch = format!(
+        "{}/{}",
+        remote.kind.request_label().to_lowercase(),
+        request.number
+    );
+    let exists = run_git(
+        repo_root,
+        [
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("refs/heads/{branch}"),
+        ],
+    )
+    .is_ok();
+    if exists {
+        run_git(repo_root, ["switch", branch.as_str()])?;
+    } else {
+        let start = remote.local_ref(request.number);
+        run_git(repo_root, ["switch", "-c", branch.as_str(), start.as_str()])?;
+    }
+    Ok(branch)
+}
+
+/// token 经 stdin 交给 curl（`-H @-`），不出现在进程参数里。
+fn get_json<T: DeserializeOwned>(
+    remote: &ForgeRemote,
+    token: Option<&str>,
+    url: &str,
+) -> Result<T> {
+    let mut command = Command::new("curl");
+    command
+        .args([
+            "-sS",
+            "-L",
+            "--fail-with-body",
+            "--max-time",
+            REQUEST_TIMEOUT_SECS,
+        ])
+        .args(["-H", "User-Agent: git-viewer"]);
+    if remote.kind == ForgeKind::GitHub {
+        command.args(["-H", "Accept: application/vnd.github+json"]);
+    }
+    if token.is_some() {
+        command.args(["-H", "@-"]);
+    }
+    let mut child = command
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("执行 curl 失败")?;
+
+    if let Some(token) = token {
+        let header = match remote.kind {
+            ForgeKind::GitHub => format!("Authorization: Bearer {token}\n"),
+            ForgeKind::GitLab => format!("PRIVATE-TOKEN: {token}\n"),
+        };
+        let mut stdin = child.stdin.take().context("写入 curl 输入失败")?;
+        stdin
+            .write_all(header.as_bytes())
+            .context("写入 curl 输入失败")?;
+    }
+    // 关闭 stdin，curl 才会开始请求。
+    drop(child.stdin.take());
+
+    let output = child.wait_with_output().context("等待 curl 结束失败")?;
+    if !output.status.success() {
+        let message = serde_json::from_slice::<ErrorBody>(&output.stdout)
+            .ok()
+            .and_then(|body| body.message)
+            .unwrap_or_else(|| String::from_utf8_lossy(&output.stderr).trim().to_string());
+        return Err(anyhow!("请求 {url} 失败：{message}"));
+    }
+    serde_json::from_slice(&output.stdout).with_context(|| format!("解析 {url} 的响应失败"))
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    #[serde(alias = "error")]
+    message: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GitHubUser {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct GitHubBranch {
+    #[serde(rename = "ref")]
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct GitHubPull {
+    number: u64,
+    title: String,
+    /// 已注销的账号为 null。
+    user: Option<GitHubUser>,
+    #[serde(default)]
+    draft: bool,
+    head: GitHubBranch,
+    base: GitHubBranch,
+    html_url: String,
+    body: Option<String>,
+    updated_at: String,
+}
+
+impl From<GitHubPull> for PullRequest {
+    fn from(pull: GitHubPull) -> Self {
+        Self {
+            number: pull.number,
+            title: pull.title,
+            author: pull.user.map(|user| user.login).unwrap_or_default(),
+            draft: pull.draft,
+            source_branch: pull.head.name,
+            target_branch: pull.base.name,
+            url: pull.html_url,
+            body: pull.body.unwrap_or_default(),
+            updated_at: pull.updated_at,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct GitHubComment {
+    user: Option<GitHubUser>,
+    body: Option<String>,
+    created_at: String,
+    /// 以下两项只有行内评论才有。
+    path: Option<String>,
+    line: Option<usize>,
+}
+
+impl From<GitHubComment> for PullRequestComment {
+    fn from(comment: GitHubComment) -> Self {
+        Self {
+            author: comment.user.map(|user| user.login).unwrap_or_default(),
+            body: comment.body.unwrap_or_default(),
+            created_at: comment.created_at,
+            path: comment.path,
+            line: comment.line,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct GitLabUser {
+    username: String,
+}
+
+#[derive(Deserialize)]
+struct GitLabMergeRequest {
+    iid: u64,
+    title: String,
+    author: Option<GitLabUser>,
+    #[serde(default)]
+    draft: bool,
+    source_branch: String,
+    target_branch: String,
+    web_url: String,
+    description: Option<String>,
+    updated_at: String,
+}
+
+impl From<GitLabMergeRequest> for PullRequest {
+    fn from(request: GitLabMergeRequest) -> Self {
+        Self {
+            number: request.iid,
+            title: request.title,
+            author: request.author.map(|user| user.username).unwrap_or_default(),
+            draft: request.draft,
+            source_branch: request.source_branch,
+            target_branch: request.target_branch,
+            url: request.web_url,
+            body: request.description.unwrap_or_default(),
+            updated_at: request.updated_at,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct GitLabPosition {
+    new_path: Option<String>,
+    old_path: Option<String>,
+    new_line: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct GitLabNote {
+    author: Option<GitLabUser>,
+    body: String,
+    created_at: String,
+    /// 指派、推送等系统生成的记录。
+    #[serde(default)]
+    system: bool,
+    position: Option<GitLabPosition>,
+}
+
+impl From<GitLabNote> for PullRequestComment {
+    fn from(note: GitLabNote) -> Self {
+        let (path, line) = match note.position {
+            Some(position) => (position.new_path.or(position.old_path), position.new_line),
+            None => (None, None),
+        };
+        Self {
+            author: note.author.map(|user| user.username).unwrap_or_default(),
+            body: note.body,
+            created_at: note.created_at,
+            path,
+            line,
+        }
+    }
+}
@@ -0,0 +1,697 @@
+//! git 读操作的后端。
+//!
+//! 状态、blob、引用、日志这类读操作经由 [`GitBackend`]；stage / commit / apply 等写操作
+//! 仍直接调用 git 命令。后端由配置项 `git_backend` 选择：`cli`（默认）启动 git 子进程，
+//! `gix` 用 gitoxide 在进程内读取，不启动子进程，输出也不受 git 的语言设置影响。
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead as _, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+
+use anyhow::{Context as _, Result, anyhow};
+
+use crate::{
+    CommitEntry, CommitSignature, FileEntry, GitViewerSettings, ReflogEntry, parse_type_1_record,
+    parse_type_2_record, parse_unmerged_record,
+};
+
+/// 一个引用及其直接指向的对象。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RefEntry {
+    /// 完整的引用名，例如 `refs/bisect/bad`。
+    pub name: String,
+    pub id: String,
+}
+
+pub trait GitBackend: Send + Sync {
+    /// `git status --porcelain=v2` 对应的变更列表。
+    fn status(&self, repo_root: &Path) -> Result<Vec<FileEntry>>;
+
+    /// 按 `<rev>:<path>` / `:<stage>:<path>` 读取 blob 的原始字节，由调用方识别编码。
+    fn read_blob(&self, repo_root: &Path, spec: &str) -> Result<Vec<u8>>;
+
+    /// 修改过 `path` 的最近 `limit` 个提交，从新到旧。
+    fn file_log(&self, repo_root: &Path, path: &str, limit: usize) -> Result<Vec<CommitEntry>>;
+
+    /// HEAD 的 reflog，从新到旧。
+    fn reflog(&self, repo_root: &Path, limit: usize) -> Result<Vec<ReflogEntry>>;
+
+    /// 名字以 `prefix` 开头的引用，按名字排序。
+    fn refs(&self, repo_root: &Path, prefix: &str) -> Result<Vec<RefEntry>>;
+}
+
+/// 返回当前进程使用的后端；首次调用时根据配置确定，之后不再变化。
+pub fn backend() -> &'static dyn GitBackend {
+    static BACKEND: OnceLock<Box<dyn GitBackend>> = OnceLock::new();
+    BACKEND
+        .get_or_init(|| {
+            let requested = GitViewerSettings::get().git_backend.as_deref();
+            match requested.unwrap_or_default().trim() {
+                "" | "cli" => Box::new(CliBackend),
+                "gix" => Box::new(GixBackend::default()),
+                other => {
+                    tracing::warn!("未知的 git 后端 {other}，改用 git 子进程");
+                    Box::new(CliBackend)
+                }
+            }
+        })
+        .as_ref()
+}
+
+/// 通过 git 子进程读取。
+struct CliBackend;
+
+impl GitBackend for CliBackend {
+    /// 边读边解析 `git status --porcelain=v2 -z` 的输出，超大仓库也不必先把全部输出读进内存。
+    /// 固定 `--untracked-files=normal`：整个未跟踪的目录只占一条 `dir/` 记录，
+    /// 不受 `status.showUntrackedFiles=all` 影响。
+    fn status(&self, repo_root: &Path) -> Result<Vec<FileEntry>> {
+        let mut child = Command::new("git")
+            .arg("-C")
+            .arg(repo_root)
+            .args(["status", "--porcelain=v2", "-z", "--untracked-files=normal"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("执行 git status 失败")?;
+        let stdout = child.stdout.take().context("读取 git status 输出失败")?;
+
+        let mut reader = BufReader::new(stdout);
+        let mut entries = Vec::new();
+        let mut record = Vec::new();
+        let mut skip_orig_path = false;
+        loop {
+            record.clear();
+            if reader
+                .read_until(b'\0', &mut record)
+                .context("读取 git status 输出失败")?
+                == 0
+            {
+                break;
+            }
+            if record.last() == Some(&b'\0') {
+                record.pop();
+            }
+            // 重命名 / 复制记录（`2 `）后面紧跟一段原路径。
+            if std::mem::take(&mut skip_orig_path) || record.is_empty() {
+                continue;
+            }
+
+            let record = String::from_utf8_lossy(&record);
+            skip_orig_path = record.starts_with("2 ");
+            if let Some(entry) = parse_status_record(&record) {
+                entries.push(entry);
+            }
+        }
+
+        let status = child.wait().context("等待 git status 结束失败")?;
+        if !status.success() {
+            return Err(anyhow!(
+                "git status 返回非零: {}",
+                status.code().unwrap_or(-1)
+            ));
+        }
+
+        Ok(entries)
+    }
+
+    fn read_blob(&self, repo_root: &Path, spec: &str) -> Result<Vec<u8>> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(repo_root)
+            .args(["show", spec])
+            .output()
+            .with_context(|| format!("执行 git show 失败：{spec}"))?;
+
+        if !output.status.success() {
+            return Err(anyhow!(
+                "git show 返回非零（{}）：{}",
+                output.status.code().unwrap_or(-1),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        Ok(output.stdout)
+    }
+
+    /// 使用 `--follow` 跨越重命名；每个提交附带该提交中文件的路径（重命名时还有原路径）
+    /// 以及是否附有 `git notes` 备注。
+    fn file_log(&self, repo_root: &Path, path: &str, limit: usize) -> Result<Vec<CommitEntry>> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(repo_root)
+            .args([
+                "log",
+                "--follow",
+                "-z",
+                "--name-status",
+                "-n",
+                &limit.to_string(),
+                "--format=%x1e%H%x1f%h%x1f%s%x1f%G?%x1f%GS%x1f%GK%x1f%N%x1f",
+                "--",
+                path,
+            ])
+            .output()
+            .context("执行 git log 失败")?;
+
+        if !output.status.success() {
+            return Err(anyhow!(
+                "git log 返回非零（{}）：{}",
+                output.status.code().unwrap_or(-1),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        // 从新到旧遍历，遇到重命名后更早的提交使用原路径；merge 提交没有 name-status，沿用当前路径。
+        let mut tracked_path = path.to_string();
+        let mut commits = Vec::new();
+        for record in output.stdout.split(|b| *b == 0x1e) {
+            if record.is_empty() {
+                continue;
+            }
+
+            let mut fields = record.split(|b| *b == 0x1f);
+            let Some(hash) = fields.next() else { continue };
+            let Some(short_hash) = fields.next() else {
+                continue;
+            };
+            let Some(subject) = fields.next() else {
+                continue;
+            };
+            let mut signature_field = || String::from_utf8_lossy(fields.next().unwrap_or_default());
+            let signature =
+                CommitSignature::parse(&signature_field(), &signature_field(), &signature_field());
+            let has_note = !fields.next().unwrap_or_default().trim_ascii().is_empty();
+
+            let hash = String::from_utf8_lossy(hash).trim().to_string();
+            if hash.is_empty() {
+                continue;
+            }
+
+            // `-z --name-status`：`<status>\0<path>\0`，重命名 / 复制为 `<status>\0<old>\0<new>\0`。
+            let changes = fields.next().unwrap_or_default();
+            let tokens: Vec<String> = changes
+                .split(|b| *b == 0)
+                .map(|token| String::from_utf8_lossy(token).trim().to_string())
+                .filter(|token| !token.is_empty())
+                .collect();
+            let old_path = match tokens.as_slice() {
+                [status, old, new, ..] if status.starts_with(['R', 'C']) => {
+                    tracked_path = new.clone();
+                    Some(old.clone())
+                }
+                [_, path, ..] => {
+                    tracked_path = path.clone();
+                    None
+                }
+                _ => None,
+            };
+
+            commits.push(CommitEntry {
+                hash,
+                short_hash: String::from_utf8_lossy(short_hash).trim().to_string(),
+                subject: String::from_utf8_lossy(subject).trim().to_string(),
+                signature,
+                path: tracked_path.clone(),
+                old_path: old_path.clone(),
+                has_note,
+            });
+            if let Some(old_path) = old_path {
+                tracked_path = old_path;
+            }
+        }
+
+        Ok(commits)
+    }
+
+    fn reflog(&self, repo_root: &Path, limit: usize) -> Result<Vec<ReflogEntry>> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(repo_root)
+            .args([
+                "log",
+                "-g",
+                "-n",
+                &limit.to_string(),
+                "--date=iso",
+                "--format=%H%x1f%h%x1f%gd%x1f%gs%x1f%G?%x1f%GS%x1f%GK%x1e",
+                "HEAD",
+            ])
+            .output()
+            .context("执行 git log -g 失败")?;
+
+        if !output.status.success() {
+            return Err(anyhow!(
+                "git log -g 返回非零（{}）：{}",
+                output.status.code().unwrap_or(-1),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        let mut entries = Vec::new();
+        for record in output.stdout.split(|b| *b == 0x1e) {
+            let record = String::from_utf8_lossy(record);
+            let record = record.trim();
+            if record.is_empty() {
+                continue;
+            }
+
+            let mut fields = record.split('\x1f');
+            let (Some(hash), Some(short_hash), Some(selector), Some(message)) =
+                (fields.next(), fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let mut signature_field = || fields.next().unwrap_or_default();
+            let signature =
+                CommitSignature::parse(signature_field(), signature_field(), signature_field());
+
+            // 带 --date 时 %gd 形如 `HEAD@{2024-01-01 12:00:00 +0800}`，
+            // 条目按从新到旧排列，因此下标就是 `HEAD@{n}` 里的 n。
+            let date = selector
+                .split_once('{')
+                .and_then(|(_, rest)| rest.strip_suffix('}'))
+                .unwrap_or_default()
+                .to_string();
+
+            entries.push(ReflogEntry {
+                hash: hash.to_string(),
+                short_hash: short_hash.to_string(),
+                selector: format!("HEAD@{{{}}}", entries.len()),
+                date,
+                message: message.to_string(),
+                signature,
+            });
+        }
+
+        Ok(entries)
+    }
+
+    /// `for-each-ref` 的模式只按整段路径匹配，因此列出 `prefix` 所在目录下的引用再按前缀过滤。
+    fn refs(&self, repo_root: &Path, prefix: &str) -> Result<Vec<RefEntry>> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(repo_root)
+            .args([
+                "for-each-ref",
+                "--format=%(refname)%1f%(objectname)",
+                ref_dir(prefix),
+            ])
+            .output()
+            .context("执行 git for-each-ref 失败")?;
+
+        if !output.status.success() {
+            return Err(anyhow!(
+                "git for-each-ref 返回非零（{}）：{}",
+                output.status.code().unwrap_or(-1),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let (name, id) = line.split_once('\x1f')?;
+                name.starts_with(prefix).then(|| RefEntry {
+                    name: name.to_string(),
+                    id: id.trim().to_string(),
+                })
+            })
+            .collect())
+    }
+}
+
+/// `prefix` 中最后一个 `/` 及之前的部分，例如 `refs/bisect/good-` 对应 `refs/bisect/`。
+fn ref_dir(prefix: &str) -> &str {
+    &prefix[..prefix.rfind('/').map_or(0, |index| index + 1)]
+}
+
+fn parse_status_record(record: &str) -> Option<FileEntry> {
+    if record.starts_with("1 ") {
+        return parse_type_1_record(record);
+    }
+    if record.starts_with("2 ") {
+        return parse_type_2_record(record);
+    }
+    if record.starts_with("u ") {
+        return parse_unmerged_record(record);
+    }
+
+    let (status, path) = if let Some(path) = record.strip_prefix("? ") {
+        ("??", path)
+    } else {
+        ("!!", record.strip_prefix("! ")?)
+    };
+    Some(FileEntry {
+        path: path.to_string(),
+        status: status.to_string(),
+    })
+}
+
+/// 通过 gitoxide 在进程内读取。
+#[derive(Default)]
+struct GixBackend {
+    /// 按仓库根目录缓存打开的仓库；引用、index 和对象库在每次读取时仍会重新检查。
+    repos: Mutex<HashMap<PathBuf, gix::ThreadSafeRepository>>,
+}
+
+impl GixBackend {
+    fn open(&self, repo_root: &Path) -> Result<gix::Repository> {
+        let mut repos = self.repos.lock().unwrap();
+        if let Some(repo) = repos.get(repo_root) {
+            return Ok(repo.to_thread_local());
+        }
+        let repo = gix::ThreadSafeRepository::open(repo_root)
+            .with_context(|| format!("打开仓库失败：{}", repo_root.display()))?;
+        let local = repo.to_thread_local();
+        repos.insert(repo_root.to_path_buf(), repo);
+        Ok(local)
+    }
+}
+
+impl GitBackend for GixBackend {
+    /// 与 `git status --porcelain=v2 --untracked-files=normal` 的结果一致：状态码按 porcelain v2
+    /// 的 `XY` 给出，未跟踪的目录只占一条 `dir/` 记录；依次列出改动、冲突和未跟踪的条目，
+    /// 各自按路径排序。
+    fn status(&self, repo_root: &Path) -> Result<Vec<FileEntry>> {
+        use gix::status::index_worktree::Item as WorktreeItem;
+        use gix::status::index_worktree::iter::Summary;
+        use gix::status::plumbing::index_as_worktree::EntryStatus;
+
+        let repo = self.open(repo_root)?;
+        let items = repo
+            .status(gix::progress::Discard)
+            .context("读取仓库状态失败")?
+            .untracked_files(gix::status::UntrackedFiles::Collapsed)
+            .index_worktree_rewrites(None)
+            .into_iter(None)
+            .context("读取仓库状态失败")?;
+
+        let mut tracked: BTreeMap<String, [u8; 2]> = BTreeMap::new();
+        let mut conflicts = BTreeMap::new();
+        let mut untracked = Vec::new();
+        for item in items {
+            match item.context("读取仓库状态失败")? {
+                gix::status::Item::TreeIndex(change) => {
+                    use gix::diff::index::ChangeRef;
+                    let x = match &change {
+                        ChangeRef::Addition { .. } => b'A',
+                        ChangeRef::Deletion { .. } => b'D',
+                        ChangeRef::Modification { .. } => b'M',
+                        ChangeRef::Rewrite { copy: true, .. } => b'C',
+                        ChangeRef::Rewrite { .. } => b'R',
+                    };
+                    tracked
+                        .entry(change.location().to_string())
+                        .or_insert(*b"..")[0] = x;
+                }
+                gix::status::Item::IndexWorktree(WorktreeItem::Modification {
+                    rela_path,
+                    status: EntryStatus::Conflict { summary, .. },
+                    ..
+                }) => {
+                    conflicts.insert(rela_path.to_string(), conflict_code(summary));
+                }
+                gix::status::Item::IndexWorktree(WorktreeItem::DirectoryContents {
+                    entry, ..
+                }) => {
+                    if entry.status != gix::dir::entry::Status::Untracked {
+                        continue;
+                    }
+                    let mut path = entry.rela_path.to_string();
+                    if matches!(
+                        entry.disk_kind,
+                        Some(gix::dir::entry::Kind::Directory | gix::dir::entry::Kind::Repository)
+                    ) {
+                        path.push('/');
+                    }
+                    untracked.push(path);
+                }
+                gix::status::Item::IndexWorktree(item) => {
+                    let y = match item.summary() {
+                        Some(Summary::Removed) => b'D',
+                        Some(Summary::Modified) => b'M',
+                        Some(Summary::TypeChange) => b'T',
+                        Some(Summary::IntentToAdd) => b'A',
+                        _ => continue,
+                    };
+                    tracked
+                        .entry(item.rela_path().to_string())
+                        .or_insert(*b"..")[1] = y;
+                }
+            }
+        }
+        // 冲突的条目只按冲突列出，不再看 index 与 HEAD 的比较。
+        tracked.retain(|path, _| !conflicts.contains_key(path));
+        untracked.sort();
+
+        let entry = |(path, xy): (String, [u8; 2])| FileEntry {
+            path,
+            status: String::from_utf8_lossy(&xy).into_owned(),
+        };
+        let untracked = untracked.into_iter().map(|path| FileEntry {
+            path,
+            status: "??".to_string(),
+        });
+        Ok(tracked
+            .into_iter()
+            .map(entry)
+            .chain(conflicts.into_iter().map(entry))
+            .chain(untracked)
+            .collect())
+    }
+
+    fn read_blob(&self, repo_root: &Path, spec: &str) -> Result<Vec<u8>> {
+        let repo = self.open(repo_root)?;
+        let id = match index_stage_spec(spec) {
+            // gitoxide 的 rev-parse 不认 `:3:<path>`，index stage 直接到 index 里找。
+            Some((stage, path)) => repo
+                .index_or_empty()
+                .context("读取 index 失败")?
+                .entry_by_path_and_stage(path.into(), stage)
+                .map(|entry| entry.id)
+                .with_context(|| format!("index 中没有 {spec}"))?,
+            None => repo
+                .rev_parse_single(spec)
+                .with_context(|| format!("找不到对象：{spec}"))?
+                .detach(),
+        };
+        let blob = repo
+            .find_object(id)
+            .with_context(|| format!("读取对象失败：{spec}"))?
+            .try_into_blob()
+            .with_context(|| format!("{spec} 不是文件"))?;
+        Ok(blob.detach().data)
+    }
+
+    /// 与 `git log --follow` 一样跨越重命名：文件在父提交中不存在时按 50% 相似度找出原路径。
+    /// 只列出与每个父提交中该文件都不同的提交，合并提交沿用当前路径。
+    fn file_log(&self, repo_root: &Path, path: &str, limit: usize) -> Result<Vec<CommitEntry>> {
+        let repo = self.open(repo_root)?;
+        let head = repo.head_id().context("读取 HEAD 失败")?;
+        let notes = repo
+            .find_reference("refs/notes/commits")
+            .ok()
+            .and_then(|mut notes| notes.peel_to_tree().ok());
+        let walk = repo
+            .rev_walk([head])
+            .sorting(gix::revision::walk::Sorting::ByCommitTime(
+                Default::default(),
+            ))
+            .all()
+            .context("遍历提交失败")?;
+
+        let mut tracked_path = path.to_string();
+        let mut commits = Vec::new();
+        for info in walk {
+            if commits.len() >= limit {
+                break;
+            }
+            let info = info.context("遍历提交失败")?;
+            let commit = info.object().context("读取提交失败")?;
+            let tree = commit.tree().context("读取提交的目录树失败")?;
+            let id = entry_id(&tree, &tracked_path)?;
+
+            let mut parent_trees = Vec::new();
+            for parent in info.parent_ids() {
+                let parent = parent.object()?.try_into_commit()?;
+                parent_trees.push(parent.tree().context("读取父提交的目录树失败")?);
+            }
+            let mut unchanged = id.is_none() && parent_trees.is_empty();
+            for parent_tree in &parent_trees {
+                unchanged |= entry_id(parent_tree, &tracked_path)? == id;
+            }
+            if unchanged {
+                continue;
+            }
+
+            let old_path = match parent_trees.as_slice() {
+                [parent_tree]
+                    if id.is_some() && entry_id(parent_tree, &tracked_path)?.is_none() =>
+                {
+                    renamed_from(&repo, parent_tree, &tree, &tracked_path)?
+                }
+                _ => None,
+            };
+
+            let hash = info.id.to_string();
+            let has_note = notes.as_ref().is_some_and(|notes| {
+                [hash.clone(), format!("{}/{}", &hash[..2], &hash[2..])]
+                    .iter()
+                    .any(|path| matches!(notes.lookup_entry_by_path(path), Ok(Some(_))))
+            });
+            commits.push(CommitEntry {
+                short_hash: info.id().shorten_or_id().to_string(),
+                subject: commit_subject(&commit),
+                signature: commit_signature(&commit),
+                hash,
+                path: tracked_path.clone(),
+                old_path: old_path.clone(),
+                has_note,
+            });
+            if let Some(old_path) = old_path {
+                tracked_path = old_path;
+            }
+        }
+
+        Ok(commits)
+    }
+
+    fn reflog(&self, repo_root: &Path, limit: usize) -> Result<Vec<ReflogEntry>> {
+        let repo = self.open(repo_root)?;
+        let head = repo.find_reference("HEAD").context("读取 HEAD 失败")?;
+        let mut log = head.log_iter();
+        let Some(lines) = log.rev().context("读取 reflog 失败")? else {
+            return Ok(Vec::new());
+        };
+
+        let mut entries = Vec::new();
+        for line in lines {
+            if entries.len() >= limit {
+                break;
+            }
+            let line = line.context("读取 reflog 失败")?;
+            // 与 `git log -g` 一样跳过指向已不存在的提交的条目。
+            let Some(commit) = repo
+                .find_object(line.new_oid)
+                .ok()
+                .and_then(|object| object.try_into_commit().ok())
+            else {
+                continue;
+            };
+            let date = line.signature.time.format(gix::date::time::format::ISO8601);
+
+            entries.push(ReflogEntry {
+                hash: line.new_oid.to_string(),
+                short_hash: commit.id().shorten_or_id().to_string(),
+                selector: format!("HEAD@{{{}}}", entries.len()),
+                date,
+                message: line.message.to_string(),
+                signature: commit_signature(&commit),
+            });
+        }
+
+        Ok(entries)
+    }
+
+    fn refs(&self, repo_root: &Path, prefix: &str) -> Result<Vec<RefEntry>> {
+        let repo = self.open(repo_root)?;
+        let platform = repo.references().context("读取引用失败")?;
+        let references = match ref_dir(prefix) {
+            "" => platform.all(),
+            dir => platform.prefixed(dir),
+        }
+        .context("读取引用失败")?;
+
+        let mut refs = Vec::new();
+        for reference in references {
+            let reference = reference.map_err(|err| anyhow!("读取引用失败：{err}"))?;
+            let name = reference.name().as_bstr().to_string();
+            let Some(id) = reference.target().try_id().map(|id| id.to_string()) else {
+                continue;
+            };
+            if name.starts_with(prefix) {
+                refs.push(RefEntry { name, id });
+            }
+        }
+        refs.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(refs)
+    }
+}
+
+/// 拆开 `:<stage>:<path>` 形式的 spec。
+fn index_stage_spec(spec: &str) -> Option<(gix::index::entry::Stage, &str)> {
+    use gix::index::entry::Stage;
+    let (stage, path) = spec.strip_prefix(':')?.split_once(':')?;
+    let stage = match stage {
+        "0" => Stage::Unconflicted,
+        "1" => Stage::Base,
+        "2" => Stage::Ours,
+        "3" => Stage::Theirs,
+        _ => return None,
+    };
+    Some((stage, path))
+}
+
+/// porcelain v2 中冲突条目的 `XY`。
+fn conflict_code(conflict: gix::status::plumbing::index_as_worktree::Conflict) -> [u8; 2] {
+    use gix::status::plumbing::index_as_worktree::Conflict;
+    match conflict {
+        Conflict::BothDeleted => *b"DD",
+        Conflict::AddedByUs => *b"AU",
+        Conflict::DeletedByThem => *b"UD",
+        Conflict::AddedByThem => *b"UA",
+        Conflict::DeletedByUs => *b"DU",
+        Conflict::BothAdded => *b"AA",
+        Conflict::BothModified => *b"UU",
+    }
+}
+
+/// `tree` 中 `path` 处条目的对象 id；不存在时为 `None`。
+fn entry_id(tree: &gix::Tree<'_>, path: &str) -> Result<Option<gix::ObjectId>> {
+    Ok(tree
+        .lookup_entry_by_path(path)
+        .with_context(|| format!("读取目录树失败：{path}"))?
+        .map(|entry| entry.object_id()))
+}
+
+/// 从 `old` 到 `new` 的改动中，重命名为 `path` 的文件的原路径。
+fn renamed_from(
+    repo: &gix::Repository,
+    old: &gix::Tree<'_>,
+    new: &gix::Tree<'_>,
+    path: &str,
+) -> Result<Option<String>> {
+    use gix::object::tree::diff::ChangeDetached;
+
+    let options = gix::diff::Options::default().with_rewrites(Some(Default::default()));
+    let changes = repo
+        .diff_tree_to_tree(old, new, options)
+        .context("比较目录树失败")?;
+    Ok(changes.into_iter().find_map(|change| match change {
+        ChangeDetached::Rewrite {
+            source_location,
+            location,
+            copy: false,
+            ..
+        } if location == path => Some(source_location.to_string()),
+        _ => None,
+    }))
+}
+
+fn commit_subject(commit: &gix::Commit<'_>) -> String {
+    commit
+        .message()
+        .map(|message| message.summary().to_string())
+        .unwrap_or_default()
+}
+
+/// gitoxide 不校验签名：带签名的提交按无法校验（`%G?` 的 `E`）显示。
+fn commit_signature(commit: &gix::Commit<'_>) -> CommitSignature {
+    let signed = commit
+        .decode()
+        .is_ok_and(|commit| commit.extra_headers().pgp_signature().is_some());
+    CommitSignature::parse(if signed { "E" } else { "N" }, "", "")
+}
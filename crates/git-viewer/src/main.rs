@@ -1,23 +1,51 @@
+mod askpass;
+mod encoding;
+mod forge;
+mod git;
+mod stack;
+mod stats;
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::rc::Rc;
-use std::time::Duration;
+use std::sync::{Arc, Mutex, OnceLock, mpsc};
+use std::time::{Duration, Instant};
 
 use anyhow::Context as _;
 use anyhow::{Result, anyhow};
+use encoding::TextEncoding;
 use gpui::StatefulInteractiveElement as _;
 use gpui::prelude::FluentBuilder as _;
 use gpui::*;
 use gpui::{KeyBinding, actions};
 use gpui_component::{
-    ActiveTheme as _, Disableable as _, Root, TitleBar, VirtualListScrollHandle, WindowExt as _,
+    ActiveTheme as _, Disableable as _, IconName, Root, Sizable as _, TitleBar,
+    VirtualListScrollHandle,
     button::{Button, ButtonVariants as _},
     input::{Input, InputState},
-    notification::Notification,
+    list::ListItem,
     popover::Popover,
+    resizable::h_resizable,
     scroll::{Scrollbar, ScrollbarState},
     v_virtual_list,
 };
+use gpui_manos_components::breadcrumb::{Breadcrumb, BreadcrumbItem};
+use gpui_manos_components::command_palette::{
+    CommandPalette, CommandPaletteItem, CommandPaletteProvider,
+};
+use gpui_manos_components::notification_center::{
+    NotificationBell, NotificationCenter, Toast, WindowToastExt as _,
+};
+use gpui_manos_components::resizable::{
+    FilePanelSizeStorage, MemoryPanelSizeStorage, PanelSizeStorage, PersistedPanelSizes,
+    ResizablePanelGroupExt as _,
+};
+use gpui_manos_components::status_bar::{StatusBar, StatusBarSegment};
+use gpui_manos_dnd::{DndListDropTarget, DndListItem, DndListRowState, DndListState, dnd_list};
+use gpui_manos_plate::MarkdownView;
+use serde::{Deserialize, Serialize};
 
 const CONTEXT: &str = "GitViewer";
 
@@ -73,6 +101,12 @@ struct FileEntry {
     status: String,
 }
 
+/// 展开未跟踪目录时最多列出的文件数。
+const UNTRACKED_DIR_PREVIEW_LIMIT: usize = 200;
+
+/// diff 标题面包屑中每级下拉最多列出的变更文件数。
+const BREADCRUMB_MENU_LIMIT: usize = 50;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum StatusFilter {
     All,
@@ -87,17 +121,235 @@ enum AppScreen {
     StatusList,
     DiffView,
     ConflictView,
+    Reflog,
+    BranchCompare,
+    Bisect,
+    CommitStack,
+    PullRequests,
+    RepoStats,
 }
 
 #[derive(Clone, Copy, Debug)]
 struct DiffViewOptions {
     ignore_whitespace: bool,
+    /// 忽略仅 CRLF/LF 不同的行。
+    ignore_eol: bool,
     context_lines: usize,
 }
 
+impl Default for DiffViewOptions {
+    fn default() -> Self {
+        Self {
+            ignore_whitespace: false,
+            ignore_eol: false,
+            context_lines: 3,
+        }
+    }
+}
+
 const MAX_CONTEXT_LINES: usize = 20;
 const DIFF_REBUILD_DEBOUNCE_MS: u64 = 120;
 
+const DIFF_CACHE_CAPACITY: usize = 64;
+const DIFF_CACHE_MAX_BYTES: usize = 64 * 1024 * 1024;
+const DIFF_PREFETCH_RADIUS: usize = 3;
+const DIFF_PRELOAD_MAX_FILE_BYTES: u64 = 32 * 1024;
+
+/// 共享配置文件 `settings.toml` 中 `[git-viewer]` 表的内容；未设置的项使用默认值。
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct GitViewerSettings {
+    diff_cache_capacity: Option<usize>,
+    diff_cache_max_bytes: Option<usize>,
+    diff_prefetch_radius: Option<usize>,
+    diff_preload_max_bytes: Option<u64>,
+    /// 打开外部编辑器的命令，优先级低于 `GIT_VIEWER_EDITOR`。
+    editor: Option<String>,
+    /// GitHub / GitLab 的 API token，按主机名；环境变量中的 token 优先。
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    forge_tokens: BTreeMap<String, String>,
+    /// 打开冲突时依次尝试的自动解决策略（`prefer-ours`、`prefer-theirs`、`union`、
+    /// `whitespace-only`）；未设置时只用 `whitespace-only`，空列表表示关闭。
+    conflict_auto_resolve: Option<Vec<String>>,
+    /// 读操作使用的 git 后端：`cli`（git 子进程，默认）或 `gix`（进程内的 gitoxide）。
+    git_backend: Option<String>,
+}
+
+impl GitViewerSettings {
+    /// 配置的冲突自动解决策略；无法识别的名字记录警告后忽略。
+    fn conflict_auto_resolve(&self) -> Vec<diffview::AutoResolveStrategy> {
+        let Some(names) = self.conflict_auto_resolve.as_ref() else {
+            return vec![diffview::AutoResolveStrategy::WhitespaceOnly];
+        };
+        names
+            .iter()
+            .filter_map(|name| {
+                name.trim()
+                    .parse::<diffview::AutoResolveStrategy>()
+                    .inspect_err(|err| tracing::warn!("忽略冲突自动解决策略：{err}"))
+                    .ok()
+            })
+            .collect()
+    }
+
+    /// 启动时读取一次；配置文件无法解析时记录警告并使用默认值。
+    fn get() -> &'static Self {
+        static SETTINGS: OnceLock<GitViewerSettings> = OnceLock::new();
+        SETTINGS.get_or_init(|| {
+            manos_config::ConfigStore::open_default()
+                .and_then(|store| store.namespace::<Self>("git-viewer").try_get())
+                .unwrap_or_else(|err| {
+                    tracing::warn!("读取配置失败，使用默认值：{err}");
+                    Self::default()
+                })
+        })
+    }
+}
+
+/// diff 文本缓存与预取策略；默认值见上面的常量，可由配置文件覆盖，环境变量优先于配置文件。
+#[derive(Clone, Copy, Debug)]
+struct DiffCacheSettings {
+    /// 最多缓存多少个文件的两侧文本。
+    capacity: usize,
+    /// 缓存文本的总字节数上限。
+    max_bytes: usize,
+    /// 打开一个文件后，预取状态列表中紧随其后的几个文件。
+    prefetch_radius: usize,
+    /// 状态刷新后在后台预载工作区大小不超过该值的文件；0 表示关闭。
+    preload_max_file_bytes: u64,
+}
+
+impl DiffCacheSettings {
+    fn load(settings: &GitViewerSettings) -> Self {
+        fn var<T: std::str::FromStr>(name: &str, configured: Option<T>, default: T) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.trim().parse().ok())
+                .or(configured)
+                .unwrap_or(default)
+        }
+
+        Self {
+            capacity: var(
+                "GIT_VIEWER_DIFF_CACHE_CAPACITY",
+                settings.diff_cache_capacity,
+                DIFF_CACHE_CAPACITY,
+            ),
+            max_bytes: var(
+                "GIT_VIEWER_DIFF_CACHE_MAX_BYTES",
+                settings.diff_cache_max_bytes,
+                DIFF_CACHE_MAX_BYTES,
+            ),
+            prefetch_radius: var(
+                "GIT_VIEWER_DIFF_PREFETCH_RADIUS",
+                settings.diff_prefetch_radius,
+                DIFF_PREFETCH_RADIUS,
+            ),
+            preload_max_file_bytes: var(
+                "GIT_VIEWER_DIFF_PRELOAD_MAX_BYTES",
+                settings.diff_preload_max_bytes,
+                DIFF_PRELOAD_MAX_FILE_BYTES,
+            ),
+        }
+    }
+}
+
+/// 一次 diff 读出的两侧文本；读取失败的一侧按空内容处理并记下错误。
+#[derive(Clone, Debug)]
+struct DiffTexts {
+    old_text: String,
+    old_err: Option<String>,
+    new_text: String,
+    new_err: Option<String>,
+    /// 任一侧不是不带 BOM 的 UTF-8 时识别出的编码，新版本一侧优先。
+    encoding: Option<TextEncoding>,
+}
+
+impl DiffTexts {
+    fn len(&self) -> usize {
+        self.old_text.len() + self.new_text.len()
+    }
+}
+
+/// 按 (路径, 对比目标) 缓存的 diff 文本，最近使用的排在末尾。
+///
+/// 状态刷新时整体清空并递增 `generation`，在此之前启动的预取结果会被丢弃。
+struct DiffTextCache {
+    settings: DiffCacheSettings,
+    entries: VecDeque<(String, CompareTarget, Arc<DiffTexts>)>,
+    bytes: usize,
+    generation: u64,
+}
+
+impl DiffTextCache {
+    fn new(settings: DiffCacheSettings) -> Self {
+        Self {
+            settings,
+            entries: VecDeque::new(),
+            bytes: 0,
+            generation: 0,
+        }
+    }
+
+    fn position(&self, path: &str, target: &CompareTarget) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|(entry_path, entry_target, _)| entry_path == path && entry_target == target)
+    }
+
+    fn contains(&self, path: &str, target: &CompareTarget) -> bool {
+        self.position(path, target).is_some()
+    }
+
+    fn get(&mut self, path: &str, target: &CompareTarget) -> Option<Arc<DiffTexts>> {
+        let index = self.position(path, target)?;
+        let entry = self.entries.remove(index)?;
+        let texts = entry.2.clone();
+        self.entries.push_back(entry);
+        Some(texts)
+    }
+
+    /// 写入缓存；读取出错或超过字节上限的结果不缓存，`generation` 过期的写入直接丢弃。
+    fn insert(
+        &mut self,
+        generation: u64,
+        path: String,
+        target: CompareTarget,
+        texts: Arc<DiffTexts>,
+    ) {
+        if generation != self.generation
+            || self.settings.capacity == 0
+            || texts.old_err.is_some()
+            || texts.new_err.is_some()
+            || texts.len() > self.settings.max_bytes
+        {
+            return;
+        }
+
+        let existing = self
+            .position(&path, &target)
+            .and_then(|index| self.entries.remove(index));
+        if let Some((_, _, old)) = existing {
+            self.bytes -= old.len();
+        }
+        self.bytes += texts.len();
+        self.entries.push_back((path, target, texts));
+
+        while self.entries.len() > self.settings.capacity || self.bytes > self.settings.max_bytes {
+            let Some((_, _, evicted)) = self.entries.pop_front() else {
+                break;
+            };
+            self.bytes -= evicted.len();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.bytes = 0;
+        self.generation = self.generation.wrapping_add(1);
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum SplitLayout {
     Aligned,
@@ -118,11 +370,86 @@ enum CompareTarget {
     Refs { left: String, right: String },
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SignatureStatus {
+    Unsigned,
+    Verified,
+    Untrusted,
+    Expired,
+    Revoked,
+    Bad,
+    Unverifiable,
+}
+
+impl SignatureStatus {
+    /// 对应 `git log --format=%G?` 的输出。
+    fn from_code(code: &str) -> Self {
+        match code.trim() {
+            "G" => Self::Verified,
+            "U" => Self::Untrusted,
+            "X" | "Y" => Self::Expired,
+            "R" => Self::Revoked,
+            "B" => Self::Bad,
+            "E" => Self::Unverifiable,
+            _ => Self::Unsigned,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Unsigned => "未签名",
+            Self::Verified => "已验证",
+            Self::Untrusted => "未信任",
+            Self::Expired => "已过期",
+            Self::Revoked => "已吊销",
+            Self::Bad => "签名无效",
+            Self::Unverifiable => "无法验证",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CommitSignature {
+    status: SignatureStatus,
+    signer: String,
+    key: String,
+}
+
+impl CommitSignature {
+    fn parse(code: &str, signer: &str, key: &str) -> Self {
+        Self {
+            status: SignatureStatus::from_code(code),
+            signer: signer.trim().to_string(),
+            key: key.trim().to_string(),
+        }
+    }
+
+    fn is_signed(&self) -> bool {
+        self.status != SignatureStatus::Unsigned
+    }
+
+    fn details(&self) -> String {
+        match (self.signer.is_empty(), self.key.is_empty()) {
+            (false, false) => format!("{}（{}）", self.signer, self.key),
+            (false, true) => self.signer.clone(),
+            (true, false) => format!("key {}", self.key),
+            (true, true) => String::new(),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 struct CommitEntry {
     hash: String,
     short_hash: String,
     subject: String,
+    signature: CommitSignature,
+    /// 该提交中文件的路径（跟随重命名）。
+    path: String,
+    /// 该提交把文件从 `old_path` 重命名为 `path`。
+    old_path: Option<String>,
+    /// 附有 `git notes` 备注。
+    has_note: bool,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -142,1279 +469,1493 @@ struct FileHistoryOverlayState {
     filter_input: Entity<InputState>,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum CommandPaletteCommand {
-    Back,
-    Next,
-    Prev,
-    ToggleViewMode,
-    ToggleSplitLayout,
-    ToggleWhitespace,
-    ExpandAll,
-    OpenFileHistory,
-    ApplyEditor,
-    SaveConflict,
-    SaveConflictAndAdd,
+#[derive(Clone, Debug)]
+struct CommitFailure {
+    exit_code: i32,
+    output: String,
+    hooks: Vec<String>,
 }
 
-#[derive(Clone)]
-struct CommandPaletteOverlayState {
-    input: Entity<InputState>,
-    selected: usize,
+#[derive(Clone, Debug)]
+enum CommitOutcome {
+    Committed { summary: String },
+    Rejected(CommitFailure),
 }
 
-#[derive(Clone, Copy)]
-struct CommandPaletteItem {
-    command: CommandPaletteCommand,
-    title: &'static str,
-    keywords: &'static str,
+#[derive(Clone, Debug)]
+struct AmendState {
+    loading: bool,
+    files: Vec<FileEntry>,
+    previous_message: String,
 }
 
-const COMMAND_PALETTE_ITEMS: &[CommandPaletteItem] = &[
-    CommandPaletteItem {
-        command: CommandPaletteCommand::Back,
-        title: "返回",
-        keywords: "back esc 返回 close",
-    },
-    CommandPaletteItem {
-        command: CommandPaletteCommand::Next,
-        title: "下一处（hunk/冲突）",
-        keywords: "next 下一个 hunk 冲突 navigate",
-    },
-    CommandPaletteItem {
-        command: CommandPaletteCommand::Prev,
-        title: "上一处（hunk/冲突）",
-        keywords: "prev 上一个 hunk 冲突 navigate",
-    },
-    CommandPaletteItem {
-        command: CommandPaletteCommand::ToggleViewMode,
-        title: "切换视图 Split/Inline",
-        keywords: "toggle view mode split inline 视图",
-    },
-    CommandPaletteItem {
-        command: CommandPaletteCommand::ToggleSplitLayout,
-        title: "切换布局 对齐/分栏",
-        keywords: "toggle layout aligned two-pane 对齐 分栏",
-    },
-    CommandPaletteItem {
-        command: CommandPaletteCommand::ToggleWhitespace,
-        title: "切换忽略空白",
-        keywords: "toggle whitespace ignore 空白 忽略",
-    },
-    CommandPaletteItem {
-        command: CommandPaletteCommand::ExpandAll,
-        title: "展开全部折叠",
-        keywords: "expand all folds 展开 折叠",
-    },
-    CommandPaletteItem {
-        command: CommandPaletteCommand::OpenFileHistory,
-        title: "打开文件历史对比",
-        keywords: "history log commit 历史 对比",
-    },
-    CommandPaletteItem {
-        command: CommandPaletteCommand::ApplyEditor,
-        title: "应用合并结果编辑",
-        keywords: "apply editor 应用 合并 结果",
-    },
-    CommandPaletteItem {
-        command: CommandPaletteCommand::SaveConflict,
-        title: "保存冲突结果到文件",
-        keywords: "save conflict 保存 写入",
-    },
-    CommandPaletteItem {
-        command: CommandPaletteCommand::SaveConflictAndAdd,
-        title: "保存并 git add",
-        keywords: "save add stage resolved 解决",
-    },
-];
+const RECENT_COLLABORATOR_LIMIT: usize = 10;
+
+/// 提交信息 trailer 菜单的候选项。
+#[derive(Clone, Debug, Default)]
+struct TrailerSuggestions {
+    /// 当前用户（`Name <email>`），用于 Signed-off-by。
+    identity: Option<String>,
+    /// 最近的协作者，按出现次数排序，不含当前用户。
+    collaborators: Vec<String>,
+    /// 从当前分支名里识别出的 issue 编号。
+    issue: Option<String>,
+}
 
-#[derive(Clone, Copy, Debug)]
-enum ConflictResolution {
-    Ours,
-    Theirs,
-    Base,
-    Both,
+#[derive(Clone)]
+struct CommitFailureOverlayState {
+    failure: CommitFailure,
+    no_verify: bool,
 }
 
-#[derive(Clone, Copy, Debug)]
-enum HunkApplyAction {
-    Stage,
-    Unstage,
-    Revert,
+const COMMIT_OUTPUT_MAX_LINES: usize = 2000;
+
+const ASKPASS_POLL_INTERVAL_MS: u64 = 150;
+
+struct AskpassOverlayState {
+    request: askpass::AskpassRequest,
+    input: Entity<InputState>,
 }
 
-impl HunkApplyAction {
-    fn label(self) -> &'static str {
-        match self {
-            Self::Stage => "Stage",
-            Self::Unstage => "Unstage",
-            Self::Revert => "Revert",
-        }
-    }
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PatchApplyTarget {
+    Worktree,
+    Index,
+}
 
-    fn required_compare_target(self) -> CompareTarget {
+impl PatchApplyTarget {
+    fn label(self) -> &'static str {
         match self {
-            Self::Stage | Self::Revert => CompareTarget::IndexToWorktree,
-            Self::Unstage => CompareTarget::HeadToIndex,
+            Self::Worktree => "工作区",
+            Self::Index => "暂存区",
         }
     }
 
     fn git_args(self) -> Vec<&'static str> {
         match self {
-            Self::Stage => vec!["apply", "--cached"],
-            Self::Unstage => vec!["apply", "-R", "--cached"],
-            Self::Revert => vec!["apply", "-R"],
+            Self::Worktree => vec!["apply", "--verbose", "--reject", "-"],
+            Self::Index => vec!["apply", "--verbose", "--reject", "--cached", "-"],
         }
     }
 }
 
-#[derive(Clone, Debug)]
-enum DisplayRow {
-    HunkHeader {
-        text: SharedString,
-    },
-    Fold {
-        old_start: usize,
-        new_start: usize,
-        len: usize,
-    },
-    Code {
-        kind: diffview::DiffRowKind,
-        old_line: Option<usize>,
-        new_line: Option<usize>,
-        old_segments: Vec<diffview::DiffSegment>,
-        new_segments: Vec<diffview::DiffSegment>,
-    },
-}
-
-#[derive(Clone)]
-struct DiffViewState {
-    title: SharedString,
-    path: Option<String>,
-    status: Option<String>,
-    compare_target: CompareTarget,
+/// patch 中的一个文件：以当前工作区内容为基准，在内存中应用后的预览。
+struct PatchFilePreview {
+    path: String,
     old_text: String,
     new_text: String,
-    old_lines: Vec<String>,
-    new_lines: Vec<String>,
-    diff_model: diffview::DiffModel,
-    rows: Vec<DisplayRow>,
-    hunk_rows: Vec<usize>,
-    current_hunk: usize,
-    scroll_handle: VirtualListScrollHandle,
-    scroll_state: ScrollbarState,
-    list_item_sizes: Rc<Vec<Size<Pixels>>>,
-    list_item_height: Pixels,
+    hunk_headers: Vec<SharedString>,
+    added: usize,
+    removed: usize,
+    binary: bool,
+    /// 在工作区内容中找不到上下文的 hunk（下标），`git apply` 很可能会拒绝它们。
+    unmatched_hunks: Vec<usize>,
 }
 
 #[derive(Clone, Debug)]
-enum ConflictRow {
-    EmptyState {
-        text: SharedString,
-    },
-    BlockHeader {
-        conflict_index: usize,
-        ours_branch_name: SharedString,
-        theirs_branch_name: SharedString,
-        has_base: bool,
-    },
-    Code {
-        kind: diffview::DiffRowKind,
-        ours_segments: Vec<diffview::DiffSegment>,
-        base_segments: Vec<diffview::DiffSegment>,
-        theirs_segments: Vec<diffview::DiffSegment>,
-    },
+struct PatchApplyOutcome {
+    target: PatchApplyTarget,
+    success: bool,
+    /// 被拒绝的 hunk：(文件, 从 1 开始的 hunk 序号)。
+    rejected: Vec<(String, Vec<usize>)>,
+    output: String,
 }
 
-#[derive(Clone)]
-struct ConflictViewState {
-    title: SharedString,
-    path: Option<String>,
-    text: String,
-    result_input: Entity<InputState>,
-    show_result_editor: bool,
-    conflicts: Vec<diffview::ConflictRegion>,
-    rows: Vec<ConflictRow>,
-    conflict_rows: Vec<usize>,
-    current_conflict: usize,
-    scroll_handle: VirtualListScrollHandle,
-    scroll_state: ScrollbarState,
-    list_item_sizes: Rc<Vec<Size<Pixels>>>,
-    list_item_height: Pixels,
+struct PatchApplyOverlayState {
+    source: SharedString,
+    patch: String,
+    files: Vec<PatchFilePreview>,
+    applying: bool,
+    outcome: Option<PatchApplyOutcome>,
+    /// 在 diff 视图中预览某个文件时隐藏，返回后恢复。
+    hidden: bool,
 }
 
-struct GitViewerApp {
-    repo_root: PathBuf,
-    files: Vec<FileEntry>,
-    loading: bool,
-    git_available: bool,
-    focus_handle: FocusHandle,
-    screen: AppScreen,
-    diff_view: Option<DiffViewState>,
-    conflict_view: Option<ConflictViewState>,
-    diff_options: DiffViewOptions,
-    compare_left_input: Entity<InputState>,
-    compare_right_input: Entity<InputState>,
-    file_history_overlay: Option<FileHistoryOverlayState>,
-    command_palette_overlay: Option<CommandPaletteOverlayState>,
-    diff_content_revision: u64,
-    diff_rebuild_seq: u64,
-    split_layout: SplitLayout,
-    view_mode: DiffViewMode,
-    status_filter: StatusFilter,
+const REFLOG_LIMIT: usize = 300;
+
+#[derive(Clone, Debug)]
+struct ReflogEntry {
+    hash: String,
+    short_hash: String,
+    selector: String,
+    date: String,
+    message: String,
+    signature: CommitSignature,
 }
 
-impl GitViewerApp {
-    fn new(window: &mut Window, cx: &mut Context<Self>, start_dir: PathBuf) -> Self {
-        let this = cx.entity();
-        let repo_root = detect_repo_root(&start_dir);
-        let repo_root_for_task = repo_root.clone();
-        let git_available = Command::new("git").arg("--version").output().is_ok();
-        let focus_handle = cx.focus_handle().tab_stop(true);
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ReflogAction {
+    Checkout,
+    ResetSoft,
+    ResetHard,
+    CreateBranch,
+}
 
-        if git_available {
-            cx.spawn_in(window, async move |_, window| {
-                let entries = window
-                    .background_executor()
-                    .spawn(async move {
-                        fetch_git_status(&repo_root_for_task)
-                            .map_err(|err| {
-                                eprintln!("git status failed: {err:?}");
-                                err
-                            })
-                            .unwrap_or_default()
-                    })
-                    .await;
+impl ReflogAction {
+    fn label(self) -> &'static str {
+        match self {
+            ReflogAction::Checkout => "检出（detached）",
+            ReflogAction::ResetSoft => "reset --soft",
+            ReflogAction::ResetHard => "reset --hard",
+            ReflogAction::CreateBranch => "新建分支",
+        }
+    }
+}
 
-                let _ = window.update(|_window, cx| {
-                    this.update(cx, |this, _cx| {
-                        this.loading = false;
-                        this.files = entries;
-                    })
-                });
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum IgnoreScope {
+    File,
+    Extension,
+    Directory,
+}
 
-                Some(())
-            })
-            .detach();
-        } else {
-            window.push_notification(
-                Notification::new()
-                    .message("未检测到 git 命令：已禁用仓库状态与 Git 操作（可打开 Demo）"),
-                cx,
-            );
+impl IgnoreScope {
+    fn label(self) -> &'static str {
+        match self {
+            IgnoreScope::File => "忽略此文件",
+            IgnoreScope::Extension => "忽略此扩展名",
+            IgnoreScope::Directory => "忽略此目录",
         }
+    }
+}
 
-        let compare_left_input = cx.new(|cx| {
-            InputState::new(window, cx)
-                .placeholder("左侧 ref（例如 HEAD~1 / a1b2c3）")
-                .default_value("HEAD")
-        });
-        let compare_right_input = cx.new(|cx| {
-            InputState::new(window, cx)
-                .placeholder("右侧 ref（留空=工作区，或 INDEX / :）")
-                .default_value("")
-        });
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum IgnoreFile {
+    Gitignore,
+    InfoExclude,
+}
 
-        Self {
-            repo_root,
-            files: Vec::new(),
-            loading: git_available,
-            git_available,
-            focus_handle,
-            screen: AppScreen::StatusList,
-            diff_view: None,
-            conflict_view: None,
-            diff_options: DiffViewOptions {
-                ignore_whitespace: false,
-                context_lines: 3,
-            },
-            compare_left_input,
-            compare_right_input,
-            file_history_overlay: None,
-            command_palette_overlay: None,
-            diff_content_revision: 0,
-            diff_rebuild_seq: 0,
-            split_layout: SplitLayout::TwoPane,
-            view_mode: DiffViewMode::Split,
-            status_filter: StatusFilter::All,
+impl IgnoreFile {
+    fn label(self) -> &'static str {
+        match self {
+            IgnoreFile::Gitignore => ".gitignore",
+            IgnoreFile::InfoExclude => ".git/info/exclude",
         }
     }
+}
 
-    fn focus_handle(&self) -> FocusHandle {
-        self.focus_handle.clone()
-    }
+struct ReflogViewState {
+    entries: Vec<ReflogEntry>,
+    loading: bool,
+    running: bool,
+    selected: usize,
+    branch_input: Entity<InputState>,
+    confirm_hard_reset: bool,
+}
 
-    fn open_demo(&mut self) {
-        let (old, new) = demo_texts();
-        self.open_diff_view(
-            "Split Diff Demo".into(),
-            None,
-            None,
-            CompareTarget::HeadToWorktree,
-            old,
-            new,
-        );
-    }
+const BRANCH_COMPARE_COMMIT_LIMIT: usize = 500;
 
-    fn open_large_demo(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        let line_count = std::env::var("GIT_VIEWER_LARGE_DEMO_LINES")
-            .ok()
-            .and_then(|value| value.parse::<usize>().ok())
-            .filter(|value| *value > 0)
-            .unwrap_or(20_000);
+#[derive(Clone, Debug)]
+struct CompareCommit {
+    hash: String,
+    short_hash: String,
+    author: String,
+    date: String,
+    subject: String,
+}
 
-        let this = cx.entity();
-        window.push_notification(
-            Notification::new().message(format!("正在生成 Large Diff Demo（{line_count} 行）…")),
-            cx,
-        );
+#[derive(Clone, Debug)]
+struct ChangedFile {
+    status: String,
+    path: String,
+    /// 二进制文件没有行数统计。
+    stat: Option<(usize, usize)>,
+}
 
-        cx.spawn_in(window, async move |_, window| {
-            let (old_text, new_text) = window
-                .background_executor()
-                .spawn(async move { large_demo_texts(line_count) })
-                .await;
+#[derive(Clone, Debug)]
+struct BranchComparison {
+    base: String,
+    head: String,
+    /// base 与 head 的合并基；文件列表和 diff 都以它为左侧（等同于 `git diff base...head`）。
+    merge_base: String,
+    /// 在 head 上、不在 base 上的提交。
+    ahead: Vec<CompareCommit>,
+    /// 在 base 上、不在 head 上的提交。
+    behind: Vec<CompareCommit>,
+    files: Vec<ChangedFile>,
+}
 
-            let diff_options =
-                window
-                    .update(|_, cx| this.read(cx).diff_options)
-                    .unwrap_or(DiffViewOptions {
-                        ignore_whitespace: false,
-                        context_lines: 3,
-                    });
-            let view_mode = window
-                .update(|_, cx| this.read(cx).view_mode)
-                .unwrap_or(DiffViewMode::Split);
+/// 空树对象，用于根提交（没有 parent）的 diff 左侧。
+const EMPTY_TREE_HASH: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
 
-            let (old_text, new_text, model, old_lines, new_lines) = window
-                .background_executor()
-                .spawn(async move {
-                    let (model, old_lines, new_lines) = build_diff_model(
-                        &old_text,
-                        &new_text,
-                        diff_options.ignore_whitespace,
-                        diff_options.context_lines,
-                    );
-                    (old_text, new_text, model, old_lines, new_lines)
-                })
-                .await;
+#[derive(Clone, Debug)]
+struct CommitDetails {
+    hash: String,
+    parents: Vec<String>,
+    author: String,
+    author_date: String,
+    committer: String,
+    committer_date: String,
+    message: String,
+    /// `git notes`（默认 notes ref）中附在该提交上的备注。
+    note: Option<String>,
+    /// 相对第一个 parent 的变更文件。
+    files: Vec<ChangedFile>,
+}
 
-            window
-                .update(|window, cx| {
-                    window.push_notification(
-                        Notification::new().message("Large Diff Demo 已加载"),
-                        cx,
-                    );
-                    this.update(cx, |this, _cx| {
-                        this.diff_content_revision = this.diff_content_revision.wrapping_add(1);
-                        this.conflict_view = None;
-                        this.diff_view = Some(DiffViewState::from_precomputed(
-                            format!("Large Diff Demo ({line_count} lines)").into(),
-                            None,
-                            None,
-                            CompareTarget::HeadToWorktree,
-                            old_text,
-                            new_text,
-                            view_mode,
-                            model,
-                            old_lines,
-                            new_lines,
-                        ));
-                        this.screen = AppScreen::DiffView;
-                    });
-                })
-                .ok();
+struct CommitDetailsState {
+    hash: String,
+    loading: bool,
+    details: Option<CommitDetails>,
+    error: Option<String>,
+    /// 打开备注编辑器时的输入框。
+    note_input: Option<Entity<InputState>>,
+    saving_note: bool,
+}
 
-            Some(())
-        })
-        .detach();
-    }
+struct BranchCompareState {
+    base_input: Entity<InputState>,
+    head_input: Entity<InputState>,
+    loading: bool,
+    comparison: Option<BranchComparison>,
+    /// 左侧提交列表中选中的提交（完整 hash）。
+    selected_commit: Option<String>,
+    /// 关闭时返回的页面；None 表示返回状态列表。
+    return_screen: Option<AppScreen>,
+}
 
-    fn open_conflict_demo(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        let text = conflict_demo_text();
-        let initial_text = text.clone();
-        let result_input = cx.new(move |cx| {
-            InputState::new(window, cx)
-                .code_editor("text")
-                .default_value(initial_text)
-        });
-        self.open_conflict_view("Conflict Demo".into(), None, text, result_input);
-    }
+/// 选中的变更请求的评论。
+struct PullRequestComments {
+    number: u64,
+    loading: bool,
+    comments: Vec<forge::PullRequestComment>,
+}
 
-    fn open_diff_view(
-        &mut self,
-        title: SharedString,
-        path: Option<String>,
-        status: Option<String>,
-        compare_target: CompareTarget,
-        old_text: String,
-        new_text: String,
-    ) {
-        self.diff_content_revision = self.diff_content_revision.wrapping_add(1);
-        self.conflict_view = None;
-        self.diff_view = Some(DiffViewState::new(
-            title,
-            path,
-            status,
-            compare_target,
-            old_text,
-            new_text,
-            self.diff_options,
-            self.view_mode,
-        ));
-        self.screen = AppScreen::DiffView;
-    }
+struct PullRequestsState {
+    /// 识别出的 GitHub / GitLab remote；还没检测完或不是托管仓库时为 None。
+    remote: Option<forge::ForgeRemote>,
+    /// 检测过 remote 之后为 true，用来区分“检测中”和“不支持”。
+    detected: bool,
+    token_input: Entity<InputState>,
+    loading: bool,
+    /// 检出或抓取 diff 进行中。
+    running: bool,
+    requests: Vec<forge::PullRequest>,
+    selected: Option<usize>,
+    comments: Option<PullRequestComments>,
+}
 
-    fn open_conflict_view(
-        &mut self,
-        title: SharedString,
-        path: Option<String>,
-        text: String,
-        result_input: Entity<InputState>,
-    ) {
-        self.diff_view = None;
-        self.conflict_view = Some(ConflictViewState::new(title, path, text, result_input));
-        self.screen = AppScreen::ConflictView;
-    }
+/// 统计页每周柱状图显示的周数。
+const REPO_STATS_WEEKS: usize = 26;
+/// 统计页作者和目录各显示的条数。
+const REPO_STATS_TOP: usize = 12;
 
-    fn close_diff_view(&mut self) {
-        self.screen = AppScreen::StatusList;
-    }
+struct RepoStatsState {
+    stats: stats::RepoStats,
+    loading: bool,
+    /// 每次重新统计加一；旧的统计任务发现代数变了就停下。
+    generation: u64,
+}
 
-    fn close_conflict_view(&mut self) {
-        self.screen = AppScreen::StatusList;
-    }
+/// 正在查看 diff 的变更请求；diff 视图右侧是它的 head 时显示当前文件的行内评论。
+struct PullRequestReview {
+    number: u64,
+    head_ref: String,
+    comments: Vec<forge::PullRequestComment>,
+}
 
-    fn open_file_diff(
-        &mut self,
-        path: String,
-        status: String,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) {
-        let target = default_compare_target(&status);
-        self.open_file_diff_with_target(path, status, target, window, cx);
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BisectAction {
+    Start,
+    Good,
+    Bad,
+    Skip,
+    Reset,
+}
+
+impl BisectAction {
+    fn label(self) -> &'static str {
+        match self {
+            BisectAction::Start => "开始 bisect",
+            BisectAction::Good => "Good（没有问题）",
+            BisectAction::Bad => "Bad（有问题）",
+            BisectAction::Skip => "Skip（无法测试）",
+            BisectAction::Reset => "结束 bisect",
+        }
     }
+}
 
-    fn open_file_diff_with_target(
-        &mut self,
-        path: String,
-        status: String,
-        target: CompareTarget,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) {
-        let this = cx.entity();
-        let repo_root = self.repo_root.clone();
-        let path_for_task = path.clone();
-        let status_for_task = status.clone();
+/// `git bisect` 的当前进度；每次操作后都从仓库里的 `refs/bisect/*` 重新读取。
+#[derive(Clone, Debug, Default)]
+struct BisectProgress {
+    active: bool,
+    /// 当前检出、等待标记的提交。
+    current: Option<CompareCommit>,
+    bad: Option<String>,
+    good_count: usize,
+    skip_count: usize,
+    /// 还在嫌疑范围内的提交数（含 bad 本身）。
+    candidates: Option<usize>,
+    /// 测完当前提交后还剩多少个提交、大约几步（同 `git bisect` 的提示）。
+    remaining: Option<(usize, usize)>,
+    /// 已定位的第一个坏提交。
+    culprit: Option<CompareCommit>,
+}
 
-        window.push_notification(
-            Notification::new().message(format!(
-                "正在加载 diff：{status} {path}（{}）",
-                compare_target_label(&target)
-            )),
-            cx,
-        );
+const COMMIT_STACK_DIR: &str = "git-viewer/stack";
 
-        cx.spawn_in(window, async move |_, window| {
-            let path_for_task_bg = path_for_task.clone();
-            let status_for_task_bg = status_for_task.clone();
-            let target_for_io = target.clone();
-            let (old_text, old_err, new_text, new_err) = window
-                .background_executor()
-                .spawn(async move {
-                    let (old_text, old_err) = match target_for_io.clone() {
-                        CompareTarget::HeadToWorktree | CompareTarget::HeadToIndex => {
-                            match read_head_file(&repo_root, &path_for_task_bg, &status_for_task_bg)
-                            {
-                                Ok(text) => (text, None),
-                                Err(err) => (String::new(), Some(err.to_string())),
-                            }
-                        }
-                        CompareTarget::IndexToWorktree => {
-                            match read_index_file(
-                                &repo_root,
-                                &path_for_task_bg,
-                                &status_for_task_bg,
-                            ) {
-                                Ok(text) => (text, None),
-                                Err(err) => (String::new(), Some(err.to_string())),
-                            }
-                        }
-                        CompareTarget::Refs { left, .. } => {
-                            match read_specified_file(&repo_root, &path_for_task_bg, &left) {
-                                Ok(text) => (text, None),
-                                Err(err) => (String::new(), Some(err.to_string())),
-                            }
-                        }
-                    };
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StackAction {
+    Pick,
+    /// 合并进上一个保留的提交。
+    Squash,
+    Drop,
+}
 
-                    let (new_text, new_err) = match target_for_io {
-                        CompareTarget::HeadToWorktree | CompareTarget::IndexToWorktree => {
-                            match read_working_file(&repo_root, &path_for_task_bg) {
-                                Ok(text) => (text, None),
-                                Err(err) => (String::new(), Some(err.to_string())),
-                            }
-                        }
-                        CompareTarget::HeadToIndex => {
-                            match read_index_file(
-                                &repo_root,
-                                &path_for_task_bg,
-                                &status_for_task_bg,
-                            ) {
-                                Ok(text) => (text, None),
-                                Err(err) => (String::new(), Some(err.to_string())),
-                            }
-                        }
-                        CompareTarget::Refs { right, .. } => {
-                            match read_specified_file(&repo_root, &path_for_task_bg, &right) {
-                                Ok(text) => (text, None),
-                                Err(err) => (String::new(), Some(err.to_string())),
-                            }
-                        }
-                    };
+/// 提交栈中的一个提交，以及用户对它的编辑。
+#[derive(Clone, Debug)]
+struct StackCommit {
+    hash: String,
+    short_hash: String,
+    subject: String,
+    message: String,
+    action: StackAction,
+    edited_message: Option<String>,
+}
 
-                    (old_text, old_err, new_text, new_err)
-                })
-                .await;
+struct CommitStackState {
+    base_input: Entity<InputState>,
+    /// `base_input` 与 HEAD 的合并基；栈就是 `merge_base..HEAD`。
+    merge_base: Option<String>,
+    /// 载入时的提交顺序，用来判断是否拖动过。
+    original_order: Vec<String>,
+    list: Entity<DndListState<StackCommit>>,
+    message_input: Entity<InputState>,
+    /// 当前载入到 `message_input` 的提交。
+    editing_hash: Option<String>,
+    loading: bool,
+    running: bool,
+}
 
-            let diff_options =
-                window
-                    .update(|_, cx| this.read(cx).diff_options)
-                    .unwrap_or(DiffViewOptions {
-                        ignore_whitespace: false,
-                        context_lines: 3,
-                    });
-            let view_mode = window
-                .update(|_, cx| this.read(cx).view_mode)
-                .unwrap_or(DiffViewMode::Split);
+struct BisectViewState {
+    bad_input: Entity<InputState>,
+    good_input: Entity<InputState>,
+    progress: BisectProgress,
+    loading: bool,
+    running: bool,
+    /// 最近一次 `git bisect` 命令的输出。
+    output: Option<String>,
+}
 
-            let (old_text, new_text, model, old_lines, new_lines) = window
-                .background_executor()
-                .spawn(async move {
-                    let (model, old_lines, new_lines) = build_diff_model(
-                        &old_text,
-                        &new_text,
-                        diff_options.ignore_whitespace,
-                        diff_options.context_lines,
-                    );
-                    (old_text, new_text, model, old_lines, new_lines)
-                })
-                .await;
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CommandPaletteCommand {
+    Back,
+    Next,
+    Prev,
+    ToggleViewMode,
+    ToggleSplitLayout,
+    TogglePaneScrollLink,
+    SyncPaneScroll,
+    ToggleWhitespace,
+    ToggleIgnoreEol,
+    ExpandAll,
+    ToggleFoldByFunction,
+    OpenFileHistory,
+    OpenInEditor,
+    CopyPatch,
+    CopyNewText,
+    ExportPatch,
+    ApplyPatchFromFile,
+    ApplyPatchFromClipboard,
+    OpenBranchCompare,
+    ApplyEditor,
+    SaveConflict,
+    SaveConflictAndAdd,
+    NextConflictFile,
+    PrevConflictFile,
+    OpenReflog,
+    OpenBisect,
+    OpenCommitStack,
+    OpenPullRequests,
+    OpenRepoStats,
+    UndoLastRollback,
+}
 
-            window
-                .update(|window, cx| {
-                    if let Some(err) = old_err {
-                        window.push_notification(
-                            Notification::new().message(format!(
-                                "读取 {} 版本失败，按空内容处理：{err}",
-                                compare_target_side_label(&target, Side::Old)
-                            )),
-                            cx,
-                        );
-                    }
-                    if let Some(err) = new_err {
-                        window.push_notification(
-                            Notification::new().message(format!(
-                                "读取 {} 版本失败，按空内容处理：{err}",
-                                compare_target_side_label(&target, Side::New)
-                            )),
-                            cx,
-                        );
-                    }
+const COMMAND_PALETTE_ITEMS: &[CommandPaletteItem<CommandPaletteCommand>] = &[
+    CommandPaletteItem::new(CommandPaletteCommand::Back, "返回", "back esc 返回 close"),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::Next,
+        "下一处（hunk/冲突）",
+        "next 下一个 hunk 冲突 navigate",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::Prev,
+        "上一处（hunk/冲突）",
+        "prev 上一个 hunk 冲突 navigate",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::ToggleViewMode,
+        "切换视图 Split/Inline",
+        "toggle view mode split inline 视图",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::ToggleSplitLayout,
+        "切换布局 对齐/分栏",
+        "toggle layout aligned two-pane 对齐 分栏",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::TogglePaneScrollLink,
+        "切换分栏联动滚动",
+        "toggle scroll link sync independent pane 联动 滚动 独立",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::SyncPaneScroll,
+        "右侧对齐到左侧对应行",
+        "sync align scroll counterpart line pane 对齐 同步 对应行",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::ToggleWhitespace,
+        "切换忽略空白",
+        "toggle whitespace ignore 空白 忽略",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::ToggleIgnoreEol,
+        "切换忽略换行符差异",
+        "toggle eol crlf lf line ending ignore 换行符 忽略",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::ExpandAll,
+        "展开全部折叠",
+        "expand all folds 展开 折叠",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::ToggleFoldByFunction,
+        "切换按函数折叠",
+        "fold by function context collapse 函数 折叠",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::OpenFileHistory,
+        "打开文件历史对比",
+        "history log commit 历史 对比",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::OpenInEditor,
+        "在外部编辑器中打开（当前 hunk 所在行）",
+        "open editor external vscode code zed vim 编辑器 打开",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::CopyPatch,
+        "复制为 patch（选中行或当前 hunk）",
+        "copy patch diff hunk selection clipboard 复制 补丁",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::CopyNewText,
+        "复制新版本文本（选中行或当前 hunk）",
+        "copy new text clipboard 复制 文本 新版本",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::ExportPatch,
+        "导出 patch（当前文件或全部已暂存变更）",
+        "export patch diff save file staged 导出 补丁 保存",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::ApplyPatchFromFile,
+        "从文件应用 patch…",
+        "apply patch diff file git apply 应用 补丁 文件",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::ApplyPatchFromClipboard,
+        "从剪贴板应用 patch",
+        "apply patch diff clipboard paste git apply 应用 补丁 剪贴板 粘贴",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::ApplyEditor,
+        "应用合并结果编辑",
+        "apply editor 应用 合并 结果",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::SaveConflict,
+        "保存冲突结果到文件",
+        "save conflict 保存 写入",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::SaveConflictAndAdd,
+        "保存并 git add",
+        "save add stage resolved 解决",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::NextConflictFile,
+        "下一个冲突文件",
+        "next conflict file merge 下一个 冲突 文件",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::PrevConflictFile,
+        "上一个冲突文件",
+        "previous prev conflict file merge 上一个 冲突 文件",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::OpenBranchCompare,
+        "对比分支（领先/落后提交与变更文件）",
+        "compare branch ahead behind refs merge base 对比 分支 领先 落后",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::OpenPullRequests,
+        "Pull Request / Merge Request（GitHub / GitLab）",
+        "pull request merge request pr mr github gitlab review checkout 拉取 合并 请求 评审 检出",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::OpenRepoStats,
+        "仓库统计（作者、目录改动、大文件、语言）",
+        "stats statistics author churn language loc size dashboard 统计 作者 改动 语言 行数 大文件",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::OpenReflog,
+        "打开 Reflog（恢复丢失的提交）",
+        "reflog recover restore reset branch 恢复 找回",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::OpenBisect,
+        "Bisect（二分查找引入问题的提交）",
+        "bisect good bad skip culprit regression 二分 定位 回归",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::OpenCommitStack,
+        "提交栈（重排、合并、改写上游之后的提交）",
+        "stack patch rebase reorder squash reword drop 提交栈 重排 合并 改写",
+    ),
+    CommandPaletteItem::new(
+        CommandPaletteCommand::UndoLastRollback,
+        "撤销上次回滚（从备份恢复文件）",
+        "undo rollback revert restore backup trash 撤销 回滚 恢复 备份",
+    ),
+];
 
-                    this.update(cx, |this, _cx| {
-                        this.diff_content_revision = this.diff_content_revision.wrapping_add(1);
-                        this.conflict_view = None;
-                        this.diff_view = Some(DiffViewState::from_precomputed(
-                            format!("{status_for_task} {path_for_task}").into(),
-                            Some(path_for_task.clone()),
-                            Some(status_for_task.clone()),
-                            target,
-                            old_text,
-                            new_text,
-                            view_mode,
-                            model,
-                            old_lines,
-                            new_lines,
-                        ));
-                        this.screen = AppScreen::DiffView;
-                    });
-                })
-                .ok();
+/// 回滚前备份的工作区文件，用于“撤销上次回滚”。
+#[derive(Clone, Debug)]
+struct RollbackBackup {
+    path: String,
+    backup: PathBuf,
+}
 
-            Some(())
-        })
-        .detach();
+#[derive(Clone, Copy, Debug)]
+enum ConflictResolution {
+    Ours,
+    Theirs,
+    Base,
+    Both,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum HunkApplyAction {
+    Stage,
+    Unstage,
+    Revert,
+}
+
+impl HunkApplyAction {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Stage => "Stage",
+            Self::Unstage => "Unstage",
+            Self::Revert => "Revert",
+        }
     }
 
-    fn open_file_diff_with_refs(
-        &mut self,
-        path: String,
-        status: Option<String>,
-        left_ref: String,
-        right_ref: String,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) {
-        if !self.git_available {
-            window.push_notification(
-                Notification::new().message("未检测到 git 命令，无法进行历史/任意版本对比"),
-                cx,
-            );
-            return;
+    fn required_compare_target(self) -> CompareTarget {
+        match self {
+            Self::Stage | Self::Revert => CompareTarget::IndexToWorktree,
+            Self::Unstage => CompareTarget::HeadToIndex,
         }
+    }
 
-        let left_ref = left_ref.trim().to_string();
-        let right_ref = right_ref.trim().to_string();
-        if left_ref.is_empty() {
-            window.push_notification(
-                Notification::new().message("左侧 ref 不能为空（例如 HEAD / HEAD~1 / a1b2c3）"),
-                cx,
-            );
-            return;
+    fn git_args(self) -> Vec<&'static str> {
+        match self {
+            Self::Stage => vec!["apply", "--cached"],
+            Self::Unstage => vec!["apply", "-R", "--cached"],
+            Self::Revert => vec!["apply", "-R"],
         }
+    }
+}
 
-        let compare_target = CompareTarget::Refs {
-            left: left_ref.clone(),
-            right: right_ref.clone(),
-        };
+#[derive(Clone, Debug)]
+enum DisplayRow {
+    HunkHeader {
+        hunk: usize,
+        text: SharedString,
+    },
+    Fold {
+        old_start: usize,
+        new_start: usize,
+        len: usize,
+        /// 折叠区域末尾所在函数的签名行。
+        function: Option<SharedString>,
+    },
+    Code {
+        kind: diffview::DiffRowKind,
+        old_line: Option<usize>,
+        new_line: Option<usize>,
+        old_segments: Vec<diffview::DiffSegment>,
+        new_segments: Vec<diffview::DiffSegment>,
+    },
+}
 
-        let title: SharedString = match &status {
-            Some(status) if !status.trim().is_empty() => format!("{status} {path}").into(),
-            _ => path.clone().into(),
-        };
+/// diff 视图中的一段连续行，只记录位置；具体的 [`DisplayRow`] 在渲染可见行时再从
+/// `DiffModel` 生成，避免超大文件一次性展开全部行。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RowSpan {
+    HunkHeader {
+        hunk: usize,
+    },
+    /// hunk 内从 `first_row` 开始的 `len` 行；`paired` 表示 Inline 模式下每个 Modified 行拆成删除 + 新增两行。
+    HunkRows {
+        hunk: usize,
+        first_row: usize,
+        len: usize,
+        paired: bool,
+    },
+    Fold {
+        old_start: usize,
+        new_start: usize,
+        len: usize,
+    },
+    /// 已展开的折叠区域。
+    Context {
+        old_start: usize,
+        new_start: usize,
+        len: usize,
+    },
+}
 
-        window.push_notification(
-            Notification::new().message(format!(
-                "正在加载 diff：{path}（{}）",
-                compare_target_label(&compare_target)
-            )),
-            cx,
-        );
+impl RowSpan {
+    fn row_count(self) -> usize {
+        match self {
+            RowSpan::HunkHeader { .. } | RowSpan::Fold { .. } => 1,
+            RowSpan::HunkRows { len, paired, .. } => {
+                if paired {
+                    len * 2
+                } else {
+                    len
+                }
+            }
+            RowSpan::Context { len, .. } => len,
+        }
+    }
+}
 
-        let this = cx.entity();
-        let repo_root = self.repo_root.clone();
-        let path_for_task = path.clone();
-        let status_for_task = status.clone();
+#[derive(Clone, Debug, Default)]
+struct DiffRowLayout {
+    spans: Vec<RowSpan>,
+    /// 每段首行的行号，用于二分定位。
+    starts: Vec<usize>,
+    len: usize,
+}
 
-        cx.spawn_in(window, async move |_, window| {
-            let path_for_task_bg = path_for_task.clone();
-            let left_ref_for_io = left_ref.clone();
-            let right_ref_for_io = right_ref.clone();
-            let (old_text, old_err, new_text, new_err) = window
-                .background_executor()
-                .spawn(async move {
-                    let (old_text, old_err) = match read_specified_file(
-                        &repo_root,
-                        &path_for_task_bg,
-                        &left_ref_for_io,
-                    ) {
-                        Ok(text) => (text, None),
-                        Err(err) => (String::new(), Some(err.to_string())),
-                    };
+const LFS_POINTER_VERSIONS: [&str; 2] = [
+    "https://git-lfs.github.com/spec/v1",
+    "https://hawser.github.com/spec/v1",
+];
+const LFS_POINTER_MAX_LEN: usize = 1024;
 
-                    let (new_text, new_err) =
-                        match read_specified_file(&repo_root, &path_for_task_bg, &right_ref_for_io)
-                        {
-                            Ok(text) => (text, None),
-                            Err(err) => (String::new(), Some(err.to_string())),
-                        };
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct LfsPointer {
+    oid: String,
+    size: u64,
+}
 
-                    (old_text, old_err, new_text, new_err)
-                })
-                .await;
+/// 任一侧是 LFS 指针时，diff 展示的是对象摘要而不是指针文本；原始内容保留下来，
+/// 以便用户按需通过 `git lfs smudge` 加载实际内容后再对比。
+#[derive(Clone, Debug)]
+struct LfsDiffInfo {
+    old: Option<LfsPointer>,
+    new: Option<LfsPointer>,
+    old_source: String,
+    new_source: String,
+    content_loaded: bool,
+}
 
-            let diff_options =
-                window
-                    .update(|_, cx| this.read(cx).diff_options)
-                    .unwrap_or(DiffViewOptions {
-                        ignore_whitespace: false,
-                        context_lines: 3,
-                    });
-            let view_mode = window
-                .update(|_, cx| this.read(cx).view_mode)
-                .unwrap_or(DiffViewMode::Split);
+const MINIMAP_BUCKETS: usize = 240;
 
-            let (old_text, new_text, model, old_lines, new_lines) = window
-                .background_executor()
-                .spawn(async move {
-                    let (model, old_lines, new_lines) = build_diff_model(
-                        &old_text,
-                        &new_text,
-                        diff_options.ignore_whitespace,
-                        diff_options.context_lines,
-                    );
-                    (old_text, new_text, model, old_lines, new_lines)
-                })
-                .await;
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum MinimapMarkKind {
+    Added,
+    Removed,
+    Modified,
+    Conflict,
+    SearchMatch,
+}
 
-            window
-                .update(|window, cx| {
-                    if let Some(err) = old_err {
-                        window.push_notification(
-                            Notification::new().message(format!(
-                                "读取 {} 版本失败，按空内容处理：{err}",
-                                compare_target_side_label(&compare_target, Side::Old)
-                            )),
-                            cx,
-                        );
-                    }
-                    if let Some(err) = new_err {
-                        window.push_notification(
-                            Notification::new().message(format!(
-                                "读取 {} 版本失败，按空内容处理：{err}",
-                                compare_target_side_label(&compare_target, Side::New)
-                            )),
-                            cx,
-                        );
-                    }
-
-                    this.update(cx, |this, _cx| {
-                        this.diff_content_revision = this.diff_content_revision.wrapping_add(1);
-                        this.conflict_view = None;
-                        this.diff_view = Some(DiffViewState::from_precomputed(
-                            title,
-                            Some(path_for_task.clone()),
-                            status_for_task.clone(),
-                            compare_target.clone(),
-                            old_text,
-                            new_text,
-                            view_mode,
-                            model,
-                            old_lines,
-                            new_lines,
-                        ));
-                        this.screen = AppScreen::DiffView;
-                    });
-                })
-                .ok();
+/// minimap 上的一段标记；行按 [`MINIMAP_BUCKETS`] 分桶，相邻同类桶合并为一段。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct MinimapMark {
+    first_bucket: usize,
+    last_bucket: usize,
+    buckets: usize,
+    kind: MinimapMarkKind,
+    row: usize,
+}
 
-            Some(())
-        })
-        .detach();
-    }
+/// diff 查找栏的匹配方式开关。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct DiffSearchOptions {
+    case_sensitive: bool,
+    whole_word: bool,
+    regex: bool,
+}
 
-    fn apply_compare_refs_from_inputs(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        let Some(diff_view) = self.diff_view.as_ref() else {
-            return;
+impl DiffSearchOptions {
+    /// 把查询编译成正则；非正则模式下先转义，整词模式再包一层 `\b`。
+    fn compile(self, query: &str) -> Result<regex::Regex, regex::Error> {
+        let pattern = if self.regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
         };
-        let Some(path) = diff_view.path.clone() else {
-            window.push_notification(
-                Notification::new().message("当前 diff 为 demo，暂不支持任意版本对比"),
-                cx,
-            );
-            return;
+        let pattern = if self.whole_word {
+            format!(r"\b(?:{pattern})\b")
+        } else {
+            pattern
         };
-
-        let left_ref = self.compare_left_input.read(cx).value().to_string();
-        let right_ref = self.compare_right_input.read(cx).value().to_string();
-        let status = diff_view.status.clone();
-        self.open_file_diff_with_refs(path, status, left_ref, right_ref, window, cx);
-    }
-
-    fn swap_compare_refs_inputs(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        let left_ref = self.compare_left_input.read(cx).value().to_string();
-        let right_ref = self.compare_right_input.read(cx).value().to_string();
-
-        self.compare_left_input.update(cx, |state, cx| {
-            state.set_value(right_ref.clone(), window, cx);
-        });
-        self.compare_right_input.update(cx, |state, cx| {
-            state.set_value(left_ref.clone(), window, cx);
-        });
-        cx.notify();
+        regex::RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .build()
     }
+}
 
-    fn open_file_history_overlay(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        if !self.git_available {
-            window.push_notification(
-                Notification::new().message("未检测到 git 命令，无法打开历史对比"),
-                cx,
-            );
-            return;
-        }
-
-        self.command_palette_overlay = None;
+#[derive(Clone, Debug)]
+struct DiffSearchState {
+    query: String,
+    options: DiffSearchOptions,
+    /// 正则无效时为 `None`，此时 `rows` 为空，错误信息放在 `error`。
+    pattern: Option<regex::Regex>,
+    error: Option<String>,
+    rows: Rc<Vec<usize>>,
+    current: Option<usize>,
+}
 
-        let Some(diff_view) = self.diff_view.as_ref() else {
-            return;
-        };
-        let Some(path) = diff_view.path.clone() else {
-            window.push_notification(
-                Notification::new().message("当前 diff 为 demo，暂不支持历史对比"),
-                cx,
-            );
-            return;
-        };
+#[derive(Clone)]
+struct DiffViewState {
+    title: SharedString,
+    path: Option<String>,
+    status: Option<String>,
+    compare_target: CompareTarget,
+    old_text: String,
+    new_text: String,
+    old_lines: Vec<String>,
+    new_lines: Vec<String>,
+    diff_model: diffview::DiffModel,
+    rows: DiffRowLayout,
+    hunk_rows: Vec<usize>,
+    current_hunk: usize,
+    scroll_handle: VirtualListScrollHandle,
+    scroll_state: ScrollbarState,
+    /// 分栏布局取消联动滚动时，右侧（新）面板使用的独立滚动状态。
+    new_pane_scroll_handle: VirtualListScrollHandle,
+    new_pane_scroll_state: ScrollbarState,
+    list_item_sizes: Rc<Vec<Size<Pixels>>>,
+    list_item_height: Pixels,
+    lfs: Option<LfsDiffInfo>,
+    minimap: Option<Rc<Vec<MinimapMark>>>,
+    search: Option<DiffSearchState>,
+    /// 选中的行（锚点, 当前行），用于复制选中行为 patch / 文本。
+    selection: Option<(usize, usize)>,
+    /// 按函数折叠：只折叠完整的未变更函数，改动所在函数整体展开。
+    fold_by_function: bool,
+    /// 文件不是 UTF-8 时识别出的编码，显示在状态栏。
+    encoding: Option<TextEncoding>,
+    /// 计算 diff 的耗时，显示在状态栏。
+    diff_timing: DiffTiming,
+}
 
-        let status = diff_view.status.clone();
-        let filter_input = cx.new(|cx| {
-            InputState::new(window, cx)
-                .placeholder("搜索 commit（hash / message）")
-                .default_value("")
-        });
+#[derive(Clone, Debug)]
+enum ConflictRow {
+    EmptyState {
+        text: SharedString,
+    },
+    BlockHeader {
+        conflict_index: usize,
+        ours_branch_name: SharedString,
+        theirs_branch_name: SharedString,
+        has_base: bool,
+    },
+    Code {
+        kind: diffview::DiffRowKind,
+        ours_segments: Vec<diffview::DiffSegment>,
+        base_segments: Vec<diffview::DiffSegment>,
+        theirs_segments: Vec<diffview::DiffSegment>,
+    },
+}
 
-        self.file_history_overlay = Some(FileHistoryOverlayState {
-            path: path.clone(),
-            status,
-            commits: Vec::new(),
-            loading: true,
-            selected: 0,
-            mode: HistoryCompareMode::ParentToCommit,
-            filter_input: filter_input.clone(),
-        });
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ConflictSource {
+    WorktreeMarkers,
+    /// `auto_resolved` 是按配置的策略自动解决、不再显示为冲突的区域数。
+    IndexStages {
+        auto_merged: usize,
+        auto_resolved: usize,
+    },
+}
 
-        filter_input.update(cx, |state, cx| state.focus(window, cx));
+/// 冲突视图的内容：由 index stage 做出的 diff3 合并模型，或带冲突标记的文本。
+enum ConflictContent {
+    Merge(diffview::MergeModel),
+    Markers(String),
+}
 
-        let this = cx.entity();
-        let repo_root = self.repo_root.clone();
-        let path_for_task = path.clone();
-        cx.spawn_in(window, async move |_, window| {
-            let path_for_task_bg = path_for_task.clone();
-            let path_for_update = path_for_task.clone();
-            let repo_root_bg = repo_root.clone();
-            let commits = window
-                .background_executor()
-                .spawn(async move { fetch_file_history(&repo_root_bg, &path_for_task_bg, 200) })
-                .await;
+impl ConflictContent {
+    /// 结果编辑器里显示的文本；合并模型中还没解决的块用冲突标记表示。
+    fn result_text(&self) -> String {
+        match self {
+            ConflictContent::Merge(model) => model.to_conflict_text("ours", "theirs").0,
+            ConflictContent::Markers(text) => text.clone(),
+        }
+    }
 
-            window
-                .update(|window, cx| match commits {
-                    Ok(commits) => this.update(cx, |this, cx| {
-                        if let Some(overlay) = this.file_history_overlay.as_mut() {
-                            if overlay.path == path_for_update {
-                                overlay.loading = false;
-                                overlay.commits = commits;
-                                overlay.selected = 0;
-                                cx.notify();
-                            }
-                        }
+    fn blocks(&self) -> Vec<ConflictBlock> {
+        match self {
+            ConflictContent::Merge(model) => model
+                .chunks
+                .iter()
+                .enumerate()
+                .filter_map(|(index, chunk)| match chunk {
+                    diffview::MergeChunk::Conflict { base, ours, theirs } => Some(ConflictBlock {
+                        ours_branch_name: "ours".to_string(),
+                        theirs_branch_name: "theirs".to_string(),
+                        ours: ours.clone(),
+                        base: Some(base.clone()),
+                        theirs: theirs.clone(),
+                        location: ConflictLocation::Chunk(index),
                     }),
-                    Err(err) => {
-                        window.push_notification(
-                            Notification::new().message(format!("获取历史失败：{err:#}")),
-                            cx,
-                        );
-                        this.update(cx, |this, cx| {
-                            if let Some(overlay) = this.file_history_overlay.as_mut() {
-                                if overlay.path == path_for_update {
-                                    overlay.loading = false;
-                                    cx.notify();
-                                }
-                            }
-                        });
-                    }
+                    _ => None,
                 })
-                .ok();
-
-            Some(())
-        })
-        .detach();
-    }
-
-    fn close_file_history_overlay(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        if self.file_history_overlay.take().is_some() {
-            window.focus(&self.focus_handle);
-            cx.notify();
+                .collect(),
+            ConflictContent::Markers(text) => diffview::parse_conflicts(text)
+                .into_iter()
+                .map(|region| ConflictBlock {
+                    ours: text[region.ours].to_string(),
+                    base: region.base.map(|range| text[range].to_string()),
+                    theirs: text[region.theirs].to_string(),
+                    location: ConflictLocation::Markers(region.range),
+                    ours_branch_name: region.ours_branch_name,
+                    theirs_branch_name: region.theirs_branch_name,
+                })
+                .collect(),
         }
     }
+}
 
-    fn apply_selected_file_history_commit(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        let Some(overlay) = self.file_history_overlay.as_ref() else {
-            return;
-        };
-        if overlay.loading || overlay.commits.is_empty() {
-            return;
-        }
+/// 冲突视图里的一处冲突及其三侧内容。
+#[derive(Clone, Debug)]
+struct ConflictBlock {
+    ours_branch_name: String,
+    theirs_branch_name: String,
+    ours: String,
+    base: Option<String>,
+    theirs: String,
+    location: ConflictLocation,
+}
 
-        let query = overlay.filter_input.read(cx).value().to_string();
-        let filtered = filter_commits(&overlay.commits, &query);
-        let Some(entry) = filtered.get(overlay.selected.min(filtered.len().saturating_sub(1)))
-        else {
-            return;
-        };
+#[derive(Clone, Debug)]
+enum ConflictLocation {
+    /// 合并模型 `chunks` 中的下标。
+    Chunk(usize),
+    /// 整段冲突（含标记行）在文本中的范围。
+    Markers(Range<usize>),
+}
 
-        let (left_ref, right_ref) = match overlay.mode {
-            HistoryCompareMode::ParentToCommit => (format!("{}^", entry.hash), entry.hash.clone()),
-            HistoryCompareMode::CommitToWorktree => (entry.hash.clone(), String::new()),
-        };
+struct ConflictViewState {
+    title: SharedString,
+    path: Option<String>,
+    source: ConflictSource,
+    content: ConflictContent,
+    /// `content` 的结果文本，与结果编辑器的初始内容一致。
+    text: String,
+    result_input: Entity<InputState>,
+    show_result_editor: bool,
+    conflicts: Vec<ConflictBlock>,
+    rows: Vec<ConflictRow>,
+    conflict_rows: Vec<usize>,
+    current_conflict: usize,
+    scroll_handle: VirtualListScrollHandle,
+    scroll_state: ScrollbarState,
+    list_item_sizes: Rc<Vec<Size<Pixels>>>,
+    list_item_height: Pixels,
+    /// 工作区文件的编码，保存结果时按它写回。
+    encoding: TextEncoding,
+}
 
-        let path = overlay.path.clone();
-        let status = overlay.status.clone();
-        self.file_history_overlay = None;
-        self.open_file_diff_with_refs(path, status, left_ref, right_ref, window, cx);
-    }
+struct GitViewerApp {
+    repo_root: PathBuf,
+    /// 当前分支名；HEAD 分离时为短提交号。随状态刷新一起更新。
+    current_branch: Option<String>,
+    files: Vec<FileEntry>,
+    /// 与 `files` 同步增量维护的各类计数。
+    status_counts: StatusCounts,
+    loading: bool,
+    git_available: bool,
+    focus_handle: FocusHandle,
+    screen: AppScreen,
+    diff_view: Option<DiffViewState>,
+    conflict_view: Option<ConflictViewState>,
+    diff_options: DiffViewOptions,
+    compare_left_input: Entity<InputState>,
+    compare_right_input: Entity<InputState>,
+    diff_search_input: Entity<InputState>,
+    diff_search_options: DiffSearchOptions,
+    file_history_overlay: Option<FileHistoryOverlayState>,
+    command_palette_overlay: Option<CommandPalette<CommandPaletteCommand>>,
+    commit_message_input: Entity<InputState>,
+    committing: bool,
+    amend: Option<AmendState>,
+    trailer_suggestions: Option<TrailerSuggestions>,
+    trailer_suggestions_loading: bool,
+    commit_failure_overlay: Option<CommitFailureOverlayState>,
+    askpass_overlay: Option<AskpassOverlayState>,
+    askpass_queue: VecDeque<askpass::AskpassRequest>,
+    patch_apply_overlay: Option<PatchApplyOverlayState>,
+    reflog_view: Option<ReflogViewState>,
+    branch_compare_view: Option<BranchCompareState>,
+    pull_requests_view: Option<PullRequestsState>,
+    repo_stats_view: Option<RepoStatsState>,
+    pull_request_review: Option<PullRequestReview>,
+    bisect_view: Option<BisectViewState>,
+    commit_stack: Option<CommitStackState>,
+    commit_details: Option<CommitDetailsState>,
+    /// 关闭 diff 视图时返回的页面；None 表示返回状态列表。
+    diff_return_screen: Option<AppScreen>,
+    diff_content_revision: u64,
+    diff_rebuild_seq: u64,
+    split_layout: SplitLayout,
+    pane_scroll_linked: bool,
+    /// 并排面板的分隔位置，跨启动保留。
+    panel_sizes: Rc<dyn PanelSizeStorage>,
+    notification_center: Entity<NotificationCenter>,
+    view_mode: DiffViewMode,
+    fold_by_function: bool,
+    /// Markdown 文件在 diff 视图里改为显示渲染后的右侧版本。
+    markdown_preview: bool,
+    markdown_view: Option<Entity<MarkdownView>>,
+    status_filter: StatusFilter,
+    /// 最近一次 Revert hunk 前的文件备份。
+    last_rollback: Option<RollbackBackup>,
+    /// 冲突文件路径 -> 工作区中剩余的冲突标记数。
+    conflict_marker_counts: HashMap<String, usize>,
+    conflict_marker_counts_stale: bool,
+    conflict_marker_counts_loading: bool,
+    /// 已读取的 diff 文本；后台预取任务也会写入，所以放在锁里共享。
+    diff_cache: Arc<Mutex<DiffTextCache>>,
+    diff_preload_stale: bool,
+    /// 未跟踪目录（`dir/`）-> 其中未被忽略的文件。
+    untracked_dir_files: HashMap<String, Vec<String>>,
+    untracked_dir_files_stale: bool,
+    untracked_dir_files_loading: bool,
+    expanded_untracked_dirs: HashSet<String>,
+    /// 已点过一次“删除目录”、等待再次确认的目录。
+    confirm_delete_untracked_dir: Option<String>,
+}
 
-    fn handle_file_history_overlay_key(
-        &mut self,
-        event: &KeyDownEvent,
+impl GitViewerApp {
+    fn new(
         window: &mut Window,
         cx: &mut Context<Self>,
-    ) -> bool {
-        let Some(overlay) = self.file_history_overlay.as_mut() else {
-            return false;
-        };
-
-        match event.keystroke.key.as_str() {
-            "escape" => {
-                self.close_file_history_overlay(window, cx);
-                true
-            }
-            "enter" => {
-                self.apply_selected_file_history_commit(window, cx);
-                true
-            }
-            "up" | "down" | "pageup" | "pagedown" => {
-                let query = overlay.filter_input.read(cx).value().to_string();
-                let filtered_len = filter_commits(&overlay.commits, &query).len();
-                if filtered_len == 0 {
-                    return true;
-                }
-
-                let step = match event.keystroke.key.as_str() {
-                    "pageup" | "pagedown" => 10usize,
-                    _ => 1usize,
-                };
+        start_dir: PathBuf,
+        launch_target: Option<LaunchTarget>,
+        askpass_requests: Option<mpsc::Receiver<askpass::AskpassRequest>>,
+    ) -> Self {
+        let this = cx.entity();
+        let repo_root = detect_repo_root(&start_dir);
+        let repo_root_for_task = repo_root.clone();
+        let git_available = Command::new("git").arg("--version").output().is_ok();
+        let focus_handle = cx.focus_handle().tab_stop(true);
+        let notification_center = cx.new(|cx| {
+            NotificationCenter::new(window, cx)
+                .history_title("通知记录")
+                .empty_text("暂无通知")
+                .clear_label("清空")
+        });
 
-                let mut selected = overlay.selected.min(filtered_len.saturating_sub(1));
-                match event.keystroke.key.as_str() {
-                    "up" => selected = selected.saturating_sub(step),
-                    "pageup" => selected = selected.saturating_sub(step),
-                    "down" => selected = (selected + step).min(filtered_len.saturating_sub(1)),
-                    "pagedown" => selected = (selected + step).min(filtered_len.saturating_sub(1)),
-                    _ => {}
-                }
-                overlay.selected = selected;
-                cx.notify();
-                true
-            }
-            _ => false,
-        }
-    }
+        if git_available {
+            cx.spawn_in(window, async move |_, window| {
+                let entries = window
+                    .background_executor()
+                    .spawn(async move {
+                        fetch_git_status(&repo_root_for_task)
+                            .map_err(|err| {
+                                tracing::error!("git status failed: {err:?}");
+                                err
+                            })
+                            .unwrap_or_default()
+                    })
+                    .await;
 
-    fn open_command_palette(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        self.file_history_overlay = None;
+                let _ = window.update(|window, cx| {
+                    this.update(cx, |this, cx| {
+                        this.loading = false;
+                        this.set_status_entries(entries);
+                        if let Some(target) = launch_target {
+                            this.open_launch_target(target, window, cx);
+                        }
+                    })
+                });
 
-        if let Some(overlay) = self.command_palette_overlay.as_ref() {
-            overlay
-                .input
-                .update(cx, |state, cx| state.focus(window, cx));
-            return;
+                Some(())
+            })
+            .detach();
+        } else {
+            window.push_toast(
+                Toast::warning("未检测到 git 命令：已禁用仓库状态与 Git 操作（可打开 Demo）"),
+                cx,
+            );
         }
 
-        let input = cx.new(|cx| {
+        let compare_left_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("左侧 ref（例如 HEAD~1 / a1b2c3）")
+                .default_value("HEAD")
+        });
+        let compare_right_input = cx.new(|cx| {
             InputState::new(window, cx)
-                .placeholder("输入命令…（↑↓ 选择，Enter 执行，Esc 关闭）")
+                .placeholder("右侧 ref（留空=工作区，或 INDEX / :）")
                 .default_value("")
         });
-
-        input.update(cx, |state, cx| {
-            state.set_value(String::new(), window, cx);
-            state.focus(window, cx);
+        let diff_search_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("在 diff 中查找")
+                .default_value("")
+        });
+        let commit_message_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .code_editor("text")
+                .placeholder("提交信息（第一行为标题）")
+                .default_value("")
         });
 
-        self.command_palette_overlay = Some(CommandPaletteOverlayState { input, selected: 0 });
-        cx.notify();
-    }
+        if let Some(receiver) = askpass_requests {
+            // askpass 请求来自后台线程，这里定时轮询并转交给 UI。
+            cx.spawn_in(window, async move |this, window| {
+                loop {
+                    Timer::after(Duration::from_millis(ASKPASS_POLL_INTERVAL_MS)).await;
+
+                    let mut requests = Vec::new();
+                    loop {
+                        match receiver.try_recv() {
+                            Ok(request) => requests.push(request),
+                            Err(mpsc::TryRecvError::Empty) => break,
+                            Err(mpsc::TryRecvError::Disconnected) => return,
+                        }
+                    }
+                    if requests.is_empty() {
+                        continue;
+                    }
 
-    fn close_command_palette(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        if self.command_palette_overlay.take().is_some() {
-            window.focus(&self.focus_handle);
-            cx.notify();
+                    let delivered = window.update(|window, cx| {
+                        this.update(cx, |this, cx| {
+                            this.enqueue_askpass_requests(requests, window, cx);
+                        })
+                    });
+                    if !matches!(delivered, Ok(Ok(()))) {
+                        return;
+                    }
+                }
+            })
+            .detach();
+        }
+
+        Self {
+            current_branch: read_head_branch(&repo_root),
+            repo_root,
+            files: Vec::new(),
+            status_counts: StatusCounts::default(),
+            loading: git_available,
+            git_available,
+            focus_handle,
+            screen: AppScreen::StatusList,
+            diff_view: None,
+            conflict_view: None,
+            diff_options: DiffViewOptions::default(),
+            compare_left_input,
+            compare_right_input,
+            diff_search_input,
+            diff_search_options: DiffSearchOptions::default(),
+            file_history_overlay: None,
+            command_palette_overlay: None,
+            commit_message_input,
+            committing: false,
+            amend: None,
+            trailer_suggestions: None,
+            trailer_suggestions_loading: false,
+            commit_failure_overlay: None,
+            askpass_overlay: None,
+            askpass_queue: VecDeque::new(),
+            patch_apply_overlay: None,
+            reflog_view: None,
+            branch_compare_view: None,
+            pull_requests_view: None,
+            repo_stats_view: None,
+            pull_request_review: None,
+            bisect_view: None,
+            commit_stack: None,
+            commit_details: None,
+            diff_return_screen: None,
+            diff_content_revision: 0,
+            diff_rebuild_seq: 0,
+            split_layout: SplitLayout::TwoPane,
+            pane_scroll_linked: true,
+            panel_sizes: panel_size_storage(),
+            notification_center,
+            view_mode: DiffViewMode::Split,
+            fold_by_function: false,
+            markdown_preview: false,
+            markdown_view: None,
+            status_filter: StatusFilter::All,
+            last_rollback: None,
+            conflict_marker_counts: HashMap::new(),
+            conflict_marker_counts_stale: false,
+            conflict_marker_counts_loading: false,
+            diff_cache: Arc::new(Mutex::new(DiffTextCache::new(DiffCacheSettings::load(
+                GitViewerSettings::get(),
+            )))),
+            diff_preload_stale: false,
+            untracked_dir_files: HashMap::new(),
+            untracked_dir_files_stale: false,
+            untracked_dir_files_loading: false,
+            expanded_untracked_dirs: HashSet::new(),
+            confirm_delete_untracked_dir: None,
         }
     }
 
-    fn command_palette_command_enabled(&self, command: CommandPaletteCommand) -> bool {
-        match command {
-            CommandPaletteCommand::Back => true,
-            CommandPaletteCommand::Next | CommandPaletteCommand::Prev => match self.screen {
-                AppScreen::DiffView => self
-                    .diff_view
-                    .as_ref()
-                    .is_some_and(|view| !view.hunk_rows.is_empty()),
-                AppScreen::ConflictView => self
-                    .conflict_view
-                    .as_ref()
-                    .is_some_and(|view| !view.conflict_rows.is_empty()),
-                AppScreen::StatusList => false,
-            },
-            CommandPaletteCommand::ToggleViewMode => matches!(self.screen, AppScreen::DiffView),
-            CommandPaletteCommand::ToggleSplitLayout => {
-                matches!(self.screen, AppScreen::DiffView) && self.view_mode == DiffViewMode::Split
-            }
-            CommandPaletteCommand::ToggleWhitespace => matches!(self.screen, AppScreen::DiffView),
-            CommandPaletteCommand::ExpandAll => {
-                self.screen == AppScreen::DiffView
-                    && self.diff_view.as_ref().is_some_and(|view| {
-                        view.rows
-                            .iter()
-                            .any(|row| matches!(row, DisplayRow::Fold { .. }))
-                    })
-            }
-            CommandPaletteCommand::OpenFileHistory => {
-                self.git_available
-                    && self.screen == AppScreen::DiffView
-                    && self
-                        .diff_view
-                        .as_ref()
-                        .is_some_and(|view| view.path.is_some())
-            }
-            CommandPaletteCommand::ApplyEditor => matches!(self.screen, AppScreen::ConflictView),
-            CommandPaletteCommand::SaveConflict | CommandPaletteCommand::SaveConflictAndAdd => {
-                let Some(view) = self.conflict_view.as_ref() else {
-                    return false;
-                };
-                let can_save = view.path.is_some() && view.conflicts.is_empty();
-                match command {
-                    CommandPaletteCommand::SaveConflict => can_save,
-                    CommandPaletteCommand::SaveConflictAndAdd => can_save && self.git_available,
-                    _ => false,
-                }
-            }
+    fn focus_handle(&self) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+
+    /// 用新一轮 `git status` 的结果更新文件列表；只按差异增量更新计数，没有变化时保持原列表不动。
+    fn set_status_entries(&mut self, entries: Vec<FileEntry>) {
+        // 冲突文件的内容可能在状态不变的情况下被修改（例如保存了部分解决结果）。
+        self.conflict_marker_counts_stale = true;
+        // 暂存、提交、编辑都会改变 diff 两侧的内容，缓存整体作废后重新预载。
+        if let Ok(mut cache) = self.diff_cache.lock() {
+            cache.clear();
+        }
+        self.diff_preload_stale = true;
+        self.untracked_dir_files_stale = true;
+        self.confirm_delete_untracked_dir = None;
+        // 切换分支不一定改变文件状态，所以在比较状态之前读取。
+        self.current_branch = read_head_branch(&self.repo_root);
+
+        let delta = diff_status_entries(&self.files, &entries);
+        if delta.is_empty() {
+            return;
+        }
+
+        for entry in &delta.removed {
+            self.status_counts.remove(entry);
+        }
+        for (old, new) in &delta.changed {
+            self.status_counts.remove(old);
+            self.status_counts.add(new);
         }
+        for entry in &delta.added {
+            self.status_counts.add(entry);
+        }
+        self.files = entries;
     }
 
-    fn run_command_palette_command(
+    /// 处理命令行里的 `--diff` / `--compare` / `--conflict`，在首次加载状态后直接进入对应界面。
+    fn open_launch_target(
         &mut self,
-        command: CommandPaletteCommand,
+        target: LaunchTarget,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        match command {
-            CommandPaletteCommand::Back => {
-                if self.command_palette_overlay.is_some() {
-                    self.close_command_palette(window, cx);
-                    return;
-                }
-                if self.file_history_overlay.is_some() {
-                    self.close_file_history_overlay(window, cx);
+        let path = match &target {
+            LaunchTarget::Diff(path) | LaunchTarget::Conflict(path) => Some(path),
+            LaunchTarget::Compare { path, .. } => path.as_ref(),
+        };
+        let relative_path = match path {
+            Some(path) => match repo_relative_path(&self.repo_root, path) {
+                Some(relative_path) => Some(relative_path),
+                None => {
+                    window.push_toast(
+                        Toast::info(format!(
+                            "文件不在仓库 {} 内：{}",
+                            self.repo_root.display(),
+                            path.display()
+                        )),
+                        cx,
+                    );
                     return;
                 }
-
-                match self.screen {
-                    AppScreen::DiffView => self.close_diff_view(),
-                    AppScreen::ConflictView => self.close_conflict_view(),
-                    AppScreen::StatusList => {}
-                }
-                window.focus(&self.focus_handle);
-            }
-            CommandPaletteCommand::Next => match self.screen {
-                AppScreen::DiffView => self.jump_hunk(1),
-                AppScreen::ConflictView => self.jump_conflict(1),
-                AppScreen::StatusList => {}
             },
-            CommandPaletteCommand::Prev => match self.screen {
-                AppScreen::DiffView => self.jump_hunk(-1),
-                AppScreen::ConflictView => self.jump_conflict(-1),
-                AppScreen::StatusList => {}
+            None => None,
+        };
+        let status = relative_path.as_ref().and_then(|relative_path| {
+            self.files
+                .iter()
+                .find(|entry| &entry.path == relative_path)
+                .map(|entry| entry.status.clone())
+        });
+
+        match (target, relative_path) {
+            (LaunchTarget::Diff(_), Some(path)) => match status {
+                Some(status) => self.open_file_diff(path, status, window, cx),
+                None => self.open_file_diff_with_refs(
+                    path,
+                    None,
+                    "HEAD".to_string(),
+                    String::new(),
+                    window,
+                    cx,
+                ),
             },
-            CommandPaletteCommand::ToggleViewMode => {
-                if matches!(self.screen, AppScreen::DiffView) {
-                    let next = match self.view_mode {
-                        DiffViewMode::Split => DiffViewMode::Inline,
-                        DiffViewMode::Inline => DiffViewMode::Split,
-                    };
-                    self.set_view_mode(next);
-                }
-            }
-            CommandPaletteCommand::ToggleSplitLayout => {
-                if self.screen == AppScreen::DiffView && self.view_mode == DiffViewMode::Split {
-                    self.split_layout = match self.split_layout {
-                        SplitLayout::Aligned => SplitLayout::TwoPane,
-                        SplitLayout::TwoPane => SplitLayout::Aligned,
-                    };
-                }
+            (LaunchTarget::Conflict(_), Some(path)) => {
+                let status = status.unwrap_or_else(|| "UU".to_string());
+                self.open_conflict_file(path, status, window, cx);
             }
-            CommandPaletteCommand::ToggleWhitespace => {
-                if matches!(self.screen, AppScreen::DiffView) {
-                    let next = !self.diff_options.ignore_whitespace;
-                    self.set_ignore_whitespace(next, window, cx);
-                }
-            }
-            CommandPaletteCommand::ExpandAll => {
-                if matches!(self.screen, AppScreen::DiffView) {
-                    self.expand_all_folds();
-                }
-            }
-            CommandPaletteCommand::OpenFileHistory => {
-                if matches!(self.screen, AppScreen::DiffView) {
-                    self.open_file_history_overlay(window, cx);
-                }
-            }
-            CommandPaletteCommand::ApplyEditor => {
-                if matches!(self.screen, AppScreen::ConflictView) {
-                    self.apply_conflict_editor(window, cx);
-                }
+            (LaunchTarget::Compare { base, head, .. }, Some(path)) => {
+                self.compare_left_input.update(cx, |state, cx| {
+                    state.set_value(base.clone(), window, cx);
+                });
+                self.compare_right_input.update(cx, |state, cx| {
+                    state.set_value(head.clone(), window, cx);
+                });
+                self.open_file_diff_with_refs(path, status, base, head, window, cx);
             }
-            CommandPaletteCommand::SaveConflict => {
-                if matches!(self.screen, AppScreen::ConflictView) {
-                    self.save_conflict_to_working_tree(false, window, cx);
-                }
-            }
-            CommandPaletteCommand::SaveConflictAndAdd => {
-                if matches!(self.screen, AppScreen::ConflictView) {
-                    self.save_conflict_to_working_tree(true, window, cx);
-                }
+            (LaunchTarget::Compare { base, head, .. }, None) => {
+                self.open_branch_compare_view(window, cx);
+                let Some(compare) = self.branch_compare_view.as_ref() else {
+                    return;
+                };
+                let (base_input, head_input) =
+                    (compare.base_input.clone(), compare.head_input.clone());
+                base_input.update(cx, |state, cx| state.set_value(base, window, cx));
+                head_input.update(cx, |state, cx| state.set_value(head, window, cx));
+                self.run_branch_compare(window, cx);
             }
+            (LaunchTarget::Diff(_) | LaunchTarget::Conflict(_), None) => {}
         }
-        cx.notify();
     }
 
-    fn apply_selected_command_palette_item(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        let Some(overlay) = self.command_palette_overlay.as_ref() else {
-            return;
-        };
+    fn open_demo(&mut self) {
+        let (old, new) = demo_texts();
+        self.open_diff_view(
+            "Split Diff Demo".into(),
+            None,
+            None,
+            CompareTarget::HeadToWorktree,
+            old,
+            new,
+        );
+    }
 
-        let query = overlay.input.read(cx).value().to_string();
-        let filtered = filter_command_palette_items(&query);
-        let Some(item) = filtered.get(overlay.selected.min(filtered.len().saturating_sub(1)))
-        else {
-            return;
-        };
-        if !self.command_palette_command_enabled(item.command) {
-            return;
-        }
+    fn open_large_demo(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let line_count = std::env::var("GIT_VIEWER_LARGE_DEMO_LINES")
+            .ok()
+            .and_then(|value| value.parse::<usize>().ok())
+            .filter(|value| *value > 0)
+            .unwrap_or(20_000);
 
-        let command = item.command;
-        self.run_command_palette_command(command, window, cx);
-        self.close_command_palette(window, cx);
-    }
+        let this = cx.entity();
+        window.push_toast(
+            Toast::info(format!("正在生成 Large Diff Demo（{line_count} 行）…")),
+            cx,
+        );
 
-    fn handle_command_palette_key(
-        &mut self,
-        event: &KeyDownEvent,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) -> bool {
-        let Some(overlay) = self.command_palette_overlay.as_mut() else {
-            return false;
-        };
+        cx.spawn_in(window, async move |_, window| {
+            let (old_text, new_text) = window
+                .background_executor()
+                .spawn(async move { large_demo_texts(line_count) })
+                .await;
 
-        match event.keystroke.key.as_str() {
-            "escape" => {
-                self.close_command_palette(window, cx);
-                true
-            }
-            "enter" => {
-                self.apply_selected_command_palette_item(window, cx);
-                true
-            }
-            "up" | "down" | "pageup" | "pagedown" => {
-                let query = overlay.input.read(cx).value().to_string();
-                let filtered_len = filter_command_palette_items(&query).len();
-                if filtered_len == 0 {
-                    return true;
-                }
+            let diff_options = window
+                .update(|_, cx| this.read(cx).diff_options)
+                .unwrap_or_default();
+            let view_mode = window
+                .update(|_, cx| this.read(cx).view_mode)
+                .unwrap_or(DiffViewMode::Split);
 
-                let step = match event.keystroke.key.as_str() {
-                    "pageup" | "pagedown" => 10usize,
-                    _ => 1usize,
-                };
+            let (old_text, new_text, model, old_lines, new_lines, diff_timing) = window
+                .background_executor()
+                .spawn(async move {
+                    let (model, old_lines, new_lines, diff_timing) =
+                        build_diff_model(&old_text, &new_text, diff_options);
+                    (old_text, new_text, model, old_lines, new_lines, diff_timing)
+                })
+                .await;
 
-                let mut selected = overlay.selected.min(filtered_len.saturating_sub(1));
-                match event.keystroke.key.as_str() {
-                    "up" => selected = selected.saturating_sub(step),
-                    "pageup" => selected = selected.saturating_sub(step),
-                    "down" => selected = (selected + step).min(filtered_len.saturating_sub(1)),
-                    "pagedown" => selected = (selected + step).min(filtered_len.saturating_sub(1)),
-                    _ => {}
-                }
-                overlay.selected = selected;
-                cx.notify();
-                true
-            }
-            _ => false,
-        }
+            window
+                .update(|window, cx| {
+                    window.push_toast(Toast::success("Large Diff Demo 已加载"), cx);
+                    this.update(cx, |this, _cx| {
+                        this.diff_content_revision = this.diff_content_revision.wrapping_add(1);
+                        this.conflict_view = None;
+                        this.diff_view = Some(DiffViewState::from_precomputed(
+                            format!("Large Diff Demo ({line_count} lines)").into(),
+                            None,
+                            None,
+                            CompareTarget::HeadToWorktree,
+                            old_text,
+                            new_text,
+                            view_mode,
+                            model,
+                            old_lines,
+                            new_lines,
+                            diff_timing,
+                        ));
+                        this.screen = AppScreen::DiffView;
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
     }
 
-    fn set_compare_target(
+    fn open_conflict_demo(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let content = ConflictContent::Markers(conflict_demo_text());
+        let initial_text = content.result_text();
+        let result_input = cx.new(move |cx| {
+            InputState::new(window, cx)
+                .code_editor("text")
+                .default_value(initial_text)
+        });
+        self.open_conflict_view(
+            "Conflict Demo".into(),
+            None,
+            ConflictSource::WorktreeMarkers,
+            content,
+            result_input,
+        );
+    }
+
+    fn open_diff_view(
         &mut self,
-        target: CompareTarget,
-        window: &mut Window,
-        cx: &mut Context<Self>,
+        title: SharedString,
+        path: Option<String>,
+        status: Option<String>,
+        compare_target: CompareTarget,
+        old_text: String,
+        new_text: String,
     ) {
-        let Some(diff_view) = self.diff_view.as_ref() else {
-            return;
-        };
-        if diff_view.compare_target == target {
-            return;
+        self.diff_content_revision = self.diff_content_revision.wrapping_add(1);
+        self.conflict_view = None;
+        self.diff_view = Some(DiffViewState::new(
+            title,
+            path,
+            status,
+            compare_target,
+            old_text,
+            new_text,
+            self.diff_options,
+            self.view_mode,
+        ));
+        self.screen = AppScreen::DiffView;
+    }
+
+    fn open_conflict_view(
+        &mut self,
+        title: SharedString,
+        path: Option<String>,
+        source: ConflictSource,
+        content: ConflictContent,
+        result_input: Entity<InputState>,
+    ) {
+        self.diff_view = None;
+        self.conflict_view = Some(ConflictViewState::new(
+            title,
+            path,
+            source,
+            content,
+            result_input,
+        ));
+        self.screen = AppScreen::ConflictView;
+    }
+
+    fn close_diff_view(&mut self) {
+        self.screen = self
+            .diff_return_screen
+            .take()
+            .unwrap_or(AppScreen::StatusList);
+        if let Some(overlay) = self.patch_apply_overlay.as_mut() {
+            overlay.hidden = false;
         }
+    }
 
-        let Some(path) = diff_view.path.clone() else {
+    fn close_conflict_view(&mut self) {
+        self.screen = AppScreen::StatusList;
+    }
+
+    /// 状态列表中所有冲突文件（按列表顺序），以及当前冲突视图所在文件的下标。
+    fn conflict_files(&self) -> (Vec<&FileEntry>, Option<usize>) {
+        let files: Vec<&FileEntry> = self
+            .files
+            .iter()
+            .filter(|entry| is_conflict_status(&entry.status))
+            .collect();
+        let current_path = self
+            .conflict_view
+            .as_ref()
+            .and_then(|view| view.path.as_deref());
+        let current =
+            current_path.and_then(|path| files.iter().position(|entry| entry.path == path));
+        (files, current)
+    }
+
+    fn has_other_conflict_files(&self) -> bool {
+        let (files, current) = self.conflict_files();
+        files.len() > usize::from(current.is_some())
+    }
+
+    /// 打开上一个 / 下一个冲突文件（首尾循环）。
+    fn jump_conflict_file(&mut self, direction: i32, window: &mut Window, cx: &mut Context<Self>) {
+        let (files, current) = self.conflict_files();
+        if files.len() <= usize::from(current.is_some()) {
+            window.push_toast(Toast::info("没有其它冲突文件"), cx);
             return;
+        }
+
+        let len = files.len() as i64;
+        let next = match current {
+            Some(index) => (index as i64 + i64::from(direction)).rem_euclid(len),
+            None if direction < 0 => len - 1,
+            None => 0,
         };
-        let status = diff_view.status.clone().unwrap_or_default();
-        self.open_file_diff_with_target(path, status, target, window, cx);
+        let entry = files[next as usize];
+        let (path, status) = (entry.path.clone(), entry.status.clone());
+        self.open_conflict_file(path, status, window, cx);
     }
 
-    fn open_file(
-        &mut self,
-        path: String,
-        status: String,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) {
-        if status.contains('U') {
-            self.open_conflict_file(path, status, window, cx);
-        } else {
-            self.open_file_diff(path, status, window, cx);
+    /// 状态刷新后在后台统计每个冲突文件工作区中剩余的冲突标记数。
+    fn request_conflict_marker_counts(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.conflict_marker_counts_stale || self.conflict_marker_counts_loading {
+            return;
         }
-    }
+        self.conflict_marker_counts_stale = false;
 
-    fn open_conflict_file(
-        &mut self,
-        path: String,
-        status: String,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) {
+        let paths: Vec<String> = self
+            .files
+            .iter()
+            .filter(|entry| is_conflict_status(&entry.status))
+            .map(|entry| entry.path.clone())
+            .collect();
+        if paths.is_empty() {
+            self.conflict_marker_counts.clear();
+            return;
+        }
+
+        self.conflict_marker_counts_loading = true;
         let this = cx.entity();
         let repo_root = self.repo_root.clone();
-        let path_for_task = path.clone();
-        let status_for_task = status.clone();
-
-        window.push_notification(
-            Notification::new().message(format!("正在加载冲突：{status} {path}")),
-            cx,
-        );
 
         cx.spawn_in(window, async move |_, window| {
-            let path_for_task_bg = path_for_task.clone();
-            let (text, err) = window
+            let counts = window
                 .background_executor()
                 .spawn(async move {
-                    match read_working_file(&repo_root, &path_for_task_bg) {
-                        Ok(text) => (text, None),
-                        Err(err) => (String::new(), Some(err.to_string())),
-                    }
+                    paths
+                        .into_iter()
+                        .filter_map(|path| {
+                            let text = read_working_file(&repo_root, &path).ok()?;
+                            Some((path, diffview::parse_conflicts(&text).len()))
+                        })
+                        .collect::<HashMap<_, _>>()
                 })
                 .await;
 
             window
-                .update(|window, cx| {
-                    if let Some(err) = err {
-                        window.push_notification(
-                            Notification::new()
-                                .message(format!("读取工作区版本失败，按空内容处理：{err}")),
-                            cx,
-                        );
-                    }
-
-                    let initial_text = text.clone();
-                    let result_input = cx.new(move |cx| {
-                        InputState::new(window, cx)
-                            .code_editor("text")
-                            .default_value(initial_text)
-                    });
-                    this.update(cx, |this, _cx| {
-                        this.open_conflict_view(
-                            format!("{status_for_task} {path_for_task}").into(),
-                            Some(path_for_task),
-                            text,
-                            result_input,
-                        );
+                .update(|_, cx| {
+                    this.update(cx, |this, cx| {
+                        this.conflict_marker_counts = counts;
+                        this.conflict_marker_counts_loading = false;
+                        cx.notify();
                     });
                 })
                 .ok();
@@ -1424,136 +1965,313 @@ impl GitViewerApp {
         .detach();
     }
 
-    fn set_ignore_whitespace(&mut self, value: bool, window: &mut Window, cx: &mut Context<Self>) {
-        if self.diff_options.ignore_whitespace == value {
+    fn request_untracked_dir_files(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.untracked_dir_files_stale || self.untracked_dir_files_loading {
             return;
         }
-        self.diff_options.ignore_whitespace = value;
-        self.request_diff_rebuild(window, cx);
-    }
+        self.untracked_dir_files_stale = false;
 
-    fn set_context_lines(&mut self, value: usize, window: &mut Window, cx: &mut Context<Self>) {
-        let value = value.min(MAX_CONTEXT_LINES);
-        if self.diff_options.context_lines == value {
+        let dirs: Vec<String> = self
+            .files
+            .iter()
+            .filter(|entry| is_untracked_dir(entry))
+            .map(|entry| entry.path.clone())
+            .collect();
+        self.expanded_untracked_dirs
+            .retain(|dir| dirs.iter().any(|path| path == dir));
+        if dirs.is_empty() {
+            self.untracked_dir_files.clear();
             return;
         }
-        self.diff_options.context_lines = value;
-        self.request_diff_rebuild(window, cx);
-    }
 
-    fn set_view_mode(&mut self, mode: DiffViewMode) {
-        if self.view_mode == mode {
-            return;
-        }
-        self.view_mode = mode;
-        if let Some(diff_view) = self.diff_view.as_mut() {
-            diff_view.rebuild_rows(mode);
+        self.untracked_dir_files_loading = true;
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+
+        cx.spawn_in(window, async move |_, window| {
+            let files = window
+                .background_executor()
+                .spawn(async move {
+                    dirs.into_iter()
+                        .filter_map(|dir| {
+                            let files = fetch_untracked_dir_files(&repo_root, &dir).ok()?;
+                            Some((dir, files))
+                        })
+                        .collect::<HashMap<_, _>>()
+                })
+                .await;
+
+            window
+                .update(|_, cx| {
+                    this.update(cx, |this, cx| {
+                        this.untracked_dir_files = files;
+                        this.untracked_dir_files_loading = false;
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn toggle_untracked_dir(&mut self, dir: &str) {
+        if !self.expanded_untracked_dirs.remove(dir) {
+            self.expanded_untracked_dirs.insert(dir.to_string());
         }
     }
 
-    fn request_diff_rebuild(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        let Some(diff_view) = self.diff_view.as_ref() else {
+    fn stage_untracked_dir(&mut self, dir: String, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.git_available {
             return;
-        };
+        }
 
-        self.diff_rebuild_seq = self.diff_rebuild_seq.wrapping_add(1);
-        let rebuild_seq = self.diff_rebuild_seq;
-        let content_revision = self.diff_content_revision;
-        let compare_target = diff_view.compare_target.clone();
         let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        window.push_toast(Toast::info(format!("git add {dir}")), cx);
 
         cx.spawn_in(window, async move |_, window| {
-            Timer::after(Duration::from_millis(DIFF_REBUILD_DEBOUNCE_MS)).await;
+            let dir_for_task = dir.clone();
+            let (result, entries) = window
+                .background_executor()
+                .spawn(async move {
+                    let result = run_git(&repo_root, ["add", "--", dir_for_task.as_str()]);
+                    let entries = fetch_git_status(&repo_root);
+                    (result, entries)
+                })
+                .await;
 
-            let snapshot = window
-                .update(|_, cx| {
-                    let app = this.read(cx);
-                    if app.diff_rebuild_seq != rebuild_seq
-                        || app.diff_content_revision != content_revision
-                    {
-                        return None;
+            window
+                .update(|window, cx| {
+                    if let Err(err) = result {
+                        window.push_toast(Toast::error(format!("添加 {dir} 失败：{err:#}")), cx);
                     }
 
-                    let diff_view = app.diff_view.as_ref()?;
-                    Some((
-                        diff_view.title.clone(),
-                        diff_view.path.clone(),
-                        diff_view.status.clone(),
-                        diff_view.old_text.clone(),
-                        diff_view.new_text.clone(),
-                        diff_view.scroll_handle.clone(),
-                        diff_view.scroll_state.clone(),
-                        diff_view.current_hunk,
-                        app.diff_options,
-                        app.view_mode,
-                    ))
+                    this.update(cx, |this, cx| {
+                        if let Ok(entries) = entries {
+                            this.set_status_entries(entries);
+                        }
+                        cx.notify();
+                    });
                 })
-                .ok()
-                .flatten();
+                .ok();
 
-            let Some((
-                title,
-                path,
-                status,
-                old_text,
-                new_text,
-                scroll_handle,
-                scroll_state,
-                current_hunk,
-                diff_options,
-                view_mode,
-            )) = snapshot
-            else {
-                return Some(());
-            };
+            Some(())
+        })
+        .detach();
+    }
+
+    /// 把整个未跟踪目录移到回收目录；需要连续点击两次，之后可通过“撤销回滚”恢复。
+    fn delete_untracked_dir(&mut self, dir: String, window: &mut Window, cx: &mut Context<Self>) {
+        if self.confirm_delete_untracked_dir.as_deref() != Some(dir.as_str()) {
+            self.confirm_delete_untracked_dir = Some(dir);
+            cx.notify();
+            return;
+        }
+        self.confirm_delete_untracked_dir = None;
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
 
-            let (old_text, new_text, model, old_lines, new_lines) = window
+        cx.spawn_in(window, async move |_, window| {
+            let dir_for_task = dir.clone();
+            let (result, entries) = window
                 .background_executor()
                 .spawn(async move {
-                    let (model, old_lines, new_lines) = build_diff_model(
-                        &old_text,
-                        &new_text,
-                        diff_options.ignore_whitespace,
-                        diff_options.context_lines,
-                    );
-                    (old_text, new_text, model, old_lines, new_lines)
+                    let result = move_untracked_dir_to_trash(&repo_root, &dir_for_task);
+                    let entries = fetch_git_status(&repo_root);
+                    (result, entries)
                 })
                 .await;
 
             window
-                .update(|_window, cx| {
-                    this.update(cx, |this, cx| {
-                        if this.diff_rebuild_seq != rebuild_seq
-                            || this.diff_content_revision != content_revision
-                        {
+                .update(|window, cx| {
+                    let backup = match result {
+                        Ok(backup) => backup,
+                        Err(err) => {
+                            window
+                                .push_toast(Toast::error(format!("删除 {dir} 失败：{err:#}")), cx);
                             return;
                         }
+                    };
+                    window.push_toast(
+                        Toast::success(format!(
+                            "已删除 {dir}，备份在 {}（可撤销）",
+                            backup.backup.display()
+                        )),
+                        cx,
+                    );
 
-                        let still_same = this.diff_view.as_ref().is_some_and(|view| {
-                            view.path == path && view.compare_target == compare_target
-                        });
-                        if !still_same {
-                            return;
+                    this.update(cx, |this, cx| {
+                        this.last_rollback = Some(backup);
+                        if let Ok(entries) = entries {
+                            this.set_status_entries(entries);
                         }
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn open_file_diff(
+        &mut self,
+        path: String,
+        status: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        // amend 时对比 HEAD^ ↔ 暂存区，也就是修改后的提交将包含的内容。
+        if self.amend.is_some() && !is_untracked_status(&status) {
+            self.open_file_diff_with_refs(
+                path,
+                Some(status),
+                "HEAD^".to_string(),
+                "INDEX".to_string(),
+                window,
+                cx,
+            );
+            return;
+        }
+
+        let target = default_compare_target(&status);
+        self.open_file_diff_with_target(path, status, target, window, cx);
+    }
+
+    fn open_file_diff_with_target(
+        &mut self,
+        path: String,
+        status: String,
+        target: CompareTarget,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        let path_for_task = path.clone();
+        let status_for_task = status.clone();
+
+        window.push_toast(
+            Toast::info(format!(
+                "正在加载 diff：{status} {path}（{}）",
+                compare_target_label(&target)
+            )),
+            cx,
+        );
+
+        let cache = self.diff_cache.clone();
+        let (cached, generation) = match self.diff_cache.lock() {
+            Ok(mut cache) => (cache.get(&path, &target), cache.generation),
+            Err(_) => (None, 0),
+        };
+
+        cx.spawn_in(window, async move |_, window| {
+            let path_for_task_bg = path_for_task.clone();
+            let status_for_task_bg = status_for_task.clone();
+            let target_for_io = target.clone();
+            let DiffTexts {
+                old_text,
+                old_err,
+                new_text,
+                new_err,
+                encoding,
+            } = match cached {
+                Some(texts) => (*texts).clone(),
+                None => {
+                    window
+                        .background_executor()
+                        .spawn(async move {
+                            let texts = load_diff_texts(
+                                &repo_root,
+                                &path_for_task_bg,
+                                &status_for_task_bg,
+                                &target_for_io,
+                            );
+                            if let Ok(mut cache) = cache.lock() {
+                                cache.insert(
+                                    generation,
+                                    path_for_task_bg,
+                                    target_for_io,
+                                    Arc::new(texts.clone()),
+                                );
+                            }
+                            texts
+                        })
+                        .await
+                }
+            };
+
+            let diff_options = window
+                .update(|_, cx| this.read(cx).diff_options)
+                .unwrap_or_default();
+            let view_mode = window
+                .update(|_, cx| this.read(cx).view_mode)
+                .unwrap_or(DiffViewMode::Split);
+
+            let (old_text, new_text, lfs, model, old_lines, new_lines, diff_timing) = window
+                .background_executor()
+                .spawn(async move {
+                    let (old_text, new_text, lfs) = detect_lfs_diff(old_text, new_text);
+                    let (model, old_lines, new_lines, diff_timing) =
+                        build_diff_model(&old_text, &new_text, diff_options);
+                    (
+                        old_text,
+                        new_text,
+                        lfs,
+                        model,
+                        old_lines,
+                        new_lines,
+                        diff_timing,
+                    )
+                })
+                .await;
 
+            window
+                .update(|window, cx| {
+                    if let Some(err) = old_err {
+                        window.push_toast(
+                            Toast::error(format!(
+                                "读取 {} 版本失败，按空内容处理：{err}",
+                                compare_target_side_label(&target, Side::Old)
+                            )),
+                            cx,
+                        );
+                    }
+                    if let Some(err) = new_err {
+                        window.push_toast(
+                            Toast::error(format!(
+                                "读取 {} 版本失败，按空内容处理：{err}",
+                                compare_target_side_label(&target, Side::New)
+                            )),
+                            cx,
+                        );
+                    }
+
+                    this.update(cx, |this, cx| {
+                        this.diff_content_revision = this.diff_content_revision.wrapping_add(1);
+                        this.conflict_view = None;
                         let mut next = DiffViewState::from_precomputed(
-                            title,
-                            path,
-                            status,
-                            compare_target,
+                            format!("{status_for_task} {path_for_task}").into(),
+                            Some(path_for_task.clone()),
+                            Some(status_for_task.clone()),
+                            target,
                             old_text,
                             new_text,
                             view_mode,
                             model,
                             old_lines,
                             new_lines,
+                            diff_timing,
                         );
-                        next.scroll_handle = scroll_handle;
-                        next.scroll_state = scroll_state;
-                        next.current_hunk =
-                            current_hunk.min(next.hunk_rows.len().saturating_sub(1));
+                        next.lfs = lfs;
+                        next.encoding = encoding;
                         this.diff_view = Some(next);
-                        cx.notify();
+                        this.screen = AppScreen::DiffView;
+                        this.prefetch_diffs_after(&path_for_task, cx);
                     });
                 })
                 .ok();
@@ -1563,324 +2281,268 @@ impl GitViewerApp {
         .detach();
     }
 
-    fn jump_hunk(&mut self, direction: i32) {
-        let Some(diff_view) = self.diff_view.as_mut() else {
+    /// 在后台读取状态列表中 `path` 之后的若干文件，切到下一个文件时可以直接命中缓存。
+    fn prefetch_diffs_after(&self, path: &str, cx: &mut Context<Self>) {
+        if self.amend.is_some() {
             return;
+        }
+        let radius = match self.diff_cache.lock() {
+            Ok(cache) => cache.settings.prefetch_radius,
+            Err(_) => return,
         };
-        if diff_view.hunk_rows.is_empty() {
+        let Some(index) = self.files.iter().position(|entry| entry.path == path) else {
             return;
-        }
-
-        let mut next_index = diff_view.current_hunk;
-        if direction < 0 {
-            next_index = next_index.saturating_sub(1);
-        } else if direction > 0 {
-            next_index = (next_index + 1).min(diff_view.hunk_rows.len().saturating_sub(1));
-        }
+        };
 
-        diff_view.current_hunk = next_index;
-        let row_index = diff_view.hunk_rows[next_index];
-        diff_view
-            .scroll_handle
-            .scroll_to_item(row_index, ScrollStrategy::Top);
+        let jobs = self.files[index + 1..]
+            .iter()
+            .filter(|entry| !is_conflict_status(&entry.status) && !is_untracked_dir(entry))
+            .take(radius)
+            .map(|entry| {
+                (
+                    entry.path.clone(),
+                    entry.status.clone(),
+                    default_compare_target(&entry.status),
+                )
+            })
+            .collect();
+        self.fill_diff_cache(jobs, None, cx);
     }
 
-    fn expand_fold(&mut self, row_index: usize) {
-        let Some(diff_view) = self.diff_view.as_mut() else {
+    /// 状态刷新后在后台预载所有小文件的 diff 文本。
+    fn request_diff_preload(&mut self, cx: &mut Context<Self>) {
+        if !self.diff_preload_stale {
             return;
+        }
+        self.diff_preload_stale = false;
+
+        let (capacity, max_file_bytes) = match self.diff_cache.lock() {
+            Ok(cache) => (
+                cache.settings.capacity,
+                cache.settings.preload_max_file_bytes,
+            ),
+            Err(_) => return,
         };
+        if max_file_bytes == 0 || self.amend.is_some() {
+            return;
+        }
 
-        let DisplayRow::Fold {
-            old_start,
-            new_start,
-            len,
-        } = diff_view
-            .rows
-            .get(row_index)
-            .cloned()
-            .unwrap_or(DisplayRow::Fold {
-                old_start: 0,
-                new_start: 0,
-                len: 0,
+        let jobs = self
+            .files
+            .iter()
+            .filter(|entry| !is_conflict_status(&entry.status) && !is_untracked_dir(entry))
+            .take(capacity)
+            .map(|entry| {
+                (
+                    entry.path.clone(),
+                    entry.status.clone(),
+                    default_compare_target(&entry.status),
+                )
             })
-        else {
-            return;
-        };
-
-        if len == 0 {
-            diff_view.rows.remove(row_index);
-            diff_view.recalc_hunk_rows();
-            return;
-        }
-
-        let mut expanded = Vec::with_capacity(len);
-        for offset in 0..len {
-            let old_index = old_start + offset;
-            let new_index = new_start + offset;
-
-            let old_text = diff_view
-                .old_lines
-                .get(old_index)
-                .cloned()
-                .unwrap_or_default();
-            let new_text = diff_view
-                .new_lines
-                .get(new_index)
-                .cloned()
-                .unwrap_or_else(|| old_text.clone());
-
-            let old_segments = vec![diffview::DiffSegment {
-                kind: diffview::DiffSegmentKind::Unchanged,
-                text: old_text.clone(),
-            }];
-            let new_segments = vec![diffview::DiffSegment {
-                kind: diffview::DiffSegmentKind::Unchanged,
-                text: new_text.clone(),
-            }];
-
-            expanded.push(DisplayRow::Code {
-                kind: diffview::DiffRowKind::Unchanged,
-                old_line: Some(old_index + 1),
-                new_line: Some(new_index + 1),
-                old_segments,
-                new_segments,
-            });
-        }
-
-        diff_view.rows.splice(row_index..=row_index, expanded);
-        diff_view.recalc_hunk_rows();
-    }
-
-    fn expand_all_folds(&mut self) {
-        while self.diff_view.as_ref().is_some_and(|diff_view| {
-            diff_view
-                .rows
-                .iter()
-                .any(|row| matches!(row, DisplayRow::Fold { .. }))
-        }) {
-            let next_index = self
-                .diff_view
-                .as_ref()
-                .and_then(|diff_view| {
-                    diff_view.rows.iter().enumerate().find_map(|(index, row)| {
-                        matches!(row, DisplayRow::Fold { .. }).then_some(index)
-                    })
-                })
-                .unwrap_or(0);
-            self.expand_fold(next_index);
-        }
+            .collect();
+        self.fill_diff_cache(jobs, Some(max_file_bytes), cx);
     }
 
-    fn resolve_conflict(
-        &mut self,
-        conflict_index: usize,
-        resolution: ConflictResolution,
-        window: &mut Window,
+    /// 依次读取 `jobs` 写入缓存；`max_file_bytes` 限制只读取工作区中不超过该大小的文件。
+    fn fill_diff_cache(
+        &self,
+        jobs: Vec<(String, String, CompareTarget)>,
+        max_file_bytes: Option<u64>,
         cx: &mut Context<Self>,
     ) {
-        let Some(conflict_view) = self.conflict_view.as_mut() else {
-            return;
-        };
-        if conflict_index >= conflict_view.conflicts.len() {
-            return;
-        }
-
-        let region = conflict_view.conflicts[conflict_index].clone();
-        let replacement = match resolution {
-            ConflictResolution::Ours => conflict_view.text[region.ours.clone()].to_string(),
-            ConflictResolution::Theirs => conflict_view.text[region.theirs.clone()].to_string(),
-            ConflictResolution::Base => region
-                .base
-                .as_ref()
-                .map(|range| conflict_view.text[range.clone()].to_string())
-                .unwrap_or_default(),
-            ConflictResolution::Both => format!(
-                "{}{}",
-                &conflict_view.text[region.ours.clone()],
-                &conflict_view.text[region.theirs.clone()]
-            ),
-        };
-
-        let mut next = String::with_capacity(
-            conflict_view.text.len().saturating_sub(region.range.len()) + replacement.len(),
-        );
-        next.push_str(&conflict_view.text[..region.range.start]);
-        next.push_str(&replacement);
-        next.push_str(&conflict_view.text[region.range.end..]);
-
-        conflict_view.text = next;
-        conflict_view.rebuild();
-        let updated_text = conflict_view.text.clone();
-        let result_input = conflict_view.result_input.clone();
-        result_input.update(cx, move |state, cx| {
-            state.set_value(updated_text, window, cx);
-        });
-    }
-
-    fn jump_conflict(&mut self, direction: i32) {
-        let Some(conflict_view) = self.conflict_view.as_mut() else {
+        if jobs.is_empty() || !self.git_available {
             return;
-        };
-        if conflict_view.conflict_rows.is_empty() {
-            return;
-        }
-
-        let mut next_index = conflict_view.current_conflict;
-        if direction < 0 {
-            next_index = next_index.saturating_sub(1);
-        } else if direction > 0 {
-            next_index = (next_index + 1).min(conflict_view.conflict_rows.len().saturating_sub(1));
         }
-
-        conflict_view.current_conflict = next_index;
-        let row_index = conflict_view.conflict_rows[next_index];
-        conflict_view
-            .scroll_handle
-            .scroll_to_item(row_index, ScrollStrategy::Top);
-    }
-
-    fn apply_conflict_editor(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        let Some(conflict_view) = self.conflict_view.as_mut() else {
-            return;
+        let cache = self.diff_cache.clone();
+        let generation = match cache.lock() {
+            Ok(cache) => cache.generation,
+            Err(_) => return,
         };
+        let repo_root = self.repo_root.clone();
 
-        let edited_text = conflict_view.result_input.read(cx).value().to_string();
-        if edited_text == conflict_view.text {
-            window.push_notification(Notification::new().message("结果编辑器无改动"), cx);
-            return;
-        }
-
-        conflict_view.text = edited_text;
-        conflict_view.rebuild();
+        cx.background_executor()
+            .spawn(async move {
+                for (path, status, target) in jobs {
+                    let skip = match cache.lock() {
+                        Ok(cache) => {
+                            cache.generation != generation || cache.contains(&path, &target)
+                        }
+                        Err(_) => return,
+                    };
+                    if skip {
+                        continue;
+                    }
+                    let too_large = max_file_bytes.is_some_and(|max_file_bytes| {
+                        std::fs::metadata(repo_root.join(&path))
+                            .is_ok_and(|metadata| metadata.len() > max_file_bytes)
+                    });
+                    if too_large {
+                        continue;
+                    }
 
-        let remaining = conflict_view.conflicts.len();
-        window.push_notification(
-            Notification::new().message(if remaining == 0 {
-                "已应用编辑：冲突已清零".to_string()
-            } else {
-                format!("已应用编辑：仍有 {remaining} 处冲突")
-            }),
-            cx,
-        );
-        cx.notify();
+                    let texts = load_diff_texts(&repo_root, &path, &status, &target);
+                    match cache.lock() {
+                        Ok(mut cache) => cache.insert(generation, path, target, Arc::new(texts)),
+                        Err(_) => return,
+                    }
+                }
+            })
+            .detach();
     }
 
-    fn save_conflict_to_working_tree(
+    fn open_file_diff_with_refs(
         &mut self,
-        add_to_index: bool,
+        path: String,
+        status: Option<String>,
+        left_ref: String,
+        right_ref: String,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        if add_to_index && !self.git_available {
-            window.push_notification(
-                Notification::new().message("未检测到 git 命令，无法执行 git add"),
+        if !self.git_available {
+            window.push_toast(
+                Toast::warning("未检测到 git 命令，无法进行历史/任意版本对比"),
                 cx,
             );
             return;
         }
 
-        let Some(conflict_view) = self.conflict_view.as_mut() else {
-            return;
-        };
-        let Some(path) = conflict_view.path.clone() else {
-            return;
-        };
-
-        let edited_text = conflict_view.result_input.read(cx).value().to_string();
-        if edited_text != conflict_view.text {
-            conflict_view.text = edited_text;
-            conflict_view.rebuild();
-        }
-
-        if !conflict_view.conflicts.is_empty() {
-            window.push_notification(
-                Notification::new().message("仍有冲突未解决，无法保存/标记已解决"),
+        let left_ref = left_ref.trim().to_string();
+        let right_ref = right_ref.trim().to_string();
+        if left_ref.is_empty() {
+            window.push_toast(
+                Toast::warning("左侧 ref 不能为空（例如 HEAD / HEAD~1 / a1b2c3）"),
                 cx,
             );
             return;
         }
 
-        let this = cx.entity();
-        let repo_root = self.repo_root.clone();
-        let text = conflict_view.text.clone();
-        let path_for_task = path.clone();
+        let compare_target = CompareTarget::Refs {
+            left: left_ref.clone(),
+            right: right_ref.clone(),
+        };
 
-        window.push_notification(
-            Notification::new().message(if add_to_index {
-                format!("保存并标记已解决：{path}")
-            } else {
-                format!("保存到文件：{path}")
-            }),
+        // 两侧 ref 指向不同路径（例如重命名前后）时，标题显示 `旧路径 → 新路径`。
+        let left_path = ref_spec_path(&left_ref).unwrap_or(path.as_str());
+        let right_path = ref_spec_path(&right_ref).unwrap_or(path.as_str());
+        let display_path = if left_path == right_path {
+            right_path.to_string()
+        } else {
+            format!("{left_path} → {right_path}")
+        };
+        let title: SharedString = match &status {
+            Some(status) if !status.trim().is_empty() => format!("{status} {display_path}").into(),
+            _ => display_path.into(),
+        };
+
+        window.push_toast(
+            Toast::info(format!(
+                "正在加载 diff：{path}（{}）",
+                compare_target_label(&compare_target)
+            )),
             cx,
         );
 
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        let path_for_task = path.clone();
+        let status_for_task = status.clone();
+
         cx.spawn_in(window, async move |_, window| {
-            let (write_result, add_result, status_result) = window
+            let path_for_task_bg = path_for_task.clone();
+            let left_ref_for_io = left_ref.clone();
+            let right_ref_for_io = right_ref.clone();
+            let (old_text, old_err, new_text, new_err, encoding) = window
                 .background_executor()
                 .spawn(async move {
-                    let full_path = repo_root.join(&path_for_task);
-                    let write_result = std::fs::write(&full_path, text.as_bytes())
-                        .with_context(|| format!("写入文件失败：{path_for_task}"));
+                    let (old_text, old_err, old_encoding) = match read_specified_file(
+                        &repo_root,
+                        &path_for_task_bg,
+                        &left_ref_for_io,
+                    ) {
+                        Ok((text, encoding)) => (text, None, encoding),
+                        Err(err) => (String::new(), Some(err.to_string()), TextEncoding::UTF8),
+                    };
 
-                    let add_result = if add_to_index && write_result.is_ok() {
-                        let output = Command::new("git")
-                            .arg("-C")
-                            .arg(&repo_root)
-                            .args(["add", "--"])
-                            .arg(&path_for_task)
-                            .output()
-                            .with_context(|| format!("执行 git add 失败：{path_for_task}"));
+                    let (new_text, new_err, new_encoding) =
+                        match read_specified_file(&repo_root, &path_for_task_bg, &right_ref_for_io)
+                        {
+                            Ok((text, encoding)) => (text, None, encoding),
+                            Err(err) => (String::new(), Some(err.to_string()), TextEncoding::UTF8),
+                        };
 
-                        match output {
-                            Ok(output) if output.status.success() => Ok(()),
-                            Ok(output) => Err(anyhow!(
-                                "git add 返回非零（{}）：{}",
-                                output.status.code().unwrap_or(-1),
-                                String::from_utf8_lossy(&output.stderr).trim()
-                            )),
-                            Err(err) => Err(err),
-                        }
-                    } else {
-                        Ok(())
-                    };
+                    let encoding = detected_encoding(old_encoding, new_encoding);
+                    (old_text, old_err, new_text, new_err, encoding)
+                })
+                .await;
 
-                    let status_result = fetch_git_status(&repo_root);
+            let diff_options = window
+                .update(|_, cx| this.read(cx).diff_options)
+                .unwrap_or_default();
+            let view_mode = window
+                .update(|_, cx| this.read(cx).view_mode)
+                .unwrap_or(DiffViewMode::Split);
 
-                    (write_result, add_result, status_result)
+            let (old_text, new_text, lfs, model, old_lines, new_lines, diff_timing) = window
+                .background_executor()
+                .spawn(async move {
+                    let (old_text, new_text, lfs) = detect_lfs_diff(old_text, new_text);
+                    let (model, old_lines, new_lines, diff_timing) =
+                        build_diff_model(&old_text, &new_text, diff_options);
+                    (
+                        old_text,
+                        new_text,
+                        lfs,
+                        model,
+                        old_lines,
+                        new_lines,
+                        diff_timing,
+                    )
                 })
                 .await;
 
             window
                 .update(|window, cx| {
-                    if let Err(err) = write_result {
-                        window.push_notification(
-                            Notification::new().message(format!("保存失败：{err}")),
+                    if let Some(err) = old_err {
+                        window.push_toast(
+                            Toast::error(format!(
+                                "读取 {} 版本失败，按空内容处理：{err}",
+                                compare_target_side_label(&compare_target, Side::Old)
+                            )),
                             cx,
                         );
-                        return;
                     }
-                    if let Err(err) = add_result {
-                        window.push_notification(
-                            Notification::new().message(format!("git add 失败：{err}")),
-                            cx,
-                        );
-                    } else if add_to_index {
-                        window.push_notification(
-                            Notification::new().message("已保存并标记为已解决（git add）"),
-                            cx,
-                        );
-                    } else {
-                        window.push_notification(
-                            Notification::new().message("已保存到工作区文件"),
+                    if let Some(err) = new_err {
+                        window.push_toast(
+                            Toast::error(format!(
+                                "读取 {} 版本失败，按空内容处理：{err}",
+                                compare_target_side_label(&compare_target, Side::New)
+                            )),
                             cx,
                         );
                     }
 
-                    if let Ok(entries) = status_result {
-                        this.update(cx, |this, _cx| {
-                            this.files = entries;
-                        });
-                    }
+                    this.update(cx, |this, _cx| {
+                        this.diff_content_revision = this.diff_content_revision.wrapping_add(1);
+                        this.conflict_view = None;
+                        let mut next = DiffViewState::from_precomputed(
+                            title,
+                            Some(path_for_task.clone()),
+                            status_for_task.clone(),
+                            compare_target.clone(),
+                            old_text,
+                            new_text,
+                            view_mode,
+                            model,
+                            old_lines,
+                            new_lines,
+                            diff_timing,
+                        );
+                        next.lfs = lfs;
+                        next.encoding = encoding;
+                        this.diff_view = Some(next);
+                        this.screen = AppScreen::DiffView;
+                    });
                 })
                 .ok();
 
@@ -1889,215 +2551,107 @@ impl GitViewerApp {
         .detach();
     }
 
-    fn stage_current_file(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+    fn apply_compare_refs_from_inputs(&mut self, window: &mut Window, cx: &mut Context<Self>) {
         let Some(diff_view) = self.diff_view.as_ref() else {
             return;
         };
         let Some(path) = diff_view.path.clone() else {
-            return;
-        };
-
-        let compare_target = diff_view.compare_target.clone();
-        if matches!(compare_target, CompareTarget::Refs { .. }) {
-            window.push_notification(
-                Notification::new()
-                    .message("自定义对比模式下不支持 Stage 文件，请先切换到内置对比目标"),
+            window.push_toast(
+                Toast::warning("当前 diff 为 demo，暂不支持任意版本对比"),
                 cx,
             );
             return;
-        }
-        let scroll_handle = diff_view.scroll_handle.clone();
-        let scroll_state = diff_view.scroll_state.clone();
-        let current_hunk = diff_view.current_hunk;
+        };
 
-        let this = cx.entity();
-        let repo_root = self.repo_root.clone();
-        let path_for_task = path.clone();
-
-        window.push_notification(Notification::new().message(format!("git add {path}")), cx);
+        let left_ref = self.compare_left_input.read(cx).value().to_string();
+        let right_ref = self.compare_right_input.read(cx).value().to_string();
+        let status = diff_view.status.clone();
+        self.open_file_diff_with_refs(path, status, left_ref, right_ref, window, cx);
+    }
 
-        cx.spawn_in(window, async move |_, window| {
-            let diff_options =
-                window
-                    .update(|_, cx| this.read(cx).diff_options)
-                    .unwrap_or(DiffViewOptions {
-                        ignore_whitespace: false,
-                        context_lines: 3,
-                    });
-            let view_mode = window
-                .update(|_, cx| this.read(cx).view_mode)
-                .unwrap_or(DiffViewMode::Split);
+    fn swap_compare_refs_inputs(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let left_ref = self.compare_left_input.read(cx).value().to_string();
+        let right_ref = self.compare_right_input.read(cx).value().to_string();
 
-            let path_for_task_bg = path_for_task.clone();
-            let compare_target_for_io = compare_target.clone();
-            let (
-                add_ok,
-                add_err,
-                entries,
-                status_err,
-                updated_status,
-                old_text,
-                old_err,
-                new_text,
-                new_err,
-                model,
-                old_lines,
-                new_lines,
-            ) = window
-                .background_executor()
-                .spawn(async move {
-                    let add_result = run_git(repo_root.as_path(), ["add", "--", &path_for_task_bg]);
-                    let add_ok = add_result.is_ok();
-                    let add_err = add_result.err().map(|err| err.to_string());
-                    let (entries, status_err, updated_status) = match fetch_git_status(&repo_root) {
-                        Ok(entries) => {
-                            let updated_status = entries
-                                .iter()
-                                .find(|entry| entry.path == path_for_task_bg)
-                                .map(|entry| entry.status.clone())
-                                .unwrap_or_default();
-                            (Some(entries), None, updated_status)
-                        }
-                        Err(err) => (None, Some(err.to_string()), String::new()),
-                    };
+        self.compare_left_input.update(cx, |state, cx| {
+            state.set_value(right_ref.clone(), window, cx);
+        });
+        self.compare_right_input.update(cx, |state, cx| {
+            state.set_value(left_ref.clone(), window, cx);
+        });
+        cx.notify();
+    }
 
-                    let (old_text, old_err) = match compare_target_for_io.clone() {
-                        CompareTarget::HeadToWorktree | CompareTarget::HeadToIndex => {
-                            match read_head_file(&repo_root, &path_for_task_bg, &updated_status) {
-                                Ok(text) => (text, None),
-                                Err(err) => (String::new(), Some(err.to_string())),
-                            }
-                        }
-                        CompareTarget::IndexToWorktree => {
-                            match read_index_file(&repo_root, &path_for_task_bg, &updated_status) {
-                                Ok(text) => (text, None),
-                                Err(err) => (String::new(), Some(err.to_string())),
-                            }
-                        }
-                        CompareTarget::Refs { .. } => {
-                            (String::new(), Some("不支持的对比目标".to_string()))
-                        }
-                    };
+    fn open_file_history_overlay(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.git_available {
+            window.push_toast(Toast::warning("未检测到 git 命令，无法打开历史对比"), cx);
+            return;
+        }
 
-                    let (new_text, new_err) = match compare_target_for_io {
-                        CompareTarget::HeadToWorktree | CompareTarget::IndexToWorktree => {
-                            match read_working_file(&repo_root, &path_for_task_bg) {
-                                Ok(text) => (text, None),
-                                Err(err) => (String::new(), Some(err.to_string())),
-                            }
-                        }
-                        CompareTarget::HeadToIndex => {
-                            match read_index_file(&repo_root, &path_for_task_bg, &updated_status) {
-                                Ok(text) => (text, None),
-                                Err(err) => (String::new(), Some(err.to_string())),
-                            }
-                        }
-                        CompareTarget::Refs { .. } => {
-                            (String::new(), Some("不支持的对比目标".to_string()))
-                        }
-                    };
+        self.command_palette_overlay = None;
 
-                    let (model, old_lines, new_lines) = build_diff_model(
-                        &old_text,
-                        &new_text,
-                        diff_options.ignore_whitespace,
-                        diff_options.context_lines,
-                    );
+        let Some(diff_view) = self.diff_view.as_ref() else {
+            return;
+        };
+        let Some(path) = diff_view.path.clone() else {
+            window.push_toast(Toast::warning("当前 diff 为 demo，暂不支持历史对比"), cx);
+            return;
+        };
 
-                    (
-                        add_ok,
-                        add_err,
-                        entries,
-                        status_err,
-                        updated_status,
-                        old_text,
-                        old_err,
-                        new_text,
-                        new_err,
-                        model,
-                        old_lines,
-                        new_lines,
-                    )
-                })
-                .await;
+        let status = diff_view.status.clone();
+        let filter_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("搜索 commit（hash / message）")
+                .default_value("")
+        });
 
-            window
-                .update(|window, cx| {
-                    if let Some(err) = add_err {
-                        window.push_notification(
-                            Notification::new().message(format!("git add 失败：{err}")),
-                            cx,
-                        );
-                    } else {
-                        window.push_notification(Notification::new().message("git add 成功"), cx);
-                    }
+        self.file_history_overlay = Some(FileHistoryOverlayState {
+            path: path.clone(),
+            status,
+            commits: Vec::new(),
+            loading: true,
+            selected: 0,
+            mode: HistoryCompareMode::ParentToCommit,
+            filter_input: filter_input.clone(),
+        });
 
-                    if let Some(err) = status_err {
-                        window.push_notification(
-                            Notification::new().message(format!("刷新 git 状态失败：{err}")),
-                            cx,
-                        );
-                    }
+        filter_input.update(cx, |state, cx| state.focus(window, cx));
 
-                    if let Some(err) = old_err {
-                        window.push_notification(
-                            Notification::new().message(format!(
-                                "读取 {} 版本失败，按空内容处理：{err}",
-                                compare_target_side_label(&compare_target, Side::Old)
-                            )),
-                            cx,
-                        );
-                    }
-                    if let Some(err) = new_err {
-                        window.push_notification(
-                            Notification::new().message(format!(
-                                "读取 {} 版本失败，按空内容处理：{err}",
-                                compare_target_side_label(&compare_target, Side::New)
-                            )),
-                            cx,
-                        );
-                    }
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        let path_for_task = path.clone();
+        cx.spawn_in(window, async move |_, window| {
+            let path_for_task_bg = path_for_task.clone();
+            let path_for_update = path_for_task.clone();
+            let repo_root_bg = repo_root.clone();
+            let commits = window
+                .background_executor()
+                .spawn(async move { fetch_file_history(&repo_root_bg, &path_for_task_bg, 200) })
+                .await;
 
-                    this.update(cx, |this, cx| {
-                        if let Some(entries) = entries {
-                            this.files = entries;
+            window
+                .update(|window, cx| match commits {
+                    Ok(commits) => this.update(cx, |this, cx| {
+                        if let Some(overlay) = this.file_history_overlay.as_mut() {
+                            if overlay.path == path_for_update {
+                                overlay.loading = false;
+                                overlay.commits = commits;
+                                overlay.selected = 0;
+                                cx.notify();
+                            }
                         }
-
-                        if add_ok {
-                            let title: SharedString = if updated_status.is_empty() {
-                                path_for_task.clone().into()
-                            } else {
-                                format!("{updated_status} {path_for_task}").into()
-                            };
-
-                            if let Some(diff_view) = this.diff_view.as_ref() {
-                                if diff_view.path.as_deref() == Some(path_for_task.as_str()) {
-                                    let mut next = DiffViewState::from_precomputed(
-                                        title,
-                                        Some(path_for_task.clone()),
-                                        (!updated_status.is_empty())
-                                            .then(|| updated_status.clone()),
-                                        compare_target,
-                                        old_text,
-                                        new_text,
-                                        view_mode,
-                                        model,
-                                        old_lines,
-                                        new_lines,
-                                    );
-                                    next.scroll_handle = scroll_handle.clone();
-                                    next.scroll_state = scroll_state.clone();
-                                    next.current_hunk =
-                                        current_hunk.min(next.hunk_rows.len().saturating_sub(1));
-                                    this.diff_content_revision =
-                                        this.diff_content_revision.wrapping_add(1);
-                                    this.diff_view = Some(next);
+                    }),
+                    Err(err) => {
+                        window.push_toast(Toast::error(format!("获取历史失败：{err:#}")), cx);
+                        this.update(cx, |this, cx| {
+                            if let Some(overlay) = this.file_history_overlay.as_mut() {
+                                if overlay.path == path_for_update {
+                                    overlay.loading = false;
+                                    cx.notify();
                                 }
                             }
-                        }
-
-                        cx.notify();
-                    });
+                        });
+                    }
                 })
                 .ok();
 
@@ -2106,474 +2660,520 @@ impl GitViewerApp {
         .detach();
     }
 
-    fn unstage_current_file(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        let Some(diff_view) = self.diff_view.as_ref() else {
+    fn close_file_history_overlay(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.file_history_overlay.take().is_some() {
+            window.focus(&self.focus_handle);
+            cx.notify();
+        }
+    }
+
+    fn apply_selected_file_history_commit(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(overlay) = self.file_history_overlay.as_ref() else {
             return;
         };
-        let Some(path) = diff_view.path.clone() else {
+        if overlay.loading || overlay.commits.is_empty() {
             return;
-        };
+        }
 
-        let compare_target = diff_view.compare_target.clone();
-        if matches!(compare_target, CompareTarget::Refs { .. }) {
-            window.push_notification(
-                Notification::new()
-                    .message("自定义对比模式下不支持 Unstage 文件，请先切换到内置对比目标"),
-                cx,
-            );
+        let query = overlay.filter_input.read(cx).value().to_string();
+        let filtered = filter_commits(&overlay.commits, &query);
+        let Some(entry) = filtered.get(overlay.selected.min(filtered.len().saturating_sub(1)))
+        else {
             return;
-        }
-        let scroll_handle = diff_view.scroll_handle.clone();
-        let scroll_state = diff_view.scroll_state.clone();
-        let current_hunk = diff_view.current_hunk;
+        };
 
-        let this = cx.entity();
-        let repo_root = self.repo_root.clone();
-        let path_for_task = path.clone();
-
-        window.push_notification(
-            Notification::new().message(format!("git reset HEAD -- {path}")),
-            cx,
-        );
-
-        cx.spawn_in(window, async move |_, window| {
-            let diff_options =
-                window
-                    .update(|_, cx| this.read(cx).diff_options)
-                    .unwrap_or(DiffViewOptions {
-                        ignore_whitespace: false,
-                        context_lines: 3,
-                    });
-            let view_mode = window
-                .update(|_, cx| this.read(cx).view_mode)
-                .unwrap_or(DiffViewMode::Split);
-
-            let path_for_task_bg = path_for_task.clone();
-            let compare_target_for_io = compare_target.clone();
-            let (
-                reset_ok,
-                reset_err,
-                entries,
-                status_err,
-                updated_status,
-                old_text,
-                old_err,
-                new_text,
-                new_err,
-                model,
-                old_lines,
-                new_lines,
-            ) = window
-                .background_executor()
-                .spawn(async move {
-                    let reset_result = run_git(
-                        repo_root.as_path(),
-                        ["reset", "HEAD", "--", &path_for_task_bg],
-                    );
-                    let reset_ok = reset_result.is_ok();
-                    let reset_err = reset_result.err().map(|err| err.to_string());
-                    let (entries, status_err, updated_status) = match fetch_git_status(&repo_root) {
-                        Ok(entries) => {
-                            let updated_status = entries
-                                .iter()
-                                .find(|entry| entry.path == path_for_task_bg)
-                                .map(|entry| entry.status.clone())
-                                .unwrap_or_default();
-                            (Some(entries), None, updated_status)
-                        }
-                        Err(err) => (None, Some(err.to_string()), String::new()),
-                    };
-
-                    let (old_text, old_err) = match compare_target_for_io.clone() {
-                        CompareTarget::HeadToWorktree | CompareTarget::HeadToIndex => {
-                            match read_head_file(&repo_root, &path_for_task_bg, &updated_status) {
-                                Ok(text) => (text, None),
-                                Err(err) => (String::new(), Some(err.to_string())),
-                            }
-                        }
-                        CompareTarget::IndexToWorktree => {
-                            match read_index_file(&repo_root, &path_for_task_bg, &updated_status) {
-                                Ok(text) => (text, None),
-                                Err(err) => (String::new(), Some(err.to_string())),
-                            }
-                        }
-                        CompareTarget::Refs { .. } => {
-                            (String::new(), Some("不支持的对比目标".to_string()))
-                        }
-                    };
-
-                    let (new_text, new_err) = match compare_target_for_io {
-                        CompareTarget::HeadToWorktree | CompareTarget::IndexToWorktree => {
-                            match read_working_file(&repo_root, &path_for_task_bg) {
-                                Ok(text) => (text, None),
-                                Err(err) => (String::new(), Some(err.to_string())),
-                            }
-                        }
-                        CompareTarget::HeadToIndex => {
-                            match read_index_file(&repo_root, &path_for_task_bg, &updated_status) {
-                                Ok(text) => (text, None),
-                                Err(err) => (String::new(), Some(err.to_string())),
-                            }
-                        }
-                        CompareTarget::Refs { .. } => {
-                            (String::new(), Some("不支持的对比目标".to_string()))
-                        }
-                    };
-
-                    let (model, old_lines, new_lines) = build_diff_model(
-                        &old_text,
-                        &new_text,
-                        diff_options.ignore_whitespace,
-                        diff_options.context_lines,
-                    );
-
-                    (
-                        reset_ok,
-                        reset_err,
-                        entries,
-                        status_err,
-                        updated_status,
-                        old_text,
-                        old_err,
-                        new_text,
-                        new_err,
-                        model,
-                        old_lines,
-                        new_lines,
-                    )
-                })
-                .await;
-
-            window
-                .update(|window, cx| {
-                    if let Some(err) = reset_err {
-                        window.push_notification(
-                            Notification::new().message(format!("unstage 失败：{err}")),
-                            cx,
-                        );
-                    } else {
-                        window.push_notification(Notification::new().message("unstage 成功"), cx);
-                    }
-
-                    if let Some(err) = status_err {
-                        window.push_notification(
-                            Notification::new().message(format!("刷新 git 状态失败：{err}")),
-                            cx,
-                        );
-                    }
-
-                    if let Some(err) = old_err {
-                        window.push_notification(
-                            Notification::new().message(format!(
-                                "读取 {} 版本失败，按空内容处理：{err}",
-                                compare_target_side_label(&compare_target, Side::Old)
-                            )),
-                            cx,
-                        );
-                    }
-                    if let Some(err) = new_err {
-                        window.push_notification(
-                            Notification::new().message(format!(
-                                "读取 {} 版本失败，按空内容处理：{err}",
-                                compare_target_side_label(&compare_target, Side::New)
-                            )),
-                            cx,
-                        );
-                    }
-
-                    this.update(cx, |this, cx| {
-                        if let Some(entries) = entries {
-                            this.files = entries;
-                        }
-
-                        if reset_ok {
-                            let title: SharedString = if updated_status.is_empty() {
-                                path_for_task.clone().into()
-                            } else {
-                                format!("{updated_status} {path_for_task}").into()
-                            };
-
-                            if let Some(diff_view) = this.diff_view.as_ref() {
-                                if diff_view.path.as_deref() == Some(path_for_task.as_str()) {
-                                    let mut next = DiffViewState::from_precomputed(
-                                        title,
-                                        Some(path_for_task.clone()),
-                                        (!updated_status.is_empty())
-                                            .then(|| updated_status.clone()),
-                                        compare_target,
-                                        old_text,
-                                        new_text,
-                                        view_mode,
-                                        model,
-                                        old_lines,
-                                        new_lines,
-                                    );
-                                    next.scroll_handle = scroll_handle.clone();
-                                    next.scroll_state = scroll_state.clone();
-                                    next.current_hunk =
-                                        current_hunk.min(next.hunk_rows.len().saturating_sub(1));
-                                    this.diff_content_revision =
-                                        this.diff_content_revision.wrapping_add(1);
-                                    this.diff_view = Some(next);
-                                }
-                            }
-                        }
-
-                        cx.notify();
-                    });
-                })
-                .ok();
-
-            Some(())
-        })
-        .detach();
-    }
-
-    fn stage_current_hunk(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        self.apply_current_hunk(HunkApplyAction::Stage, window, cx);
-    }
-
-    fn unstage_current_hunk(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        self.apply_current_hunk(HunkApplyAction::Unstage, window, cx);
-    }
+        // 用完整的 `<rev>:<path>` 读取，文件在该提交时可能还是旧路径。
+        let (left_ref, right_ref) = match overlay.mode {
+            HistoryCompareMode::ParentToCommit => (
+                format!(
+                    "{}^:{}",
+                    entry.hash,
+                    entry.old_path.as_deref().unwrap_or(&entry.path)
+                ),
+                format!("{}:{}", entry.hash, entry.path),
+            ),
+            HistoryCompareMode::CommitToWorktree => {
+                (format!("{}:{}", entry.hash, entry.path), String::new())
+            }
+        };
 
-    fn revert_current_hunk(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        self.apply_current_hunk(HunkApplyAction::Revert, window, cx);
+        let path = overlay.path.clone();
+        let status = overlay.status.clone();
+        self.file_history_overlay = None;
+        self.open_file_diff_with_refs(path, status, left_ref, right_ref, window, cx);
     }
 
-    fn apply_current_hunk(
+    fn handle_file_history_overlay_key(
         &mut self,
-        action: HunkApplyAction,
+        event: &KeyDownEvent,
         window: &mut Window,
         cx: &mut Context<Self>,
-    ) {
-        let Some(diff_view) = self.diff_view.as_ref() else {
-            return;
+    ) -> bool {
+        let Some(overlay) = self.file_history_overlay.as_mut() else {
+            return false;
         };
 
-        let required_target = action.required_compare_target();
-        if diff_view.compare_target != required_target {
-            window.push_notification(
-                Notification::new().message(format!(
-                    "当前对比为 {}，请切换到 {} 再执行 {}",
-                    compare_target_label(&diff_view.compare_target),
-                    compare_target_label(&required_target),
-                    action.label()
-                )),
-                cx,
-            );
-            return;
+        match event.keystroke.key.as_str() {
+            "escape" => {
+                self.close_file_history_overlay(window, cx);
+                true
+            }
+            "enter" => {
+                self.apply_selected_file_history_commit(window, cx);
+                true
+            }
+            "up" | "down" | "pageup" | "pagedown" => {
+                let query = overlay.filter_input.read(cx).value().to_string();
+                let filtered_len = filter_commits(&overlay.commits, &query).len();
+                if filtered_len == 0 {
+                    return true;
+                }
+
+                let step = match event.keystroke.key.as_str() {
+                    "pageup" | "pagedown" => 10usize,
+                    _ => 1usize,
+                };
+
+                let mut selected = overlay.selected.min(filtered_len.saturating_sub(1));
+                match event.keystroke.key.as_str() {
+                    "up" => selected = selected.saturating_sub(step),
+                    "pageup" => selected = selected.saturating_sub(step),
+                    "down" => selected = (selected + step).min(filtered_len.saturating_sub(1)),
+                    "pagedown" => selected = (selected + step).min(filtered_len.saturating_sub(1)),
+                    _ => {}
+                }
+                overlay.selected = selected;
+                cx.notify();
+                true
+            }
+            _ => false,
         }
+    }
 
-        let Some(path) = diff_view.path.clone() else {
-            return;
-        };
+    fn open_command_palette(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.file_history_overlay = None;
 
-        let hunk_count = diff_view.diff_model.hunks.len();
-        if hunk_count == 0 {
+        if let Some(palette) = self.command_palette_overlay.as_ref() {
+            palette.focus(window, cx);
             return;
         }
-        let hunk_index = diff_view.current_hunk.min(hunk_count.saturating_sub(1));
-        let patch = unified_patch_for_hunk(&path, &diff_view.diff_model.hunks[hunk_index]);
 
-        let compare_target = diff_view.compare_target.clone();
-        let scroll_handle = diff_view.scroll_handle.clone();
-        let scroll_state = diff_view.scroll_state.clone();
-        let current_hunk = diff_view.current_hunk;
+        let palette = CommandPalette::new(
+            COMMAND_PALETTE_ITEMS.to_vec(),
+            "输入命令…（↑↓ 选择，Enter 执行，Esc 关闭）",
+            window,
+            cx,
+        )
+        .title("命令")
+        .empty_text("没有匹配命令")
+        .hint("↑↓ 选择 · Enter 执行 · Esc 关闭")
+        .close_label("关闭 (Esc)");
+
+        self.command_palette_overlay = Some(palette);
+        cx.notify();
+    }
+
+    fn command_palette_command_enabled(&self, command: CommandPaletteCommand) -> bool {
+        match command {
+            CommandPaletteCommand::Back => true,
+            CommandPaletteCommand::Next | CommandPaletteCommand::Prev => match self.screen {
+                AppScreen::DiffView => self
+                    .diff_view
+                    .as_ref()
+                    .is_some_and(|view| !view.hunk_rows.is_empty()),
+                AppScreen::ConflictView => self
+                    .conflict_view
+                    .as_ref()
+                    .is_some_and(|view| !view.conflict_rows.is_empty()),
+                AppScreen::Reflog => self
+                    .reflog_view
+                    .as_ref()
+                    .is_some_and(|view| !view.entries.is_empty()),
+                AppScreen::StatusList
+                | AppScreen::BranchCompare
+                | AppScreen::Bisect
+                | AppScreen::CommitStack
+                | AppScreen::PullRequests
+                | AppScreen::RepoStats => false,
+            },
+            CommandPaletteCommand::ToggleViewMode => matches!(self.screen, AppScreen::DiffView),
+            CommandPaletteCommand::ToggleSplitLayout => {
+                matches!(self.screen, AppScreen::DiffView) && self.view_mode == DiffViewMode::Split
+            }
+            CommandPaletteCommand::TogglePaneScrollLink => self.two_pane_visible(),
+            CommandPaletteCommand::SyncPaneScroll => {
+                self.two_pane_visible() && !self.pane_scroll_linked
+            }
+            CommandPaletteCommand::ToggleWhitespace => matches!(self.screen, AppScreen::DiffView),
+            CommandPaletteCommand::ToggleIgnoreEol => matches!(self.screen, AppScreen::DiffView),
+            CommandPaletteCommand::ExpandAll => {
+                self.screen == AppScreen::DiffView
+                    && self
+                        .diff_view
+                        .as_ref()
+                        .is_some_and(|view| view.rows.has_folds())
+            }
+            CommandPaletteCommand::ToggleFoldByFunction => self.screen == AppScreen::DiffView,
+            CommandPaletteCommand::OpenFileHistory => {
+                self.git_available
+                    && self.screen == AppScreen::DiffView
+                    && self
+                        .diff_view
+                        .as_ref()
+                        .is_some_and(|view| view.path.is_some())
+            }
+            CommandPaletteCommand::OpenInEditor => {
+                self.screen == AppScreen::DiffView
+                    && self
+                        .diff_view
+                        .as_ref()
+                        .is_some_and(|view| view.path.is_some())
+            }
+            CommandPaletteCommand::CopyPatch | CommandPaletteCommand::CopyNewText => {
+                self.screen == AppScreen::DiffView
+                    && self
+                        .diff_view
+                        .as_ref()
+                        .is_some_and(|view| view.selection.is_some() || !view.hunk_rows.is_empty())
+            }
+            CommandPaletteCommand::ExportPatch => match self.screen {
+                AppScreen::DiffView => self
+                    .diff_view
+                    .as_ref()
+                    .is_some_and(|view| !view.diff_model.hunks.is_empty()),
+                AppScreen::StatusList => self.git_available && !self.loading,
+                _ => false,
+            },
+            CommandPaletteCommand::ApplyPatchFromFile
+            | CommandPaletteCommand::ApplyPatchFromClipboard => {
+                self.screen == AppScreen::StatusList && self.git_available
+            }
+            CommandPaletteCommand::ApplyEditor => matches!(self.screen, AppScreen::ConflictView),
+            CommandPaletteCommand::SaveConflict | CommandPaletteCommand::SaveConflictAndAdd => {
+                let Some(view) = self.conflict_view.as_ref() else {
+                    return false;
+                };
+                let can_save = view.path.is_some() && view.conflicts.is_empty();
+                match command {
+                    CommandPaletteCommand::SaveConflict => can_save,
+                    CommandPaletteCommand::SaveConflictAndAdd => can_save && self.git_available,
+                    _ => false,
+                }
+            }
+            CommandPaletteCommand::NextConflictFile | CommandPaletteCommand::PrevConflictFile => {
+                self.screen == AppScreen::ConflictView && self.has_other_conflict_files()
+            }
+            CommandPaletteCommand::OpenReflog
+            | CommandPaletteCommand::OpenBranchCompare
+            | CommandPaletteCommand::OpenBisect
+            | CommandPaletteCommand::OpenCommitStack
+            | CommandPaletteCommand::OpenPullRequests
+            | CommandPaletteCommand::OpenRepoStats => self.git_available,
+            CommandPaletteCommand::UndoLastRollback => self.last_rollback.is_some(),
+        }
+    }
+
+    fn run_command_palette_command(
+        &mut self,
+        command: CommandPaletteCommand,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match command {
+            CommandPaletteCommand::Back => {
+                if self.command_palette_overlay.is_some() {
+                    self.close_command_palette(window, cx);
+                    return;
+                }
+                if self.file_history_overlay.is_some() {
+                    self.close_file_history_overlay(window, cx);
+                    return;
+                }
+
+                match self.screen {
+                    AppScreen::DiffView => self.close_diff_view(),
+                    AppScreen::ConflictView => self.close_conflict_view(),
+                    AppScreen::Reflog => self.close_reflog_view(),
+                    AppScreen::BranchCompare => self.close_branch_compare_view(),
+                    AppScreen::Bisect => self.close_bisect_view(),
+                    AppScreen::CommitStack => self.close_commit_stack_view(),
+                    AppScreen::PullRequests => self.close_pull_requests_view(),
+                    AppScreen::RepoStats => self.close_repo_stats_view(),
+                    AppScreen::StatusList => {}
+                }
+                window.focus(&self.focus_handle);
+            }
+            CommandPaletteCommand::Next => match self.screen {
+                AppScreen::DiffView => self.jump_hunk(1),
+                AppScreen::ConflictView => self.jump_conflict(1),
+                AppScreen::Reflog => self.move_reflog_selection(1),
+                AppScreen::StatusList
+                | AppScreen::BranchCompare
+                | AppScreen::Bisect
+                | AppScreen::CommitStack
+                | AppScreen::PullRequests
+                | AppScreen::RepoStats => {}
+            },
+            CommandPaletteCommand::Prev => match self.screen {
+                AppScreen::DiffView => self.jump_hunk(-1),
+                AppScreen::ConflictView => self.jump_conflict(-1),
+                AppScreen::Reflog => self.move_reflog_selection(-1),
+                AppScreen::StatusList
+                | AppScreen::BranchCompare
+                | AppScreen::Bisect
+                | AppScreen::CommitStack
+                | AppScreen::PullRequests
+                | AppScreen::RepoStats => {}
+            },
+            CommandPaletteCommand::ToggleViewMode => {
+                if matches!(self.screen, AppScreen::DiffView) {
+                    let next = match self.view_mode {
+                        DiffViewMode::Split => DiffViewMode::Inline,
+                        DiffViewMode::Inline => DiffViewMode::Split,
+                    };
+                    self.set_view_mode(next);
+                }
+            }
+            CommandPaletteCommand::ToggleSplitLayout => {
+                if self.screen == AppScreen::DiffView && self.view_mode == DiffViewMode::Split {
+                    self.split_layout = match self.split_layout {
+                        SplitLayout::Aligned => SplitLayout::TwoPane,
+                        SplitLayout::TwoPane => SplitLayout::Aligned,
+                    };
+                }
+            }
+            CommandPaletteCommand::TogglePaneScrollLink => {
+                if self.two_pane_visible() {
+                    self.set_pane_scroll_linked(!self.pane_scroll_linked);
+                }
+            }
+            CommandPaletteCommand::SyncPaneScroll => {
+                if self.two_pane_visible() {
+                    self.sync_pane_scroll();
+                }
+            }
+            CommandPaletteCommand::ToggleWhitespace => {
+                if matches!(self.screen, AppScreen::DiffView) {
+                    let next = !self.diff_options.ignore_whitespace;
+                    self.set_ignore_whitespace(next, window, cx);
+                }
+            }
+            CommandPaletteCommand::ToggleIgnoreEol => {
+                if matches!(self.screen, AppScreen::DiffView) {
+                    let next = !self.diff_options.ignore_eol;
+                    self.set_ignore_eol(next, window, cx);
+                }
+            }
+            CommandPaletteCommand::ExpandAll => {
+                if matches!(self.screen, AppScreen::DiffView) {
+                    self.expand_all_folds();
+                }
+            }
+            CommandPaletteCommand::ToggleFoldByFunction => {
+                if matches!(self.screen, AppScreen::DiffView) {
+                    self.toggle_fold_by_function();
+                }
+            }
+            CommandPaletteCommand::OpenFileHistory => {
+                if matches!(self.screen, AppScreen::DiffView) {
+                    self.open_file_history_overlay(window, cx);
+                }
+            }
+            CommandPaletteCommand::OpenInEditor => {
+                if matches!(self.screen, AppScreen::DiffView) {
+                    self.open_in_external_editor(window, cx);
+                }
+            }
+            CommandPaletteCommand::CopyPatch => {
+                if matches!(self.screen, AppScreen::DiffView) {
+                    self.copy_diff_patch(window, cx);
+                }
+            }
+            CommandPaletteCommand::CopyNewText => {
+                if matches!(self.screen, AppScreen::DiffView) {
+                    self.copy_diff_new_text(window, cx);
+                }
+            }
+            CommandPaletteCommand::ExportPatch => match self.screen {
+                AppScreen::DiffView => self.export_current_file_patch(window, cx),
+                AppScreen::StatusList => self.export_staged_patch(window, cx),
+                _ => {}
+            },
+            CommandPaletteCommand::ApplyPatchFromFile => {
+                if matches!(self.screen, AppScreen::StatusList) {
+                    self.open_patch_from_file(window, cx);
+                }
+            }
+            CommandPaletteCommand::ApplyPatchFromClipboard => {
+                if matches!(self.screen, AppScreen::StatusList) {
+                    self.open_patch_from_clipboard(window, cx);
+                }
+            }
+            CommandPaletteCommand::ApplyEditor => {
+                if matches!(self.screen, AppScreen::ConflictView) {
+                    self.apply_conflict_editor(window, cx);
+                }
+            }
+            CommandPaletteCommand::SaveConflict => {
+                if matches!(self.screen, AppScreen::ConflictView) {
+                    self.save_conflict_to_working_tree(false, window, cx);
+                }
+            }
+            CommandPaletteCommand::SaveConflictAndAdd => {
+                if matches!(self.screen, AppScreen::ConflictView) {
+                    self.save_conflict_to_working_tree(true, window, cx);
+                }
+            }
+            CommandPaletteCommand::NextConflictFile => {
+                if matches!(self.screen, AppScreen::ConflictView) {
+                    self.jump_conflict_file(1, window, cx);
+                }
+            }
+            CommandPaletteCommand::PrevConflictFile => {
+                if matches!(self.screen, AppScreen::ConflictView) {
+                    self.jump_conflict_file(-1, window, cx);
+                }
+            }
+            CommandPaletteCommand::OpenReflog => self.open_reflog_view(window, cx),
+            CommandPaletteCommand::OpenBranchCompare => self.open_branch_compare_view(window, cx),
+            CommandPaletteCommand::OpenBisect => self.open_bisect_view(window, cx),
+            CommandPaletteCommand::OpenCommitStack => self.open_commit_stack_view(window, cx),
+            CommandPaletteCommand::OpenPullRequests => self.open_pull_requests_view(window, cx),
+            CommandPaletteCommand::OpenRepoStats => self.open_repo_stats_view(window, cx),
+            CommandPaletteCommand::UndoLastRollback => self.undo_last_rollback(window, cx),
+        }
+        cx.notify();
+    }
+
+    fn set_compare_target(
+        &mut self,
+        target: CompareTarget,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(diff_view) = self.diff_view.as_ref() else {
+            return;
+        };
+        if diff_view.compare_target == target {
+            return;
+        }
+
+        let Some(path) = diff_view.path.clone() else {
+            return;
+        };
+        let status = diff_view.status.clone().unwrap_or_default();
+        self.open_file_diff_with_target(path, status, target, window, cx);
+    }
+
+    fn open_file(
+        &mut self,
+        path: String,
+        status: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if status.contains('U') {
+            self.open_conflict_file(path, status, window, cx);
+        } else {
+            self.open_file_diff(path, status, window, cx);
+        }
+    }
 
+    fn open_conflict_file(
+        &mut self,
+        path: String,
+        status: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
         let this = cx.entity();
         let repo_root = self.repo_root.clone();
         let path_for_task = path.clone();
+        let status_for_task = status.clone();
 
-        window.push_notification(
-            Notification::new().message(format!(
-                "{} hunk {}/{}: {path_for_task}",
-                action.label(),
-                hunk_index + 1,
-                hunk_count
-            )),
-            cx,
-        );
+        window.push_toast(Toast::info(format!("正在加载冲突：{status} {path}")), cx);
 
         cx.spawn_in(window, async move |_, window| {
-            let diff_options =
-                window
-                    .update(|_, cx| this.read(cx).diff_options)
-                    .unwrap_or(DiffViewOptions {
-                        ignore_whitespace: false,
-                        context_lines: 3,
-                    });
-            let view_mode = window
-                .update(|_, cx| this.read(cx).view_mode)
-                .unwrap_or(DiffViewMode::Split);
-
             let path_for_task_bg = path_for_task.clone();
-            let compare_target_for_io = compare_target.clone();
-            let (
-                apply_result,
-                entries,
-                status_err,
-                updated_status,
-                old_text,
-                old_err,
-                new_text,
-                new_err,
-                model,
-                old_lines,
-                new_lines,
-            ) = window
+            let (content, source, encoding, stage_err, err) = window
                 .background_executor()
                 .spawn(async move {
-                    let apply_result =
-                        run_git_with_stdin(repo_root.as_path(), action.git_args(), &patch);
-                    let (entries, status_err, updated_status) = match fetch_git_status(&repo_root) {
-                        Ok(entries) => {
-                            let updated_status = entries
-                                .iter()
-                                .find(|entry| entry.path == path_for_task_bg)
-                                .map(|entry| entry.status.clone())
-                                .unwrap_or_default();
-                            (Some(entries), None, updated_status)
-                        }
-                        Err(err) => (None, Some(err.to_string()), String::new()),
-                    };
-
-                    let (old_text, old_err) = match compare_target_for_io.clone() {
-                        CompareTarget::HeadToWorktree | CompareTarget::HeadToIndex => {
-                            match read_head_file(&repo_root, &path_for_task_bg, &updated_status) {
-                                Ok(text) => (text, None),
-                                Err(err) => (String::new(), Some(err.to_string())),
-                            }
-                        }
-                        CompareTarget::IndexToWorktree => {
-                            match read_index_file(&repo_root, &path_for_task_bg, &updated_status) {
-                                Ok(text) => (text, None),
-                                Err(err) => (String::new(), Some(err.to_string())),
-                            }
-                        }
-                        CompareTarget::Refs { .. } => {
-                            (String::new(), Some("不支持的对比目标".to_string()))
-                        }
-                    };
-
-                    let (new_text, new_err) = match compare_target_for_io {
-                        CompareTarget::HeadToWorktree | CompareTarget::IndexToWorktree => {
-                            match read_working_file(&repo_root, &path_for_task_bg) {
-                                Ok(text) => (text, None),
-                                Err(err) => (String::new(), Some(err.to_string())),
-                            }
-                        }
-                        CompareTarget::HeadToIndex => {
-                            match read_index_file(&repo_root, &path_for_task_bg, &updated_status) {
-                                Ok(text) => (text, None),
-                                Err(err) => (String::new(), Some(err.to_string())),
-                            }
-                        }
-                        CompareTarget::Refs { .. } => {
-                            (String::new(), Some("不支持的对比目标".to_string()))
+                    // 保存时按工作区文件原来的编码写回。
+                    let working = read_working_file_decoded(&repo_root, &path_for_task_bg);
+                    let encoding = working
+                        .as_ref()
+                        .map(|(_, encoding)| *encoding)
+                        .unwrap_or(TextEncoding::UTF8);
+
+                    // 优先用 index 中的 :1/:2/:3 做 diff3 合并；读不到 stage（例如冲突已被
+                    // 标记为解决）时才退回解析工作区文件里的冲突标记。
+                    let stage_err = match read_conflict_stages(&repo_root, &path_for_task_bg) {
+                        Ok((base, ours, theirs)) => {
+                            let mut model = diffview::merge3(&base, &ours, &theirs);
+                            let report = model
+                                .auto_resolve(&GitViewerSettings::get().conflict_auto_resolve());
+                            let source = ConflictSource::IndexStages {
+                                auto_merged: model.auto_merged_count(),
+                                auto_resolved: report.resolved.len(),
+                            };
+                            return (ConflictContent::Merge(model), source, encoding, None, None);
                         }
+                        Err(err) => err.to_string(),
                     };
 
-                    let (model, old_lines, new_lines) = build_diff_model(
-                        &old_text,
-                        &new_text,
-                        diff_options.ignore_whitespace,
-                        diff_options.context_lines,
-                    );
-
-                    (
-                        apply_result,
-                        entries,
-                        status_err,
-                        updated_status,
-                        old_text,
-                        old_err,
-                        new_text,
-                        new_err,
-                        model,
-                        old_lines,
-                        new_lines,
-                    )
+                    match working {
+                        Ok((text, _)) => (
+                            ConflictContent::Markers(text),
+                            ConflictSource::WorktreeMarkers,
+                            encoding,
+                            Some(stage_err),
+                            None,
+                        ),
+                        Err(err) => (
+                            ConflictContent::Markers(String::new()),
+                            ConflictSource::WorktreeMarkers,
+                            encoding,
+                            Some(stage_err),
+                            Some(err.to_string()),
+                        ),
+                    }
                 })
                 .await;
 
             window
                 .update(|window, cx| {
-                    if let Err(err) = apply_result {
-                        window.push_notification(
-                            Notification::new().message(format!("{} 失败：{err}", action.label())),
-                            cx,
-                        );
-                        return;
-                    }
-
-                    window.push_notification(
-                        Notification::new().message(format!("{} 成功", action.label())),
-                        cx,
-                    );
-
-                    if let Some(err) = status_err {
-                        window.push_notification(
-                            Notification::new().message(format!("刷新 git 状态失败：{err}")),
-                            cx,
-                        );
-                    }
-
-                    if let Some(err) = old_err {
-                        window.push_notification(
-                            Notification::new().message(format!(
-                                "读取 {} 版本失败，按空内容处理：{err}",
-                                compare_target_side_label(&compare_target, Side::Old)
+                    if let Some(stage_err) = stage_err {
+                        window.push_toast(
+                            Toast::info(format!(
+                                "未能读取 index 冲突 stage，改为解析工作区冲突标记：{stage_err}"
                             )),
                             cx,
                         );
                     }
-                    if let Some(err) = new_err {
-                        window.push_notification(
-                            Notification::new().message(format!(
-                                "读取 {} 版本失败，按空内容处理：{err}",
-                                compare_target_side_label(&compare_target, Side::New)
-                            )),
+                    if let Some(err) = err {
+                        window.push_toast(
+                            Toast::error(format!("读取工作区版本失败，按空内容处理：{err}")),
                             cx,
                         );
                     }
 
-                    this.update(cx, |this, cx| {
-                        if let Some(entries) = entries {
-                            this.files = entries;
-                        }
-
-                        let title: SharedString = if updated_status.is_empty() {
-                            path_for_task.clone().into()
-                        } else {
-                            format!("{updated_status} {path_for_task}").into()
-                        };
-
-                        if let Some(diff_view) = this.diff_view.as_ref() {
-                            if diff_view.path.as_deref() == Some(path_for_task.as_str()) {
-                                let mut next = DiffViewState::from_precomputed(
-                                    title,
-                                    Some(path_for_task.clone()),
-                                    (!updated_status.is_empty()).then(|| updated_status.clone()),
-                                    compare_target,
-                                    old_text,
-                                    new_text,
-                                    view_mode,
-                                    model,
-                                    old_lines,
-                                    new_lines,
-                                );
-                                next.scroll_handle = scroll_handle.clone();
-                                next.scroll_state = scroll_state.clone();
-                                next.current_hunk =
-                                    current_hunk.min(next.hunk_rows.len().saturating_sub(1));
-                                this.diff_content_revision =
-                                    this.diff_content_revision.wrapping_add(1);
-                                this.diff_view = Some(next);
-                            }
+                    let initial_text = content.result_text();
+                    let result_input = cx.new(move |cx| {
+                        InputState::new(window, cx)
+                            .code_editor("text")
+                            .default_value(initial_text)
+                    });
+                    this.update(cx, |this, _cx| {
+                        this.open_conflict_view(
+                            format!("{status_for_task} {path_for_task}").into(),
+                            Some(path_for_task),
+                            source,
+                            content,
+                            result_input,
+                        );
+                        if let Some(conflict_view) = this.conflict_view.as_mut() {
+                            conflict_view.encoding = encoding;
                         }
-
-                        cx.notify();
                     });
                 })
                 .ok();
@@ -2583,1789 +3183,8674 @@ impl GitViewerApp {
         .detach();
     }
 
-    fn render_status_list(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> Div {
-        let counts = StatusCounts::from_entries(&self.files);
-        let filtered_count = self
-            .files
-            .iter()
-            .filter(|entry| matches_filter(entry, self.status_filter))
-            .count();
+    fn set_ignore_whitespace(&mut self, value: bool, window: &mut Window, cx: &mut Context<Self>) {
+        if self.diff_options.ignore_whitespace == value {
+            return;
+        }
+        self.diff_options.ignore_whitespace = value;
+        self.request_diff_rebuild(window, cx);
+    }
 
-        let header: SharedString = if !self.git_available {
-            "未检测到 git 命令：仅可运行 Diff/Conflict Demo（请安装 git 或配置 PATH）".into()
-        } else if self.loading {
-            "正在加载 git 状态…".into()
-        } else {
-            format!("显示 {} / {} 个文件", filtered_count, counts.all).into()
-        };
+    fn set_ignore_eol(&mut self, value: bool, window: &mut Window, cx: &mut Context<Self>) {
+        if self.diff_options.ignore_eol == value {
+            return;
+        }
+        self.diff_options.ignore_eol = value;
+        self.request_diff_rebuild(window, cx);
+    }
 
-        let demo_button = Button::new("open-demo")
-            .label("打开 Diff Demo")
-            .ghost()
-            .on_click(cx.listener(|this, _, _window, cx| {
-                this.open_demo();
-                cx.notify();
-            }));
+    fn set_context_lines(&mut self, value: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let value = value.min(MAX_CONTEXT_LINES);
+        if self.diff_options.context_lines == value {
+            return;
+        }
+        self.diff_options.context_lines = value;
+        self.request_diff_rebuild(window, cx);
+    }
 
-        let large_demo_button = Button::new("open-large-demo")
-            .label("打开 Large Diff Demo")
-            .ghost()
-            .on_click(cx.listener(|this, _, window, cx| {
-                this.open_large_demo(window, cx);
-                cx.notify();
-            }));
+    fn set_view_mode(&mut self, mode: DiffViewMode) {
+        if self.view_mode == mode {
+            return;
+        }
+        self.view_mode = mode;
+        if let Some(diff_view) = self.diff_view.as_mut() {
+            diff_view.rebuild_rows(mode);
+        }
+    }
 
-        let conflict_demo_button = Button::new("open-conflict-demo")
-            .label("打开 Conflict Demo")
-            .ghost()
-            .on_click(cx.listener(|this, _, window, cx| {
-                this.open_conflict_demo(window, cx);
-                cx.notify();
-            }));
+    fn toggle_markdown_preview(&mut self) {
+        self.markdown_preview = !self.markdown_preview;
+    }
 
-        let filter_button = |filter: StatusFilter, id: &'static str, label: String| {
-            let mut button = Button::new(id).label(label);
-            if self.status_filter == filter {
-                button = button.primary();
-            } else {
-                button = button
-                    .ghost()
-                    .on_click(cx.listener(move |this, _, _window, cx| {
-                        this.status_filter = filter;
-                        cx.notify();
-                    }));
-            }
-            button
+    fn toggle_fold_by_function(&mut self) {
+        self.fold_by_function = !self.fold_by_function;
+        if let Some(diff_view) = self.diff_view.as_mut() {
+            diff_view.fold_by_function = self.fold_by_function;
+            diff_view.rebuild_rows(self.view_mode);
+        }
+    }
+
+    fn request_diff_rebuild(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(diff_view) = self.diff_view.as_ref() else {
+            return;
         };
 
-        let filter_bar = div()
-            .flex()
-            .flex_row()
-            .items_center()
-            .gap(px(6.))
-            .child(filter_button(
-                StatusFilter::All,
-                "filter-all",
-                format!("All {}", counts.all),
-            ))
-            .child(filter_button(
-                StatusFilter::Conflicts,
-                "filter-conflicts",
-                format!("Conflicts {}", counts.conflicts),
-            ))
-            .child(filter_button(
-                StatusFilter::Staged,
-                "filter-staged",
-                format!("Staged {}", counts.staged),
-            ))
-            .child(filter_button(
-                StatusFilter::Unstaged,
-                "filter-unstaged",
-                format!("Unstaged {}", counts.unstaged),
-            ))
-            .child(filter_button(
-                StatusFilter::Untracked,
-                "filter-untracked",
-                format!("Untracked {}", counts.untracked),
-            ));
+        self.diff_rebuild_seq = self.diff_rebuild_seq.wrapping_add(1);
+        let rebuild_seq = self.diff_rebuild_seq;
+        let content_revision = self.diff_content_revision;
+        let compare_target = diff_view.compare_target.clone();
+        let this = cx.entity();
 
-        let list: Vec<AnyElement> = if self.loading {
-            vec![div().child("加载中…").into_any_element()]
-        } else if self.files.is_empty() {
-            vec![div().child("没有检测到变更文件").into_any_element()]
-        } else {
-            self.files
-                .iter()
-                .filter(|entry| matches_filter(entry, self.status_filter))
-                .enumerate()
-                .map(|(index, entry)| {
-                    let path = entry.path.clone();
-                    let status = entry.status.clone();
-                    Button::new(("file", index))
-                        .label(format!("{status} {path}"))
-                        .w_full()
-                        .on_click(cx.listener(move |this, _, window, cx| {
-                            println!("[git-viewer] 打开文件: {status} {path}");
-                            this.open_file(path.clone(), status.clone(), window, cx);
-                            cx.notify();
-                        }))
-                        .into_any_element()
+        cx.spawn_in(window, async move |_, window| {
+            Timer::after(Duration::from_millis(DIFF_REBUILD_DEBOUNCE_MS)).await;
+
+            let snapshot = window
+                .update(|_, cx| {
+                    let app = this.read(cx);
+                    if app.diff_rebuild_seq != rebuild_seq
+                        || app.diff_content_revision != content_revision
+                    {
+                        return None;
+                    }
+
+                    let diff_view = app.diff_view.as_ref()?;
+                    Some((
+                        diff_view.title.clone(),
+                        diff_view.path.clone(),
+                        diff_view.status.clone(),
+                        diff_view.old_text.clone(),
+                        diff_view.new_text.clone(),
+                        diff_view.lfs.clone(),
+                        diff_view.scroll_handle.clone(),
+                        diff_view.scroll_state.clone(),
+                        diff_view.current_hunk,
+                        app.diff_options,
+                        app.view_mode,
+                    ))
                 })
-                .collect()
-        };
+                .ok()
+                .flatten();
 
-        div()
-            .flex()
-            .flex_col()
-            .gap(px(8.))
-            .p(px(12.))
-            .child(
-                div()
-                    .flex()
-                    .flex_row()
-                    .items_center()
-                    .justify_between()
-                    .gap(px(8.))
-                    .child(div().child(header))
-                    .child(
-                        div()
-                            .flex()
-                            .flex_row()
-                            .gap(px(8.))
-                            .child(demo_button)
-                            .child(large_demo_button)
-                            .child(conflict_demo_button),
-                    ),
-            )
-            .child(filter_bar)
-            .child(div().flex_col().gap(px(6.)).children(list))
+            let Some((
+                title,
+                path,
+                status,
+                old_text,
+                new_text,
+                lfs,
+                scroll_handle,
+                scroll_state,
+                current_hunk,
+                diff_options,
+                view_mode,
+            )) = snapshot
+            else {
+                return Some(());
+            };
+
+            let (old_text, new_text, model, old_lines, new_lines, diff_timing) = window
+                .background_executor()
+                .spawn(async move {
+                    let (model, old_lines, new_lines, diff_timing) =
+                        build_diff_model(&old_text, &new_text, diff_options);
+                    (old_text, new_text, model, old_lines, new_lines, diff_timing)
+                })
+                .await;
+
+            window
+                .update(|_window, cx| {
+                    this.update(cx, |this, cx| {
+                        if this.diff_rebuild_seq != rebuild_seq
+                            || this.diff_content_revision != content_revision
+                        {
+                            return;
+                        }
+
+                        let still_same = this.diff_view.as_ref().is_some_and(|view| {
+                            view.path == path && view.compare_target == compare_target
+                        });
+                        if !still_same {
+                            return;
+                        }
+
+                        let encoding = this.diff_view.as_ref().and_then(|view| view.encoding);
+                        let mut next = DiffViewState::from_precomputed(
+                            title,
+                            path,
+                            status,
+                            compare_target,
+                            old_text,
+                            new_text,
+                            view_mode,
+                            model,
+                            old_lines,
+                            new_lines,
+                            diff_timing,
+                        );
+                        next.scroll_handle = scroll_handle;
+                        next.lfs = lfs;
+                        next.encoding = encoding;
+                        next.scroll_state = scroll_state;
+                        next.current_hunk =
+                            current_hunk.min(next.hunk_rows.len().saturating_sub(1));
+                        this.diff_view = Some(next);
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
     }
 
-    fn render_diff_view(&mut self, window: &mut Window, cx: &mut Context<Self>) -> Div {
+    fn jump_hunk(&mut self, direction: i32) {
         let Some(diff_view) = self.diff_view.as_mut() else {
-            return div().p(px(12.)).child("No diff view");
+            return;
         };
+        if diff_view.hunk_rows.is_empty() {
+            return;
+        }
 
-        let view_mode = self.view_mode;
-        let inline_mode = view_mode == DiffViewMode::Inline;
-        let two_pane = matches!(self.split_layout, SplitLayout::TwoPane);
-        let title = diff_view.title.clone();
-        let compare_target = diff_view.compare_target.clone();
-        let ignore_whitespace = self.diff_options.ignore_whitespace;
-        let context_lines = self.diff_options.context_lines;
-        let hunk_count = diff_view.hunk_rows.len();
-        let can_prev_hunk = diff_view.current_hunk > 0;
-        let can_next_hunk = diff_view.current_hunk + 1 < diff_view.hunk_rows.len();
-        let file_status = diff_view.status.as_deref().unwrap_or_default();
-        let has_file_path = diff_view.path.is_some();
-        let git_available = self.git_available;
-        let can_stage = git_available
-            && has_file_path
-            && (is_untracked_status(file_status)
-                || status_xy(file_status).is_some_and(|(_, y)| y != ' ' && y != '?' && y != '!'));
-        let can_unstage = git_available
-            && has_file_path
-            && status_xy(file_status).is_some_and(|(x, _)| x != ' ' && x != '?' && x != '!');
-        let has_hunks = hunk_count > 0;
-        let can_stage_hunk = git_available
-            && has_file_path
-            && has_hunks
-            && !is_untracked_status(file_status)
-            && compare_target == CompareTarget::IndexToWorktree;
-        let can_revert_hunk = can_stage_hunk;
-        let can_unstage_hunk = git_available
-            && has_file_path
-            && has_hunks
-            && compare_target == CompareTarget::HeadToIndex;
-        let rows_len = diff_view.rows.len();
-        let scroll_handle = diff_view.scroll_handle.clone();
-        let scroll_state = diff_view.scroll_state.clone();
-        let row_height = window.line_height() + px(4.);
-        let item_sizes = diff_view.item_sizes(row_height);
+        let mut next_index = diff_view.current_hunk;
+        if direction < 0 {
+            next_index = next_index.saturating_sub(1);
+        } else if direction > 0 {
+            next_index = (next_index + 1).min(diff_view.hunk_rows.len().saturating_sub(1));
+        }
 
-        let app = cx.entity();
-        let compare_left_input = self.compare_left_input.clone();
-        let compare_right_input = self.compare_right_input.clone();
-        let compare_label: SharedString =
-            format!("对比: {}", compare_target_label(&compare_target)).into();
-        let compare_control: AnyElement = if git_available && has_file_path {
-            let app_for_menu = app.clone();
-            let active_target = compare_target.clone();
-            Popover::new("diff-compare-menu")
-                .appearance(false)
-                .trigger(
-                    Button::new("diff-compare-trigger")
-                        .label(compare_label.clone())
-                        .ghost()
-                        .tooltip("选择对比目标")
-                        .on_click(|_, _, _| {}),
-                )
-                .content(move |_, _window, cx| {
-                    let theme = cx.theme();
-                    let popover = cx.entity();
+        diff_view.current_hunk = next_index;
+        let row_index = diff_view.hunk_rows[next_index];
+        diff_view.scroll_to_row(row_index);
+    }
 
-                    let app_for_items = app_for_menu.clone();
-                    let popover_for_items = popover.clone();
-                    let active_target = active_target.clone();
-                    let make_item = |id: &'static str,
-                                     label: SharedString,
-                                     target: CompareTarget| {
-                        let app = app_for_items.clone();
-                        let popover = popover_for_items.clone();
-                        let is_active = active_target == target;
+    fn open_in_external_editor(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(diff_view) = self.diff_view.as_ref() else {
+            return;
+        };
+        let Some(path) = diff_view.path.clone() else {
+            window.push_toast(Toast::info("当前 diff 为 demo，没有可打开的文件"), cx);
+            return;
+        };
 
-                        div()
-                            .id(id)
-                            .flex()
-                            .items_center()
-                            .h(px(32.))
-                            .px(px(10.))
-                            .rounded(px(6.))
-                            .text_sm()
-                            .when(is_active, |this| {
-                                this.bg(theme.accent)
-                                    .text_color(theme.accent_foreground)
-                                    .cursor_default()
-                            })
-                            .when(!is_active, |this| {
-                                this.bg(theme.transparent)
-                                    .text_color(theme.popover_foreground)
-                                    .cursor_pointer()
-                                    .hover(|this| {
-                                        this.bg(theme.accent.alpha(0.4))
-                                            .text_color(theme.accent_foreground)
-                                    })
-                                    .active(|this| {
-                                        this.bg(theme.accent).text_color(theme.accent_foreground)
-                                    })
-                                    .on_mouse_down(MouseButton::Left, move |_, window, cx| {
-                                        window.prevent_default();
-                                        let target = target.clone();
-                                        app.update(cx, |this, cx| {
-                                            this.set_compare_target(target, window, cx);
-                                            cx.notify();
-                                        });
-                                        popover.update(cx, |state, cx| state.dismiss(window, cx));
-                                    })
-                            })
-                            .child(label)
-                    };
+        let line = diff_view.current_line();
+        let repo_root = self.repo_root.clone();
+        let file = repo_root.join(&path);
+        cx.spawn_in(window, async move |_, window| {
+            let result = window
+                .background_executor()
+                .spawn(async move { launch_editor(&repo_root, &file, line) })
+                .await;
 
-                    div()
-                        .p(px(6.))
-                        .w(px(360.))
-                        .bg(theme.popover)
-                        .border_1()
-                        .border_color(theme.border)
-                        .rounded(theme.radius)
-                        .shadow_md()
-                        .flex()
-                        .flex_col()
-                        .gap(px(4.))
-                        .child(
-                            div()
-                                .px(px(6.))
-                                .py(px(4.))
-                                .text_xs()
-                                .text_color(theme.muted_foreground)
-                                .child("选择对比目标"),
-                        )
-                        .child(make_item(
-                            "diff-compare-head-worktree",
-                            "HEAD ↔ 工作区".into(),
-                            CompareTarget::HeadToWorktree,
-                        ))
-                        .child(make_item(
-                            "diff-compare-index-worktree",
-                            "暂存 ↔ 工作区".into(),
-                            CompareTarget::IndexToWorktree,
-                        ))
-                        .child(make_item(
-                            "diff-compare-head-index",
-                            "HEAD ↔ 暂存".into(),
-                            CompareTarget::HeadToIndex,
-                        ))
-                        .child(div().h(px(1.)).bg(theme.border.alpha(0.4)).my(px(4.)))
-                        .child(
-                            div()
-                                .px(px(6.))
-                                .py(px(4.))
-                                .text_xs()
-                                .text_color(theme.muted_foreground)
-                                .child("任意版本对比"),
-                        )
-                        .child(
-                            div()
-                                .px(px(6.))
-                                .flex()
-                                .flex_col()
-                                .gap(px(6.))
-                                .child(Input::new(&compare_left_input).w_full())
-                                .child(Input::new(&compare_right_input).w_full())
-                                .child(
-                                    div()
-                                        .flex()
-                                        .flex_row()
-                                        .justify_end()
-                                        .gap(px(6.))
-                                        .child(
-                                            Button::new("diff-compare-swap")
-                                                .label("交换")
-                                                .ghost()
-                                                .on_click({
-                                                    let app = app_for_menu.clone();
-                                                    move |_, window, cx| {
-                                                        app.update(cx, |this, cx| {
-                                                            this.swap_compare_refs_inputs(
-                                                                window, cx,
-                                                            );
-                                                        });
-                                                    }
-                                                }),
-                                        )
-                                        .child(
-                                            Button::new("diff-compare-apply")
-                                                .label("应用")
-                                                .primary()
-                                                .on_click({
-                                                    let app = app_for_menu.clone();
-                                                    let popover = popover.clone();
-                                                    move |_, window, cx| {
-                                                        app.update(cx, |this, cx| {
-                                                            this.apply_compare_refs_from_inputs(
-                                                                window, cx,
-                                                            );
-                                                            cx.notify();
-                                                        });
-                                                        popover.update(cx, |state, cx| {
-                                                            state.dismiss(window, cx)
-                                                        });
-                                                    }
-                                                }),
-                                        ),
-                                ),
-                        )
-                        .child(div().h(px(1.)).bg(theme.border.alpha(0.4)).my(px(4.)))
-                        .child(
-                            Button::new("diff-history-open")
-                                .label("从历史选择 commit…")
-                                .ghost()
-                                .w_full()
-                                .on_click({
-                                    let app = app_for_menu.clone();
-                                    let popover = popover.clone();
-                                    move |_, window, cx| {
-                                        app.update(cx, |this, cx| {
-                                            this.open_file_history_overlay(window, cx);
-                                        });
-                                        popover.update(cx, |state, cx| state.dismiss(window, cx));
-                                    }
-                                }),
-                        )
+            window
+                .update(|window, cx| {
+                    let toast = match result {
+                        Ok(editor) => Toast::info(format!("已在 {editor} 中打开 {path}:{line}")),
+                        Err(err) => Toast::error(format!("打开外部编辑器失败：{err:#}")),
+                    };
+                    window.push_toast(toast, cx);
                 })
-                .into_any_element()
-        } else {
-            Button::new("diff-compare-disabled")
-                .label(compare_label)
-                .ghost()
-                .disabled(true)
-                .into_any_element()
-        };
+                .ok();
 
-        let can_expand_all = diff_view
-            .rows
-            .iter()
-            .any(|row| matches!(row, DisplayRow::Fold { .. }));
+            Some(())
+        })
+        .detach();
+    }
 
-        let stage_file_label: SharedString = if can_stage {
-            "Stage 文件".into()
-        } else if !git_available {
-            "Stage 文件（git 不可用）".into()
-        } else if !has_file_path {
-            "Stage 文件（demo 不支持）".into()
-        } else {
-            "Stage 文件（无可暂存变更）".into()
+    fn select_diff_row(&mut self, index: usize, extend: bool) {
+        let Some(diff_view) = self.diff_view.as_mut() else {
+            return;
         };
-        let unstage_file_label: SharedString = if can_unstage {
-            "Unstage 文件".into()
-        } else if !git_available {
-            "Unstage 文件（git 不可用）".into()
-        } else if !has_file_path {
-            "Unstage 文件（demo 不支持）".into()
-        } else {
-            "Unstage 文件（无可取消暂存）".into()
+        diff_view.selection = match diff_view.selection {
+            Some((anchor, _)) if extend => Some((anchor, index)),
+            Some((anchor, head)) if anchor == index && head == index => None,
+            _ => Some((index, index)),
         };
+    }
 
-        let stage_hunk_label: SharedString = if can_stage_hunk {
-            "Stage 当前 hunk".into()
-        } else if !git_available {
-            "Stage 当前 hunk（git 不可用）".into()
-        } else if !has_file_path {
-            "Stage 当前 hunk（demo 不支持）".into()
-        } else if !has_hunks {
-            "Stage 当前 hunk（无 hunk）".into()
-        } else if compare_target != CompareTarget::IndexToWorktree {
-            "Stage 当前 hunk（切到 暂存↔工作区）".into()
+    fn copy_diff_patch(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(diff_view) = self.diff_view.as_ref() else {
+            return;
+        };
+        let path = diff_view
+            .path
+            .clone()
+            .unwrap_or_else(|| diff_view.title.to_string());
+        let source = if diff_view.selection.is_some() {
+            "选中行"
         } else {
-            "Stage 当前 hunk（不可用）".into()
+            "当前 hunk"
         };
 
-        let unstage_hunk_label: SharedString = if can_unstage_hunk {
-            "Unstage 当前 hunk".into()
-        } else if !git_available {
-            "Unstage 当前 hunk（git 不可用）".into()
-        } else if !has_file_path {
-            "Unstage 当前 hunk（demo 不支持）".into()
-        } else if !has_hunks {
-            "Unstage 当前 hunk（无 hunk）".into()
-        } else if compare_target != CompareTarget::HeadToIndex {
-            "Unstage 当前 hunk（切到 HEAD↔暂存）".into()
-        } else {
-            "Unstage 当前 hunk（不可用）".into()
+        let message = match diff_view.patch_for_selection_or_hunk(&path) {
+            Some(patch) => {
+                cx.write_to_clipboard(ClipboardItem::new_string(patch));
+                format!("已复制{source}为 patch")
+            }
+            None => format!("{source}没有可复制的变更"),
         };
+        window.push_toast(Toast::info(message), cx);
+    }
 
-        let revert_hunk_label: SharedString = if can_revert_hunk {
-            "Revert 当前 hunk".into()
-        } else if !git_available {
-            "Revert 当前 hunk（git 不可用）".into()
-        } else if !has_file_path {
-            "Revert 当前 hunk（demo 不支持）".into()
-        } else if !has_hunks {
-            "Revert 当前 hunk（无 hunk）".into()
-        } else if compare_target != CompareTarget::IndexToWorktree {
-            "Revert 当前 hunk（切到 暂存↔工作区）".into()
+    fn copy_diff_new_text(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(diff_view) = self.diff_view.as_ref() else {
+            return;
+        };
+        let source = if diff_view.selection.is_some() {
+            "选中行"
         } else {
-            "Revert 当前 hunk（不可用）".into()
+            "当前 hunk"
         };
 
-        let shortcuts_message: SharedString = concat!(
-            "快捷键：\n",
-            "Esc 返回\n",
-            "Alt+N / Alt+P 下一/上一 hunk\n",
-            "Alt+V 切换 Split/Inline\n",
-            "Alt+L 切换对齐/分栏\n",
-            "Alt+W 忽略空白\n",
-            "Alt+E 展开全部\n",
-        )
-        .into();
+        let message = match diff_view.new_text_for_selection_or_hunk() {
+            Some(text) => {
+                cx.write_to_clipboard(ClipboardItem::new_string(text));
+                format!("已复制{source}的新版本文本")
+            }
+            None => format!("{source}在新版本中没有内容"),
+        };
+        window.push_toast(Toast::info(message), cx);
+    }
 
-        let more_menu = {
-            let app_for_menu = app.clone();
-            Popover::new("diff-more-menu")
-                .appearance(false)
-                .trigger(
-                    Button::new("diff-more-trigger")
-                        .label("更多")
-                        .ghost()
-                        .on_click(|_, _, _| {}),
-                )
-                .content(move |_, _window, cx| {
-                    let theme = cx.theme();
-                    let popover = cx.entity();
+    fn export_current_file_patch(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(diff_view) = self.diff_view.as_ref() else {
+            return;
+        };
+        if diff_view.diff_model.hunks.is_empty() {
+            window.push_toast(Toast::info("当前文件没有变更"), cx);
+            return;
+        }
 
-                    let make_action = move |id: &'static str,
-                                            label: SharedString,
-                                            disabled: bool,
-                                            action: Rc<dyn Fn(&mut Window, &mut App) + 'static>| {
-                        let popover = popover.clone();
-                        Button::new(id)
-                            .label(label)
-                            .ghost()
-                            .disabled(disabled)
-                            .w_full()
-                            .on_click({
-                                let action = action.clone();
-                                move |_, window, cx| {
-                                    action(window, cx);
-                                    popover.update(cx, |state, cx| state.dismiss(window, cx));
-                                }
-                            })
-                            .into_any_element()
-                    };
+        let path = diff_view
+            .path
+            .clone()
+            .unwrap_or_else(|| diff_view.title.to_string());
+        let patch = unified_patch_for_file(&path, &diff_view.diff_model.hunks);
+        let file_name = Path::new(&path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "changes".to_string());
+        self.save_patch_file(patch, format!("{file_name}.patch"), window, cx);
+    }
 
-                    let view_toggle_split_label: SharedString = if inline_mode {
-                        "分栏（Inline 模式不可用）".into()
-                    } else if two_pane {
-                        "分栏: 开".into()
-                    } else {
-                        "分栏: 关".into()
-                    };
-                    let view_toggle_ws_label: SharedString = if ignore_whitespace {
-                        "忽略空白: 开".into()
-                    } else {
-                        "忽略空白: 关".into()
-                    };
+    fn export_staged_patch(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.git_available {
+            return;
+        }
 
-                    let app_for_split = app_for_menu.clone();
-                    let toggle_split = Rc::new(move |_window: &mut Window, cx: &mut App| {
-                        app_for_split.update(cx, |this, cx| {
-                            if this.view_mode == DiffViewMode::Inline {
-                                return;
-                            }
-                            this.split_layout = match this.split_layout {
-                                SplitLayout::Aligned => SplitLayout::TwoPane,
-                                SplitLayout::TwoPane => SplitLayout::Aligned,
-                            };
-                            cx.notify();
-                        });
-                    });
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        let amend = self.amend.is_some();
+        cx.spawn_in(window, async move |_, window| {
+            let result = window
+                .background_executor()
+                .spawn(async move { fetch_staged_patch(&repo_root, amend) })
+                .await;
 
-                    let app_for_ws = app_for_menu.clone();
-                    let toggle_ws = Rc::new(move |window: &mut Window, cx: &mut App| {
-                        app_for_ws.update(cx, |this, cx| {
-                            let next = !this.diff_options.ignore_whitespace;
-                            this.set_ignore_whitespace(next, window, cx);
-                            cx.notify();
+            window
+                .update(|window, cx| match result {
+                    Ok(patch) if patch.is_empty() => {
+                        window.push_toast(Toast::info("暂存区没有可导出的变更"), cx);
+                    }
+                    Ok(patch) => {
+                        this.update(cx, |this, cx| {
+                            this.save_patch_file(patch, "staged.patch".to_string(), window, cx);
                         });
-                    });
+                    }
+                    Err(err) => {
+                        window.push_toast(Toast::error(format!("生成 patch 失败：{err:#}")), cx);
+                    }
+                })
+                .ok();
 
-                    let app_for_expand = app_for_menu.clone();
-                    let expand_all = Rc::new(move |_window: &mut Window, cx: &mut App| {
-                        app_for_expand.update(cx, |this, cx| {
-                            this.expand_all_folds();
-                            cx.notify();
-                        });
-                    });
+            Some(())
+        })
+        .detach();
+    }
 
-                    let shortcuts_message = shortcuts_message.clone();
-                    let show_shortcuts = Rc::new(move |window: &mut Window, cx: &mut App| {
-                        window.push_notification(
-                            Notification::new().message(shortcuts_message.to_string()),
-                            cx,
-                        );
-                    });
+    fn save_patch_file(
+        &mut self,
+        patch: String,
+        suggested_name: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let receiver = cx.prompt_for_new_path(&self.repo_root, Some(&suggested_name));
+        cx.spawn_in(window, async move |_, window| {
+            let target = match receiver.await.ok()? {
+                Ok(target) => target?,
+                Err(err) => {
+                    window
+                        .update(|window, cx| {
+                            window.push_toast(
+                                Toast::error(format!("打开保存对话框失败：{err:#}")),
+                                cx,
+                            );
+                        })
+                        .ok();
+                    return None;
+                }
+            };
 
-                    let app_for_stage_file = app_for_menu.clone();
-                    let stage_file = Rc::new(move |window: &mut Window, cx: &mut App| {
-                        app_for_stage_file.update(cx, |this, cx| {
-                            this.stage_current_file(window, cx);
-                            cx.notify();
-                        });
-                    });
+            let target_for_task = target.clone();
+            let result = window
+                .background_executor()
+                .spawn(async move { std::fs::write(&target_for_task, patch) })
+                .await;
 
-                    let app_for_unstage_file = app_for_menu.clone();
-                    let unstage_file = Rc::new(move |window: &mut Window, cx: &mut App| {
-                        app_for_unstage_file.update(cx, |this, cx| {
-                            this.unstage_current_file(window, cx);
-                            cx.notify();
-                        });
-                    });
+            window
+                .update(|window, cx| {
+                    let toast = match result {
+                        Ok(()) => Toast::success(format!("已导出 patch 到 {}", target.display())),
+                        Err(err) => Toast::error(format!("写入 {} 失败：{err}", target.display())),
+                    };
+                    window.push_toast(toast, cx);
+                })
+                .ok();
 
-                    let app_for_stage_hunk = app_for_menu.clone();
-                    let stage_hunk = Rc::new(move |window: &mut Window, cx: &mut App| {
-                        app_for_stage_hunk.update(cx, |this, cx| {
-                            this.stage_current_hunk(window, cx);
-                            cx.notify();
-                        });
-                    });
+            Some(())
+        })
+        .detach();
+    }
 
-                    let app_for_unstage_hunk = app_for_menu.clone();
-                    let unstage_hunk = Rc::new(move |window: &mut Window, cx: &mut App| {
-                        app_for_unstage_hunk.update(cx, |this, cx| {
-                            this.unstage_current_hunk(window, cx);
-                            cx.notify();
-                        });
-                    });
+    fn two_pane_visible(&self) -> bool {
+        self.screen == AppScreen::DiffView
+            && self.view_mode == DiffViewMode::Split
+            && self.split_layout == SplitLayout::TwoPane
+    }
 
-                    let app_for_revert_hunk = app_for_menu.clone();
-                    let revert_hunk = Rc::new(move |window: &mut Window, cx: &mut App| {
-                        app_for_revert_hunk.update(cx, |this, cx| {
-                            this.revert_current_hunk(window, cx);
-                            cx.notify();
-                        });
-                    });
+    fn set_pane_scroll_linked(&mut self, linked: bool) {
+        if self.pane_scroll_linked == linked {
+            return;
+        }
+        if !linked {
+            // 解除联动时右侧从当前位置开始独立滚动。
+            self.sync_pane_scroll();
+        }
+        self.pane_scroll_linked = linked;
+    }
 
-                    let can_toggle_split = !inline_mode;
+    /// 把右侧面板滚动到左侧顶部行的对应行（两侧行号一一对齐，偏移量相同即对应）。
+    fn sync_pane_scroll(&mut self) {
+        let Some(diff_view) = self.diff_view.as_ref() else {
+            return;
+        };
+        diff_view
+            .new_pane_scroll_handle
+            .set_offset(diff_view.scroll_handle.offset());
+    }
 
-                    div()
-                        .p(px(8.))
-                        .bg(theme.popover)
-                        .border_1()
-                        .border_color(theme.border)
-                        .rounded(theme.radius)
-                        .shadow_md()
-                        .flex()
-                        .flex_col()
-                        .gap(px(6.))
-                        .child(
-                            div()
-                                .px(px(4.))
-                                .py(px(2.))
-                                .text_xs()
-                                .text_color(theme.muted_foreground)
-                                .child("视图"),
-                        )
-                        .child(make_action(
-                            "diff-more-toggle-split",
-                            view_toggle_split_label,
-                            !can_toggle_split,
-                            toggle_split,
-                        ))
-                        .child(make_action(
-                            "diff-more-toggle-ws",
-                            view_toggle_ws_label,
-                            false,
-                            toggle_ws,
-                        ))
-                        .child(
-                            div()
-                                .flex()
-                                .flex_row()
-                                .items_center()
-                                .gap(px(8.))
-                                .px(px(6.))
-                                .child(div().text_sm().child(format!("上下文: {context_lines}")))
-                                .child(
-                                    Button::new("diff-more-context-dec")
-                                        .label("－")
-                                        .ghost()
-                                        .disabled(context_lines == 0)
-                                        .on_click({
-                                            let app = app_for_menu.clone();
-                                            move |_, window, cx| {
-                                                app.update(cx, |this, cx| {
-                                                    let next = this
-                                                        .diff_options
-                                                        .context_lines
-                                                        .saturating_sub(1);
-                                                    this.set_context_lines(next, window, cx);
-                                                    cx.notify();
-                                                });
-                                            }
-                                        }),
-                                )
-                                .child(
-                                    Button::new("diff-more-context-inc")
-                                        .label("＋")
-                                        .ghost()
-                                        .disabled(context_lines >= MAX_CONTEXT_LINES)
-                                        .on_click({
-                                            let app = app_for_menu.clone();
-                                            move |_, window, cx| {
-                                                app.update(cx, |this, cx| {
-                                                    let next = this.diff_options.context_lines + 1;
-                                                    this.set_context_lines(next, window, cx);
-                                                    cx.notify();
-                                                });
-                                            }
-                                        }),
-                                ),
-                        )
-                        .child(make_action(
-                            "diff-more-expand-all",
-                            "展开全部".into(),
-                            !can_expand_all,
-                            expand_all,
-                        ))
-                        .child(make_action(
-                            "diff-more-shortcuts",
-                            "快捷键…".into(),
-                            false,
-                            show_shortcuts,
-                        ))
-                        .child(div().h(px(1.)).bg(theme.border.alpha(0.4)))
-                        .child(
-                            div()
-                                .px(px(4.))
-                                .py(px(2.))
-                                .text_xs()
-                                .text_color(theme.muted_foreground)
-                                .child("Git"),
-                        )
-                        .child(make_action(
-                            "diff-more-stage-file",
-                            stage_file_label.clone(),
-                            !can_stage,
-                            stage_file,
-                        ))
-                        .child(make_action(
-                            "diff-more-unstage-file",
-                            unstage_file_label.clone(),
-                            !can_unstage,
-                            unstage_file,
-                        ))
-                        .child(make_action(
-                            "diff-more-stage-hunk",
-                            stage_hunk_label.clone(),
-                            !can_stage_hunk,
-                            stage_hunk,
-                        ))
-                        .child(make_action(
-                            "diff-more-unstage-hunk",
-                            unstage_hunk_label.clone(),
-                            !can_unstage_hunk,
-                            unstage_hunk,
-                        ))
-                        .child(make_action(
-                            "diff-more-revert-hunk",
-                            revert_hunk_label.clone(),
-                            !can_revert_hunk,
-                            revert_hunk,
-                        ))
-                })
+    fn jump_search_match(&mut self, direction: i32, cx: &mut Context<Self>) {
+        let query = self.diff_search_input.read(cx).value().to_string();
+        let options = self.diff_search_options;
+        let Some(diff_view) = self.diff_view.as_mut() else {
+            return;
         };
+        let rows = diff_view.search_rows(&query, options);
+        let Some(search) = diff_view.search.as_mut() else {
+            return;
+        };
+        if rows.is_empty() {
+            return;
+        }
 
-        let toolbar = div()
-            .flex()
-            .flex_col()
-            .gap(px(8.))
-            .p(px(12.))
-            .border_b_1()
-            .border_color(cx.theme().border)
-            .child(
+        let current = match (search.current, direction < 0) {
+            (None, true) => rows.len() - 1,
+            (None, false) => 0,
+            (Some(current), true) => current.checked_sub(1).unwrap_or(rows.len() - 1),
+            (Some(current), false) => (current + 1) % rows.len(),
+        };
+        search.current = Some(current);
+        diff_view.scroll_to_row(rows[current]);
+    }
+
+    fn toggle_diff_search_option(
+        &mut self,
+        toggle: impl FnOnce(&mut DiffSearchOptions),
+        cx: &mut Context<Self>,
+    ) {
+        toggle(&mut self.diff_search_options);
+        self.jump_search_match(1, cx);
+    }
+
+    /// 第 `segments` 这一侧代码中命中当前查找的字节区间，用于行内高亮。
+    fn search_highlights(&self, segments: &[diffview::DiffSegment]) -> Vec<Range<usize>> {
+        self.diff_view
+            .as_ref()
+            .map(|diff_view| diff_view.search_ranges(&segments_text(segments)))
+            .unwrap_or_default()
+    }
+
+    fn expand_fold(&mut self, row_index: usize) {
+        let Some(diff_view) = self.diff_view.as_mut() else {
+            return;
+        };
+
+        if diff_view.rows.expand_fold(row_index) {
+            diff_view.recalc_hunk_rows();
+        }
+    }
+
+    fn expand_all_folds(&mut self) {
+        let Some(diff_view) = self.diff_view.as_mut() else {
+            return;
+        };
+
+        if diff_view.rows.expand_all_folds() {
+            diff_view.recalc_hunk_rows();
+        }
+    }
+
+    fn resolve_conflict(
+        &mut self,
+        conflict_index: usize,
+        resolution: ConflictResolution,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(conflict_view) = self.conflict_view.as_mut() else {
+            return;
+        };
+        if conflict_index >= conflict_view.conflicts.len() {
+            return;
+        }
+
+        let block = conflict_view.conflicts[conflict_index].clone();
+        let replacement = match resolution {
+            ConflictResolution::Ours => block.ours,
+            ConflictResolution::Theirs => block.theirs,
+            ConflictResolution::Base => block.base.unwrap_or_default(),
+            ConflictResolution::Both => block.ours + &block.theirs,
+        };
+
+        match (&mut conflict_view.content, block.location) {
+            (ConflictContent::Merge(model), ConflictLocation::Chunk(index)) => {
+                model.resolve(index, replacement);
+            }
+            (ConflictContent::Markers(text), ConflictLocation::Markers(range)) => {
+                text.replace_range(range, &replacement);
+            }
+            _ => return,
+        }
+        conflict_view.rebuild();
+        let updated_text = conflict_view.text.clone();
+        let result_input = conflict_view.result_input.clone();
+        result_input.update(cx, move |state, cx| {
+            state.set_value(updated_text, window, cx);
+        });
+    }
+
+    fn jump_conflict(&mut self, direction: i32) {
+        let Some(conflict_view) = self.conflict_view.as_mut() else {
+            return;
+        };
+        if conflict_view.conflict_rows.is_empty() {
+            return;
+        }
+
+        let mut next_index = conflict_view.current_conflict;
+        if direction < 0 {
+            next_index = next_index.saturating_sub(1);
+        } else if direction > 0 {
+            next_index = (next_index + 1).min(conflict_view.conflict_rows.len().saturating_sub(1));
+        }
+
+        conflict_view.current_conflict = next_index;
+        let row_index = conflict_view.conflict_rows[next_index];
+        conflict_view
+            .scroll_handle
+            .scroll_to_item(row_index, ScrollStrategy::Top);
+    }
+
+    fn apply_conflict_editor(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(conflict_view) = self.conflict_view.as_mut() else {
+            return;
+        };
+
+        let edited_text = conflict_view.result_input.read(cx).value().to_string();
+        if edited_text == conflict_view.text {
+            window.push_toast(Toast::info("结果编辑器无改动"), cx);
+            return;
+        }
+
+        conflict_view.apply_edited_text(edited_text);
+
+        let remaining = conflict_view.conflicts.len();
+        window.push_toast(
+            Toast::success(if remaining == 0 {
+                "已应用编辑：冲突已清零".to_string()
+            } else {
+                format!("已应用编辑：仍有 {remaining} 处冲突")
+            }),
+            cx,
+        );
+        cx.notify();
+    }
+
+    fn save_conflict_to_working_tree(
+        &mut self,
+        add_to_index: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if add_to_index && !self.git_available {
+            window.push_toast(Toast::warning("未检测到 git 命令，无法执行 git add"), cx);
+            return;
+        }
+
+        let Some(conflict_view) = self.conflict_view.as_mut() else {
+            return;
+        };
+        let Some(path) = conflict_view.path.clone() else {
+            return;
+        };
+
+        let edited_text = conflict_view.result_input.read(cx).value().to_string();
+        if edited_text != conflict_view.text {
+            conflict_view.apply_edited_text(edited_text);
+        }
+
+        if !conflict_view.conflicts.is_empty() {
+            window.push_toast(Toast::warning("仍有冲突未解决，无法保存/标记已解决"), cx);
+            return;
+        }
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        let text = conflict_view.text.clone();
+        let encoding = conflict_view.encoding;
+        let path_for_task = path.clone();
+
+        window.push_toast(
+            Toast::info(if add_to_index {
+                format!("保存并标记已解决：{path}")
+            } else {
+                format!("保存到文件：{path}")
+            }),
+            cx,
+        );
+
+        cx.spawn_in(window, async move |_, window| {
+            let (write_result, add_result, status_result) = window
+                .background_executor()
+                .spawn(async move {
+                    let full_path = repo_root.join(&path_for_task);
+                    let write_result = encoding.encode(&text).and_then(|bytes| {
+                        std::fs::write(&full_path, bytes)
+                            .with_context(|| format!("写入文件失败：{path_for_task}"))
+                    });
+
+                    let add_result = if add_to_index && write_result.is_ok() {
+                        let output = Command::new("git")
+                            .arg("-C")
+                            .arg(&repo_root)
+                            .args(["add", "--"])
+                            .arg(&path_for_task)
+                            .output()
+                            .with_context(|| format!("执行 git add 失败：{path_for_task}"));
+
+                        match output {
+                            Ok(output) if output.status.success() => Ok(()),
+                            Ok(output) => Err(anyhow!(
+                                "git add 返回非零（{}）：{}",
+                                output.status.code().unwrap_or(-1),
+                                String::from_utf8_lossy(&output.stderr).trim()
+                            )),
+                            Err(err) => Err(err),
+                        }
+                    } else {
+                        Ok(())
+                    };
+
+                    let status_result = fetch_git_status(&repo_root);
+
+                    (write_result, add_result, status_result)
+                })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    if let Err(err) = write_result {
+                        window.push_toast(Toast::error(format!("保存失败：{err}")), cx);
+                        return;
+                    }
+                    if let Err(err) = add_result {
+                        window.push_toast(Toast::error(format!("git add 失败：{err}")), cx);
+                    } else if add_to_index {
+                        window.push_toast(Toast::success("已保存并标记为已解决（git add）"), cx);
+                    } else {
+                        window.push_toast(Toast::success("已保存到工作区文件"), cx);
+                    }
+
+                    if let Ok(entries) = status_result {
+                        this.update(cx, |this, _cx| {
+                            this.set_status_entries(entries);
+                        });
+                    }
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn stage_current_file(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(diff_view) = self.diff_view.as_ref() else {
+            return;
+        };
+        let Some(path) = diff_view.path.clone() else {
+            return;
+        };
+
+        let compare_target = diff_view.compare_target.clone();
+        if matches!(compare_target, CompareTarget::Refs { .. }) {
+            window.push_toast(
+                Toast::warning("自定义对比模式下不支持 Stage 文件，请先切换到内置对比目标"),
+                cx,
+            );
+            return;
+        }
+        let scroll_handle = diff_view.scroll_handle.clone();
+        let scroll_state = diff_view.scroll_state.clone();
+        let current_hunk = diff_view.current_hunk;
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        let path_for_task = path.clone();
+
+        window.push_toast(Toast::info(format!("git add {path}")), cx);
+
+        cx.spawn_in(window, async move |_, window| {
+            let diff_options = window
+                .update(|_, cx| this.read(cx).diff_options)
+                .unwrap_or_default();
+            let view_mode = window
+                .update(|_, cx| this.read(cx).view_mode)
+                .unwrap_or(DiffViewMode::Split);
+
+            let path_for_task_bg = path_for_task.clone();
+            let compare_target_for_io = compare_target.clone();
+            let (
+                add_ok,
+                add_err,
+                entries,
+                status_err,
+                updated_status,
+                old_text,
+                old_err,
+                new_text,
+                new_err,
+                lfs,
+                model,
+                old_lines,
+                new_lines,
+                diff_timing,
+            ) = window
+                .background_executor()
+                .spawn(async move {
+                    let add_result = run_git(repo_root.as_path(), ["add", "--", &path_for_task_bg]);
+                    let add_ok = add_result.is_ok();
+                    let add_err = add_result.err().map(|err| err.to_string());
+                    let (entries, status_err, updated_status) = match fetch_git_status(&repo_root) {
+                        Ok(entries) => {
+                            let updated_status = entries
+                                .iter()
+                                .find(|entry| entry.path == path_for_task_bg)
+                                .map(|entry| entry.status.clone())
+                                .unwrap_or_default();
+                            (Some(entries), None, updated_status)
+                        }
+                        Err(err) => (None, Some(err.to_string()), String::new()),
+                    };
+
+                    let (old_text, old_err) = match compare_target_for_io.clone() {
+                        CompareTarget::HeadToWorktree | CompareTarget::HeadToIndex => {
+                            match read_head_file(&repo_root, &path_for_task_bg, &updated_status) {
+                                Ok((text, _)) => (text, None),
+                                Err(err) => (String::new(), Some(err.to_string())),
+                            }
+                        }
+                        CompareTarget::IndexToWorktree => {
+                            match read_index_file(&repo_root, &path_for_task_bg, &updated_status) {
+                                Ok((text, _)) => (text, None),
+                                Err(err) => (String::new(), Some(err.to_string())),
+                            }
+                        }
+                        CompareTarget::Refs { .. } => {
+                            (String::new(), Some("不支持的对比目标".to_string()))
+                        }
+                    };
+
+                    let (new_text, new_err) = match compare_target_for_io {
+                        CompareTarget::HeadToWorktree | CompareTarget::IndexToWorktree => {
+                            match read_working_file(&repo_root, &path_for_task_bg) {
+                                Ok(text) => (text, None),
+                                Err(err) => (String::new(), Some(err.to_string())),
+                            }
+                        }
+                        CompareTarget::HeadToIndex => {
+                            match read_index_file(&repo_root, &path_for_task_bg, &updated_status) {
+                                Ok((text, _)) => (text, None),
+                                Err(err) => (String::new(), Some(err.to_string())),
+                            }
+                        }
+                        CompareTarget::Refs { .. } => {
+                            (String::new(), Some("不支持的对比目标".to_string()))
+                        }
+                    };
+
+                    let (old_text, new_text, lfs) = detect_lfs_diff(old_text, new_text);
+                    let (model, old_lines, new_lines, diff_timing) =
+                        build_diff_model(&old_text, &new_text, diff_options);
+
+                    (
+                        add_ok,
+                        add_err,
+                        entries,
+                        status_err,
+                        updated_status,
+                        old_text,
+                        old_err,
+                        new_text,
+                        new_err,
+                        lfs,
+                        model,
+                        old_lines,
+                        new_lines,
+                        diff_timing,
+                    )
+                })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    if let Some(err) = add_err {
+                        window.push_toast(Toast::error(format!("git add 失败：{err}")), cx);
+                    } else {
+                        window.push_toast(Toast::success("git add 成功"), cx);
+                    }
+
+                    if let Some(err) = status_err {
+                        window.push_toast(Toast::error(format!("刷新 git 状态失败：{err}")), cx);
+                    }
+
+                    if let Some(err) = old_err {
+                        window.push_toast(
+                            Toast::error(format!(
+                                "读取 {} 版本失败，按空内容处理：{err}",
+                                compare_target_side_label(&compare_target, Side::Old)
+                            )),
+                            cx,
+                        );
+                    }
+                    if let Some(err) = new_err {
+                        window.push_toast(
+                            Toast::error(format!(
+                                "读取 {} 版本失败，按空内容处理：{err}",
+                                compare_target_side_label(&compare_target, Side::New)
+                            )),
+                            cx,
+                        );
+                    }
+
+                    this.update(cx, |this, cx| {
+                        if let Some(entries) = entries {
+                            this.set_status_entries(entries);
+                        }
+
+                        if add_ok {
+                            let title: SharedString = if updated_status.is_empty() {
+                                path_for_task.clone().into()
+                            } else {
+                                format!("{updated_status} {path_for_task}").into()
+                            };
+
+                            if let Some(diff_view) = this.diff_view.as_ref() {
+                                if diff_view.path.as_deref() == Some(path_for_task.as_str()) {
+                                    let mut next = DiffViewState::from_precomputed(
+                                        title,
+                                        Some(path_for_task.clone()),
+                                        (!updated_status.is_empty())
+                                            .then(|| updated_status.clone()),
+                                        compare_target,
+                                        old_text,
+                                        new_text,
+                                        view_mode,
+                                        model,
+                                        old_lines,
+                                        new_lines,
+                                        diff_timing,
+                                    );
+                                    next.scroll_handle = scroll_handle.clone();
+                                    next.lfs = lfs;
+                                    next.encoding = diff_view.encoding;
+                                    next.scroll_state = scroll_state.clone();
+                                    next.current_hunk =
+                                        current_hunk.min(next.hunk_rows.len().saturating_sub(1));
+                                    this.diff_content_revision =
+                                        this.diff_content_revision.wrapping_add(1);
+                                    this.diff_view = Some(next);
+                                }
+                            }
+                        }
+
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn unstage_current_file(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(diff_view) = self.diff_view.as_ref() else {
+            return;
+        };
+        let Some(path) = diff_view.path.clone() else {
+            return;
+        };
+
+        let compare_target = diff_view.compare_target.clone();
+        if matches!(compare_target, CompareTarget::Refs { .. }) {
+            window.push_toast(
+                Toast::warning("自定义对比模式下不支持 Unstage 文件，请先切换到内置对比目标"),
+                cx,
+            );
+            return;
+        }
+        let scroll_handle = diff_view.scroll_handle.clone();
+        let scroll_state = diff_view.scroll_state.clone();
+        let current_hunk = diff_view.current_hunk;
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        let path_for_task = path.clone();
+
+        window.push_toast(Toast::info(format!("git reset HEAD -- {path}")), cx);
+
+        cx.spawn_in(window, async move |_, window| {
+            let diff_options = window
+                .update(|_, cx| this.read(cx).diff_options)
+                .unwrap_or_default();
+            let view_mode = window
+                .update(|_, cx| this.read(cx).view_mode)
+                .unwrap_or(DiffViewMode::Split);
+
+            let path_for_task_bg = path_for_task.clone();
+            let compare_target_for_io = compare_target.clone();
+            let (
+                reset_ok,
+                reset_err,
+                entries,
+                status_err,
+                updated_status,
+                old_text,
+                old_err,
+                new_text,
+                new_err,
+                lfs,
+                model,
+                old_lines,
+                new_lines,
+                diff_timing,
+            ) = window
+                .background_executor()
+                .spawn(async move {
+                    let reset_result = run_git(
+                        repo_root.as_path(),
+                        ["reset", "HEAD", "--", &path_for_task_bg],
+                    );
+                    let reset_ok = reset_result.is_ok();
+                    let reset_err = reset_result.err().map(|err| err.to_string());
+                    let (entries, status_err, updated_status) = match fetch_git_status(&repo_root) {
+                        Ok(entries) => {
+                            let updated_status = entries
+                                .iter()
+                                .find(|entry| entry.path == path_for_task_bg)
+                                .map(|entry| entry.status.clone())
+                                .unwrap_or_default();
+                            (Some(entries), None, updated_status)
+                        }
+                        Err(err) => (None, Some(err.to_string()), String::new()),
+                    };
+
+                    let (old_text, old_err) = match compare_target_for_io.clone() {
+                        CompareTarget::HeadToWorktree | CompareTarget::HeadToIndex => {
+                            match read_head_file(&repo_root, &path_for_task_bg, &updated_status) {
+                                Ok((text, _)) => (text, None),
+                                Err(err) => (String::new(), Some(err.to_string())),
+                            }
+                        }
+                        CompareTarget::IndexToWorktree => {
+                            match read_index_file(&repo_root, &path_for_task_bg, &updated_status) {
+                                Ok((text, _)) => (text, None),
+                                Err(err) => (String::new(), Some(err.to_string())),
+                            }
+                        }
+                        CompareTarget::Refs { .. } => {
+                            (String::new(), Some("不支持的对比目标".to_string()))
+                        }
+                    };
+
+                    let (new_text, new_err) = match compare_target_for_io {
+                        CompareTarget::HeadToWorktree | CompareTarget::IndexToWorktree => {
+                            match read_working_file(&repo_root, &path_for_task_bg) {
+                                Ok(text) => (text, None),
+                                Err(err) => (String::new(), Some(err.to_string())),
+                            }
+                        }
+                        CompareTarget::HeadToIndex => {
+                            match read_index_file(&repo_root, &path_for_task_bg, &updated_status) {
+                                Ok((text, _)) => (text, None),
+                                Err(err) => (String::new(), Some(err.to_string())),
+                            }
+                        }
+                        CompareTarget::Refs { .. } => {
+                            (String::new(), Some("不支持的对比目标".to_string()))
+                        }
+                    };
+
+                    let (old_text, new_text, lfs) = detect_lfs_diff(old_text, new_text);
+                    let (model, old_lines, new_lines, diff_timing) =
+                        build_diff_model(&old_text, &new_text, diff_options);
+
+                    (
+                        reset_ok,
+                        reset_err,
+                        entries,
+                        status_err,
+                        updated_status,
+                        old_text,
+                        old_err,
+                        new_text,
+                        new_err,
+                        lfs,
+                        model,
+                        old_lines,
+                        new_lines,
+                        diff_timing,
+                    )
+                })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    if let Some(err) = reset_err {
+                        window.push_toast(Toast::error(format!("unstage 失败：{err}")), cx);
+                    } else {
+                        window.push_toast(Toast::success("unstage 成功"), cx);
+                    }
+
+                    if let Some(err) = status_err {
+                        window.push_toast(Toast::error(format!("刷新 git 状态失败：{err}")), cx);
+                    }
+
+                    if let Some(err) = old_err {
+                        window.push_toast(
+                            Toast::error(format!(
+                                "读取 {} 版本失败，按空内容处理：{err}",
+                                compare_target_side_label(&compare_target, Side::Old)
+                            )),
+                            cx,
+                        );
+                    }
+                    if let Some(err) = new_err {
+                        window.push_toast(
+                            Toast::error(format!(
+                                "读取 {} 版本失败，按空内容处理：{err}",
+                                compare_target_side_label(&compare_target, Side::New)
+                            )),
+                            cx,
+                        );
+                    }
+
+                    this.update(cx, |this, cx| {
+                        if let Some(entries) = entries {
+                            this.set_status_entries(entries);
+                        }
+
+                        if reset_ok {
+                            let title: SharedString = if updated_status.is_empty() {
+                                path_for_task.clone().into()
+                            } else {
+                                format!("{updated_status} {path_for_task}").into()
+                            };
+
+                            if let Some(diff_view) = this.diff_view.as_ref() {
+                                if diff_view.path.as_deref() == Some(path_for_task.as_str()) {
+                                    let mut next = DiffViewState::from_precomputed(
+                                        title,
+                                        Some(path_for_task.clone()),
+                                        (!updated_status.is_empty())
+                                            .then(|| updated_status.clone()),
+                                        compare_target,
+                                        old_text,
+                                        new_text,
+                                        view_mode,
+                                        model,
+                                        old_lines,
+                                        new_lines,
+                                        diff_timing,
+                                    );
+                                    next.scroll_handle = scroll_handle.clone();
+                                    next.lfs = lfs;
+                                    next.encoding = diff_view.encoding;
+                                    next.scroll_state = scroll_state.clone();
+                                    next.current_hunk =
+                                        current_hunk.min(next.hunk_rows.len().saturating_sub(1));
+                                    this.diff_content_revision =
+                                        this.diff_content_revision.wrapping_add(1);
+                                    this.diff_view = Some(next);
+                                }
+                            }
+                        }
+
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn stage_current_hunk(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.apply_current_hunk(HunkApplyAction::Stage, window, cx);
+    }
+
+    fn unstage_current_hunk(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.apply_current_hunk(HunkApplyAction::Unstage, window, cx);
+    }
+
+    fn revert_current_hunk(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.apply_current_hunk(HunkApplyAction::Revert, window, cx);
+    }
+
+    /// 当前 diff 中可以直接作用于单个 hunk 的操作，用于 hunk 头上的按钮。
+    fn hunk_header_actions(&self) -> &'static [HunkApplyAction] {
+        let Some(diff_view) = self.diff_view.as_ref() else {
+            return &[];
+        };
+        let file_status = diff_view.status.as_deref().unwrap_or_default();
+        if !self.git_available
+            || diff_view.path.is_none()
+            || diff_view.lfs.is_some()
+            || is_untracked_status(file_status)
+        {
+            return &[];
+        }
+        match diff_view.compare_target {
+            CompareTarget::IndexToWorktree => &[HunkApplyAction::Stage, HunkApplyAction::Revert],
+            CompareTarget::HeadToIndex => &[HunkApplyAction::Unstage],
+            _ => &[],
+        }
+    }
+
+    fn apply_hunk_at(
+        &mut self,
+        hunk_index: usize,
+        action: HunkApplyAction,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(diff_view) = self.diff_view.as_mut() else {
+            return;
+        };
+        if hunk_index >= diff_view.diff_model.hunks.len() {
+            return;
+        }
+        diff_view.current_hunk = hunk_index;
+        self.apply_current_hunk(action, window, cx);
+    }
+
+    fn apply_current_hunk(
+        &mut self,
+        action: HunkApplyAction,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(diff_view) = self.diff_view.as_ref() else {
+            return;
+        };
+
+        if diff_view.lfs.is_some() {
+            window.push_toast(
+                Toast::warning("Git LFS 文件不支持按 hunk 操作，请使用 Stage/Unstage 文件"),
+                cx,
+            );
+            return;
+        }
+
+        let required_target = action.required_compare_target();
+        if diff_view.compare_target != required_target {
+            window.push_toast(
+                Toast::info(format!(
+                    "当前对比为 {}，请切换到 {} 再执行 {}",
+                    compare_target_label(&diff_view.compare_target),
+                    compare_target_label(&required_target),
+                    action.label()
+                )),
+                cx,
+            );
+            return;
+        }
+
+        let Some(path) = diff_view.path.clone() else {
+            return;
+        };
+
+        let hunk_count = diff_view.diff_model.hunks.len();
+        if hunk_count == 0 {
+            return;
+        }
+        let hunk_index = diff_view.current_hunk.min(hunk_count.saturating_sub(1));
+        let patch = unified_patch_for_hunk(&path, &diff_view.diff_model.hunks[hunk_index]);
+
+        let compare_target = diff_view.compare_target.clone();
+        let scroll_handle = diff_view.scroll_handle.clone();
+        let scroll_state = diff_view.scroll_state.clone();
+        let current_hunk = diff_view.current_hunk;
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        let path_for_task = path.clone();
+
+        window.push_toast(
+            Toast::info(format!(
+                "{} hunk {}/{}: {path_for_task}",
+                action.label(),
+                hunk_index + 1,
+                hunk_count
+            )),
+            cx,
+        );
+
+        cx.spawn_in(window, async move |_, window| {
+            let diff_options = window
+                .update(|_, cx| this.read(cx).diff_options)
+                .unwrap_or_default();
+            let view_mode = window
+                .update(|_, cx| this.read(cx).view_mode)
+                .unwrap_or(DiffViewMode::Split);
+
+            let path_for_task_bg = path_for_task.clone();
+            let compare_target_for_io = compare_target.clone();
+            let (
+                apply_result,
+                backup,
+                entries,
+                status_err,
+                updated_status,
+                old_text,
+                old_err,
+                new_text,
+                new_err,
+                model,
+                old_lines,
+                new_lines,
+                diff_timing,
+            ) = window
+                .background_executor()
+                .spawn(async move {
+                    // Revert 会丢弃工作区改动，先把当前内容备份到仓库的回收站目录。
+                    let (apply_result, backup) = if action == HunkApplyAction::Revert {
+                        match backup_worktree_file(&repo_root, &path_for_task_bg) {
+                            Ok(backup) => (
+                                run_git_with_stdin(repo_root.as_path(), action.git_args(), &patch),
+                                backup,
+                            ),
+                            Err(err) => (Err(err.context("回滚前备份文件失败，已取消")), None),
+                        }
+                    } else {
+                        (
+                            run_git_with_stdin(repo_root.as_path(), action.git_args(), &patch),
+                            None,
+                        )
+                    };
+                    let (entries, status_err, updated_status) = match fetch_git_status(&repo_root) {
+                        Ok(entries) => {
+                            let updated_status = entries
+                                .iter()
+                                .find(|entry| entry.path == path_for_task_bg)
+                                .map(|entry| entry.status.clone())
+                                .unwrap_or_default();
+                            (Some(entries), None, updated_status)
+                        }
+                        Err(err) => (None, Some(err.to_string()), String::new()),
+                    };
+
+                    let (old_text, old_err) = match compare_target_for_io.clone() {
+                        CompareTarget::HeadToWorktree | CompareTarget::HeadToIndex => {
+                            match read_head_file(&repo_root, &path_for_task_bg, &updated_status) {
+                                Ok((text, _)) => (text, None),
+                                Err(err) => (String::new(), Some(err.to_string())),
+                            }
+                        }
+                        CompareTarget::IndexToWorktree => {
+                            match read_index_file(&repo_root, &path_for_task_bg, &updated_status) {
+                                Ok((text, _)) => (text, None),
+                                Err(err) => (String::new(), Some(err.to_string())),
+                            }
+                        }
+                        CompareTarget::Refs { .. } => {
+                            (String::new(), Some("不支持的对比目标".to_string()))
+                        }
+                    };
+
+                    let (new_text, new_err) = match compare_target_for_io {
+                        CompareTarget::HeadToWorktree | CompareTarget::IndexToWorktree => {
+                            match read_working_file(&repo_root, &path_for_task_bg) {
+                                Ok(text) => (text, None),
+                                Err(err) => (String::new(), Some(err.to_string())),
+                            }
+                        }
+                        CompareTarget::HeadToIndex => {
+                            match read_index_file(&repo_root, &path_for_task_bg, &updated_status) {
+                                Ok((text, _)) => (text, None),
+                                Err(err) => (String::new(), Some(err.to_string())),
+                            }
+                        }
+                        CompareTarget::Refs { .. } => {
+                            (String::new(), Some("不支持的对比目标".to_string()))
+                        }
+                    };
+
+                    let (model, old_lines, new_lines, diff_timing) =
+                        build_diff_model(&old_text, &new_text, diff_options);
+
+                    (
+                        apply_result,
+                        backup,
+                        entries,
+                        status_err,
+                        updated_status,
+                        old_text,
+                        old_err,
+                        new_text,
+                        new_err,
+                        model,
+                        old_lines,
+                        new_lines,
+                        diff_timing,
+                    )
+                })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    if let Err(err) = apply_result {
+                        window.push_toast(
+                            Toast::error(format!("{} 失败：{err}", action.label())),
+                            cx,
+                        );
+                        return;
+                    }
+
+                    let message = match &backup {
+                        Some(backup) => format!(
+                            "{} 成功，原内容已备份到 {}（可通过“撤销上次回滚”恢复）",
+                            action.label(),
+                            backup.backup.display()
+                        ),
+                        None => format!("{} 成功", action.label()),
+                    };
+                    window.push_toast(Toast::success(message), cx);
+
+                    if let Some(err) = status_err {
+                        window.push_toast(Toast::error(format!("刷新 git 状态失败：{err}")), cx);
+                    }
+
+                    if let Some(err) = old_err {
+                        window.push_toast(
+                            Toast::error(format!(
+                                "读取 {} 版本失败，按空内容处理：{err}",
+                                compare_target_side_label(&compare_target, Side::Old)
+                            )),
+                            cx,
+                        );
+                    }
+                    if let Some(err) = new_err {
+                        window.push_toast(
+                            Toast::error(format!(
+                                "读取 {} 版本失败，按空内容处理：{err}",
+                                compare_target_side_label(&compare_target, Side::New)
+                            )),
+                            cx,
+                        );
+                    }
+
+                    this.update(cx, |this, cx| {
+                        if let Some(entries) = entries {
+                            this.set_status_entries(entries);
+                        }
+                        if backup.is_some() {
+                            this.last_rollback = backup;
+                        }
+
+                        let title: SharedString = if updated_status.is_empty() {
+                            path_for_task.clone().into()
+                        } else {
+                            format!("{updated_status} {path_for_task}").into()
+                        };
+
+                        if let Some(diff_view) = this.diff_view.as_ref() {
+                            if diff_view.path.as_deref() == Some(path_for_task.as_str()) {
+                                let mut next = DiffViewState::from_precomputed(
+                                    title,
+                                    Some(path_for_task.clone()),
+                                    (!updated_status.is_empty()).then(|| updated_status.clone()),
+                                    compare_target,
+                                    old_text,
+                                    new_text,
+                                    view_mode,
+                                    model,
+                                    old_lines,
+                                    new_lines,
+                                    diff_timing,
+                                );
+                                next.scroll_handle = scroll_handle.clone();
+                                next.encoding = diff_view.encoding;
+                                next.scroll_state = scroll_state.clone();
+                                next.current_hunk =
+                                    current_hunk.min(next.hunk_rows.len().saturating_sub(1));
+                                this.diff_content_revision =
+                                    this.diff_content_revision.wrapping_add(1);
+                                this.diff_view = Some(next);
+                            }
+                        }
+
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn undo_last_rollback(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(backup) = self.last_rollback.clone() else {
+            window.push_toast(Toast::info("没有可撤销的回滚"), cx);
+            return;
+        };
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+
+        cx.spawn_in(window, async move |_, window| {
+            let backup_for_io = backup.clone();
+            let (restore_result, entries) = window
+                .background_executor()
+                .spawn(async move {
+                    let target = repo_root.join(backup_for_io.path.trim_end_matches('/'));
+                    // 删除未跟踪目录时备份的是整个目录，直接移回原处。
+                    let restore_result = if backup_for_io.backup.is_dir() {
+                        std::fs::rename(&backup_for_io.backup, &target)
+                    } else {
+                        std::fs::copy(&backup_for_io.backup, &target).map(|_| ())
+                    }
+                    .with_context(|| format!("恢复文件失败：{}", backup_for_io.path));
+                    (restore_result, fetch_git_status(&repo_root).ok())
+                })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    if let Err(err) = restore_result {
+                        window.push_toast(Toast::error(format!("{err:#}")), cx);
+                        return;
+                    }
+
+                    window.push_toast(Toast::success(format!("已撤销回滚：{}", backup.path)), cx);
+
+                    this.update(cx, |this, cx| {
+                        this.last_rollback = None;
+                        if let Some(entries) = entries {
+                            this.set_status_entries(entries);
+                        }
+
+                        // 当前正在查看该文件时重新读取，让 diff 反映恢复后的内容。
+                        let reopen = this
+                            .diff_view
+                            .as_ref()
+                            .filter(|diff_view| {
+                                diff_view.path.as_deref() == Some(backup.path.as_str())
+                            })
+                            .map(|diff_view| diff_view.compare_target.clone());
+                        if let Some(compare_target) = reopen {
+                            let status = this
+                                .files
+                                .iter()
+                                .find(|entry| entry.path == backup.path)
+                                .map(|entry| entry.status.clone())
+                                .unwrap_or_default();
+                            this.open_file_diff_with_target(
+                                backup.path.clone(),
+                                status,
+                                compare_target,
+                                window,
+                                cx,
+                            );
+                        }
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    /// 把当前 diff 对应的工作区文件统一改为 CRLF（`crlf`）或 LF，改写前先备份以便撤销。
+    fn normalize_line_endings(&mut self, crlf: bool, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(diff_view) = self.diff_view.as_ref() else {
+            return;
+        };
+        let Some(path) = diff_view.path.clone() else {
+            return;
+        };
+        let status = diff_view.status.clone().unwrap_or_default();
+        let compare_target = diff_view.compare_target.clone();
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+
+        cx.spawn_in(window, async move |_, window| {
+            let path_for_io = path.clone();
+            let result = window
+                .background_executor()
+                .spawn(async move { rewrite_line_endings(&repo_root, &path_for_io, crlf) })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    let backup = match result {
+                        Ok(backup) => backup,
+                        Err(err) => {
+                            window.push_toast(Toast::error(format!("统一换行符失败：{err:#}")), cx);
+                            return;
+                        }
+                    };
+                    window.push_toast(
+                        Toast::success(format!(
+                            "已将 {path} 的换行符统一为 {}（可通过“撤销上次回滚”恢复）",
+                            eol_label(crlf)
+                        )),
+                        cx,
+                    );
+
+                    this.update(cx, |this, cx| {
+                        if backup.is_some() {
+                            this.last_rollback = backup;
+                        }
+                        this.open_file_diff_with_target(path, status, compare_target, window, cx);
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn load_lfs_content(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(diff_view) = self.diff_view.as_ref() else {
+            return;
+        };
+        let Some(path) = diff_view.path.clone() else {
+            return;
+        };
+        let Some(lfs) = diff_view.lfs.clone() else {
+            return;
+        };
+        if lfs.content_loaded {
+            return;
+        }
+        if !self.git_available {
+            window.push_toast(Toast::warning("未检测到 git 命令，无法获取 LFS 内容"), cx);
+            return;
+        }
+
+        let title = diff_view.title.clone();
+        let status = diff_view.status.clone();
+        let compare_target = diff_view.compare_target.clone();
+        let content_revision = self.diff_content_revision;
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+
+        window.push_toast(Toast::info(format!("正在获取 LFS 内容：{path}")), cx);
+
+        cx.spawn_in(window, async move |_, window| {
+            let path_for_task = path.clone();
+            let lfs_for_task = lfs.clone();
+            let loaded = window
+                .background_executor()
+                .spawn(async move {
+                    let old_text = match lfs_for_task.old.as_ref() {
+                        Some(pointer) => lfs_smudge(&repo_root, &path_for_task, pointer)?,
+                        None => lfs_for_task.old_source.clone(),
+                    };
+                    let new_text = match lfs_for_task.new.as_ref() {
+                        Some(pointer) => lfs_smudge(&repo_root, &path_for_task, pointer)?,
+                        None => lfs_for_task.new_source.clone(),
+                    };
+                    anyhow::Ok((old_text, new_text))
+                })
+                .await;
+
+            let (old_text, new_text) = match loaded {
+                Ok(texts) => texts,
+                Err(err) => {
+                    window
+                        .update(|window, cx| {
+                            window.push_toast(
+                                Toast::error(format!("获取 LFS 内容失败：{err:#}")),
+                                cx,
+                            );
+                        })
+                        .ok();
+                    return Some(());
+                }
+            };
+
+            let diff_options = window
+                .update(|_, cx| this.read(cx).diff_options)
+                .unwrap_or_default();
+            let view_mode = window
+                .update(|_, cx| this.read(cx).view_mode)
+                .unwrap_or(DiffViewMode::Split);
+
+            let (old_text, new_text, model, old_lines, new_lines, diff_timing) = window
+                .background_executor()
+                .spawn(async move {
+                    let (model, old_lines, new_lines, diff_timing) =
+                        build_diff_model(&old_text, &new_text, diff_options);
+                    (old_text, new_text, model, old_lines, new_lines, diff_timing)
+                })
+                .await;
+
+            window
+                .update(|_window, cx| {
+                    this.update(cx, |this, cx| {
+                        if this.diff_content_revision != content_revision {
+                            return;
+                        }
+                        let still_same = this.diff_view.as_ref().is_some_and(|view| {
+                            view.path.as_deref() == Some(path.as_str())
+                                && view.compare_target == compare_target
+                        });
+                        if !still_same {
+                            return;
+                        }
+
+                        let mut next = DiffViewState::from_precomputed(
+                            title,
+                            Some(path.clone()),
+                            status,
+                            compare_target,
+                            old_text,
+                            new_text,
+                            view_mode,
+                            model,
+                            old_lines,
+                            new_lines,
+                            diff_timing,
+                        );
+                        next.lfs = Some(LfsDiffInfo {
+                            content_loaded: true,
+                            ..lfs
+                        });
+                        this.diff_content_revision = this.diff_content_revision.wrapping_add(1);
+                        this.diff_view = Some(next);
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn ignore_untracked_path(
+        &mut self,
+        path: String,
+        scope: IgnoreScope,
+        target: IgnoreFile,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if !self.git_available {
+            return;
+        }
+        let Some(pattern) = gitignore_pattern(&path, scope) else {
+            window.push_toast(
+                Toast::warning(format!("{}：{path} 不适用", scope.label())),
+                cx,
+            );
+            return;
+        };
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        cx.spawn_in(window, async move |_, window| {
+            let pattern_for_task = pattern.clone();
+            let (result, entries) = window
+                .background_executor()
+                .spawn(async move {
+                    let result = append_ignore_pattern(&repo_root, target, &pattern_for_task);
+                    let entries = fetch_git_status(&repo_root);
+                    (result, entries)
+                })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    let toast = match result {
+                        Ok(true) => {
+                            Toast::success(format!("已添加 {pattern} 到 {}", target.label()))
+                        }
+                        Ok(false) => Toast::info(format!("{} 中已存在 {pattern}", target.label())),
+                        Err(err) => Toast::error(format!("写入 {} 失败：{err:#}", target.label())),
+                    };
+                    window.push_toast(toast, cx);
+
+                    this.update(cx, |this, cx| {
+                        if let Ok(entries) = entries {
+                            this.set_status_entries(entries);
+                        }
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn open_patch_from_file(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.git_available {
+            return;
+        }
+
+        let receiver = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("选择 patch 文件".into()),
+        });
+        let this = cx.entity();
+        cx.spawn_in(window, async move |_, window| {
+            let path = match receiver.await.ok()? {
+                Ok(paths) => paths?.into_iter().next()?,
+                Err(err) => {
+                    window
+                        .update(|window, cx| {
+                            window.push_toast(
+                                Toast::error(format!("打开文件对话框失败：{err:#}")),
+                                cx,
+                            );
+                        })
+                        .ok();
+                    return None;
+                }
+            };
+
+            let path_for_task = path.clone();
+            let result = window
+                .background_executor()
+                .spawn(async move { std::fs::read_to_string(&path_for_task) })
+                .await;
+
+            window
+                .update(|window, cx| match result {
+                    Ok(patch) => {
+                        this.update(cx, |this, cx| {
+                            this.load_patch_preview(
+                                path.display().to_string().into(),
+                                patch,
+                                window,
+                                cx,
+                            );
+                        });
+                    }
+                    Err(err) => {
+                        window.push_toast(
+                            Toast::error(format!("读取 {} 失败：{err}", path.display())),
+                            cx,
+                        );
+                    }
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn open_patch_from_clipboard(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.git_available {
+            return;
+        }
+
+        let Some(patch) = cx.read_from_clipboard().and_then(|item| item.text()) else {
+            window.push_toast(Toast::info("剪贴板中没有文本"), cx);
+            return;
+        };
+        self.load_patch_preview("剪贴板".into(), patch, window, cx);
+    }
+
+    fn load_patch_preview(
+        &mut self,
+        source: SharedString,
+        patch: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        cx.spawn_in(window, async move |_, window| {
+            let (patch, files) = window
+                .background_executor()
+                .spawn(async move {
+                    let files = build_patch_previews(&repo_root, &patch);
+                    (patch, files)
+                })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    if files.is_empty() {
+                        window.push_toast(
+                            Toast::info(format!("{source} 中没有识别到 patch 内容")),
+                            cx,
+                        );
+                        return;
+                    }
+
+                    this.update(cx, |this, cx| {
+                        this.patch_apply_overlay = Some(PatchApplyOverlayState {
+                            source,
+                            patch,
+                            files,
+                            applying: false,
+                            outcome: None,
+                            hidden: false,
+                        });
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn close_patch_apply_overlay(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.patch_apply_overlay = None;
+        window.focus(&self.focus_handle);
+        cx.notify();
+    }
+
+    fn preview_patch_file(&mut self, index: usize) {
+        let Some(overlay) = self.patch_apply_overlay.as_mut() else {
+            return;
+        };
+        let Some(file) = overlay.files.get(index) else {
+            return;
+        };
+
+        let title: SharedString = format!("Patch 预览：{}", file.path).into();
+        let (old_text, new_text) = (file.old_text.clone(), file.new_text.clone());
+        overlay.hidden = true;
+        self.open_diff_view(
+            title,
+            None,
+            None,
+            CompareTarget::IndexToWorktree,
+            old_text,
+            new_text,
+        );
+    }
+
+    fn apply_patch(
+        &mut self,
+        target: PatchApplyTarget,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(overlay) = self.patch_apply_overlay.as_mut() else {
+            return;
+        };
+        if overlay.applying || !self.git_available {
+            return;
+        }
+        overlay.applying = true;
+        overlay.outcome = None;
+        let patch = overlay.patch.clone();
+        cx.notify();
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        cx.spawn_in(window, async move |_, window| {
+            let (result, entries) = window
+                .background_executor()
+                .spawn(async move {
+                    let result = run_git_apply_patch(&repo_root, target, &patch);
+                    let entries = fetch_git_status(&repo_root);
+                    (result, entries)
+                })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    let outcome = match result {
+                        Ok(outcome) => {
+                            let toast = if outcome.success {
+                                Toast::success(format!("已将 patch 应用到{}", target.label()))
+                            } else if outcome.rejected.is_empty() {
+                                Toast::error(format!("git apply 失败，{}未修改", target.label()))
+                            } else {
+                                let count: usize =
+                                    outcome.rejected.iter().map(|(_, hunks)| hunks.len()).sum();
+                                Toast::warning(format!(
+                                    "已应用到{}，{count} 个 hunk 被拒绝",
+                                    target.label()
+                                ))
+                            };
+                            window.push_toast(toast, cx);
+                            Some(outcome)
+                        }
+                        Err(err) => {
+                            window.push_toast(Toast::error(format!("git apply 失败：{err:#}")), cx);
+                            None
+                        }
+                    };
+
+                    this.update(cx, |this, cx| {
+                        if let Some(overlay) = this.patch_apply_overlay.as_mut() {
+                            overlay.applying = false;
+                            overlay.outcome = outcome;
+                        }
+                        if let Ok(entries) = entries {
+                            this.set_status_entries(entries);
+                        }
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn commit_staged_changes(
+        &mut self,
+        no_verify: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if !self.git_available {
+            window.push_toast(Toast::warning("未检测到 git 命令，无法提交"), cx);
+            return;
+        }
+        if self.committing {
+            return;
+        }
+
+        let message = self.commit_message_input.read(cx).value().to_string();
+        if message.trim().is_empty() {
+            window.push_toast(Toast::warning("提交信息不能为空"), cx);
+            return;
+        }
+
+        let amend = self.amend.is_some();
+        self.committing = true;
+        self.commit_failure_overlay = None;
+        cx.notify();
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        window.push_toast(
+            Toast::info(format!(
+                "git commit{}{} …",
+                if amend { " --amend" } else { "" },
+                if no_verify { " --no-verify" } else { "" }
+            )),
+            cx,
+        );
+
+        cx.spawn_in(window, async move |_, window| {
+            let (commit_result, status_result) = window
+                .background_executor()
+                .spawn(async move {
+                    let commit_result = run_git_commit(&repo_root, &message, no_verify, amend);
+                    let status_result = fetch_git_status(&repo_root);
+                    (commit_result, status_result)
+                })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    let mut failure = None;
+                    let mut committed = false;
+                    match commit_result {
+                        Ok(CommitOutcome::Committed { summary }) => {
+                            committed = true;
+                            window.push_toast(
+                                Toast::success(if amend {
+                                    format!("已修改上一次提交：{summary}")
+                                } else {
+                                    format!("提交成功：{summary}")
+                                }),
+                                cx,
+                            );
+                            let input = this.read(cx).commit_message_input.clone();
+                            input.update(cx, |state, cx| {
+                                state.set_value(String::new(), window, cx);
+                            });
+                        }
+                        Ok(CommitOutcome::Rejected(rejected)) => {
+                            failure = Some(rejected);
+                        }
+                        Err(err) => {
+                            window
+                                .push_toast(Toast::error(format!("git commit 失败：{err:#}")), cx);
+                        }
+                    }
+
+                    this.update(cx, |this, cx| {
+                        this.committing = false;
+                        if committed {
+                            this.amend = None;
+                        }
+                        if let Ok(entries) = status_result {
+                            this.set_status_entries(entries);
+                        }
+                        this.commit_failure_overlay =
+                            failure.map(|failure| CommitFailureOverlayState { failure, no_verify });
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    /// 首次显示提交框时在后台读取 trailer 候选项（当前用户、最近协作者、分支对应的 issue）。
+    fn request_trailer_suggestions(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.git_available
+            || self.trailer_suggestions.is_some()
+            || self.trailer_suggestions_loading
+        {
+            return;
+        }
+        self.trailer_suggestions_loading = true;
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+
+        cx.spawn_in(window, async move |_, window| {
+            let suggestions = window
+                .background_executor()
+                .spawn(async move { fetch_trailer_suggestions(&repo_root) })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    if let Err(err) = &suggestions {
+                        window.push_toast(Toast::error(format!("读取最近协作者失败：{err}")), cx);
+                    }
+                    this.update(cx, |this, cx| {
+                        this.trailer_suggestions = Some(suggestions.unwrap_or_default());
+                        this.trailer_suggestions_loading = false;
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn insert_commit_trailer(
+        &mut self,
+        trailer: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let message = self.commit_message_input.read(cx).value().to_string();
+        let next = append_trailer(&message, trailer);
+        if next == message {
+            return;
+        }
+        self.commit_message_input.update(cx, |state, cx| {
+            state.set_value(next, window, cx);
+        });
+        cx.notify();
+    }
+
+    fn set_amend(&mut self, enabled: bool, window: &mut Window, cx: &mut Context<Self>) {
+        if enabled == self.amend.is_some() {
+            return;
+        }
+
+        if !enabled {
+            if let Some(amend) = self.amend.take() {
+                let previous_message = amend.previous_message;
+                self.commit_message_input.update(cx, |state, cx| {
+                    state.set_value(previous_message, window, cx);
+                });
+            }
+            cx.notify();
+            return;
+        }
+
+        if !self.git_available {
+            window.push_toast(Toast::warning("未检测到 git 命令，无法修改上一次提交"), cx);
+            return;
+        }
+
+        let previous_message = self.commit_message_input.read(cx).value().to_string();
+        self.amend = Some(AmendState {
+            loading: true,
+            files: Vec::new(),
+            previous_message,
+        });
+        cx.notify();
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+
+        cx.spawn_in(window, async move |_, window| {
+            let (message, files) = window
+                .background_executor()
+                .spawn(async move {
+                    (
+                        fetch_last_commit_message(&repo_root),
+                        fetch_last_commit_files(&repo_root),
+                    )
+                })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    let message = match message {
+                        Ok(message) => message,
+                        Err(err) => {
+                            window
+                                .push_toast(Toast::error(format!("读取上一次提交失败：{err}")), cx);
+                            this.update(cx, |this, cx| {
+                                this.amend = None;
+                                cx.notify();
+                            });
+                            return;
+                        }
+                    };
+                    if let Err(err) = &files {
+                        window.push_toast(
+                            Toast::error(format!("读取上一次提交的文件列表失败：{err}")),
+                            cx,
+                        );
+                    }
+
+                    if this.read(cx).amend.is_none() {
+                        return;
+                    }
+
+                    let input = this.read(cx).commit_message_input.clone();
+                    input.update(cx, |state, cx| {
+                        state.set_value(message, window, cx);
+                    });
+                    this.update(cx, |this, cx| {
+                        if let Some(amend) = this.amend.as_mut() {
+                            amend.loading = false;
+                            amend.files = files.unwrap_or_default();
+                        }
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn open_branch_compare_view(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.git_available {
+            window.push_toast(Toast::warning("未检测到 git 命令，无法对比分支"), cx);
+            return;
+        }
+
+        if self.branch_compare_view.is_none() {
+            let base_input = cx.new(|cx| {
+                InputState::new(window, cx)
+                    .placeholder("基准（例如 main / origin/main）")
+                    .default_value("")
+            });
+            let head_input = cx.new(|cx| {
+                InputState::new(window, cx)
+                    .placeholder("对比（例如 HEAD / feature）")
+                    .default_value("HEAD")
+            });
+            self.branch_compare_view = Some(BranchCompareState {
+                base_input,
+                head_input,
+                loading: false,
+                comparison: None,
+                selected_commit: None,
+                return_screen: None,
+            });
+        }
+        self.screen = AppScreen::BranchCompare;
+        cx.notify();
+    }
+
+    fn close_branch_compare_view(&mut self) {
+        self.screen = self
+            .branch_compare_view
+            .as_mut()
+            .and_then(|compare| compare.return_screen.take())
+            .unwrap_or(AppScreen::StatusList);
+    }
+
+    fn swap_branch_compare_refs(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(compare) = self.branch_compare_view.as_ref() else {
+            return;
+        };
+        let base = compare.base_input.read(cx).value().to_string();
+        let head = compare.head_input.read(cx).value().to_string();
+        let (base_input, head_input) = (compare.base_input.clone(), compare.head_input.clone());
+        base_input.update(cx, |state, cx| state.set_value(head, window, cx));
+        head_input.update(cx, |state, cx| state.set_value(base, window, cx));
+        self.run_branch_compare(window, cx);
+    }
+
+    fn run_branch_compare(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(compare) = self.branch_compare_view.as_mut() else {
+            return;
+        };
+        let base = compare.base_input.read(cx).value().trim().to_string();
+        let head = compare.head_input.read(cx).value().trim().to_string();
+        let head = if head.is_empty() {
+            "HEAD".to_string()
+        } else {
+            head
+        };
+        if base.is_empty() {
+            window.push_toast(
+                Toast::warning("基准 ref 不能为空（例如 main / origin/main）"),
+                cx,
+            );
+            return;
+        }
+        compare.loading = true;
+        cx.notify();
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        cx.spawn_in(window, async move |_, window| {
+            let result = window
+                .background_executor()
+                .spawn(async move { fetch_branch_comparison(&repo_root, &base, &head) })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    let comparison = match result {
+                        Ok(comparison) => Some(comparison),
+                        Err(err) => {
+                            window.push_toast(Toast::error(format!("对比分支失败：{err:#}")), cx);
+                            None
+                        }
+                    };
+
+                    this.update(cx, |this, cx| {
+                        if let Some(compare) = this.branch_compare_view.as_mut() {
+                            compare.loading = false;
+                            if comparison.is_some() {
+                                compare.comparison = comparison;
+                                compare.selected_commit = None;
+                            }
+                        }
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn open_branch_compare_file(
+        &mut self,
+        index: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(compare) = self.branch_compare_view.as_mut() else {
+            return;
+        };
+        let Some(comparison) = compare.comparison.as_ref() else {
+            return;
+        };
+        let Some(file) = comparison.files.get(index) else {
+            return;
+        };
+
+        let path = file.path.clone();
+        let status = file.status.clone();
+        let left = comparison.merge_base.clone();
+        let right = comparison.head.clone();
+        self.diff_return_screen = Some(AppScreen::BranchCompare);
+        self.open_file_diff_with_refs(path, Some(status), left, right, window, cx);
+    }
+
+    fn open_pull_requests_view(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.git_available {
+            window.push_toast(
+                Toast::warning("未检测到 git 命令，无法查看 Pull Request"),
+                cx,
+            );
+            return;
+        }
+
+        if self.pull_requests_view.is_none() {
+            let token_input = cx.new(|cx| {
+                InputState::new(window, cx)
+                    .placeholder("访问 token（私有仓库需要）")
+                    .masked(true)
+            });
+            self.pull_requests_view = Some(PullRequestsState {
+                remote: None,
+                detected: false,
+                token_input,
+                loading: false,
+                running: false,
+                requests: Vec::new(),
+                selected: None,
+                comments: None,
+            });
+            self.reload_pull_requests(window, cx);
+        }
+        self.screen = AppScreen::PullRequests;
+        cx.notify();
+    }
+
+    fn close_pull_requests_view(&mut self) {
+        self.screen = AppScreen::StatusList;
+    }
+
+    /// 输入框里的 token 优先，其次是环境变量和配置文件。
+    fn forge_token(&self, remote: &forge::ForgeRemote, cx: &App) -> Option<String> {
+        self.pull_requests_view
+            .as_ref()
+            .map(|view| view.token_input.read(cx).value().trim().to_string())
+            .filter(|token| !token.is_empty())
+            .or_else(|| remote.token())
+    }
+
+    fn selected_pull_request(&self) -> Option<(forge::ForgeRemote, forge::PullRequest)> {
+        let view = self.pull_requests_view.as_ref()?;
+        let request = view.requests.get(view.selected?)?;
+        Some((view.remote.clone()?, request.clone()))
+    }
+
+    fn reload_pull_requests(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(view) = self.pull_requests_view.as_mut() else {
+            return;
+        };
+        if view.loading {
+            return;
+        }
+        view.loading = true;
+        let token = Some(view.token_input.read(cx).value().trim().to_string())
+            .filter(|token| !token.is_empty());
+        cx.notify();
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        cx.spawn_in(window, async move |_, window| {
+            let result = window
+                .background_executor()
+                .spawn(async move { fetch_pull_requests(&repo_root, token) })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    if let Err(err) = &result {
+                        window.push_toast(
+                            Toast::error(format!("读取 Pull Request 失败：{err:#}")),
+                            cx,
+                        );
+                    }
+
+                    this.update(cx, |this, cx| {
+                        if let Some(view) = this.pull_requests_view.as_mut() {
+                            view.loading = false;
+                            view.detected = true;
+                            if let Ok((remote, requests)) = result {
+                                view.remote = remote;
+                                view.requests = requests;
+                                view.selected = None;
+                                view.comments = None;
+                            }
+                        }
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn save_forge_token(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(view) = self.pull_requests_view.as_ref() else {
+            return;
+        };
+        let Some(host) = view.remote.as_ref().map(|remote| remote.host.clone()) else {
+            window.push_toast(Toast::warning("没有识别出 GitHub / GitLab 的 remote"), cx);
+            return;
+        };
+        let token = view.token_input.read(cx).value().trim().to_string();
+        match forge::save_token(&host, &token) {
+            Ok(()) if token.is_empty() => {
+                window.push_toast(Toast::success(format!("已清除 {host} 的 token")), cx)
+            }
+            Ok(()) => window.push_toast(Toast::success(format!("已保存 {host} 的 token")), cx),
+            Err(err) => window.push_toast(Toast::error(format!("保存 token 失败：{err:#}")), cx),
+        }
+        self.reload_pull_requests(window, cx);
+    }
+
+    /// 选中一个变更请求并加载它的评论；同一个请求只加载一次。
+    fn select_pull_request(&mut self, index: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(view) = self.pull_requests_view.as_mut() else {
+            return;
+        };
+        view.selected = Some(index);
+        cx.notify();
+        let Some((remote, request)) = self.selected_pull_request() else {
+            return;
+        };
+        let number = request.number;
+        let token = self.forge_token(&remote, cx);
+        let Some(view) = self.pull_requests_view.as_mut() else {
+            return;
+        };
+        if view
+            .comments
+            .as_ref()
+            .is_some_and(|comments| comments.number == number)
+        {
+            return;
+        }
+        view.comments = Some(PullRequestComments {
+            number,
+            loading: true,
+            comments: Vec::new(),
+        });
+
+        let this = cx.entity();
+        cx.spawn_in(window, async move |_, window| {
+            let result = window
+                .background_executor()
+                .spawn(
+                    async move { forge::pull_request_comments(&remote, token.as_deref(), number) },
+                )
+                .await;
+
+            window
+                .update(|window, cx| {
+                    if let Err(err) = &result {
+                        window.push_toast(Toast::error(format!("读取评论失败：{err:#}")), cx);
+                    }
+                    let comments = result.unwrap_or_default();
+
+                    this.update(cx, |this, cx| {
+                        // diff 可能在评论加载完之前就打开了。
+                        if let Some(review) = this
+                            .pull_request_review
+                            .as_mut()
+                            .filter(|review| review.number == number)
+                        {
+                            review.comments = comments.clone();
+                        }
+                        if let Some(state) = this
+                            .pull_requests_view
+                            .as_mut()
+                            .and_then(|view| view.comments.as_mut())
+                            .filter(|state| state.number == number)
+                        {
+                            state.loading = false;
+                            state.comments = comments;
+                        }
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn checkout_pull_request(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some((remote, request)) = self.selected_pull_request() else {
+            return;
+        };
+        let Some(view) = self.pull_requests_view.as_mut() else {
+            return;
+        };
+        if view.running {
+            return;
+        }
+        view.running = true;
+        let label = format!("{} #{}", remote.kind.request_label(), request.number);
+        window.push_toast(Toast::info(format!("正在检出 {label}…")), cx);
+        cx.notify();
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        cx.spawn_in(window, async move |_, window| {
+            let (result, entries) = window
+                .background_executor()
+                .spawn(async move {
+                    let result = forge::checkout_pull_request(&repo_root, &remote, &request);
+                    let entries = fetch_git_status(&repo_root);
+                    (result, entries)
+                })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    match &result {
+                        Ok(branch) => window.push_toast(
+                            Toast::success(format!("已检出 {label} 到分支 {branch}")),
+                            cx,
+                        ),
+                        Err(err) => window
+                            .push_toast(Toast::error(format!("检出 {label} 失败：{err:#}")), cx),
+                    }
+
+                    this.update(cx, |this, cx| {
+                        if let Ok(entries) = entries {
+                            this.set_status_entries(entries);
+                        }
+                        if let Some(view) = this.pull_requests_view.as_mut() {
+                            view.running = false;
+                        }
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    /// 抓取变更请求的 head，再用分支对比页面查看它相对目标分支的提交和文件 diff。
+    fn open_pull_request_diff(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some((remote, request)) = self.selected_pull_request() else {
+            return;
+        };
+        let Some(view) = self.pull_requests_view.as_mut() else {
+            return;
+        };
+        if view.running {
+            return;
+        }
+        view.running = true;
+        let comments = view
+            .comments
+            .as_ref()
+            .filter(|comments| comments.number == request.number)
+            .map(|comments| comments.comments.clone())
+            .unwrap_or_default();
+        cx.notify();
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        cx.spawn_in(window, async move |_, window| {
+            let result = window
+                .background_executor()
+                .spawn({
+                    let remote = remote.clone();
+                    let request = request.clone();
+                    async move { forge::fetch_pull_request(&repo_root, &remote, &request) }
+                })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    this.update(cx, |this, cx| {
+                        if let Some(view) = this.pull_requests_view.as_mut() {
+                            view.running = false;
+                        }
+                        if let Err(err) = result {
+                            window.push_toast(
+                                Toast::error(format!(
+                                    "抓取 {} #{} 失败：{err:#}",
+                                    remote.kind.request_label(),
+                                    request.number
+                                )),
+                                cx,
+                            );
+                            cx.notify();
+                            return;
+                        }
+
+                        let head = remote.local_ref(request.number);
+                        this.pull_request_review = Some(PullRequestReview {
+                            number: request.number,
+                            head_ref: head.clone(),
+                            comments,
+                        });
+                        this.open_branch_compare_view(window, cx);
+                        let Some(compare) = this.branch_compare_view.as_mut() else {
+                            return;
+                        };
+                        compare.return_screen = Some(AppScreen::PullRequests);
+                        let (base_input, head_input) =
+                            (compare.base_input.clone(), compare.head_input.clone());
+                        let base = remote.target_ref(&request);
+                        base_input.update(cx, |state, cx| state.set_value(base, window, cx));
+                        head_input.update(cx, |state, cx| state.set_value(head, window, cx));
+                        this.run_branch_compare(window, cx);
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn open_repo_stats_view(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.git_available {
+            window.push_toast(Toast::warning("未检测到 git 命令，无法统计仓库"), cx);
+            return;
+        }
+
+        if self.repo_stats_view.is_none() {
+            self.repo_stats_view = Some(RepoStatsState {
+                stats: stats::RepoStats::default(),
+                loading: false,
+                generation: 0,
+            });
+            self.reload_repo_stats(window, cx);
+        }
+        self.screen = AppScreen::RepoStats;
+        cx.notify();
+    }
+
+    fn close_repo_stats_view(&mut self) {
+        self.screen = AppScreen::StatusList;
+    }
+
+    /// 从头重新统计；结果按批合并进界面，离开页面后统计仍在后台继续。
+    fn reload_repo_stats(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(view) = self.repo_stats_view.as_mut() else {
+            return;
+        };
+        view.generation += 1;
+        view.stats = stats::RepoStats::default();
+        view.loading = true;
+        let generation = view.generation;
+        cx.notify();
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        cx.spawn_in(window, async move |_, window| {
+            let mut collector = stats::StatsCollector::new(repo_root);
+            loop {
+                let (returned, result) = window
+                    .background_executor()
+                    .spawn(async move {
+                        let result = collector.next_batch();
+                        (collector, result)
+                    })
+                    .await;
+                collector = returned;
+
+                let keep_going = window
+                    .update(|window, cx| {
+                        if let Err(err) = &result {
+                            window.push_toast(Toast::error(format!("统计仓库失败：{err:#}")), cx);
+                        }
+
+                        this.update(cx, |this, cx| {
+                            let Some(view) = this
+                                .repo_stats_view
+                                .as_mut()
+                                .filter(|view| view.generation == generation)
+                            else {
+                                return false;
+                            };
+                            let batch = result.ok().flatten();
+                            let has_batch = batch.is_some();
+                            match batch {
+                                Some(batch) => view.stats.merge(batch),
+                                // 算完或出错都停在这里，已有的部分结果保留。
+                                None => view.loading = false,
+                            }
+                            cx.notify();
+                            has_batch
+                        })
+                    })
+                    .unwrap_or(false);
+                if !keep_going {
+                    break;
+                }
+            }
+
+            Some(())
+        })
+        .detach();
+    }
+
+    /// 选中的提交变化时按需加载详情；同一个提交只加载一次。
+    fn request_commit_details(&mut self, hash: &str, window: &mut Window, cx: &mut Context<Self>) {
+        if self
+            .commit_details
+            .as_ref()
+            .is_some_and(|state| state.hash == hash)
+        {
+            return;
+        }
+        self.commit_details = Some(CommitDetailsState {
+            hash: hash.to_string(),
+            loading: true,
+            details: None,
+            error: None,
+            note_input: None,
+            saving_note: false,
+        });
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        let hash = hash.to_string();
+        cx.spawn_in(window, async move |_, window| {
+            let hash_for_task = hash.clone();
+            let result = window
+                .background_executor()
+                .spawn(async move { fetch_commit_details(&repo_root, &hash_for_task) })
+                .await;
+
+            window
+                .update(|_window, cx| {
+                    this.update(cx, |this, cx| {
+                        let Some(state) = this
+                            .commit_details
+                            .as_mut()
+                            .filter(|state| state.hash == hash)
+                        else {
+                            return;
+                        };
+                        state.loading = false;
+                        match result {
+                            Ok(details) => state.details = Some(details),
+                            Err(err) => state.error = Some(format!("{err:#}")),
+                        }
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn edit_commit_note(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(state) = self.commit_details.as_mut() else {
+            return;
+        };
+        let note = state
+            .details
+            .as_ref()
+            .and_then(|details| details.note.clone())
+            .unwrap_or_default();
+        state.note_input = Some(cx.new(|cx| {
+            InputState::new(window, cx)
+                .multi_line()
+                .placeholder("为该提交添加备注（git notes）")
+                .default_value(note)
+        }));
+    }
+
+    fn cancel_commit_note_edit(&mut self) {
+        if let Some(state) = self.commit_details.as_mut() {
+            state.note_input = None;
+        }
+    }
+
+    /// 保存编辑器中的备注；`remove` 或内容为空时删除备注。
+    fn save_commit_note(&mut self, remove: bool, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(state) = self.commit_details.as_mut() else {
+            return;
+        };
+        if state.saving_note {
+            return;
+        }
+        let note = if remove {
+            String::new()
+        } else {
+            state
+                .note_input
+                .as_ref()
+                .map(|input| input.read(cx).value().trim_end().to_string())
+                .unwrap_or_default()
+        };
+        state.saving_note = true;
+        let hash = state.hash.clone();
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        cx.spawn_in(window, async move |_, window| {
+            let hash_for_task = hash.clone();
+            let note_for_task = note.clone();
+            let result = window
+                .background_executor()
+                .spawn(async move { write_commit_note(&repo_root, &hash_for_task, &note_for_task) })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    this.update(cx, |this, cx| {
+                        if let Some(state) = this
+                            .commit_details
+                            .as_mut()
+                            .filter(|state| state.hash == hash)
+                        {
+                            state.saving_note = false;
+                        }
+                        if let Err(err) = result {
+                            window.push_toast(Toast::error(format!("保存备注失败：{err:#}")), cx);
+                            cx.notify();
+                            return;
+                        }
+
+                        if let Some(overlay) = this.file_history_overlay.as_mut() {
+                            for entry in overlay.commits.iter_mut().filter(|e| e.hash == hash) {
+                                entry.has_note = !note.is_empty();
+                            }
+                        }
+                        // 重新读取详情，显示保存后的备注。
+                        this.commit_details = None;
+                        this.request_commit_details(&hash, window, cx);
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn open_commit_file_diff(&mut self, index: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(details) = self
+            .commit_details
+            .as_ref()
+            .and_then(|state| state.details.as_ref())
+        else {
+            return;
+        };
+        let Some(file) = details.files.get(index) else {
+            return;
+        };
+
+        let path = file.path.clone();
+        let status = file.status.clone();
+        let left = details
+            .parents
+            .first()
+            .cloned()
+            .unwrap_or_else(|| EMPTY_TREE_HASH.to_string());
+        let right = details.hash.clone();
+
+        self.file_history_overlay = None;
+        if matches!(
+            self.screen,
+            AppScreen::Reflog | AppScreen::BranchCompare | AppScreen::Bisect
+        ) {
+            self.diff_return_screen = Some(self.screen.clone());
+        }
+        self.open_file_diff_with_refs(path, Some(status), left, right, window, cx);
+    }
+
+    fn render_commit_details_pane(
+        &mut self,
+        hash: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> AnyElement {
+        self.request_commit_details(hash, window, cx);
+        let theme = cx.theme();
+        let app = cx.entity();
+
+        let container = div()
+            .id("commit-details")
+            .flex()
+            .flex_col()
+            .gap(px(4.))
+            .max_h(px(240.))
+            .overflow_y_scroll()
+            .px(px(10.))
+            .py(px(8.))
+            .border_1()
+            .border_color(theme.border.alpha(0.5))
+            .rounded(theme.radius)
+            .text_xs();
+
+        let Some(state) = self.commit_details.as_ref() else {
+            return container.into_any_element();
+        };
+        let Some(details) = state.details.as_ref() else {
+            let message = match &state.error {
+                Some(err) => format!("读取 commit 详情失败：{err}"),
+                None => "正在加载 commit 详情…".to_string(),
+            };
+            return container
+                .text_color(theme.muted_foreground)
+                .child(message)
+                .into_any_element();
+        };
+
+        let field = |label: &'static str, value: String| {
+            div()
+                .flex()
+                .flex_row()
+                .gap(px(8.))
+                .child(
+                    div()
+                        .w(px(56.))
+                        .flex_none()
+                        .text_color(theme.muted_foreground)
+                        .child(label),
+                )
+                .child(div().flex_1().min_w(px(0.)).truncate().child(value))
+        };
+        let parents = if details.parents.is_empty() {
+            "（根提交）".to_string()
+        } else {
+            details
+                .parents
+                .iter()
+                .map(|parent| parent.chars().take(10).collect::<String>())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        let note_section = match state.note_input.as_ref() {
+            Some(note_input) => div()
+                .flex()
+                .flex_col()
+                .gap(px(4.))
+                .child(
+                    div()
+                        .h(px(80.))
+                        .child(Input::new(note_input).w_full().h_full()),
+                )
+                .child(
+                    div()
+                        .flex()
+                        .flex_row()
+                        .gap(px(6.))
+                        .child(
+                            Button::new("commit-note-save")
+                                .label(if state.saving_note {
+                                    "保存中…"
+                                } else {
+                                    "保存备注"
+                                })
+                                .primary()
+                                .xsmall()
+                                .disabled(state.saving_note)
+                                .on_click({
+                                    let app = app.clone();
+                                    move |_, window, cx| {
+                                        app.update(cx, |this, cx| {
+                                            this.save_commit_note(false, window, cx);
+                                            cx.notify();
+                                        });
+                                    }
+                                }),
+                        )
+                        .child(
+                            Button::new("commit-note-cancel")
+                                .label("取消")
+                                .ghost()
+                                .xsmall()
+                                .disabled(state.saving_note)
+                                .on_click({
+                                    let app = app.clone();
+                                    move |_, _window, cx| {
+                                        app.update(cx, |this, cx| {
+                                            this.cancel_commit_note_edit();
+                                            cx.notify();
+                                        });
+                                    }
+                                }),
+                        )
+                        .when(details.note.is_some(), |this| {
+                            this.child(
+                                Button::new("commit-note-remove")
+                                    .label("删除备注")
+                                    .ghost()
+                                    .danger()
+                                    .xsmall()
+                                    .disabled(state.saving_note)
+                                    .on_click({
+                                        let app = app.clone();
+                                        move |_, window, cx| {
+                                            app.update(cx, |this, cx| {
+                                                this.save_commit_note(true, window, cx);
+                                                cx.notify();
+                                            });
+                                        }
+                                    }),
+                            )
+                        }),
+                ),
+            None => div()
+                .flex()
+                .flex_row()
+                .items_start()
+                .gap(px(8.))
+                .child(
+                    div()
+                        .w(px(56.))
+                        .flex_none()
+                        .text_color(theme.muted_foreground)
+                        .child("备注"),
+                )
+                .child(
+                    div()
+                        .flex_1()
+                        .min_w(px(0.))
+                        .font_family(theme.mono_font_family.clone())
+                        .when_none(&details.note, |this| {
+                            this.text_color(theme.muted_foreground).child("（无）")
+                        })
+                        .when_some(details.note.as_ref(), |this, note| {
+                            this.children(
+                                note.lines().map(|line| div().child(preserve_spaces(line))),
+                            )
+                        }),
+                )
+                .child(
+                    Button::new("commit-note-edit")
+                        .label(if details.note.is_some() {
+                            "编辑备注"
+                        } else {
+                            "添加备注"
+                        })
+                        .ghost()
+                        .xsmall()
+                        .on_click({
+                            let app = app.clone();
+                            move |_, window, cx| {
+                                app.update(cx, |this, cx| {
+                                    this.edit_commit_note(window, cx);
+                                    cx.notify();
+                                });
+                            }
+                        }),
+                ),
+        };
+
+        let message_lines = details.message.lines().map(|line| {
+            let text = preserve_spaces(line);
+            div()
+                .child(if text.is_empty() {
+                    "\u{00A0}".to_string()
+                } else {
+                    text
+                })
+                .into_any_element()
+        });
+
+        let files = details.files.iter().enumerate().map(|(index, file)| {
+            let stat = match file.stat {
+                Some((added, removed)) => format!("+{added} -{removed}"),
+                None => "二进制".to_string(),
+            };
+            let app = app.clone();
+            div()
+                .id(("commit-details-file", index))
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap(px(8.))
+                .px(px(4.))
+                .rounded(px(4.))
+                .cursor_pointer()
+                .hover(|this| this.bg(theme.accent.alpha(0.4)))
+                .on_mouse_down(MouseButton::Left, move |_, window, cx| {
+                    window.prevent_default();
+                    app.update(cx, |this, cx| {
+                        this.open_commit_file_diff(index, window, cx);
+                        cx.notify();
+                    });
+                })
+                .child(
+                    div()
+                        .w(px(20.))
+                        .flex_none()
+                        .font_family(theme.mono_font_family.clone())
+                        .child(file.status.clone()),
+                )
+                .child(
+                    div()
+                        .flex_1()
+                        .min_w(px(0.))
+                        .truncate()
+                        .child(file.path.clone()),
+                )
+                .child(
+                    div()
+                        .flex_none()
+                        .font_family(theme.mono_font_family.clone())
+                        .text_color(theme.muted_foreground)
+                        .child(stat),
+                )
+        });
+
+        container
+            .child(field("提交", details.hash.clone()))
+            .child(field("父提交", parents))
+            .child(field(
+                "作者",
+                format!("{}  {}", details.author, details.author_date),
+            ))
+            .child(field(
+                "提交者",
+                format!("{}  {}", details.committer, details.committer_date),
+            ))
+            .child(
+                div()
+                    .my(px(4.))
+                    .p(px(6.))
+                    .rounded(px(4.))
+                    .bg(theme.muted.alpha(0.12))
+                    .font_family(theme.mono_font_family.clone())
+                    .children(message_lines),
+            )
+            .child(note_section)
+            .child(div().text_color(theme.muted_foreground).child(format!(
+                "{} 个文件变更（点击打开 diff）",
+                details.files.len()
+            )))
+            .children(files)
+            .into_any_element()
+    }
+
+    fn open_reflog_view(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.git_available {
+            window.push_toast(Toast::warning("未检测到 git 命令，无法查看 reflog"), cx);
+            return;
+        }
+
+        if self.reflog_view.is_none() {
+            let branch_input = cx.new(|cx| {
+                InputState::new(window, cx)
+                    .placeholder("新分支名（例如 recover/lost-work）")
+                    .default_value("")
+            });
+            self.reflog_view = Some(ReflogViewState {
+                entries: Vec::new(),
+                loading: true,
+                running: false,
+                selected: 0,
+                branch_input,
+                confirm_hard_reset: false,
+            });
+        }
+        self.screen = AppScreen::Reflog;
+        self.reload_reflog(window, cx);
+        cx.notify();
+    }
+
+    fn close_reflog_view(&mut self) {
+        self.screen = AppScreen::StatusList;
+    }
+
+    fn reload_reflog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(reflog_view) = self.reflog_view.as_mut() else {
+            return;
+        };
+        reflog_view.loading = true;
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        cx.spawn_in(window, async move |_, window| {
+            let result = window
+                .background_executor()
+                .spawn(async move { fetch_reflog(&repo_root, REFLOG_LIMIT) })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    let entries = match result {
+                        Ok(entries) => entries,
+                        Err(err) => {
+                            window.push_toast(Toast::error(format!("读取 reflog 失败：{err}")), cx);
+                            Vec::new()
+                        }
+                    };
+
+                    this.update(cx, |this, cx| {
+                        if let Some(reflog_view) = this.reflog_view.as_mut() {
+                            reflog_view.loading = false;
+                            reflog_view.selected =
+                                reflog_view.selected.min(entries.len().saturating_sub(1));
+                            reflog_view.entries = entries;
+                            reflog_view.confirm_hard_reset = false;
+                        }
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn select_reflog_entry(&mut self, index: usize) {
+        let Some(reflog_view) = self.reflog_view.as_mut() else {
+            return;
+        };
+        if index >= reflog_view.entries.len() {
+            return;
+        }
+        if reflog_view.selected != index {
+            reflog_view.confirm_hard_reset = false;
+        }
+        reflog_view.selected = index;
+    }
+
+    fn move_reflog_selection(&mut self, direction: i32) {
+        let Some(reflog_view) = self.reflog_view.as_ref() else {
+            return;
+        };
+        let next = if direction < 0 {
+            reflog_view.selected.saturating_sub(1)
+        } else {
+            (reflog_view.selected + 1).min(reflog_view.entries.len().saturating_sub(1))
+        };
+        self.select_reflog_entry(next);
+    }
+
+    fn run_reflog_action(
+        &mut self,
+        action: ReflogAction,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(reflog_view) = self.reflog_view.as_mut() else {
+            return;
+        };
+        if reflog_view.running {
+            return;
+        }
+        let Some(entry) = reflog_view.entries.get(reflog_view.selected).cloned() else {
+            return;
+        };
+
+        // reset --hard 会丢弃未提交的修改，需要再点一次确认。
+        if action == ReflogAction::ResetHard && !reflog_view.confirm_hard_reset {
+            reflog_view.confirm_hard_reset = true;
+            window.push_toast(
+                Toast::info(format!(
+                    "再次点击确认 reset --hard 到 {}：工作区和暂存区的未提交修改将被丢弃",
+                    entry.selector
+                )),
+                cx,
+            );
+            cx.notify();
+            return;
+        }
+
+        let args: Vec<String> = match action {
+            ReflogAction::Checkout => {
+                vec!["checkout".into(), "--detach".into(), entry.hash.clone()]
+            }
+            ReflogAction::ResetSoft => vec!["reset".into(), "--soft".into(), entry.hash.clone()],
+            ReflogAction::ResetHard => vec!["reset".into(), "--hard".into(), entry.hash.clone()],
+            ReflogAction::CreateBranch => {
+                let name = reflog_view.branch_input.read(cx).value().trim().to_string();
+                if name.is_empty() {
+                    window.push_toast(Toast::warning("请先输入新分支名"), cx);
+                    return;
+                }
+                vec!["branch".into(), "--".into(), name, entry.hash.clone()]
+            }
+        };
+
+        reflog_view.running = true;
+        reflog_view.confirm_hard_reset = false;
+        cx.notify();
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        window.push_toast(Toast::info(format!("git {}", args.join(" "))), cx);
+
+        cx.spawn_in(window, async move |_, window| {
+            let (result, entries) = window
+                .background_executor()
+                .spawn(async move {
+                    let result = run_git(&repo_root, &args);
+                    let entries = fetch_git_status(&repo_root);
+                    (result, entries)
+                })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    match &result {
+                        Ok(()) => window.push_toast(
+                            Toast::success(format!(
+                                "{} 完成：{} {}",
+                                action.label(),
+                                entry.short_hash,
+                                entry.message
+                            )),
+                            cx,
+                        ),
+                        Err(err) => window.push_toast(
+                            Toast::error(format!("{} 失败：{err}", action.label())),
+                            cx,
+                        ),
+                    }
+
+                    this.update(cx, |this, cx| {
+                        if let Ok(entries) = entries {
+                            this.set_status_entries(entries);
+                        }
+                        if let Some(reflog_view) = this.reflog_view.as_mut() {
+                            reflog_view.running = false;
+                            if result.is_ok() && action == ReflogAction::CreateBranch {
+                                reflog_view.branch_input.update(cx, |state, cx| {
+                                    state.set_value(String::new(), window, cx);
+                                });
+                            }
+                        }
+                        if result.is_ok() {
+                            this.reload_reflog(window, cx);
+                        }
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn open_bisect_view(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.git_available {
+            window.push_toast(Toast::warning("未检测到 git 命令，无法使用 bisect"), cx);
+            return;
+        }
+
+        if self.bisect_view.is_none() {
+            let bad_input = cx.new(|cx| {
+                InputState::new(window, cx)
+                    .placeholder("有问题的提交（例如 HEAD）")
+                    .default_value("HEAD")
+            });
+            let good_input = cx.new(|cx| {
+                InputState::new(window, cx)
+                    .placeholder("没有问题的提交，可填多个，空格分隔（例如 v1.0）")
+                    .default_value("")
+            });
+            self.bisect_view = Some(BisectViewState {
+                bad_input,
+                good_input,
+                progress: BisectProgress::default(),
+                loading: true,
+                running: false,
+                output: None,
+            });
+        }
+        self.screen = AppScreen::Bisect;
+        self.reload_bisect_progress(window, cx);
+        cx.notify();
+    }
+
+    fn close_bisect_view(&mut self) {
+        self.screen = AppScreen::StatusList;
+    }
+
+    fn reload_bisect_progress(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(bisect_view) = self.bisect_view.as_mut() else {
+            return;
+        };
+        bisect_view.loading = true;
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        cx.spawn_in(window, async move |_, window| {
+            let result = window
+                .background_executor()
+                .spawn(async move { fetch_bisect_progress(&repo_root) })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    let progress = match result {
+                        Ok(progress) => progress,
+                        Err(err) => {
+                            window.push_toast(
+                                Toast::error(format!("读取 bisect 状态失败：{err}")),
+                                cx,
+                            );
+                            BisectProgress::default()
+                        }
+                    };
+
+                    this.update(cx, |this, cx| {
+                        if let Some(bisect_view) = this.bisect_view.as_mut() {
+                            bisect_view.loading = false;
+                            bisect_view.progress = progress;
+                        }
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn run_bisect_action(
+        &mut self,
+        action: BisectAction,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(bisect_view) = self.bisect_view.as_mut() else {
+            return;
+        };
+        if bisect_view.running {
+            return;
+        }
+
+        let args: Vec<String> = match action {
+            BisectAction::Start => {
+                let bad = bisect_view.bad_input.read(cx).value().trim().to_string();
+                let goods: Vec<String> = bisect_view
+                    .good_input
+                    .read(cx)
+                    .value()
+                    .split_whitespace()
+                    .map(str::to_string)
+                    .collect();
+                if goods.is_empty() {
+                    window.push_toast(Toast::warning("请至少填写一个没有问题的提交"), cx);
+                    return;
+                }
+                let bad = if bad.is_empty() {
+                    "HEAD".to_string()
+                } else {
+                    bad
+                };
+                ["bisect".to_string(), "start".to_string(), bad]
+                    .into_iter()
+                    .chain(goods)
+                    .collect()
+            }
+            BisectAction::Good => vec!["bisect".into(), "good".into()],
+            BisectAction::Bad => vec!["bisect".into(), "bad".into()],
+            BisectAction::Skip => vec!["bisect".into(), "skip".into()],
+            BisectAction::Reset => vec!["bisect".into(), "reset".into()],
+        };
+
+        bisect_view.running = true;
+        cx.notify();
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        cx.spawn_in(window, async move |_, window| {
+            let (result, progress, entries) = window
+                .background_executor()
+                .spawn(async move {
+                    let result = run_git_output(&repo_root, &args);
+                    let progress = fetch_bisect_progress(&repo_root);
+                    let entries = fetch_git_status(&repo_root);
+                    (result, progress, entries)
+                })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    if let Err(err) = &result {
+                        window.push_toast(
+                            Toast::error(format!("{} 失败：{err}", action.label())),
+                            cx,
+                        );
+                    }
+
+                    this.update(cx, |this, cx| {
+                        if let Ok(entries) = entries {
+                            this.set_status_entries(entries);
+                        }
+                        if let Some(bisect_view) = this.bisect_view.as_mut() {
+                            bisect_view.running = false;
+                            if let Ok(output) = result {
+                                let output = output.trim().to_string();
+                                bisect_view.output = (!output.is_empty()).then_some(output);
+                            }
+                            if let Ok(progress) = progress {
+                                bisect_view.progress = progress;
+                            }
+                        }
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn open_commit_stack_view(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.git_available {
+            window.push_toast(Toast::warning("未检测到 git 命令，无法编辑提交栈"), cx);
+            return;
+        }
+
+        if self.commit_stack.is_none() {
+            let base_input = cx.new(|cx| {
+                InputState::new(window, cx)
+                    .placeholder("栈底（例如 @{upstream} / origin/main）")
+                    .default_value("@{upstream}")
+            });
+            let message_input = cx.new(|cx| {
+                InputState::new(window, cx)
+                    .multi_line()
+                    .placeholder("选中一个提交后在这里修改提交信息")
+                    .default_value("")
+            });
+            let list = cx.new(|cx| {
+                DndListState::new(cx)
+                    .drop_strategy(stack::drop_strategy())
+                    .can_drop_into(|drop, items, _| {
+                        stack::can_squash_into(items, drop.from, drop.into)
+                    })
+                    .on_drop_into(|drop, items| stack::squash_into(items, drop.from, drop.into))
+            });
+            self.commit_stack = Some(CommitStackState {
+                base_input,
+                merge_base: None,
+                original_order: Vec::new(),
+                list,
+                message_input,
+                editing_hash: None,
+                loading: false,
+                running: false,
+            });
+        }
+        self.screen = AppScreen::CommitStack;
+        self.reload_commit_stack(window, cx);
+        cx.notify();
+    }
+
+    fn close_commit_stack_view(&mut self) {
+        self.screen = AppScreen::StatusList;
+    }
+
+    fn reload_commit_stack(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(stack) = self.commit_stack.as_mut() else {
+            return;
+        };
+        let base = stack.base_input.read(cx).value().trim().to_string();
+        let base = if base.is_empty() {
+            "@{upstream}".to_string()
+        } else {
+            base
+        };
+        stack.loading = true;
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        cx.spawn_in(window, async move |_, window| {
+            let result = window
+                .background_executor()
+                .spawn(async move { fetch_commit_stack(&repo_root, &base) })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    let (merge_base, commits) = match result {
+                        Ok((merge_base, commits)) => (Some(merge_base), commits),
+                        Err(err) => {
+                            window.push_toast(Toast::error(format!("读取提交栈失败：{err}")), cx);
+                            (None, Vec::new())
+                        }
+                    };
+
+                    this.update(cx, |this, cx| {
+                        let Some(stack) = this.commit_stack.as_mut() else {
+                            return;
+                        };
+                        stack.loading = false;
+                        stack.merge_base = merge_base;
+                        stack.editing_hash = None;
+                        stack.original_order =
+                            commits.iter().map(|commit| commit.hash.clone()).collect();
+                        let items: Vec<DndListItem<StackCommit>> = commits
+                            .into_iter()
+                            .map(|commit| {
+                                DndListItem::new(
+                                    commit.hash.clone(),
+                                    commit.subject.clone(),
+                                    commit,
+                                )
+                            })
+                            .collect();
+                        stack.list.update(cx, |list, cx| list.set_items(items, cx));
+                        stack.message_input.update(cx, |state, cx| {
+                            state.set_value(String::new(), window, cx);
+                        });
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    /// 修改栈中 `hash` 对应提交的编辑状态，保持当前顺序和选中项。
+    fn update_stack_commit(
+        &mut self,
+        hash: &str,
+        update: impl FnOnce(&mut StackCommit),
+        cx: &mut Context<Self>,
+    ) {
+        let Some(stack) = self.commit_stack.as_ref() else {
+            return;
+        };
+        stack.list.update(cx, |list, cx| {
+            let selected = list.selected_index();
+            let mut items = list.items_ref().to_vec();
+            if let Some(item) = items.iter_mut().find(|item| item.data.hash == hash) {
+                update(&mut item.data);
+            }
+            list.set_items(items, cx);
+            list.set_selected_index(selected, cx);
+        });
+        cx.notify();
+    }
+
+    fn toggle_stack_action(&mut self, hash: &str, action: StackAction, cx: &mut Context<Self>) {
+        self.update_stack_commit(
+            hash,
+            |commit| {
+                commit.action = if commit.action == action {
+                    StackAction::Pick
+                } else {
+                    action
+                };
+            },
+            cx,
+        );
+    }
+
+    fn save_stack_message(&mut self, cx: &mut Context<Self>) {
+        let Some(stack) = self.commit_stack.as_ref() else {
+            return;
+        };
+        let Some(hash) = stack.editing_hash.clone() else {
+            return;
+        };
+        let message = stack.message_input.read(cx).value().trim_end().to_string();
+        self.update_stack_commit(
+            &hash,
+            |commit| {
+                commit.edited_message =
+                    (!message.is_empty() && message != commit.message).then_some(message);
+            },
+            cx,
+        );
+    }
+
+    /// 选中项变化时把对应提交的信息载入编辑框。
+    fn sync_stack_message_input(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(stack) = self.commit_stack.as_mut() else {
+            return;
+        };
+        let list = stack.list.read(cx);
+        let selected = list
+            .selected_index()
+            .and_then(|index| list.items_ref().get(index))
+            .map(|item| item.data.clone());
+        let selected_hash = selected.as_ref().map(|commit| commit.hash.clone());
+        if selected_hash == stack.editing_hash {
+            return;
+        }
+
+        stack.editing_hash = selected_hash;
+        let message = selected
+            .map(|commit| commit.edited_message.unwrap_or(commit.message))
+            .unwrap_or_default();
+        stack.message_input.update(cx, |state, cx| {
+            state.set_value(message, window, cx);
+        });
+    }
+
+    /// 按当前编辑结果执行一次非交互的 `git rebase -i`。
+    fn apply_commit_stack(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(stack) = self.commit_stack.as_mut() else {
+            return;
+        };
+        if stack.running {
+            return;
+        }
+        let Some(merge_base) = stack.merge_base.clone() else {
+            return;
+        };
+        let commits: Vec<StackCommit> = stack
+            .list
+            .read(cx)
+            .items_ref()
+            .iter()
+            .map(|item| item.data.clone())
+            .collect();
+        if commits.is_empty() {
+            return;
+        }
+        if commits
+            .iter()
+            .find(|commit| commit.action != StackAction::Drop)
+            .is_some_and(|commit| commit.action == StackAction::Squash)
+        {
+            window.push_toast(
+                Toast::warning("第一个保留的提交不能 squash：它前面没有可以合并进去的提交"),
+                cx,
+            );
+            return;
+        }
+
+        stack.running = true;
+        cx.notify();
+
+        let this = cx.entity();
+        let repo_root = self.repo_root.clone();
+        window.push_toast(Toast::info(format!("git rebase -i {merge_base}")), cx);
+
+        cx.spawn_in(window, async move |_, window| {
+            let (result, entries) = window
+                .background_executor()
+                .spawn(async move {
+                    let result = run_commit_stack_rebase(&repo_root, &merge_base, &commits);
+                    let entries = fetch_git_status(&repo_root);
+                    (result, entries)
+                })
+                .await;
+
+            window
+                .update(|window, cx| {
+                    match &result {
+                        Ok(()) => window.push_toast(
+                            Toast::success("提交栈已更新"),
+                            cx,
+                        ),
+                        Err(err) => window.push_toast(
+                            Toast::error(format!(
+                                "rebase 失败：{err}（如已暂停，可在状态列表处理冲突后 git rebase --continue，或 git rebase --abort）"
+                            )),
+                            cx,
+                        ),
+                    }
+
+                    this.update(cx, |this, cx| {
+                        if let Ok(entries) = entries {
+                            this.set_status_entries(entries);
+                        }
+                        if let Some(stack) = this.commit_stack.as_mut() {
+                            stack.running = false;
+                        }
+                        this.reload_commit_stack(window, cx);
+                        cx.notify();
+                    });
+                })
+                .ok();
+
+            Some(())
+        })
+        .detach();
+    }
+
+    fn close_commit_failure_overlay(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.commit_failure_overlay = None;
+        window.focus(&self.focus_handle);
+        cx.notify();
+    }
+
+    fn enqueue_askpass_requests(
+        &mut self,
+        requests: Vec<askpass::AskpassRequest>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.askpass_queue.extend(requests);
+        if self.askpass_overlay.is_none() {
+            self.show_next_askpass_request(window, cx);
+        }
+    }
+
+    fn show_next_askpass_request(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(request) = self.askpass_queue.pop_front() else {
+            self.askpass_overlay = None;
+            window.focus(&self.focus_handle);
+            cx.notify();
+            return;
+        };
+
+        let secret = request.is_secret();
+        let input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .masked(secret)
+                .placeholder(if secret {
+                    "密码 / 口令"
+                } else {
+                    "输入内容"
+                })
+                .default_value("")
+        });
+        input.update(cx, |state, cx| state.focus(window, cx));
+
+        self.askpass_overlay = Some(AskpassOverlayState { request, input });
+        cx.notify();
+    }
+
+    fn finish_askpass_request(
+        &mut self,
+        submit: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(overlay) = self.askpass_overlay.take() else {
+            return;
+        };
+
+        let answer = submit.then(|| overlay.input.read(cx).value().to_string());
+        overlay.request.respond(answer);
+        self.show_next_askpass_request(window, cx);
+    }
+
+    fn handle_askpass_key(
+        &mut self,
+        event: &KeyDownEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        if self.askpass_overlay.is_none() {
+            return false;
+        }
+
+        match event.keystroke.key.as_str() {
+            "escape" => {
+                self.finish_askpass_request(false, window, cx);
+                true
+            }
+            "enter" => {
+                self.finish_askpass_request(true, window, cx);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn render_status_list(&mut self, window: &mut Window, cx: &mut Context<Self>) -> Div {
+        self.request_conflict_marker_counts(window, cx);
+        self.request_untracked_dir_files(window, cx);
+        self.request_diff_preload(cx);
+        self.request_trailer_suggestions(window, cx);
+        let counts = self.status_counts;
+        let remaining_conflicts: usize = self.conflict_marker_counts.values().sum();
+        let filtered_count = self
+            .files
+            .iter()
+            .filter(|entry| matches_filter(entry, self.status_filter))
+            .count();
+
+        let header: SharedString = if !self.git_available {
+            "未检测到 git 命令：仅可运行 Diff/Conflict Demo（请安装 git 或配置 PATH）".into()
+        } else if self.loading {
+            "正在加载 git 状态…".into()
+        } else {
+            format!("显示 {} / {} 个文件", filtered_count, counts.all).into()
+        };
+
+        let demo_button = Button::new("open-demo")
+            .label("打开 Diff Demo")
+            .ghost()
+            .on_click(cx.listener(|this, _, _window, cx| {
+                this.open_demo();
+                cx.notify();
+            }));
+
+        let large_demo_button = Button::new("open-large-demo")
+            .label("打开 Large Diff Demo")
+            .ghost()
+            .on_click(cx.listener(|this, _, window, cx| {
+                this.open_large_demo(window, cx);
+                cx.notify();
+            }));
+
+        let reflog_button = Button::new("open-reflog")
+            .label("Reflog")
+            .ghost()
+            .disabled(!self.git_available)
+            .on_click(cx.listener(|this, _, window, cx| {
+                this.open_reflog_view(window, cx);
+            }));
+
+        let bisect_button = Button::new("open-bisect")
+            .label("Bisect")
+            .ghost()
+            .disabled(!self.git_available)
+            .on_click(cx.listener(|this, _, window, cx| {
+                this.open_bisect_view(window, cx);
+            }));
+
+        let commit_stack_button = Button::new("open-commit-stack")
+            .label("提交栈")
+            .ghost()
+            .disabled(!self.git_available)
+            .on_click(cx.listener(|this, _, window, cx| {
+                this.open_commit_stack_view(window, cx);
+            }));
+
+        let branch_compare_button = Button::new("open-branch-compare")
+            .label("对比分支")
+            .ghost()
+            .disabled(!self.git_available)
+            .on_click(cx.listener(|this, _, window, cx| {
+                this.open_branch_compare_view(window, cx);
+            }));
+
+        let pull_requests_button = Button::new("open-pull-requests")
+            .label("Pull Request")
+            .ghost()
+            .disabled(!self.git_available)
+            .on_click(cx.listener(|this, _, window, cx| {
+                this.open_pull_requests_view(window, cx);
+            }));
+
+        let repo_stats_button = Button::new("open-repo-stats")
+            .label("统计")
+            .ghost()
+            .disabled(!self.git_available)
+            .on_click(cx.listener(|this, _, window, cx| {
+                this.open_repo_stats_view(window, cx);
+            }));
+
+        let conflict_demo_button = Button::new("open-conflict-demo")
+            .label("打开 Conflict Demo")
+            .ghost()
+            .on_click(cx.listener(|this, _, window, cx| {
+                this.open_conflict_demo(window, cx);
+                cx.notify();
+            }));
+
+        let filter_button = |filter: StatusFilter, id: &'static str, label: String| {
+            let mut button = Button::new(id).label(label);
+            if self.status_filter == filter {
+                button = button.primary();
+            } else {
+                button = button
+                    .ghost()
+                    .on_click(cx.listener(move |this, _, _window, cx| {
+                        this.status_filter = filter;
+                        cx.notify();
+                    }));
+            }
+            button
+        };
+
+        let filter_bar = div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap(px(6.))
+            .child(filter_button(
+                StatusFilter::All,
+                "filter-all",
+                format!("All {}", counts.all),
+            ))
+            .child(filter_button(
+                StatusFilter::Conflicts,
+                "filter-conflicts",
+                if remaining_conflicts > 0 {
+                    format!("Conflicts {} · {remaining_conflicts} 处", counts.conflicts)
+                } else {
+                    format!("Conflicts {}", counts.conflicts)
+                },
+            ))
+            .child(filter_button(
+                StatusFilter::Staged,
+                "filter-staged",
+                format!("Staged {}", counts.staged),
+            ))
+            .child(filter_button(
+                StatusFilter::Unstaged,
+                "filter-unstaged",
+                format!("Unstaged {}", counts.unstaged),
+            ))
+            .child(filter_button(
+                StatusFilter::Untracked,
+                "filter-untracked",
+                format!("Untracked {}", counts.untracked),
+            ));
+
+        let amending = self.amend.is_some();
+        let can_commit = self.git_available
+            && !self.loading
+            && !self.committing
+            && (counts.staged > 0 || amending);
+        let amend_files = self.amend.as_ref().map(|amend| {
+            let header: SharedString = if amend.loading {
+                "正在读取上一次提交…".into()
+            } else {
+                format!(
+                    "上一次提交包含 {} 个文件（点击查看 HEAD^ ↔ 暂存区）",
+                    amend.files.len()
+                )
+                .into()
+            };
+            div()
+                .flex()
+                .flex_col()
+                .gap(px(2.))
+                .child(
+                    div()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(header),
+                )
+                .children(amend.files.iter().enumerate().map(|(index, entry)| {
+                    let path = entry.path.clone();
+                    let status = entry.status.clone();
+                    Button::new(("amend-file", index))
+                        .label(format!("{status} {path}"))
+                        .ghost()
+                        .w_full()
+                        .on_click(cx.listener(move |this, _, window, cx| {
+                            this.open_file_diff_with_refs(
+                                path.clone(),
+                                Some(status.clone()),
+                                "HEAD^".to_string(),
+                                "INDEX".to_string(),
+                                window,
+                                cx,
+                            );
+                            cx.notify();
+                        }))
+                }))
+        });
+        let commit_panel = div()
+            .flex()
+            .flex_col()
+            .gap(px(6.))
+            .p(px(8.))
+            .border_1()
+            .border_color(cx.theme().border.alpha(0.6))
+            .rounded(cx.theme().radius)
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .gap(px(8.))
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(if amending {
+                                format!("修改上一次提交（amend，已暂存 {} 个文件）", counts.staged)
+                            } else {
+                                format!("提交 {} 个已暂存文件", counts.staged)
+                            }),
+                    )
+                    .child(
+                        div()
+                            .flex()
+                            .flex_row()
+                            .items_center()
+                            .gap(px(6.))
+                            .child(
+                                Button::new("commit-amend-toggle")
+                                    .label("Amend")
+                                    .when(amending, |this| this.primary())
+                                    .when(!amending, |this| this.ghost())
+                                    .tooltip("修改上一次提交：载入其提交信息，并改为对比 HEAD^")
+                                    .disabled(!self.git_available || self.committing)
+                                    .on_click(cx.listener(move |this, _, window, cx| {
+                                        this.set_amend(!amending, window, cx);
+                                    })),
+                            )
+                            .child(self.render_trailer_menu(cx))
+                            .child(
+                                Button::new("commit-export-patch")
+                                    .label("导出 patch")
+                                    .ghost()
+                                    .tooltip("把将要提交的全部变更导出为 .patch 文件")
+                                    .disabled(
+                                        !self.git_available
+                                            || self.loading
+                                            || (counts.staged == 0 && !amending),
+                                    )
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        this.export_staged_patch(window, cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("commit-staged")
+                                    .label(if self.committing {
+                                        "提交中…"
+                                    } else if amending {
+                                        "修改提交"
+                                    } else {
+                                        "提交"
+                                    })
+                                    .primary()
+                                    .disabled(!can_commit)
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        this.commit_staged_changes(false, window, cx);
+                                    })),
+                            ),
+                    ),
+            )
+            .child(
+                div()
+                    .h(px(96.))
+                    .child(Input::new(&self.commit_message_input).w_full().h_full()),
+            )
+            .children(amend_files);
+
+        let list: Vec<AnyElement> = if self.loading {
+            vec![div().child("加载中…").into_any_element()]
+        } else if self.files.is_empty() {
+            vec![div().child("没有检测到变更文件").into_any_element()]
+        } else {
+            self.files
+                .iter()
+                .filter(|entry| matches_filter(entry, self.status_filter))
+                .enumerate()
+                .map(|(index, entry)| {
+                    if is_untracked_dir(entry) {
+                        return self
+                            .render_untracked_dir(index, &entry.path, cx)
+                            .into_any_element();
+                    }
+
+                    let path = entry.path.clone();
+                    let status = entry.status.clone();
+                    let untracked = is_untracked_status(&status);
+                    let remaining = self
+                        .conflict_marker_counts
+                        .get(&path)
+                        .filter(|_| is_conflict_status(&status));
+                    let label = match remaining {
+                        Some(0) => format!("{status} {path}（冲突标记已清除，待 git add）"),
+                        Some(count) => format!("{status} {path}（剩余 {count} 处冲突）"),
+                        None => format!("{status} {path}"),
+                    };
+                    let file_button =
+                        Button::new(("file", index))
+                            .label(label)
+                            .w_full()
+                            .on_click(cx.listener(move |this, _, window, cx| {
+                                tracing::debug!("打开文件: {status} {path}");
+                                this.open_file(path.clone(), status.clone(), window, cx);
+                                cx.notify();
+                            }));
+                    if !untracked {
+                        return file_button.into_any_element();
+                    }
+
+                    div()
+                        .flex()
+                        .flex_row()
+                        .items_center()
+                        .gap(px(6.))
+                        .child(div().flex_1().min_w(px(0.)).child(file_button))
+                        .child(self.render_ignore_menu(index, entry.path.clone(), cx))
+                        .into_any_element()
+                })
+                .collect()
+        };
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.))
+            .p(px(12.))
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .gap(px(8.))
+                    .child(div().child(header))
+                    .child(
+                        div()
+                            .flex()
+                            .flex_row()
+                            .gap(px(8.))
+                            .child(demo_button)
+                            .child(large_demo_button)
+                            .child(conflict_demo_button)
+                            .child(reflog_button)
+                            .child(bisect_button)
+                            .child(commit_stack_button)
+                            .child(branch_compare_button)
+                            .child(pull_requests_button)
+                            .child(repo_stats_button)
+                            .child(self.render_apply_patch_menu(cx))
+                            .child(
+                                NotificationBell::new(&self.notification_center)
+                                    .tooltip("通知记录"),
+                            ),
+                    ),
+            )
+            .child(filter_bar)
+            .child(commit_panel)
+            .child(div().flex_col().gap(px(6.)).children(list))
+    }
+
+    /// 未跟踪目录折叠成一个节点，展开后列出其中的文件。
+    fn render_untracked_dir(&self, index: usize, dir: &str, cx: &Context<Self>) -> Div {
+        let theme = cx.theme();
+        let files = self.untracked_dir_files.get(dir);
+        let expanded = self.expanded_untracked_dirs.contains(dir);
+        let confirm_delete = self.confirm_delete_untracked_dir.as_deref() == Some(dir);
+        let label = match files {
+            Some(files) => format!(
+                "{} ?? {dir}（{} 个文件）",
+                if expanded { "▾" } else { "▸" },
+                files.len()
+            ),
+            None => format!("▸ ?? {dir}（统计中…）"),
+        };
+
+        let toggle_dir = dir.to_string();
+        let stage_dir = dir.to_string();
+        let delete_dir = dir.to_string();
+        let header = div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap(px(6.))
+            .child(
+                div().flex_1().min_w(px(0.)).child(
+                    Button::new(("untracked-dir", index))
+                        .label(label)
+                        .w_full()
+                        .on_click(cx.listener(move |this, _, _window, cx| {
+                            this.toggle_untracked_dir(&toggle_dir);
+                            cx.notify();
+                        })),
+                ),
+            )
+            .child(
+                Button::new(("untracked-dir-add", index))
+                    .label("全部添加")
+                    .ghost()
+                    .disabled(!self.git_available)
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        this.stage_untracked_dir(stage_dir.clone(), window, cx);
+                    })),
+            )
+            .child(self.render_ignore_menu(index, dir.to_string(), cx))
+            .child(
+                Button::new(("untracked-dir-delete", index))
+                    .label(if confirm_delete {
+                        "确认删除？"
+                    } else {
+                        "删除目录"
+                    })
+                    .ghost()
+                    .when(confirm_delete, |this| this.danger())
+                    .tooltip("移到 .git/git-viewer/trash，可通过“撤销回滚”恢复")
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        this.delete_untracked_dir(delete_dir.clone(), window, cx);
+                    })),
+            );
+
+        let children = files.filter(|_| expanded).map(|files| {
+            let hidden = files.len().saturating_sub(UNTRACKED_DIR_PREVIEW_LIMIT);
+            div()
+                .id(("untracked-dir-files", index))
+                .flex()
+                .flex_col()
+                .gap(px(4.))
+                .pl(px(20.))
+                .children(
+                    files
+                        .iter()
+                        .take(UNTRACKED_DIR_PREVIEW_LIMIT)
+                        .enumerate()
+                        .map(|(file_index, path)| {
+                            let path = path.clone();
+                            Button::new(("untracked-dir-file", file_index))
+                                .label(format!("?? {path}"))
+                                .ghost()
+                                .w_full()
+                                .on_click(cx.listener(move |this, _, window, cx| {
+                                    this.open_file(path.clone(), "??".to_string(), window, cx);
+                                    cx.notify();
+                                }))
+                        }),
+                )
+                .when(hidden > 0, |this| {
+                    this.child(
+                        div()
+                            .text_xs()
+                            .text_color(theme.muted_foreground)
+                            .child(format!("还有 {hidden} 个文件未列出")),
+                    )
+                })
+        });
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(4.))
+            .child(header)
+            .children(children)
+    }
+
+    fn render_trailer_menu(&self, cx: &Context<Self>) -> impl IntoElement {
+        let app = cx.entity();
+        let suggestions = self.trailer_suggestions.clone().unwrap_or_default();
+        let loading = self.trailer_suggestions_loading;
+
+        Popover::new("commit-trailer-menu")
+            .appearance(false)
+            .trigger(
+                Button::new("commit-trailer-menu-trigger")
+                    .label("Trailer…")
+                    .ghost()
+                    .tooltip("在提交信息末尾追加 Co-authored-by / Signed-off-by / issue 引用")
+                    .disabled(!self.git_available || self.committing)
+                    .on_click(|_, _, _| {}),
+            )
+            .content(move |_, _window, cx| {
+                let theme = cx.theme();
+                let popover = cx.entity();
+
+                let make_action = |id: (&'static str, usize), trailer: String| {
+                    let app = app.clone();
+                    let popover = popover.clone();
+                    Button::new(id)
+                        .label(trailer.clone())
+                        .ghost()
+                        .w_full()
+                        .on_click(move |_, window, cx| {
+                            app.update(cx, |this, cx| {
+                                this.insert_commit_trailer(&trailer, window, cx);
+                            });
+                            popover.update(cx, |state, cx| state.dismiss(window, cx));
+                        })
+                        .into_any_element()
+                };
+
+                let mut actions = Vec::new();
+                if let Some(identity) = suggestions.identity.as_ref() {
+                    actions.push(make_action(
+                        ("trailer-signoff", 0),
+                        format!("Signed-off-by: {identity}"),
+                    ));
+                }
+                if let Some(issue) = suggestions.issue.as_ref() {
+                    actions.push(make_action(("trailer-refs", 0), format!("Refs: #{issue}")));
+                    actions.push(make_action(
+                        ("trailer-fixes", 0),
+                        format!("Fixes: #{issue}"),
+                    ));
+                }
+                for (index, collaborator) in suggestions.collaborators.iter().enumerate() {
+                    actions.push(make_action(
+                        ("trailer-co-author", index),
+                        format!("Co-authored-by: {collaborator}"),
+                    ));
+                }
+                if actions.is_empty() {
+                    actions.push(
+                        div()
+                            .text_xs()
+                            .text_color(theme.muted_foreground)
+                            .child(if loading {
+                                "正在读取最近协作者…"
+                            } else {
+                                "没有可用的 trailer"
+                            })
+                            .into_any_element(),
+                    );
+                }
+
+                div()
+                    .p(px(8.))
+                    .bg(theme.popover)
+                    .border_1()
+                    .border_color(theme.border)
+                    .rounded(theme.radius)
+                    .shadow_md()
+                    .flex()
+                    .flex_col()
+                    .gap(px(6.))
+                    .children(actions)
+            })
+    }
+
+    fn render_apply_patch_menu(&self, cx: &Context<Self>) -> impl IntoElement {
+        let app = cx.entity();
+
+        Popover::new("apply-patch-menu")
+            .appearance(false)
+            .trigger(
+                Button::new("apply-patch-menu-trigger")
+                    .label("应用 Patch…")
+                    .ghost()
+                    .disabled(!self.git_available)
+                    .on_click(|_, _, _| {}),
+            )
+            .content(move |_, _window, cx| {
+                let theme = cx.theme();
+                let popover = cx.entity();
+
+                let make_action =
+                    |id: &'static str, label: &'static str, from_file: bool| {
+                        let app = app.clone();
+                        let popover = popover.clone();
+                        Button::new(id).label(label).ghost().w_full().on_click(
+                            move |_, window, cx| {
+                                app.update(cx, |this, cx| {
+                                    if from_file {
+                                        this.open_patch_from_file(window, cx);
+                                    } else {
+                                        this.open_patch_from_clipboard(window, cx);
+                                    }
+                                });
+                                popover.update(cx, |state, cx| state.dismiss(window, cx));
+                            },
+                        )
+                    };
+
+                div()
+                    .p(px(8.))
+                    .bg(theme.popover)
+                    .border_1()
+                    .border_color(theme.border)
+                    .rounded(theme.radius)
+                    .shadow_md()
+                    .flex()
+                    .flex_col()
+                    .gap(px(6.))
+                    .child(make_action("apply-patch-file", "从文件…", true))
+                    .child(make_action("apply-patch-clipboard", "从剪贴板", false))
+            })
+    }
+
+    fn render_ignore_menu(
+        &self,
+        index: usize,
+        path: String,
+        cx: &Context<Self>,
+    ) -> impl IntoElement {
+        let app = cx.entity();
+        let has_extension = gitignore_pattern(&path, IgnoreScope::Extension).is_some();
+        let has_directory = gitignore_pattern(&path, IgnoreScope::Directory).is_some();
+
+        Popover::new(("ignore-menu", index))
+            .appearance(false)
+            .trigger(
+                Button::new(("ignore-menu-trigger", index))
+                    .label("忽略…")
+                    .ghost()
+                    .disabled(!self.git_available)
+                    .on_click(|_, _, _| {}),
+            )
+            .content(move |_, _window, cx| {
+                let theme = cx.theme();
+                let popover = cx.entity();
+
+                let make_action = |id: &'static str, scope: IgnoreScope, target: IgnoreFile| {
+                    let disabled = match scope {
+                        IgnoreScope::File => false,
+                        IgnoreScope::Extension => !has_extension,
+                        IgnoreScope::Directory => !has_directory,
+                    };
+                    let app = app.clone();
+                    let popover = popover.clone();
+                    let path = path.clone();
+                    Button::new(id)
+                        .label(scope.label())
+                        .ghost()
+                        .disabled(disabled)
+                        .w_full()
+                        .on_click(move |_, window, cx| {
+                            app.update(cx, |this, cx| {
+                                this.ignore_untracked_path(path.clone(), scope, target, window, cx);
+                            });
+                            popover.update(cx, |state, cx| state.dismiss(window, cx));
+                        })
+                };
+
+                let section = |label: &'static str| {
+                    div()
+                        .px(px(4.))
+                        .py(px(2.))
+                        .text_xs()
+                        .text_color(theme.muted_foreground)
+                        .child(label)
+                };
+
+                div()
+                    .p(px(8.))
+                    .bg(theme.popover)
+                    .border_1()
+                    .border_color(theme.border)
+                    .rounded(theme.radius)
+                    .shadow_md()
+                    .flex()
+                    .flex_col()
+                    .gap(px(6.))
+                    .child(section("写入 .gitignore"))
+                    .child(make_action(
+                        "ignore-file",
+                        IgnoreScope::File,
+                        IgnoreFile::Gitignore,
+                    ))
+                    .child(make_action(
+                        "ignore-extension",
+                        IgnoreScope::Extension,
+                        IgnoreFile::Gitignore,
+                    ))
+                    .child(make_action(
+                        "ignore-directory",
+                        IgnoreScope::Directory,
+                        IgnoreFile::Gitignore,
+                    ))
+                    .child(div().h(px(1.)).bg(theme.border.alpha(0.4)))
+                    .child(section("仅本地（.git/info/exclude）"))
+                    .child(make_action(
+                        "exclude-file",
+                        IgnoreScope::File,
+                        IgnoreFile::InfoExclude,
+                    ))
+                    .child(make_action(
+                        "exclude-extension",
+                        IgnoreScope::Extension,
+                        IgnoreFile::InfoExclude,
+                    ))
+                    .child(make_action(
+                        "exclude-directory",
+                        IgnoreScope::Directory,
+                        IgnoreFile::InfoExclude,
+                    ))
+            })
+    }
+
+    fn render_diff_view(&mut self, window: &mut Window, cx: &mut Context<Self>) -> Div {
+        let Some(diff_view) = self.diff_view.as_mut() else {
+            return div().p(px(12.)).child("No diff view");
+        };
+
+        let view_mode = self.view_mode;
+        // 新打开的 diff 默认按 hunk 折叠，这里补上按函数折叠的设置。
+        if diff_view.fold_by_function != self.fold_by_function {
+            diff_view.fold_by_function = self.fold_by_function;
+            diff_view.rebuild_rows(view_mode);
+        }
+        let inline_mode = view_mode == DiffViewMode::Inline;
+        let two_pane = matches!(self.split_layout, SplitLayout::TwoPane);
+        let title = diff_view.title.clone();
+        // 重命名时标题是 `旧路径 → 新路径`，仍按原样显示文本。
+        let path_breadcrumb = diff_view
+            .path
+            .as_deref()
+            .filter(|_| !title.contains(" → "))
+            .map(|path| {
+                let status = diff_view.status.clone().unwrap_or_default();
+                (
+                    status,
+                    render_path_breadcrumb(&self.files, path, cx.entity()),
+                )
+            });
+        let encoding_label = diff_view.encoding.map(|encoding| encoding.label());
+        let is_markdown = diff_view.path.as_deref().is_some_and(is_markdown_path);
+        let markdown_preview_on = self.markdown_preview;
+        let markdown_preview = if markdown_preview_on && is_markdown {
+            // 相对路径的图片按文件所在目录解析。
+            let base_dir = diff_view
+                .path
+                .as_deref()
+                .and_then(|path| self.repo_root.join(path).parent().map(Path::to_path_buf));
+            let view = match self.markdown_view.clone() {
+                Some(view) => {
+                    if view.read(cx).markdown() != diff_view.new_text {
+                        let text = diff_view.new_text.clone();
+                        view.update(cx, |view, cx| view.set_markdown(text, base_dir, cx));
+                    }
+                    view
+                }
+                None => {
+                    let text = diff_view.new_text.clone();
+                    let view = cx.new(|cx| MarkdownView::new(text, base_dir, window, cx));
+                    self.markdown_view = Some(view.clone());
+                    view
+                }
+            };
+            Some(view)
+        } else {
+            None
+        };
+        let compare_target = diff_view.compare_target.clone();
+        let ignore_whitespace = self.diff_options.ignore_whitespace;
+        let ignore_eol = self.diff_options.ignore_eol;
+        let eol_change = diff_view.eol_change();
+        let fold_by_function = self.fold_by_function;
+        let context_lines = self.diff_options.context_lines;
+        let hunk_count = diff_view.hunk_rows.len();
+        let can_prev_hunk = diff_view.current_hunk > 0;
+        let can_next_hunk = diff_view.current_hunk + 1 < diff_view.hunk_rows.len();
+        let file_status = diff_view.status.as_deref().unwrap_or_default();
+        let has_file_path = diff_view.path.is_some();
+        let git_available = self.git_available;
+        let can_stage = git_available
+            && has_file_path
+            && (is_untracked_status(file_status)
+                || status_xy(file_status).is_some_and(|(_, y)| y != ' ' && y != '?' && y != '!'));
+        let can_unstage = git_available
+            && has_file_path
+            && status_xy(file_status).is_some_and(|(x, _)| x != ' ' && x != '?' && x != '!');
+        let has_hunks = hunk_count > 0;
+        let is_lfs = diff_view.lfs.is_some();
+        let can_stage_hunk = git_available
+            && has_file_path
+            && has_hunks
+            && !is_lfs
+            && !is_untracked_status(file_status)
+            && compare_target == CompareTarget::IndexToWorktree;
+        let can_revert_hunk = can_stage_hunk;
+        let can_undo_rollback = self.last_rollback.is_some();
+        let can_normalize_eol = git_available
+            && has_file_path
+            && !is_lfs
+            && matches!(
+                compare_target,
+                CompareTarget::HeadToWorktree | CompareTarget::IndexToWorktree
+            );
+        let can_unstage_hunk = git_available
+            && has_file_path
+            && has_hunks
+            && !is_lfs
+            && compare_target == CompareTarget::HeadToIndex;
+        let rows_len = diff_view.rows.len();
+        let search_input = self.diff_search_input.clone();
+        let search_query = search_input.read(cx).value().to_string();
+        let search_options = self.diff_search_options;
+        let search_rows = diff_view.search_rows(&search_query, search_options);
+        let search_current = diff_view.search.as_ref().and_then(|search| search.current);
+        let search_error = diff_view
+            .search
+            .as_ref()
+            .and_then(|search| search.error.clone());
+        let minimap_marks = diff_view.minimap_marks();
+        let scroll_handle = diff_view.scroll_handle.clone();
+        let scroll_state = diff_view.scroll_state.clone();
+        let pane_scroll_linked = self.pane_scroll_linked;
+        let new_pane_scroll_handle = if pane_scroll_linked {
+            scroll_handle.clone()
+        } else {
+            diff_view.new_pane_scroll_handle.clone()
+        };
+        let new_pane_scroll_state = diff_view.new_pane_scroll_state.clone();
+        let row_height = window.line_height() + px(4.);
+        let item_sizes = diff_view.item_sizes(row_height);
+
+        let app = cx.entity();
+        let compare_left_input = self.compare_left_input.clone();
+        let compare_right_input = self.compare_right_input.clone();
+        let compare_label: SharedString =
+            format!("对比: {}", compare_target_label(&compare_target)).into();
+        let compare_control: AnyElement = if git_available && has_file_path {
+            let app_for_menu = app.clone();
+            let active_target = compare_target.clone();
+            Popover::new("diff-compare-menu")
+                .appearance(false)
+                .trigger(
+                    Button::new("diff-compare-trigger")
+                        .label(compare_label.clone())
+                        .ghost()
+                        .tooltip("选择对比目标")
+                        .on_click(|_, _, _| {}),
+                )
+                .content(move |_, _window, cx| {
+                    let theme = cx.theme();
+                    let popover = cx.entity();
+
+                    let app_for_items = app_for_menu.clone();
+                    let popover_for_items = popover.clone();
+                    let active_target = active_target.clone();
+                    let make_item = |id: &'static str,
+                                     label: SharedString,
+                                     target: CompareTarget| {
+                        let app = app_for_items.clone();
+                        let popover = popover_for_items.clone();
+                        let is_active = active_target == target;
+
+                        div()
+                            .id(id)
+                            .flex()
+                            .items_center()
+                            .h(px(32.))
+                            .px(px(10.))
+                            .rounded(px(6.))
+                            .text_sm()
+                            .when(is_active, |this| {
+                                this.bg(theme.accent)
+                                    .text_color(theme.accent_foreground)
+                                    .cursor_default()
+                            })
+                            .when(!is_active, |this| {
+                                this.bg(theme.transparent)
+                                    .text_color(theme.popover_foreground)
+                                    .cursor_pointer()
+                                    .hover(|this| {
+                                        this.bg(theme.accent.alpha(0.4))
+                                            .text_color(theme.accent_foreground)
+                                    })
+                                    .active(|this| {
+                                        this.bg(theme.accent).text_color(theme.accent_foreground)
+                                    })
+                                    .on_mouse_down(MouseButton::Left, move |_, window, cx| {
+                                        window.prevent_default();
+                                        let target = target.clone();
+                                        app.update(cx, |this, cx| {
+                                            this.set_compare_target(target, window, cx);
+                                            cx.notify();
+                                        });
+                                        popover.update(cx, |state, cx| state.dismiss(window, cx));
+                                    })
+                            })
+                            .child(label)
+                    };
+
+                    div()
+                        .p(px(6.))
+                        .w(px(360.))
+                        .bg(theme.popover)
+                        .border_1()
+                        .border_color(theme.border)
+                        .rounded(theme.radius)
+                        .shadow_md()
+                        .flex()
+                        .flex_col()
+                        .gap(px(4.))
+                        .child(
+                            div()
+                                .px(px(6.))
+                                .py(px(4.))
+                                .text_xs()
+                                .text_color(theme.muted_foreground)
+                                .child("选择对比目标"),
+                        )
+                        .child(make_item(
+                            "diff-compare-head-worktree",
+                            "HEAD ↔ 工作区".into(),
+                            CompareTarget::HeadToWorktree,
+                        ))
+                        .child(make_item(
+                            "diff-compare-index-worktree",
+                            "暂存 ↔ 工作区".into(),
+                            CompareTarget::IndexToWorktree,
+                        ))
+                        .child(make_item(
+                            "diff-compare-head-index",
+                            "HEAD ↔ 暂存".into(),
+                            CompareTarget::HeadToIndex,
+                        ))
+                        .child(div().h(px(1.)).bg(theme.border.alpha(0.4)).my(px(4.)))
+                        .child(
+                            div()
+                                .px(px(6.))
+                                .py(px(4.))
+                                .text_xs()
+                                .text_color(theme.muted_foreground)
+                                .child("任意版本对比"),
+                        )
+                        .child(
+                            div()
+                                .px(px(6.))
+                                .flex()
+                                .flex_col()
+                                .gap(px(6.))
+                                .child(Input::new(&compare_left_input).w_full())
+                                .child(Input::new(&compare_right_input).w_full())
+                                .child(
+                                    div()
+                                        .flex()
+                                        .flex_row()
+                                        .justify_end()
+                                        .gap(px(6.))
+                                        .child(
+                                            Button::new("diff-compare-swap")
+                                                .label("交换")
+                                                .ghost()
+                                                .on_click({
+                                                    let app = app_for_menu.clone();
+                                                    move |_, window, cx| {
+                                                        app.update(cx, |this, cx| {
+                                                            this.swap_compare_refs_inputs(
+                                                                window, cx,
+                                                            );
+                                                        });
+                                                    }
+                                                }),
+                                        )
+                                        .child(
+                                            Button::new("diff-compare-apply")
+                                                .label("应用")
+                                                .primary()
+                                                .on_click({
+                                                    let app = app_for_menu.clone();
+                                                    let popover = popover.clone();
+                                                    move |_, window, cx| {
+                                                        app.update(cx, |this, cx| {
+                                                            this.apply_compare_refs_from_inputs(
+                                                                window, cx,
+                                                            );
+                                                            cx.notify();
+                                                        });
+                                                        popover.update(cx, |state, cx| {
+                                                            state.dismiss(window, cx)
+                                                        });
+                                                    }
+                                                }),
+                                        ),
+                                ),
+                        )
+                        .child(div().h(px(1.)).bg(theme.border.alpha(0.4)).my(px(4.)))
+                        .child(
+                            Button::new("diff-history-open")
+                                .label("从历史选择 commit…")
+                                .ghost()
+                                .w_full()
+                                .on_click({
+                                    let app = app_for_menu.clone();
+                                    let popover = popover.clone();
+                                    move |_, window, cx| {
+                                        app.update(cx, |this, cx| {
+                                            this.open_file_history_overlay(window, cx);
+                                        });
+                                        popover.update(cx, |state, cx| state.dismiss(window, cx));
+                                    }
+                                }),
+                        )
+                })
+                .into_any_element()
+        } else {
+            Button::new("diff-compare-disabled")
+                .label(compare_label)
+                .ghost()
+                .disabled(true)
+                .into_any_element()
+        };
+
+        let can_expand_all = diff_view.rows.has_folds();
+        let has_selection = diff_view.selection.is_some();
+        let can_copy = has_selection || has_hunks;
+        let copy_patch_label: SharedString = if has_selection {
+            "复制选中行为 patch".into()
+        } else {
+            "复制当前 hunk 为 patch".into()
+        };
+        let copy_new_text_label: SharedString = if has_selection {
+            "复制选中行的新版本文本".into()
+        } else {
+            "复制当前 hunk 的新版本文本".into()
+        };
+
+        let stage_file_label: SharedString = if can_stage {
+            "Stage 文件".into()
+        } else if !git_available {
+            "Stage 文件（git 不可用）".into()
+        } else if !has_file_path {
+            "Stage 文件（demo 不支持）".into()
+        } else {
+            "Stage 文件（无可暂存变更）".into()
+        };
+        let unstage_file_label: SharedString = if can_unstage {
+            "Unstage 文件".into()
+        } else if !git_available {
+            "Unstage 文件（git 不可用）".into()
+        } else if !has_file_path {
+            "Unstage 文件（demo 不支持）".into()
+        } else {
+            "Unstage 文件（无可取消暂存）".into()
+        };
+
+        let stage_hunk_label: SharedString = if can_stage_hunk {
+            "Stage 当前 hunk".into()
+        } else if !git_available {
+            "Stage 当前 hunk（git 不可用）".into()
+        } else if !has_file_path {
+            "Stage 当前 hunk（demo 不支持）".into()
+        } else if is_lfs {
+            "Stage 当前 hunk（LFS 文件不支持）".into()
+        } else if !has_hunks {
+            "Stage 当前 hunk（无 hunk）".into()
+        } else if compare_target != CompareTarget::IndexToWorktree {
+            "Stage 当前 hunk（切到 暂存↔工作区）".into()
+        } else {
+            "Stage 当前 hunk（不可用）".into()
+        };
+
+        let unstage_hunk_label: SharedString = if can_unstage_hunk {
+            "Unstage 当前 hunk".into()
+        } else if !git_available {
+            "Unstage 当前 hunk（git 不可用）".into()
+        } else if !has_file_path {
+            "Unstage 当前 hunk（demo 不支持）".into()
+        } else if is_lfs {
+            "Unstage 当前 hunk（LFS 文件不支持）".into()
+        } else if !has_hunks {
+            "Unstage 当前 hunk（无 hunk）".into()
+        } else if compare_target != CompareTarget::HeadToIndex {
+            "Unstage 当前 hunk（切到 HEAD↔暂存）".into()
+        } else {
+            "Unstage 当前 hunk（不可用）".into()
+        };
+
+        let revert_hunk_label: SharedString = if can_revert_hunk {
+            "Revert 当前 hunk".into()
+        } else if !git_available {
+            "Revert 当前 hunk（git 不可用）".into()
+        } else if !has_file_path {
+            "Revert 当前 hunk（demo 不支持）".into()
+        } else if is_lfs {
+            "Revert 当前 hunk（LFS 文件不支持）".into()
+        } else if !has_hunks {
+            "Revert 当前 hunk（无 hunk）".into()
+        } else if compare_target != CompareTarget::IndexToWorktree {
+            "Revert 当前 hunk（切到 暂存↔工作区）".into()
+        } else {
+            "Revert 当前 hunk（不可用）".into()
+        };
+
+        let shortcuts_message: SharedString = concat!(
+            "快捷键：\n",
+            "Esc 返回\n",
+            "Alt+N / Alt+P 下一/上一 hunk\n",
+            "Alt+V 切换 Split/Inline\n",
+            "Alt+L 切换对齐/分栏\n",
+            "Alt+W 忽略空白\n",
+            "Alt+E 展开全部\n",
+        )
+        .into();
+
+        let more_menu = {
+            let app_for_menu = app.clone();
+            Popover::new("diff-more-menu")
+                .appearance(false)
+                .trigger(
+                    Button::new("diff-more-trigger")
+                        .label("更多")
+                        .ghost()
+                        .on_click(|_, _, _| {}),
+                )
+                .content(move |_, _window, cx| {
+                    let theme = cx.theme();
+                    let popover = cx.entity();
+
+                    let make_action = move |id: &'static str,
+                                            label: SharedString,
+                                            disabled: bool,
+                                            action: Rc<dyn Fn(&mut Window, &mut App) + 'static>| {
+                        let popover = popover.clone();
+                        Button::new(id)
+                            .label(label)
+                            .ghost()
+                            .disabled(disabled)
+                            .w_full()
+                            .on_click({
+                                let action = action.clone();
+                                move |_, window, cx| {
+                                    action(window, cx);
+                                    popover.update(cx, |state, cx| state.dismiss(window, cx));
+                                }
+                            })
+                            .into_any_element()
+                    };
+
+                    let view_toggle_split_label: SharedString = if inline_mode {
+                        "分栏（Inline 模式不可用）".into()
+                    } else if two_pane {
+                        "分栏: 开".into()
+                    } else {
+                        "分栏: 关".into()
+                    };
+                    let view_toggle_ws_label: SharedString = if ignore_whitespace {
+                        "忽略空白: 开".into()
+                    } else {
+                        "忽略空白: 关".into()
+                    };
+                    let view_toggle_eol_label: SharedString = if ignore_eol {
+                        "忽略换行符: 开".into()
+                    } else {
+                        "忽略换行符: 关".into()
+                    };
+
+                    let app_for_split = app_for_menu.clone();
+                    let toggle_split = Rc::new(move |_window: &mut Window, cx: &mut App| {
+                        app_for_split.update(cx, |this, cx| {
+                            if this.view_mode == DiffViewMode::Inline {
+                                return;
+                            }
+                            this.split_layout = match this.split_layout {
+                                SplitLayout::Aligned => SplitLayout::TwoPane,
+                                SplitLayout::TwoPane => SplitLayout::Aligned,
+                            };
+                            cx.notify();
+                        });
+                    });
+
+                    let app_for_ws = app_for_menu.clone();
+                    let toggle_ws = Rc::new(move |window: &mut Window, cx: &mut App| {
+                        app_for_ws.update(cx, |this, cx| {
+                            let next = !this.diff_options.ignore_whitespace;
+                            this.set_ignore_whitespace(next, window, cx);
+                            cx.notify();
+                        });
+                    });
+
+                    let app_for_eol = app_for_menu.clone();
+                    let toggle_eol = Rc::new(move |window: &mut Window, cx: &mut App| {
+                        app_for_eol.update(cx, |this, cx| {
+                            let next = !this.diff_options.ignore_eol;
+                            this.set_ignore_eol(next, window, cx);
+                            cx.notify();
+                        });
+                    });
+
+                    let app_for_expand = app_for_menu.clone();
+                    let expand_all = Rc::new(move |_window: &mut Window, cx: &mut App| {
+                        app_for_expand.update(cx, |this, cx| {
+                            this.expand_all_folds();
+                            cx.notify();
+                        });
+                    });
+
+                    let fold_by_function_label: SharedString = if fold_by_function {
+                        "按函数折叠: 开".into()
+                    } else {
+                        "按函数折叠: 关".into()
+                    };
+                    let app_for_fold_mode = app_for_menu.clone();
+                    let toggle_fold_by_function =
+                        Rc::new(move |_window: &mut Window, cx: &mut App| {
+                            app_for_fold_mode.update(cx, |this, cx| {
+                                this.toggle_fold_by_function();
+                                cx.notify();
+                            });
+                        });
+
+                    let shortcuts_message = shortcuts_message.clone();
+                    let show_shortcuts = Rc::new(move |window: &mut Window, cx: &mut App| {
+                        window.push_toast(Toast::info(shortcuts_message.to_string()), cx);
+                    });
+
+                    let app_for_stage_file = app_for_menu.clone();
+                    let stage_file = Rc::new(move |window: &mut Window, cx: &mut App| {
+                        app_for_stage_file.update(cx, |this, cx| {
+                            this.stage_current_file(window, cx);
+                            cx.notify();
+                        });
+                    });
+
+                    let app_for_unstage_file = app_for_menu.clone();
+                    let unstage_file = Rc::new(move |window: &mut Window, cx: &mut App| {
+                        app_for_unstage_file.update(cx, |this, cx| {
+                            this.unstage_current_file(window, cx);
+                            cx.notify();
+                        });
+                    });
+
+                    let app_for_stage_hunk = app_for_menu.clone();
+                    let stage_hunk = Rc::new(move |window: &mut Window, cx: &mut App| {
+                        app_for_stage_hunk.update(cx, |this, cx| {
+                            this.stage_current_hunk(window, cx);
+                            cx.notify();
+                        });
+                    });
+
+                    let app_for_unstage_hunk = app_for_menu.clone();
+                    let unstage_hunk = Rc::new(move |window: &mut Window, cx: &mut App| {
+                        app_for_unstage_hunk.update(cx, |this, cx| {
+                            this.unstage_current_hunk(window, cx);
+                            cx.notify();
+                        });
+                    });
+
+                    let app_for_revert_hunk = app_for_menu.clone();
+                    let revert_hunk = Rc::new(move |window: &mut Window, cx: &mut App| {
+                        app_for_revert_hunk.update(cx, |this, cx| {
+                            this.revert_current_hunk(window, cx);
+                            cx.notify();
+                        });
+                    });
+
+                    let app_for_undo_rollback = app_for_menu.clone();
+                    let undo_rollback = Rc::new(move |window: &mut Window, cx: &mut App| {
+                        app_for_undo_rollback.update(cx, |this, cx| {
+                            this.undo_last_rollback(window, cx);
+                        });
+                    });
+
+                    let app_for_copy_patch = app_for_menu.clone();
+                    let copy_patch = Rc::new(move |window: &mut Window, cx: &mut App| {
+                        app_for_copy_patch.update(cx, |this, cx| {
+                            this.copy_diff_patch(window, cx);
+                        });
+                    });
+
+                    let app_for_copy_text = app_for_menu.clone();
+                    let copy_new_text = Rc::new(move |window: &mut Window, cx: &mut App| {
+                        app_for_copy_text.update(cx, |this, cx| {
+                            this.copy_diff_new_text(window, cx);
+                        });
+                    });
+
+                    let app_for_export = app_for_menu.clone();
+                    let export_patch = Rc::new(move |window: &mut Window, cx: &mut App| {
+                        app_for_export.update(cx, |this, cx| {
+                            this.export_current_file_patch(window, cx);
+                        });
+                    });
+
+                    let app_for_editor = app_for_menu.clone();
+                    let open_in_editor = Rc::new(move |window: &mut Window, cx: &mut App| {
+                        app_for_editor.update(cx, |this, cx| {
+                            this.open_in_external_editor(window, cx);
+                        });
+                    });
+
+                    let can_toggle_split = !inline_mode;
+
+                    div()
+                        .p(px(8.))
+                        .bg(theme.popover)
+                        .border_1()
+                        .border_color(theme.border)
+                        .rounded(theme.radius)
+                        .shadow_md()
+                        .flex()
+                        .flex_col()
+                        .gap(px(6.))
+                        .child(
+                            div()
+                                .px(px(4.))
+                                .py(px(2.))
+                                .text_xs()
+                                .text_color(theme.muted_foreground)
+                                .child("视图"),
+                        )
+                        .child(make_action(
+                            "diff-more-toggle-split",
+                            view_toggle_split_label,
+                            !can_toggle_split,
+                            toggle_split,
+                        ))
+                        .child(make_action(
+                            "diff-more-toggle-ws",
+                            view_toggle_ws_label,
+                            false,
+                            toggle_ws,
+                        ))
+                        .child(make_action(
+                            "diff-more-toggle-eol",
+                            view_toggle_eol_label,
+                            ignore_whitespace,
+                            toggle_eol,
+                        ))
+                        .child(
+                            div()
+                                .flex()
+                                .flex_row()
+                                .items_center()
+                                .gap(px(8.))
+                                .px(px(6.))
+                                .child(div().text_sm().child(format!("上下文: {context_lines}")))
+                                .child(
+                                    Button::new("diff-more-context-dec")
+                                        .label("－")
+                                        .ghost()
+                                        .disabled(context_lines == 0)
+                                        .on_click({
+                                            let app = app_for_menu.clone();
+                                            move |_, window, cx| {
+                                                app.update(cx, |this, cx| {
+                                                    let next = this
+                                                        .diff_options
+                                                        .context_lines
+                                                        .saturating_sub(1);
+                                                    this.set_context_lines(next, window, cx);
+                                                    cx.notify();
+                                                });
+                                            }
+                                        }),
+                                )
+                                .child(
+                                    Button::new("diff-more-context-inc")
+                                        .label("＋")
+                                        .ghost()
+                                        .disabled(context_lines >= MAX_CONTEXT_LINES)
+                                        .on_click({
+                                            let app = app_for_menu.clone();
+                                            move |_, window, cx| {
+                                                app.update(cx, |this, cx| {
+                                                    let next = this.diff_options.context_lines + 1;
+                                                    this.set_context_lines(next, window, cx);
+                                                    cx.notify();
+                                                });
+                                            }
+                                        }),
+                                ),
+                        )
+                        .child(make_action(
+                            "diff-more-fold-by-function",
+                            fold_by_function_label,
+                            false,
+                            toggle_fold_by_function,
+                        ))
+                        .child(make_action(
+                            "diff-more-expand-all",
+                            "展开全部".into(),
+                            !can_expand_all,
+                            expand_all,
+                        ))
+                        .child(make_action(
+                            "diff-more-shortcuts",
+                            "快捷键…".into(),
+                            false,
+                            show_shortcuts,
+                        ))
+                        .child(div().h(px(1.)).bg(theme.border.alpha(0.4)))
+                        .child(
+                            div()
+                                .px(px(4.))
+                                .py(px(2.))
+                                .text_xs()
+                                .text_color(theme.muted_foreground)
+                                .child("文件"),
+                        )
+                        .child(make_action(
+                            "diff-more-open-in-editor",
+                            "在外部编辑器中打开".into(),
+                            !has_file_path,
+                            open_in_editor,
+                        ))
+                        .child(make_action(
+                            "diff-more-copy-patch",
+                            copy_patch_label.clone(),
+                            !can_copy,
+                            copy_patch,
+                        ))
+                        .child(make_action(
+                            "diff-more-copy-new-text",
+                            copy_new_text_label.clone(),
+                            !can_copy,
+                            copy_new_text,
+                        ))
+                        .child(make_action(
+                            "diff-more-export-patch",
+                            "导出 patch…".into(),
+                            !has_hunks,
+                            export_patch,
+                        ))
+                        .child(div().h(px(1.)).bg(theme.border.alpha(0.4)))
+                        .child(
+                            div()
+                                .px(px(4.))
+                                .py(px(2.))
+                                .text_xs()
+                                .text_color(theme.muted_foreground)
+                                .child("Git"),
+                        )
+                        .child(make_action(
+                            "diff-more-stage-file",
+                            stage_file_label.clone(),
+                            !can_stage,
+                            stage_file,
+                        ))
+                        .child(make_action(
+                            "diff-more-unstage-file",
+                            unstage_file_label.clone(),
+                            !can_unstage,
+                            unstage_file,
+                        ))
+                        .child(make_action(
+                            "diff-more-stage-hunk",
+                            stage_hunk_label.clone(),
+                            !can_stage_hunk,
+                            stage_hunk,
+                        ))
+                        .child(make_action(
+                            "diff-more-unstage-hunk",
+                            unstage_hunk_label.clone(),
+                            !can_unstage_hunk,
+                            unstage_hunk,
+                        ))
+                        .child(make_action(
+                            "diff-more-revert-hunk",
+                            revert_hunk_label.clone(),
+                            !can_revert_hunk,
+                            revert_hunk,
+                        ))
+                        .child(make_action(
+                            "diff-more-undo-rollback",
+                            "撤销上次回滚".into(),
+                            !can_undo_rollback,
+                            undo_rollback,
+                        ))
+                })
+        };
+
+        let toolbar = div()
+            .flex()
+            .flex_col()
+            .gap(px(8.))
+            .p(px(12.))
+            .border_b_1()
+            .border_color(cx.theme().border)
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap(px(8.))
+                    .child(
+                        Button::new("back")
+                            .label("返回")
+                            .ghost()
+                            .tooltip_with_action("返回", &Back, Some(CONTEXT))
+                            .on_click(cx.listener(|this, _, _window, cx| {
+                                this.close_diff_view();
+                                cx.notify();
+                            })),
+                    )
+                    .map(|this| match path_breadcrumb {
+                        Some((status, breadcrumb)) => this.child(
+                            div()
+                                .flex()
+                                .flex_row()
+                                .items_center()
+                                .gap(px(4.))
+                                .flex_1()
+                                .min_w(px(0.))
+                                .overflow_hidden()
+                                .when(!status.trim().is_empty(), |this| {
+                                    this.child(render_file_badge(
+                                        status.trim().to_string(),
+                                        cx.theme(),
+                                    ))
+                                })
+                                .child(breadcrumb),
+                        ),
+                        None => this.child(div().flex_1().min_w(px(0.)).truncate().child(title)),
+                    })
+                    .when(is_markdown, |this| {
+                        this.child(
+                            Button::new("markdown-preview")
+                                .label(if markdown_preview_on {
+                                    "查看 diff"
+                                } else {
+                                    "预览"
+                                })
+                                .ghost()
+                                .xsmall()
+                                .tooltip("渲染 Markdown，显示右侧版本")
+                                .on_click(cx.listener(|this, _, _window, cx| {
+                                    this.toggle_markdown_preview();
+                                    cx.notify();
+                                })),
+                        )
+                    })
+                    .when_some(eol_change, |this, (old_crlf, new_crlf)| {
+                        this.child(render_file_badge(
+                            format!(
+                                "换行符变化 {} → {}",
+                                eol_label(old_crlf),
+                                eol_label(new_crlf)
+                            ),
+                            cx.theme(),
+                        ))
+                        .when(can_normalize_eol, |this| {
+                            this.child(
+                                Button::new("normalize-eol")
+                                    .label(format!("统一为 {}", eol_label(old_crlf)))
+                                    .ghost()
+                                    .xsmall()
+                                    .tooltip("把工作区文件的换行符统一改回对比基准的风格")
+                                    .on_click(cx.listener(move |this, _, window, cx| {
+                                        this.normalize_line_endings(old_crlf, window, cx);
+                                    })),
+                            )
+                        })
+                    }),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap(px(12.))
+                    .flex_wrap()
+                    .child(
+                        div()
+                            .flex()
+                            .flex_row()
+                            .items_center()
+                            .gap(px(6.))
+                            .child({
+                                let button = Button::new("mode-split").label("Split");
+                                if view_mode == DiffViewMode::Split {
+                                    button.primary()
+                                } else {
+                                    button.ghost().on_click(cx.listener(|this, _, _window, cx| {
+                                        this.set_view_mode(DiffViewMode::Split);
+                                        cx.notify();
+                                    }))
+                                }
+                            })
+                            .child({
+                                let button = Button::new("mode-inline").label("Inline");
+                                if view_mode == DiffViewMode::Inline {
+                                    button.primary()
+                                } else {
+                                    button.ghost().on_click(cx.listener(|this, _, _window, cx| {
+                                        this.set_view_mode(DiffViewMode::Inline);
+                                        cx.notify();
+                                    }))
+                                }
+                            }),
+                    )
+                    .when(!inline_mode && two_pane, |this| {
+                        this.child(
+                            div()
+                                .flex()
+                                .flex_row()
+                                .items_center()
+                                .gap(px(6.))
+                                .child(
+                                    Button::new("pane-scroll-link")
+                                        .label(if pane_scroll_linked {
+                                            "联动滚动: 开"
+                                        } else {
+                                            "联动滚动: 关"
+                                        })
+                                        .ghost()
+                                        .tooltip("分栏时左右两侧是否一起滚动")
+                                        .on_click(cx.listener(move |this, _, _window, cx| {
+                                            this.set_pane_scroll_linked(!pane_scroll_linked);
+                                            cx.notify();
+                                        })),
+                                )
+                                .when(!pane_scroll_linked, |this| {
+                                    this.child(
+                                        Button::new("pane-scroll-sync")
+                                            .label("对齐对应行")
+                                            .ghost()
+                                            .tooltip("把右侧滚动到左侧当前行的对应行")
+                                            .on_click(cx.listener(|this, _, _window, cx| {
+                                                this.sync_pane_scroll();
+                                                cx.notify();
+                                            })),
+                                    )
+                                }),
+                        )
+                    })
+                    .child(compare_control)
+                    .child(
+                        Button::new("prev-hunk")
+                            .label("上一 hunk")
+                            .ghost()
+                            .tooltip_with_action("上一 hunk", &Prev, Some(CONTEXT))
+                            .disabled(!can_prev_hunk)
+                            .on_click(cx.listener(|this, _, _window, cx| {
+                                this.jump_hunk(-1);
+                                cx.notify();
+                            })),
+                    )
+                    .child(
+                        Button::new("next-hunk")
+                            .label("下一 hunk")
+                            .ghost()
+                            .tooltip_with_action("下一 hunk", &Next, Some(CONTEXT))
+                            .disabled(!can_next_hunk)
+                            .on_click(cx.listener(|this, _, _window, cx| {
+                                this.jump_hunk(1);
+                                cx.notify();
+                            })),
+                    )
+                    .child(
+                        div()
+                            .flex()
+                            .flex_row()
+                            .items_center()
+                            .gap(px(4.))
+                            .child(div().w(px(200.)).child(Input::new(&search_input).w_full()))
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(if search_query.trim().is_empty() {
+                                        String::new()
+                                    } else if search_error.is_some() {
+                                        "正则无效".to_string()
+                                    } else if let Some(current) = search_current {
+                                        format!("{}/{}", current + 1, search_rows.len())
+                                    } else {
+                                        format!("{} 处", search_rows.len())
+                                    }),
+                            )
+                            .child(
+                                Button::new("search-case-sensitive")
+                                    .label("Aa")
+                                    .ghost()
+                                    .when(search_options.case_sensitive, |this| this.primary())
+                                    .tooltip("区分大小写")
+                                    .on_click(cx.listener(|this, _, _window, cx| {
+                                        this.toggle_diff_search_option(
+                                            |options| {
+                                                options.case_sensitive = !options.case_sensitive
+                                            },
+                                            cx,
+                                        );
+                                        cx.notify();
+                                    })),
+                            )
+                            .child(
+                                Button::new("search-whole-word")
+                                    .label("W")
+                                    .ghost()
+                                    .when(search_options.whole_word, |this| this.primary())
+                                    .tooltip("全词匹配")
+                                    .on_click(cx.listener(|this, _, _window, cx| {
+                                        this.toggle_diff_search_option(
+                                            |options| options.whole_word = !options.whole_word,
+                                            cx,
+                                        );
+                                        cx.notify();
+                                    })),
+                            )
+                            .child(
+                                Button::new("search-regex")
+                                    .label(".*")
+                                    .ghost()
+                                    .when(search_options.regex, |this| this.primary())
+                                    .tooltip(
+                                        search_error
+                                            .unwrap_or_else(|| "使用正则表达式".to_string()),
+                                    )
+                                    .on_click(cx.listener(|this, _, _window, cx| {
+                                        this.toggle_diff_search_option(
+                                            |options| options.regex = !options.regex,
+                                            cx,
+                                        );
+                                        cx.notify();
+                                    })),
+                            )
+                            .child(
+                                Button::new("search-prev")
+                                    .label("↑")
+                                    .ghost()
+                                    .tooltip("上一个匹配")
+                                    .disabled(search_rows.is_empty())
+                                    .on_click(cx.listener(|this, _, _window, cx| {
+                                        this.jump_search_match(-1, cx);
+                                        cx.notify();
+                                    })),
+                            )
+                            .child(
+                                Button::new("search-next")
+                                    .label("↓")
+                                    .ghost()
+                                    .tooltip("下一个匹配")
+                                    .disabled(search_rows.is_empty())
+                                    .on_click(cx.listener(|this, _, _window, cx| {
+                                        this.jump_search_match(1, cx);
+                                        cx.notify();
+                                    })),
+                            ),
+                    )
+                    .child(more_menu),
+            );
+
+        let list = match view_mode {
+            DiffViewMode::Inline => v_virtual_list(
+                cx.entity(),
+                "diff-inline-list",
+                item_sizes,
+                move |this, visible_range, window, cx| {
+                    visible_range
+                        .map(|index| this.render_inline_row(index, row_height, window, cx))
+                        .collect::<Vec<_>>()
+                },
+            )
+            .track_scroll(&scroll_handle)
+            .into_any_element(),
+            DiffViewMode::Split => match self.split_layout {
+                SplitLayout::Aligned => v_virtual_list(
+                    cx.entity(),
+                    "diff-aligned-list",
+                    item_sizes,
+                    move |this, visible_range, window, cx| {
+                        visible_range
+                            .map(|index| this.render_demo_row(index, row_height, window, cx))
+                            .collect::<Vec<_>>()
+                    },
+                )
+                .track_scroll(&scroll_handle)
+                .into_any_element(),
+                SplitLayout::TwoPane => {
+                    let old_list = v_virtual_list(
+                        cx.entity(),
+                        "diff-two-pane-old",
+                        item_sizes.clone(),
+                        move |this, visible_range, window, cx| {
+                            visible_range
+                                .map(|index| {
+                                    this.render_pane_row(Side::Old, index, row_height, window, cx)
+                                })
+                                .collect::<Vec<_>>()
+                        },
+                    )
+                    .track_scroll(&scroll_handle)
+                    .into_any_element();
+
+                    let new_list = v_virtual_list(
+                        cx.entity(),
+                        "diff-two-pane-new",
+                        item_sizes,
+                        move |this, visible_range, window, cx| {
+                            visible_range
+                                .map(|index| {
+                                    this.render_pane_row(Side::New, index, row_height, window, cx)
+                                })
+                                .collect::<Vec<_>>()
+                        },
+                    )
+                    .track_scroll(&new_pane_scroll_handle)
+                    .into_any_element();
+
+                    let theme = cx.theme();
+                    let pane_header_height = window.line_height() + px(6.);
+                    let old_header = div()
+                        .h(pane_header_height)
+                        .px(px(12.))
+                        .flex()
+                        .items_center()
+                        .bg(theme.muted.alpha(0.2))
+                        .text_sm()
+                        .text_color(theme.muted_foreground)
+                        .child(compare_target_side_label(&compare_target, Side::Old));
+                    let new_header = div()
+                        .h(pane_header_height)
+                        .px(px(12.))
+                        .flex()
+                        .items_center()
+                        .bg(theme.muted.alpha(0.2))
+                        .text_sm()
+                        .text_color(theme.muted_foreground)
+                        .child(compare_target_side_label(&compare_target, Side::New));
+
+                    let sizes = PersistedPanelSizes::new("diff-two-pane", self.panel_sizes.clone());
+                    let total = window.viewport_size().width;
+                    h_resizable("diff-two-pane")
+                        .persist(&sizes)
+                        .child(
+                            sizes.panel(0, 2, total).child(
+                                div()
+                                    .flex()
+                                    .flex_col()
+                                    .size_full()
+                                    .child(old_header)
+                                    .child(div().flex_1().min_h(px(0.)).child(old_list)),
+                            ),
+                        )
+                        .child(
+                            sizes.panel(1, 2, total).child(
+                                div().flex().flex_col().size_full().child(new_header).child(
+                                    div()
+                                        .flex_1()
+                                        .min_h(px(0.))
+                                        .relative()
+                                        .child(new_list)
+                                        .when(!pane_scroll_linked, |this| {
+                                            this.child(Scrollbar::uniform_scroll(
+                                                &new_pane_scroll_state,
+                                                &new_pane_scroll_handle,
+                                            ))
+                                        }),
+                                ),
+                            ),
+                        )
+                        .into_any_element()
+                }
+            },
+        };
+
+        let hunk_position = if hunk_count == 0 {
+            "0/0".to_string()
+        } else {
+            format!("{}/{}", diff_view.current_hunk + 1, hunk_count)
+        };
+        let on_off = |value: bool| if value { "开" } else { "关" };
+        let mut status_left = vec![
+            StatusBarSegment::new(
+                "status-view-mode",
+                match view_mode {
+                    DiffViewMode::Split => "视图: Split",
+                    DiffViewMode::Inline => "视图: Inline",
+                },
+            )
+            .priority(3)
+            .tooltip("切换视图 (Alt+V)")
+            .on_click(cx.listener(|this, _, _window, cx| {
+                let next = match this.view_mode {
+                    DiffViewMode::Split => DiffViewMode::Inline,
+                    DiffViewMode::Inline => DiffViewMode::Split,
+                };
+                this.set_view_mode(next);
+                cx.notify();
+            })),
+        ];
+        if view_mode == DiffViewMode::Split {
+            status_left.push(
+                StatusBarSegment::new(
+                    "status-split-layout",
+                    format!("布局: {}", if two_pane { "分栏" } else { "对齐" }),
+                )
+                .priority(1)
+                .tooltip("切换布局 (Alt+L)")
+                .on_click(cx.listener(|this, _, _window, cx| {
+                    this.split_layout = match this.split_layout {
+                        SplitLayout::Aligned => SplitLayout::TwoPane,
+                        SplitLayout::TwoPane => SplitLayout::Aligned,
+                    };
+                    cx.notify();
+                })),
+            );
+        }
+        status_left.extend([
+            StatusBarSegment::new("status-file", format!("文件: {}", diff_view.title)).priority(-1),
+            StatusBarSegment::new(
+                "status-compare",
+                format!("对比: {}", compare_target_label(&compare_target)),
+            )
+            .priority(1),
+            StatusBarSegment::new("status-context", format!("上下文: {context_lines}")),
+            StatusBarSegment::new(
+                "status-ignore-whitespace",
+                format!("忽略空白: {}", on_off(ignore_whitespace)),
+            )
+            .tooltip("切换忽略空白 (Alt+W)")
+            .on_click(cx.listener(move |this, _, window, cx| {
+                this.set_ignore_whitespace(!ignore_whitespace, window, cx);
+            })),
+            StatusBarSegment::new(
+                "status-ignore-eol",
+                format!("忽略换行符: {}", on_off(ignore_eol)),
+            )
+            .on_click(cx.listener(move |this, _, window, cx| {
+                this.set_ignore_eol(!ignore_eol, window, cx);
+            })),
+        ]);
+
+        // 右侧从窗口边缘往里排。
+        let status_right =
+            [StatusBarSegment::new("status-hunk", format!("hunk: {hunk_position}")).priority(4)]
+                .into_iter()
+                .chain(self.current_branch.clone().map(|branch| {
+                    StatusBarSegment::new("status-branch", format!("分支: {branch}"))
+                        .tooltip("当前分支")
+                        .priority(3)
+                }))
+                .chain([
+                    StatusBarSegment::new(
+                        "status-encoding",
+                        encoding_label.unwrap_or_else(|| "UTF-8".to_string()),
+                    )
+                    .tooltip("文件编码")
+                    .priority(2),
+                    StatusBarSegment::new(
+                        "status-diff-duration",
+                        if diff_timing_enabled() {
+                            format!(
+                                "耗时: {} ({})",
+                                format_diff_duration(diff_view.diff_timing.total),
+                                diff_view.diff_timing.breakdown()
+                            )
+                        } else {
+                            format!(
+                                "耗时: {}",
+                                format_diff_duration(diff_view.diff_timing.total)
+                            )
+                        },
+                    )
+                    .tooltip(format!(
+                        "计算 diff 的耗时：{}",
+                        diff_view.diff_timing.breakdown()
+                    ))
+                    .priority(1),
+                    StatusBarSegment::new(
+                        "status-keys",
+                        "Esc 返回 · Alt+N/P 导航 · Alt+W 空白 · Alt+V 视图",
+                    )
+                    .priority(-2),
+                ]);
+        let status_bar = StatusBar::new("diff-status-bar")
+            .left_segments(status_left)
+            .right_segments(status_right)
+            .px(px(6.));
+
+        let diff_scroll_ruler = if rows_len > 0 && hunk_count > 0 {
+            let theme = cx.theme();
+            let total_rows = rows_len.max(1) as f32;
+            let right_inset = px(14.);
+            let width = px(8.);
+            let max_markers = 200usize;
+            let marker_step = (hunk_count / max_markers).max(1);
+            let mut marker_indices: Vec<usize> = (0..hunk_count).step_by(marker_step).collect();
+            marker_indices.push(diff_view.current_hunk);
+            marker_indices.sort_unstable();
+            marker_indices.dedup();
+
+            let mut ruler = div()
+                .id("diff-scroll-ruler")
+                .absolute()
+                .top(px(6.))
+                .bottom(px(6.))
+                .right(right_inset)
+                .w(width)
+                .rounded(px(999.))
+                .bg(theme.muted.alpha(0.1))
+                .border_1()
+                .border_color(theme.border.alpha(0.35))
+                .relative();
+
+            for hunk_index in marker_indices {
+                let row_index = diff_view.hunk_rows.get(hunk_index).copied().unwrap_or(0);
+                let fraction = (row_index as f32 / total_rows).clamp(0.0, 1.0);
+                let is_current = hunk_index == diff_view.current_hunk;
+                let color = if is_current {
+                    theme.blue
+                } else {
+                    theme.blue.alpha(0.65)
+                };
+                let height = if is_current { px(6.) } else { px(3.) };
+                let radius = if is_current { px(6.) } else { px(4.) };
+
+                ruler = ruler.child(
+                    div()
+                        .id(("diff-ruler-marker", hunk_index))
+                        .absolute()
+                        .top(relative(fraction))
+                        .left(px(1.))
+                        .right(px(1.))
+                        .h(height)
+                        .rounded(radius)
+                        .bg(color)
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |this, _, _window, cx| {
+                                if let Some(diff_view) = this.diff_view.as_mut() {
+                                    if hunk_index < diff_view.hunk_rows.len() {
+                                        diff_view.current_hunk = hunk_index;
+                                        diff_view.scroll_to_row(row_index);
+                                    }
+                                }
+                                cx.notify();
+                            }),
+                        ),
+                );
+            }
+
+            Some(ruler)
+        } else {
+            None
+        };
+
+        let search_marks = build_minimap_marks(
+            rows_len,
+            search_rows
+                .iter()
+                .map(|row| (*row, MinimapMarkKind::SearchMatch)),
+        );
+        let diff_minimap = (!minimap_marks.is_empty() || !search_marks.is_empty()).then(|| {
+            let theme = cx.theme();
+            let mut minimap = div()
+                .id("diff-minimap")
+                .absolute()
+                .top(px(6.))
+                .bottom(px(6.))
+                .right(px(26.))
+                .w(px(10.))
+                .bg(theme.muted.alpha(0.08))
+                .relative();
+
+            for (index, mark) in minimap_marks.iter().chain(&search_marks).enumerate() {
+                let color = match mark.kind {
+                    MinimapMarkKind::Added => theme.green.alpha(0.7),
+                    MinimapMarkKind::Removed => theme.red.alpha(0.6),
+                    MinimapMarkKind::Modified => theme.yellow.alpha(0.7),
+                    MinimapMarkKind::Conflict => theme.red,
+                    MinimapMarkKind::SearchMatch => theme.foreground.alpha(0.85),
+                };
+                let is_search = mark.kind == MinimapMarkKind::SearchMatch;
+                let buckets = mark.buckets as f32;
+                let top = mark.first_bucket as f32 / buckets;
+                let height = (mark.last_bucket + 1 - mark.first_bucket) as f32 / buckets;
+                let row_index = mark.row;
+
+                minimap = minimap.child(
+                    div()
+                        .id(("diff-minimap-mark", index))
+                        .absolute()
+                        .top(relative(top))
+                        .when(is_search, |this| this.left(px(3.)).right(px(3.)).h(px(2.)))
+                        .when(!is_search, |this| {
+                            this.left(px(0.))
+                                .right(px(0.))
+                                .h(relative(height))
+                                .min_h(px(2.))
+                        })
+                        .bg(color)
+                        .cursor_pointer()
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |this, _, _window, cx| {
+                                if let Some(diff_view) = this.diff_view.as_mut() {
+                                    diff_view.scroll_to_row(row_index);
+                                }
+                                cx.notify();
+                            }),
+                        ),
+                );
+            }
+
+            minimap
+        });
+
+        let review_comments: Vec<forge::PullRequestComment> = match (
+            self.pull_request_review.as_ref(),
+            &diff_view.compare_target,
+            diff_view.path.as_deref(),
+        ) {
+            (Some(review), CompareTarget::Refs { right, .. }, Some(path))
+                if *right == review.head_ref =>
+            {
+                review
+                    .comments
+                    .iter()
+                    .filter(|comment| comment.path.as_deref() == Some(path))
+                    .cloned()
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+        let review_banner = (!review_comments.is_empty()).then(|| {
+            let theme = cx.theme();
+            div()
+                .id("pr-review-comments")
+                .flex()
+                .flex_col()
+                .gap(px(2.))
+                .max_h(px(160.))
+                .overflow_y_scroll()
+                .px(px(12.))
+                .py(px(6.))
+                .border_b_1()
+                .border_color(theme.border.alpha(0.6))
+                .bg(theme.yellow.alpha(0.06))
+                .text_sm()
+                .child(
+                    div()
+                        .text_xs()
+                        .text_color(theme.muted_foreground)
+                        .child(format!(
+                            "{} 条评审评论（点击跳到所在 hunk）",
+                            review_comments.len()
+                        )),
+                )
+                .children(
+                    review_comments
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, comment)| {
+                            let line = comment.line;
+                            div()
+                                .id(("pr-review-comment", index))
+                                .flex()
+                                .flex_row()
+                                .gap(px(8.))
+                                .px(px(4.))
+                                .rounded(px(4.))
+                                .when_some(line, |this, line| {
+                                    this.cursor_pointer()
+                                        .hover(|this| this.bg(theme.accent.alpha(0.4)))
+                                        .on_mouse_down(
+                                            MouseButton::Left,
+                                            cx.listener(move |this, _, _window, cx| {
+                                                if let Some(diff_view) = this.diff_view.as_mut() {
+                                                    diff_view.jump_to_new_line(line);
+                                                }
+                                                cx.notify();
+                                            }),
+                                        )
+                                })
+                                .child(
+                                    div()
+                                        .w(px(56.))
+                                        .flex_none()
+                                        .font_family(theme.mono_font_family.clone())
+                                        .text_color(theme.muted_foreground)
+                                        .child(
+                                            line.map(|line| format!("L{line}")).unwrap_or_default(),
+                                        ),
+                                )
+                                .child(
+                                    div()
+                                        .flex_none()
+                                        .text_color(theme.muted_foreground)
+                                        .child(comment.author),
+                                )
+                                .child(div().flex_1().min_w(px(0.)).child(comment.body))
+                        }),
+                )
+        });
+
+        let lfs_banner = diff_view.lfs.as_ref().map(|lfs| {
+            let summary = format!(
+                "Git LFS：{} → {}",
+                lfs_side_label(lfs.old.as_ref(), &lfs.old_source),
+                lfs_side_label(lfs.new.as_ref(), &lfs.new_source)
+            );
+            let content_loaded = lfs.content_loaded;
+            div()
+                .flex()
+                .flex_row()
+                .items_center()
+                .justify_between()
+                .gap(px(12.))
+                .px(px(12.))
+                .py(px(6.))
+                .border_b_1()
+                .border_color(cx.theme().border.alpha(0.6))
+                .bg(cx.theme().blue.alpha(0.08))
+                .text_sm()
+                .child(div().flex_1().min_w(px(0.)).truncate().child(summary))
+                .child(
+                    Button::new("lfs-load-content")
+                        .label(if content_loaded {
+                            "已加载实际内容"
+                        } else {
+                            "加载实际内容对比"
+                        })
+                        .ghost()
+                        .disabled(content_loaded || !git_available)
+                        .on_click(cx.listener(|this, _, window, cx| {
+                            this.load_lfs_content(window, cx);
+                        })),
+                )
+        });
+
+        let mut viewport = div()
+            .flex()
+            .flex_col()
+            .flex_1()
+            .relative()
+            .overflow_hidden();
+
+        if let Some(preview) = markdown_preview {
+            viewport = viewport.p(px(12.)).child(preview);
+        } else {
+            viewport = viewport
+                .child(list)
+                .child(Scrollbar::uniform_scroll(&scroll_state, &scroll_handle));
+
+            if let Some(minimap) = diff_minimap {
+                viewport = viewport.child(minimap);
+            }
+
+            if let Some(ruler) = diff_scroll_ruler {
+                viewport = viewport.child(ruler);
+            }
+        }
+
+        div()
+            .flex()
+            .flex_col()
+            .size_full()
+            .child(toolbar)
+            .children(lfs_banner)
+            .children(review_banner)
+            .child(viewport)
+            .child(status_bar)
+    }
+
+    fn render_conflict_view(&mut self, window: &mut Window, cx: &mut Context<Self>) -> Div {
+        let (conflict_file_count, conflict_file_index) = {
+            let (files, current) = self.conflict_files();
+            (files.len(), current)
+        };
+        let can_switch_conflict_file =
+            conflict_file_count > usize::from(conflict_file_index.is_some());
+        let conflict_file_position = match conflict_file_index {
+            Some(index) => format!("{}/{conflict_file_count}", index + 1),
+            None => format!("-/{conflict_file_count}"),
+        };
+
+        let Some(conflict_view) = self.conflict_view.as_mut() else {
+            return div().p(px(12.)).child("No conflict view");
+        };
+
+        let git_available = self.git_available;
+        let two_pane = matches!(self.split_layout, SplitLayout::TwoPane);
+        let show_result_editor = conflict_view.show_result_editor;
+        let title = conflict_view.title.clone();
+        let encoding_label = Some(conflict_view.encoding)
+            .filter(|encoding| !encoding.is_plain_utf8())
+            .map(|encoding| encoding.label());
+        let conflicts_count = conflict_view.conflicts.len();
+        let can_prev = conflict_view.current_conflict > 0;
+        let conflict_total = conflict_view.conflict_rows.len();
+        let can_next = conflict_view.current_conflict + 1 < conflict_total;
+        let has_path = conflict_view.path.is_some();
+        let can_save = has_path && conflicts_count == 0;
+        let can_add = can_save && git_available;
+        let rows_len = conflict_view.rows.len();
+        let scroll_handle = conflict_view.scroll_handle.clone();
+        let scroll_state = conflict_view.scroll_state.clone();
+        let row_height = window.line_height() + px(4.);
+        let item_sizes = conflict_view.item_sizes(row_height);
+        let conflict_position = if conflict_total == 0 {
+            "0/0".to_string()
+        } else {
+            format!("{}/{}", conflict_view.current_conflict + 1, conflict_total)
+        };
+
+        let save_file_label: SharedString = if can_save {
+            "保存到文件".into()
+        } else if has_path {
+            "保存到文件（仍有冲突未解决）".into()
+        } else {
+            "保存到文件（demo 不支持）".into()
+        };
+        let save_add_label: SharedString = if can_add {
+            "保存并 git add".into()
+        } else if !git_available {
+            "保存并 git add（git 不可用）".into()
+        } else if has_path {
+            "保存并 git add（仍有冲突未解决）".into()
+        } else {
+            "保存并 git add（demo 不支持）".into()
+        };
+
+        let save_shortcut = if cfg!(target_os = "macos") {
+            "Cmd+S"
+        } else {
+            "Ctrl+S"
+        };
+        let save_add_shortcut = if cfg!(target_os = "macos") {
+            "Cmd+Shift+S"
+        } else {
+            "Ctrl+Shift+S"
+        };
+        let shortcuts_message: SharedString = format!(
+            "快捷键：\n\
+             Esc 返回\n\
+             Alt+N / Alt+P 下一/上一冲突\n\
+             Alt+L 切换对齐/分栏\n\
+             Alt+A 应用编辑\n\
+             {save_shortcut} 保存（冲突清零后）\n\
+             {save_add_shortcut} 保存并 git add（冲突清零后）\n"
+        )
+        .into();
+
+        let app = cx.entity();
+        let more_menu = {
+            let app_for_menu = app.clone();
+            Popover::new("conflict-more-menu")
+                .appearance(false)
+                .trigger(
+                    Button::new("conflict-more-trigger")
+                        .label("更多")
+                        .ghost()
+                        .tooltip("更多操作")
+                        .on_click(|_, _, _| {}),
+                )
+                .content(move |_, _window, cx| {
+                    let theme = cx.theme();
+                    let popover = cx.entity();
+
+                    let make_action = move |id: &'static str,
+                                            label: SharedString,
+                                            disabled: bool,
+                                            action: Rc<dyn Fn(&mut Window, &mut App) + 'static>| {
+                        let popover = popover.clone();
+                        Button::new(id)
+                            .label(label)
+                            .ghost()
+                            .disabled(disabled)
+                            .w_full()
+                            .on_click({
+                                let action = action.clone();
+                                move |_, window, cx| {
+                                    action(window, cx);
+                                    popover.update(cx, |state, cx| state.dismiss(window, cx));
+                                }
+                            })
+                            .into_any_element()
+                    };
+
+                    let split_label: SharedString = if two_pane {
+                        "分栏: 开".into()
+                    } else {
+                        "分栏: 关".into()
+                    };
+                    let result_label: SharedString = if show_result_editor {
+                        "结果面板: 开".into()
+                    } else {
+                        "结果面板: 关".into()
+                    };
+
+                    let app_for_split = app_for_menu.clone();
+                    let toggle_split = Rc::new(move |_window: &mut Window, cx: &mut App| {
+                        app_for_split.update(cx, |this, cx| {
+                            this.split_layout = match this.split_layout {
+                                SplitLayout::Aligned => SplitLayout::TwoPane,
+                                SplitLayout::TwoPane => SplitLayout::Aligned,
+                            };
+                            cx.notify();
+                        });
+                    });
+
+                    let app_for_result = app_for_menu.clone();
+                    let toggle_result = Rc::new(move |_window: &mut Window, cx: &mut App| {
+                        app_for_result.update(cx, |this, cx| {
+                            if let Some(view) = this.conflict_view.as_mut() {
+                                view.show_result_editor = !view.show_result_editor;
+                            }
+                            cx.notify();
+                        });
+                    });
+
+                    let app_for_apply = app_for_menu.clone();
+                    let apply_editor = Rc::new(move |window: &mut Window, cx: &mut App| {
+                        app_for_apply.update(cx, |this, cx| {
+                            this.apply_conflict_editor(window, cx);
+                        });
+                    });
+
+                    let shortcuts_message = shortcuts_message.clone();
+                    let show_shortcuts = Rc::new(move |window: &mut Window, cx: &mut App| {
+                        window.push_toast(Toast::info(shortcuts_message.to_string()), cx);
+                    });
+
+                    let app_for_save = app_for_menu.clone();
+                    let save_to_file = Rc::new(move |window: &mut Window, cx: &mut App| {
+                        app_for_save.update(cx, |this, cx| {
+                            this.save_conflict_to_working_tree(false, window, cx);
+                            cx.notify();
+                        });
+                    });
+
+                    let app_for_save_add = app_for_menu.clone();
+                    let save_and_add = Rc::new(move |window: &mut Window, cx: &mut App| {
+                        app_for_save_add.update(cx, |this, cx| {
+                            this.save_conflict_to_working_tree(true, window, cx);
+                            cx.notify();
+                        });
+                    });
+
+                    div()
+                        .p(px(8.))
+                        .bg(theme.popover)
+                        .border_1()
+                        .border_color(theme.border)
+                        .rounded(theme.radius)
+                        .shadow_md()
+                        .flex()
+                        .flex_col()
+                        .gap(px(6.))
+                        .child(
+                            div()
+                                .px(px(4.))
+                                .py(px(2.))
+                                .text_xs()
+                                .text_color(theme.muted_foreground)
+                                .child("视图"),
+                        )
+                        .child(make_action(
+                            "conflict-more-toggle-split",
+                            split_label,
+                            false,
+                            toggle_split,
+                        ))
+                        .child(make_action(
+                            "conflict-more-toggle-result",
+                            result_label,
+                            false,
+                            toggle_result,
+                        ))
+                        .child(make_action(
+                            "conflict-more-apply",
+                            "应用编辑".into(),
+                            !show_result_editor,
+                            apply_editor,
+                        ))
+                        .child(div().h(px(1.)).bg(theme.border.alpha(0.4)))
+                        .child(
+                            div()
+                                .px(px(4.))
+                                .py(px(2.))
+                                .text_xs()
+                                .text_color(theme.muted_foreground)
+                                .child("保存"),
+                        )
+                        .child(make_action(
+                            "conflict-more-save",
+                            save_file_label.clone(),
+                            !can_save,
+                            save_to_file,
+                        ))
+                        .child(make_action(
+                            "conflict-more-save-add",
+                            save_add_label.clone(),
+                            !can_add,
+                            save_and_add,
+                        ))
+                        .child(make_action(
+                            "conflict-more-shortcuts",
+                            "快捷键…".into(),
+                            false,
+                            show_shortcuts,
+                        ))
+                })
+        };
+
+        let toolbar = div()
+            .flex()
+            .flex_col()
+            .gap(px(8.))
+            .p(px(12.))
+            .border_b_1()
+            .border_color(cx.theme().border)
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap(px(8.))
+                    .child(
+                        Button::new("conflict-back")
+                            .label("返回")
+                            .ghost()
+                            .tooltip_with_action("返回", &Back, Some(CONTEXT))
+                            .on_click(cx.listener(|this, _, _window, cx| {
+                                this.close_conflict_view();
+                                cx.notify();
+                            })),
+                    )
+                    .child(div().flex_1().min_w(px(0.)).truncate().child(title))
+                    .when_some(encoding_label, |this, label| {
+                        this.child(render_file_badge(label, cx.theme()))
+                    }),
+            )
+            .child(
                 div()
                     .flex()
-                    .flex_row()
+                    .flex_row()
+                    .items_center()
+                    .gap(px(12.))
+                    .flex_wrap()
+                    .child(div().child(format!("冲突: {conflict_position}")))
+                    .child(
+                        Button::new("conflict-prev")
+                            .label("上一冲突")
+                            .ghost()
+                            .tooltip_with_action("上一冲突", &Prev, Some(CONTEXT))
+                            .disabled(!can_prev)
+                            .on_click(cx.listener(|this, _, _window, cx| {
+                                this.jump_conflict(-1);
+                                cx.notify();
+                            })),
+                    )
+                    .child(
+                        Button::new("conflict-next")
+                            .label("下一冲突")
+                            .ghost()
+                            .tooltip_with_action("下一冲突", &Next, Some(CONTEXT))
+                            .disabled(!can_next)
+                            .on_click(cx.listener(|this, _, _window, cx| {
+                                this.jump_conflict(1);
+                                cx.notify();
+                            })),
+                    )
+                    .child(div().child(format!("冲突文件: {conflict_file_position}")))
+                    .child(
+                        Button::new("conflict-prev-file")
+                            .label("上一文件")
+                            .ghost()
+                            .tooltip("打开上一个冲突文件")
+                            .disabled(!can_switch_conflict_file)
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                this.jump_conflict_file(-1, window, cx);
+                                cx.notify();
+                            })),
+                    )
+                    .child(
+                        Button::new("conflict-next-file")
+                            .label("下一文件")
+                            .ghost()
+                            .tooltip("打开下一个冲突文件")
+                            .disabled(!can_switch_conflict_file)
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                this.jump_conflict_file(1, window, cx);
+                                cx.notify();
+                            })),
+                    )
+                    .child(more_menu),
+            );
+
+        let list = match self.split_layout {
+            SplitLayout::Aligned => v_virtual_list(
+                cx.entity(),
+                "conflict-aligned-list",
+                item_sizes,
+                move |this, visible_range, window, cx| {
+                    visible_range
+                        .map(|index| this.render_conflict_row(index, row_height, window, cx))
+                        .collect::<Vec<_>>()
+                },
+            )
+            .track_scroll(&scroll_handle)
+            .into_any_element(),
+            SplitLayout::TwoPane => {
+                let show_base = conflict_view
+                    .conflicts
+                    .iter()
+                    .any(|conflict| conflict.base.is_some());
+
+                let ours_list = v_virtual_list(
+                    cx.entity(),
+                    "conflict-pane-ours",
+                    item_sizes.clone(),
+                    move |this, visible_range, window, cx| {
+                        visible_range
+                            .map(|index| {
+                                this.render_conflict_pane_row(
+                                    ConflictPane::Ours,
+                                    index,
+                                    row_height,
+                                    window,
+                                    cx,
+                                )
+                            })
+                            .collect::<Vec<_>>()
+                    },
+                )
+                .track_scroll(&scroll_handle)
+                .into_any_element();
+
+                let base_list = show_base.then(|| {
+                    v_virtual_list(
+                        cx.entity(),
+                        "conflict-pane-base",
+                        item_sizes.clone(),
+                        move |this, visible_range, window, cx| {
+                            visible_range
+                                .map(|index| {
+                                    this.render_conflict_pane_row(
+                                        ConflictPane::Base,
+                                        index,
+                                        row_height,
+                                        window,
+                                        cx,
+                                    )
+                                })
+                                .collect::<Vec<_>>()
+                        },
+                    )
+                    .track_scroll(&scroll_handle)
+                    .into_any_element()
+                });
+
+                let theirs_list = v_virtual_list(
+                    cx.entity(),
+                    "conflict-pane-theirs",
+                    item_sizes,
+                    move |this, visible_range, window, cx| {
+                        visible_range
+                            .map(|index| {
+                                this.render_conflict_pane_row(
+                                    ConflictPane::Theirs,
+                                    index,
+                                    row_height,
+                                    window,
+                                    cx,
+                                )
+                            })
+                            .collect::<Vec<_>>()
+                    },
+                )
+                .track_scroll(&scroll_handle)
+                .into_any_element();
+
+                let theme = cx.theme();
+                let pane_header_height = window.line_height() + px(6.);
+                let ours_label = conflict_view
+                    .conflicts
+                    .first()
+                    .map(|c| c.ours_branch_name.clone())
+                    .unwrap_or_else(|| "Ours".to_string());
+                let theirs_label = conflict_view
+                    .conflicts
+                    .first()
+                    .map(|c| c.theirs_branch_name.clone())
+                    .unwrap_or_else(|| "Theirs".to_string());
+
+                let old_header = div()
+                    .h(pane_header_height)
+                    .px(px(12.))
+                    .flex()
                     .items_center()
-                    .gap(px(8.))
-                    .child(
-                        Button::new("back")
-                            .label("返回")
-                            .ghost()
-                            .tooltip_with_action("返回", &Back, Some(CONTEXT))
-                            .on_click(cx.listener(|this, _, _window, cx| {
-                                this.close_diff_view();
-                                cx.notify();
-                            })),
-                    )
-                    .child(div().flex_1().min_w(px(0.)).truncate().child(title)),
-            )
-            .child(
-                div()
+                    .bg(theme.muted.alpha(0.2))
+                    .text_sm()
+                    .text_color(theme.muted_foreground)
+                    .child(format!("Ours: {ours_label}"));
+                let base_header = div()
+                    .h(pane_header_height)
+                    .px(px(12.))
                     .flex()
-                    .flex_row()
                     .items_center()
-                    .gap(px(12.))
-                    .flex_wrap()
-                    .child(
+                    .bg(theme.muted.alpha(0.2))
+                    .text_sm()
+                    .text_color(theme.muted_foreground)
+                    .child("Base（diff3）");
+                let theirs_header = div()
+                    .h(pane_header_height)
+                    .px(px(12.))
+                    .flex()
+                    .items_center()
+                    .bg(theme.muted.alpha(0.2))
+                    .text_sm()
+                    .text_color(theme.muted_foreground)
+                    .child(format!("Theirs: {theirs_label}"));
+
+                // 分隔条由 h_resizable 绘制。有无 base 栏时分别记住各自的分隔位置。
+                let count = if base_list.is_some() { 3 } else { 2 };
+                let key = format!("conflict-panes-{count}");
+                let sizes = PersistedPanelSizes::new(key.clone(), self.panel_sizes.clone());
+                let total = window.viewport_size().width;
+                let mut row = h_resizable(SharedString::from(key)).persist(&sizes).child(
+                    sizes.panel(0, count, total).child(
                         div()
                             .flex()
-                            .flex_row()
-                            .items_center()
-                            .gap(px(6.))
-                            .child({
-                                let button = Button::new("mode-split").label("Split");
-                                if view_mode == DiffViewMode::Split {
-                                    button.primary()
-                                } else {
-                                    button.ghost().on_click(cx.listener(|this, _, _window, cx| {
-                                        this.set_view_mode(DiffViewMode::Split);
-                                        cx.notify();
-                                    }))
-                                }
-                            })
-                            .child({
-                                let button = Button::new("mode-inline").label("Inline");
-                                if view_mode == DiffViewMode::Inline {
-                                    button.primary()
-                                } else {
-                                    button.ghost().on_click(cx.listener(|this, _, _window, cx| {
-                                        this.set_view_mode(DiffViewMode::Inline);
-                                        cx.notify();
-                                    }))
+                            .flex_col()
+                            .size_full()
+                            .child(old_header)
+                            .child(div().flex_1().min_h(px(0.)).child(ours_list)),
+                    ),
+                );
+
+                if let Some(base_list) = base_list {
+                    row = row.child(
+                        sizes.panel(1, count, total).child(
+                            div()
+                                .flex()
+                                .flex_col()
+                                .size_full()
+                                .child(base_header)
+                                .child(div().flex_1().min_h(px(0.)).child(base_list)),
+                        ),
+                    );
+                }
+
+                row.child(
+                    sizes.panel(count - 1, count, total).child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .size_full()
+                            .child(theirs_header)
+                            .child(div().flex_1().min_h(px(0.)).child(theirs_list)),
+                    ),
+                )
+                .into_any_element()
+            }
+        };
+
+        let status_left = div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap(px(12.))
+            .child(div().truncate().child(format!(
+                "文件: {}",
+                conflict_view.path.as_deref().unwrap_or("<demo>")
+            )))
+            .child(match conflict_view.source {
+                ConflictSource::IndexStages {
+                    auto_merged,
+                    auto_resolved: 0,
+                } => format!("来源: index stage（diff3，自动合并 {auto_merged} 处）"),
+                ConflictSource::IndexStages {
+                    auto_merged,
+                    auto_resolved,
+                } => format!(
+                    "来源: index stage（diff3，自动合并 {auto_merged} 处，按策略自动解决 {auto_resolved} 处）"
+                ),
+                ConflictSource::WorktreeMarkers => "来源: 工作区冲突标记".to_string(),
+            })
+            .child(format!("未解决: {conflicts_count}"));
+        let status_hint = div()
+            .truncate()
+            .text_color(cx.theme().muted_foreground)
+            .child("Esc 返回 · Alt+N/P 导航 · Alt+A 应用 · Cmd/Ctrl+S 保存");
+        let status_right = div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap(px(12.))
+            .child(if can_add {
+                "可保存并标记已解决".to_string()
+            } else if can_save {
+                "可保存（git 不可用，无法标记已解决）".to_string()
+            } else if has_path {
+                "仍有冲突未解决（编辑后点“应用”）".to_string()
+            } else {
+                "demo（不可保存）".to_string()
+            })
+            .child(status_hint);
+        let status_bar = div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .justify_between()
+            .px(px(12.))
+            .py(px(6.))
+            .border_t_1()
+            .border_color(cx.theme().border.alpha(0.6))
+            .bg(cx.theme().muted.alpha(0.12))
+            .text_xs()
+            .text_color(cx.theme().muted_foreground)
+            .child(status_left)
+            .child(status_right);
+
+        let conflict_scroll_ruler = if rows_len > 0 && !conflict_view.conflict_rows.is_empty() {
+            let theme = cx.theme();
+            let total_rows = rows_len.max(1) as f32;
+            let right_inset = px(14.);
+            let width = px(8.);
+            let conflict_total = conflict_view.conflict_rows.len();
+            let max_markers = 200usize;
+            let marker_step = (conflict_total / max_markers).max(1);
+            let mut marker_indices: Vec<usize> = (0..conflict_total).step_by(marker_step).collect();
+            marker_indices.push(conflict_view.current_conflict);
+            marker_indices.sort_unstable();
+            marker_indices.dedup();
+
+            let mut ruler = div()
+                .id("conflict-scroll-ruler")
+                .absolute()
+                .top(px(6.))
+                .bottom(px(6.))
+                .right(right_inset)
+                .w(width)
+                .rounded(px(999.))
+                .bg(theme.muted.alpha(0.1))
+                .border_1()
+                .border_color(theme.border.alpha(0.35))
+                .relative();
+
+            for conflict_index in marker_indices {
+                let row_index = conflict_view
+                    .conflict_rows
+                    .get(conflict_index)
+                    .copied()
+                    .unwrap_or(0);
+                let fraction = (row_index as f32 / total_rows).clamp(0.0, 1.0);
+                let is_current = conflict_index == conflict_view.current_conflict;
+                let color = if is_current {
+                    theme.red
+                } else {
+                    theme.red.alpha(0.65)
+                };
+                let height = if is_current { px(6.) } else { px(3.) };
+                let radius = if is_current { px(6.) } else { px(4.) };
+
+                ruler = ruler.child(
+                    div()
+                        .id(("conflict-ruler-marker", conflict_index))
+                        .absolute()
+                        .top(relative(fraction))
+                        .left(px(1.))
+                        .right(px(1.))
+                        .h(height)
+                        .rounded(radius)
+                        .bg(color)
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |this, _, _window, cx| {
+                                if let Some(conflict_view) = this.conflict_view.as_mut() {
+                                    if conflict_index < conflict_view.conflict_rows.len() {
+                                        conflict_view.current_conflict = conflict_index;
+                                        conflict_view
+                                            .scroll_handle
+                                            .scroll_to_item(row_index, ScrollStrategy::Top);
+                                    }
                                 }
-                            }),
-                    )
-                    .child(compare_control)
-                    .child(
-                        Button::new("prev-hunk")
-                            .label("上一 hunk")
-                            .ghost()
-                            .tooltip_with_action("上一 hunk", &Prev, Some(CONTEXT))
-                            .disabled(!can_prev_hunk)
-                            .on_click(cx.listener(|this, _, _window, cx| {
-                                this.jump_hunk(-1);
-                                cx.notify();
-                            })),
-                    )
-                    .child(
-                        Button::new("next-hunk")
-                            .label("下一 hunk")
-                            .ghost()
-                            .tooltip_with_action("下一 hunk", &Next, Some(CONTEXT))
-                            .disabled(!can_next_hunk)
-                            .on_click(cx.listener(|this, _, _window, cx| {
-                                this.jump_hunk(1);
                                 cx.notify();
-                            })),
-                    )
-                    .child(more_menu),
-            );
+                            }),
+                        ),
+                );
+            }
 
-        let list = match view_mode {
-            DiffViewMode::Inline => v_virtual_list(
-                cx.entity(),
-                "diff-inline-list",
-                item_sizes,
-                move |this, visible_range, window, cx| {
-                    visible_range
-                        .map(|index| this.render_inline_row(index, row_height, window, cx))
-                        .collect::<Vec<_>>()
-                },
-            )
-            .track_scroll(&scroll_handle)
-            .into_any_element(),
-            DiffViewMode::Split => match self.split_layout {
-                SplitLayout::Aligned => v_virtual_list(
-                    cx.entity(),
-                    "diff-aligned-list",
-                    item_sizes,
-                    move |this, visible_range, window, cx| {
-                        visible_range
-                            .map(|index| this.render_demo_row(index, row_height, window, cx))
-                            .collect::<Vec<_>>()
-                    },
-                )
-                .track_scroll(&scroll_handle)
-                .into_any_element(),
-                SplitLayout::TwoPane => {
-                    let old_list = v_virtual_list(
-                        cx.entity(),
-                        "diff-two-pane-old",
-                        item_sizes.clone(),
-                        move |this, visible_range, window, cx| {
-                            visible_range
-                                .map(|index| {
-                                    this.render_pane_row(Side::Old, index, row_height, window, cx)
-                                })
-                                .collect::<Vec<_>>()
-                        },
-                    )
-                    .track_scroll(&scroll_handle)
-                    .into_any_element();
+            Some(ruler)
+        } else {
+            None
+        };
 
-                    let new_list = v_virtual_list(
-                        cx.entity(),
-                        "diff-two-pane-new",
-                        item_sizes,
-                        move |this, visible_range, window, cx| {
-                            visible_range
-                                .map(|index| {
-                                    this.render_pane_row(Side::New, index, row_height, window, cx)
-                                })
-                                .collect::<Vec<_>>()
-                        },
-                    )
-                    .track_scroll(&scroll_handle)
-                    .into_any_element();
+        let mut viewport = div()
+            .flex()
+            .flex_col()
+            .flex_1()
+            .relative()
+            .overflow_hidden()
+            .child(list)
+            .child(Scrollbar::uniform_scroll(&scroll_state, &scroll_handle));
 
-                    let theme = cx.theme();
-                    let pane_header_height = window.line_height() + px(6.);
-                    let old_header = div()
-                        .h(pane_header_height)
-                        .px(px(12.))
+        if let Some(ruler) = conflict_scroll_ruler {
+            viewport = viewport.child(ruler);
+        }
+
+        let result_panel = show_result_editor.then(|| {
+            div()
+                .flex()
+                .flex_col()
+                .flex_none()
+                .h(px(220.))
+                .border_t_1()
+                .border_color(cx.theme().border.alpha(0.6))
+                .bg(cx.theme().muted.alpha(0.06))
+                .child(
+                    div()
                         .flex()
+                        .flex_row()
                         .items_center()
-                        .bg(theme.muted.alpha(0.2))
-                        .text_sm()
-                        .text_color(theme.muted_foreground)
-                        .child(compare_target_side_label(&compare_target, Side::Old));
-                    let new_header = div()
-                        .h(pane_header_height)
+                        .justify_between()
                         .px(px(12.))
+                        .py(px(8.))
+                        .child(div().text_sm().child("合并结果（可编辑）"))
+                        .child(
+                            div().flex().flex_row().items_center().gap(px(6.)).child(
+                                Button::new("conflict-apply-editor-inline")
+                                    .label("应用")
+                                    .ghost()
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        this.apply_conflict_editor(window, cx);
+                                    })),
+                            ),
+                        ),
+                )
+                .child(
+                    div()
                         .flex()
-                        .items_center()
-                        .bg(theme.muted.alpha(0.2))
-                        .text_sm()
-                        .text_color(theme.muted_foreground)
-                        .child(compare_target_side_label(&compare_target, Side::New));
+                        .flex_col()
+                        .flex_1()
+                        .min_h(px(0.))
+                        .p(px(12.))
+                        .child(Input::new(&conflict_view.result_input).h_full().w_full()),
+                )
+        });
+
+        let mut root = div()
+            .flex()
+            .flex_col()
+            .size_full()
+            .child(toolbar)
+            .child(viewport);
+
+        if let Some(panel) = result_panel {
+            root = root.child(panel);
+        }
+
+        root.child(status_bar)
+    }
+
+    fn render_reflog_view(&mut self, window: &mut Window, cx: &mut Context<Self>) -> Div {
+        let selected_hash = self.reflog_view.as_ref().and_then(|reflog_view| {
+            reflog_view
+                .entries
+                .get(reflog_view.selected)
+                .filter(|_| !reflog_view.loading)
+                .map(|entry| entry.hash.clone())
+        });
+        let commit_details = selected_hash.map(|hash| {
+            div()
+                .mx(px(12.))
+                .mb(px(8.))
+                .child(self.render_commit_details_pane(&hash, window, cx))
+        });
+
+        let Some(reflog_view) = self.reflog_view.as_ref() else {
+            return div().p(px(12.)).child("No reflog view");
+        };
+        let theme = cx.theme();
+
+        let loading = reflog_view.loading;
+        let running = reflog_view.running;
+        let selected = reflog_view.selected;
+        let has_selection = !loading && selected < reflog_view.entries.len();
+        let can_act = has_selection && !running;
+        let confirm_hard_reset = reflog_view.confirm_hard_reset;
+        let details = reflog_view
+            .entries
+            .get(selected)
+            .filter(|_| has_selection)
+            .map(|entry| {
+                render_signature_details(&entry.hash, &entry.message, &entry.signature, theme)
+                    .mx(px(12.))
+                    .mb(px(8.))
+            });
 
+        let list: Vec<AnyElement> = if loading && reflog_view.entries.is_empty() {
+            vec![
+                div()
+                    .px(px(12.))
+                    .py(px(10.))
+                    .text_sm()
+                    .text_color(theme.muted_foreground)
+                    .child("加载 reflog 中…")
+                    .into_any_element(),
+            ]
+        } else if reflog_view.entries.is_empty() {
+            vec![
+                div()
+                    .px(px(12.))
+                    .py(px(10.))
+                    .text_sm()
+                    .text_color(theme.muted_foreground)
+                    .child("reflog 为空")
+                    .into_any_element(),
+            ]
+        } else {
+            reflog_view
+                .entries
+                .iter()
+                .enumerate()
+                .map(|(index, entry)| {
+                    let is_selected = index == selected;
                     div()
+                        .id(("reflog-entry", index))
                         .flex()
                         .flex_row()
-                        .size_full()
+                        .items_center()
+                        .gap(px(12.))
+                        .h(px(32.))
+                        .px(px(10.))
+                        .rounded(px(6.))
+                        .text_sm()
+                        .when(is_selected, |this| {
+                            this.bg(theme.accent)
+                                .text_color(theme.accent_foreground)
+                                .cursor_default()
+                        })
+                        .when(!is_selected, |this| {
+                            this.bg(theme.transparent)
+                                .text_color(theme.foreground)
+                                .cursor_pointer()
+                                .hover(|this| {
+                                    this.bg(theme.accent.alpha(0.4))
+                                        .text_color(theme.accent_foreground)
+                                })
+                                .on_mouse_down(
+                                    MouseButton::Left,
+                                    cx.listener(move |this, _, _window, cx| {
+                                        this.select_reflog_entry(index);
+                                        cx.notify();
+                                    }),
+                                )
+                        })
                         .child(
                             div()
-                                .flex()
-                                .flex_col()
-                                .flex_1()
-                                .min_w(px(0.))
-                                .border_r_1()
-                                .border_color(theme.border.alpha(0.6))
-                                .child(old_header)
-                                .child(div().flex_1().min_h(px(0.)).child(old_list)),
+                                .w(px(96.))
+                                .flex_none()
+                                .font_family(theme.mono_font_family.clone())
+                                .child(entry.selector.clone()),
+                        )
+                        .child(
+                            div()
+                                .w(px(72.))
+                                .flex_none()
+                                .font_family(theme.mono_font_family.clone())
+                                .child(entry.short_hash.clone()),
+                        )
+                        .child(
+                            div()
+                                .w(px(180.))
+                                .flex_none()
+                                .truncate()
+                                .child(entry.date.clone()),
                         )
                         .child(
                             div()
-                                .flex()
-                                .flex_col()
                                 .flex_1()
                                 .min_w(px(0.))
-                                .child(new_header)
-                                .child(div().flex_1().min_h(px(0.)).child(new_list)),
+                                .truncate()
+                                .child(entry.message.clone()),
                         )
+                        .when(entry.signature.is_signed(), |this| {
+                            this.child(render_signature_badge(entry.signature.status, theme))
+                        })
                         .into_any_element()
-                }
-            },
+                })
+                .collect()
         };
 
-        let hunk_position = if hunk_count == 0 {
-            "0/0".to_string()
-        } else {
-            format!("{}/{}", diff_view.current_hunk + 1, hunk_count)
-        };
-        let status_left = match view_mode {
-            DiffViewMode::Split => div()
-                .flex()
-                .flex_row()
-                .items_center()
-                .gap(px(12.))
-                .child("视图: Split")
-                .child(format!("布局: {}", if two_pane { "分栏" } else { "对齐" }))
-                .child(div().truncate().child(format!("文件: {}", diff_view.title)))
-                .child(format!("对比: {}", compare_target_label(&compare_target)))
-                .child(format!("上下文: {context_lines}"))
-                .child(format!(
-                    "忽略空白: {}",
-                    if ignore_whitespace { "开" } else { "关" }
-                )),
-            DiffViewMode::Inline => div()
-                .flex()
-                .flex_row()
-                .items_center()
-                .gap(px(12.))
-                .child("视图: Inline")
-                .child(div().truncate().child(format!("文件: {}", diff_view.title)))
-                .child(format!("对比: {}", compare_target_label(&compare_target)))
-                .child(format!("上下文: {context_lines}"))
-                .child(format!(
-                    "忽略空白: {}",
-                    if ignore_whitespace { "开" } else { "关" }
-                )),
-        };
-        let status_right = div()
+        let toolbar = div()
             .flex()
             .flex_row()
             .items_center()
-            .gap(px(12.))
-            .child(format!("hunk: {hunk_position}"))
+            .gap(px(8.))
+            .p(px(12.))
+            .border_b_1()
+            .border_color(theme.border)
+            .child(
+                Button::new("reflog-back")
+                    .label("返回")
+                    .ghost()
+                    .tooltip_with_action("返回", &Back, Some(CONTEXT))
+                    .on_click(cx.listener(|this, _, _window, cx| {
+                        this.close_reflog_view();
+                        cx.notify();
+                    })),
+            )
             .child(
                 div()
+                    .flex_1()
+                    .min_w(px(0.))
                     .truncate()
-                    .text_color(cx.theme().muted_foreground)
-                    .child("Esc 返回 · Alt+N/P 导航 · Alt+W 空白 · Alt+V 视图"),
+                    .child("Reflog（HEAD）"),
+            )
+            .child(
+                Button::new("reflog-refresh")
+                    .label("刷新")
+                    .ghost()
+                    .disabled(loading)
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.reload_reflog(window, cx);
+                        cx.notify();
+                    })),
             );
-        let status_bar = div()
+
+        let action_button = |id: &'static str, action: ReflogAction, label: SharedString| {
+            Button::new(id)
+                .label(label)
+                .ghost()
+                .disabled(!can_act)
+                .on_click(cx.listener(move |this, _, window, cx| {
+                    this.run_reflog_action(action, window, cx);
+                }))
+        };
+
+        let actions = div()
             .flex()
             .flex_row()
             .items_center()
-            .justify_between()
+            .gap(px(8.))
+            .flex_wrap()
             .px(px(12.))
-            .py(px(6.))
+            .py(px(8.))
             .border_t_1()
-            .border_color(cx.theme().border.alpha(0.6))
-            .bg(cx.theme().muted.alpha(0.12))
-            .text_xs()
-            .text_color(cx.theme().muted_foreground)
-            .child(status_left)
-            .child(status_right);
+            .border_color(theme.border.alpha(0.6))
+            .child(action_button(
+                "reflog-checkout",
+                ReflogAction::Checkout,
+                ReflogAction::Checkout.label().into(),
+            ))
+            .child(action_button(
+                "reflog-reset-soft",
+                ReflogAction::ResetSoft,
+                ReflogAction::ResetSoft.label().into(),
+            ))
+            .child(action_button(
+                "reflog-reset-hard",
+                ReflogAction::ResetHard,
+                if confirm_hard_reset {
+                    "确认 reset --hard".into()
+                } else {
+                    ReflogAction::ResetHard.label().into()
+                },
+            ))
+            .child(div().w(px(1.)).h(px(20.)).bg(theme.border))
+            .child(
+                div()
+                    .w(px(260.))
+                    .child(Input::new(&reflog_view.branch_input).w_full()),
+            )
+            .child(action_button(
+                "reflog-create-branch",
+                ReflogAction::CreateBranch,
+                ReflogAction::CreateBranch.label().into(),
+            ));
 
-        let diff_scroll_ruler = if rows_len > 0 && hunk_count > 0 {
-            let theme = cx.theme();
-            let total_rows = rows_len.max(1) as f32;
-            let right_inset = px(14.);
-            let width = px(8.);
-            let max_markers = 200usize;
-            let marker_step = (hunk_count / max_markers).max(1);
-            let mut marker_indices: Vec<usize> = (0..hunk_count).step_by(marker_step).collect();
-            marker_indices.push(diff_view.current_hunk);
-            marker_indices.sort_unstable();
-            marker_indices.dedup();
+        div()
+            .flex()
+            .flex_col()
+            .size_full()
+            .child(toolbar)
+            .child(
+                div()
+                    .id("reflog-list")
+                    .flex()
+                    .flex_col()
+                    .flex_1()
+                    .min_h(px(0.))
+                    .gap(px(2.))
+                    .p(px(8.))
+                    .overflow_y_scroll()
+                    .children(list),
+            )
+            .children(details)
+            .children(commit_details)
+            .child(actions)
+    }
 
-            let mut ruler = div()
-                .id("diff-scroll-ruler")
-                .absolute()
-                .top(px(6.))
-                .bottom(px(6.))
-                .right(right_inset)
-                .w(width)
-                .rounded(px(999.))
-                .bg(theme.muted.alpha(0.1))
-                .border_1()
-                .border_color(theme.border.alpha(0.35))
-                .relative();
+    fn render_bisect_view(&mut self, window: &mut Window, cx: &mut Context<Self>) -> Div {
+        let culprit_hash = self
+            .bisect_view
+            .as_ref()
+            .and_then(|bisect_view| bisect_view.progress.culprit.as_ref())
+            .map(|commit| commit.hash.clone());
+        let culprit_details = culprit_hash.map(|hash| {
+            div()
+                .mx(px(12.))
+                .mb(px(8.))
+                .child(self.render_commit_details_pane(&hash, window, cx))
+        });
 
-            for hunk_index in marker_indices {
-                let row_index = diff_view.hunk_rows.get(hunk_index).copied().unwrap_or(0);
-                let fraction = (row_index as f32 / total_rows).clamp(0.0, 1.0);
-                let is_current = hunk_index == diff_view.current_hunk;
-                let color = if is_current {
-                    theme.blue
-                } else {
-                    theme.blue.alpha(0.65)
-                };
-                let height = if is_current { px(6.) } else { px(3.) };
-                let radius = if is_current { px(6.) } else { px(4.) };
+        let Some(bisect_view) = self.bisect_view.as_ref() else {
+            return div().p(px(12.)).child("No bisect view");
+        };
+        let theme = cx.theme();
+        let progress = &bisect_view.progress;
+        let busy = bisect_view.loading || bisect_view.running;
 
-                ruler = ruler.child(
+        let toolbar = div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap(px(8.))
+            .p(px(12.))
+            .border_b_1()
+            .border_color(theme.border)
+            .child(
+                Button::new("bisect-back")
+                    .label("返回")
+                    .ghost()
+                    .tooltip_with_action("返回", &Back, Some(CONTEXT))
+                    .on_click(cx.listener(|this, _, _window, cx| {
+                        this.close_bisect_view();
+                        cx.notify();
+                    })),
+            )
+            .child(div().flex_1().min_w(px(0.)).truncate().child("Bisect"))
+            .child(
+                Button::new("bisect-refresh")
+                    .label("刷新")
+                    .ghost()
+                    .disabled(busy)
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.reload_bisect_progress(window, cx);
+                        cx.notify();
+                    })),
+            );
+
+        let action_button = |id: &'static str, action: BisectAction| {
+            Button::new(id)
+                .label(action.label())
+                .ghost()
+                .disabled(busy)
+                .on_click(cx.listener(move |this, _, window, cx| {
+                    this.run_bisect_action(action, window, cx);
+                }))
+        };
+
+        let commit_line = |label: &'static str, commit: &CompareCommit| {
+            div()
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap(px(12.))
+                .text_sm()
+                .child(
                     div()
-                        .id(("diff-ruler-marker", hunk_index))
-                        .absolute()
-                        .top(relative(fraction))
-                        .left(px(1.))
-                        .right(px(1.))
-                        .h(height)
-                        .rounded(radius)
-                        .bg(color)
-                        .on_mouse_down(
-                            MouseButton::Left,
-                            cx.listener(move |this, _, _window, cx| {
-                                if let Some(diff_view) = this.diff_view.as_mut() {
-                                    if hunk_index < diff_view.hunk_rows.len() {
-                                        diff_view.current_hunk = hunk_index;
-                                        diff_view
-                                            .scroll_handle
-                                            .scroll_to_item(row_index, ScrollStrategy::Top);
-                                    }
-                                }
-                                cx.notify();
-                            }),
+                        .w(px(96.))
+                        .flex_none()
+                        .text_color(theme.muted_foreground)
+                        .child(label),
+                )
+                .child(
+                    div()
+                        .w(px(72.))
+                        .flex_none()
+                        .font_family(theme.mono_font_family.clone())
+                        .child(commit.short_hash.clone()),
+                )
+                .child(
+                    div()
+                        .flex_1()
+                        .min_w(px(0.))
+                        .truncate()
+                        .child(commit.subject.clone()),
+                )
+                .child(
+                    div()
+                        .flex_none()
+                        .text_color(theme.muted_foreground)
+                        .child(format!("{} · {}", commit.author, commit.date)),
+                )
+        };
+
+        let body = if bisect_view.loading && !progress.active {
+            div()
+                .text_sm()
+                .text_color(theme.muted_foreground)
+                .child("读取 bisect 状态中…")
+        } else if !progress.active {
+            div()
+                .flex()
+                .flex_col()
+                .gap(px(8.))
+                .child(
+                    div()
+                        .text_sm()
+                        .text_color(theme.muted_foreground)
+                        .child("指定一个有问题的提交和至少一个没有问题的提交，git 会逐个检出中间的提交让你标记。"),
+                )
+                .child(
+                    div()
+                        .flex()
+                        .flex_row()
+                        .items_center()
+                        .gap(px(8.))
+                        .child(div().w(px(96.)).text_sm().child("Bad"))
+                        .child(div().w(px(360.)).child(Input::new(&bisect_view.bad_input).w_full())),
+                )
+                .child(
+                    div()
+                        .flex()
+                        .flex_row()
+                        .items_center()
+                        .gap(px(8.))
+                        .child(div().w(px(96.)).text_sm().child("Good"))
+                        .child(div().w(px(360.)).child(Input::new(&bisect_view.good_input).w_full())),
+                )
+                .child(
+                    div()
+                        .flex()
+                        .flex_row()
+                        .child(action_button("bisect-start", BisectAction::Start).primary()),
+                )
+        } else {
+            let summary = match (progress.culprit.as_ref(), progress.remaining) {
+                (Some(_), _) => "已找到第一个有问题的提交".to_string(),
+                (None, Some((remaining, steps))) => format!(
+                    "测完当前提交后还剩 {remaining} 个提交（大约 {steps} 步）· 已标记 good {} 个、skip {} 个",
+                    progress.good_count, progress.skip_count
+                ),
+                (None, None) if progress.bad.is_none() => {
+                    "还没有标记有问题的提交：先在当前提交上标记 Bad".to_string()
+                }
+                (None, None) => "还没有标记没有问题的提交：先找一个正常的提交标记 Good".to_string(),
+            };
+
+            div()
+                .flex()
+                .flex_col()
+                .gap(px(8.))
+                .child(div().text_sm().child(summary))
+                .when_some(
+                    progress
+                        .current
+                        .as_ref()
+                        .filter(|_| progress.culprit.is_none()),
+                    |this, commit| this.child(commit_line("当前待测", commit)),
+                )
+                .when_some(progress.culprit.as_ref(), |this, commit| {
+                    this.child(commit_line("第一个坏提交", commit))
+                })
+                .child(
+                    div()
+                        .flex()
+                        .flex_row()
+                        .items_center()
+                        .gap(px(8.))
+                        .flex_wrap()
+                        .when(progress.culprit.is_none(), |this| {
+                            this.child(action_button("bisect-good", BisectAction::Good))
+                                .child(action_button("bisect-bad", BisectAction::Bad))
+                                .child(action_button("bisect-skip", BisectAction::Skip))
+                                .child(div().w(px(1.)).h(px(20.)).bg(theme.border))
+                        })
+                        .child(action_button("bisect-reset", BisectAction::Reset)),
+                )
+        };
+
+        let output = bisect_view.output.clone().map(|output| {
+            div()
+                .mx(px(12.))
+                .mb(px(8.))
+                .px(px(10.))
+                .py(px(8.))
+                .border_1()
+                .border_color(theme.border.alpha(0.5))
+                .rounded(theme.radius)
+                .text_xs()
+                .font_family(theme.mono_font_family.clone())
+                .text_color(theme.muted_foreground)
+                .children(
+                    output
+                        .lines()
+                        .map(|line| div().child(line.to_string()))
+                        .collect::<Vec<_>>(),
+                )
+        });
+
+        div()
+            .flex()
+            .flex_col()
+            .size_full()
+            .child(toolbar)
+            .child(
+                div()
+                    .id("bisect-body")
+                    .flex()
+                    .flex_col()
+                    .flex_1()
+                    .min_h(px(0.))
+                    .p(px(12.))
+                    .overflow_y_scroll()
+                    .child(body),
+            )
+            .children(output)
+            .children(culprit_details)
+    }
+
+    fn render_commit_stack_view(&mut self, window: &mut Window, cx: &mut Context<Self>) -> Div {
+        self.sync_stack_message_input(window, cx);
+
+        let Some(stack) = self.commit_stack.as_ref() else {
+            return div().p(px(12.)).child("No commit stack");
+        };
+        let theme = cx.theme();
+        let app = cx.entity();
+        let busy = stack.loading || stack.running;
+        let items = stack.list.read(cx).items_ref();
+        let count = items.len();
+        let changed = items.iter().any(|item| {
+            item.data.action != StackAction::Pick || item.data.edited_message.is_some()
+        }) || !items
+            .iter()
+            .map(|item| &item.data.hash)
+            .eq(stack.original_order.iter());
+        let has_selection = stack.editing_hash.is_some();
+
+        let toolbar = div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap(px(8.))
+            .p(px(12.))
+            .border_b_1()
+            .border_color(theme.border)
+            .child(
+                Button::new("commit-stack-back")
+                    .label("返回")
+                    .ghost()
+                    .tooltip_with_action("返回", &Back, Some(CONTEXT))
+                    .on_click(cx.listener(|this, _, _window, cx| {
+                        this.close_commit_stack_view();
+                        cx.notify();
+                    })),
+            )
+            .child(div().flex_none().child("提交栈"))
+            .child(
+                div()
+                    .w(px(260.))
+                    .child(Input::new(&stack.base_input).w_full()),
+            )
+            .child(
+                Button::new("commit-stack-reload")
+                    .label("载入")
+                    .ghost()
+                    .tooltip("重新读取栈底到 HEAD 之间的提交，放弃未执行的编辑")
+                    .disabled(busy)
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.reload_commit_stack(window, cx);
+                        cx.notify();
+                    })),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .min_w(px(0.))
+                    .truncate()
+                    .text_sm()
+                    .text_color(theme.muted_foreground)
+                    .child(match stack.merge_base.as_deref() {
+                        Some(merge_base) => format!(
+                            "{count} 个提交（基于 {}）· 拖动左侧把手调整顺序，拖到另一个提交上合并进去，自上而下依次应用",
+                            merge_base.chars().take(10).collect::<String>()
                         ),
-                );
-            }
+                        None if stack.loading => "读取中…".to_string(),
+                        None => "没有可编辑的提交".to_string(),
+                    }),
+            )
+            .child(
+                Button::new("commit-stack-apply")
+                    .label(if stack.running {
+                        "执行中…"
+                    } else {
+                        "执行 rebase"
+                    })
+                    .primary()
+                    .disabled(busy || !changed)
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.apply_commit_stack(window, cx);
+                    })),
+            );
 
-            Some(ruler)
-        } else {
-            None
-        };
+        let list = dnd_list(&stack.list, move |index, item, row_state, _window, cx| {
+            render_stack_row(&app, index, item, row_state, cx)
+        });
 
-        let mut viewport = div()
+        let editor = div()
             .flex()
             .flex_col()
-            .flex_1()
-            .relative()
-            .overflow_hidden()
-            .child(list)
-            .child(Scrollbar::uniform_scroll(&scroll_state, &scroll_handle));
-
-        if let Some(ruler) = diff_scroll_ruler {
-            viewport = viewport.child(ruler);
-        }
+            .gap(px(6.))
+            .px(px(12.))
+            .py(px(8.))
+            .border_t_1()
+            .border_color(theme.border.alpha(0.6))
+            .child(
+                div()
+                    .h(px(120.))
+                    .child(Input::new(&stack.message_input).w_full().h_full()),
+            )
+            .child(
+                div().flex().flex_row().justify_end().child(
+                    Button::new("commit-stack-save-message")
+                        .label("保存提交信息")
+                        .ghost()
+                        .disabled(busy || !has_selection)
+                        .on_click(cx.listener(|this, _, _window, cx| {
+                            this.save_stack_message(cx);
+                        })),
+                ),
+            );
 
         div()
             .flex()
             .flex_col()
             .size_full()
             .child(toolbar)
-            .child(viewport)
-            .child(status_bar)
+            .child(div().flex_1().min_h(px(0.)).p(px(8.)).child(list))
+            .child(editor)
     }
 
-    fn render_conflict_view(&mut self, window: &mut Window, cx: &mut Context<Self>) -> Div {
-        let Some(conflict_view) = self.conflict_view.as_mut() else {
-            return div().p(px(12.)).child("No conflict view");
+    fn render_branch_compare_view(&mut self, window: &mut Window, cx: &mut Context<Self>) -> Div {
+        let selected_commit = self
+            .branch_compare_view
+            .as_ref()
+            .and_then(|compare| compare.selected_commit.clone());
+        let commit_details = selected_commit
+            .as_ref()
+            .map(|hash| self.render_commit_details_pane(hash, window, cx));
+
+        let Some(compare) = self.branch_compare_view.as_ref() else {
+            return div().p(px(12.)).child("No branch compare view");
         };
+        let theme = cx.theme();
+        let loading = compare.loading;
 
-        let git_available = self.git_available;
-        let two_pane = matches!(self.split_layout, SplitLayout::TwoPane);
-        let show_result_editor = conflict_view.show_result_editor;
-        let title = conflict_view.title.clone();
-        let conflicts_count = conflict_view.conflicts.len();
-        let can_prev = conflict_view.current_conflict > 0;
-        let conflict_total = conflict_view.conflict_rows.len();
-        let can_next = conflict_view.current_conflict + 1 < conflict_total;
-        let has_path = conflict_view.path.is_some();
-        let can_save = has_path && conflicts_count == 0;
-        let can_add = can_save && git_available;
-        let rows_len = conflict_view.rows.len();
-        let scroll_handle = conflict_view.scroll_handle.clone();
-        let scroll_state = conflict_view.scroll_state.clone();
-        let row_height = window.line_height() + px(4.);
-        let item_sizes = conflict_view.item_sizes(row_height);
-        let conflict_position = if conflict_total == 0 {
-            "0/0".to_string()
-        } else {
-            format!("{}/{}", conflict_view.current_conflict + 1, conflict_total)
+        let toolbar = div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap(px(8.))
+            .p(px(12.))
+            .border_b_1()
+            .border_color(theme.border)
+            .child(
+                Button::new("branch-compare-back")
+                    .label("返回")
+                    .ghost()
+                    .tooltip_with_action("返回", &Back, Some(CONTEXT))
+                    .on_click(cx.listener(|this, _, _window, cx| {
+                        this.close_branch_compare_view();
+                        cx.notify();
+                    })),
+            )
+            .child(div().flex_none().child("对比分支"))
+            .child(
+                div()
+                    .w(px(220.))
+                    .child(Input::new(&compare.base_input).w_full()),
+            )
+            .child(
+                div()
+                    .text_color(theme.muted_foreground)
+                    .font_family(theme.mono_font_family.clone())
+                    .child("..."),
+            )
+            .child(
+                div()
+                    .w(px(220.))
+                    .child(Input::new(&compare.head_input).w_full()),
+            )
+            .child(
+                Button::new("branch-compare-swap")
+                    .label("交换")
+                    .ghost()
+                    .disabled(loading)
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.swap_branch_compare_refs(window, cx);
+                    })),
+            )
+            .child(
+                Button::new("branch-compare-run")
+                    .label(if loading { "对比中…" } else { "对比" })
+                    .primary()
+                    .disabled(loading)
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.run_branch_compare(window, cx);
+                    })),
+            );
+
+        let Some(comparison) = compare.comparison.as_ref() else {
+            return div().flex().flex_col().size_full().child(toolbar).child(
+                div()
+                    .p(px(12.))
+                    .text_sm()
+                    .text_color(theme.muted_foreground)
+                    .child(if loading {
+                        "正在对比…"
+                    } else {
+                        "输入两个 ref 后点击“对比”，查看领先/落后的提交和变更文件"
+                    }),
+            );
         };
 
-        let save_file_label: SharedString = if can_save {
-            "保存到文件".into()
-        } else if has_path {
-            "保存到文件（仍有冲突未解决）".into()
-        } else {
-            "保存到文件（demo 不支持）".into()
+        let muted_row = |text: String| {
+            div()
+                .px(px(10.))
+                .py(px(6.))
+                .text_sm()
+                .text_color(theme.muted_foreground)
+                .child(text)
+                .into_any_element()
         };
-        let save_add_label: SharedString = if can_add {
-            "保存并 git add".into()
-        } else if !git_available {
-            "保存并 git add（git 不可用）".into()
-        } else if has_path {
-            "保存并 git add（仍有冲突未解决）".into()
-        } else {
-            "保存并 git add（demo 不支持）".into()
+        let section = |text: String| {
+            div()
+                .px(px(10.))
+                .pt(px(8.))
+                .pb(px(4.))
+                .text_xs()
+                .text_color(theme.muted_foreground)
+                .child(text)
+                .into_any_element()
         };
-
-        let save_shortcut = if cfg!(target_os = "macos") {
-            "Cmd+S"
-        } else {
-            "Ctrl+S"
+        let commit_row = |commit: &CompareCommit| {
+            let is_selected = selected_commit.as_ref() == Some(&commit.hash);
+            let hash = commit.hash.clone();
+            div()
+                .id(SharedString::from(format!(
+                    "branch-compare-commit-{}",
+                    commit.hash
+                )))
+                .rounded(px(6.))
+                .when(is_selected, |this| {
+                    this.bg(theme.accent).text_color(theme.accent_foreground)
+                })
+                .when(!is_selected, |this| {
+                    this.cursor_pointer()
+                        .hover(|this| this.bg(theme.accent.alpha(0.4)))
+                })
+                .on_mouse_down(
+                    MouseButton::Left,
+                    cx.listener(move |this, _, _window, cx| {
+                        if let Some(compare) = this.branch_compare_view.as_mut() {
+                            compare.selected_commit = Some(hash.clone());
+                        }
+                        cx.notify();
+                    }),
+                )
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap(px(12.))
+                .h(px(28.))
+                .px(px(10.))
+                .text_sm()
+                .child(
+                    div()
+                        .w(px(72.))
+                        .flex_none()
+                        .font_family(theme.mono_font_family.clone())
+                        .child(commit.short_hash.clone()),
+                )
+                .child(
+                    div()
+                        .w(px(88.))
+                        .flex_none()
+                        .text_color(theme.muted_foreground)
+                        .child(commit.date.clone()),
+                )
+                .child(
+                    div()
+                        .flex_1()
+                        .min_w(px(0.))
+                        .truncate()
+                        .child(commit.subject.clone()),
+                )
+                .child(
+                    div()
+                        .w(px(120.))
+                        .flex_none()
+                        .truncate()
+                        .text_color(theme.muted_foreground)
+                        .child(commit.author.clone()),
+                )
+                .into_any_element()
         };
-        let save_add_shortcut = if cfg!(target_os = "macos") {
-            "Cmd+Shift+S"
-        } else {
-            "Ctrl+Shift+S"
+        let limit_note = |commits: &[CompareCommit]| {
+            (commits.len() >= BRANCH_COMPARE_COMMIT_LIMIT)
+                .then(|| muted_row(format!("仅显示最近 {BRANCH_COMPARE_COMMIT_LIMIT} 个提交")))
         };
-        let shortcuts_message: SharedString = format!(
-            "快捷键：\n\
-             Esc 返回\n\
-             Alt+N / Alt+P 下一/上一冲突\n\
-             Alt+L 切换对齐/分栏\n\
-             Alt+A 应用编辑\n\
-             {save_shortcut} 保存（冲突清零后）\n\
-             {save_add_shortcut} 保存并 git add（冲突清零后）\n"
-        )
-        .into();
 
-        let app = cx.entity();
-        let more_menu = {
-            let app_for_menu = app.clone();
-            Popover::new("conflict-more-menu")
-                .appearance(false)
-                .trigger(
-                    Button::new("conflict-more-trigger")
-                        .label("更多")
-                        .ghost()
-                        .tooltip("更多操作")
-                        .on_click(|_, _, _| {}),
-                )
-                .content(move |_, _window, cx| {
-                    let theme = cx.theme();
-                    let popover = cx.entity();
+        let mut commits: Vec<AnyElement> = Vec::new();
+        commits.push(section(format!(
+            "{} 领先 {} 个提交",
+            comparison.head,
+            comparison.ahead.len()
+        )));
+        if comparison.ahead.is_empty() {
+            commits.push(muted_row("（无）".to_string()));
+        }
+        commits.extend(comparison.ahead.iter().map(commit_row));
+        commits.extend(limit_note(&comparison.ahead));
+        commits.push(section(format!(
+            "{} 落后 {} 个提交（仅在 {} 上）",
+            comparison.head,
+            comparison.behind.len(),
+            comparison.base
+        )));
+        if comparison.behind.is_empty() {
+            commits.push(muted_row("（无）".to_string()));
+        }
+        commits.extend(comparison.behind.iter().map(commit_row));
+        commits.extend(limit_note(&comparison.behind));
 
-                    let make_action = move |id: &'static str,
-                                            label: SharedString,
-                                            disabled: bool,
-                                            action: Rc<dyn Fn(&mut Window, &mut App) + 'static>| {
-                        let popover = popover.clone();
-                        Button::new(id)
-                            .label(label)
+        let (total_added, total_removed) = comparison
+            .files
+            .iter()
+            .filter_map(|file| file.stat)
+            .fold((0, 0), |(added, removed), (file_added, file_removed)| {
+                (added + file_added, removed + file_removed)
+            });
+        let mut files: Vec<AnyElement> = vec![section(format!(
+            "{} 个文件变更  +{total_added} -{total_removed}（相对合并基 {}）",
+            comparison.files.len(),
+            comparison.merge_base.chars().take(7).collect::<String>()
+        ))];
+        if comparison.files.is_empty() {
+            files.push(muted_row("没有文件变更".to_string()));
+        }
+        files.extend(comparison.files.iter().enumerate().map(|(index, file)| {
+            let stat = match file.stat {
+                Some((added, removed)) => format!("+{added} -{removed}"),
+                None => "二进制".to_string(),
+            };
+            div()
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap(px(8.))
+                .child(
+                    div().flex_1().min_w(px(0.)).child(
+                        Button::new(("branch-compare-file", index))
+                            .label(format!("{} {}", file.status, file.path))
                             .ghost()
-                            .disabled(disabled)
                             .w_full()
-                            .on_click({
-                                let action = action.clone();
-                                move |_, window, cx| {
-                                    action(window, cx);
-                                    popover.update(cx, |state, cx| state.dismiss(window, cx));
-                                }
-                            })
-                            .into_any_element()
-                    };
-
-                    let split_label: SharedString = if two_pane {
-                        "分栏: 开".into()
-                    } else {
-                        "分栏: 关".into()
-                    };
-                    let result_label: SharedString = if show_result_editor {
-                        "结果面板: 开".into()
-                    } else {
-                        "结果面板: 关".into()
-                    };
+                            .on_click(cx.listener(move |this, _, window, cx| {
+                                this.open_branch_compare_file(index, window, cx);
+                                cx.notify();
+                            })),
+                    ),
+                )
+                .child(
+                    div()
+                        .flex_none()
+                        .text_xs()
+                        .font_family(theme.mono_font_family.clone())
+                        .text_color(theme.muted_foreground)
+                        .child(stat),
+                )
+                .into_any_element()
+        }));
 
-                    let app_for_split = app_for_menu.clone();
-                    let toggle_split = Rc::new(move |_window: &mut Window, cx: &mut App| {
-                        app_for_split.update(cx, |this, cx| {
-                            this.split_layout = match this.split_layout {
-                                SplitLayout::Aligned => SplitLayout::TwoPane,
-                                SplitLayout::TwoPane => SplitLayout::Aligned,
-                            };
-                            cx.notify();
-                        });
-                    });
+        div().flex().flex_col().size_full().child(toolbar).child(
+            div()
+                .flex()
+                .flex_row()
+                .flex_1()
+                .min_h(px(0.))
+                .child(
+                    div()
+                        .id("branch-compare-commits")
+                        .flex()
+                        .flex_col()
+                        .flex_1()
+                        .min_w(px(0.))
+                        .p(px(8.))
+                        .border_r_1()
+                        .border_color(theme.border.alpha(0.6))
+                        .overflow_y_scroll()
+                        .children(commits),
+                )
+                .when_some(commit_details, |this, details| {
+                    this.child(
+                        div()
+                            .flex_1()
+                            .min_w(px(0.))
+                            .p(px(8.))
+                            .border_r_1()
+                            .border_color(theme.border.alpha(0.6))
+                            .child(details),
+                    )
+                })
+                .child(
+                    div()
+                        .id("branch-compare-files")
+                        .flex()
+                        .flex_col()
+                        .flex_1()
+                        .min_w(px(0.))
+                        .gap(px(2.))
+                        .p(px(8.))
+                        .overflow_y_scroll()
+                        .children(files),
+                ),
+        )
+    }
 
-                    let app_for_result = app_for_menu.clone();
-                    let toggle_result = Rc::new(move |_window: &mut Window, cx: &mut App| {
-                        app_for_result.update(cx, |this, cx| {
-                            if let Some(view) = this.conflict_view.as_mut() {
-                                view.show_result_editor = !view.show_result_editor;
-                            }
-                            cx.notify();
-                        });
-                    });
+    fn render_repo_stats_view(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> Div {
+        let Some(view) = self.repo_stats_view.as_ref() else {
+            return div().p(px(12.)).child("No repo stats view");
+        };
+        let theme = cx.theme();
+        let stats = &view.stats;
+        let loading = view.loading;
 
-                    let app_for_apply = app_for_menu.clone();
-                    let apply_editor = Rc::new(move |window: &mut Window, cx: &mut App| {
-                        app_for_apply.update(cx, |this, cx| {
-                            this.apply_conflict_editor(window, cx);
-                        });
-                    });
+        let progress = if stats.files_total > 0 {
+            format!(
+                "{} 个提交 · 已扫描 {}/{} 个文件",
+                stats.commits, stats.files_scanned, stats.files_total
+            )
+        } else {
+            format!("{} 个提交", stats.commits)
+        };
+        let toolbar = div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap(px(8.))
+            .p(px(12.))
+            .border_b_1()
+            .border_color(theme.border)
+            .child(
+                Button::new("repo-stats-back")
+                    .label("返回")
+                    .ghost()
+                    .tooltip_with_action("返回", &Back, Some(CONTEXT))
+                    .on_click(cx.listener(|this, _, _window, cx| {
+                        this.close_repo_stats_view();
+                        cx.notify();
+                    })),
+            )
+            .child(div().flex_none().child("仓库统计"))
+            .child(
+                div()
+                    .flex_1()
+                    .min_w(px(0.))
+                    .truncate()
+                    .text_sm()
+                    .text_color(theme.muted_foreground)
+                    .child(if loading {
+                        format!("{progress} · 统计中…")
+                    } else {
+                        progress
+                    }),
+            )
+            .child(
+                Button::new("repo-stats-refresh")
+                    .label("重新统计")
+                    .primary()
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.reload_repo_stats(window, cx);
+                    })),
+            );
 
-                    let shortcuts_message = shortcuts_message.clone();
-                    let show_shortcuts = Rc::new(move |window: &mut Window, cx: &mut App| {
-                        window.push_notification(
-                            Notification::new().message(shortcuts_message.to_string()),
-                            cx,
-                        );
-                    });
+        let muted = |text: &str| {
+            div()
+                .text_sm()
+                .text_color(theme.muted_foreground)
+                .child(text.to_string())
+                .into_any_element()
+        };
+        let empty_text = if loading {
+            "统计中…"
+        } else {
+            "没有数据"
+        };
+        let section = |title: &str, body: Vec<AnyElement>| {
+            div()
+                .flex()
+                .flex_col()
+                .gap(px(8.))
+                .p(px(12.))
+                .border_1()
+                .border_color(theme.border)
+                .rounded(theme.radius)
+                .child(div().text_sm().child(title.to_string()))
+                .children(body)
+        };
+        let label_column = |width: f32, text: String| {
+            div()
+                .w(px(width))
+                .flex_none()
+                .truncate()
+                .text_sm()
+                .child(text)
+        };
+        let value_column = |text: String| {
+            div()
+                .w(px(80.))
+                .flex_none()
+                .text_right()
+                .text_sm()
+                .text_color(theme.muted_foreground)
+                .child(text)
+        };
 
-                    let app_for_save = app_for_menu.clone();
-                    let save_to_file = Rc::new(move |window: &mut Window, cx: &mut App| {
-                        app_for_save.update(cx, |this, cx| {
-                            this.save_conflict_to_working_tree(false, window, cx);
-                            cx.notify();
-                        });
-                    });
+        // 每周提交：竖直柱状图。
+        let weeks = stats.recent_weeks(REPO_STATS_WEEKS);
+        let mut weekly: Vec<AnyElement> = Vec::new();
+        if weeks.is_empty() {
+            weekly.push(muted(empty_text));
+        } else {
+            let max = weeks.iter().map(|(_, count)| *count).max().unwrap_or(1);
+            weekly.push(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_end()
+                    .gap(px(2.))
+                    .h(px(120.))
+                    .children(weeks.iter().map(|(_, count)| {
+                        div()
+                            .flex_1()
+                            .h(relative(*count as f32 / max as f32))
+                            .rounded_t(px(2.))
+                            .bg(theme.blue)
+                    }))
+                    .into_any_element(),
+            );
+            weekly.push(
+                div()
+                    .flex()
+                    .flex_row()
+                    .justify_between()
+                    .text_xs()
+                    .text_color(theme.muted_foreground)
+                    .child(weeks[0].0.to_string())
+                    .child(format!("最多 {max} 个/周"))
+                    .child(weeks[weeks.len() - 1].0.to_string())
+                    .into_any_element(),
+            );
+        }
 
-                    let app_for_save_add = app_for_menu.clone();
-                    let save_and_add = Rc::new(move |window: &mut Window, cx: &mut App| {
-                        app_for_save_add.update(cx, |this, cx| {
-                            this.save_conflict_to_working_tree(true, window, cx);
-                            cx.notify();
-                        });
-                    });
+        // 作者：每人一行，中间是与上图同一时间范围的每周迷你柱状图。
+        let authors = stats.top_authors(REPO_STATS_TOP);
+        let mut author_rows: Vec<AnyElement> = Vec::new();
+        if authors.is_empty() {
+            author_rows.push(muted(empty_text));
+        }
+        let author_max = authors
+            .iter()
+            .flat_map(|(_, author)| {
+                weeks
+                    .iter()
+                    .filter_map(move |(week, _)| author.weeks.get(*week).copied())
+            })
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        author_rows.extend(authors.iter().map(|(name, author)| {
+            div()
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap(px(12.))
+                .child(label_column(160., name.to_string()))
+                .child(
+                    div()
+                        .flex_1()
+                        .flex()
+                        .flex_row()
+                        .items_end()
+                        .gap(px(1.))
+                        .h(px(20.))
+                        .children(weeks.iter().map(|(week, _)| {
+                            let count = author.weeks.get(*week).copied().unwrap_or(0);
+                            div()
+                                .flex_1()
+                                .h(relative(count as f32 / author_max as f32))
+                                .bg(theme.accent)
+                        })),
+                )
+                .child(value_column(format!("{} 个提交", author.commits)))
+                .into_any_element()
+        }));
 
+        // 目录改动：增加和删除叠在同一条上。
+        let directories = stats.top_directories(REPO_STATS_TOP);
+        let mut directory_rows: Vec<AnyElement> = Vec::new();
+        if directories.is_empty() {
+            directory_rows.push(muted(empty_text));
+        }
+        let churn_max = directories
+            .iter()
+            .map(|(_, churn)| churn.total())
+            .max()
+            .unwrap_or(1)
+            .max(1) as f32;
+        directory_rows.extend(directories.iter().map(|(dir, churn)| {
+            div()
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap(px(12.))
+                .child(
+                    label_column(220., dir.to_string()).font_family(theme.mono_font_family.clone()),
+                )
+                .child(
                     div()
-                        .p(px(8.))
-                        .bg(theme.popover)
-                        .border_1()
-                        .border_color(theme.border)
-                        .rounded(theme.radius)
-                        .shadow_md()
+                        .flex_1()
                         .flex()
-                        .flex_col()
-                        .gap(px(6.))
+                        .flex_row()
+                        .h(px(10.))
+                        .rounded(px(2.))
+                        .bg(theme.muted)
                         .child(
                             div()
-                                .px(px(4.))
-                                .py(px(2.))
-                                .text_xs()
-                                .text_color(theme.muted_foreground)
-                                .child("视图"),
+                                .h_full()
+                                .w(relative(churn.added as f32 / churn_max))
+                                .bg(theme.green),
                         )
-                        .child(make_action(
-                            "conflict-more-toggle-split",
-                            split_label,
-                            false,
-                            toggle_split,
-                        ))
-                        .child(make_action(
-                            "conflict-more-toggle-result",
-                            result_label,
-                            false,
-                            toggle_result,
-                        ))
-                        .child(make_action(
-                            "conflict-more-apply",
-                            "应用编辑".into(),
-                            !show_result_editor,
-                            apply_editor,
-                        ))
-                        .child(div().h(px(1.)).bg(theme.border.alpha(0.4)))
                         .child(
                             div()
-                                .px(px(4.))
-                                .py(px(2.))
-                                .text_xs()
-                                .text_color(theme.muted_foreground)
-                                .child("保存"),
-                        )
-                        .child(make_action(
-                            "conflict-more-save",
-                            save_file_label.clone(),
-                            !can_save,
-                            save_to_file,
-                        ))
-                        .child(make_action(
-                            "conflict-more-save-add",
-                            save_add_label.clone(),
-                            !can_add,
-                            save_and_add,
-                        ))
-                        .child(make_action(
-                            "conflict-more-shortcuts",
-                            "快捷键…".into(),
-                            false,
-                            show_shortcuts,
-                        ))
-                })
+                                .h_full()
+                                .w(relative(churn.removed as f32 / churn_max))
+                                .bg(theme.red),
+                        ),
+                )
+                .child(value_column(format!("+{} -{}", churn.added, churn.removed)))
+                .into_any_element()
+        }));
+
+        // 最大的文件。
+        let mut file_rows: Vec<AnyElement> = Vec::new();
+        if stats.largest_files.is_empty() {
+            file_rows.push(muted(empty_text));
+        }
+        let size_max = stats
+            .largest_files
+            .first()
+            .map_or(1, |file| file.bytes)
+            .max(1) as f32;
+        file_rows.extend(stats.largest_files.iter().map(|file| {
+            div()
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap(px(12.))
+                .child(
+                    div()
+                        .flex_1()
+                        .min_w(px(0.))
+                        .truncate()
+                        .text_sm()
+                        .font_family(theme.mono_font_family.clone())
+                        .child(file.path.clone()),
+                )
+                .child(
+                    repo_stats_bar(file.bytes as f32 / size_max, theme.yellow, theme.muted)
+                        .w(px(160.)),
+                )
+                .child(value_column(format_byte_size(file.bytes)))
+                .into_any_element()
+        }));
+
+        // 语言：一条按行数分段的总条，下面是图例。
+        let languages = stats.languages_by_lines();
+        let total_lines = languages
+            .iter()
+            .map(|(_, stats)| stats.lines)
+            .sum::<u64>()
+            .max(1) as f32;
+        let mut language_rows: Vec<AnyElement> = Vec::new();
+        if languages.is_empty() {
+            language_rows.push(muted(empty_text));
+        } else {
+            language_rows.push(
+                div()
+                    .flex()
+                    .flex_row()
+                    .h(px(14.))
+                    .rounded(px(3.))
+                    .overflow_hidden()
+                    .bg(theme.muted)
+                    .children(languages.iter().enumerate().map(|(ix, (_, stats))| {
+                        div()
+                            .h_full()
+                            .w(relative(stats.lines as f32 / total_lines))
+                            .bg(repo_stats_color(ix))
+                    }))
+                    .into_any_element(),
+            );
+        }
+        language_rows.extend(languages.iter().enumerate().map(|(ix, (language, stats))| {
+            div()
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap(px(12.))
+                .child(
+                    div()
+                        .size(px(10.))
+                        .flex_none()
+                        .rounded(px(2.))
+                        .bg(repo_stats_color(ix)),
+                )
+                .child(label_column(120., language.to_string()))
+                .child(
+                    div()
+                        .flex_1()
+                        .text_sm()
+                        .text_color(theme.muted_foreground)
+                        .child(format!("{} 个文件", stats.files)),
+                )
+                .child(value_column(format!("{} 行", stats.lines)))
+                .child(value_column(format!(
+                    "{:.1}%",
+                    stats.lines as f32 / total_lines * 100.
+                )))
+                .into_any_element()
+        }));
+
+        div().flex().flex_col().size_full().child(toolbar).child(
+            div()
+                .id("repo-stats")
+                .flex()
+                .flex_col()
+                .flex_1()
+                .min_h(px(0.))
+                .gap(px(12.))
+                .p(px(12.))
+                .overflow_y_scroll()
+                .child(section("每周提交", weekly))
+                .child(section("作者（每周提交）", author_rows))
+                .child(section("目录改动行数", directory_rows))
+                .child(section("当前代码行数（按语言）", language_rows))
+                .child(section("最大的文件", file_rows)),
+        )
+    }
+
+    fn render_pull_requests_view(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> Div {
+        let Some(view) = self.pull_requests_view.as_ref() else {
+            return div().p(px(12.)).child("No pull request view");
         };
+        let theme = cx.theme();
+        let loading = view.loading;
+        let running = view.running;
+        let request_label = view
+            .remote
+            .as_ref()
+            .map(|remote| remote.kind.request_label())
+            .unwrap_or("PR");
 
         let toolbar = div()
             .flex()
-            .flex_col()
+            .flex_row()
+            .items_center()
             .gap(px(8.))
             .p(px(12.))
             .border_b_1()
-            .border_color(cx.theme().border)
+            .border_color(theme.border)
             .child(
-                div()
-                    .flex()
-                    .flex_row()
-                    .items_center()
-                    .gap(px(8.))
-                    .child(
-                        Button::new("conflict-back")
-                            .label("返回")
-                            .ghost()
-                            .tooltip_with_action("返回", &Back, Some(CONTEXT))
-                            .on_click(cx.listener(|this, _, _window, cx| {
-                                this.close_conflict_view();
-                                cx.notify();
-                            })),
-                    )
-                    .child(div().flex_1().min_w(px(0.)).truncate().child(title)),
+                Button::new("pull-requests-back")
+                    .label("返回")
+                    .ghost()
+                    .tooltip_with_action("返回", &Back, Some(CONTEXT))
+                    .on_click(cx.listener(|this, _, _window, cx| {
+                        this.close_pull_requests_view();
+                        cx.notify();
+                    })),
             )
+            .child(div().flex_none().child("Pull Request"))
+            .when_some(view.remote.as_ref(), |this, remote| {
+                this.child(
+                    div()
+                        .flex_1()
+                        .min_w(px(0.))
+                        .truncate()
+                        .text_sm()
+                        .text_color(theme.muted_foreground)
+                        .child(format!(
+                            "{} · {}/{}",
+                            remote.name, remote.host, remote.project
+                        )),
+                )
+            })
+            .when(view.remote.is_none(), |this| this.child(div().flex_1()))
             .child(
                 div()
-                    .flex()
-                    .flex_row()
-                    .items_center()
-                    .gap(px(12.))
-                    .flex_wrap()
-                    .child(div().child(format!("冲突: {conflict_position}")))
-                    .child(
-                        Button::new("conflict-prev")
-                            .label("上一冲突")
-                            .ghost()
-                            .tooltip_with_action("上一冲突", &Prev, Some(CONTEXT))
-                            .disabled(!can_prev)
-                            .on_click(cx.listener(|this, _, _window, cx| {
-                                this.jump_conflict(-1);
-                                cx.notify();
-                            })),
-                    )
-                    .child(
-                        Button::new("conflict-next")
-                            .label("下一冲突")
-                            .ghost()
-                            .tooltip_with_action("下一冲突", &Next, Some(CONTEXT))
-                            .disabled(!can_next)
-                            .on_click(cx.listener(|this, _, _window, cx| {
-                                this.jump_conflict(1);
-                                cx.notify();
-                            })),
-                    )
-                    .child(more_menu),
+                    .w(px(240.))
+                    .child(Input::new(&view.token_input).w_full()),
+            )
+            .child(
+                Button::new("pull-requests-save-token")
+                    .label("保存 token")
+                    .ghost()
+                    .disabled(view.remote.is_none())
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.save_forge_token(window, cx);
+                    })),
+            )
+            .child(
+                Button::new("pull-requests-refresh")
+                    .label(if loading { "读取中…" } else { "刷新" })
+                    .primary()
+                    .disabled(loading)
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.reload_pull_requests(window, cx);
+                    })),
             );
 
-        let list = match self.split_layout {
-            SplitLayout::Aligned => v_virtual_list(
-                cx.entity(),
-                "conflict-aligned-list",
-                item_sizes,
-                move |this, visible_range, window, cx| {
-                    visible_range
-                        .map(|index| this.render_conflict_row(index, row_height, window, cx))
-                        .collect::<Vec<_>>()
-                },
-            )
-            .track_scroll(&scroll_handle)
-            .into_any_element(),
-            SplitLayout::TwoPane => {
-                let show_base = conflict_view
-                    .conflicts
-                    .iter()
-                    .any(|conflict| conflict.base.is_some());
+        let muted = |text: String| {
+            div()
+                .p(px(12.))
+                .text_sm()
+                .text_color(theme.muted_foreground)
+                .child(text)
+        };
+        if !view.detected {
+            return div()
+                .flex()
+                .flex_col()
+                .size_full()
+                .child(toolbar)
+                .child(muted("正在读取…".to_string()));
+        }
+        if view.remote.is_none() {
+            return div()
+                .flex()
+                .flex_col()
+                .size_full()
+                .child(toolbar)
+                .child(muted(
+                    "当前仓库没有指向 GitHub / GitLab 的 remote".to_string(),
+                ));
+        }
 
-                let ours_list = v_virtual_list(
-                    cx.entity(),
-                    "conflict-pane-ours",
-                    item_sizes.clone(),
-                    move |this, visible_range, window, cx| {
-                        visible_range
-                            .map(|index| {
-                                this.render_conflict_pane_row(
-                                    ConflictPane::Ours,
-                                    index,
-                                    row_height,
-                                    window,
-                                    cx,
-                                )
-                            })
-                            .collect::<Vec<_>>()
-                    },
+        let mut requests: Vec<AnyElement> = Vec::new();
+        if view.requests.is_empty() {
+            requests.push(
+                muted(if loading {
+                    "正在读取…".to_string()
+                } else {
+                    format!("没有打开的 {request_label}")
+                })
+                .into_any_element(),
+            );
+        }
+        requests.extend(view.requests.iter().enumerate().map(|(index, request)| {
+            let is_selected = view.selected == Some(index);
+            div()
+                .id(("pull-request", index))
+                .rounded(px(6.))
+                .when(is_selected, |this| {
+                    this.bg(theme.accent).text_color(theme.accent_foreground)
+                })
+                .when(!is_selected, |this| {
+                    this.cursor_pointer()
+                        .hover(|this| this.bg(theme.accent.alpha(0.4)))
+                })
+                .on_mouse_down(
+                    MouseButton::Left,
+                    cx.listener(move |this, _, window, cx| {
+                        this.select_pull_request(index, window, cx);
+                    }),
                 )
-                .track_scroll(&scroll_handle)
-                .into_any_element();
-
-                let base_list = show_base.then(|| {
-                    v_virtual_list(
-                        cx.entity(),
-                        "conflict-pane-base",
-                        item_sizes.clone(),
-                        move |this, visible_range, window, cx| {
-                            visible_range
-                                .map(|index| {
-                                    this.render_conflict_pane_row(
-                                        ConflictPane::Base,
-                                        index,
-                                        row_height,
-                                        window,
-                                        cx,
-                                    )
-                                })
-                                .collect::<Vec<_>>()
-                        },
-                    )
-                    .track_scroll(&scroll_handle)
-                    .into_any_element()
-                });
-
-                let theirs_list = v_virtual_list(
-                    cx.entity(),
-                    "conflict-pane-theirs",
-                    item_sizes,
-                    move |this, visible_range, window, cx| {
-                        visible_range
-                            .map(|index| {
-                                this.render_conflict_pane_row(
-                                    ConflictPane::Theirs,
-                                    index,
-                                    row_height,
-                                    window,
-                                    cx,
-                                )
-                            })
-                            .collect::<Vec<_>>()
-                    },
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap(px(12.))
+                .h(px(28.))
+                .px(px(10.))
+                .text_sm()
+                .child(
+                    div()
+                        .w(px(56.))
+                        .flex_none()
+                        .font_family(theme.mono_font_family.clone())
+                        .child(format!("#{}", request.number)),
                 )
-                .track_scroll(&scroll_handle)
-                .into_any_element();
-
-                let theme = cx.theme();
-                let pane_header_height = window.line_height() + px(6.);
-                let ours_label = conflict_view
-                    .conflicts
-                    .first()
-                    .map(|c| c.ours_branch_name.clone())
-                    .unwrap_or_else(|| "Ours".to_string());
-                let theirs_label = conflict_view
-                    .conflicts
-                    .first()
-                    .map(|c| c.theirs_branch_name.clone())
-                    .unwrap_or_else(|| "Theirs".to_string());
-
-                let old_header = div()
-                    .h(pane_header_height)
-                    .px(px(12.))
-                    .flex()
-                    .items_center()
-                    .bg(theme.muted.alpha(0.2))
-                    .text_sm()
-                    .text_color(theme.muted_foreground)
-                    .child(format!("Ours: {ours_label}"));
-                let base_header = div()
-                    .h(pane_header_height)
-                    .px(px(12.))
-                    .flex()
-                    .items_center()
-                    .bg(theme.muted.alpha(0.2))
-                    .text_sm()
-                    .text_color(theme.muted_foreground)
-                    .child("Base（diff3）");
-                let theirs_header = div()
-                    .h(pane_header_height)
-                    .px(px(12.))
-                    .flex()
-                    .items_center()
-                    .bg(theme.muted.alpha(0.2))
-                    .text_sm()
-                    .text_color(theme.muted_foreground)
-                    .child(format!("Theirs: {theirs_label}"));
-
-                let border_color = theme.border.alpha(0.6);
-                let mut row = div().flex().flex_row().size_full().child(
+                .child(
                     div()
-                        .flex()
-                        .flex_col()
                         .flex_1()
                         .min_w(px(0.))
-                        .border_r_1()
-                        .border_color(border_color)
-                        .child(old_header)
-                        .child(div().flex_1().min_h(px(0.)).child(ours_list)),
-                );
+                        .truncate()
+                        .child(if request.draft {
+                            format!("[草稿] {}", request.title)
+                        } else {
+                            request.title.clone()
+                        }),
+                )
+                .child(
+                    div()
+                        .w(px(120.))
+                        .flex_none()
+                        .truncate()
+                        .text_color(theme.muted_foreground)
+                        .child(request.author.clone()),
+                )
+                .into_any_element()
+        }));
+
+        let details = view
+            .selected
+            .and_then(|index| view.requests.get(index))
+            .map(|request| {
+                let comments = view
+                    .comments
+                    .as_ref()
+                    .filter(|comments| comments.number == request.number);
+                let mut comment_rows: Vec<AnyElement> = Vec::new();
+                match comments {
+                    Some(comments) if comments.loading => {
+                        comment_rows.push(muted("正在读取评论…".to_string()).into_any_element());
+                    }
+                    Some(comments) if comments.comments.is_empty() => {
+                        comment_rows.push(muted("没有评论".to_string()).into_any_element());
+                    }
+                    Some(comments) => {
+                        comment_rows.extend(comments.comments.iter().map(|comment| {
+                            let location = match (&comment.path, comment.line) {
+                                (Some(path), Some(line)) => format!(" · {path}:{line}"),
+                                (Some(path), None) => format!(" · {path}"),
+                                _ => String::new(),
+                            };
+                            div()
+                                .flex()
+                                .flex_col()
+                                .gap(px(2.))
+                                .py(px(6.))
+                                .border_b_1()
+                                .border_color(theme.border.alpha(0.4))
+                                .child(div().text_xs().text_color(theme.muted_foreground).child(
+                                    format!(
+                                        "{} · {}{location}",
+                                        comment.author, comment.created_at
+                                    ),
+                                ))
+                                .child(div().text_sm().child(comment.body.clone()))
+                                .into_any_element()
+                        }));
+                    }
+                    None => {}
+                }
 
-                if let Some(base_list) = base_list {
-                    row = row.child(
+                div()
+                    .id("pull-request-details")
+                    .flex()
+                    .flex_col()
+                    .gap(px(8.))
+                    .flex_1()
+                    .min_w(px(0.))
+                    .p(px(12.))
+                    .overflow_y_scroll()
+                    .child(div().text_lg().child(request.title.clone()))
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(theme.muted_foreground)
+                            .font_family(theme.mono_font_family.clone())
+                            .child(format!(
+                                "{request_label} #{} · {} · {} → {} · 更新于 {}",
+                                request.number,
+                                request.author,
+                                request.source_branch,
+                                request.target_branch,
+                                request.updated_at
+                            )),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(theme.muted_foreground)
+                            .child(request.url.clone()),
+                    )
+                    .child(
                         div()
                             .flex()
-                            .flex_col()
-                            .flex_1()
-                            .min_w(px(0.))
-                            .border_r_1()
-                            .border_color(border_color)
-                            .child(base_header)
-                            .child(div().flex_1().min_h(px(0.)).child(base_list)),
-                    );
-                }
+                            .flex_row()
+                            .gap(px(8.))
+                            .child(
+                                Button::new("pull-request-diff")
+                                    .label("查看 diff")
+                                    .primary()
+                                    .disabled(running)
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        this.open_pull_request_diff(window, cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("pull-request-checkout")
+                                    .label("检出到本地分支")
+                                    .ghost()
+                                    .disabled(running)
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        this.checkout_pull_request(window, cx);
+                                    })),
+                            ),
+                    )
+                    .when(!request.body.trim().is_empty(), |this| {
+                        this.child(
+                            div()
+                                .p(px(8.))
+                                .rounded(px(6.))
+                                .bg(theme.muted.alpha(0.3))
+                                .text_sm()
+                                .child(request.body.clone()),
+                        )
+                    })
+                    .child(
+                        div()
+                            .pt(px(8.))
+                            .text_xs()
+                            .text_color(theme.muted_foreground)
+                            .child("评论"),
+                    )
+                    .children(comment_rows)
+            });
 
-                row.child(
+        div().flex().flex_col().size_full().child(toolbar).child(
+            div()
+                .flex()
+                .flex_row()
+                .flex_1()
+                .min_h(px(0.))
+                .child(
                     div()
+                        .id("pull-request-list")
                         .flex()
                         .flex_col()
                         .flex_1()
                         .min_w(px(0.))
-                        .child(theirs_header)
-                        .child(div().flex_1().min_h(px(0.)).child(theirs_list)),
+                        .p(px(8.))
+                        .border_r_1()
+                        .border_color(theme.border.alpha(0.6))
+                        .overflow_y_scroll()
+                        .children(requests),
                 )
-                .into_any_element()
+                .children(details),
+        )
+    }
+
+    fn render_file_history_overlay(
+        &mut self,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<AnyElement> {
+        let selected_hash = self.file_history_overlay.as_ref().and_then(|overlay| {
+            if overlay.loading {
+                return None;
             }
-        };
+            let query = overlay.filter_input.read(cx).value().to_string();
+            let filtered = filter_commits(&overlay.commits, &query);
+            filtered
+                .get(overlay.selected.min(filtered.len().saturating_sub(1)))
+                .map(|entry| entry.hash.clone())
+        });
+        let commit_details =
+            selected_hash.map(|hash| self.render_commit_details_pane(&hash, window, cx));
 
-        let status_left = div()
-            .flex()
-            .flex_row()
-            .items_center()
-            .gap(px(12.))
-            .child(div().truncate().child(format!(
-                "文件: {}",
-                conflict_view.path.as_deref().unwrap_or("<demo>")
-            )))
-            .child(format!("未解决: {conflicts_count}"));
-        let status_hint = div()
-            .truncate()
-            .text_color(cx.theme().muted_foreground)
-            .child("Esc 返回 · Alt+N/P 导航 · Alt+A 应用 · Cmd/Ctrl+S 保存");
-        let status_right = div()
-            .flex()
-            .flex_row()
-            .items_center()
-            .gap(px(12.))
-            .child(if can_add {
-                "可保存并标记已解决".to_string()
-            } else if can_save {
-                "可保存（git 不可用，无法标记已解决）".to_string()
-            } else if has_path {
-                "仍有冲突未解决（编辑后点“应用”）".to_string()
-            } else {
-                "demo（不可保存）".to_string()
-            })
-            .child(status_hint);
-        let status_bar = div()
-            .flex()
-            .flex_row()
-            .items_center()
-            .justify_between()
-            .px(px(12.))
-            .py(px(6.))
-            .border_t_1()
-            .border_color(cx.theme().border.alpha(0.6))
-            .bg(cx.theme().muted.alpha(0.12))
-            .text_xs()
-            .text_color(cx.theme().muted_foreground)
-            .child(status_left)
-            .child(status_right);
+        let overlay = self.file_history_overlay.as_mut()?;
+        let theme = cx.theme();
+        let app = cx.entity();
 
-        let conflict_scroll_ruler = if rows_len > 0 && !conflict_view.conflict_rows.is_empty() {
-            let theme = cx.theme();
-            let total_rows = rows_len.max(1) as f32;
-            let right_inset = px(14.);
-            let width = px(8.);
-            let conflict_total = conflict_view.conflict_rows.len();
-            let max_markers = 200usize;
-            let marker_step = (conflict_total / max_markers).max(1);
-            let mut marker_indices: Vec<usize> = (0..conflict_total).step_by(marker_step).collect();
-            marker_indices.push(conflict_view.current_conflict);
-            marker_indices.sort_unstable();
-            marker_indices.dedup();
+        let loading = overlay.loading;
+        let mode = overlay.mode;
+        let filter_input = overlay.filter_input.clone();
+        let title = overlay.path.clone();
+        let history_path = title.clone();
 
-            let mut ruler = div()
-                .id("conflict-scroll-ruler")
-                .absolute()
-                .top(px(6.))
-                .bottom(px(6.))
-                .right(right_inset)
-                .w(width)
-                .rounded(px(999.))
-                .bg(theme.muted.alpha(0.1))
-                .border_1()
-                .border_color(theme.border.alpha(0.35))
-                .relative();
+        let query = overlay.filter_input.read(cx).value().to_string();
+        let filtered = filter_commits(&overlay.commits, &query);
 
-            for conflict_index in marker_indices {
-                let row_index = conflict_view
-                    .conflict_rows
-                    .get(conflict_index)
-                    .copied()
-                    .unwrap_or(0);
-                let fraction = (row_index as f32 / total_rows).clamp(0.0, 1.0);
-                let is_current = conflict_index == conflict_view.current_conflict;
-                let color = if is_current {
-                    theme.red
-                } else {
-                    theme.red.alpha(0.65)
-                };
-                let height = if is_current { px(6.) } else { px(3.) };
-                let radius = if is_current { px(6.) } else { px(4.) };
+        let selected = overlay.selected.min(filtered.len().saturating_sub(1));
 
-                ruler = ruler.child(
-                    div()
-                        .id(("conflict-ruler-marker", conflict_index))
-                        .absolute()
-                        .top(relative(fraction))
-                        .left(px(1.))
-                        .right(px(1.))
-                        .h(height)
-                        .rounded(radius)
-                        .bg(color)
-                        .on_mouse_down(
-                            MouseButton::Left,
-                            cx.listener(move |this, _, _window, cx| {
-                                if let Some(conflict_view) = this.conflict_view.as_mut() {
-                                    if conflict_index < conflict_view.conflict_rows.len() {
-                                        conflict_view.current_conflict = conflict_index;
-                                        conflict_view
-                                            .scroll_handle
-                                            .scroll_to_item(row_index, ScrollStrategy::Top);
-                                    }
-                                }
-                                cx.notify();
-                            }),
-                        ),
-                );
-            }
+        let can_apply = !loading && !filtered.is_empty();
+        let details = (!loading)
+            .then(|| filtered.get(selected))
+            .flatten()
+            .map(|entry| {
+                render_signature_details(&entry.hash, &entry.subject, &entry.signature, theme)
+            });
 
-            Some(ruler)
+        let list: Vec<AnyElement> = if loading {
+            vec![
+                div()
+                    .px(px(12.))
+                    .py(px(10.))
+                    .text_sm()
+                    .text_color(theme.muted_foreground)
+                    .child("加载历史中…")
+                    .into_any_element(),
+            ]
+        } else if filtered.is_empty() {
+            vec![
+                div()
+                    .px(px(12.))
+                    .py(px(10.))
+                    .text_sm()
+                    .text_color(theme.muted_foreground)
+                    .child("没有匹配的 commit")
+                    .into_any_element(),
+            ]
         } else {
-            None
-        };
-
-        let mut viewport = div()
-            .flex()
-            .flex_col()
-            .flex_1()
-            .relative()
-            .overflow_hidden()
-            .child(list)
-            .child(Scrollbar::uniform_scroll(&scroll_state, &scroll_handle));
-
-        if let Some(ruler) = conflict_scroll_ruler {
-            viewport = viewport.child(ruler);
-        }
-
-        let result_panel = show_result_editor.then(|| {
-            div()
-                .flex()
-                .flex_col()
-                .flex_none()
-                .h(px(220.))
-                .border_t_1()
-                .border_color(cx.theme().border.alpha(0.6))
-                .bg(cx.theme().muted.alpha(0.06))
-                .child(
+            filtered
+                .iter()
+                .take(120)
+                .enumerate()
+                .map(|(index, entry)| {
+                    let is_selected = index == selected;
+                    let label = match &entry.old_path {
+                        Some(old_path) => format!(
+                            "{}  {}  （{old_path} → {}）",
+                            entry.short_hash, entry.subject, entry.path
+                        ),
+                        None if entry.path != history_path => {
+                            format!(
+                                "{}  {}  （{}）",
+                                entry.short_hash, entry.subject, entry.path
+                            )
+                        }
+                        None => format!("{}  {}", entry.short_hash, entry.subject),
+                    };
+                    let app = app.clone();
                     div()
+                        .id(("history-commit", index))
                         .flex()
                         .flex_row()
                         .items_center()
-                        .justify_between()
-                        .px(px(12.))
-                        .py(px(8.))
-                        .child(div().text_sm().child("合并结果（可编辑）"))
-                        .child(
-                            div().flex().flex_row().items_center().gap(px(6.)).child(
-                                Button::new("conflict-apply-editor-inline")
-                                    .label("应用")
-                                    .ghost()
-                                    .on_click(cx.listener(|this, _, window, cx| {
-                                        this.apply_conflict_editor(window, cx);
-                                    })),
-                            ),
-                        ),
-                )
-                .child(
-                    div()
-                        .flex()
-                        .flex_col()
-                        .flex_1()
-                        .min_h(px(0.))
-                        .p(px(12.))
-                        .child(Input::new(&conflict_view.result_input).h_full().w_full()),
-                )
-        });
+                        .h(px(32.))
+                        .px(px(10.))
+                        .rounded(px(6.))
+                        .text_sm()
+                        .when(is_selected, |this| {
+                            this.bg(theme.accent)
+                                .text_color(theme.accent_foreground)
+                                .cursor_default()
+                        })
+                        .when(!is_selected, |this| {
+                            this.bg(theme.transparent)
+                                .text_color(theme.popover_foreground)
+                                .cursor_pointer()
+                                .hover(|this| {
+                                    this.bg(theme.accent.alpha(0.4))
+                                        .text_color(theme.accent_foreground)
+                                })
+                                .active(|this| {
+                                    this.bg(theme.accent).text_color(theme.accent_foreground)
+                                })
+                                .on_mouse_down(MouseButton::Left, move |_, window, cx| {
+                                    window.prevent_default();
+                                    app.update(cx, |this, cx| {
+                                        if let Some(overlay) = this.file_history_overlay.as_mut() {
+                                            overlay.selected = index;
+                                        }
+                                        cx.notify();
+                                    });
+                                })
+                        })
+                        .gap(px(8.))
+                        .child(div().flex_1().min_w(px(0.)).truncate().child(label))
+                        .when(entry.has_note, |this| {
+                            this.child(render_file_badge("备注".into(), theme))
+                        })
+                        .when(entry.signature.is_signed(), |this| {
+                            this.child(render_signature_badge(entry.signature.status, theme))
+                        })
+                        .into_any_element()
+                })
+                .collect()
+        };
 
-        let mut root = div()
+        let mode_parent_active = mode == HistoryCompareMode::ParentToCommit;
+        let mode_worktree_active = mode == HistoryCompareMode::CommitToWorktree;
+
+        let overlay_container = div()
+            .id("file-history-overlay")
+            .w(px(720.))
+            .max_w(relative(0.92))
+            .bg(theme.popover)
+            .border_1()
+            .border_color(theme.border)
+            .rounded(theme.radius)
+            .shadow_lg()
             .flex()
             .flex_col()
-            .size_full()
-            .child(toolbar)
-            .child(viewport);
-
-        if let Some(panel) = result_panel {
-            root = root.child(panel);
-        }
+            .gap(px(10.))
+            .p(px(12.))
+            .on_mouse_down(MouseButton::Left, |_, window, cx| {
+                window.prevent_default();
+                cx.stop_propagation();
+            })
+            .on_key_down({
+                let app = app.clone();
+                move |event, window, cx| {
+                    let handled = app.update(cx, |this, cx| {
+                        this.handle_file_history_overlay_key(event, window, cx)
+                    });
+                    if handled {
+                        window.prevent_default();
+                        cx.stop_propagation();
+                    }
+                }
+            })
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .gap(px(12.))
+                    .child(
+                        div().flex().flex_col().gap(px(2.)).child("历史对比").child(
+                            div()
+                                .text_xs()
+                                .text_color(theme.muted_foreground)
+                                .truncate()
+                                .child(title),
+                        ),
+                    )
+                    .child(
+                        Button::new("history-overlay-close")
+                            .label("关闭 (Esc)")
+                            .ghost()
+                            .on_click({
+                                let app = app.clone();
+                                move |_, window, cx| {
+                                    app.update(cx, |this, cx| {
+                                        this.close_file_history_overlay(window, cx);
+                                    });
+                                }
+                            }),
+                    ),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .gap(px(12.))
+                    .child(Input::new(&filter_input).w_full())
+                    .child(
+                        div()
+                            .flex()
+                            .flex_row()
+                            .items_center()
+                            .gap(px(6.))
+                            .child(
+                                Button::new("history-mode-parent")
+                                    .label("变更（parent→commit）")
+                                    .when(mode_parent_active, |this| this.primary())
+                                    .when(!mode_parent_active, |this| this.ghost())
+                                    .on_click({
+                                        let app = app.clone();
+                                        move |_, _window, cx| {
+                                            app.update(cx, |this, cx| {
+                                                if let Some(overlay) =
+                                                    this.file_history_overlay.as_mut()
+                                                {
+                                                    overlay.mode =
+                                                        HistoryCompareMode::ParentToCommit;
+                                                }
+                                                cx.notify();
+                                            });
+                                        }
+                                    }),
+                            )
+                            .child(
+                                Button::new("history-mode-worktree")
+                                    .label("对比工作区")
+                                    .when(mode_worktree_active, |this| this.primary())
+                                    .when(!mode_worktree_active, |this| this.ghost())
+                                    .on_click({
+                                        let app = app.clone();
+                                        move |_, _window, cx| {
+                                            app.update(cx, |this, cx| {
+                                                if let Some(overlay) =
+                                                    this.file_history_overlay.as_mut()
+                                                {
+                                                    overlay.mode =
+                                                        HistoryCompareMode::CommitToWorktree;
+                                                }
+                                                cx.notify();
+                                            });
+                                        }
+                                    }),
+                            ),
+                    ),
+            )
+            .child(
+                div()
+                    .id("file-history-overlay-list")
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.))
+                    .min_h(px(0.))
+                    .max_h(px(360.))
+                    .overflow_y_scroll()
+                    .border_1()
+                    .border_color(theme.border.alpha(0.5))
+                    .rounded(theme.radius)
+                    .p(px(6.))
+                    .children(list),
+            )
+            .children(details)
+            .children(commit_details)
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .gap(px(12.))
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(theme.muted_foreground)
+                            .child("↑↓ 选择 · Enter 打开 · Esc 关闭"),
+                    )
+                    .child(
+                        Button::new("history-overlay-apply")
+                            .label("打开对比")
+                            .primary()
+                            .disabled(!can_apply)
+                            .on_click({
+                                let app = app.clone();
+                                move |_, window, cx| {
+                                    app.update(cx, |this, cx| {
+                                        this.apply_selected_file_history_commit(window, cx);
+                                    });
+                                }
+                            }),
+                    ),
+            );
 
-        root.child(status_bar)
+        Some(
+            div()
+                .id("file-history-overlay-backdrop")
+                .absolute()
+                .top(px(0.))
+                .bottom(px(0.))
+                .left(px(0.))
+                .right(px(0.))
+                .bg(theme.background.alpha(0.75))
+                .flex()
+                .flex_row()
+                .justify_center()
+                .pt(px(72.))
+                .on_mouse_down(MouseButton::Left, {
+                    let app = app.clone();
+                    move |_, window, cx| {
+                        window.prevent_default();
+                        app.update(cx, |this, cx| {
+                            this.close_file_history_overlay(window, cx);
+                        });
+                    }
+                })
+                .child(overlay_container)
+                .into_any_element(),
+        )
     }
 
-    fn render_file_history_overlay(
+    fn render_commit_failure_overlay(
         &mut self,
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) -> Option<AnyElement> {
-        let overlay = self.file_history_overlay.as_mut()?;
+        let overlay = self.commit_failure_overlay.as_ref()?;
         let theme = cx.theme();
         let app = cx.entity();
 
-        let loading = overlay.loading;
-        let mode = overlay.mode;
-        let filter_input = overlay.filter_input.clone();
-        let title = overlay.path.clone();
-
-        let query = overlay.filter_input.read(cx).value().to_string();
-        let filtered = filter_commits(&overlay.commits, &query);
-
-        let selected = overlay.selected.min(filtered.len().saturating_sub(1));
-
-        let can_apply = !loading && !filtered.is_empty();
+        let failure = &overlay.failure;
+        let can_retry = !overlay.no_verify && !failure.hooks.is_empty() && !self.committing;
+        let headline: SharedString = if failure.hooks.is_empty() {
+            format!("git commit 失败（退出码 {}）", failure.exit_code).into()
+        } else {
+            format!(
+                "提交被 hook 拒绝（{}，退出码 {}）",
+                failure.hooks.join(" / "),
+                failure.exit_code
+            )
+            .into()
+        };
+        let output_text = failure.output.clone();
 
-        let list: Vec<AnyElement> = if loading {
+        let total_lines = failure.output.lines().count();
+        let mut lines: Vec<AnyElement> = if failure.output.trim().is_empty() {
             vec![
                 div()
-                    .px(px(12.))
-                    .py(px(10.))
-                    .text_sm()
-                    .text_color(theme.muted_foreground)
-                    .child("加载历史中…")
-                    .into_any_element(),
-            ]
-        } else if filtered.is_empty() {
-            vec![
-                div()
-                    .px(px(12.))
-                    .py(px(10.))
-                    .text_sm()
                     .text_color(theme.muted_foreground)
-                    .child("没有匹配的 commit")
+                    .child("（没有输出）")
                     .into_any_element(),
             ]
         } else {
-            filtered
-                .iter()
-                .take(120)
-                .enumerate()
-                .map(|(index, entry)| {
-                    let is_selected = index == selected;
-                    let label = format!("{}  {}", entry.short_hash, entry.subject);
-                    let app = app.clone();
+            failure
+                .output
+                .lines()
+                .take(COMMIT_OUTPUT_MAX_LINES)
+                .map(|line| {
+                    let text = preserve_spaces(line);
                     div()
-                        .id(("history-commit", index))
-                        .flex()
-                        .flex_row()
-                        .items_center()
-                        .h(px(32.))
-                        .px(px(10.))
-                        .rounded(px(6.))
-                        .text_sm()
-                        .when(is_selected, |this| {
-                            this.bg(theme.accent)
-                                .text_color(theme.accent_foreground)
-                                .cursor_default()
-                        })
-                        .when(!is_selected, |this| {
-                            this.bg(theme.transparent)
-                                .text_color(theme.popover_foreground)
-                                .cursor_pointer()
-                                .hover(|this| {
-                                    this.bg(theme.accent.alpha(0.4))
-                                        .text_color(theme.accent_foreground)
-                                })
-                                .active(|this| {
-                                    this.bg(theme.accent).text_color(theme.accent_foreground)
-                                })
-                                .on_mouse_down(MouseButton::Left, move |_, window, cx| {
-                                    window.prevent_default();
-                                    app.update(cx, |this, cx| {
-                                        if let Some(overlay) = this.file_history_overlay.as_mut() {
-                                            overlay.selected = index;
-                                        }
-                                        cx.notify();
-                                    });
-                                })
+                        .child(if text.is_empty() {
+                            "\u{00A0}".to_string()
+                        } else {
+                            text
                         })
-                        .child(div().truncate().child(label))
                         .into_any_element()
                 })
                 .collect()
         };
-
-        let mode_parent_active = mode == HistoryCompareMode::ParentToCommit;
-        let mode_worktree_active = mode == HistoryCompareMode::CommitToWorktree;
+        if total_lines > COMMIT_OUTPUT_MAX_LINES {
+            lines.push(
+                div()
+                    .text_color(theme.muted_foreground)
+                    .child(format!(
+                        "… 省略 {} 行（可复制完整输出）",
+                        total_lines - COMMIT_OUTPUT_MAX_LINES
+                    ))
+                    .into_any_element(),
+            );
+        }
 
         let overlay_container = div()
-            .id("file-history-overlay")
-            .w(px(720.))
+            .id("commit-failure-overlay")
+            .w(px(760.))
             .max_w(relative(0.92))
             .bg(theme.popover)
             .border_1()
@@ -4380,17 +11865,289 @@ impl GitViewerApp {
                 window.prevent_default();
                 cx.stop_propagation();
             })
-            .on_key_down({
-                let app = app.clone();
-                move |event, window, cx| {
-                    let handled = app.update(cx, |this, cx| {
-                        this.handle_file_history_overlay_key(event, window, cx)
-                    });
-                    if handled {
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .gap(px(12.))
+                    .child(
+                        div().flex().flex_col().gap(px(2.)).child("提交失败").child(
+                            div()
+                                .text_xs()
+                                .text_color(theme.muted_foreground)
+                                .truncate()
+                                .child(headline),
+                        ),
+                    )
+                    .child(
+                        Button::new("commit-failure-close")
+                            .label("关闭 (Esc)")
+                            .ghost()
+                            .on_click({
+                                let app = app.clone();
+                                move |_, window, cx| {
+                                    app.update(cx, |this, cx| {
+                                        this.close_commit_failure_overlay(window, cx);
+                                    });
+                                }
+                            }),
+                    ),
+            )
+            .child(
+                div()
+                    .id("commit-failure-output")
+                    .flex()
+                    .flex_col()
+                    .min_h(px(0.))
+                    .max_h(px(420.))
+                    .overflow_y_scroll()
+                    .border_1()
+                    .border_color(theme.border.alpha(0.5))
+                    .rounded(theme.radius)
+                    .bg(theme.muted.alpha(0.12))
+                    .p(px(8.))
+                    .font_family(theme.mono_font_family.clone())
+                    .text_xs()
+                    .text_color(theme.popover_foreground)
+                    .children(lines),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .gap(px(12.))
+                    .child(div().text_xs().text_color(theme.muted_foreground).child(
+                        if failure.hooks.is_empty() {
+                            "提交信息已保留，修复后可重新提交"
+                        } else {
+                            "--no-verify 会跳过 pre-commit / commit-msg hook"
+                        },
+                    ))
+                    .child(
+                        div()
+                            .flex()
+                            .flex_row()
+                            .items_center()
+                            .gap(px(6.))
+                            .child(
+                                Button::new("commit-failure-copy")
+                                    .label("复制输出")
+                                    .ghost()
+                                    .on_click(move |_, window, cx| {
+                                        cx.write_to_clipboard(ClipboardItem::new_string(
+                                            output_text.clone(),
+                                        ));
+                                        window.push_toast(Toast::success("已复制 hook 输出"), cx);
+                                    }),
+                            )
+                            .when(!overlay.no_verify && !failure.hooks.is_empty(), |this| {
+                                this.child(
+                                    Button::new("commit-failure-no-verify")
+                                        .label("使用 --no-verify 重试")
+                                        .primary()
+                                        .disabled(!can_retry)
+                                        .on_click({
+                                            let app = app.clone();
+                                            move |_, window, cx| {
+                                                app.update(cx, |this, cx| {
+                                                    this.commit_staged_changes(true, window, cx);
+                                                });
+                                            }
+                                        }),
+                                )
+                            }),
+                    ),
+            );
+
+        Some(
+            div()
+                .id("commit-failure-overlay-backdrop")
+                .absolute()
+                .top(px(0.))
+                .bottom(px(0.))
+                .left(px(0.))
+                .right(px(0.))
+                .bg(theme.background.alpha(0.75))
+                .flex()
+                .flex_row()
+                .justify_center()
+                .pt(px(72.))
+                .on_mouse_down(MouseButton::Left, {
+                    let app = app.clone();
+                    move |_, window, cx| {
                         window.prevent_default();
-                        cx.stop_propagation();
+                        app.update(cx, |this, cx| {
+                            this.close_commit_failure_overlay(window, cx);
+                        });
+                    }
+                })
+                .child(overlay_container)
+                .into_any_element(),
+        )
+    }
+
+    fn render_patch_apply_overlay(
+        &mut self,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<AnyElement> {
+        let overlay = self
+            .patch_apply_overlay
+            .as_ref()
+            .filter(|overlay| !overlay.hidden)?;
+        let theme = cx.theme();
+        let app = cx.entity();
+
+        let total_hunks: usize = overlay
+            .files
+            .iter()
+            .map(|file| file.hunk_headers.len())
+            .sum();
+        let headline: SharedString = format!(
+            "来自{}：{} 个文件，{} 个 hunk（以工作区内容为基准预览）",
+            overlay.source,
+            overlay.files.len(),
+            total_hunks
+        )
+        .into();
+
+        let outcome = overlay.outcome.as_ref();
+        let files = overlay
+            .files
+            .iter()
+            .enumerate()
+            .map(|(index, file)| {
+                let rejected = outcome
+                    .and_then(|outcome| {
+                        outcome.rejected.iter().find(|(path, _)| *path == file.path)
+                    })
+                    .map(|(_, hunks)| hunks.as_slice())
+                    .unwrap_or_default();
+
+                let hunks = file
+                    .hunk_headers
+                    .iter()
+                    .enumerate()
+                    .map(|(hunk_index, header)| {
+                        let badge = if rejected.contains(&(hunk_index + 1)) {
+                            Some(("已拒绝", theme.red))
+                        } else if file.unmatched_hunks.contains(&hunk_index) {
+                            Some(("与工作区不匹配", theme.yellow))
+                        } else {
+                            None
+                        };
+                        div()
+                            .flex()
+                            .flex_row()
+                            .items_center()
+                            .gap(px(8.))
+                            .pl(px(16.))
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .min_w(px(0.))
+                                    .truncate()
+                                    .font_family(theme.mono_font_family.clone())
+                                    .text_xs()
+                                    .text_color(theme.muted_foreground)
+                                    .child(header.clone()),
+                            )
+                            .when_some(badge, |this, (label, color)| {
+                                this.child(
+                                    div()
+                                        .flex_none()
+                                        .px(px(6.))
+                                        .rounded(px(4.))
+                                        .border_1()
+                                        .border_color(color.alpha(0.6))
+                                        .bg(color.alpha(0.12))
+                                        .text_xs()
+                                        .text_color(color)
+                                        .child(label),
+                                )
+                            })
+                    });
+
+                let label = if file.binary {
+                    format!("{}（二进制，无法预览）", file.path)
+                } else {
+                    format!("{}  +{} -{}", file.path, file.added, file.removed)
+                };
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.))
+                    .child(
+                        Button::new(("patch-apply-file", index))
+                            .label(label)
+                            .ghost()
+                            .w_full()
+                            .disabled(file.binary)
+                            .on_click({
+                                let app = app.clone();
+                                move |_, _window, cx| {
+                                    app.update(cx, |this, cx| {
+                                        this.preview_patch_file(index);
+                                        cx.notify();
+                                    });
+                                }
+                            }),
+                    )
+                    .children(hunks)
+            })
+            .collect::<Vec<_>>();
+
+        let status: Option<SharedString> = match outcome {
+            _ if overlay.applying => Some("正在执行 git apply…".into()),
+            Some(outcome) if outcome.success => {
+                Some(format!("已应用到{}", outcome.target.label()).into())
+            }
+            Some(outcome) if !outcome.rejected.is_empty() => Some(
+                match outcome.target {
+                    PatchApplyTarget::Worktree => {
+                        "部分 hunk 被拒绝，已在工作区写入对应的 .rej 文件".to_string()
                     }
+                    PatchApplyTarget::Index => "部分 hunk 被拒绝".to_string(),
                 }
+                .into(),
+            ),
+            Some(outcome) => Some(outcome.output.clone().into()),
+            None => None,
+        };
+        let can_apply = self.git_available && !overlay.applying;
+
+        let apply_button = |id: &'static str, target: PatchApplyTarget| {
+            let app = app.clone();
+            Button::new(id)
+                .label(format!("应用到{}", target.label()))
+                .disabled(!can_apply)
+                .on_click(move |_, window, cx| {
+                    app.update(cx, |this, cx| {
+                        this.apply_patch(target, window, cx);
+                    });
+                })
+        };
+
+        let overlay_container = div()
+            .id("patch-apply-overlay")
+            .w(px(760.))
+            .max_w(relative(0.92))
+            .bg(theme.popover)
+            .border_1()
+            .border_color(theme.border)
+            .rounded(theme.radius)
+            .shadow_lg()
+            .flex()
+            .flex_col()
+            .gap(px(10.))
+            .p(px(12.))
+            .on_mouse_down(MouseButton::Left, |_, window, cx| {
+                window.prevent_default();
+                cx.stop_propagation();
             })
             .child(
                 div()
@@ -4400,23 +12157,28 @@ impl GitViewerApp {
                     .justify_between()
                     .gap(px(12.))
                     .child(
-                        div().flex().flex_col().gap(px(2.)).child("历史对比").child(
-                            div()
-                                .text_xs()
-                                .text_color(theme.muted_foreground)
-                                .truncate()
-                                .child(title),
-                        ),
+                        div()
+                            .flex()
+                            .flex_col()
+                            .gap(px(2.))
+                            .child("应用 Patch")
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(theme.muted_foreground)
+                                    .truncate()
+                                    .child(headline),
+                            ),
                     )
                     .child(
-                        Button::new("history-overlay-close")
+                        Button::new("patch-apply-close")
                             .label("关闭 (Esc)")
                             .ghost()
                             .on_click({
                                 let app = app.clone();
                                 move |_, window, cx| {
                                     app.update(cx, |this, cx| {
-                                        this.close_file_history_overlay(window, cx);
+                                        this.close_patch_apply_overlay(window, cx);
                                     });
                                 }
                             }),
@@ -4424,74 +12186,14 @@ impl GitViewerApp {
             )
             .child(
                 div()
-                    .flex()
-                    .flex_row()
-                    .items_center()
-                    .justify_between()
-                    .gap(px(12.))
-                    .child(Input::new(&filter_input).w_full())
-                    .child(
-                        div()
-                            .flex()
-                            .flex_row()
-                            .items_center()
-                            .gap(px(6.))
-                            .child(
-                                Button::new("history-mode-parent")
-                                    .label("变更（parent→commit）")
-                                    .when(mode_parent_active, |this| this.primary())
-                                    .when(!mode_parent_active, |this| this.ghost())
-                                    .on_click({
-                                        let app = app.clone();
-                                        move |_, _window, cx| {
-                                            app.update(cx, |this, cx| {
-                                                if let Some(overlay) =
-                                                    this.file_history_overlay.as_mut()
-                                                {
-                                                    overlay.mode =
-                                                        HistoryCompareMode::ParentToCommit;
-                                                }
-                                                cx.notify();
-                                            });
-                                        }
-                                    }),
-                            )
-                            .child(
-                                Button::new("history-mode-worktree")
-                                    .label("对比工作区")
-                                    .when(mode_worktree_active, |this| this.primary())
-                                    .when(!mode_worktree_active, |this| this.ghost())
-                                    .on_click({
-                                        let app = app.clone();
-                                        move |_, _window, cx| {
-                                            app.update(cx, |this, cx| {
-                                                if let Some(overlay) =
-                                                    this.file_history_overlay.as_mut()
-                                                {
-                                                    overlay.mode =
-                                                        HistoryCompareMode::CommitToWorktree;
-                                                }
-                                                cx.notify();
-                                            });
-                                        }
-                                    }),
-                            ),
-                    ),
-            )
-            .child(
-                div()
-                    .id("file-history-overlay-list")
+                    .id("patch-apply-files")
                     .flex()
                     .flex_col()
-                    .gap(px(2.))
+                    .gap(px(6.))
                     .min_h(px(0.))
-                    .max_h(px(360.))
+                    .max_h(px(420.))
                     .overflow_y_scroll()
-                    .border_1()
-                    .border_color(theme.border.alpha(0.5))
-                    .rounded(theme.radius)
-                    .p(px(6.))
-                    .children(list),
+                    .children(files),
             )
             .child(
                 div()
@@ -4502,29 +12204,32 @@ impl GitViewerApp {
                     .gap(px(12.))
                     .child(
                         div()
+                            .flex_1()
+                            .min_w(px(0.))
                             .text_xs()
                             .text_color(theme.muted_foreground)
-                            .child("↑↓ 选择 · Enter 打开 · Esc 关闭"),
+                            .child(status.unwrap_or_else(|| "点击文件可在 diff 视图中预览".into())),
                     )
                     .child(
-                        Button::new("history-overlay-apply")
-                            .label("打开对比")
-                            .primary()
-                            .disabled(!can_apply)
-                            .on_click({
-                                let app = app.clone();
-                                move |_, window, cx| {
-                                    app.update(cx, |this, cx| {
-                                        this.apply_selected_file_history_commit(window, cx);
-                                    });
-                                }
-                            }),
+                        div()
+                            .flex()
+                            .flex_row()
+                            .items_center()
+                            .gap(px(6.))
+                            .child(
+                                apply_button("patch-apply-worktree", PatchApplyTarget::Worktree)
+                                    .ghost(),
+                            )
+                            .child(
+                                apply_button("patch-apply-index", PatchApplyTarget::Index)
+                                    .primary(),
+                            ),
                     ),
             );
 
         Some(
             div()
-                .id("file-history-overlay-backdrop")
+                .id("patch-apply-overlay-backdrop")
                 .absolute()
                 .top(px(0.))
                 .bottom(px(0.))
@@ -4540,7 +12245,7 @@ impl GitViewerApp {
                     move |_, window, cx| {
                         window.prevent_default();
                         app.update(cx, |this, cx| {
-                            this.close_file_history_overlay(window, cx);
+                            this.close_patch_apply_overlay(window, cx);
                         });
                     }
                 })
@@ -4549,93 +12254,25 @@ impl GitViewerApp {
         )
     }
 
-    fn render_command_palette_overlay(
+    fn render_askpass_overlay(
         &mut self,
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) -> Option<AnyElement> {
-        let overlay = self.command_palette_overlay.as_ref()?;
+        let overlay = self.askpass_overlay.as_ref()?;
         let theme = cx.theme();
         let app = cx.entity();
 
-        let query = overlay.input.read(cx).value().to_string();
-        let filtered = filter_command_palette_items(&query);
-        let selected = overlay.selected.min(filtered.len().saturating_sub(1));
-
-        let mut list: Vec<AnyElement> = Vec::new();
-        if filtered.is_empty() {
-            list.push(
-                div()
-                    .px(px(12.))
-                    .py(px(10.))
-                    .text_sm()
-                    .text_color(theme.muted_foreground)
-                    .child("没有匹配命令")
-                    .into_any_element(),
-            );
+        let prompt: SharedString = if overlay.request.prompt.trim().is_empty() {
+            "git 请求输入凭据".into()
         } else {
-            for (index, item) in filtered.iter().take(80).enumerate() {
-                let is_selected = index == selected;
-                let enabled = self.command_palette_command_enabled(item.command);
-                let app_for_click = app.clone();
-                let command = item.command;
-
-                let mut row = div()
-                    .id(("command-palette-item", index))
-                    .flex()
-                    .flex_row()
-                    .items_center()
-                    .justify_between()
-                    .h(px(32.))
-                    .px(px(10.))
-                    .rounded(px(6.))
-                    .text_sm()
-                    .child(div().truncate().child(item.title));
-
-                if enabled {
-                    row = row
-                        .when(is_selected, |this| {
-                            this.bg(theme.accent)
-                                .text_color(theme.accent_foreground)
-                                .cursor_default()
-                        })
-                        .when(!is_selected, |this| {
-                            this.bg(theme.transparent)
-                                .text_color(theme.popover_foreground)
-                                .cursor_pointer()
-                                .hover(|this| {
-                                    this.bg(theme.accent.alpha(0.4))
-                                        .text_color(theme.accent_foreground)
-                                })
-                                .active(|this| {
-                                    this.bg(theme.accent).text_color(theme.accent_foreground)
-                                })
-                                .on_mouse_down(MouseButton::Left, move |_, window, cx| {
-                                    window.prevent_default();
-                                    app_for_click.update(cx, |this, cx| {
-                                        if let Some(overlay) = this.command_palette_overlay.as_mut()
-                                        {
-                                            overlay.selected = index;
-                                        }
-                                        this.run_command_palette_command(command, window, cx);
-                                        this.close_command_palette(window, cx);
-                                    });
-                                })
-                        });
-                } else {
-                    row = row
-                        .bg(theme.transparent)
-                        .text_color(theme.muted_foreground)
-                        .cursor_default();
-                }
-
-                list.push(row.into_any_element());
-            }
-        }
+            overlay.request.prompt.clone().into()
+        };
+        let pending = self.askpass_queue.len();
 
         let overlay_container = div()
-            .id("command-palette-overlay")
-            .w(px(640.))
+            .id("askpass-overlay")
+            .w(px(520.))
             .max_w(relative(0.92))
             .bg(theme.popover)
             .border_1()
@@ -4653,9 +12290,8 @@ impl GitViewerApp {
             .on_key_down({
                 let app = app.clone();
                 move |event, window, cx| {
-                    let handled = app.update(cx, |this, cx| {
-                        this.handle_command_palette_key(event, window, cx)
-                    });
+                    let handled =
+                        app.update(cx, |this, cx| this.handle_askpass_key(event, window, cx));
                     if handled {
                         window.prevent_default();
                         cx.stop_propagation();
@@ -4665,51 +12301,70 @@ impl GitViewerApp {
             .child(
                 div()
                     .flex()
-                    .flex_row()
-                    .items_center()
-                    .justify_between()
-                    .gap(px(12.))
-                    .child(div().flex().flex_col().gap(px(2.)).child("命令"))
+                    .flex_col()
+                    .gap(px(2.))
+                    .child("Git 需要输入凭据")
                     .child(
-                        Button::new("command-palette-close")
-                            .label("关闭 (Esc)")
-                            .ghost()
-                            .on_click({
-                                let app = app.clone();
-                                move |_, window, cx| {
-                                    app.update(cx, |this, cx| {
-                                        this.close_command_palette(window, cx);
-                                    });
-                                }
-                            }),
+                        div()
+                            .text_xs()
+                            .text_color(theme.muted_foreground)
+                            .child(prompt),
                     ),
             )
             .child(Input::new(&overlay.input).w_full())
             .child(
                 div()
-                    .id("command-palette-list")
                     .flex()
-                    .flex_col()
-                    .gap(px(2.))
-                    .min_h(px(0.))
-                    .max_h(px(360.))
-                    .overflow_y_scroll()
-                    .border_1()
-                    .border_color(theme.border.alpha(0.5))
-                    .rounded(theme.radius)
-                    .p(px(6.))
-                    .children(list),
-            )
-            .child(
-                div()
-                    .text_xs()
-                    .text_color(theme.muted_foreground)
-                    .child("↑↓ 选择 · Enter 执行 · Esc 关闭"),
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .gap(px(12.))
+                    .child(div().text_xs().text_color(theme.muted_foreground).child(
+                        if pending > 0 {
+                            format!("Enter 确定，Esc 取消（还有 {pending} 个请求）")
+                        } else {
+                            "Enter 确定，Esc 取消".to_string()
+                        },
+                    ))
+                    .child(
+                        div()
+                            .flex()
+                            .flex_row()
+                            .items_center()
+                            .gap(px(6.))
+                            .child(
+                                Button::new("askpass-cancel")
+                                    .label("取消")
+                                    .ghost()
+                                    .on_click({
+                                        let app = app.clone();
+                                        move |_, window, cx| {
+                                            app.update(cx, |this, cx| {
+                                                this.finish_askpass_request(false, window, cx);
+                                            });
+                                        }
+                                    }),
+                            )
+                            .child(
+                                Button::new("askpass-submit")
+                                    .label("确定")
+                                    .primary()
+                                    .on_click({
+                                        let app = app.clone();
+                                        move |_, window, cx| {
+                                            app.update(cx, |this, cx| {
+                                                this.finish_askpass_request(true, window, cx);
+                                            });
+                                        }
+                                    }),
+                            ),
+                    ),
             );
 
+        // 凭据请求会阻塞 git，背景点击不关闭，必须显式确定或取消。
         Some(
             div()
-                .id("command-palette-overlay-backdrop")
+                .id("askpass-overlay-backdrop")
                 .absolute()
                 .top(px(0.))
                 .bottom(px(0.))
@@ -4720,14 +12375,9 @@ impl GitViewerApp {
                 .flex_row()
                 .justify_center()
                 .pt(px(72.))
-                .on_mouse_down(MouseButton::Left, {
-                    let app = app.clone();
-                    move |_, window, cx| {
-                        window.prevent_default();
-                        app.update(cx, |this, cx| {
-                            this.close_command_palette(window, cx);
-                        });
-                    }
+                .on_mouse_down(MouseButton::Left, |_, window, cx| {
+                    window.prevent_default();
+                    cx.stop_propagation();
                 })
                 .child(overlay_container)
                 .into_any_element(),
@@ -4879,17 +12529,25 @@ impl GitViewerApp {
                     .border_b_1()
                     .border_color(border.alpha(0.35))
                     .child(
-                        render_side(Side::Old, kind, None, &ours_segments, mono.clone(), theme)
-                            .on_mouse_down(
-                                MouseButton::Left,
-                                cx.listener(move |this, _, _window, cx| {
-                                    if let Some(view) = this.conflict_view.as_ref() {
-                                        view.scroll_handle
-                                            .scroll_to_item(index, ScrollStrategy::Top);
-                                    }
-                                    cx.notify();
-                                }),
-                            ),
+                        render_side(
+                            Side::Old,
+                            kind,
+                            None,
+                            &ours_segments,
+                            &[],
+                            mono.clone(),
+                            theme,
+                        )
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |this, _, _window, cx| {
+                                if let Some(view) = this.conflict_view.as_ref() {
+                                    view.scroll_handle
+                                        .scroll_to_item(index, ScrollStrategy::Top);
+                                }
+                                cx.notify();
+                            }),
+                        ),
                     )
                     .child(div().w(px(1.)).h_full().bg(border.alpha(0.6)));
 
@@ -4901,6 +12559,7 @@ impl GitViewerApp {
                                 diffview::DiffRowKind::Unchanged,
                                 None,
                                 &base_segments,
+                                &[],
                                 mono.clone(),
                                 theme,
                             )
@@ -4920,7 +12579,7 @@ impl GitViewerApp {
                 }
 
                 row.child(
-                    render_side(Side::New, kind, None, &theirs_segments, mono, theme)
+                    render_side(Side::New, kind, None, &theirs_segments, &[], mono, theme)
                         .on_mouse_down(
                             MouseButton::Left,
                             cx.listener(move |this, _, _window, cx| {
@@ -5116,7 +12775,7 @@ impl GitViewerApp {
                     .border_b_1()
                     .border_color(border.alpha(0.35))
                     .child({
-                        let mut cell = render_side(side, kind, None, &segments, mono, theme);
+                        let mut cell = render_side(side, kind, None, &segments, &[], mono, theme);
                         if let Some(bg) = base_bg {
                             cell = cell.bg(bg);
                         }
@@ -5135,6 +12794,59 @@ impl GitViewerApp {
         }
     }
 
+    /// hunk 头：左侧 gutter 放 Stage/Unstage/Revert 小按钮，鼠标悬停在该行时才显示。
+    fn render_hunk_header(
+        &self,
+        hunk: usize,
+        text: SharedString,
+        height: Pixels,
+        cx: &mut Context<Self>,
+    ) -> Div {
+        let theme = cx.theme();
+        let group: SharedString = format!("hunk-header-{hunk}").into();
+        let actions = self.hunk_header_actions();
+
+        let gutter = actions.iter().fold(
+            div()
+                .flex()
+                .items_center()
+                .gap(px(2.))
+                .invisible()
+                .group_hover(group.clone(), |style| style.visible()),
+            |gutter, &action| {
+                let (id, icon, tooltip) = match action {
+                    HunkApplyAction::Stage => ("hunk-stage", "+", "Stage 此 hunk"),
+                    HunkApplyAction::Unstage => ("hunk-unstage", "−", "Unstage 此 hunk"),
+                    HunkApplyAction::Revert => ("hunk-revert", "↶", "Revert 此 hunk"),
+                };
+                gutter.child(
+                    Button::new((id, hunk))
+                        .label(icon)
+                        .ghost()
+                        .xsmall()
+                        .tooltip(tooltip)
+                        .on_click(cx.listener(move |this, _, window, cx| {
+                            this.apply_hunk_at(hunk, action, window, cx);
+                            cx.notify();
+                        })),
+                )
+            },
+        );
+
+        div()
+            .group(group)
+            .h(height)
+            .px(px(12.))
+            .flex()
+            .items_center()
+            .gap(px(8.))
+            .bg(theme.muted.alpha(0.35))
+            .font_family(theme.mono_font_family.clone())
+            .text_sm()
+            .when(!actions.is_empty(), |this| this.child(gutter))
+            .child(text)
+    }
+
     fn render_demo_row(
         &mut self,
         index: usize,
@@ -5146,26 +12858,24 @@ impl GitViewerApp {
         let Some(row) = self
             .diff_view
             .as_ref()
-            .and_then(|diff_view| diff_view.rows.get(index))
-            .cloned()
+            .and_then(|diff_view| diff_view.row(index))
         else {
             return div();
         };
+        let selected = self
+            .diff_view
+            .as_ref()
+            .is_some_and(|diff_view| diff_view.is_row_selected(index));
 
         match row {
-            DisplayRow::HunkHeader { text } => div()
-                .h(height)
-                .px(px(12.))
-                .flex()
-                .items_center()
-                .bg(theme.muted.alpha(0.35))
-                .font_family(theme.mono_font_family.clone())
-                .text_sm()
-                .child(text),
+            DisplayRow::HunkHeader { hunk, text } => {
+                self.render_hunk_header(hunk, text, height, cx)
+            }
             DisplayRow::Fold {
                 old_start,
                 new_start,
                 len,
+                function,
             } => {
                 let label = format!(
                     "… 隐藏了 {len} 行未变更内容（old: {}..{}, new: {}..{}）点击展开",
@@ -5179,10 +12889,14 @@ impl GitViewerApp {
                     .px(px(12.))
                     .flex()
                     .items_center()
+                    .gap(px(12.))
                     .bg(theme.muted.alpha(0.25))
                     .text_sm()
                     .text_color(theme.muted_foreground)
                     .child(label)
+                    .when_some(function, |this, function| {
+                        this.child(render_fold_function(function, theme))
+                    })
                     .on_mouse_down(
                         MouseButton::Left,
                         cx.listener(move |this, _, _window, cx| {
@@ -5205,6 +12919,8 @@ impl GitViewerApp {
             } => {
                 let border = theme.border;
                 let mono = theme.mono_font_family.clone();
+                let old_highlights = self.search_highlights(&old_segments);
+                let new_highlights = self.search_highlights(&new_segments);
 
                 div()
                     .h(height)
@@ -5213,42 +12929,45 @@ impl GitViewerApp {
                     .items_center()
                     .border_b_1()
                     .border_color(border.alpha(0.35))
+                    .relative()
                     .child(
                         render_side(
                             Side::Old,
                             kind,
                             old_line,
                             &old_segments,
+                            &old_highlights,
                             mono.clone(),
                             theme,
                         )
                         .on_mouse_down(
                             MouseButton::Left,
-                            cx.listener(move |this, _, _window, cx| {
-                                if let Some(diff_view) = this.diff_view.as_ref() {
-                                    diff_view
-                                        .scroll_handle
-                                        .scroll_to_item(index, ScrollStrategy::Top);
-                                }
+                            cx.listener(move |this, event: &MouseDownEvent, _window, cx| {
+                                this.select_diff_row(index, event.modifiers.shift);
                                 cx.notify();
                             }),
                         ),
                     )
                     .child(div().w(px(1.)).h_full().bg(border.alpha(0.6)))
                     .child(
-                        render_side(Side::New, kind, new_line, &new_segments, mono, theme)
-                            .on_mouse_down(
-                                MouseButton::Left,
-                                cx.listener(move |this, _, _window, cx| {
-                                    if let Some(diff_view) = this.diff_view.as_ref() {
-                                        diff_view
-                                            .scroll_handle
-                                            .scroll_to_item(index, ScrollStrategy::Top);
-                                    }
-                                    cx.notify();
-                                }),
-                            ),
+                        render_side(
+                            Side::New,
+                            kind,
+                            new_line,
+                            &new_segments,
+                            &new_highlights,
+                            mono,
+                            theme,
+                        )
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |this, event: &MouseDownEvent, _window, cx| {
+                                this.select_diff_row(index, event.modifiers.shift);
+                                cx.notify();
+                            }),
+                        ),
                     )
+                    .when(selected, |row| row.child(render_selection_highlight(theme)))
             }
         }
     }
@@ -5264,26 +12983,24 @@ impl GitViewerApp {
         let Some(row) = self
             .diff_view
             .as_ref()
-            .and_then(|diff_view| diff_view.rows.get(index))
-            .cloned()
+            .and_then(|diff_view| diff_view.row(index))
         else {
             return div();
         };
+        let selected = self
+            .diff_view
+            .as_ref()
+            .is_some_and(|diff_view| diff_view.is_row_selected(index));
 
         match row {
-            DisplayRow::HunkHeader { text } => div()
-                .h(height)
-                .px(px(12.))
-                .flex()
-                .items_center()
-                .bg(theme.muted.alpha(0.35))
-                .font_family(theme.mono_font_family.clone())
-                .text_sm()
-                .child(text),
+            DisplayRow::HunkHeader { hunk, text } => {
+                self.render_hunk_header(hunk, text, height, cx)
+            }
             DisplayRow::Fold {
                 old_start,
                 new_start,
                 len,
+                function,
             } => {
                 let label = format!(
                     "… 隐藏了 {len} 行未变更内容（old: {}..{}, new: {}..{}）点击展开",
@@ -5297,10 +13014,14 @@ impl GitViewerApp {
                     .px(px(12.))
                     .flex()
                     .items_center()
+                    .gap(px(12.))
                     .bg(theme.muted.alpha(0.25))
                     .text_sm()
                     .text_color(theme.muted_foreground)
                     .child(label)
+                    .when_some(function, |this, function| {
+                        this.child(render_fold_function(function, theme))
+                    })
                     .on_mouse_down(
                         MouseButton::Left,
                         cx.listener(move |this, _, _window, cx| {
@@ -5362,6 +13083,7 @@ impl GitViewerApp {
                     }
                 };
 
+                let highlights = self.search_highlights(&segments);
                 let gutter_width = px(94.);
                 let num_width = px(32.);
 
@@ -5372,6 +13094,7 @@ impl GitViewerApp {
                     .items_center()
                     .border_b_1()
                     .border_color(border.alpha(0.35))
+                    .relative()
                     .bg(bg)
                     .child(div().w(px(3.)).h_full().bg(marker_color))
                     .child(
@@ -5404,19 +13127,16 @@ impl GitViewerApp {
                             .whitespace_nowrap()
                             .font_family(mono)
                             .text_sm()
-                            .children(render_segments(&segments, theme)),
+                            .children(render_segments(&segments, &highlights, theme)),
                     )
                     .on_mouse_down(
                         MouseButton::Left,
-                        cx.listener(move |this, _, _window, cx| {
-                            if let Some(diff_view) = this.diff_view.as_ref() {
-                                diff_view
-                                    .scroll_handle
-                                    .scroll_to_item(index, ScrollStrategy::Top);
-                            }
+                        cx.listener(move |this, event: &MouseDownEvent, _window, cx| {
+                            this.select_diff_row(index, event.modifiers.shift);
                             cx.notify();
                         }),
                     )
+                    .when(selected, |row| row.child(render_selection_highlight(theme)))
             }
         }
     }
@@ -5433,31 +13153,32 @@ impl GitViewerApp {
         let Some(row) = self
             .diff_view
             .as_ref()
-            .and_then(|diff_view| diff_view.rows.get(index))
-            .cloned()
+            .and_then(|diff_view| diff_view.row(index))
         else {
             return div();
         };
+        let selected = self
+            .diff_view
+            .as_ref()
+            .is_some_and(|diff_view| diff_view.is_row_selected(index));
 
         match row {
-            DisplayRow::HunkHeader { text } => div()
-                .h(height)
-                .px(px(12.))
-                .flex()
-                .items_center()
-                .bg(theme.muted.alpha(0.35))
-                .font_family(theme.mono_font_family.clone())
-                .text_sm()
-                .child(text),
-            DisplayRow::Fold { len, .. } => div()
+            DisplayRow::HunkHeader { hunk, text } => {
+                self.render_hunk_header(hunk, text, height, cx)
+            }
+            DisplayRow::Fold { len, function, .. } => div()
                 .h(height)
                 .px(px(12.))
                 .flex()
                 .items_center()
+                .gap(px(12.))
                 .bg(theme.muted.alpha(0.25))
                 .text_sm()
                 .text_color(theme.muted_foreground)
                 .child(format!("… 隐藏了 {len} 行未变更内容，点击展开"))
+                .when_some(function, |this, function| {
+                    this.child(render_fold_function(function, theme))
+                })
                 .on_mouse_down(
                     MouseButton::Left,
                     cx.listener(move |this, _, _window, cx| {
@@ -5483,6 +13204,7 @@ impl GitViewerApp {
                     Side::Old => (old_line, old_segments),
                     Side::New => (new_line, new_segments),
                 };
+                let highlights = self.search_highlights(&segments);
 
                 div()
                     .h(height)
@@ -5490,34 +13212,78 @@ impl GitViewerApp {
                     .items_center()
                     .border_b_1()
                     .border_color(border.alpha(0.35))
+                    .relative()
                     .child(
-                        render_side(side, kind, line_no, &segments, mono, theme).on_mouse_down(
-                            MouseButton::Left,
-                            cx.listener(move |this, _, _window, cx| {
-                                if let Some(diff_view) = this.diff_view.as_ref() {
-                                    diff_view
-                                        .scroll_handle
-                                        .scroll_to_item(index, ScrollStrategy::Top);
-                                }
-                                cx.notify();
-                            }),
-                        ),
+                        render_side(side, kind, line_no, &segments, &highlights, mono, theme)
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |this, event: &MouseDownEvent, _window, cx| {
+                                    this.select_diff_row(index, event.modifiers.shift);
+                                    cx.notify();
+                                }),
+                            ),
                     )
+                    .when(selected, |row| row.child(render_selection_highlight(theme)))
             }
         }
     }
 }
 
+impl CommandPaletteProvider for GitViewerApp {
+    type Action = CommandPaletteCommand;
+
+    fn command_palette(&self) -> Option<&CommandPalette<CommandPaletteCommand>> {
+        self.command_palette_overlay.as_ref()
+    }
+
+    fn command_palette_mut(&mut self) -> Option<&mut CommandPalette<CommandPaletteCommand>> {
+        self.command_palette_overlay.as_mut()
+    }
+
+    fn command_enabled(&self, command: CommandPaletteCommand) -> bool {
+        self.command_palette_command_enabled(command)
+    }
+
+    fn run_command(
+        &mut self,
+        command: CommandPaletteCommand,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.run_command_palette_command(command, window, cx);
+    }
+
+    fn close_command_palette(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.command_palette_overlay.take().is_some() {
+            window.focus(&self.focus_handle);
+            cx.notify();
+        }
+    }
+}
+
 impl Render for GitViewerApp {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let content = match self.screen {
             AppScreen::StatusList => self.render_status_list(window, cx).into_any_element(),
             AppScreen::DiffView => self.render_diff_view(window, cx).into_any_element(),
             AppScreen::ConflictView => self.render_conflict_view(window, cx).into_any_element(),
+            AppScreen::Reflog => self.render_reflog_view(window, cx).into_any_element(),
+            AppScreen::BranchCompare => self
+                .render_branch_compare_view(window, cx)
+                .into_any_element(),
+            AppScreen::Bisect => self.render_bisect_view(window, cx).into_any_element(),
+            AppScreen::CommitStack => self.render_commit_stack_view(window, cx).into_any_element(),
+            AppScreen::PullRequests => self
+                .render_pull_requests_view(window, cx)
+                .into_any_element(),
+            AppScreen::RepoStats => self.render_repo_stats_view(window, cx).into_any_element(),
         };
 
         let file_history_overlay = self.render_file_history_overlay(window, cx);
-        let command_palette_overlay = self.render_command_palette_overlay(window, cx);
+        let command_palette_overlay = self.render_command_palette(window, cx);
+        let commit_failure_overlay = self.render_commit_failure_overlay(window, cx);
+        let patch_apply_overlay = self.render_patch_apply_overlay(window, cx);
+        let askpass_overlay = self.render_askpass_overlay(window, cx);
 
         let mut root = div()
             .id("git-viewer-root")
@@ -5535,10 +13301,26 @@ impl Render for GitViewerApp {
                     }
                 }))
                 .on_action(cx.listener(|this, _: &Back, window, cx| {
+                    if this.askpass_overlay.is_some() {
+                        this.finish_askpass_request(false, window, cx);
+                        return;
+                    }
+                    if this.commit_failure_overlay.is_some() {
+                        this.close_commit_failure_overlay(window, cx);
+                        return;
+                    }
                     if this.command_palette_overlay.is_some() {
                         this.close_command_palette(window, cx);
                         return;
                     }
+                    if this
+                        .patch_apply_overlay
+                        .as_ref()
+                        .is_some_and(|overlay| !overlay.hidden)
+                    {
+                        this.close_patch_apply_overlay(window, cx);
+                        return;
+                    }
                     if this.file_history_overlay.is_some() {
                         this.close_file_history_overlay(window, cx);
                         return;
@@ -5546,6 +13328,12 @@ impl Render for GitViewerApp {
                     match this.screen {
                         AppScreen::DiffView => this.close_diff_view(),
                         AppScreen::ConflictView => this.close_conflict_view(),
+                        AppScreen::Reflog => this.close_reflog_view(),
+                        AppScreen::BranchCompare => this.close_branch_compare_view(),
+                        AppScreen::Bisect => this.close_bisect_view(),
+                        AppScreen::CommitStack => this.close_commit_stack_view(),
+                        AppScreen::PullRequests => this.close_pull_requests_view(),
+                        AppScreen::RepoStats => this.close_repo_stats_view(),
                         AppScreen::StatusList => {}
                     }
                     window.focus(&this.focus_handle);
@@ -5555,7 +13343,13 @@ impl Render for GitViewerApp {
                     match this.screen {
                         AppScreen::DiffView => this.jump_hunk(1),
                         AppScreen::ConflictView => this.jump_conflict(1),
-                        AppScreen::StatusList => {}
+                        AppScreen::Reflog => this.move_reflog_selection(1),
+                        AppScreen::StatusList
+                        | AppScreen::BranchCompare
+                        | AppScreen::Bisect
+                        | AppScreen::CommitStack
+                        | AppScreen::PullRequests
+                        | AppScreen::RepoStats => {}
                     }
                     cx.notify();
                 }))
@@ -5563,7 +13357,13 @@ impl Render for GitViewerApp {
                     match this.screen {
                         AppScreen::DiffView => this.jump_hunk(-1),
                         AppScreen::ConflictView => this.jump_conflict(-1),
-                        AppScreen::StatusList => {}
+                        AppScreen::Reflog => this.move_reflog_selection(-1),
+                        AppScreen::StatusList
+                        | AppScreen::BranchCompare
+                        | AppScreen::Bisect
+                        | AppScreen::CommitStack
+                        | AppScreen::PullRequests
+                        | AppScreen::RepoStats => {}
                     }
                     cx.notify();
                 }))
@@ -5585,7 +13385,15 @@ impl Render for GitViewerApp {
                             }
                         }
                         AppScreen::ConflictView => {}
-                        AppScreen::StatusList => return,
+                        AppScreen::StatusList
+                        | AppScreen::Reflog
+                        | AppScreen::BranchCompare
+                        | AppScreen::Bisect
+                        | AppScreen::CommitStack
+                        | AppScreen::PullRequests
+                        | AppScreen::RepoStats => {
+                            return;
+                        }
                     }
 
                     this.split_layout = match this.split_layout {
@@ -5637,7 +13445,19 @@ impl Render for GitViewerApp {
             root = root.child(overlay);
         }
 
-        root
+        if let Some(overlay) = commit_failure_overlay {
+            root = root.child(overlay);
+        }
+
+        if let Some(overlay) = patch_apply_overlay {
+            root = root.child(overlay);
+        }
+
+        if let Some(overlay) = askpass_overlay {
+            root = root.child(overlay);
+        }
+
+        root.child(self.notification_center.clone())
     }
 }
 
@@ -5654,11 +13474,32 @@ enum ConflictPane {
     Theirs,
 }
 
+fn render_selection_highlight(theme: &gpui_component::Theme) -> Div {
+    div()
+        .absolute()
+        .top(px(0.))
+        .bottom(px(0.))
+        .left(px(0.))
+        .right(px(0.))
+        .bg(theme.accent.alpha(0.15))
+}
+
+fn render_fold_function(function: SharedString, theme: &gpui_component::Theme) -> Div {
+    div()
+        .flex_1()
+        .min_w(px(0.))
+        .truncate()
+        .font_family(theme.mono_font_family.clone())
+        .text_color(theme.muted_foreground.alpha(0.8))
+        .child(function)
+}
+
 fn render_side(
     side: Side,
     kind: diffview::DiffRowKind,
     line_no: Option<usize>,
     segments: &[diffview::DiffSegment],
+    highlights: &[Range<usize>],
     font_family: SharedString,
     theme: &gpui_component::Theme,
 ) -> Div {
@@ -5697,47 +13538,336 @@ fn render_side(
                 .px(px(8.))
                 .flex()
                 .items_center()
-                .justify_end()
-                .bg(gutter_bg)
-                .text_xs()
-                .text_color(theme.muted_foreground)
-                .font_family(font_family.clone())
-                .child(line_no),
+                .justify_end()
+                .bg(gutter_bg)
+                .text_xs()
+                .text_color(theme.muted_foreground)
+                .font_family(font_family.clone())
+                .child(line_no),
+        )
+        .child(
+            div()
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap(px(0.))
+                .flex_1()
+                .min_w(px(0.))
+                .px(px(8.))
+                .overflow_hidden()
+                .whitespace_nowrap()
+                .font_family(font_family)
+                .text_sm()
+                .children(render_segments(segments, highlights, theme)),
+        )
+}
+
+fn render_stack_row(
+    app: &Entity<GitViewerApp>,
+    index: usize,
+    item: &DndListItem<StackCommit>,
+    row_state: DndListRowState,
+    cx: &mut App,
+) -> ListItem {
+    let theme = cx.theme();
+    let commit = &item.data;
+    let dropped = commit.action == StackAction::Drop;
+    let hash = commit.hash.clone();
+
+    let toggle = |id: &'static str, label: &'static str, action: StackAction| {
+        let app = app.clone();
+        let hash = hash.clone();
+        Button::new((id, index))
+            .label(label)
+            .ghost()
+            .when(commit.action == action, |this| this.primary())
+            .on_click(move |_, _, cx| {
+                app.update(cx, |this, cx| this.toggle_stack_action(&hash, action, cx));
+            })
+    };
+
+    let squash_target = row_state.drop_target == Some(DndListDropTarget::Inside);
+
+    ListItem::new(("commit-stack-row", index))
+        .when(row_state.dragging, |this| this.opacity(0.4))
+        .child(
+            div()
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap(px(10.))
+                .text_sm()
+                .child(
+                    div()
+                        .w(px(20.))
+                        .flex_none()
+                        .text_color(theme.muted_foreground)
+                        .child("≡"),
+                )
+                .child(
+                    div()
+                        .w(px(72.))
+                        .flex_none()
+                        .font_family(theme.mono_font_family.clone())
+                        .child(commit.short_hash.clone()),
+                )
+                .child(
+                    div()
+                        .flex_1()
+                        .min_w(px(0.))
+                        .truncate()
+                        .when(dropped, |this| {
+                            this.line_through().text_color(theme.muted_foreground)
+                        })
+                        .child(
+                            commit
+                                .edited_message
+                                .as_deref()
+                                .and_then(|message| message.lines().next())
+                                .unwrap_or(&commit.subject)
+                                .to_string(),
+                        ),
+                )
+                .when(commit.edited_message.is_some(), |this| {
+                    this.child(
+                        div()
+                            .flex_none()
+                            .text_xs()
+                            .text_color(theme.muted_foreground)
+                            .child("已改信息"),
+                    )
+                })
+                .when(squash_target, |this| {
+                    this.child(
+                        div()
+                            .flex_none()
+                            .px(px(6.))
+                            .rounded(px(4.))
+                            .text_xs()
+                            .bg(theme.primary)
+                            .text_color(theme.primary_foreground)
+                            .child("squash 进此提交"),
+                    )
+                })
+                .child(toggle(
+                    "commit-stack-squash",
+                    "Squash ↑",
+                    StackAction::Squash,
+                ))
+                .child(toggle("commit-stack-drop", "丢弃", StackAction::Drop)),
+        )
+}
+
+/// diff 标题里的路径面包屑。
+///
+/// 每级目录的下拉列出该目录下的变更文件，文件名一级的下拉列出同目录的变更文件，
+/// 点击即打开对应文件的 diff。
+fn render_path_breadcrumb(
+    files: &[FileEntry],
+    path: &str,
+    app: Entity<GitViewerApp>,
+) -> Breadcrumb {
+    let segments: Vec<&str> = path.split('/').collect();
+    let last = segments.len() - 1;
+
+    let items = segments.iter().enumerate().map(|(ix, segment)| {
+        let is_file = ix == last;
+        // 目录一级列出其下所有变更文件；文件一级只列出同目录下的文件。
+        let dir = segments[..if is_file { ix } else { ix + 1 }].join("/");
+        let prefix = if dir.is_empty() {
+            String::new()
+        } else {
+            format!("{dir}/")
+        };
+
+        let mut item = BreadcrumbItem::new(segment.to_string()).icon(if is_file {
+            IconName::File
+        } else {
+            IconName::Folder
+        });
+        let entries = files
+            .iter()
+            .filter_map(|entry| {
+                let relative = entry.path.strip_prefix(prefix.as_str())?;
+                (!is_file || !relative.contains('/')).then_some((entry, relative))
+            })
+            .take(BREADCRUMB_MENU_LIMIT);
+        for (entry, relative) in entries {
+            let app = app.clone();
+            let file_path = entry.path.clone();
+            let status = entry.status.clone();
+            item = item.menu_item_selected(
+                format!("{} {relative}", status.trim()),
+                entry.path == path,
+                move |window, cx| {
+                    app.update(cx, |this, cx| {
+                        this.open_file(file_path.clone(), status.clone(), window, cx);
+                        cx.notify();
+                    });
+                },
+            );
+        }
+        item
+    });
+
+    Breadcrumb::new("diff-path-breadcrumb")
+        .max_items(5)
+        .children(items)
+}
+
+/// 标题栏上提示文件编码（非 UTF-8 时才显示）。
+fn render_file_badge(label: String, theme: &gpui_component::Theme) -> Div {
+    div()
+        .flex_none()
+        .px(px(6.))
+        .rounded(px(4.))
+        .border_1()
+        .border_color(theme.border)
+        .text_xs()
+        .text_color(theme.muted_foreground)
+        .child(label)
+}
+
+fn eol_label(crlf: bool) -> &'static str {
+    if crlf { "CRLF" } else { "LF" }
+}
+
+fn render_signature_badge(status: SignatureStatus, theme: &gpui_component::Theme) -> Div {
+    let color = match status {
+        SignatureStatus::Verified => theme.green,
+        SignatureStatus::Untrusted | SignatureStatus::Expired | SignatureStatus::Unverifiable => {
+            theme.yellow
+        }
+        SignatureStatus::Revoked | SignatureStatus::Bad => theme.red,
+        SignatureStatus::Unsigned => theme.muted_foreground,
+    };
+
+    div()
+        .flex_none()
+        .px(px(6.))
+        .rounded(px(4.))
+        .border_1()
+        .border_color(color.alpha(0.6))
+        .bg(color.alpha(0.12))
+        .text_xs()
+        .text_color(color)
+        .child(status.label())
+}
+
+fn render_signature_details(
+    hash: &str,
+    subject: &str,
+    signature: &CommitSignature,
+    theme: &gpui_component::Theme,
+) -> Div {
+    let details = signature.details();
+
+    div()
+        .flex()
+        .flex_col()
+        .gap(px(4.))
+        .px(px(10.))
+        .py(px(8.))
+        .border_1()
+        .border_color(theme.border.alpha(0.5))
+        .rounded(theme.radius)
+        .bg(theme.muted.alpha(0.12))
+        .text_xs()
+        .child(
+            div()
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap(px(8.))
+                .child(
+                    div()
+                        .flex_none()
+                        .font_family(theme.mono_font_family.clone())
+                        .child(hash.to_string()),
+                )
+                .child(
+                    div()
+                        .flex_1()
+                        .min_w(px(0.))
+                        .truncate()
+                        .child(subject.to_string()),
+                ),
         )
         .child(
             div()
                 .flex()
                 .flex_row()
                 .items_center()
-                .gap(px(0.))
-                .flex_1()
-                .min_w(px(0.))
-                .px(px(8.))
-                .overflow_hidden()
-                .whitespace_nowrap()
-                .font_family(font_family)
-                .text_sm()
-                .children(render_segments(segments, theme)),
+                .gap(px(8.))
+                .child(render_signature_badge(signature.status, theme))
+                .child(
+                    div()
+                        .flex_1()
+                        .min_w(px(0.))
+                        .truncate()
+                        .text_color(theme.muted_foreground)
+                        .child(if details.is_empty() {
+                            if signature.is_signed() {
+                                "签名者未知".to_string()
+                            } else {
+                                "此 commit 没有签名".to_string()
+                            }
+                        } else {
+                            format!("签名者：{details}")
+                        }),
+                ),
         )
 }
 
-fn render_segments(segments: &[diffview::DiffSegment], theme: &gpui_component::Theme) -> Vec<Div> {
-    segments
-        .iter()
-        .map(|seg| {
-            let (bg, fg) = match seg.kind {
-                diffview::DiffSegmentKind::Unchanged => (theme.transparent, theme.foreground),
-                diffview::DiffSegmentKind::Added => (theme.green.alpha(0.28), theme.foreground),
-                diffview::DiffSegmentKind::Removed => (theme.red.alpha(0.28), theme.foreground),
-            };
+/// 渲染一行代码的各个片段；`highlights` 是整行文本中的查找命中区间（字节偏移，升序且不重叠），
+/// 命中部分会从所在片段中切出来单独加底色。
+fn render_segments(
+    segments: &[diffview::DiffSegment],
+    highlights: &[Range<usize>],
+    theme: &gpui_component::Theme,
+) -> Vec<Div> {
+    let piece = |text: &str, bg: Hsla, fg: Hsla| {
+        div()
+            .flex_none()
+            .bg(bg)
+            .text_color(fg)
+            .child(preserve_spaces(text))
+    };
 
-            div()
-                .flex_none()
-                .bg(bg)
-                .text_color(fg)
-                .child(preserve_spaces(&seg.text))
-        })
-        .collect()
+    let mut pieces = Vec::with_capacity(segments.len());
+    let mut offset = 0;
+    for seg in segments {
+        let (bg, fg) = match seg.kind {
+            diffview::DiffSegmentKind::Unchanged => (theme.transparent, theme.foreground),
+            diffview::DiffSegmentKind::Added => (theme.green.alpha(0.28), theme.foreground),
+            diffview::DiffSegmentKind::Removed => (theme.red.alpha(0.28), theme.foreground),
+        };
+        let end = offset + seg.text.len();
+
+        let mut cursor = offset;
+        for range in highlights
+            .iter()
+            .filter(|range| range.start < end && range.end > offset)
+        {
+            let start = range.start.max(offset);
+            let stop = range.end.min(end);
+            if cursor < start {
+                pieces.push(piece(&seg.text[cursor - offset..start - offset], bg, fg));
+            }
+            pieces.push(piece(
+                &seg.text[start - offset..stop - offset],
+                theme.yellow.alpha(0.45),
+                fg,
+            ));
+            cursor = stop;
+        }
+        if cursor < end {
+            pieces.push(piece(&seg.text[cursor - offset..], bg, fg));
+        }
+
+        offset = end;
+    }
+    pieces
 }
 
 fn preserve_spaces(text: &str) -> String {
@@ -5763,13 +13893,9 @@ impl DiffViewState {
         options: DiffViewOptions,
         view_mode: DiffViewMode,
     ) -> Self {
-        let (diff_model, old_lines, new_lines) = build_diff_model(
-            &old_text,
-            &new_text,
-            options.ignore_whitespace,
-            options.context_lines,
-        );
-        let rows = build_display_rows_from_model(&diff_model, &old_lines, &new_lines, view_mode);
+        let (diff_model, old_lines, new_lines, diff_timing) =
+            build_diff_model(&old_text, &new_text, options);
+        let rows = DiffRowLayout::build(&diff_model, old_lines.len(), new_lines.len(), view_mode);
         let mut this = Self {
             title,
             path,
@@ -5785,8 +13911,17 @@ impl DiffViewState {
             current_hunk: 0,
             scroll_handle: VirtualListScrollHandle::new(),
             scroll_state: ScrollbarState::default(),
+            new_pane_scroll_handle: VirtualListScrollHandle::new(),
+            new_pane_scroll_state: ScrollbarState::default(),
             list_item_sizes: Rc::new(Vec::new()),
             list_item_height: px(0.),
+            lfs: None,
+            minimap: None,
+            search: None,
+            selection: None,
+            fold_by_function: false,
+            encoding: None,
+            diff_timing,
         };
         this.recalc_hunk_rows();
         this
@@ -5803,8 +13938,9 @@ impl DiffViewState {
         diff_model: diffview::DiffModel,
         old_lines: Vec<String>,
         new_lines: Vec<String>,
+        diff_timing: DiffTiming,
     ) -> Self {
-        let rows = build_display_rows_from_model(&diff_model, &old_lines, &new_lines, view_mode);
+        let rows = DiffRowLayout::build(&diff_model, old_lines.len(), new_lines.len(), view_mode);
         let mut this = Self {
             title,
             path,
@@ -5820,8 +13956,17 @@ impl DiffViewState {
             current_hunk: 0,
             scroll_handle: VirtualListScrollHandle::new(),
             scroll_state: ScrollbarState::default(),
+            new_pane_scroll_handle: VirtualListScrollHandle::new(),
+            new_pane_scroll_state: ScrollbarState::default(),
             list_item_sizes: Rc::new(Vec::new()),
             list_item_height: px(0.),
+            lfs: None,
+            minimap: None,
+            search: None,
+            selection: None,
+            fold_by_function: false,
+            encoding: None,
+            diff_timing,
         };
         this.recalc_hunk_rows();
         this
@@ -5837,24 +13982,532 @@ impl DiffViewState {
     }
 
     fn rebuild_rows(&mut self, view_mode: DiffViewMode) {
-        self.rows = build_display_rows_from_model(
+        self.rows = DiffRowLayout::build(
             &self.diff_model,
-            &self.old_lines,
-            &self.new_lines,
+            self.old_lines.len(),
+            self.new_lines.len(),
             view_mode,
         );
+        if self.fold_by_function {
+            self.rows.apply_function_context(&self.old_lines);
+        }
         self.recalc_hunk_rows();
     }
 
-    fn recalc_hunk_rows(&mut self) {
-        self.hunk_rows = self
-            .rows
+    /// 按需生成第 `index` 行，只有虚拟列表当前可见的行会走到这里。
+    fn row(&self, index: usize) -> Option<DisplayRow> {
+        let (span, offset) = self.rows.locate(index)?;
+        match span {
+            RowSpan::HunkHeader { hunk: hunk_index } => {
+                let hunk = self.diff_model.hunks.get(hunk_index)?;
+                let mut text = format!(
+                    "@@ -{},{} +{},{} @@",
+                    hunk.old_start + 1,
+                    hunk.old_len,
+                    hunk.new_start + 1,
+                    hunk.new_len
+                );
+                // 与 git 一样，在 hunk 头后附上 hunk 之前最近的函数签名。
+                let function = hunk
+                    .old_start
+                    .checked_sub(1)
+                    .and_then(|index| enclosing_function(&self.old_lines, index));
+                if let Some(function) = function {
+                    text.push(' ');
+                    text.push_str(function);
+                }
+                Some(DisplayRow::HunkHeader {
+                    hunk: hunk_index,
+                    text: text.into(),
+                })
+            }
+            RowSpan::HunkRows {
+                hunk,
+                first_row,
+                paired,
+                ..
+            } => {
+                let row_index = first_row + if paired { offset / 2 } else { offset };
+                let row = self.diff_model.hunks.get(hunk)?.rows.get(row_index)?;
+                let old_line = row.old.as_ref().map(|l| l.line_index + 1);
+                let new_line = row.new.as_ref().map(|l| l.line_index + 1);
+                // 只有换行符不同的行在 CRLF 一侧行尾补一个 ␍，否则两侧看起来完全一样。
+                let eol_changed = row.eol_changed();
+                let side_segments = |line: Option<&diffview::SideLine>, kind| {
+                    let Some(line) = line else {
+                        return Vec::new();
+                    };
+                    let mut segments = line.segments.clone();
+                    if eol_changed && line.crlf {
+                        segments.push(diffview::DiffSegment {
+                            kind,
+                            text: "␍".to_string(),
+                        });
+                    }
+                    segments
+                };
+                let old_segments =
+                    || side_segments(row.old.as_ref(), diffview::DiffSegmentKind::Removed);
+                let new_segments =
+                    || side_segments(row.new.as_ref(), diffview::DiffSegmentKind::Added);
+
+                Some(if !paired {
+                    DisplayRow::Code {
+                        kind: row.kind(),
+                        old_line,
+                        new_line,
+                        old_segments: old_segments(),
+                        new_segments: new_segments(),
+                    }
+                } else if offset % 2 == 0 {
+                    DisplayRow::Code {
+                        kind: diffview::DiffRowKind::Removed,
+                        old_line,
+                        new_line: None,
+                        old_segments: old_segments(),
+                        new_segments: Vec::new(),
+                    }
+                } else {
+                    DisplayRow::Code {
+                        kind: diffview::DiffRowKind::Added,
+                        old_line: None,
+                        new_line,
+                        old_segments: Vec::new(),
+                        new_segments: new_segments(),
+                    }
+                })
+            }
+            RowSpan::Fold {
+                old_start,
+                new_start,
+                len,
+            } => Some(DisplayRow::Fold {
+                old_start,
+                new_start,
+                len,
+                function: enclosing_function(&self.old_lines, old_start + len - 1)
+                    .map(|function| SharedString::from(function.to_string())),
+            }),
+            RowSpan::Context {
+                old_start,
+                new_start,
+                ..
+            } => {
+                let old_index = old_start + offset;
+                let new_index = new_start + offset;
+                let old_text = self.old_lines.get(old_index).cloned().unwrap_or_default();
+                let new_text = self
+                    .new_lines
+                    .get(new_index)
+                    .cloned()
+                    .unwrap_or_else(|| old_text.clone());
+
+                Some(DisplayRow::Code {
+                    kind: diffview::DiffRowKind::Unchanged,
+                    old_line: Some(old_index + 1),
+                    new_line: Some(new_index + 1),
+                    old_segments: vec![diffview::DiffSegment {
+                        kind: diffview::DiffSegmentKind::Unchanged,
+                        text: old_text,
+                    }],
+                    new_segments: vec![diffview::DiffSegment {
+                        kind: diffview::DiffSegmentKind::Unchanged,
+                        text: new_text,
+                    }],
+                })
+            }
+        }
+    }
+
+    fn selected_rows(&self) -> Option<std::ops::RangeInclusive<usize>> {
+        let (anchor, head) = self.selection?;
+        Some(anchor.min(head)..=anchor.max(head))
+    }
+
+    fn is_row_selected(&self, index: usize) -> bool {
+        self.selected_rows()
+            .is_some_and(|range| range.contains(&index))
+    }
+
+    /// 选中范围内每个 hunk 行被选中的一侧：(hunk, 行, 旧侧, 新侧)。
+    fn selected_hunk_rows(&self) -> Vec<(usize, usize, bool, bool)> {
+        let Some(range) = self.selected_rows() else {
+            return Vec::new();
+        };
+
+        let mut selected: Vec<(usize, usize, bool, bool)> = Vec::new();
+        for index in range {
+            let Some((
+                RowSpan::HunkRows {
+                    hunk,
+                    first_row,
+                    paired,
+                    ..
+                },
+                offset,
+            )) = self.rows.locate(index)
+            else {
+                continue;
+            };
+            let (row, old_side, new_side) = if paired {
+                (first_row + offset / 2, offset % 2 == 0, offset % 2 == 1)
+            } else {
+                (first_row + offset, true, true)
+            };
+            match selected.last_mut() {
+                Some(last) if last.0 == hunk && last.1 == row => {
+                    last.2 |= old_side;
+                    last.3 |= new_side;
+                }
+                _ => selected.push((hunk, row, old_side, new_side)),
+            }
+        }
+        selected
+    }
+
+    /// 选中行（没有选中时为当前 hunk）生成的 unified patch。
+    fn patch_for_selection_or_hunk(&self, path: &str) -> Option<String> {
+        if self.selection.is_none() {
+            let hunk = self.diff_model.hunks.get(self.current_hunk)?;
+            return Some(unified_patch_for_hunk(path, hunk));
+        }
+
+        let selected = self.selected_hunk_rows();
+        let mut hunks: Vec<(usize, Vec<(usize, bool, bool)>)> = Vec::new();
+        for (hunk, row, old_side, new_side) in selected {
+            match hunks.last_mut() {
+                Some((last, rows)) if *last == hunk => rows.push((row, old_side, new_side)),
+                _ => hunks.push((hunk, vec![(row, old_side, new_side)])),
+            }
+        }
+
+        let mut out = String::new();
+        let mut delta = 0isize;
+        for (hunk_index, rows) in hunks {
+            let Some(hunk) = self.diff_model.hunks.get(hunk_index) else {
+                continue;
+            };
+            let is_selected = |row_index: usize, old: bool| {
+                rows.iter().any(|(row, old_side, new_side)| {
+                    *row == row_index && if old { *old_side } else { *new_side }
+                })
+            };
+            if let Some((body, old_len, new_len)) = partial_hunk_body(hunk, is_selected) {
+                let old_start = if old_len == 0 {
+                    hunk.old_start
+                } else {
+                    hunk.old_start + 1
+                };
+                let new_start = (old_start as isize + delta).max(0) as usize;
+                out.push_str(&format!(
+                    "@@ -{old_start},{old_len} +{new_start},{new_len} @@\n"
+                ));
+                out.push_str(&body);
+                delta += new_len as isize - old_len as isize;
+            }
+        }
+
+        if out.is_empty() {
+            return None;
+        }
+        Some(format!("--- a/{path}\n+++ b/{path}\n{out}"))
+    }
+
+    /// 选中行（没有选中时为当前 hunk）在新版本中的文本。
+    fn new_text_for_selection_or_hunk(&self) -> Option<String> {
+        let mut lines: Vec<&str> = Vec::new();
+        match self.selected_rows() {
+            Some(range) => {
+                for index in range {
+                    match self.rows.locate(index) {
+                        Some((
+                            RowSpan::HunkRows {
+                                hunk,
+                                first_row,
+                                paired,
+                                ..
+                            },
+                            offset,
+                        )) => {
+                            if paired && offset % 2 == 0 {
+                                continue;
+                            }
+                            let row_index = first_row + if paired { offset / 2 } else { offset };
+                            if let Some(line) = self
+                                .diff_model
+                                .hunks
+                                .get(hunk)
+                                .and_then(|hunk| hunk.rows.get(row_index))
+                                .and_then(|row| row.new.as_ref())
+                            {
+                                lines.push(&line.text);
+                            }
+                        }
+                        Some((RowSpan::Context { new_start, .. }, offset)) => {
+                            if let Some(line) = self.new_lines.get(new_start + offset) {
+                                lines.push(line);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            None => {
+                let hunk = self.diff_model.hunks.get(self.current_hunk)?;
+                lines.extend(
+                    hunk.rows
+                        .iter()
+                        .filter_map(|row| row.new.as_ref())
+                        .map(|line| line.text.as_str()),
+                );
+            }
+        }
+
+        if lines.is_empty() {
+            return None;
+        }
+        let mut text = lines.join("\n");
+        text.push('\n');
+        Some(text)
+    }
+
+    /// 当前 hunk 中第一处变更在新文件中的行号（从 1 开始）；没有 hunk 时为 1。
+    fn current_line(&self) -> usize {
+        let Some(hunk) = self.diff_model.hunks.get(self.current_hunk) else {
+            return 1;
+        };
+        hunk.rows
             .iter()
-            .enumerate()
-            .filter_map(|(index, row)| {
-                matches!(row, DisplayRow::HunkHeader { .. }).then_some(index)
-            })
-            .collect();
+            .find(|row| row.kind() != diffview::DiffRowKind::Unchanged)
+            .and_then(|row| row.new.as_ref().or(row.old.as_ref()))
+            .map(|line| line.line_index + 1)
+            .unwrap_or(hunk.new_start + 1)
+    }
+
+    /// 跳到新文件第 `line` 行（从 1 开始）所在的 hunk；该行不在任何 hunk 中时不动。
+    fn jump_to_new_line(&mut self, line: usize) {
+        let index = line.saturating_sub(1);
+        let Some(hunk) = self.diff_model.hunks.iter().position(|hunk| {
+            (hunk.new_start..hunk.new_start + hunk.new_len.max(1)).contains(&index)
+        }) else {
+            return;
+        };
+        if let Some(&row) = self.hunk_rows.get(hunk) {
+            self.current_hunk = hunk;
+            self.scroll_to_row(row);
+        }
+    }
+
+    /// 滚动到指定行；分栏取消联动时右侧面板一起跳转。
+    fn scroll_to_row(&self, row_index: usize) {
+        self.scroll_handle
+            .scroll_to_item(row_index, ScrollStrategy::Top);
+        self.new_pane_scroll_handle
+            .scroll_to_item(row_index, ScrollStrategy::Top);
+    }
+
+    fn minimap_marks(&mut self) -> Rc<Vec<MinimapMark>> {
+        if let Some(marks) = self.minimap.as_ref() {
+            return marks.clone();
+        }
+
+        let mut rows = Vec::new();
+        for (span, start) in self.rows.spans.iter().zip(&self.rows.starts) {
+            let RowSpan::HunkRows {
+                hunk,
+                first_row,
+                len,
+                paired,
+            } = *span
+            else {
+                continue;
+            };
+            let Some(hunk) = self.diff_model.hunks.get(hunk) else {
+                continue;
+            };
+            for (offset, row) in hunk.rows[first_row..first_row + len].iter().enumerate() {
+                let kind = if row
+                    .new
+                    .as_ref()
+                    .is_some_and(|line| is_conflict_marker_line(&segments_text(&line.segments)))
+                {
+                    MinimapMarkKind::Conflict
+                } else {
+                    match row.kind() {
+                        diffview::DiffRowKind::Unchanged => continue,
+                        diffview::DiffRowKind::Added => MinimapMarkKind::Added,
+                        diffview::DiffRowKind::Removed => MinimapMarkKind::Removed,
+                        diffview::DiffRowKind::Modified => MinimapMarkKind::Modified,
+                    }
+                };
+                if paired {
+                    rows.push((start + offset * 2, kind));
+                    rows.push((start + offset * 2 + 1, kind));
+                } else {
+                    rows.push((start + offset, kind));
+                }
+            }
+        }
+
+        let marks = Rc::new(build_minimap_marks(self.rows.len(), rows));
+        self.minimap = Some(marks.clone());
+        marks
+    }
+
+    /// 返回命中 `query` 的行；折叠区域内的命中记在折叠行上。
+    fn search_rows(&mut self, query: &str, options: DiffSearchOptions) -> Rc<Vec<usize>> {
+        let query = query.trim().to_string();
+        if query.is_empty() {
+            self.search = None;
+            return Rc::new(Vec::new());
+        }
+        if let Some(search) = self
+            .search
+            .as_ref()
+            .filter(|search| search.query == query && search.options == options)
+        {
+            return search.rows.clone();
+        }
+
+        let (pattern, error) = match options.compile(&query) {
+            Ok(pattern) => (Some(pattern), None),
+            Err(err) => (None, Some(err.to_string())),
+        };
+        let Some(regex) = pattern.as_ref() else {
+            let rows = Rc::new(Vec::new());
+            self.search = Some(DiffSearchState {
+                query,
+                options,
+                pattern,
+                error,
+                rows: rows.clone(),
+                current: None,
+            });
+            return rows;
+        };
+
+        // 空匹配（如 `a*`）不算命中，否则几乎每一行都会被选中。
+        let matches = |text: &str| regex.find_iter(text).any(|found| !found.is_empty());
+        let mut rows = Vec::new();
+        for (span, start) in self.rows.spans.iter().zip(&self.rows.starts) {
+            match *span {
+                RowSpan::HunkHeader { .. } => {}
+                RowSpan::HunkRows {
+                    hunk,
+                    first_row,
+                    len,
+                    paired,
+                } => {
+                    let Some(hunk) = self.diff_model.hunks.get(hunk) else {
+                        continue;
+                    };
+                    for (offset, row) in hunk.rows[first_row..first_row + len].iter().enumerate() {
+                        let old_hit = row
+                            .old
+                            .as_ref()
+                            .is_some_and(|line| matches(&segments_text(&line.segments)));
+                        let new_hit = row
+                            .new
+                            .as_ref()
+                            .is_some_and(|line| matches(&segments_text(&line.segments)));
+                        if paired {
+                            if old_hit {
+                                rows.push(start + offset * 2);
+                            }
+                            if new_hit {
+                                rows.push(start + offset * 2 + 1);
+                            }
+                        } else if old_hit || new_hit {
+                            rows.push(start + offset);
+                        }
+                    }
+                }
+                RowSpan::Fold {
+                    old_start,
+                    new_start,
+                    len,
+                } => {
+                    let old_hit = self
+                        .old_lines
+                        .iter()
+                        .skip(old_start)
+                        .take(len)
+                        .any(|line| matches(line));
+                    let new_hit = self
+                        .new_lines
+                        .iter()
+                        .skip(new_start)
+                        .take(len)
+                        .any(|line| matches(line));
+                    if old_hit || new_hit {
+                        rows.push(*start);
+                    }
+                }
+                RowSpan::Context {
+                    old_start,
+                    new_start,
+                    len,
+                } => {
+                    for offset in 0..len {
+                        let old_hit = self
+                            .old_lines
+                            .get(old_start + offset)
+                            .is_some_and(|line| matches(line));
+                        let new_hit = self
+                            .new_lines
+                            .get(new_start + offset)
+                            .is_some_and(|line| matches(line));
+                        if old_hit || new_hit {
+                            rows.push(start + offset);
+                        }
+                    }
+                }
+            }
+        }
+
+        let rows = Rc::new(rows);
+        self.search = Some(DiffSearchState {
+            query,
+            options,
+            pattern,
+            error,
+            rows: rows.clone(),
+            current: None,
+        });
+        rows
+    }
+
+    /// `text` 中命中当前查找的非空字节区间；没有查找时为空。
+    fn search_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        let Some(pattern) = self
+            .search
+            .as_ref()
+            .and_then(|search| search.pattern.as_ref())
+        else {
+            return Vec::new();
+        };
+        pattern
+            .find_iter(text)
+            .filter(|found| !found.is_empty())
+            .map(|found| found.range())
+            .collect()
+    }
+
+    /// 存在只有换行符不同的行时，返回第一处的（旧侧是否 CRLF, 新侧是否 CRLF）。
+    fn eol_change(&self) -> Option<(bool, bool)> {
+        self.diff_model
+            .hunks
+            .iter()
+            .flat_map(|hunk| &hunk.rows)
+            .find(|row| row.eol_changed())
+            .and_then(|row| Some((row.old.as_ref()?.crlf, row.new.as_ref()?.crlf)))
+    }
+
+    fn recalc_hunk_rows(&mut self) {
+        self.hunk_rows = self.rows.hunk_header_rows();
+        self.minimap = None;
+        self.search = None;
+        self.selection = None;
 
         if self.current_hunk >= self.hunk_rows.len() {
             self.current_hunk = self.hunk_rows.len().saturating_sub(1);
@@ -5866,27 +14519,29 @@ impl ConflictViewState {
     fn new(
         title: SharedString,
         path: Option<String>,
-        text: String,
+        source: ConflictSource,
+        content: ConflictContent,
         result_input: Entity<InputState>,
     ) -> Self {
-        let conflicts = diffview::parse_conflicts(&text);
-        let rows = build_conflict_rows(&text, &conflicts);
         let mut this = Self {
             title,
             path,
-            text,
+            source,
+            content,
+            text: String::new(),
             result_input,
             show_result_editor: true,
-            conflicts,
-            rows,
+            conflicts: Vec::new(),
+            rows: Vec::new(),
             conflict_rows: Vec::new(),
             current_conflict: 0,
             scroll_handle: VirtualListScrollHandle::new(),
             scroll_state: ScrollbarState::default(),
             list_item_sizes: Rc::new(Vec::new()),
             list_item_height: px(0.),
+            encoding: TextEncoding::UTF8,
         };
-        this.recalc_conflict_rows();
+        this.rebuild();
         this
     }
 
@@ -5900,11 +14555,18 @@ impl ConflictViewState {
     }
 
     fn rebuild(&mut self) {
-        self.conflicts = diffview::parse_conflicts(&self.text);
-        self.rows = build_conflict_rows(&self.text, &self.conflicts);
+        self.text = self.content.result_text();
+        self.conflicts = self.content.blocks();
+        self.rows = build_conflict_rows(&self.conflicts);
         self.recalc_conflict_rows();
     }
 
+    /// 手动编辑过的结果文本取代原来的内容，其中剩下的冲突按冲突标记识别。
+    fn apply_edited_text(&mut self, text: String) {
+        self.content = ConflictContent::Markers(text);
+        self.rebuild();
+    }
+
     fn recalc_conflict_rows(&mut self) {
         self.conflict_rows = self
             .rows
@@ -6037,168 +14699,398 @@ fn conflict_demo_text() -> String {
 fn build_diff_model(
     old_text: &str,
     new_text: &str,
-    ignore_whitespace: bool,
-    context_lines: usize,
-) -> (diffview::DiffModel, Vec<String>, Vec<String>) {
+    options: DiffViewOptions,
+) -> (diffview::DiffModel, Vec<String>, Vec<String>, DiffTiming) {
+    let started = Instant::now();
     let old_doc = diffview::Document::from_str(old_text);
     let new_doc = diffview::Document::from_str(new_text);
     let old_lines = old_doc.lines();
     let new_lines = new_doc.lines();
-    let model = diffview::diff_documents(
+    let (model, phases) = diffview::diff_documents_timed(
         &old_doc,
         &new_doc,
         diffview::DiffOptions {
-            context_lines,
-            ignore_whitespace,
+            context_lines: options.context_lines,
+            ignore_whitespace: options.ignore_whitespace,
+            ignore_eol: options.ignore_eol,
         },
     );
 
-    (model, old_lines, new_lines)
-}
+    let timing = DiffTiming {
+        total: started.elapsed(),
+        phases,
+    };
+    if diff_timing_enabled() {
+        tracing::info!(
+            old_lines = old_lines.len(),
+            new_lines = new_lines.len(),
+            "diff 耗时 {}：{}",
+            format_diff_duration(timing.total),
+            timing.breakdown()
+        );
+    }
+
+    (model, old_lines, new_lines, timing)
+}
+
+fn segments_text(segments: &[diffview::DiffSegment]) -> String {
+    segments
+        .iter()
+        .map(|segment| segment.text.as_str())
+        .collect()
+}
+
+fn is_conflict_marker_line(text: &str) -> bool {
+    ["<<<<<<< ", "=======", ">>>>>>> ", "||||||| "]
+        .iter()
+        .any(|marker| text.starts_with(marker))
+}
+
+/// 把逐行的标记压缩成最多 [`MINIMAP_BUCKETS`] 个桶；同一桶内取优先级最高的类型。
+fn build_minimap_marks(
+    total_rows: usize,
+    rows: impl IntoIterator<Item = (usize, MinimapMarkKind)>,
+) -> Vec<MinimapMark> {
+    if total_rows == 0 {
+        return Vec::new();
+    }
+
+    let buckets = total_rows.min(MINIMAP_BUCKETS);
+    let mut slots: Vec<Option<(MinimapMarkKind, usize)>> = vec![None; buckets];
+    for (row, kind) in rows {
+        let bucket = (row * buckets / total_rows).min(buckets - 1);
+        let slot = &mut slots[bucket];
+        match slot {
+            Some((existing, _)) if *existing >= kind => {}
+            Some((existing, _)) => *existing = kind,
+            None => *slot = Some((kind, row)),
+        }
+    }
+
+    let mut marks: Vec<MinimapMark> = Vec::new();
+    for (bucket, slot) in slots.into_iter().enumerate() {
+        let Some((kind, row)) = slot else {
+            continue;
+        };
+        match marks.last_mut() {
+            Some(last) if last.kind == kind && last.last_bucket + 1 == bucket => {
+                last.last_bucket = bucket;
+                continue;
+            }
+            _ => {}
+        }
+        marks.push(MinimapMark {
+            first_bucket: bucket,
+            last_bucket: bucket,
+            buckets,
+            kind,
+            row,
+        });
+    }
+    marks
+}
+
+impl DiffRowLayout {
+    /// 只遍历 hunk 计算各段的位置；hunk 之间的未变更区域作为折叠段。
+    fn build(
+        model: &diffview::DiffModel,
+        old_line_count: usize,
+        new_line_count: usize,
+        view_mode: DiffViewMode,
+    ) -> Self {
+        let mut spans = Vec::new();
+        let mut old_pos = 0usize;
+        let mut new_pos = 0usize;
+
+        for (hunk_index, hunk) in model.hunks.iter().enumerate() {
+            let gap_old = hunk.old_start.saturating_sub(old_pos);
+            let gap_new = hunk.new_start.saturating_sub(new_pos);
+            let gap_len = gap_old.min(gap_new);
+            if gap_len > 0 {
+                spans.push(RowSpan::Fold {
+                    old_start: old_pos,
+                    new_start: new_pos,
+                    len: gap_len,
+                });
+            }
+
+            spans.push(RowSpan::HunkHeader { hunk: hunk_index });
 
-fn build_display_rows_from_model(
-    model: &diffview::DiffModel,
-    old_lines: &[String],
-    new_lines: &[String],
-    view_mode: DiffViewMode,
-) -> Vec<DisplayRow> {
-    let mut rows = Vec::new();
-    let mut old_pos = 0usize;
-    let mut new_pos = 0usize;
-
-    for hunk in &model.hunks {
-        let gap_old = hunk.old_start.saturating_sub(old_pos);
-        let gap_new = hunk.new_start.saturating_sub(new_pos);
-        let gap_len = gap_old.min(gap_new);
-        if gap_len > 0 {
-            rows.push(DisplayRow::Fold {
+            match view_mode {
+                DiffViewMode::Split => {
+                    if !hunk.rows.is_empty() {
+                        spans.push(RowSpan::HunkRows {
+                            hunk: hunk_index,
+                            first_row: 0,
+                            len: hunk.rows.len(),
+                            paired: false,
+                        });
+                    }
+                }
+                DiffViewMode::Inline => {
+                    let is_modified =
+                        |index: usize| hunk.rows[index].kind() == diffview::DiffRowKind::Modified;
+                    let mut run_start = 0usize;
+                    for index in 1..=hunk.rows.len() {
+                        if index == hunk.rows.len() || is_modified(index) != is_modified(run_start)
+                        {
+                            spans.push(RowSpan::HunkRows {
+                                hunk: hunk_index,
+                                first_row: run_start,
+                                len: index - run_start,
+                                paired: is_modified(run_start),
+                            });
+                            run_start = index;
+                        }
+                    }
+                }
+            }
+
+            old_pos = hunk.old_start + hunk.old_len;
+            new_pos = hunk.new_start + hunk.new_len;
+        }
+
+        let tail_old = old_line_count.saturating_sub(old_pos);
+        let tail_new = new_line_count.saturating_sub(new_pos);
+        let tail_len = tail_old.min(tail_new);
+        if tail_len > 0 {
+            spans.push(RowSpan::Fold {
                 old_start: old_pos,
                 new_start: new_pos,
-                len: gap_len,
+                len: tail_len,
             });
         }
 
-        rows.push(DisplayRow::HunkHeader {
-            text: format!(
-                "@@ -{},{} +{},{} @@",
-                hunk.old_start + 1,
-                hunk.old_len,
-                hunk.new_start + 1,
-                hunk.new_len
-            )
-            .into(),
-        });
+        let mut layout = Self {
+            spans,
+            starts: Vec::new(),
+            len: 0,
+        };
+        layout.recalc_starts();
+        layout
+    }
 
-        for row in &hunk.rows {
-            let kind = row.kind();
-            let old_line = row.old.as_ref().map(|l| l.line_index + 1);
-            let new_line = row.new.as_ref().map(|l| l.line_index + 1);
-            let old_segments = row
-                .old
-                .as_ref()
-                .map(|l| l.segments.clone())
-                .unwrap_or_default();
-            let new_segments = row
-                .new
-                .as_ref()
-                .map(|l| l.segments.clone())
-                .unwrap_or_default();
+    fn recalc_starts(&mut self) {
+        self.starts.clear();
+        self.starts.reserve(self.spans.len());
+        let mut next = 0usize;
+        for span in &self.spans {
+            self.starts.push(next);
+            next += span.row_count();
+        }
+        self.len = next;
+    }
 
-            match view_mode {
-                DiffViewMode::Split => rows.push(DisplayRow::Code {
-                    kind,
-                    old_line,
-                    new_line,
-                    old_segments,
-                    new_segments,
-                }),
-                DiffViewMode::Inline => match kind {
-                    diffview::DiffRowKind::Modified => {
-                        rows.push(DisplayRow::Code {
-                            kind: diffview::DiffRowKind::Removed,
-                            old_line,
-                            new_line: None,
-                            old_segments,
-                            new_segments: Vec::new(),
-                        });
-                        rows.push(DisplayRow::Code {
-                            kind: diffview::DiffRowKind::Added,
-                            old_line: None,
-                            new_line,
-                            old_segments: Vec::new(),
-                            new_segments,
-                        });
-                    }
-                    diffview::DiffRowKind::Added => rows.push(DisplayRow::Code {
-                        kind,
-                        old_line: None,
-                        new_line,
-                        old_segments: Vec::new(),
-                        new_segments,
-                    }),
-                    diffview::DiffRowKind::Removed => rows.push(DisplayRow::Code {
-                        kind,
-                        old_line,
-                        new_line: None,
-                        old_segments,
-                        new_segments: Vec::new(),
-                    }),
-                    diffview::DiffRowKind::Unchanged => rows.push(DisplayRow::Code {
-                        kind,
-                        old_line,
-                        new_line,
-                        old_segments,
-                        new_segments,
-                    }),
-                },
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    /// 返回第 `index` 行所在的段及其在段内的偏移。
+    fn locate(&self, index: usize) -> Option<(RowSpan, usize)> {
+        if index >= self.len {
+            return None;
+        }
+        let span_index = self.starts.partition_point(|start| *start <= index) - 1;
+        Some((self.spans[span_index], index - self.starts[span_index]))
+    }
+
+    fn has_folds(&self) -> bool {
+        self.spans
+            .iter()
+            .any(|span| matches!(span, RowSpan::Fold { .. }))
+    }
+
+    fn hunk_header_rows(&self) -> Vec<usize> {
+        self.spans
+            .iter()
+            .zip(&self.starts)
+            .filter_map(|(span, start)| {
+                matches!(span, RowSpan::HunkHeader { .. }).then_some(*start)
+            })
+            .collect()
+    }
+
+    fn expand_fold(&mut self, row_index: usize) -> bool {
+        let Some((RowSpan::Fold { .. }, _)) = self.locate(row_index) else {
+            return false;
+        };
+        let span_index = self.starts.partition_point(|start| *start <= row_index) - 1;
+        expand_span(&mut self.spans[span_index]);
+        self.recalc_starts();
+        true
+    }
+
+    fn expand_all_folds(&mut self) -> bool {
+        if !self.has_folds() {
+            return false;
+        }
+        self.spans.iter_mut().for_each(expand_span);
+        self.recalc_starts();
+        true
+    }
+
+    /// 按函数折叠：折叠区域中紧接上一个 hunk 的函数剩余部分、以及下一个 hunk 所在函数
+    /// 的开头都展开，只保留中间完整的未变更函数为折叠段；找不到函数边界时整段展开。
+    fn apply_function_context(&mut self, lines: &[String]) {
+        let span_count = self.spans.len();
+        let mut spans = Vec::with_capacity(span_count);
+        for (index, span) in self.spans.iter().enumerate() {
+            let RowSpan::Fold {
+                old_start,
+                new_start,
+                len,
+            } = *span
+            else {
+                spans.push(*span);
+                continue;
+            };
+
+            let has_prev_hunk = index > 0;
+            let has_next_hunk = index + 1 < span_count;
+            let fold_lines = lines.get(old_start..old_start + len).unwrap_or_default();
+            let first_header = fold_lines.iter().position(|line| is_function_header(line));
+            let last_header = fold_lines.iter().rposition(|line| is_function_header(line));
+
+            let (keep_head, tail_start) = match (first_header, last_header) {
+                (Some(first), Some(last)) => (
+                    if has_prev_hunk { first } else { 0 },
+                    if has_next_hunk { last } else { len },
+                ),
+                _ => (len, len),
+            };
+
+            if keep_head > 0 {
+                spans.push(RowSpan::Context {
+                    old_start,
+                    new_start,
+                    len: keep_head,
+                });
+            }
+            if tail_start > keep_head {
+                spans.push(RowSpan::Fold {
+                    old_start: old_start + keep_head,
+                    new_start: new_start + keep_head,
+                    len: tail_start - keep_head,
+                });
+            }
+            if len > tail_start.max(keep_head) {
+                let start = tail_start.max(keep_head);
+                spans.push(RowSpan::Context {
+                    old_start: old_start + start,
+                    new_start: new_start + start,
+                    len: len - start,
+                });
             }
         }
+        self.spans = spans;
+        self.recalc_starts();
+    }
+}
+
+const FUNCTION_SCAN_LIMIT: usize = 2000;
+
+const FUNCTION_KEYWORDS: [&str; 14] = [
+    "fn",
+    "def",
+    "class",
+    "func",
+    "function",
+    "impl",
+    "struct",
+    "enum",
+    "trait",
+    "interface",
+    "mod",
+    "module",
+    "namespace",
+    "sub",
+];
+
+const FUNCTION_MODIFIERS: [&str; 10] = [
+    "pub",
+    "async",
+    "unsafe",
+    "const",
+    "export",
+    "default",
+    "static",
+    "public",
+    "private",
+    "protected",
+];
 
-        old_pos = hunk.old_start + hunk.old_len;
-        new_pos = hunk.new_start + hunk.new_len;
+/// 判断一行是否像函数（或类型）签名：沿用 git 默认的 hunk 头规则（顶格且以字母、`_` 或 `$`
+/// 开头），并补充识别缩进的 `fn` / `def` / `class` 等常见关键字。
+fn is_function_header(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    let Some(first) = trimmed.chars().next() else {
+        return false;
+    };
+
+    if trimmed.len() == line.len()
+        && (first.is_alphabetic() || first == '_' || first == '$')
+        && !trimmed.trim_end().ends_with(';')
+    {
+        return true;
     }
 
-    let tail_old = old_lines.len().saturating_sub(old_pos);
-    let tail_new = new_lines.len().saturating_sub(new_pos);
-    let tail_len = tail_old.min(tail_new);
-    if tail_len > 0 {
-        rows.push(DisplayRow::Fold {
-            old_start: old_pos,
-            new_start: new_pos,
-            len: tail_len,
+    let mut words = trimmed
+        .split(|c: char| c.is_whitespace() || c == '(')
+        .filter(|word| !word.is_empty())
+        .skip_while(|word| {
+            FUNCTION_MODIFIERS.contains(word) || *word == "crate)" || *word == "super)"
         });
-    }
+    words
+        .next()
+        .is_some_and(|word| FUNCTION_KEYWORDS.contains(&word))
+}
 
-    rows
+/// 从第 `index` 行往前找最近的函数签名，最多回溯 [`FUNCTION_SCAN_LIMIT`] 行。
+fn enclosing_function(lines: &[String], index: usize) -> Option<&str> {
+    let end = index.checked_add(1)?.min(lines.len());
+    lines[..end]
+        .iter()
+        .rev()
+        .take(FUNCTION_SCAN_LIMIT)
+        .find(|line| is_function_header(line))
+        .map(|line| line.trim())
+}
+
+fn expand_span(span: &mut RowSpan) {
+    if let RowSpan::Fold {
+        old_start,
+        new_start,
+        len,
+    } = *span
+    {
+        *span = RowSpan::Context {
+            old_start,
+            new_start,
+            len,
+        };
+    }
 }
 
-fn build_conflict_rows(text: &str, conflicts: &[diffview::ConflictRegion]) -> Vec<ConflictRow> {
+fn build_conflict_rows(conflicts: &[ConflictBlock]) -> Vec<ConflictRow> {
     if conflicts.is_empty() {
         return vec![ConflictRow::EmptyState {
-            text: "没有检测到冲突标记（<<<<<<< / ======= / >>>>>>>）".into(),
+            text: "没有未解决的冲突".into(),
         }];
     }
 
     let mut rows = Vec::new();
-    for (conflict_index, region) in conflicts.iter().enumerate() {
+    for (conflict_index, block) in conflicts.iter().enumerate() {
         rows.push(ConflictRow::BlockHeader {
             conflict_index,
-            ours_branch_name: region.ours_branch_name.clone().into(),
-            theirs_branch_name: region.theirs_branch_name.clone().into(),
-            has_base: region.base.is_some(),
+            ours_branch_name: block.ours_branch_name.clone().into(),
+            theirs_branch_name: block.theirs_branch_name.clone().into(),
+            has_base: block.base.is_some(),
         });
 
-        let ours_text = &text[region.ours.clone()];
-        let theirs_text = &text[region.theirs.clone()];
-        let base_text = region
-            .base
-            .as_ref()
-            .map(|range| &text[range.clone()])
-            .unwrap_or("");
-
-        let ours_lines = diffview::Document::from_str(ours_text).lines();
-        let base_lines = diffview::Document::from_str(base_text).lines();
-        let theirs_lines = diffview::Document::from_str(theirs_text).lines();
+        let ours_lines = diffview::Document::from_str(&block.ours).lines();
+        let base_lines =
+            diffview::Document::from_str(block.base.as_deref().unwrap_or_default()).lines();
+        let theirs_lines = diffview::Document::from_str(&block.theirs).lines();
         let max_len = ours_lines
             .len()
             .max(base_lines.len())
@@ -6261,120 +15153,629 @@ fn build_conflict_rows(text: &str, conflicts: &[diffview::ConflictRegion]) -> Ve
 }
 
 fn read_working_file(repo_root: &Path, path: &str) -> Result<String> {
+    read_working_file_decoded(repo_root, path).map(|(text, _)| text)
+}
+
+/// 读取工作区文件并识别编码；文件不存在时按空的 UTF-8 文本处理。
+fn read_working_file_decoded(repo_root: &Path, path: &str) -> Result<(String, TextEncoding)> {
     let full_path = repo_root.join(path);
     match std::fs::read(&full_path) {
-        Ok(bytes) => Ok(String::from_utf8_lossy(&bytes).into_owned()),
-        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Ok(bytes) => Ok(encoding::decode(&bytes)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            Ok((String::new(), TextEncoding::UTF8))
+        }
         Err(err) => Err(err).with_context(|| format!("读取工作区文件失败：{path}")),
     }
 }
 
-fn read_head_file(repo_root: &Path, path: &str, status: &str) -> Result<String> {
+/// 两侧中需要在界面上提示的编码，新版本一侧优先。
+fn detected_encoding(old: TextEncoding, new: TextEncoding) -> Option<TextEncoding> {
+    [new, old]
+        .into_iter()
+        .find(|encoding| !encoding.is_plain_utf8())
+}
+
+/// 按对比目标读取文件两侧的文本。
+fn load_diff_texts(
+    repo_root: &Path,
+    path: &str,
+    status: &str,
+    target: &CompareTarget,
+) -> DiffTexts {
+    let split = |result: Result<(String, TextEncoding)>| match result {
+        Ok((text, encoding)) => (text, None, encoding),
+        Err(err) => (String::new(), Some(err.to_string()), TextEncoding::UTF8),
+    };
+
+    let (old_text, old_err, old_encoding) = split(match target {
+        CompareTarget::HeadToWorktree | CompareTarget::HeadToIndex => {
+            read_head_file(repo_root, path, status)
+        }
+        CompareTarget::IndexToWorktree => read_index_file(repo_root, path, status),
+        CompareTarget::Refs { left, .. } => read_specified_file(repo_root, path, left),
+    });
+    let (new_text, new_err, new_encoding) = split(match target {
+        CompareTarget::HeadToWorktree | CompareTarget::IndexToWorktree => {
+            read_working_file_decoded(repo_root, path)
+        }
+        CompareTarget::HeadToIndex => read_index_file(repo_root, path, status),
+        CompareTarget::Refs { right, .. } => read_specified_file(repo_root, path, right),
+    });
+
+    DiffTexts {
+        old_text,
+        old_err,
+        new_text,
+        new_err,
+        encoding: detected_encoding(old_encoding, new_encoding),
+    }
+}
+
+fn read_head_file(repo_root: &Path, path: &str, status: &str) -> Result<(String, TextEncoding)> {
     if status == "??" || status.contains('A') {
-        return Ok(String::new());
+        return Ok((String::new(), TextEncoding::UTF8));
     }
 
     let spec = format!("HEAD:{path}");
-    let output = Command::new("git")
+    git_show_decoded(repo_root, &spec)
+}
+
+fn read_index_file(repo_root: &Path, path: &str, status: &str) -> Result<(String, TextEncoding)> {
+    if status == "??" {
+        return Ok((String::new(), TextEncoding::UTF8));
+    }
+
+    if let Some((x, _)) = status_xy(status) {
+        if x == 'D' {
+            return Ok((String::new(), TextEncoding::UTF8));
+        }
+    }
+
+    let spec = format!(":{path}");
+    git_show_decoded(repo_root, &spec)
+}
+
+fn git_show(repo_root: &Path, spec: &str) -> Result<String> {
+    git_show_decoded(repo_root, spec).map(|(text, _)| text)
+}
+
+fn git_show_decoded(repo_root: &Path, spec: &str) -> Result<(String, TextEncoding)> {
+    let bytes = git::backend().read_blob(repo_root, spec)?;
+    Ok(encoding::decode(&bytes))
+}
+
+/// 读取冲突文件在 index 中的 base/ours/theirs（stage 1/2/3）。
+///
+/// add/add 冲突没有 base，删除/修改冲突缺少一侧，这些情况按空内容处理；
+/// 两侧都读不到说明该路径并不处于冲突状态。
+fn read_conflict_stages(repo_root: &Path, path: &str) -> Result<(String, String, String)> {
+    let base = git_show(repo_root, &format!(":1:{path}")).unwrap_or_default();
+    let ours = git_show(repo_root, &format!(":2:{path}"));
+    let theirs = git_show(repo_root, &format!(":3:{path}"));
+
+    match (ours, theirs) {
+        (Err(err), Err(_)) => Err(err.context("index 中没有该文件的冲突 stage")),
+        (ours, theirs) => Ok((base, ours.unwrap_or_default(), theirs.unwrap_or_default())),
+    }
+}
+
+fn read_specified_file(repo_root: &Path, path: &str, spec: &str) -> Result<(String, TextEncoding)> {
+    let spec = spec.trim();
+    if spec.is_empty() {
+        return read_working_file_decoded(repo_root, path);
+    }
+
+    if spec.eq_ignore_ascii_case("WORKTREE") {
+        return read_working_file_decoded(repo_root, path);
+    }
+
+    if spec.eq_ignore_ascii_case("INDEX") || spec == ":" {
+        return git_show_decoded(repo_root, &format!(":{path}"));
+    }
+
+    if spec.contains(':') {
+        return git_show_decoded(repo_root, spec);
+    }
+
+    git_show_decoded(repo_root, &format!("{spec}:{path}"))
+}
+
+fn parse_lfs_pointer(text: &str) -> Option<LfsPointer> {
+    if text.len() > LFS_POINTER_MAX_LEN {
+        return None;
+    }
+
+    let mut lines = text.lines();
+    let version = lines.next()?.strip_prefix("version ")?;
+    if !LFS_POINTER_VERSIONS.contains(&version.trim()) {
+        return None;
+    }
+
+    let mut oid = None;
+    let mut size = None;
+    for line in lines {
+        let (key, value) = line.split_once(' ')?;
+        match key {
+            "oid" => {
+                let hex = value.trim().strip_prefix("sha256:")?;
+                if hex.is_empty() || !hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+                    return None;
+                }
+                oid = Some(hex.to_ascii_lowercase());
+            }
+            "size" => size = value.trim().parse::<u64>().ok(),
+            _ => {}
+        }
+    }
+
+    Some(LfsPointer {
+        oid: oid?,
+        size: size?,
+    })
+}
+
+fn lfs_pointer_text(pointer: &LfsPointer) -> String {
+    format!(
+        "version {}\noid sha256:{}\nsize {}\n",
+        LFS_POINTER_VERSIONS[0], pointer.oid, pointer.size
+    )
+}
+
+/// 检测 LFS 指针：只要任一侧是指针，就把两侧替换成对象摘要（oid/size），
+/// 避免对指针文本做无意义的行 diff。
+fn detect_lfs_diff(old_text: String, new_text: String) -> (String, String, Option<LfsDiffInfo>) {
+    let old = parse_lfs_pointer(&old_text);
+    let new = parse_lfs_pointer(&new_text);
+    if old.is_none() && new.is_none() {
+        return (old_text, new_text, None);
+    }
+
+    let old_summary = lfs_summary_text(old.as_ref(), &old_text);
+    let new_summary = lfs_summary_text(new.as_ref(), &new_text);
+    let info = LfsDiffInfo {
+        old,
+        new,
+        old_source: old_text,
+        new_source: new_text,
+        content_loaded: false,
+    };
+    (old_summary, new_summary, Some(info))
+}
+
+fn lfs_summary_text(pointer: Option<&LfsPointer>, source: &str) -> String {
+    match pointer {
+        Some(pointer) => format!(
+            "Git LFS 对象\noid sha256:{}\nsize {} ({})\n",
+            pointer.oid,
+            pointer.size,
+            format_byte_size(pointer.size)
+        ),
+        None if source.is_empty() => String::new(),
+        None => format!(
+            "实际内容（未存为 LFS 指针）\nsize {} ({})\n",
+            source.len(),
+            format_byte_size(source.len() as u64)
+        ),
+    }
+}
+
+fn lfs_side_label(pointer: Option<&LfsPointer>, source: &str) -> String {
+    match pointer {
+        Some(pointer) => format!(
+            "{} ({})",
+            &pointer.oid[..pointer.oid.len().min(12)],
+            format_byte_size(pointer.size)
+        ),
+        None if source.is_empty() => "（无）".to_string(),
+        None => format!("实际内容 ({})", format_byte_size(source.len() as u64)),
+    }
+}
+
+/// 水平条形图的一条；`fraction` 是相对最大值的比例。
+fn repo_stats_bar(fraction: f32, color: Hsla, track: Hsla) -> Div {
+    div()
+        .h(px(10.))
+        .flex_none()
+        .rounded(px(2.))
+        .bg(track)
+        .child(
+            div()
+                .h_full()
+                .w(relative(fraction.clamp(0., 1.)))
+                .rounded(px(2.))
+                .bg(color),
+        )
+}
+
+/// 按黄金分割取色相，相邻的分段颜色差得足够开。
+fn repo_stats_color(index: usize) -> Hsla {
+    hsla((index as f32 * 0.618_034).fract(), 0.55, 0.55, 1.)
+}
+
+fn format_byte_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+fn lfs_smudge(repo_root: &Path, path: &str, pointer: &LfsPointer) -> Result<String> {
+    use std::io::Write as _;
+    use std::process::Stdio;
+
+    let mut child = Command::new("git")
         .arg("-C")
         .arg(repo_root)
-        .args(["show", &spec])
-        .output()
-        .with_context(|| format!("执行 git show 失败：{spec}"))?;
+        .args(["lfs", "smudge", "--", path])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("执行 git lfs smudge 失败（是否已安装 git-lfs？）")?;
+
+    if let Some(mut input) = child.stdin.take() {
+        input
+            .write_all(lfs_pointer_text(pointer).as_bytes())
+            .context("写入 git stdin 失败")?;
+    }
 
+    let output = child.wait_with_output().context("等待 git 进程失败")?;
     if !output.status.success() {
         return Err(anyhow!(
-            "git show 返回非零（{}）：{}",
+            "git lfs smudge 返回非零（{}）：{}",
             output.status.code().unwrap_or(-1),
             String::from_utf8_lossy(&output.stderr).trim()
         ));
     }
 
+    if output.stdout.contains(&0) {
+        return Err(anyhow!(
+            "LFS 对象是二进制内容（{}），无法按文本对比",
+            format_byte_size(pointer.size)
+        ));
+    }
+
     Ok(String::from_utf8_lossy(&output.stdout).into_owned())
 }
 
-fn read_index_file(repo_root: &Path, path: &str, status: &str) -> Result<String> {
-    if status == "??" {
-        return Ok(String::new());
+const EDITOR_ENV: &str = "GIT_VIEWER_EDITOR";
+
+/// 按优先级解析编辑器命令：`GIT_VIEWER_EDITOR` → 配置文件中的 `editor` →
+/// `git config gitviewer.editor` →
+/// `$VISUAL` / `$EDITOR` → 自动探测 VS Code / Zed。
+///
+/// 命令中可以使用 `{file}` 与 `{line}` 占位符，例如 `code -g {file}:{line}`。
+fn resolve_editor_command(repo_root: &Path) -> Option<String> {
+    let non_empty = |value: String| {
+        let value = value.trim().to_string();
+        (!value.is_empty()).then_some(value)
+    };
+
+    if let Some(command) = std::env::var(EDITOR_ENV).ok().and_then(non_empty) {
+        return Some(command);
     }
 
-    if let Some((x, _)) = status_xy(status) {
-        if x == 'D' {
-            return Ok(String::new());
+    if let Some(command) = GitViewerSettings::get().editor.clone().and_then(non_empty) {
+        return Some(command);
+    }
+
+    if let Some(command) = Command::new("git")
+        .arg("-C")
+        .arg(repo_root)
+        .args(["config", "--get", "gitviewer.editor"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| non_empty(String::from_utf8_lossy(&output.stdout).to_string()))
+    {
+        return Some(command);
+    }
+
+    for name in ["VISUAL", "EDITOR"] {
+        if let Some(command) = std::env::var(name).ok().and_then(non_empty) {
+            return Some(command);
         }
     }
 
-    let spec = format!(":{path}");
+    ["code", "zed"]
+        .into_iter()
+        .find(|program| {
+            Command::new(program)
+                .arg("--version")
+                .output()
+                .is_ok_and(|output| output.status.success())
+        })
+        .map(str::to_string)
+}
+
+/// 把编辑器命令展开成 (程序, 参数)；未使用占位符时按常见编辑器的约定追加文件与行号。
+fn editor_invocation(command: &str, file: &Path, line: usize) -> Option<(String, Vec<String>)> {
+    let file = file.to_string_lossy();
+    let mut parts = split_command_line(command);
+    if parts.is_empty() {
+        return None;
+    }
+
+    let has_placeholder = parts
+        .iter()
+        .any(|part| part.contains("{file}") || part.contains("{line}"));
+    if has_placeholder {
+        for part in &mut parts {
+            *part = part
+                .replace("{file}", &file)
+                .replace("{line}", &line.to_string());
+        }
+    } else {
+        let program_name = Path::new(&parts[0])
+            .file_stem()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match program_name.as_str() {
+            "code" | "code-insiders" | "codium" | "cursor" => {
+                parts.push("-g".to_string());
+                parts.push(format!("{file}:{line}"));
+            }
+            "zed" | "subl" | "sublime_text" | "idea" | "fleet" => {
+                parts.push(format!("{file}:{line}"));
+            }
+            "vim" | "nvim" | "vi" | "nano" | "emacs" | "emacsclient" | "hx" | "helix" | "kak"
+            | "micro" | "gvim" | "mvim" => {
+                parts.push(format!("+{line}"));
+                parts.push(file.to_string());
+            }
+            _ => parts.push(file.to_string()),
+        }
+    }
+
+    let program = parts.remove(0);
+    Some((program, parts))
+}
+
+/// 按空白切分命令行，支持单引号 / 双引号包裹含空格的参数。
+fn split_command_line(command: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut in_token = false;
+
+    for ch in command.chars() {
+        match quote {
+            Some(q) if ch == q => quote = None,
+            Some(_) => current.push(ch),
+            None if ch == '"' || ch == '\'' => {
+                quote = Some(ch);
+                in_token = true;
+            }
+            None if ch.is_whitespace() => {
+                if in_token {
+                    parts.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            None => {
+                current.push(ch);
+                in_token = true;
+            }
+        }
+    }
+    if in_token {
+        parts.push(current);
+    }
+    parts
+}
+
+/// 启动外部编辑器（不等待其退出），返回实际使用的程序名。
+fn launch_editor(repo_root: &Path, file: &Path, line: usize) -> Result<String> {
+    let command = resolve_editor_command(repo_root).ok_or_else(|| {
+        anyhow!("未配置编辑器：请设置 {EDITOR_ENV}、git config gitviewer.editor 或 $EDITOR")
+    })?;
+    let (program, args) =
+        editor_invocation(&command, file, line).ok_or_else(|| anyhow!("编辑器命令为空"))?;
+
+    let mut child = Command::new(&program)
+        .args(&args)
+        .current_dir(repo_root)
+        .spawn()
+        .with_context(|| format!("启动 {program} 失败"))?;
+    // 回收子进程，避免留下僵尸进程。
+    std::thread::spawn(move || child.wait());
+    Ok(program)
+}
+
+fn run_git<I, S>(repo_root: &Path, args: I) -> Result<()>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<std::ffi::OsStr>,
+{
     let output = Command::new("git")
         .arg("-C")
         .arg(repo_root)
-        .args(["show", &spec])
+        .args(args)
         .output()
-        .with_context(|| format!("执行 git show 失败：{spec}"))?;
+        .context("执行 git 命令失败")?;
 
-    if !output.status.success() {
-        return Err(anyhow!(
-            "git show 返回非零（{}）：{}",
-            output.status.code().unwrap_or(-1),
-            String::from_utf8_lossy(&output.stderr).trim()
-        ));
+    if output.status.success() {
+        return Ok(());
     }
 
-    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    Err(anyhow!(
+        "git 命令返回非零（{}）：{}",
+        output.status.code().unwrap_or(-1),
+        String::from_utf8_lossy(&output.stderr).trim()
+    ))
 }
 
-fn git_show(repo_root: &Path, spec: &str) -> Result<String> {
+fn run_git_output<I, S>(repo_root: &Path, args: I) -> Result<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<std::ffi::OsStr>,
+{
     let output = Command::new("git")
         .arg("-C")
         .arg(repo_root)
-        .args(["show", spec])
+        .args(args)
         .output()
-        .with_context(|| format!("执行 git show 失败：{spec}"))?;
+        .context("执行 git 命令失败")?;
 
-    if !output.status.success() {
-        return Err(anyhow!(
-            "git show 返回非零（{}）：{}",
-            output.status.code().unwrap_or(-1),
-            String::from_utf8_lossy(&output.stderr).trim()
-        ));
+    if output.status.success() {
+        return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
     }
 
-    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    Err(anyhow!(
+        "git 命令返回非零（{}）：{}",
+        output.status.code().unwrap_or(-1),
+        String::from_utf8_lossy(&output.stderr).trim()
+    ))
 }
 
-fn read_specified_file(repo_root: &Path, path: &str, spec: &str) -> Result<String> {
-    let spec = spec.trim();
-    if spec.is_empty() {
-        return read_working_file(repo_root, path);
+const ROLLBACK_TRASH_DIR: &str = "git-viewer/trash";
+
+/// 配置目录下的面板尺寸文件；找不到配置目录时只在本次运行中记住。
+fn panel_size_storage() -> Rc<dyn PanelSizeStorage> {
+    match manos_config::app_dir("git-viewer") {
+        Some(dir) => Rc::new(FilePanelSizeStorage::new(dir.join("panel-sizes"))),
+        None => Rc::new(MemoryPanelSizeStorage::default()),
     }
+}
 
-    if spec.eq_ignore_ascii_case("WORKTREE") {
-        return read_working_file(repo_root, path);
+/// 把工作区文件复制到 `<git-dir>/git-viewer/trash/<时间戳>/<path>`；文件不存在时返回 `None`。
+fn backup_worktree_file(repo_root: &Path, path: &str) -> Result<Option<RollbackBackup>> {
+    let source = repo_root.join(path);
+    if !source.is_file() {
+        return Ok(None);
     }
 
-    if spec.eq_ignore_ascii_case("INDEX") || spec == ":" {
-        return git_show(repo_root, &format!(":{path}"));
+    let backup = rollback_backup_path(repo_root, path)?;
+    std::fs::copy(&source, &backup)
+        .with_context(|| format!("备份文件失败：{}", backup.display()))?;
+
+    Ok(Some(RollbackBackup {
+        path: path.to_string(),
+        backup,
+    }))
+}
+
+/// 按原编码改写工作区文件的换行符，返回改写前的备份。
+fn rewrite_line_endings(
+    repo_root: &Path,
+    path: &str,
+    crlf: bool,
+) -> Result<Option<RollbackBackup>> {
+    let full_path = repo_root.join(path);
+    let bytes = std::fs::read(&full_path)
+        .with_context(|| format!("读取文件失败：{}", full_path.display()))?;
+    let (text, encoding) = encoding::decode(&bytes);
+
+    let lf_text = text.replace("\r\n", "\n");
+    let normalized = if crlf {
+        lf_text.replace('\n', "\r\n")
+    } else {
+        lf_text
+    };
+    if normalized == text {
+        return Ok(None);
     }
 
-    if spec.contains(':') {
-        return git_show(repo_root, spec);
+    let encoded = encoding.encode(&normalized)?;
+    let backup = backup_worktree_file(repo_root, path)?;
+    std::fs::write(&full_path, encoded)
+        .with_context(|| format!("写入文件失败：{}", full_path.display()))?;
+    Ok(backup)
+}
+
+/// 把未跟踪目录整个移到 `<git-dir>/git-viewer/trash/<时间戳>/<dir>`。
+fn move_untracked_dir_to_trash(repo_root: &Path, dir: &str) -> Result<RollbackBackup> {
+    let source = repo_root.join(dir.trim_end_matches('/'));
+    if !source.is_dir() {
+        return Err(anyhow!("目录不存在：{dir}"));
     }
 
-    git_show(repo_root, &format!("{spec}:{path}"))
+    let backup = rollback_backup_path(repo_root, dir.trim_end_matches('/'))?;
+    std::fs::rename(&source, &backup)
+        .with_context(|| format!("移动目录失败：{}", backup.display()))?;
+
+    Ok(RollbackBackup {
+        path: dir.to_string(),
+        backup,
+    })
 }
 
-fn run_git<I, S>(repo_root: &Path, args: I) -> Result<()>
+/// `path` 在本次回收目录中的位置，并创建好父目录。
+fn rollback_backup_path(repo_root: &Path, path: &str) -> Result<PathBuf> {
+    let trash_dir = PathBuf::from(
+        run_git_output(repo_root, ["rev-parse", "--git-path", ROLLBACK_TRASH_DIR])?.trim(),
+    );
+    let trash_dir = if trash_dir.is_relative() {
+        repo_root.join(trash_dir)
+    } else {
+        trash_dir
+    };
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default();
+    let backup = trash_dir.join(timestamp.to_string()).join(path);
+    if let Some(parent) = backup.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("创建备份目录失败：{}", parent.display()))?;
+    }
+    Ok(backup)
+}
+
+/// 未跟踪目录中未被忽略的文件，路径相对仓库根目录。
+fn fetch_untracked_dir_files(repo_root: &Path, dir: &str) -> Result<Vec<String>> {
+    let output = run_git_output(
+        repo_root,
+        [
+            "ls-files",
+            "--others",
+            "--exclude-standard",
+            "-z",
+            "--",
+            dir,
+        ],
+    )?;
+    Ok(output
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+fn run_git_with_stdin<I, S>(repo_root: &Path, args: I, stdin: &str) -> Result<()>
 where
     I: IntoIterator<Item = S>,
     S: AsRef<std::ffi::OsStr>,
 {
-    let output = Command::new("git")
+    use std::io::Write as _;
+    use std::process::Stdio;
+
+    let mut child = Command::new("git")
         .arg("-C")
         .arg(repo_root)
         .args(args)
-        .output()
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
         .context("执行 git 命令失败")?;
 
+    if let Some(mut input) = child.stdin.take() {
+        input
+            .write_all(stdin.as_bytes())
+            .context("写入 git stdin 失败")?;
+    }
+
+    let output = child.wait_with_output().context("等待 git 进程失败")?;
     if output.status.success() {
         return Ok(());
     }
@@ -6386,57 +15787,665 @@ where
     ))
 }
 
-fn run_git_with_stdin<I, S>(repo_root: &Path, args: I, stdin: &str) -> Result<()>
-where
-    I: IntoIterator<Item = S>,
-    S: AsRef<std::ffi::OsStr>,
-{
+fn build_patch_previews(repo_root: &Path, patch: &str) -> Vec<PatchFilePreview> {
+    diffview::parse_patch(patch)
+        .into_iter()
+        .map(|file| {
+            let old_text = match file.old_path.as_deref() {
+                Some(path) if !file.binary => {
+                    read_working_file(repo_root, path).unwrap_or_default()
+                }
+                _ => String::new(),
+            };
+            let applied = file.apply(&old_text);
+            let new_text = if file.is_deleted_file() {
+                String::new()
+            } else {
+                applied.text
+            };
+
+            PatchFilePreview {
+                path: file.path().to_string(),
+                old_text,
+                new_text,
+                hunk_headers: file.hunks.iter().map(|hunk| hunk.header().into()).collect(),
+                added: file.hunks.iter().map(|hunk| hunk.added_count()).sum(),
+                removed: file.hunks.iter().map(|hunk| hunk.removed_count()).sum(),
+                binary: file.binary,
+                unmatched_hunks: applied.rejected,
+            }
+        })
+        .collect()
+}
+
+fn run_git_apply_patch(
+    repo_root: &Path,
+    target: PatchApplyTarget,
+    patch: &str,
+) -> Result<PatchApplyOutcome> {
     use std::io::Write as _;
     use std::process::Stdio;
 
     let mut child = Command::new("git")
         .arg("-C")
         .arg(repo_root)
-        .args(args)
+        .args(target.git_args())
         .stdin(Stdio::piped())
-        .stdout(Stdio::null())
+        .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .spawn()
-        .context("执行 git 命令失败")?;
+        .context("执行 git apply 失败")?;
 
     if let Some(mut input) = child.stdin.take() {
         input
-            .write_all(stdin.as_bytes())
+            .write_all(patch.as_bytes())
+            .context("写入 git stdin 失败")?;
+    }
+
+    let output = child.wait_with_output().context("等待 git 进程失败")?;
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    Ok(PatchApplyOutcome {
+        target,
+        success: output.status.success(),
+        rejected: parse_apply_rejects(&stderr),
+        output: stderr.trim().to_string(),
+    })
+}
+
+/// 解析 `git apply --verbose --reject` 输出中的 “Applying patch X with N rejects...” 与
+/// “Rejected hunk #N.” 行。
+fn parse_apply_rejects(output: &str) -> Vec<(String, Vec<usize>)> {
+    let mut rejected: Vec<(String, Vec<usize>)> = Vec::new();
+    let mut current: Option<String> = None;
+
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("Applying patch ") {
+            current = rest.split(" with ").next().map(str::to_string);
+        } else if line.starts_with("Applied patch ") {
+            current = None;
+        } else if let Some(rest) = line.strip_prefix("Rejected hunk #") {
+            let (Some(path), Ok(hunk)) = (current.as_ref(), rest.trim_end_matches('.').parse())
+            else {
+                continue;
+            };
+            match rejected.last_mut() {
+                Some((last, hunks)) if last == path => hunks.push(hunk),
+                _ => rejected.push((path.clone(), vec![hunk])),
+            }
+        }
+    }
+    rejected
+}
+
+fn run_git_commit(
+    repo_root: &Path,
+    message: &str,
+    no_verify: bool,
+    amend: bool,
+) -> Result<CommitOutcome> {
+    use std::io::Write as _;
+    use std::process::Stdio;
+
+    let mut command = Command::new("git");
+    command
+        .arg("-C")
+        .arg(repo_root)
+        .args(["commit", "--file=-"]);
+    if no_verify {
+        command.arg("--no-verify");
+    }
+    if amend {
+        command.arg("--amend");
+    }
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("执行 git commit 失败")?;
+
+    if let Some(mut input) = child.stdin.take() {
+        input
+            .write_all(message.as_bytes())
             .context("写入 git stdin 失败")?;
     }
 
     let output = child.wait_with_output().context("等待 git 进程失败")?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    if output.status.success() {
+        let summary = stdout.lines().next().unwrap_or_default().trim().to_string();
+        return Ok(CommitOutcome::Committed { summary });
+    }
+
+    // hook 的输出既可能走 stdout 也可能走 stderr，这里原样拼接，避免丢信息。
+    let mut combined = String::new();
+    for part in [stdout.trim_end(), stderr.trim_end()] {
+        if part.is_empty() {
+            continue;
+        }
+        if !combined.is_empty() {
+            combined.push('\n');
+        }
+        combined.push_str(part);
+    }
+
+    Ok(CommitOutcome::Rejected(CommitFailure {
+        exit_code: output.status.code().unwrap_or(-1),
+        output: combined,
+        hooks: detect_commit_hooks(repo_root),
+    }))
+}
+
+/// 返回当前仓库中会被 `--no-verify` 跳过的、已启用的提交 hook。
+///
+/// `git rev-parse --git-path hooks` 会遵循 `core.hooksPath`，也能正确处理 worktree。
+fn detect_commit_hooks(repo_root: &Path) -> Vec<String> {
+    let Ok(output) = Command::new("git")
+        .arg("-C")
+        .arg(repo_root)
+        .args(["rev-parse", "--git-path", "hooks"])
+        .output()
+    else {
+        return Vec::new();
+    };
+    if !output.status.success() {
+        return Vec::new();
+    }
+
+    let hooks_dir = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if hooks_dir.is_empty() {
+        return Vec::new();
+    }
+    let mut hooks_dir = PathBuf::from(hooks_dir);
+    if hooks_dir.is_relative() {
+        hooks_dir = repo_root.join(hooks_dir);
+    }
+
+    ["pre-commit", "commit-msg"]
+        .into_iter()
+        .filter(|name| is_executable_file(&hooks_dir.join(name)))
+        .map(str::to_string)
+        .collect()
+}
+
+fn is_executable_file(path: &Path) -> bool {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return false;
+    };
+    if !metadata.is_file() {
+        return false;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+        metadata.permissions().mode() & 0o111 != 0
+    }
+    #[cfg(not(unix))]
+    {
+        true
+    }
+}
+
+fn fetch_file_history(repo_root: &Path, path: &str, limit: usize) -> Result<Vec<CommitEntry>> {
+    git::backend().file_log(repo_root, path, limit)
+}
+
+fn fetch_branch_comparison(repo_root: &Path, base: &str, head: &str) -> Result<BranchComparison> {
+    // 没有共同祖先时退化为直接对比两个 ref。
+    let merge_base = run_git_output(repo_root, ["merge-base", base, head])
+        .ok()
+        .map(|output| output.trim().to_string())
+        .filter(|hash| !hash.is_empty())
+        .unwrap_or_else(|| base.to_string());
+
+    let ahead = fetch_compare_commits(repo_root, &format!("{base}..{head}"))?;
+    let behind = fetch_compare_commits(repo_root, &format!("{head}..{base}"))?;
+
+    let files = fetch_changed_files(repo_root, &merge_base, head)?;
+
+    Ok(BranchComparison {
+        base: base.to_string(),
+        head: head.to_string(),
+        merge_base,
+        ahead,
+        behind,
+        files,
+    })
+}
+
+/// 识别 GitHub / GitLab remote 并列出打开的变更请求；不是托管仓库时返回 `(None, [])`。
+fn fetch_pull_requests(
+    repo_root: &Path,
+    token: Option<String>,
+) -> Result<(Option<forge::ForgeRemote>, Vec<forge::PullRequest>)> {
+    let Some(remote) = forge::ForgeRemote::detect(repo_root)? else {
+        return Ok((None, Vec::new()));
+    };
+    let token = token.or_else(|| remote.token());
+    let requests = forge::list_pull_requests(&remote, token.as_deref())?;
+    Ok((Some(remote), requests))
+}
+
+/// `left` 与 `right` 之间变更的文件及行数统计（不识别重命名）。
+fn fetch_changed_files(repo_root: &Path, left: &str, right: &str) -> Result<Vec<ChangedFile>> {
+    let name_status = run_git_output(
+        repo_root,
+        ["diff", "--no-renames", "--name-status", left, right, "--"],
+    )?;
+    let numstat = run_git_output(
+        repo_root,
+        ["diff", "--no-renames", "--numstat", left, right, "--"],
+    )?;
+
+    let mut stats = std::collections::HashMap::new();
+    for line in numstat.lines() {
+        let mut fields = line.splitn(3, '\t');
+        let (Some(added), Some(removed), Some(path)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        if let (Ok(added), Ok(removed)) = (added.parse::<usize>(), removed.parse::<usize>()) {
+            stats.insert(path.to_string(), (added, removed));
+        }
+    }
+
+    Ok(name_status
+        .lines()
+        .filter_map(|line| {
+            let (status, path) = line.split_once('\t')?;
+            Some(ChangedFile {
+                status: status.trim().to_string(),
+                path: path.to_string(),
+                stat: stats.get(path).copied(),
+            })
+        })
+        .collect())
+}
+
+fn fetch_commit_details(repo_root: &Path, hash: &str) -> Result<CommitDetails> {
+    let output = run_git_output(
+        repo_root,
+        [
+            "show",
+            "-s",
+            "--date=iso",
+            "--format=%H%x1f%P%x1f%an <%ae>%x1f%ad%x1f%cn <%ce>%x1f%cd%x1f%N%x1f%B",
+            hash,
+            "--",
+        ],
+    )?;
+
+    let mut fields = output.splitn(8, '\x1f');
+    let mut next_field = || fields.next().unwrap_or_default().trim().to_string();
+    let hash = next_field();
+    let parents: Vec<String> = next_field()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect();
+    let author = next_field();
+    let author_date = next_field();
+    let committer = next_field();
+    let committer_date = next_field();
+    let note = next_field();
+    let message = next_field();
+    if hash.is_empty() {
+        return Err(anyhow!("无法解析 commit 信息"));
+    }
+
+    let left = parents
+        .first()
+        .cloned()
+        .unwrap_or_else(|| EMPTY_TREE_HASH.to_string());
+    let files = fetch_changed_files(repo_root, &left, &hash)?;
+
+    Ok(CommitDetails {
+        hash,
+        parents,
+        author,
+        author_date,
+        committer,
+        committer_date,
+        message,
+        note: (!note.is_empty()).then_some(note),
+        files,
+    })
+}
+
+/// 写入或覆盖提交的备注；`note` 为空时删除备注。
+fn write_commit_note(repo_root: &Path, hash: &str, note: &str) -> Result<()> {
+    if note.is_empty() {
+        return run_git(repo_root, ["notes", "remove", "--ignore-missing", hash]);
+    }
+    run_git_with_stdin(repo_root, ["notes", "add", "-f", "-F", "-", hash], note)
+}
+
+fn fetch_compare_commits(repo_root: &Path, range: &str) -> Result<Vec<CompareCommit>> {
+    let output = run_git_output(
+        repo_root,
+        [
+            "log",
+            "-n",
+            &BRANCH_COMPARE_COMMIT_LIMIT.to_string(),
+            "--date=short",
+            "--format=%H%x1f%h%x1f%an%x1f%ad%x1f%s%x1e",
+            range,
+            "--",
+        ],
+    )?;
+
+    Ok(output
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_start_matches('\n').split('\x1f');
+            let hash = fields.next()?.trim();
+            if hash.is_empty() {
+                return None;
+            }
+            Some(CompareCommit {
+                hash: hash.to_string(),
+                short_hash: fields.next().unwrap_or_default().to_string(),
+                author: fields.next().unwrap_or_default().to_string(),
+                date: fields.next().unwrap_or_default().to_string(),
+                subject: fields.next().unwrap_or_default().trim().to_string(),
+            })
+        })
+        .collect())
+}
+
+fn fetch_commit_summary(repo_root: &Path, rev: &str) -> Result<CompareCommit> {
+    let output = run_git_output(
+        repo_root,
+        [
+            "log",
+            "-1",
+            "--date=short",
+            "--format=%H%x1f%h%x1f%an%x1f%ad%x1f%s",
+            rev,
+            "--",
+        ],
+    )?;
+    let mut fields = output.trim_end().split('\x1f');
+    let hash = fields.next().unwrap_or_default().trim();
+    if hash.is_empty() {
+        return Err(anyhow!("找不到提交：{rev}"));
+    }
+    Ok(CompareCommit {
+        hash: hash.to_string(),
+        short_hash: fields.next().unwrap_or_default().to_string(),
+        author: fields.next().unwrap_or_default().to_string(),
+        date: fields.next().unwrap_or_default().to_string(),
+        subject: fields.next().unwrap_or_default().trim().to_string(),
+    })
+}
+
+fn fetch_bisect_progress(repo_root: &Path) -> Result<BisectProgress> {
+    let start_file = run_git_output(repo_root, ["rev-parse", "--git-path", "BISECT_START"])?;
+    if !repo_root.join(start_file.trim()).exists() {
+        return Ok(BisectProgress::default());
+    }
+
+    let refs = git::backend().refs(repo_root, "refs/bisect/")?;
+    let bisect_refs = |prefix: &str| -> Vec<String> {
+        refs.iter()
+            .filter(|entry| entry.name.starts_with(prefix))
+            .map(|entry| entry.id.clone())
+            .collect()
+    };
+    let bad = bisect_refs("refs/bisect/bad").into_iter().next();
+    let goods = bisect_refs("refs/bisect/good-");
+    let skip_count = bisect_refs("refs/bisect/skip-").len();
+    let current = fetch_commit_summary(repo_root, "HEAD").ok();
+
+    let mut progress = BisectProgress {
+        active: true,
+        current,
+        bad: bad.clone(),
+        good_count: goods.len(),
+        skip_count,
+        ..BisectProgress::default()
+    };
+    let Some(bad) = bad.filter(|_| !goods.is_empty()) else {
+        return Ok(progress);
+    };
+
+    // 与 `git bisect` 自己的提示一致：bisect_nr 是测完当前提交后剩余的数量，bisect_steps 是预计步数。
+    let mut args = vec![
+        "rev-list".to_string(),
+        "--bisect-vars".to_string(),
+        bad.clone(),
+    ];
+    args.push("--not".to_string());
+    args.extend(goods);
+    let vars = run_git_output(repo_root, &args)?;
+    let var = |name: &str| {
+        vars.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+            .and_then(|value| value.trim().trim_matches('\'').parse::<usize>().ok())
+    };
+    let candidates = var("bisect_all");
+    progress.candidates = candidates;
+    progress.remaining = var("bisect_nr").zip(var("bisect_steps"));
+    if candidates == Some(1) {
+        progress.culprit = fetch_commit_summary(repo_root, &bad).ok();
+    }
+
+    Ok(progress)
+}
+
+/// 读取 `base` 与 HEAD 的合并基以及其后的提交（从旧到新）。栈中不允许合并提交。
+fn fetch_commit_stack(repo_root: &Path, base: &str) -> Result<(String, Vec<StackCommit>)> {
+    let merge_base = run_git_output(repo_root, ["merge-base", base, "HEAD"])?
+        .trim()
+        .to_string();
+    let range = format!("{merge_base}..HEAD");
+    if !run_git_output(repo_root, ["rev-list", "--merges", range.as_str()])?
+        .trim()
+        .is_empty()
+    {
+        return Err(anyhow!("{base}..HEAD 中包含合并提交，无法按线性提交栈编辑"));
+    }
+
+    let output = run_git_output(
+        repo_root,
+        [
+            "log",
+            "--reverse",
+            "--format=%H%x1f%h%x1f%s%x1f%B%x1e",
+            range.as_str(),
+            "--",
+        ],
+    )?;
+    let commits = output
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_start_matches('\n').splitn(4, '\x1f');
+            let hash = fields.next()?.trim();
+            if hash.is_empty() {
+                return None;
+            }
+            Some(StackCommit {
+                hash: hash.to_string(),
+                short_hash: fields.next().unwrap_or_default().to_string(),
+                subject: fields.next().unwrap_or_default().to_string(),
+                message: fields.next().unwrap_or_default().trim_end().to_string(),
+                action: StackAction::Pick,
+                edited_message: None,
+            })
+        })
+        .collect();
+
+    Ok((merge_base, commits))
+}
+
+/// 把提交栈转换成 `git rebase -i` 的 todo。
+///
+/// squash 用 `fixup` 合并到上一个保留的提交，合并后的提交信息（以及改写过的信息）
+/// 通过 `exec git commit --amend -F` 写回；第 `n` 条信息的文件路径由 `message_path(n)` 给出。
+/// 返回 todo 文本和需要写入的提交信息。
+fn commit_stack_todo(
+    commits: &[StackCommit],
+    message_path: impl Fn(usize) -> String,
+) -> (String, Vec<String>) {
+    let mut todo = String::new();
+    let mut messages = Vec::new();
+    // 当前分组：(合并后的提交信息, 是否需要改写)。
+    let mut group: Option<(String, bool)> = None;
+
+    let mut flush = |group: &mut Option<(String, bool)>, todo: &mut String| {
+        if let Some((message, true)) = group.take() {
+            todo.push_str(&format!(
+                "exec git commit --amend --no-verify --quiet -F {}\n",
+                shell_single_quote(&message_path(messages.len()))
+            ));
+            messages.push(message);
+        }
+    };
+
+    for commit in commits {
+        let message = commit.edited_message.as_deref().unwrap_or(&commit.message);
+        match commit.action {
+            StackAction::Drop => todo.push_str(&format!("drop {}\n", commit.hash)),
+            StackAction::Pick => {
+                flush(&mut group, &mut todo);
+                todo.push_str(&format!("pick {}\n", commit.hash));
+                group = Some((message.to_string(), commit.edited_message.is_some()));
+            }
+            StackAction::Squash => {
+                todo.push_str(&format!("fixup {}\n", commit.hash));
+                if let Some((combined, rewrite)) = group.as_mut() {
+                    combined.push_str("\n\n");
+                    combined.push_str(message);
+                    *rewrite = true;
+                }
+            }
+        }
+    }
+    flush(&mut group, &mut todo);
+
+    (todo, messages)
+}
+
+fn shell_single_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// 把提交栈写成 todo，用 `sequence.editor` 替换 git 生成的 todo 后执行 `git rebase -i`。
+fn run_commit_stack_rebase(repo_root: &Path, base: &str, commits: &[StackCommit]) -> Result<()> {
+    let stack_dir = PathBuf::from(
+        run_git_output(repo_root, ["rev-parse", "--git-path", COMMIT_STACK_DIR])?.trim(),
+    );
+    let stack_dir = if stack_dir.is_relative() {
+        repo_root.join(stack_dir)
+    } else {
+        stack_dir
+    };
+    if stack_dir.exists() {
+        std::fs::remove_dir_all(&stack_dir)
+            .with_context(|| format!("清理目录失败：{}", stack_dir.display()))?;
+    }
+    std::fs::create_dir_all(&stack_dir)
+        .with_context(|| format!("创建目录失败：{}", stack_dir.display()))?;
+
+    let message_path = |index: usize| {
+        stack_dir
+            .join(format!("message-{index}.txt"))
+            .to_string_lossy()
+            .into_owned()
+    };
+    let (todo, messages) = commit_stack_todo(commits, message_path);
+    for (index, message) in messages.iter().enumerate() {
+        let path = message_path(index);
+        std::fs::write(&path, format!("{message}\n"))
+            .with_context(|| format!("写入提交信息失败：{path}"))?;
+    }
+    let todo_path = stack_dir.join("todo");
+    std::fs::write(&todo_path, todo)
+        .with_context(|| format!("写入 rebase todo 失败：{}", todo_path.display()))?;
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_root)
+        .arg("-c")
+        .arg(format!(
+            "sequence.editor=cp {}",
+            shell_single_quote(&todo_path.to_string_lossy())
+        ))
+        .args(["rebase", "-i", "--no-autosquash", base])
+        .env("GIT_EDITOR", "true")
+        .output()
+        .context("执行 git rebase 失败")?;
+
     if output.status.success() {
         return Ok(());
     }
 
     Err(anyhow!(
-        "git 命令返回非零（{}）：{}",
+        "git rebase 返回非零（{}）：{}",
         output.status.code().unwrap_or(-1),
         String::from_utf8_lossy(&output.stderr).trim()
     ))
 }
 
-fn fetch_file_history(repo_root: &Path, path: &str, limit: usize) -> Result<Vec<CommitEntry>> {
+fn fetch_last_commit_message(repo_root: &Path) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_root)
+        .args(["log", "-1", "--format=%B", "HEAD"])
+        .output()
+        .context("执行 git log 失败")?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git log 返回非零（{}）：{}",
+            output.status.code().unwrap_or(-1),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim_end()
+        .to_string())
+}
+
+fn fetch_trailer_suggestions(repo_root: &Path) -> Result<TrailerSuggestions> {
+    let git_output = |args: &[&str]| {
+        Command::new("git")
+            .arg("-C")
+            .arg(repo_root)
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    let name = git_output(&["config", "user.name"]);
+    let email = git_output(&["config", "user.email"]);
+    let identity = match (name, email) {
+        (Some(name), Some(email)) => Some(format!("{name} <{email}>")),
+        _ => None,
+    };
+    let issue = git_output(&["symbolic-ref", "--short", "-q", "HEAD"])
+        .and_then(|branch| issue_number_from_branch(&branch));
+
+    // 作者与已有的 Co-authored-by 都算协作者；新提交排在前面，出现次数相同时越新越靠前。
     let output = Command::new("git")
         .arg("-C")
         .arg(repo_root)
         .args([
             "log",
             "-n",
-            &limit.to_string(),
-            "--format=%H%x1f%h%x1f%s%x1e",
-            "--",
-            path,
+            "500",
+            "--format=%aN <%aE>%n%(trailers:key=Co-authored-by,valueonly,unfold)",
         ])
         .output()
         .context("执行 git log 失败")?;
-
     if !output.status.success() {
         return Err(anyhow!(
             "git log 返回非零（{}）：{}",
@@ -6445,102 +16454,265 @@ fn fetch_file_history(repo_root: &Path, path: &str, limit: usize) -> Result<Vec<
         ));
     }
 
-    let mut commits = Vec::new();
-    for record in output.stdout.split(|b| *b == 0x1e) {
-        if record.is_empty() {
-            continue;
-        }
-
-        let mut fields = record.split(|b| *b == 0x1f);
-        let Some(hash) = fields.next() else { continue };
-        let Some(short_hash) = fields.next() else {
+    let own_email = identity
+        .as_deref()
+        .and_then(trailer_email)
+        .map(|email| email.to_lowercase());
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let person = line.trim();
+        let Some(email) = trailer_email(person) else {
             continue;
         };
-        let Some(subject) = fields.next() else {
+        let email = email.to_lowercase();
+        if own_email.as_deref() == Some(email.as_str()) {
             continue;
-        };
+        }
+        let known = counts.iter_mut().find(|(known, _)| {
+            trailer_email(known).is_some_and(|known| known.eq_ignore_ascii_case(&email))
+        });
+        match known {
+            Some((_, count)) => *count += 1,
+            None => counts.push((person.to_string(), 1)),
+        }
+    }
+    // 稳定排序，保留首次出现（即最近）的顺序。
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    let collaborators = counts
+        .into_iter()
+        .take(RECENT_COLLABORATOR_LIMIT)
+        .map(|(person, _)| person)
+        .collect();
+
+    Ok(TrailerSuggestions {
+        identity,
+        collaborators,
+        issue,
+    })
+}
+
+/// `Name <email>` 中的 email。
+fn trailer_email(person: &str) -> Option<&str> {
+    let start = person.rfind('<')?;
+    let end = person[start..].find('>')? + start;
+    let email = person[start + 1..end].trim();
+    (!email.is_empty()).then_some(email)
+}
 
-        let hash = String::from_utf8_lossy(hash).trim().to_string();
-        if hash.is_empty() {
+/// 从分支名中找 issue 编号，例如 `feature/123-login`、`fix_45`、`GH-7`。
+/// 数字前后必须是分隔符或首尾，避免把 `release/2.0` 这类版本号当成 issue。
+fn issue_number_from_branch(branch: &str) -> Option<String> {
+    let is_separator = |ch: char| matches!(ch, '/' | '-' | '_' | '#');
+    let chars: Vec<char> = branch.chars().collect();
+    let mut index = 0;
+    while index < chars.len() {
+        if !chars[index].is_ascii_digit() {
+            index += 1;
             continue;
         }
+        let start = index;
+        while index < chars.len() && chars[index].is_ascii_digit() {
+            index += 1;
+        }
+        let before_ok = start == 0 || is_separator(chars[start - 1]);
+        let after_ok = index == chars.len() || is_separator(chars[index]);
+        if before_ok && after_ok {
+            return Some(chars[start..index].iter().collect());
+        }
+    }
+    None
+}
 
-        commits.push(CommitEntry {
-            hash,
-            short_hash: String::from_utf8_lossy(short_hash).trim().to_string(),
-            subject: String::from_utf8_lossy(subject).trim().to_string(),
-        });
+/// 形如 `Key: value` 的 git trailer 行。
+fn is_trailer_line(line: &str) -> bool {
+    let Some((key, value)) = line.split_once(": ") else {
+        return false;
+    };
+    !key.is_empty()
+        && !value.trim().is_empty()
+        && key
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-')
+}
+
+/// 把 `trailer` 追加到提交信息末尾：已有 trailer 段时接在其后，否则空一行另起一段。
+/// 相同的 trailer 已存在时原样返回。
+fn append_trailer(message: &str, trailer: &str) -> String {
+    let message = message.trim_end();
+    if message.lines().any(|line| line.trim() == trailer) {
+        return message.to_string();
+    }
+    if message.is_empty() {
+        // 留出第一行给标题。
+        return format!("\n\n{trailer}");
     }
 
-    Ok(commits)
+    // 只有标题一段时，标题本身不算 trailer 段。
+    let last_paragraph = message.rsplit("\n\n").next().unwrap_or_default();
+    let has_body = message.contains("\n\n");
+    let ends_with_trailers = has_body
+        && last_paragraph
+            .lines()
+            .all(|line| is_trailer_line(line.trim()));
+
+    if ends_with_trailers {
+        format!("{message}\n{trailer}")
+    } else {
+        format!("{message}\n\n{trailer}")
+    }
 }
 
-fn fetch_git_status(repo_root: &Path) -> Result<Vec<FileEntry>> {
+/// 暂存区相对 HEAD（amend 时相对 HEAD^）的完整 patch，包含二进制变更。
+fn fetch_staged_patch(repo_root: &Path, amend: bool) -> Result<String> {
+    let mut command = Command::new("git");
+    command
+        .arg("-C")
+        .arg(repo_root)
+        .args(["diff", "--cached", "--binary"]);
+    if amend {
+        command.arg("HEAD^");
+    }
+    let output = command.output().context("执行 git diff 失败")?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git diff 返回非零（{}）：{}",
+            output.status.code().unwrap_or(-1),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn fetch_last_commit_files(repo_root: &Path) -> Result<Vec<FileEntry>> {
     let output = Command::new("git")
         .arg("-C")
         .arg(repo_root)
-        .args(["status", "--porcelain=v2", "-z"])
+        .args([
+            "diff-tree",
+            "--root",
+            "--no-commit-id",
+            "-r",
+            "--name-status",
+            "-z",
+            "HEAD",
+        ])
         .output()
-        .context("执行 git status 失败")?;
+        .context("执行 git diff-tree 失败")?;
 
     if !output.status.success() {
         return Err(anyhow!(
-            "git status 返回非零: {}",
-            output.status.code().unwrap_or(-1)
+            "git diff-tree 返回非零（{}）：{}",
+            output.status.code().unwrap_or(-1),
+            String::from_utf8_lossy(&output.stderr).trim()
         ));
     }
 
-    let mut entries = Vec::new();
-    let mut segments = output.stdout.split(|b| *b == b'\0').peekable();
-    while let Some(record) = segments.next() {
-        if record.is_empty() {
-            continue;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut fields = text.split('\0').filter(|field| !field.is_empty());
+    let mut files = Vec::new();
+    while let Some(status) = fields.next() {
+        let Some(mut path) = fields.next() else {
+            break;
+        };
+        // 重命名/复制记录带两个路径，展示新路径。
+        if status.starts_with('R') || status.starts_with('C') {
+            path = fields.next().unwrap_or(path);
         }
+        files.push(FileEntry {
+            path: path.to_string(),
+            status: status.chars().take(1).collect(),
+        });
+    }
 
-        let record = String::from_utf8_lossy(record);
-        if record.starts_with("1 ") {
-            if let Some(entry) = parse_type_1_record(&record) {
-                entries.push(entry);
-            }
-            continue;
-        }
+    Ok(files)
+}
 
-        if record.starts_with("2 ") {
-            if let Some(entry) = parse_type_2_record(&record) {
-                entries.push(entry);
-            }
-            _ = segments.next(); // orig_path
-            continue;
-        }
+fn fetch_reflog(repo_root: &Path, limit: usize) -> Result<Vec<ReflogEntry>> {
+    git::backend().reflog(repo_root, limit)
+}
 
-        if record.starts_with("u ") {
-            if let Some(entry) = parse_unmerged_record(&record) {
-                entries.push(entry);
-            }
-            continue;
-        }
+fn fetch_git_status(repo_root: &Path) -> Result<Vec<FileEntry>> {
+    git::backend().status(repo_root)
+}
 
-        if record.starts_with("? ") {
-            if let Some(path) = record.strip_prefix("? ") {
-                entries.push(FileEntry {
-                    path: path.to_string(),
-                    status: "??".to_string(),
-                });
-            }
-            continue;
+/// 直接读 HEAD 文件取当前分支，不启动 git 进程，状态刷新时可以顺带调用。
+/// HEAD 分离时返回短提交号。
+fn read_head_branch(repo_root: &Path) -> Option<String> {
+    let dot_git = repo_root.join(".git");
+    // 工作树与子模块的 `.git` 是一个写着 `gitdir: <路径>` 的文件。
+    let git_dir = if dot_git.is_file() {
+        let content = std::fs::read_to_string(&dot_git).ok()?;
+        let dir = PathBuf::from(content.trim().strip_prefix("gitdir:")?.trim());
+        if dir.is_absolute() {
+            dir
+        } else {
+            repo_root.join(dir)
         }
+    } else {
+        dot_git
+    };
 
-        if record.starts_with("! ") {
-            if let Some(path) = record.strip_prefix("! ") {
-                entries.push(FileEntry {
-                    path: path.to_string(),
-                    status: "!!".to_string(),
-                });
-            }
+    let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let head = head.trim();
+    match head.strip_prefix("ref:") {
+        Some(reference) => {
+            let reference = reference.trim();
+            Some(
+                reference
+                    .strip_prefix("refs/heads/")
+                    .unwrap_or(reference)
+                    .to_string(),
+            )
         }
+        None => Some(format!("({})", head.get(..7)?)),
+    }
+}
+
+const TIMING_ENV: &str = "GIT_VIEWER_TIMING";
+
+/// 设置了 `GIT_VIEWER_TIMING` 时，状态栏直接显示各阶段耗时，每次 diff 也写一条日志。
+fn diff_timing_enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| {
+        std::env::var(TIMING_ENV).is_ok_and(|value| !matches!(value.trim(), "" | "0" | "false"))
+    })
+}
+
+/// 一次 diff 的总耗时与 diffview 记录的各阶段耗时；总耗时还包括读入文档。
+#[derive(Clone, Copy, Debug, Default)]
+struct DiffTiming {
+    total: Duration,
+    phases: diffview::DiffTimings,
+}
+
+impl DiffTiming {
+    /// 形如 `拆行 2 ms · Myers 5 ms · 行内 1 ms · hunk <1 ms · 其他 1 ms`。
+    fn breakdown(&self) -> String {
+        let phases = &self.phases;
+        [
+            ("拆行", phases.line_split),
+            ("Myers", phases.myers),
+            ("行内", phases.intraline),
+            ("hunk", phases.hunk_building),
+            ("其他", self.total.saturating_sub(phases.total())),
+        ]
+        .into_iter()
+        .map(|(name, duration)| format!("{name} {}", format_diff_duration(duration)))
+        .collect::<Vec<_>>()
+        .join(" · ")
     }
+}
 
-    Ok(entries)
+fn format_diff_duration(duration: Duration) -> String {
+    if duration < Duration::from_millis(1) {
+        "<1 ms".to_string()
+    } else if duration < Duration::from_secs(1) {
+        format!("{} ms", duration.as_millis())
+    } else {
+        format!("{:.1} s", duration.as_secs_f64())
+    }
 }
 
 fn detect_repo_root(start_dir: &Path) -> PathBuf {
@@ -6602,33 +16774,73 @@ struct StatusCounts {
 }
 
 impl StatusCounts {
-    fn from_entries(entries: &[FileEntry]) -> Self {
-        let mut counts = Self::default();
-        counts.all = entries.len();
-
-        for entry in entries {
-            let status = entry.status.as_str();
-            if is_untracked_status(status) {
-                counts.untracked += 1;
-                continue;
-            }
-            if is_conflict_status(status) {
-                counts.conflicts += 1;
-                continue;
-            }
+    fn add(&mut self, entry: &FileEntry) {
+        self.apply(entry, |count| *count += 1);
+    }
 
-            if let Some((x, y)) = status_xy(status) {
-                if x != ' ' && x != '?' && x != '!' {
-                    counts.staged += 1;
-                }
-                if y != ' ' && y != '?' && y != '!' {
-                    counts.unstaged += 1;
-                }
+    fn remove(&mut self, entry: &FileEntry) {
+        self.apply(entry, |count| *count = count.saturating_sub(1));
+    }
+
+    fn apply(&mut self, entry: &FileEntry, update: impl Fn(&mut usize)) {
+        update(&mut self.all);
+
+        let status = entry.status.as_str();
+        if is_untracked_status(status) {
+            update(&mut self.untracked);
+            return;
+        }
+        if is_conflict_status(status) {
+            update(&mut self.conflicts);
+            return;
+        }
+
+        if let Some((x, y)) = status_xy(status) {
+            if x != ' ' && x != '?' && x != '!' {
+                update(&mut self.staged);
+            }
+            if y != ' ' && y != '?' && y != '!' {
+                update(&mut self.unstaged);
             }
         }
+    }
+}
+
+/// 两次 `git status` 之间的差异。
+#[derive(Debug, Default)]
+struct StatusDelta<'a> {
+    added: Vec<&'a FileEntry>,
+    removed: Vec<&'a FileEntry>,
+    /// (旧条目, 新条目)
+    changed: Vec<(&'a FileEntry, &'a FileEntry)>,
+}
+
+impl StatusDelta<'_> {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
 
-        counts
+fn diff_status_entries<'a>(previous: &'a [FileEntry], next: &'a [FileEntry]) -> StatusDelta<'a> {
+    let previous_by_path: HashMap<&str, &FileEntry> = previous
+        .iter()
+        .map(|entry| (entry.path.as_str(), entry))
+        .collect();
+    let next_paths: HashSet<&str> = next.iter().map(|entry| entry.path.as_str()).collect();
+
+    let mut delta = StatusDelta::default();
+    for entry in next {
+        match previous_by_path.get(entry.path.as_str()) {
+            None => delta.added.push(entry),
+            Some(old) if old.status != entry.status => delta.changed.push((old, entry)),
+            Some(_) => {}
+        }
     }
+    delta.removed = previous
+        .iter()
+        .filter(|entry| !next_paths.contains(entry.path.as_str()))
+        .collect();
+    delta
 }
 
 fn matches_filter(entry: &FileEntry, filter: StatusFilter) -> bool {
@@ -6656,10 +16868,132 @@ fn status_xy(status: &str) -> Option<(char, char)> {
     Some((chars.next()?, chars.next()?))
 }
 
+/// 为未跟踪的路径生成相对仓库根目录的 ignore 规则；不适用时返回 None。
+///
+/// porcelain 输出里未跟踪的目录以 `/` 结尾，此时“忽略此文件”和“忽略此目录”都指向该目录本身。
+fn gitignore_pattern(path: &str, scope: IgnoreScope) -> Option<String> {
+    let path = path.trim_start_matches('/');
+    let is_dir = path.ends_with('/');
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    match scope {
+        IgnoreScope::File => Some(if is_dir {
+            format!("/{}/", escape_gitignore_path(trimmed))
+        } else {
+            format!("/{}", escape_gitignore_path(trimmed))
+        }),
+        IgnoreScope::Extension => {
+            if is_dir {
+                return None;
+            }
+            let file_name = trimmed.rsplit('/').next().unwrap_or(trimmed);
+            let (stem, extension) = file_name.rsplit_once('.')?;
+            if stem.is_empty() || extension.is_empty() {
+                return None;
+            }
+            Some(format!("*.{}", escape_gitignore_path(extension)))
+        }
+        IgnoreScope::Directory => {
+            let directory = if is_dir {
+                trimmed
+            } else {
+                trimmed.rsplit_once('/')?.0
+            };
+            Some(format!("/{}/", escape_gitignore_path(directory)))
+        }
+    }
+}
+
+fn escape_gitignore_path(path: &str) -> String {
+    let mut escaped = String::with_capacity(path.len());
+    for ch in path.chars() {
+        if matches!(ch, '*' | '?' | '[' | '\\' | '#' | '!') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    if escaped.ends_with(' ') {
+        let trimmed_len = escaped.trim_end_matches(' ').len();
+        let spaces = escaped.len() - trimmed_len;
+        escaped.truncate(trimmed_len);
+        escaped.push_str(&"\\ ".repeat(spaces));
+    }
+    escaped
+}
+
+/// 追加规则到 ignore 文件；规则已存在时返回 `Ok(false)`。
+fn append_ignore_pattern(repo_root: &Path, target: IgnoreFile, pattern: &str) -> Result<bool> {
+    let file_path = match target {
+        IgnoreFile::Gitignore => repo_root.join(".gitignore"),
+        IgnoreFile::InfoExclude => {
+            let output = Command::new("git")
+                .arg("-C")
+                .arg(repo_root)
+                .args(["rev-parse", "--git-path", "info/exclude"])
+                .output()
+                .context("执行 git rev-parse 失败")?;
+            if !output.status.success() {
+                return Err(anyhow!(
+                    "git rev-parse 返回非零（{}）：{}",
+                    output.status.code().unwrap_or(-1),
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+            let path = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+            if path.is_relative() {
+                repo_root.join(path)
+            } else {
+                path
+            }
+        }
+    };
+
+    let existing = match std::fs::read_to_string(&file_path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => {
+            return Err(err).with_context(|| format!("读取 {} 失败", file_path.display()));
+        }
+    };
+    if existing.lines().any(|line| line.trim_end() == pattern) {
+        return Ok(false);
+    }
+
+    let mut content = existing;
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(pattern);
+    content.push('\n');
+
+    if let Some(parent) = file_path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("创建目录 {} 失败", parent.display()))?;
+    }
+    std::fs::write(&file_path, content)
+        .with_context(|| format!("写入 {} 失败", file_path.display()))?;
+    Ok(true)
+}
+
+fn is_markdown_path(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown"))
+}
+
 fn is_untracked_status(status: &str) -> bool {
     status == "??"
 }
 
+/// `git status` 把整个未跟踪的目录折叠成一条以 `/` 结尾的记录。
+fn is_untracked_dir(entry: &FileEntry) -> bool {
+    is_untracked_status(&entry.status) && entry.path.ends_with('/')
+}
+
 fn is_conflict_status(status: &str) -> bool {
     status.contains('U') || status == "AA" || status == "DD"
 }
@@ -6707,27 +17041,16 @@ fn filter_commits<'a>(commits: &'a [CommitEntry], query: &str) -> Vec<&'a Commit
         .collect()
 }
 
-fn filter_command_palette_items(query: &str) -> Vec<&'static CommandPaletteItem> {
-    let query = query.trim().to_lowercase();
-    if query.is_empty() {
-        return COMMAND_PALETTE_ITEMS.iter().collect();
-    }
-
-    let tokens: Vec<&str> = query.split_whitespace().filter(|t| !t.is_empty()).collect();
-    if tokens.is_empty() {
-        return COMMAND_PALETTE_ITEMS.iter().collect();
-    }
-
-    COMMAND_PALETTE_ITEMS
-        .iter()
-        .filter(|item| {
-            let title = item.title.to_lowercase();
-            let keywords = item.keywords.to_lowercase();
-            tokens
-                .iter()
-                .all(|token| title.contains(token) || keywords.contains(token))
-        })
-        .collect()
+/// `<rev>:<path>` / `:<stage>:<path>` 形式的 ref 中的路径部分。
+fn ref_spec_path(spec: &str) -> Option<&str> {
+    let path = match spec.trim().strip_prefix(':') {
+        Some(rest) => match rest.split_once(':') {
+            Some((stage, path)) if matches!(stage, "0" | "1" | "2" | "3") => path,
+            _ => rest,
+        },
+        None => spec.trim().split_once(':')?.1,
+    };
+    (!path.is_empty()).then_some(path)
 }
 
 fn display_ref_label(label: &str) -> SharedString {
@@ -6769,6 +17092,20 @@ fn compare_target_side_label(target: &CompareTarget, side: Side) -> SharedString
 }
 
 fn unified_patch_for_hunk(path: &str, hunk: &diffview::DiffHunk) -> String {
+    unified_patch_for_file(path, std::slice::from_ref(hunk))
+}
+
+fn unified_patch_for_file(path: &str, hunks: &[diffview::DiffHunk]) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("--- a/{path}\n"));
+    out.push_str(&format!("+++ b/{path}\n"));
+    for hunk in hunks {
+        push_unified_hunk(&mut out, hunk);
+    }
+    out
+}
+
+fn push_unified_hunk(out: &mut String, hunk: &diffview::DiffHunk) {
     fn start_number(start: usize, len: usize) -> usize {
         if len == 0 { start } else { start + 1 }
     }
@@ -6776,9 +17113,6 @@ fn unified_patch_for_hunk(path: &str, hunk: &diffview::DiffHunk) -> String {
     let old_start = start_number(hunk.old_start, hunk.old_len);
     let new_start = start_number(hunk.new_start, hunk.new_len);
 
-    let mut out = String::new();
-    out.push_str(&format!("--- a/{path}\n"));
-    out.push_str(&format!("+++ b/{path}\n"));
     out.push_str(&format!(
         "@@ -{old_start},{} +{new_start},{} @@\n",
         hunk.old_len, hunk.new_len
@@ -6787,113 +17121,279 @@ fn unified_patch_for_hunk(path: &str, hunk: &diffview::DiffHunk) -> String {
     for row in &hunk.rows {
         match row.kind() {
             diffview::DiffRowKind::Unchanged => {
-                let text = row
-                    .old
-                    .as_ref()
-                    .map(|line| line.text.as_str())
-                    .or_else(|| row.new.as_ref().map(|line| line.text.as_str()))
-                    .unwrap_or_default();
-                out.push(' ');
-                out.push_str(text);
-                out.push('\n');
+                if let Some(line) = row.old.as_ref().or(row.new.as_ref()) {
+                    push_patch_line(out, ' ', line);
+                }
             }
             diffview::DiffRowKind::Removed => {
-                let text = row
-                    .old
-                    .as_ref()
-                    .map(|line| line.text.as_str())
-                    .unwrap_or_default();
-                out.push('-');
-                out.push_str(text);
-                out.push('\n');
+                if let Some(old) = row.old.as_ref() {
+                    push_patch_line(out, '-', old);
+                }
             }
             diffview::DiffRowKind::Added => {
-                let text = row
-                    .new
-                    .as_ref()
-                    .map(|line| line.text.as_str())
-                    .unwrap_or_default();
-                out.push('+');
-                out.push_str(text);
-                out.push('\n');
+                if let Some(new) = row.new.as_ref() {
+                    push_patch_line(out, '+', new);
+                }
             }
             diffview::DiffRowKind::Modified => {
                 if let Some(old) = row.old.as_ref() {
-                    out.push('-');
-                    out.push_str(&old.text);
-                    out.push('\n');
+                    push_patch_line(out, '-', old);
                 }
                 if let Some(new) = row.new.as_ref() {
-                    out.push('+');
-                    out.push_str(&new.text);
-                    out.push('\n');
+                    push_patch_line(out, '+', new);
                 }
             }
         }
     }
+}
+
+/// 写入一行 patch 正文；CRLF 行保留 `\r`，否则只有换行符不同的变更无法应用。
+fn push_patch_line(out: &mut String, prefix: char, line: &diffview::SideLine) {
+    out.push(prefix);
+    out.push_str(&line.text);
+    if line.crlf {
+        out.push('\r');
+    }
+    out.push('\n');
+}
+
+/// 只保留选中变更的 hunk 正文：未选中的删除行变为上下文，未选中的新增行被丢弃，
+/// 这样生成的 patch 仍然可以直接 `git apply`。没有选中任何变更时返回 None。
+fn partial_hunk_body(
+    hunk: &diffview::DiffHunk,
+    is_selected: impl Fn(usize, bool) -> bool,
+) -> Option<(String, usize, usize)> {
+    let mut body = String::new();
+    let (mut old_len, mut new_len) = (0usize, 0usize);
+    let mut has_change = false;
+
+    for (row_index, row) in hunk.rows.iter().enumerate() {
+        if row.kind() == diffview::DiffRowKind::Unchanged {
+            if let Some(line) = row.old.as_ref().or(row.new.as_ref()) {
+                push_patch_line(&mut body, ' ', line);
+                old_len += 1;
+                new_len += 1;
+            }
+            continue;
+        }
+
+        if let Some(old) = row.old.as_ref() {
+            if is_selected(row_index, true) {
+                push_patch_line(&mut body, '-', old);
+                has_change = true;
+            } else {
+                push_patch_line(&mut body, ' ', old);
+                new_len += 1;
+            }
+            old_len += 1;
+        }
+        if let Some(new) = row.new.as_ref() {
+            if is_selected(row_index, false) {
+                push_patch_line(&mut body, '+', new);
+                new_len += 1;
+                has_change = true;
+            }
+        }
+    }
 
-    out
+    has_change.then_some((body, old_len, new_len))
+}
+
+/// 命令行指定的启动界面。
+#[derive(Clone, Debug)]
+enum LaunchTarget {
+    Diff(PathBuf),
+    Compare {
+        base: String,
+        head: String,
+        path: Option<PathBuf>,
+    },
+    Conflict(PathBuf),
+}
+
+struct LaunchArgs {
+    start_dir: PathBuf,
+    target: Option<LaunchTarget>,
 }
 
 fn print_usage() {
     println!(
-        "git-viewer {}\n\n用法：\n  git-viewer [path]\n\n说明：\n  - path：要打开的目录（默认当前目录）。\n  - 若 path 在 git 仓库内，会自动定位到仓库根目录并加载状态。\n",
-        env!("CARGO_PKG_VERSION")
+        "git-viewer {}\n\n用法：\n  git-viewer [path]\n  git-viewer --diff <file>\n  git-viewer --compare <refA>..<refB> [file]\n  git-viewer --conflict <file>\n\n说明：\n  - path：要打开的目录（默认当前目录）。\n  - 若 path 在 git 仓库内，会自动定位到仓库根目录并加载状态。\n  - --diff：直接打开该文件的 diff。\n  - --compare：对比两个 ref；指定 file 时打开该文件在两个 ref 之间的 diff，否则打开分支对比。\n    refA / refB 留空时视为 HEAD。\n  - --conflict：直接打开该文件的冲突解决界面。\n\n配置文件 {} 的 [git-viewer] 表可设置 diff_cache_capacity、diff_cache_max_bytes、\ndiff_prefetch_radius、diff_preload_max_bytes 与 editor，同名环境变量优先：\n  - GIT_VIEWER_EDITOR：打开外部编辑器的命令，可使用 {{file}} 与 {{line}} 占位符。\n  - GIT_VIEWER_DIFF_CACHE_CAPACITY：最多缓存多少个文件的 diff（默认 {}）。\n  - GIT_VIEWER_DIFF_CACHE_MAX_BYTES：diff 缓存的总字节数上限（默认 {}）。\n  - GIT_VIEWER_DIFF_PREFETCH_RADIUS：打开文件后预取其后的文件数（默认 {}）。\n  - GIT_VIEWER_DIFF_PRELOAD_MAX_BYTES：状态刷新后预载不超过该大小的文件，0 表示关闭（默认 {}）。\n  - GIT_VIEWER_TIMING：设为 1 时在状态栏显示 diff 各阶段耗时，并逐次写入日志。\n  - MANOS_LOG：日志过滤（默认 info，例如 git_viewer=debug）。\n  - MANOS_LOG_DIR：日志目录（默认 {}）。\n",
+        env!("CARGO_PKG_VERSION"),
+        manos_config::config_dir()
+            .map(|dir| dir.join("settings.toml").display().to_string())
+            .unwrap_or_default(),
+        DIFF_CACHE_CAPACITY,
+        DIFF_CACHE_MAX_BYTES,
+        DIFF_PREFETCH_RADIUS,
+        DIFF_PRELOAD_MAX_FILE_BYTES,
+        manos_log::log_dir("git-viewer")
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default()
     );
 }
 
-fn resolve_start_dir_from_args() -> Result<PathBuf> {
-    let mut args = std::env::args().skip(1);
-    let Some(arg) = args.next() else {
-        return Ok(std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
+fn resolve_args() -> Result<LaunchArgs> {
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let absolute = |arg: String| {
+        let path = PathBuf::from(arg);
+        if path.is_absolute() {
+            path
+        } else {
+            cwd.join(path)
+        }
     };
 
-    if args.next().is_some() {
-        print_usage();
-        return Err(anyhow!("参数过多：只支持 0 或 1 个 path 参数"));
+    let mut args = std::env::args().skip(1).peekable();
+    let mut target = None;
+    let mut positional = Vec::new();
+    while let Some(arg) = args.next() {
+        let next_target = match arg.as_str() {
+            "-h" | "--help" => {
+                print_usage();
+                std::process::exit(0);
+            }
+            "-V" | "--version" => {
+                println!("git-viewer {}", env!("CARGO_PKG_VERSION"));
+                std::process::exit(0);
+            }
+            "--diff" => {
+                let path = args.next().ok_or_else(|| anyhow!("--diff 缺少文件路径"))?;
+                LaunchTarget::Diff(absolute(path))
+            }
+            "--conflict" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| anyhow!("--conflict 缺少文件路径"))?;
+                LaunchTarget::Conflict(absolute(path))
+            }
+            "--compare" => {
+                let range = args
+                    .next()
+                    .ok_or_else(|| anyhow!("--compare 缺少 <refA>..<refB>"))?;
+                let (base, head) = parse_compare_range(&range)?;
+                let path = args.next_if(|arg| !arg.starts_with('-')).map(&absolute);
+                LaunchTarget::Compare { base, head, path }
+            }
+            other if other.starts_with('-') => {
+                print_usage();
+                return Err(anyhow!("未知参数：{other}"));
+            }
+            _ => {
+                positional.push(arg);
+                continue;
+            }
+        };
+        if target.replace(next_target).is_some() {
+            return Err(anyhow!("--diff / --compare / --conflict 只能指定一个"));
+        }
     }
 
-    if arg == "-h" || arg == "--help" {
+    if positional.len() > 1 {
         print_usage();
-        std::process::exit(0);
-    }
-    if arg == "-V" || arg == "--version" {
-        println!("git-viewer {}", env!("CARGO_PKG_VERSION"));
-        std::process::exit(0);
+        return Err(anyhow!("参数过多：只支持 0 或 1 个 path 参数"));
     }
 
-    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-    let mut path = PathBuf::from(arg);
-    if !path.is_absolute() {
-        path = cwd.join(path);
-    }
+    let target_path = match &target {
+        Some(LaunchTarget::Diff(path) | LaunchTarget::Conflict(path)) => Some(path),
+        Some(LaunchTarget::Compare { path, .. }) => path.as_ref(),
+        None => None,
+    };
+    let path = match (positional.pop(), target_path) {
+        (Some(arg), _) => absolute(arg),
+        (None, Some(path)) => path.clone(),
+        (None, None) => {
+            return Ok(LaunchArgs {
+                start_dir: cwd,
+                target,
+            });
+        }
+    };
 
     let path = path.canonicalize().unwrap_or_else(|_| path.clone());
 
-    if path.is_file() {
-        return Ok(path.parent().unwrap_or(Path::new(".")).to_path_buf());
+    // 目标文件可能已在工作区删除（例如 --compare 旧版本中的文件），此时从其所在目录启动。
+    let start_dir = if path.is_dir() {
+        path
+    } else {
+        path.parent().unwrap_or(Path::new(".")).to_path_buf()
+    };
+
+    if !start_dir.is_dir() {
+        return Err(anyhow!("路径不是目录：{}", start_dir.display()));
     }
 
-    if !path.is_dir() {
-        return Err(anyhow!("路径不是目录：{}", path.display()));
+    Ok(LaunchArgs { start_dir, target })
+}
+
+/// 解析 `<refA>..<refB>`；与 git 一致，省略的一侧视为 HEAD。
+fn parse_compare_range(range: &str) -> Result<(String, String)> {
+    let Some((base, head)) = range.split_once("..") else {
+        return Err(anyhow!("--compare 需要 <refA>..<refB> 形式：{range}"));
+    };
+    if head.starts_with('.') {
+        return Err(anyhow!("--compare 不支持 ... 形式：{range}"));
     }
 
-    Ok(path)
+    let or_head = |value: &str| {
+        if value.is_empty() {
+            "HEAD".to_string()
+        } else {
+            value.to_string()
+        }
+    };
+    Ok((or_head(base), or_head(head)))
+}
+
+/// 把命令行中的文件路径转换为仓库内的相对路径（`/` 分隔）。
+fn repo_relative_path(repo_root: &Path, path: &Path) -> Option<String> {
+    let root = repo_root
+        .canonicalize()
+        .unwrap_or_else(|_| repo_root.to_path_buf());
+    let path = path.canonicalize().unwrap_or_else(|_| {
+        // 文件不存在时只规范化其所在目录。
+        match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) => parent
+                .canonicalize()
+                .map(|parent| parent.join(name))
+                .unwrap_or_else(|_| path.to_path_buf()),
+            _ => path.to_path_buf(),
+        }
+    });
+
+    let relative = path.strip_prefix(&root).ok()?;
+    let parts = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>();
+    (!parts.is_empty()).then(|| parts.join("/"))
 }
 
 fn main() {
-    let start_dir = match resolve_start_dir_from_args() {
-        Ok(path) => path,
+    if let Some(code) = askpass::run_client_if_requested() {
+        std::process::exit(code);
+    }
+
+    let LaunchArgs { start_dir, target } = match resolve_args() {
+        Ok(args) => args,
         Err(err) => {
             eprintln!("{err:#}");
             std::process::exit(2);
         }
     };
 
+    let askpass_requests = askpass::start_server();
+    // 写日志文件的线程要在 askpass 设置好环境变量之后再启动。
+    let _log = manos_log::init("git-viewer");
+    let askpass_requests = askpass_requests
+        .map_err(|err| tracing::warn!("askpass 不可用：{err:#}"))
+        .ok();
+
     let app = Application::new();
 
     app.run(move |cx| {
         gpui_component::init(cx);
+        gpui_manos_plate::init(cx);
         init_keybindings(cx);
         cx.activate(true);
 
@@ -6907,7 +17407,15 @@ fn main() {
                     let start_dir = start_dir.clone();
                     move |window, cx| {
                         window.set_window_title(&format!("git-viewer — {}", start_dir.display()));
-                        let view = cx.new(|cx| GitViewerApp::new(window, cx, start_dir.clone()));
+                        let view = cx.new(|cx| {
+                            GitViewerApp::new(
+                                window,
+                                cx,
+                                start_dir.clone(),
+                                target,
+                                askpass_requests,
+                            )
+                        });
                         let handle = view.read(cx).focus_handle();
                         window.focus(&handle);
                         cx.new(|cx| Root::new(view, window, cx))
@@ -0,0 +1,60 @@
+//! 提交栈的拖放：把 dnd 列表的放置结果翻译成提交栈上的编辑。
+//!
+//! 拖到两个提交之间只是调整顺序，由列表自己完成；拖到另一个提交的中间则是把它 squash 进去。
+//! 和 `git rebase --autosquash` 处理 `fixup!` 提交的方式一样：被拖的提交（连同已经合并进它的提交）
+//! 移到目标分组的末尾并标记为 squash，执行 rebase 时生成的 todo 就是 autosquash 排好的顺序。
+
+use gpui_manos_dnd::{DndDropStrategy, DndListItem};
+
+use crate::{StackAction, StackCommit};
+
+/// 行高中上下各占这个比例的区域算作插入到前/后，中间算作 squash 进该提交。
+const SQUASH_EDGE_FRACTION: f32 = 0.25;
+
+pub fn drop_strategy() -> DndDropStrategy {
+    DndDropStrategy::Threshold(SQUASH_EDGE_FRACTION)
+}
+
+/// `ix` 处的提交和紧跟其后、已经 squash 进它的提交。
+fn group_range(items: &[DndListItem<StackCommit>], ix: usize) -> std::ops::Range<usize> {
+    let end = items[ix + 1..]
+        .iter()
+        .position(|item| item.data.action != StackAction::Squash)
+        .map_or(items.len(), |offset| ix + 1 + offset);
+    ix..end
+}
+
+/// 能否把 `from` 处的提交 squash 进 `into` 处的提交。
+pub fn can_squash_into(items: &[DndListItem<StackCommit>], from: usize, into: usize) -> bool {
+    if from >= items.len() || into >= items.len() {
+        return false;
+    }
+    if items[into].data.action == StackAction::Drop || items[from].data.action == StackAction::Drop
+    {
+        return false;
+    }
+    !group_range(items, from).contains(&into)
+}
+
+/// 把 `from` 处的提交（及其分组）squash 进 `into` 处的提交。
+pub fn squash_into(items: &mut Vec<DndListItem<StackCommit>>, from: usize, into: usize) {
+    if !can_squash_into(items, from, into) {
+        return;
+    }
+
+    let group = group_range(items, from);
+    let moved: Vec<_> = items.drain(group).collect();
+    let into = if into > from {
+        into - moved.len()
+    } else {
+        into
+    };
+    let insert_at = group_range(items, into).end;
+    items.splice(
+        insert_at..insert_at,
+        moved.into_iter().map(|mut item| {
+            item.data.action = StackAction::Squash;
+            item
+        }),
+    );
+}
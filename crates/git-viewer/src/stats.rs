@@ -0,0 +1,438 @@
+//! 仓库统计：按作者和周的提交数、按目录的改动行数、最大的文件，以及当前工作树按语言划分的行数。
+//!
+//! 历史部分只启动一次 `git log --numstat`，边读输出边每 [`HISTORY_BATCH`] 个提交交出一批；
+//! 工作树部分按 `git ls-files` 的顺序每 [`FILE_BATCH`] 个文件一批，两部分交替进行。
+//! 每一批都在后台执行器上计算，界面把它合并进 [`RepoStats`] 后立即重绘，大仓库也能先看到部分结果。
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead as _, BufReader, Read as _};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+use anyhow::{Context as _, Result, anyhow};
+
+use crate::run_git_output;
+
+const HISTORY_BATCH: usize = 500;
+const FILE_BATCH: usize = 200;
+/// 按目录统计改动时保留的路径层数，例如 `crates/git-viewer`。
+const DIRECTORY_DEPTH: usize = 2;
+/// 超过这个大小的文件不数行数，多半是生成的或数据文件。
+const MAX_COUNTED_FILE_BYTES: u64 = 4 * 1024 * 1024;
+pub const LARGEST_FILES: usize = 20;
+
+/// 增加和删除的行数；二进制文件不计。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Churn {
+    pub added: u64,
+    pub removed: u64,
+}
+
+impl Churn {
+    pub fn total(&self) -> u64 {
+        self.added + self.removed
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AuthorStats {
+    pub commits: usize,
+    /// 按 ISO 周（`2024-W07`）的提交数。
+    pub weeks: BTreeMap<String, usize>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LanguageStats {
+    pub files: usize,
+    pub lines: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct FileSize {
+    pub path: String,
+    pub bytes: u64,
+}
+
+/// 统计结果；后台交出的每一批也是一份只含新增部分的 `RepoStats`，用 [`RepoStats::merge`] 累加。
+#[derive(Clone, Debug, Default)]
+pub struct RepoStats {
+    pub commits: usize,
+    pub authors: HashMap<String, AuthorStats>,
+    /// 按 ISO 周的提交数，键可以直接按字符串排序。
+    pub weeks: BTreeMap<String, usize>,
+    /// 按目录的改动行数，根目录下的文件记在 `.` 下。
+    pub directories: HashMap<String, Churn>,
+    /// 按大小降序，最多 [`LARGEST_FILES`] 个。
+    pub largest_files: Vec<FileSize>,
+    pub languages: HashMap<&'static str, LanguageStats>,
+    pub files_scanned: usize,
+    /// `git ls-files` 列出的文件数；还没开始扫描工作树时为 0。
+    pub files_total: usize,
+}
+
+impl RepoStats {
+    pub fn merge(&mut self, batch: RepoStats) {
+        self.commits += batch.commits;
+        for (name, author) in batch.authors {
+            let entry = self.authors.entry(name).or_default();
+            entry.commits += author.commits;
+            for (week, count) in author.weeks {
+                *entry.weeks.entry(week).or_default() += count;
+            }
+        }
+        for (week, count) in batch.weeks {
+            *self.weeks.entry(week).or_default() += count;
+        }
+        for (dir, churn) in batch.directories {
+            let entry = self.directories.entry(dir).or_default();
+            entry.added += churn.added;
+            entry.removed += churn.removed;
+        }
+        self.largest_files.extend(batch.largest_files);
+        keep_largest(&mut self.largest_files);
+        for (language, stats) in batch.languages {
+            let entry = self.languages.entry(language).or_default();
+            entry.files += stats.files;
+            entry.lines += stats.lines;
+        }
+        self.files_scanned += batch.files_scanned;
+        self.files_total = self.files_total.max(batch.files_total);
+    }
+
+    /// 最近 `limit` 个有提交的周，按时间升序。
+    pub fn recent_weeks(&self, limit: usize) -> Vec<(&str, usize)> {
+        let skip = self.weeks.len().saturating_sub(limit);
+        self.weeks
+            .iter()
+            .skip(skip)
+            .map(|(week, count)| (week.as_str(), *count))
+            .collect()
+    }
+
+    /// 提交最多的 `limit` 个作者。
+    pub fn top_authors(&self, limit: usize) -> Vec<(&str, &AuthorStats)> {
+        let mut authors: Vec<_> = self
+            .authors
+            .iter()
+            .map(|(name, author)| (name.as_str(), author))
+            .collect();
+        authors.sort_by(|a, b| b.1.commits.cmp(&a.1.commits).then(a.0.cmp(b.0)));
+        authors.truncate(limit);
+        authors
+    }
+
+    /// 改动行数最多的 `limit` 个目录。
+    pub fn top_directories(&self, limit: usize) -> Vec<(&str, Churn)> {
+        let mut directories: Vec<_> = self
+            .directories
+            .iter()
+            .map(|(dir, churn)| (dir.as_str(), *churn))
+            .collect();
+        directories.sort_by(|a, b| b.1.total().cmp(&a.1.total()).then(a.0.cmp(b.0)));
+        directories.truncate(limit);
+        directories
+    }
+
+    /// 按行数降序的语言。
+    pub fn languages_by_lines(&self) -> Vec<(&'static str, LanguageStats)> {
+        let mut languages: Vec<_> = self
+            .languages
+            .iter()
+            .map(|(language, stats)| (*language, *stats))
+            .collect();
+        languages.sort_by(|a, b| b.1.lines.cmp(&a.1.lines).then(a.0.cmp(b.0)));
+        languages
+    }
+}
+
+fn keep_largest(files: &mut Vec<FileSize>) {
+    files.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+    files.truncate(LARGEST_FILES);
+}
+
+/// 分批计算 [`RepoStats`]；每次 [`StatsCollector::next_batch`] 都会阻塞，应放到后台执行器上调用。
+///
+/// drop 时会结束还在运行的 `git log`。
+pub struct StatsCollector {
+    repo_root: PathBuf,
+    history: Option<HistoryReader>,
+    history_started: bool,
+    files: Option<std::vec::IntoIter<String>>,
+    /// 下一批先算历史还是工作树。
+    history_turn: bool,
+}
+
+impl StatsCollector {
+    pub fn new(repo_root: PathBuf) -> Self {
+        Self {
+            repo_root,
+            history: None,
+            history_started: false,
+            files: None,
+            history_turn: true,
+        }
+    }
+
+    /// 下一批结果；全部算完后返回 `None`。
+    pub fn next_batch(&mut self) -> Result<Option<RepoStats>> {
+        for _ in 0..2 {
+            let history_turn = self.history_turn;
+            self.history_turn = !history_turn;
+            let batch = if history_turn {
+                self.next_history_batch()?
+            } else {
+                self.next_files_batch()?
+            };
+            if batch.is_some() {
+                return Ok(batch);
+            }
+        }
+        Ok(None)
+    }
+
+    fn next_history_batch(&mut self) -> Result<Option<RepoStats>> {
+        if !self.history_started {
+            self.history_started = true;
+            // 还没有任何提交的仓库 `git log` 会报错，直接跳过历史部分。
+            if run_git_output(&self.repo_root, ["rev-parse", "--verify", "-q", "HEAD"]).is_ok() {
+                self.history = Some(HistoryReader::spawn(&self.repo_root)?);
+            }
+        }
+        let Some(history) = self.history.as_mut() else {
+            return Ok(None);
+        };
+        let batch = history.next_batch()?;
+        if batch.is_none() {
+            self.history = None;
+        }
+        Ok(batch)
+    }
+
+    fn next_files_batch(&mut self) -> Result<Option<RepoStats>> {
+        let mut batch = RepoStats::default();
+        if self.files.is_none() {
+            let output = run_git_output(&self.repo_root, ["ls-files", "-z"])?;
+            let files: Vec<String> = output
+                .split('\0')
+                .filter(|path| !path.is_empty())
+                .map(str::to_string)
+                .collect();
+            batch.files_total = files.len();
+            self.files = Some(files.into_iter());
+        }
+        let files = self.files.as_mut().expect("file list is loaded");
+
+        for path in files.by_ref().take(FILE_BATCH) {
+            batch.files_scanned += 1;
+            scan_file(&self.repo_root, path, &mut batch);
+        }
+        keep_largest(&mut batch.largest_files);
+        Ok((batch.files_scanned > 0 || batch.files_total > 0).then_some(batch))
+    }
+}
+
+fn scan_file(repo_root: &Path, path: String, batch: &mut RepoStats) {
+    // 工作树里已删除的、子模块和符号链接都不算。
+    let full_path = repo_root.join(&path);
+    let Ok(metadata) = std::fs::symlink_metadata(&full_path) else {
+        return;
+    };
+    if !metadata.is_file() {
+        return;
+    }
+    let bytes = metadata.len();
+
+    if let Some(language) = language_for_path(&path)
+        && bytes <= MAX_COUNTED_FILE_BYTES
+        && let Ok(content) = std::fs::read(&full_path)
+        && !content.contains(&0)
+    {
+        let entry = batch.languages.entry(language).or_default();
+        entry.files += 1;
+        entry.lines += count_lines(&content);
+    }
+    batch.largest_files.push(FileSize { path, bytes });
+}
+
+fn count_lines(content: &[u8]) -> u64 {
+    let newlines = content.iter().filter(|byte| **byte == b'\n').count() as u64;
+    if content.last().is_some_and(|byte| *byte != b'\n') {
+        newlines + 1
+    } else {
+        newlines
+    }
+}
+
+/// 流式读取 `git log --numstat` 的输出。
+struct HistoryReader {
+    child: Child,
+    stdout: BufReader<ChildStdout>,
+    /// 上一批读到、但属于下一批的提交头。
+    pending_header: Option<String>,
+}
+
+impl HistoryReader {
+    fn spawn(repo_root: &Path) -> Result<Self> {
+        let mut child = Command::new("git")
+            .arg("-C")
+            .arg(repo_root)
+            .args([
+                "-c",
+                "core.quotePath=false",
+                "log",
+                "--no-renames",
+                "--numstat",
+                "--date=format-local:%G-W%V",
+                "--format=%x1e%aN%x1f%ad",
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("启动 git log 失败")?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow!("无法读取 git log 的输出"))?;
+        Ok(Self {
+            child,
+            stdout: BufReader::new(stdout),
+            pending_header: None,
+        })
+    }
+
+    fn next_batch(&mut self) -> Result<Option<RepoStats>> {
+        let mut batch = RepoStats::default();
+        if let Some(header) = self.pending_header.take() {
+            record_commit(&header, &mut batch);
+        }
+
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let read = self
+                .stdout
+                .read_until(b'\n', &mut line)
+                .context("读取 git log 输出失败")?;
+            if read == 0 {
+                self.finish()?;
+                break;
+            }
+            let text = String::from_utf8_lossy(&line);
+            let text = text.trim_end_matches(['\n', '\r']);
+            if let Some(header) = text.strip_prefix('\x1e') {
+                if batch.commits >= HISTORY_BATCH {
+                    self.pending_header = Some(header.to_string());
+                    break;
+                }
+                record_commit(header, &mut batch);
+            } else if !text.is_empty() {
+                record_numstat(text, &mut batch);
+            }
+        }
+        Ok((batch.commits > 0).then_some(batch))
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        let status = self.child.wait().context("等待 git log 结束失败")?;
+        if status.success() {
+            return Ok(());
+        }
+        let mut stderr = String::new();
+        if let Some(mut pipe) = self.child.stderr.take() {
+            let _ = pipe.read_to_string(&mut stderr);
+        }
+        Err(anyhow!(
+            "git log 返回非零（{}）：{}",
+            status.code().unwrap_or(-1),
+            stderr.trim()
+        ))
+    }
+}
+
+impl Drop for HistoryReader {
+    fn drop(&mut self) {
+        if let Ok(None) = self.child.try_wait() {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+fn record_commit(header: &str, batch: &mut RepoStats) {
+    let (author, week) = header.split_once('\x1f').unwrap_or((header, ""));
+    let author = batch.authors.entry(author.trim().to_string()).or_default();
+    author.commits += 1;
+    *author.weeks.entry(week.to_string()).or_default() += 1;
+    *batch.weeks.entry(week.to_string()).or_default() += 1;
+    batch.commits += 1;
+}
+
+/// `<增加>\t<删除>\t<路径>`；二进制文件的行数是 `-`。
+fn record_numstat(line: &str, batch: &mut RepoStats) {
+    let mut fields = line.splitn(3, '\t');
+    let (Some(added), Some(removed), Some(path)) = (fields.next(), fields.next(), fields.next())
+    else {
+        return;
+    };
+    let churn = batch.directories.entry(directory_key(path)).or_default();
+    churn.added += added.parse::<u64>().unwrap_or(0);
+    churn.removed += removed.parse::<u64>().unwrap_or(0);
+}
+
+fn directory_key(path: &str) -> String {
+    // 含特殊字符的路径即使关掉 quotePath 也会带引号。
+    let path = path.trim_matches('"');
+    let components: Vec<&str> = path.split('/').collect();
+    let dirs = &components[..components.len() - 1];
+    if dirs.is_empty() {
+        return ".".to_string();
+    }
+    dirs[..dirs.len().min(DIRECTORY_DEPTH)].join("/")
+}
+
+/// 按文件名或扩展名识别的语言；不认识的文件只参与大小统计。
+fn language_for_path(path: &str) -> Option<&'static str> {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    match name {
+        "Makefile" | "GNUmakefile" => return Some("Makefile"),
+        "Dockerfile" => return Some("Dockerfile"),
+        "CMakeLists.txt" => return Some("CMake"),
+        _ => {}
+    }
+    let extension = Path::new(name).extension()?.to_str()?.to_ascii_lowercase();
+    let language = match extension.as_str() {
+        "rs" => "Rust",
+        "c" | "h" => "C",
+        "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => "C++",
+        "m" | "mm" => "Objective-C",
+        "go" => "Go",
+        "py" | "pyi" => "Python",
+        "js" | "mjs" | "cjs" | "jsx" => "JavaScript",
+        "ts" | "mts" | "cts" | "tsx" => "TypeScript",
+        "java" => "Java",
+        "kt" | "kts" => "Kotlin",
+        "swift" => "Swift",
+        "cs" => "C#",
+        "rb" => "Ruby",
+        "php" => "PHP",
+        "lua" => "Lua",
+        "zig" => "Zig",
+        "sh" | "bash" | "zsh" | "fish" => "Shell",
+        "ps1" => "PowerShell",
+        "html" | "htm" => "HTML",
+        "css" | "scss" | "sass" | "less" => "CSS",
+        "vue" => "Vue",
+        "svelte" => "Svelte",
+        "sql" => "SQL",
+        "wgsl" | "glsl" | "hlsl" | "metal" => "Shader",
+        "md" | "markdown" => "Markdown",
+        "toml" => "TOML",
+        "yaml" | "yml" => "YAML",
+        "json" | "jsonc" => "JSON",
+        "xml" => "XML",
+        "proto" => "Protobuf",
+        _ => return None,
+    };
+    Some(language)
+}
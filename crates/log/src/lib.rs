@@ -0,0 +1,204 @@
+//! Logging shared by the manos apps.
+//!
+//! [`init`] installs a `tracing` subscriber that writes to stderr and to a daily rotated
+//! file under the platform log directory, and a panic hook that records crashes in the same
+//! file, so a log can be attached to a bug report. Libraries only emit through `tracing`.
+//!
+//! The filter is read from `MANOS_LOG`, then `RUST_LOG`, using the `EnvFilter` syntax
+//! (`info`, `git_viewer=debug,wry=warn`, …). `MANOS_LOG_DIR` overrides the directory.
+
+use std::path::{Path, PathBuf};
+
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{self, Rotation};
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::layer::SubscriberExt as _;
+use tracing_subscriber::util::SubscriberInitExt as _;
+
+pub use tracing;
+
+const FILTER_ENV: &str = "MANOS_LOG";
+const DIR_ENV: &str = "MANOS_LOG_DIR";
+
+/// Keeps the file writer alive; dropping it flushes buffered lines. Hold it in `main`.
+#[must_use = "logs written after the guard is dropped are lost"]
+pub struct LogGuard {
+    _worker: Option<WorkerGuard>,
+    dir: Option<PathBuf>,
+}
+
+impl LogGuard {
+    /// Directory the log files are written to, or `None` when logging to stderr only.
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+}
+
+/// Settings for [`Logger::init`]; [`init`] uses the defaults.
+pub struct Logger {
+    app_name: String,
+    default_filter: String,
+    max_files: usize,
+    stderr: bool,
+    file: bool,
+}
+
+impl Logger {
+    /// `app_name` names the log directory and prefixes the file names.
+    pub fn new(app_name: impl Into<String>) -> Self {
+        Self {
+            app_name: app_name.into(),
+            default_filter: "info".to_string(),
+            max_files: 7,
+            stderr: true,
+            file: true,
+        }
+    }
+
+    /// Filter used when neither `MANOS_LOG` nor `RUST_LOG` is set.
+    pub fn default_filter(mut self, filter: impl Into<String>) -> Self {
+        self.default_filter = filter.into();
+        self
+    }
+
+    /// Number of daily files kept; older ones are deleted on rotation.
+    pub fn max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files.max(1);
+        self
+    }
+
+    pub fn stderr(mut self, stderr: bool) -> Self {
+        self.stderr = stderr;
+        self
+    }
+
+    pub fn file(mut self, file: bool) -> Self {
+        self.file = file;
+        self
+    }
+
+    /// Installs the subscriber and the panic hook.
+    ///
+    /// Does nothing but return an empty guard when a global subscriber is already set, so
+    /// calling it twice is harmless.
+    pub fn init(self) -> LogGuard {
+        let filter = EnvFilter::try_from_env(FILTER_ENV)
+            .or_else(|_| EnvFilter::try_from_default_env())
+            .unwrap_or_else(|_| EnvFilter::new(&self.default_filter));
+
+        let mut file_error = None;
+        let file = if self.file {
+            log_dir(&self.app_name).and_then(|dir| {
+                let appender = rolling::Builder::new()
+                    .rotation(Rotation::DAILY)
+                    .filename_prefix(&self.app_name)
+                    .filename_suffix("log")
+                    .max_log_files(self.max_files)
+                    .build(&dir);
+                match appender {
+                    Ok(appender) => Some((dir, tracing_appender::non_blocking(appender))),
+                    Err(err) => {
+                        file_error = Some((dir, err));
+                        None
+                    }
+                }
+            })
+        } else {
+            None
+        };
+        let (dir, file_layer, worker) = match file {
+            Some((dir, (writer, worker))) => (
+                Some(dir),
+                Some(
+                    tracing_subscriber::fmt::layer()
+                        .with_ansi(false)
+                        .with_writer(writer),
+                ),
+                Some(worker),
+            ),
+            None => (None, None, None),
+        };
+        let stderr_layer = self
+            .stderr
+            .then(|| tracing_subscriber::fmt::layer().with_writer(std::io::stderr));
+
+        let installed = tracing_subscriber::registry()
+            .with(filter)
+            .with(stderr_layer)
+            .with(file_layer)
+            .try_init()
+            .is_ok();
+        if !installed {
+            return LogGuard {
+                _worker: None,
+                dir: None,
+            };
+        }
+
+        if let Some((dir, err)) = file_error {
+            tracing::warn!("cannot write logs to {}: {err}", dir.display());
+        }
+        install_panic_hook();
+        tracing::info!(
+            app = %self.app_name,
+            version = env!("CARGO_PKG_VERSION"),
+            dir = ?dir,
+            "logging started"
+        );
+
+        LogGuard {
+            _worker: worker,
+            dir,
+        }
+    }
+}
+
+/// Sets up logging for `app_name` with the default [`Logger`] settings.
+pub fn init(app_name: &str) -> LogGuard {
+    Logger::new(app_name).init()
+}
+
+/// Where the logs of `app_name` go: `~/Library/Logs/<app>` on macOS,
+/// `%LOCALAPPDATA%\<app>\logs` on Windows and `$XDG_STATE_HOME/<app>/logs` elsewhere.
+pub fn log_dir(app_name: &str) -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os(DIR_ENV).filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+
+    if cfg!(target_os = "macos") {
+        dirs::home_dir().map(|home| home.join("Library").join("Logs").join(app_name))
+    } else if cfg!(target_os = "windows") {
+        dirs::data_local_dir().map(|dir| dir.join(app_name).join("logs"))
+    } else {
+        dirs::state_dir()
+            .or_else(dirs::data_local_dir)
+            .map(|dir| dir.join(app_name).join("logs"))
+    }
+}
+
+/// Records panics as errors with a backtrace, then runs the previous hook.
+fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("<non-string panic payload>");
+        let location = info
+            .location()
+            .map(|location| location.to_string())
+            .unwrap_or_default();
+        let thread = std::thread::current();
+        let backtrace = std::backtrace::Backtrace::force_capture();
+
+        tracing::error!(
+            target: "panic",
+            thread = thread.name().unwrap_or("<unnamed>"),
+            %location,
+            "{message}\n{backtrace}"
+        );
+        previous(info);
+    }));
+}
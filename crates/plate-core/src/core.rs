@@ -46,6 +46,24 @@ impl Node {
         })
     }
 
+    pub fn math_inline(tex: impl Into<String>) -> Self {
+        let mut attrs = Attrs::default();
+        attrs.insert("tex".to_string(), Value::String(tex.into()));
+        Node::Void(VoidNode {
+            kind: "math_inline".to_string(),
+            attrs,
+        })
+    }
+
+    pub fn math_block(tex: impl Into<String>) -> Self {
+        let mut attrs = Attrs::default();
+        attrs.insert("tex".to_string(), Value::String(tex.into()));
+        Node::Void(VoidNode {
+            kind: "math_block".to_string(),
+            attrs,
+        })
+    }
+
     pub fn image(src: impl Into<String>, alt: Option<String>) -> Self {
         let mut attrs = Attrs::default();
         attrs.insert("src".to_string(), Value::String(src.into()));
@@ -97,6 +115,15 @@ impl VoidNode {
                 .filter(|s| !s.is_empty())
                 .unwrap_or("😀")
                 .to_string(),
+            "math_inline" => {
+                let tex = self.attrs.get("tex").and_then(|v| v.as_str()).unwrap_or("");
+                let text = crate::math::transliterate_tex(tex);
+                if text.is_empty() {
+                    "□".to_string()
+                } else {
+                    text
+                }
+            }
             _ => "□".to_string(),
         }
     }
@@ -118,6 +145,7 @@ impl VoidNode {
                 .filter(|s| !s.is_empty())
                 .unwrap_or("😀")
                 .len(),
+            "math_inline" => self.inline_text().len(),
             _ => 1,
         }
     }
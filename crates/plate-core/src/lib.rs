@@ -1,9 +1,17 @@
 mod core;
+mod markdown;
+mod math;
 mod ops;
 mod plugin;
 mod serde_value;
+#[cfg(feature = "wasm")]
+mod wasm;
 
 pub use crate::core::*;
+pub use crate::markdown::*;
+pub use crate::math::*;
 pub use crate::ops::*;
 pub use crate::plugin::*;
 pub use crate::serde_value::*;
+#[cfg(feature = "wasm")]
+pub use crate::wasm::*;
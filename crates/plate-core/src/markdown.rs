@@ -0,0 +1,587 @@
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use serde_json::Value;
+
+use crate::core::{Attrs, Document, ElementNode, Marks, Node, TextNode};
+
+/// Parses CommonMark (with tables, strikethrough, task lists and `$` math) into a richtext
+/// document.
+///
+/// Headings, paragraphs, block quotes, code blocks, lists, task items, tables, images, math and
+/// rules map onto the richtext node kinds; emphasis, strong, strikethrough, inline code and
+/// links become marks. Raw HTML blocks are dropped, inline HTML is kept as text.
+pub fn markdown_to_document(markdown: &str) -> Document {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_SMART_PUNCTUATION
+        | Options::ENABLE_MATH;
+    let mut builder = MarkdownBuilder::default();
+    for event in Parser::new_ext(markdown, options) {
+        builder.event(event);
+    }
+    builder.finish()
+}
+
+/// Serializes a richtext document back to CommonMark, the inverse of [`markdown_to_document`].
+///
+/// Kinds without a markdown form (toggles, columns) keep their content as plain blocks; inline
+/// marks markdown cannot express (underline, colors) are dropped.
+pub fn document_to_markdown(doc: &Document) -> String {
+    let mut out = String::new();
+    write_blocks(&doc.children, &mut out);
+    out.push('\n');
+    out
+}
+
+fn write_blocks(blocks: &[Node], out: &mut String) {
+    let mut prev_was_item = false;
+    for node in blocks {
+        let is_item =
+            matches!(node, Node::Element(el) if el.kind == "list_item" || el.kind == "todo_item");
+        let Some(block) = block_markdown(node) else {
+            continue;
+        };
+        if !out.is_empty() {
+            // Consecutive items stay in one tight list.
+            let separator = if is_item && prev_was_item {
+                "\n"
+            } else {
+                "\n\n"
+            };
+            out.push_str(separator);
+        }
+        out.push_str(&block);
+        prev_was_item = is_item;
+    }
+}
+
+fn block_markdown(node: &Node) -> Option<String> {
+    let el = match node {
+        Node::Void(v) => {
+            return Some(match v.kind.as_str() {
+                "divider" => "---".to_string(),
+                "image" => {
+                    let src = v.attrs.get("src").and_then(|v| v.as_str()).unwrap_or("");
+                    let alt = v.attrs.get("alt").and_then(|v| v.as_str()).unwrap_or("");
+                    format!("![{}]({src})", escape_markdown(alt))
+                }
+                "math_block" => {
+                    let tex = v.attrs.get("tex").and_then(|v| v.as_str()).unwrap_or("");
+                    format!("$$\n{}\n$$", tex.trim())
+                }
+                _ => inline_markdown(std::slice::from_ref(node), false),
+            });
+        }
+        Node::Text(text) => return Some(escape_markdown(&text.text)),
+        Node::Element(el) => el,
+    };
+
+    let attr_u64 = |key: &str| el.attrs.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+    let text = || inline_markdown(&el.children, false);
+    Some(match el.kind.as_str() {
+        "heading" => format!(
+            "{} {}",
+            "#".repeat(attr_u64("level").clamp(1, 6) as usize),
+            text()
+        ),
+        "code_block" => {
+            let language = el
+                .attrs
+                .get("language")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let code: String = el
+                .children
+                .iter()
+                .filter_map(|child| match child {
+                    Node::Text(text) => Some(text.text.as_str()),
+                    _ => None,
+                })
+                .collect();
+            let fence = if code.contains("```") { "~~~~" } else { "```" };
+            format!("{fence}{language}\n{code}\n{fence}")
+        }
+        "list_item" => {
+            let indent = "  ".repeat(attr_u64("list_level") as usize);
+            let marker = match el.attrs.get("list_type").and_then(|v| v.as_str()) {
+                Some("ordered") => format!("{}.", attr_u64("list_index").max(1)),
+                _ => "-".to_string(),
+            };
+            format!("{indent}{marker} {}", text())
+        }
+        "todo_item" => {
+            let indent = "  ".repeat(attr_u64("indent") as usize);
+            let checked = el.attrs.get("checked").and_then(|v| v.as_bool()) == Some(true);
+            format!("{indent}- [{}] {}", if checked { "x" } else { " " }, text())
+        }
+        "blockquote" => {
+            let mut inner = String::new();
+            write_blocks(&el.children, &mut inner);
+            inner
+                .lines()
+                .map(|line| {
+                    if line.is_empty() {
+                        ">".to_string()
+                    } else {
+                        format!("> {line}")
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        "table" => table_markdown(el),
+        kind if el
+            .children
+            .iter()
+            .all(|child| !matches!(child, Node::Element(_)))
+            && (kind == "paragraph" || !el.children.is_empty()) =>
+        {
+            text()
+        }
+        _ => {
+            let mut inner = String::new();
+            write_blocks(&el.children, &mut inner);
+            inner
+        }
+    })
+    .filter(|block| !block.is_empty())
+}
+
+fn table_markdown(table: &ElementNode) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    for (row_ix, row) in table.children.iter().enumerate() {
+        let Node::Element(row) = row else {
+            continue;
+        };
+        let cells: Vec<String> = row
+            .children
+            .iter()
+            .map(|cell| {
+                let Node::Element(cell) = cell else {
+                    return String::new();
+                };
+                cell.children
+                    .iter()
+                    .filter_map(|block| match block {
+                        // The header row is bold on import; don't double it up on export.
+                        Node::Element(block) => Some(inline_markdown(&block.children, row_ix == 0)),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+        lines.push(format!("| {} |", cells.join(" | ")));
+        if row_ix == 0 {
+            lines.push(format!("|{}", " --- |".repeat(cells.len().max(1))));
+        }
+    }
+    lines.join("\n")
+}
+
+fn inline_markdown(children: &[Node], skip_bold: bool) -> String {
+    let mut out = String::new();
+    for child in children {
+        match child {
+            Node::Text(text) if text.text.is_empty() => {}
+            Node::Text(text) => {
+                let marks = &text.marks;
+                let mut piece = if marks.code {
+                    let fence = if text.text.contains('`') { "`` " } else { "`" };
+                    format!(
+                        "{fence}{}{}",
+                        text.text,
+                        fence.chars().rev().collect::<String>()
+                    )
+                } else {
+                    escape_markdown(&text.text).replace('\n', "\\\n")
+                };
+                if marks.strikethrough {
+                    piece = format!("~~{piece}~~");
+                }
+                if marks.italic {
+                    piece = format!("*{piece}*");
+                }
+                if marks.bold && !skip_bold {
+                    piece = format!("**{piece}**");
+                }
+                if let Some(url) = &marks.link {
+                    piece = format!("[{piece}]({url})");
+                }
+                out.push_str(&piece);
+            }
+            Node::Void(v) if v.kind == "math_inline" => {
+                let tex = v.attrs.get("tex").and_then(|v| v.as_str()).unwrap_or("");
+                out.push('$');
+                out.push_str(tex.trim());
+                out.push('$');
+            }
+            Node::Void(v) => out.push_str(&escape_markdown(&v.inline_text())),
+            Node::Element(el) => out.push_str(&inline_markdown(&el.children, skip_bold)),
+        }
+    }
+    out
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if matches!(
+            ch,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '$' | '~' | '|' | '#'
+        ) {
+            out.push('\\');
+        }
+        out.push(ch);
+    }
+    out
+}
+
+#[derive(Default)]
+struct MarkdownBuilder {
+    /// Open containers (block quotes and table parts); blocks go into the innermost one.
+    containers: Vec<ElementNode>,
+    root: Vec<Node>,
+    /// The text block being filled with inline content.
+    block: Option<ElementNode>,
+    /// Attributes of a list item whose text has not started yet.
+    pending_item: Option<(String, Attrs)>,
+    /// `Some(start)` for ordered lists, innermost last.
+    lists: Vec<Option<u64>>,
+    marks: Vec<Marks>,
+    in_code_block: bool,
+    in_table_head: bool,
+    /// Source and alt text of an image being read.
+    image: Option<(String, String)>,
+    in_html_block: bool,
+}
+
+impl MarkdownBuilder {
+    fn event(&mut self, event: Event<'_>) {
+        if self.in_html_block {
+            if matches!(event, Event::End(TagEnd::HtmlBlock)) {
+                self.in_html_block = false;
+            }
+            return;
+        }
+        if let Some((_, alt)) = self.image.as_mut() {
+            match event {
+                Event::Text(text) | Event::Code(text) => alt.push_str(&text),
+                Event::End(TagEnd::Image) => self.end_image(),
+                _ => {}
+            }
+            return;
+        }
+
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => self.push_text(&text, Marks::default()),
+            Event::Code(text) => self.push_text(
+                &text,
+                Marks {
+                    code: true,
+                    ..Marks::default()
+                },
+            ),
+            Event::InlineHtml(html) | Event::Html(html) => self.push_text(&html, Marks::default()),
+            Event::FootnoteReference(label) => {
+                self.push_text(&format!("[^{label}]"), Marks::default())
+            }
+            Event::InlineMath(tex) => self.push_inline(Node::math_inline(tex.trim())),
+            Event::DisplayMath(tex) => self.push_block_void(Node::math_block(tex.trim())),
+            Event::SoftBreak => self.push_text(" ", Marks::default()),
+            Event::HardBreak => self.push_text("\n", Marks::default()),
+            Event::Rule => {
+                self.flush_block();
+                self.push_block(Node::divider());
+            }
+            Event::TaskListMarker(checked) => self.mark_task(checked),
+        }
+    }
+
+    fn start(&mut self, tag: Tag<'_>) {
+        match tag {
+            Tag::Paragraph => self.open_block("paragraph", Attrs::default()),
+            Tag::Heading { level, .. } => {
+                let mut attrs = Attrs::default();
+                attrs.insert("level".to_string(), Value::from(level as u64));
+                self.open_block("heading", attrs);
+            }
+            Tag::CodeBlock(kind) => {
+                let mut attrs = Attrs::default();
+                if let CodeBlockKind::Fenced(lang) = kind
+                    && !lang.is_empty()
+                {
+                    attrs.insert("language".to_string(), Value::String(lang.to_string()));
+                }
+                self.open_block("code_block", attrs);
+                self.in_code_block = true;
+            }
+            Tag::BlockQuote(_) => self.open_container("blockquote"),
+            Tag::List(start) => {
+                self.flush_block();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush_block();
+                let mut attrs = Attrs::default();
+                let list_type = match self.lists.last() {
+                    Some(Some(_)) => "ordered",
+                    _ => "bulleted",
+                };
+                attrs.insert(
+                    "list_type".to_string(),
+                    Value::String(list_type.to_string()),
+                );
+                let level = self.lists.len().saturating_sub(1) as u64;
+                if level > 0 {
+                    attrs.insert("list_level".to_string(), Value::from(level));
+                }
+                self.pending_item = Some(("list_item".to_string(), attrs));
+            }
+            Tag::Table(_) => self.open_container("table"),
+            Tag::TableHead => {
+                self.in_table_head = true;
+                self.open_container("table_row");
+            }
+            Tag::TableRow => self.open_container("table_row"),
+            Tag::TableCell => {
+                self.open_container("table_cell");
+                self.open_block("paragraph", Attrs::default());
+            }
+            Tag::Emphasis => self.push_marks(|marks| marks.italic = true),
+            Tag::Strong => self.push_marks(|marks| marks.bold = true),
+            Tag::Strikethrough => self.push_marks(|marks| marks.strikethrough = true),
+            Tag::Link { dest_url, .. } => {
+                let url = dest_url.to_string();
+                self.push_marks(|marks| marks.link = Some(url));
+            }
+            Tag::Image { dest_url, .. } => {
+                self.image = Some((dest_url.to_string(), String::new()));
+            }
+            Tag::HtmlBlock => {
+                self.flush_block();
+                self.in_html_block = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph | TagEnd::Heading(_) => self.flush_block(),
+            TagEnd::CodeBlock => {
+                if let Some(block) = self.block.as_mut()
+                    && let Some(Node::Text(text)) = block.children.last_mut()
+                {
+                    let trimmed = text.text.trim_end_matches('\n').len();
+                    text.text.truncate(trimmed);
+                }
+                self.in_code_block = false;
+                self.flush_block();
+            }
+            TagEnd::BlockQuote(_)
+            | TagEnd::Table
+            | TagEnd::TableHead
+            | TagEnd::TableRow
+            | TagEnd::TableCell => {
+                self.flush_block();
+                self.close_container();
+                if tag == TagEnd::TableHead {
+                    self.in_table_head = false;
+                }
+            }
+            TagEnd::List(_) => {
+                self.flush_block();
+                self.lists.pop();
+            }
+            TagEnd::Item => {
+                self.flush_block();
+                self.pending_item = None;
+            }
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough | TagEnd::Link => {
+                self.marks.pop();
+            }
+            _ => {}
+        }
+    }
+
+    fn current_marks(&self) -> Marks {
+        let mut marks = self.marks.last().cloned().unwrap_or_default();
+        if self.in_table_head {
+            marks.bold = true;
+        }
+        marks
+    }
+
+    fn push_marks(&mut self, update: impl FnOnce(&mut Marks)) {
+        let mut marks = self.marks.last().cloned().unwrap_or_default();
+        update(&mut marks);
+        self.marks.push(marks);
+    }
+
+    fn open_block(&mut self, kind: &str, attrs: Attrs) {
+        self.flush_block();
+        // The first paragraph of a list item becomes the item itself.
+        let (kind, attrs) = match self.pending_item.take() {
+            Some(item) if kind == "paragraph" => item,
+            Some(_) | None => (kind.to_string(), attrs),
+        };
+        self.block = Some(ElementNode {
+            kind,
+            attrs,
+            children: Vec::new(),
+        });
+    }
+
+    fn push_text(&mut self, text: &str, extra: Marks) {
+        if text.is_empty() {
+            return;
+        }
+        if self.block.is_none() {
+            self.open_block("paragraph", Attrs::default());
+        }
+
+        let mut marks = if self.in_code_block {
+            Marks::default()
+        } else {
+            self.current_marks()
+        };
+        marks.code |= extra.code;
+
+        let Some(block) = self.block.as_mut() else {
+            return;
+        };
+        if let Some(Node::Text(last)) = block.children.last_mut()
+            && last.marks == marks
+        {
+            last.text.push_str(text);
+            return;
+        }
+        block.children.push(Node::Text(TextNode {
+            text: text.to_string(),
+            marks,
+        }));
+    }
+
+    fn mark_task(&mut self, checked: bool) {
+        let target = match (self.pending_item.as_mut(), self.block.as_mut()) {
+            (Some((kind, attrs)), _) => Some((kind, attrs)),
+            (None, Some(block)) if block.kind == "list_item" && block.children.is_empty() => {
+                Some((&mut block.kind, &mut block.attrs))
+            }
+            _ => None,
+        };
+        let Some((kind, attrs)) = target else {
+            return;
+        };
+
+        let level = attrs.get("list_level").cloned();
+        attrs.clear();
+        attrs.insert("checked".to_string(), Value::Bool(checked));
+        if let Some(level) = level {
+            attrs.insert("indent".to_string(), level);
+        }
+        *kind = "todo_item".to_string();
+    }
+
+    fn push_inline(&mut self, node: Node) {
+        if self.block.is_none() {
+            self.open_block("paragraph", Attrs::default());
+        }
+        if let Some(block) = self.block.as_mut() {
+            block.children.push(node);
+        }
+    }
+
+    fn end_image(&mut self) {
+        let Some((src, alt)) = self.image.take() else {
+            return;
+        };
+        self.push_block_void(Node::image(src, Some(alt)));
+    }
+
+    /// Images and display math are blocks here, so one inside a paragraph splits it.
+    fn push_block_void(&mut self, node: Node) {
+        let continues = self.block.as_ref().map(|block| block.kind.clone());
+        self.flush_block();
+        self.push_block(node);
+        if continues.as_deref() == Some("paragraph") {
+            self.block = Some(ElementNode {
+                kind: "paragraph".to_string(),
+                attrs: Attrs::default(),
+                children: Vec::new(),
+            });
+        }
+    }
+
+    fn flush_block(&mut self) {
+        let Some(block) = self.block.take() else {
+            return;
+        };
+        let is_blank = block.children.iter().all(|child| match child {
+            Node::Text(text) => text.text.trim().is_empty(),
+            _ => false,
+        });
+        // Empty paragraphs are leftovers of split blocks; other kinds keep their meaning.
+        if is_blank && block.kind == "paragraph" && !self.in_table_cell() {
+            return;
+        }
+
+        let mut block = block;
+        // Keep a text leaf after a trailing inline void so the caret has somewhere to go.
+        if block.children.is_empty() || matches!(block.children.last(), Some(Node::Void(_))) {
+            block.children.push(Node::Text(TextNode {
+                text: String::new(),
+                marks: Marks::default(),
+            }));
+        }
+        self.push_block(Node::Element(block));
+    }
+
+    fn in_table_cell(&self) -> bool {
+        self.containers
+            .last()
+            .is_some_and(|container| container.kind == "table_cell")
+    }
+
+    fn push_block(&mut self, node: Node) {
+        match self.containers.last_mut() {
+            Some(container) => container.children.push(node),
+            None => self.root.push(node),
+        }
+    }
+
+    fn open_container(&mut self, kind: &str) {
+        self.flush_block();
+        self.containers.push(ElementNode {
+            kind: kind.to_string(),
+            attrs: Attrs::default(),
+            children: Vec::new(),
+        });
+    }
+
+    fn close_container(&mut self) {
+        let Some(mut container) = self.containers.pop() else {
+            return;
+        };
+        if container.children.is_empty() {
+            container.children.push(Node::paragraph(""));
+        }
+        self.push_block(Node::Element(container));
+    }
+
+    fn finish(mut self) -> Document {
+        self.flush_block();
+        while !self.containers.is_empty() {
+            self.close_container();
+        }
+        if self.root.is_empty() {
+            self.root.push(Node::paragraph(""));
+        }
+        Document {
+            children: self.root,
+        }
+    }
+}
@@ -0,0 +1,525 @@
+/// Transliterates TeX math source into a single line of Unicode text, which is how math nodes
+/// are shown. This is not TeX layout: nothing is stacked or positioned, so `\frac{a+b}{c}`
+/// reads `(a + b)/c`, `\sqrt{x}` reads `√x`, and a script without Unicode super/subscript
+/// glyphs for every character is written out, as in `e^(iπ)`.
+///
+/// Covers the subset people type in notes: Greek letters, operators and relations, arrows,
+/// blackboard/calligraphic letters, `^`/`_` scripts, `\frac`, `\sqrt`, `\text` and the usual
+/// function names. Unknown commands are kept verbatim so nothing silently disappears; malformed
+/// input never fails.
+pub fn transliterate_tex(tex: &str) -> String {
+    let mut parser = TexParser {
+        chars: tex.chars().collect(),
+        pos: 0,
+    };
+    let mut out = String::new();
+    parser.expression(&mut out, None);
+    out.trim().to_string()
+}
+
+struct TexParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl TexParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.pos += 1;
+        Some(ch)
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Transliterates tokens until `close` (consumed) or the end of input.
+    fn expression(&mut self, out: &mut String, close: Option<char>) {
+        while let Some(ch) = self.peek() {
+            if Some(ch) == close {
+                self.pos += 1;
+                return;
+            }
+            self.token(out);
+        }
+    }
+
+    fn token(&mut self, out: &mut String) {
+        let Some(ch) = self.bump() else {
+            return;
+        };
+        match ch {
+            '{' => self.expression(out, Some('}')),
+            '}' => {}
+            '^' => {
+                let arg = self.argument();
+                push_script(out, &arg, superscript, '^');
+            }
+            '_' => {
+                let arg = self.argument();
+                push_script(out, &arg, subscript, '_');
+            }
+            '\\' => self.command(out),
+            '-' => push_operator(out, "−"),
+            '+' | '=' | '<' | '>' => push_operator(out, &ch.to_string()),
+            '~' => out.push(' '),
+            '&' => out.push(' '),
+            ch if ch.is_whitespace() => {}
+            ch => out.push(ch),
+        }
+    }
+
+    /// The next group, command or single character, transliterated on its own.
+    fn argument(&mut self) -> String {
+        self.skip_spaces();
+        let mut out = String::new();
+        match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                self.expression(&mut out, Some('}'));
+            }
+            Some(_) => self.token(&mut out),
+            None => {}
+        }
+        out.trim().to_string()
+    }
+
+    /// An optional `[...]` argument, transliterated.
+    fn optional_argument(&mut self) -> Option<String> {
+        self.skip_spaces();
+        if self.peek() != Some('[') {
+            return None;
+        }
+        self.pos += 1;
+        let mut out = String::new();
+        self.expression(&mut out, Some(']'));
+        Some(out.trim().to_string())
+    }
+
+    /// A `{...}` argument taken literally, for `\text` and friends.
+    fn raw_argument(&mut self) -> String {
+        self.skip_spaces();
+        if self.peek() != Some('{') {
+            return self.bump().map(String::from).unwrap_or_default();
+        }
+        self.pos += 1;
+        let mut depth = 0usize;
+        let mut out = String::new();
+        while let Some(ch) = self.bump() {
+            match ch {
+                '{' => depth += 1,
+                '}' if depth == 0 => break,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            out.push(ch);
+        }
+        out
+    }
+
+    fn command(&mut self, out: &mut String) {
+        let start = self.pos;
+        while self.peek().is_some_and(|ch| ch.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            // Control symbols: `\{`, `\,`, `\\` ...
+            match self.bump() {
+                Some(',' | ':' | ';' | ' ') => out.push(' '),
+                Some('!') => {}
+                Some('\\') => out.push(' '),
+                Some(ch) => out.push(ch),
+                None => out.push('\\'),
+            }
+            return;
+        }
+        let name: String = self.chars[start..self.pos].iter().collect();
+
+        match name.as_str() {
+            "frac" | "dfrac" | "tfrac" => {
+                let num = self.argument();
+                let den = self.argument();
+                out.push_str(&fraction(&num, &den));
+            }
+            "sqrt" => {
+                let index = self.optional_argument();
+                let radicand = self.argument();
+                let root = match index.as_deref() {
+                    None | Some("2") => "√".to_string(),
+                    Some("3") => "∛".to_string(),
+                    Some("4") => "∜".to_string(),
+                    Some(index) => format!("{}√", map_all(index, superscript).unwrap_or_default()),
+                };
+                out.push_str(&root);
+                out.push_str(&grouped(&radicand));
+            }
+            "text" | "textrm" | "mathrm" | "operatorname" | "textit" | "mathit" | "textbf"
+            | "mathbf" => out.push_str(&self.raw_argument()),
+            "mathbb" | "mathcal" | "mathfrak" => {
+                let arg = self.argument();
+                let table = match name.as_str() {
+                    "mathbb" => blackboard,
+                    "mathcal" => calligraphic,
+                    _ => fraktur,
+                };
+                out.extend(arg.chars().map(|ch| table(ch).unwrap_or(ch)));
+            }
+            "left" | "right" | "big" | "Big" | "bigg" | "Bigg" | "displaystyle" | "limits" => {
+                // Sizing only; the delimiter that follows is kept as is.
+            }
+            "quad" | "qquad" => out.push_str("  "),
+            "over" => {
+                let num = std::mem::take(out);
+                let mut den = String::new();
+                self.expression(&mut den, None);
+                out.push_str(&fraction(num.trim(), den.trim()));
+            }
+            name if FUNCTIONS.contains(&name) => {
+                if out.chars().last().is_some_and(|ch| ch.is_alphanumeric()) {
+                    out.push(' ');
+                }
+                out.push_str(name);
+                out.push(' ');
+            }
+            name => match symbol(name) {
+                Some((symbol, true)) => push_operator(out, symbol),
+                Some((symbol, false)) => out.push_str(symbol),
+                None => {
+                    out.push('\\');
+                    out.push_str(name);
+                }
+            },
+        }
+    }
+}
+
+const FUNCTIONS: &[&str] = &[
+    "sin", "cos", "tan", "cot", "sec", "csc", "arcsin", "arccos", "arctan", "sinh", "cosh", "tanh",
+    "log", "ln", "lg", "exp", "lim", "min", "max", "sup", "inf", "det", "dim", "ker", "deg", "gcd",
+    "arg", "Pr",
+];
+
+/// Operators and relations get spaced like TeX would; other symbols are set tight.
+fn push_operator(out: &mut String, symbol: &str) {
+    let unary = out
+        .trim_end()
+        .chars()
+        .last()
+        .is_none_or(|ch| "([{|,=<>+−±×⋅".contains(ch));
+    if unary && matches!(symbol, "−" | "+" | "±" | "∓") {
+        out.push_str(symbol);
+        return;
+    }
+    let trimmed = out.trim_end().len();
+    out.truncate(trimmed);
+    if !out.is_empty() {
+        out.push(' ');
+    }
+    out.push_str(symbol);
+    out.push(' ');
+}
+
+fn push_script(out: &mut String, arg: &str, table: fn(char) -> Option<char>, marker: char) {
+    let trimmed = out.trim_end().len();
+    out.truncate(trimmed);
+    let compact: String = arg.chars().filter(|ch| !ch.is_whitespace()).collect();
+    match map_all(&compact, table) {
+        Some(mapped) => out.push_str(&mapped),
+        None if compact.chars().count() <= 1 => {
+            out.push(marker);
+            out.push_str(&compact);
+        }
+        None => {
+            out.push(marker);
+            out.push('(');
+            out.push_str(arg);
+            out.push(')');
+        }
+    }
+}
+
+fn fraction(num: &str, den: &str) -> String {
+    if let (Some(num), Some(den)) = (map_all(num, superscript), map_all(den, subscript))
+        && num.chars().count() <= 2
+        && den.chars().count() <= 2
+    {
+        return format!("{num}⁄{den}");
+    }
+    format!("{}/{}", grouped(num), grouped(den))
+}
+
+/// Parenthesizes anything longer than a single atom.
+fn grouped(text: &str) -> String {
+    let atomic = text.chars().count() <= 1
+        || text.chars().all(|ch| ch.is_alphanumeric() || ch == '.')
+        || (text.starts_with('(') && text.ends_with(')'));
+    if atomic {
+        text.to_string()
+    } else {
+        format!("({text})")
+    }
+}
+
+fn map_all(text: &str, table: fn(char) -> Option<char>) -> Option<String> {
+    if text.is_empty() {
+        return None;
+    }
+    text.chars().map(table).collect()
+}
+
+fn superscript(ch: char) -> Option<char> {
+    Some(match ch {
+        '0' => '⁰',
+        '1' => '¹',
+        '2' => '²',
+        '3' => '³',
+        '4' => '⁴',
+        '5' => '⁵',
+        '6' => '⁶',
+        '7' => '⁷',
+        '8' => '⁸',
+        '9' => '⁹',
+        '+' => '⁺',
+        '−' | '-' => '⁻',
+        '=' => '⁼',
+        '(' => '⁽',
+        ')' => '⁾',
+        'a' => 'ᵃ',
+        'b' => 'ᵇ',
+        'c' => 'ᶜ',
+        'd' => 'ᵈ',
+        'e' => 'ᵉ',
+        'f' => 'ᶠ',
+        'g' => 'ᵍ',
+        'h' => 'ʰ',
+        'i' => 'ⁱ',
+        'j' => 'ʲ',
+        'k' => 'ᵏ',
+        'l' => 'ˡ',
+        'm' => 'ᵐ',
+        'n' => 'ⁿ',
+        'o' => 'ᵒ',
+        'p' => 'ᵖ',
+        'r' => 'ʳ',
+        's' => 'ˢ',
+        't' => 'ᵗ',
+        'u' => 'ᵘ',
+        'v' => 'ᵛ',
+        'w' => 'ʷ',
+        'x' => 'ˣ',
+        'y' => 'ʸ',
+        'z' => 'ᶻ',
+        'T' => 'ᵀ',
+        '′' => '′',
+        '∗' | '*' => '*',
+        _ => return None,
+    })
+}
+
+fn subscript(ch: char) -> Option<char> {
+    Some(match ch {
+        '0' => '₀',
+        '1' => '₁',
+        '2' => '₂',
+        '3' => '₃',
+        '4' => '₄',
+        '5' => '₅',
+        '6' => '₆',
+        '7' => '₇',
+        '8' => '₈',
+        '9' => '₉',
+        '+' => '₊',
+        '−' | '-' => '₋',
+        '=' => '₌',
+        '(' => '₍',
+        ')' => '₎',
+        'a' => 'ₐ',
+        'e' => 'ₑ',
+        'h' => 'ₕ',
+        'i' => 'ᵢ',
+        'j' => 'ⱼ',
+        'k' => 'ₖ',
+        'l' => 'ₗ',
+        'm' => 'ₘ',
+        'n' => 'ₙ',
+        'o' => 'ₒ',
+        'p' => 'ₚ',
+        'r' => 'ᵣ',
+        's' => 'ₛ',
+        't' => 'ₜ',
+        'u' => 'ᵤ',
+        'v' => 'ᵥ',
+        'x' => 'ₓ',
+        _ => return None,
+    })
+}
+
+fn blackboard(ch: char) -> Option<char> {
+    Some(match ch {
+        'C' => 'ℂ',
+        'H' => 'ℍ',
+        'N' => 'ℕ',
+        'P' => 'ℙ',
+        'Q' => 'ℚ',
+        'R' => 'ℝ',
+        'Z' => 'ℤ',
+        'A'..='Z' => char::from_u32(0x1D538 + (ch as u32 - 'A' as u32))?,
+        _ => return None,
+    })
+}
+
+fn calligraphic(ch: char) -> Option<char> {
+    Some(match ch {
+        'B' => 'ℬ',
+        'E' => 'ℰ',
+        'F' => 'ℱ',
+        'H' => 'ℋ',
+        'I' => 'ℐ',
+        'L' => 'ℒ',
+        'M' => 'ℳ',
+        'R' => 'ℛ',
+        'A'..='Z' => char::from_u32(0x1D49C + (ch as u32 - 'A' as u32))?,
+        _ => return None,
+    })
+}
+
+fn fraktur(ch: char) -> Option<char> {
+    Some(match ch {
+        'C' => 'ℭ',
+        'H' => 'ℌ',
+        'I' => 'ℑ',
+        'R' => 'ℜ',
+        'Z' => 'ℨ',
+        'A'..='Z' => char::from_u32(0x1D504 + (ch as u32 - 'A' as u32))?,
+        _ => return None,
+    })
+}
+
+/// `(glyph, spaced)` for a named symbol; spaced symbols are binary operators or relations.
+fn symbol(name: &str) -> Option<(&'static str, bool)> {
+    let letter = |s| Some((s, false));
+    let op = |s| Some((s, true));
+    match name {
+        "alpha" => letter("α"),
+        "beta" => letter("β"),
+        "gamma" => letter("γ"),
+        "delta" => letter("δ"),
+        "epsilon" => letter("ϵ"),
+        "varepsilon" => letter("ε"),
+        "zeta" => letter("ζ"),
+        "eta" => letter("η"),
+        "theta" => letter("θ"),
+        "vartheta" => letter("ϑ"),
+        "iota" => letter("ι"),
+        "kappa" => letter("κ"),
+        "lambda" => letter("λ"),
+        "mu" => letter("μ"),
+        "nu" => letter("ν"),
+        "xi" => letter("ξ"),
+        "pi" => letter("π"),
+        "varpi" => letter("ϖ"),
+        "rho" => letter("ρ"),
+        "varrho" => letter("ϱ"),
+        "sigma" => letter("σ"),
+        "varsigma" => letter("ς"),
+        "tau" => letter("τ"),
+        "upsilon" => letter("υ"),
+        "phi" => letter("ϕ"),
+        "varphi" => letter("φ"),
+        "chi" => letter("χ"),
+        "psi" => letter("ψ"),
+        "omega" => letter("ω"),
+        "Gamma" => letter("Γ"),
+        "Delta" => letter("Δ"),
+        "Theta" => letter("Θ"),
+        "Lambda" => letter("Λ"),
+        "Xi" => letter("Ξ"),
+        "Pi" => letter("Π"),
+        "Sigma" => letter("Σ"),
+        "Upsilon" => letter("Υ"),
+        "Phi" => letter("Φ"),
+        "Psi" => letter("Ψ"),
+        "Omega" => letter("Ω"),
+        "infty" => letter("∞"),
+        "partial" => letter("∂"),
+        "nabla" => letter("∇"),
+        "forall" => letter("∀"),
+        "exists" => letter("∃"),
+        "emptyset" | "varnothing" => letter("∅"),
+        "hbar" => letter("ℏ"),
+        "ell" => letter("ℓ"),
+        "prime" => letter("′"),
+        "degree" => letter("°"),
+        "neg" | "lnot" => letter("¬"),
+        "sum" => letter("∑"),
+        "prod" => letter("∏"),
+        "coprod" => letter("∐"),
+        "int" => letter("∫"),
+        "iint" => letter("∬"),
+        "iiint" => letter("∭"),
+        "oint" => letter("∮"),
+        "ldots" | "dots" => letter("…"),
+        "cdots" => letter("⋯"),
+        "vdots" => letter("⋮"),
+        "ddots" => letter("⋱"),
+        "langle" => letter("⟨"),
+        "rangle" => letter("⟩"),
+        "lfloor" => letter("⌊"),
+        "rfloor" => letter("⌋"),
+        "lceil" => letter("⌈"),
+        "rceil" => letter("⌉"),
+        "vert" | "lvert" | "rvert" => letter("|"),
+        "Vert" | "lVert" | "rVert" => letter("‖"),
+        "times" => op("×"),
+        "cdot" => op("⋅"),
+        "div" => op("÷"),
+        "pm" => op("±"),
+        "mp" => op("∓"),
+        "ast" => op("∗"),
+        "circ" => op("∘"),
+        "oplus" => op("⊕"),
+        "otimes" => op("⊗"),
+        "cup" => op("∪"),
+        "cap" => op("∩"),
+        "setminus" => op("∖"),
+        "wedge" | "land" => op("∧"),
+        "vee" | "lor" => op("∨"),
+        "leq" | "le" => op("≤"),
+        "geq" | "ge" => op("≥"),
+        "neq" | "ne" => op("≠"),
+        "ll" => op("≪"),
+        "gg" => op("≫"),
+        "approx" => op("≈"),
+        "equiv" => op("≡"),
+        "cong" => op("≅"),
+        "sim" => op("∼"),
+        "simeq" => op("≃"),
+        "propto" => op("∝"),
+        "in" => op("∈"),
+        "notin" => op("∉"),
+        "ni" => op("∋"),
+        "subset" => op("⊂"),
+        "supset" => op("⊃"),
+        "subseteq" => op("⊆"),
+        "supseteq" => op("⊇"),
+        "mid" => op("∣"),
+        "parallel" => op("∥"),
+        "perp" => op("⊥"),
+        "to" | "rightarrow" => op("→"),
+        "leftarrow" | "gets" => op("←"),
+        "leftrightarrow" => op("↔"),
+        "Rightarrow" | "implies" => op("⇒"),
+        "Leftarrow" => op("⇐"),
+        "Leftrightarrow" | "iff" => op("⇔"),
+        "mapsto" => op("↦"),
+        "uparrow" => letter("↑"),
+        "downarrow" => letter("↓"),
+        _ => None,
+    }
+}
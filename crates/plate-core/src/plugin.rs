@@ -217,11 +217,13 @@ impl PluginRegistry {
             Box::new(TodoPlugin),
             Box::new(IndentPlugin),
             Box::new(AlignPlugin),
+            Box::new(DirectionPlugin),
             Box::new(FontSizePlugin),
             Box::new(ListPlugin),
             Box::new(TablePlugin),
             Box::new(MentionPlugin),
             Box::new(EmojiPlugin),
+            Box::new(MathPlugin),
             Box::new(ImagePlugin),
         ];
         Self::new(plugins).expect("richtext registry must be valid")
@@ -2314,9 +2316,11 @@ impl NormalizePass for NormalizeAlignAttrs {
                 return;
             };
 
+            // `left` is the default, except in a right-to-left block where it has to be
+            // spelled out.
             let should_remove = match value.as_str() {
                 Some("center") | Some("right") => !element_is_text_block(el, registry),
-                Some("left") => true,
+                Some("left") => !element_is_text_block(el, registry) || !element_is_rtl(el),
                 _ => true,
             };
 
@@ -2358,6 +2362,103 @@ impl NormalizePass for NormalizeAlignAttrs {
     }
 }
 
+struct DirectionPlugin;
+
+impl PlatePlugin for DirectionPlugin {
+    fn id(&self) -> &'static str {
+        "direction"
+    }
+
+    fn normalize_passes(&self) -> Vec<Box<dyn NormalizePass>> {
+        vec![Box::new(NormalizeDirAttrs)]
+    }
+
+    fn commands(&self) -> Vec<CommandSpec> {
+        vec![
+            CommandSpec::new("block.set_dir", "Set text direction", |editor, args| {
+                let dir = args
+                    .as_ref()
+                    .and_then(|v| v.get("dir"))
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| CommandError::new("Missing args.dir"))?
+                    .to_string();
+
+                set_block_dir(editor, dir)
+                    .map_err(CommandError::new)
+                    .and_then(|tx| {
+                        if tx.ops.is_empty() {
+                            return Ok(());
+                        }
+                        editor.apply(tx).map_err(|e| {
+                            CommandError::new(format!("Failed to set direction: {e:?}"))
+                        })
+                    })
+            })
+            .description(
+                "Set the text direction of the active block(s); `auto` follows the first strong character.",
+            )
+            .keywords(["direction", "rtl", "ltr", "bidi", "arabic", "hebrew"])
+            .args_example(serde_json::json!({ "dir": "rtl" })),
+        ]
+    }
+
+    fn queries(&self) -> Vec<QuerySpec> {
+        vec![QuerySpec {
+            id: "block.dir".to_string(),
+            handler: std::sync::Arc::new(|editor, _args| Ok(active_block_dir(editor))),
+        }]
+    }
+}
+
+struct NormalizeDirAttrs;
+
+impl NormalizePass for NormalizeDirAttrs {
+    fn id(&self) -> &'static str {
+        "block.normalize_dir_attrs"
+    }
+
+    fn run(&self, doc: &Document, registry: &PluginRegistry) -> Vec<Op> {
+        let mut ops = Vec::new();
+
+        fn walk(
+            nodes: &[Node],
+            path: &mut Vec<usize>,
+            registry: &PluginRegistry,
+            ops: &mut Vec<Op>,
+        ) {
+            for (ix, node) in nodes.iter().enumerate() {
+                let Node::Element(el) = node else {
+                    continue;
+                };
+
+                path.push(ix);
+                let is_text_block = element_is_text_block(el, registry);
+                if let Some(value) = el.attrs.get("dir") {
+                    let valid = matches!(value.as_str(), Some("ltr") | Some("rtl"));
+                    if !valid || !is_text_block {
+                        ops.push(Op::SetNodeAttrs {
+                            path: path.clone(),
+                            patch: crate::core::AttrPatch {
+                                set: Attrs::default(),
+                                remove: vec!["dir".to_string()],
+                            },
+                        });
+                    }
+                }
+
+                if !is_text_block {
+                    walk(&el.children, path, registry, ops);
+                }
+
+                path.pop();
+            }
+        }
+
+        walk(&doc.children, &mut Vec::new(), registry, &mut ops);
+        ops
+    }
+}
+
 struct FontSizePlugin;
 
 impl PlatePlugin for FontSizePlugin {
@@ -2660,6 +2761,234 @@ impl PlatePlugin for EmojiPlugin {
     }
 }
 
+struct MathPlugin;
+
+impl PlatePlugin for MathPlugin {
+    fn id(&self) -> &'static str {
+        "math"
+    }
+
+    fn node_specs(&self) -> Vec<NodeSpec> {
+        vec![
+            NodeSpec {
+                kind: "math_inline".to_string(),
+                role: NodeRole::Inline,
+                is_void: true,
+                children: ChildConstraint::None,
+            },
+            NodeSpec {
+                kind: "math_block".to_string(),
+                role: NodeRole::Block,
+                is_void: true,
+                children: ChildConstraint::None,
+            },
+        ]
+    }
+
+    fn transaction_transforms(&self) -> Vec<Box<dyn TransactionTransform>> {
+        vec![Box::new(MathInputRule)]
+    }
+
+    fn commands(&self) -> Vec<CommandSpec> {
+        fn tex_arg(args: Option<&Value>) -> Result<String, CommandError> {
+            args.and_then(|v| v.get("tex"))
+                .and_then(|v| v.as_str())
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+                .ok_or_else(|| CommandError::new("Missing args.tex"))
+        }
+
+        vec![
+            CommandSpec::new(
+                "math.insert_inline",
+                "Insert inline math",
+                |editor, args| {
+                    let tex = tex_arg(args.as_ref())?;
+                    insert_math_inline(editor, tex)
+                        .map_err(CommandError::new)
+                        .and_then(|tx| {
+                            editor.apply(tx).map_err(|e| {
+                                CommandError::new(format!("Failed to insert math: {e:?}"))
+                            })
+                        })
+                },
+            )
+            .description("Insert an inline TeX math node at the caret.")
+            .keywords(["math", "latex", "tex", "formula", "equation", "inline"])
+            .args_example(serde_json::json!({ "tex": "e^{i\\pi} + 1 = 0" })),
+            CommandSpec::new("math.insert_block", "Insert math block", |editor, args| {
+                let tex = tex_arg(args.as_ref())?;
+                insert_math_block(editor, tex)
+                    .map_err(CommandError::new)
+                    .and_then(|tx| {
+                        editor.apply(tx).map_err(|e| {
+                            CommandError::new(format!("Failed to insert math block: {e:?}"))
+                        })
+                    })
+            })
+            .description("Insert a display TeX math block (void) after the current block.")
+            .keywords(["math", "latex", "tex", "formula", "equation", "block"])
+            .args_example(serde_json::json!({ "tex": "\\sum_{k=1}^{n} k = \\frac{n(n+1)}{2}" })),
+            CommandSpec::new("math.set_tex", "Set math source", |editor, args| {
+                let path = parse_path_arg(args.as_ref())
+                    .ok_or_else(|| CommandError::new("Missing args.path"))?;
+                let tex = tex_arg(args.as_ref())?;
+                set_math_tex(editor, path, tex)
+                    .map_err(CommandError::new)
+                    .and_then(|tx| {
+                        editor.apply(tx).map_err(|e| {
+                            CommandError::new(format!("Failed to set math source: {e:?}"))
+                        })
+                    })
+            })
+            .description("Replace the TeX source of the math node at args.path.")
+            .keywords(["math", "latex", "tex", "edit"])
+            .args_example(serde_json::json!({ "path": [0, 1], "tex": "x^2" })),
+            CommandSpec::new("math.edit_source", "Edit math source", |editor, args| {
+                let path = parse_path_arg(args.as_ref())
+                    .ok_or_else(|| CommandError::new("Missing args.path"))?;
+                edit_math_source(editor, path)
+                    .map_err(CommandError::new)
+                    .and_then(|tx| {
+                        editor.apply(tx).map_err(|e| {
+                            CommandError::new(format!("Failed to edit math source: {e:?}"))
+                        })
+                    })
+            })
+            .description(
+                "Turn the math node at args.path back into its source text (`$tex` or `$$tex`), \
+                 caret at the end; typing the closing delimiter renders it again.",
+            )
+            .keywords(["math", "latex", "tex", "edit", "source"])
+            .args_example(serde_json::json!({ "path": [0, 1] })),
+        ]
+    }
+}
+
+/// Input rules for math: a closing `$` turns `$tex$` into an inline math node, and a paragraph
+/// that reads `$$tex$$` becomes a math block.
+struct MathInputRule;
+
+impl TransactionTransform for MathInputRule {
+    fn id(&self) -> &'static str {
+        "math.input_rule"
+    }
+
+    fn transform(&self, editor: &crate::core::Editor, tx: &Transaction) -> Option<Transaction> {
+        if tx.meta.source.as_deref()? != "ime:replace_text" {
+            return None;
+        }
+
+        let preview = editor.preview_transaction(tx).ok()?;
+        if !preview.selection.is_collapsed() {
+            return None;
+        }
+        let focus = &preview.selection.focus;
+        let (child_ix, block_path) = focus.path.split_last()?;
+        let Node::Element(block) = node_at_path(&preview.doc, block_path)? else {
+            return None;
+        };
+        if block.kind == "code_block" || !element_is_text_block(block, editor.registry()) {
+            return None;
+        }
+        let Some(Node::Text(text)) = block.children.get(*child_ix) else {
+            return None;
+        };
+        if text.marks.code {
+            return None;
+        }
+        let caret = clamp_to_char_boundary(&text.text, focus.offset);
+        let before = &text.text[..caret];
+        let body = before.strip_suffix('$')?;
+
+        let mut ops = tx.ops.clone();
+        let selection_after;
+
+        let block_tex =
+            (block.kind == "paragraph" && block.children.len() == 1 && caret == text.text.len())
+                .then(|| before.strip_prefix("$$")?.strip_suffix("$$"))
+                .flatten()
+                .filter(|tex| !tex.trim().is_empty());
+
+        if let Some(tex) = block_tex {
+            ops.push(Op::RemoveNode {
+                path: block_path.to_vec(),
+            });
+            ops.push(Op::InsertNode {
+                path: block_path.to_vec(),
+                node: Node::math_block(tex.trim()),
+            });
+
+            let (block_ix, parent_path) = block_path.split_last()?;
+            let mut next_path = parent_path.to_vec();
+            next_path.push(block_ix + 1);
+            let next_has_text = matches!(
+                node_at_path(&preview.doc, &next_path),
+                Some(Node::Element(el)) if matches!(el.children.first(), Some(Node::Text(_)))
+            );
+            if !next_has_text {
+                ops.push(Op::InsertNode {
+                    path: next_path.clone(),
+                    node: Node::paragraph(""),
+                });
+            }
+            next_path.push(0);
+            selection_after = Selection::collapsed(Point::new(next_path, 0));
+        } else {
+            let open = body.rfind('$')?;
+            let tex = &body[open + 1..];
+            // Pandoc's rule keeps prices like "$5 and $6" as text: no space inside the
+            // delimiters, and no escaped or doubled dollar signs.
+            if tex.is_empty()
+                || tex.starts_with(char::is_whitespace)
+                || tex.ends_with(char::is_whitespace)
+                || tex.ends_with('\\')
+                || body[..open].ends_with(['$', '\\'])
+            {
+                return None;
+            }
+
+            let left = &body[..open];
+            let right = &text.text[caret..];
+            let mut children: Vec<Node> = block.children[..*child_ix].to_vec();
+            if !left.is_empty() {
+                children.push(Node::Text(TextNode {
+                    text: left.to_string(),
+                    marks: text.marks.clone(),
+                }));
+            }
+            children.push(Node::math_inline(tex));
+            let caret_ix = children.len();
+            children.push(Node::Text(TextNode {
+                text: right.to_string(),
+                marks: text.marks.clone(),
+            }));
+            children.extend(block.children[child_ix + 1..].iter().cloned());
+
+            ops.push(Op::RemoveNode {
+                path: block_path.to_vec(),
+            });
+            ops.push(Op::InsertNode {
+                path: block_path.to_vec(),
+                node: Node::Element(ElementNode {
+                    kind: block.kind.clone(),
+                    attrs: block.attrs.clone(),
+                    children,
+                }),
+            });
+            let mut caret_path = block_path.to_vec();
+            caret_path.push(caret_ix);
+            selection_after = Selection::collapsed(Point::new(caret_path, 0));
+        }
+
+        let mut out = Transaction::new(ops);
+        out.meta = tx.meta.clone();
+        out.selection_after = Some(selection_after);
+        Some(out)
+    }
+}
+
 struct ImagePlugin;
 
 impl PlatePlugin for ImagePlugin {
@@ -3201,6 +3530,29 @@ fn active_block_align(editor: &crate::core::Editor) -> Value {
     }
 }
 
+fn element_is_rtl(el: &ElementNode) -> bool {
+    el.attrs.get("dir").and_then(|v| v.as_str()) == Some("rtl")
+}
+
+fn active_block_dir(editor: &crate::core::Editor) -> Value {
+    let focus = &editor.selection().focus;
+    let Some(block_path) = focus.path.split_last().map(|(_, p)| p) else {
+        return Value::Null;
+    };
+    let Some(Node::Element(el)) = node_at_path(editor.doc(), block_path) else {
+        return Value::Null;
+    };
+    if !element_is_text_block(el, editor.registry()) {
+        return Value::Null;
+    }
+
+    match el.attrs.get("dir").and_then(|v| v.as_str()) {
+        Some("ltr") => Value::String("ltr".to_string()),
+        Some("rtl") => Value::String("rtl".to_string()),
+        _ => Value::Null,
+    }
+}
+
 fn active_block_font_size(editor: &crate::core::Editor) -> Value {
     let focus = &editor.selection().focus;
     let Some(block_path) = focus.path.split_last().map(|(_, p)| p) else {
@@ -3356,7 +3708,7 @@ fn set_block_align(editor: &mut crate::core::Editor, align: String) -> Result<Tr
     for block in blocks.iter().take(b + 1).skip(a) {
         let el = block.el;
 
-        if align == "left" {
+        if align == "left" && !element_is_rtl(el) {
             if el.attrs.get("align").is_some() {
                 ops.push(Op::SetNodeAttrs {
                     path: block.path.clone(),
@@ -3389,6 +3741,72 @@ fn set_block_align(editor: &mut crate::core::Editor, align: String) -> Result<Tr
         .source("command:block.set_align"))
 }
 
+/// `auto` removes the attribute so the block follows its first strong character.
+fn set_block_dir(editor: &mut crate::core::Editor, dir: String) -> Result<Transaction, String> {
+    let dir = match dir.as_str() {
+        "ltr" | "rtl" => Some(dir),
+        "auto" => None,
+        _ => return Err("Invalid dir value".into()),
+    };
+
+    let sel = editor.selection().clone();
+    let selection_after = sel.clone();
+    let (start, end) = ordered_selection_points(&sel);
+    let Some(start_block_path) = start.path.split_last().map(|(_, p)| p.to_vec()) else {
+        return Err("Selection start is not in a text block".into());
+    };
+    let Some(end_block_path) = end.path.split_last().map(|(_, p)| p.to_vec()) else {
+        return Err("Selection end is not in a text block".into());
+    };
+
+    let blocks = text_blocks_in_order(editor.doc(), editor.registry());
+    let start_index = blocks
+        .iter()
+        .position(|b| b.path == start_block_path)
+        .ok_or_else(|| "Selection start is not in a text block".to_string())?;
+    let end_index = blocks
+        .iter()
+        .position(|b| b.path == end_block_path)
+        .ok_or_else(|| "Selection end is not in a text block".to_string())?;
+
+    let (a, b) = if start_index <= end_index {
+        (start_index, end_index)
+    } else {
+        (end_index, start_index)
+    };
+
+    let mut ops: Vec<Op> = Vec::new();
+    for block in blocks.iter().take(b + 1).skip(a) {
+        let current = block.el.attrs.get("dir").and_then(|v| v.as_str());
+        if current == dir.as_deref() {
+            continue;
+        }
+
+        let patch = match &dir {
+            Some(dir) => {
+                let mut set = Attrs::default();
+                set.insert("dir".to_string(), Value::String(dir.clone()));
+                crate::core::AttrPatch {
+                    set,
+                    remove: Vec::new(),
+                }
+            }
+            None => crate::core::AttrPatch {
+                set: Attrs::default(),
+                remove: vec!["dir".to_string()],
+            },
+        };
+        ops.push(Op::SetNodeAttrs {
+            path: block.path.clone(),
+            patch,
+        });
+    }
+
+    Ok(Transaction::new(ops)
+        .selection_after(selection_after)
+        .source("command:block.set_dir"))
+}
+
 fn set_block_font_size(
     editor: &mut crate::core::Editor,
     size: Option<u64>,
@@ -5992,6 +6410,184 @@ fn insert_images(
         .source("command:image.insert_many"))
 }
 
+fn insert_math_inline(editor: &crate::core::Editor, tex: String) -> Result<Transaction, String> {
+    let sel = editor.selection().clone();
+    if !sel.is_collapsed() {
+        return Err("Selection must be collapsed".into());
+    }
+
+    let focus = sel.focus;
+    let (child_ix, block_path) = focus
+        .path
+        .split_last()
+        .ok_or_else(|| "Selection is not in a text node".to_string())?;
+    let Some(Node::Element(el)) = node_at_path(editor.doc(), block_path) else {
+        return Err("Selection is not in a text block".into());
+    };
+    let Some(Node::Text(text)) = el.children.get(*child_ix) else {
+        return Err("Selection is not in a text node".into());
+    };
+
+    let cursor = clamp_to_char_boundary(&text.text, focus.offset);
+    let left = text.text[..cursor].to_string();
+    let right = text.text[cursor..].to_string();
+
+    let mut replacement: Vec<Node> = Vec::new();
+    if !left.is_empty() {
+        replacement.push(Node::Text(TextNode {
+            text: left,
+            marks: text.marks.clone(),
+        }));
+    }
+    replacement.push(Node::math_inline(tex));
+    let caret_ix = child_ix + replacement.len();
+    replacement.push(Node::Text(TextNode {
+        text: right,
+        marks: text.marks.clone(),
+    }));
+
+    let mut ops = vec![Op::RemoveNode {
+        path: focus.path.clone(),
+    }];
+    for (i, node) in replacement.into_iter().enumerate() {
+        let mut path = block_path.to_vec();
+        path.push(child_ix + i);
+        ops.push(Op::InsertNode { path, node });
+    }
+
+    let mut caret_path = block_path.to_vec();
+    caret_path.push(caret_ix);
+    Ok(Transaction::new(ops)
+        .selection_after(Selection::collapsed(Point::new(caret_path, 0)))
+        .source("command:math.insert_inline"))
+}
+
+fn insert_math_block(editor: &crate::core::Editor, tex: String) -> Result<Transaction, String> {
+    let focus = editor.selection().focus.clone();
+    let block_path = focus.path.split_last().map(|(_, p)| p).unwrap_or(&[]);
+
+    let (parent_path, insert_at) = if block_path.is_empty() {
+        (Vec::new(), editor.doc().children.len())
+    } else {
+        let (block_ix, parent) = block_path.split_last().unwrap();
+        (parent.to_vec(), block_ix + 1)
+    };
+
+    let mut math_path = parent_path.clone();
+    math_path.push(insert_at);
+    let mut paragraph_path = parent_path;
+    paragraph_path.push(insert_at + 1);
+    let mut caret_path = paragraph_path.clone();
+    caret_path.push(0);
+
+    Ok(Transaction::new(vec![
+        Op::InsertNode {
+            path: math_path,
+            node: Node::math_block(tex),
+        },
+        Op::InsertNode {
+            path: paragraph_path,
+            node: Node::paragraph(""),
+        },
+    ])
+    .selection_after(Selection::collapsed(Point::new(caret_path, 0)))
+    .source("command:math.insert_block"))
+}
+
+fn math_node_tex(editor: &crate::core::Editor, path: &[usize]) -> Result<(String, String), String> {
+    match node_at_path(editor.doc(), path) {
+        Some(Node::Void(v)) if v.kind == "math_inline" || v.kind == "math_block" => {
+            let tex = v.attrs.get("tex").and_then(|v| v.as_str()).unwrap_or("");
+            Ok((v.kind.clone(), tex.to_string()))
+        }
+        _ => Err("No math node at path".into()),
+    }
+}
+
+fn set_math_tex(
+    editor: &crate::core::Editor,
+    path: Vec<usize>,
+    tex: String,
+) -> Result<Transaction, String> {
+    math_node_tex(editor, &path)?;
+    let mut set = Attrs::default();
+    set.insert("tex".to_string(), Value::String(tex));
+    Ok(Transaction::new(vec![Op::SetNodeAttrs {
+        path,
+        patch: crate::core::AttrPatch {
+            set,
+            remove: Vec::new(),
+        },
+    }])
+    .selection_after(editor.selection().clone())
+    .source("command:math.set_tex"))
+}
+
+fn edit_math_source(editor: &crate::core::Editor, path: Vec<usize>) -> Result<Transaction, String> {
+    let (kind, tex) = math_node_tex(editor, &path)?;
+
+    if kind == "math_block" {
+        let source = format!("$${tex}");
+        let mut caret_path = path.clone();
+        caret_path.push(0);
+        return Ok(Transaction::new(vec![
+            Op::RemoveNode { path: path.clone() },
+            Op::InsertNode {
+                path,
+                node: Node::paragraph(source.clone()),
+            },
+        ])
+        .selection_after(Selection::collapsed(Point::new(caret_path, source.len())))
+        .source("command:math.edit_source"));
+    }
+
+    // Inline source merges into the neighbouring text so it edits like any other run.
+    let (ix, block_path) = path
+        .split_last()
+        .ok_or_else(|| "No math node at path".to_string())?;
+    let Some(Node::Element(block)) = node_at_path(editor.doc(), block_path) else {
+        return Err("Math node is not in a text block".into());
+    };
+    let left = match ix.checked_sub(1).and_then(|i| block.children.get(i)) {
+        Some(Node::Text(t)) => Some(t),
+        _ => None,
+    };
+    let right = match block.children.get(ix + 1) {
+        Some(Node::Text(t)) => Some(t),
+        _ => None,
+    };
+    let marks = left.or(right).map(|t| t.marks.clone()).unwrap_or_default();
+    let right = right.filter(|t| t.marks == marks);
+
+    let start = if left.is_some() { ix - 1 } else { *ix };
+    let end = if right.is_some() { ix + 1 } else { *ix };
+    let left_text = left.map(|t| t.text.as_str()).unwrap_or("");
+    let caret = left_text.len() + 1 + tex.len();
+    let text = format!(
+        "{left_text}${tex}{}",
+        right.map(|t| t.text.as_str()).unwrap_or("")
+    );
+
+    let mut ops: Vec<Op> = (start..=end)
+        .rev()
+        .map(|i| {
+            let mut path = block_path.to_vec();
+            path.push(i);
+            Op::RemoveNode { path }
+        })
+        .collect();
+    let mut text_path = block_path.to_vec();
+    text_path.push(start);
+    ops.push(Op::InsertNode {
+        path: text_path.clone(),
+        node: Node::Text(TextNode { text, marks }),
+    });
+
+    Ok(Transaction::new(ops)
+        .selection_after(Selection::collapsed(Point::new(text_path, caret)))
+        .source("command:math.edit_source"))
+}
+
 fn apply_mark_range(
     editor: &crate::core::Editor,
     sel: &Selection,
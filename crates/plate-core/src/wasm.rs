@@ -0,0 +1,412 @@
+//! Plate plugins compiled to WebAssembly, run in a wasmtime sandbox.
+//!
+//! A plugin module gets no imports at all, so it can only compute: documents, transactions and
+//! ops go in and out as JSON through its linear memory. Each call runs on a fuel budget and the
+//! memory is capped, so a plugin that loops or grows without bound fails that call instead of
+//! stalling the editor.
+//!
+//! # ABI (version 1)
+//!
+//! The module exports `memory` and:
+//!
+//! - `plate_alloc(len: i32) -> i32` and `plate_dealloc(ptr: i32, len: i32)`, which the host uses
+//!   to pass input buffers.
+//! - `plate_manifest() -> i64`, returning a [`WasmManifest`] as JSON.
+//! - `plate_normalize(ptr: i32, len: i32) -> i64` when the manifest sets `normalize`: the input is
+//!   `{ "doc": Document }`, the output `{ "ops": [Op] }`.
+//! - `plate_transform(ptr: i32, len: i32) -> i64` when the manifest sets `transform`: the input
+//!   is `{ "doc", "selection", "tx" }`, the output `{ "tx": Transaction | null }`.
+//! - `plate_command(ptr: i32, len: i32) -> i64` when the manifest lists commands: the input is
+//!   `{ "command", "args", "doc", "selection" }`, the output `{ "tx": Transaction | null }` or
+//!   `{ "error": string }`.
+//!
+//! Functions returning `i64` return the output buffer as `ptr << 32 | len`; the host frees it
+//! with `plate_dealloc` once read.
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use wasmtime::{
+    Config, Engine, Instance, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc,
+};
+
+use crate::core::{Document, Editor, Selection};
+use crate::ops::{Op, Transaction};
+use crate::plugin::{
+    CommandError, CommandSpec, NodeSpec, NormalizePass, PlatePlugin, PluginRegistry,
+    TransactionTransform,
+};
+
+/// The ABI version this host implements.
+pub const WASM_PLUGIN_ABI: u32 = 1;
+
+/// Resources a plugin may use per call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WasmPluginLimits {
+    /// Fuel for one hook call; roughly one unit per wasm instruction.
+    pub fuel: u64,
+    /// Upper bound of the plugin's linear memory, in bytes.
+    pub memory_bytes: usize,
+}
+
+impl Default for WasmPluginLimits {
+    fn default() -> Self {
+        Self {
+            fuel: 50_000_000,
+            memory_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WasmCommand {
+    pub id: String,
+    pub label: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+/// What a plugin module declares about itself.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WasmManifest {
+    pub abi: u32,
+    pub id: String,
+    #[serde(default)]
+    pub node_specs: Vec<NodeSpec>,
+    #[serde(default)]
+    pub commands: Vec<WasmCommand>,
+    #[serde(default)]
+    pub normalize: bool,
+    #[serde(default)]
+    pub transform: bool,
+}
+
+#[derive(Serialize)]
+struct NormalizeInput<'a> {
+    doc: &'a Document,
+}
+
+#[derive(Deserialize)]
+struct NormalizeOutput {
+    #[serde(default)]
+    ops: Vec<Op>,
+}
+
+#[derive(Serialize)]
+struct TransformInput<'a> {
+    doc: &'a Document,
+    selection: &'a Selection,
+    tx: &'a Transaction,
+}
+
+#[derive(Serialize)]
+struct CommandInput<'a> {
+    command: &'a str,
+    args: Option<Value>,
+    doc: &'a Document,
+    selection: &'a Selection,
+}
+
+#[derive(Deserialize)]
+struct TransactionOutput {
+    #[serde(default)]
+    tx: Option<Transaction>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// An instantiated module and the exports the host calls.
+struct Sandbox {
+    store: Store<StoreLimits>,
+    instance: Instance,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    dealloc: TypedFunc<(i32, i32), ()>,
+    fuel: u64,
+}
+
+impl Sandbox {
+    fn new(bytes: &[u8], limits: WasmPluginLimits) -> Result<Self, String> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).map_err(|e| format!("Failed to start wasmtime: {e}"))?;
+        let module =
+            Module::new(&engine, bytes).map_err(|e| format!("Invalid plugin module: {e}"))?;
+        if let Some(import) = module.imports().next() {
+            return Err(format!(
+                "Plugin module imports {}::{}, but plugins get no imports",
+                import.module(),
+                import.name()
+            ));
+        }
+
+        let mut store = Store::new(
+            &engine,
+            StoreLimitsBuilder::new()
+                .memory_size(limits.memory_bytes)
+                .instances(1)
+                .build(),
+        );
+        store.limiter(|limits| limits);
+        store
+            .set_fuel(limits.fuel)
+            .map_err(|e| format!("Failed to set plugin fuel: {e}"))?;
+        let instance = Instance::new(&mut store, &module, &[])
+            .map_err(|e| format!("Failed to instantiate plugin: {e}"))?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| "Plugin module does not export `memory`".to_string())?;
+        let alloc = instance
+            .get_typed_func(&mut store, "plate_alloc")
+            .map_err(|e| format!("Plugin module does not export `plate_alloc`: {e}"))?;
+        let dealloc = instance
+            .get_typed_func(&mut store, "plate_dealloc")
+            .map_err(|e| format!("Plugin module does not export `plate_dealloc`: {e}"))?;
+
+        Ok(Self {
+            store,
+            instance,
+            memory,
+            alloc,
+            dealloc,
+            fuel: limits.fuel,
+        })
+    }
+
+    fn manifest(&mut self) -> Result<WasmManifest, String> {
+        self.refuel()?;
+        let manifest = self
+            .instance
+            .get_typed_func::<(), i64>(&mut self.store, "plate_manifest")
+            .map_err(|e| format!("Plugin module does not export `plate_manifest`: {e}"))?;
+        let packed = manifest
+            .call(&mut self.store, ())
+            .map_err(|e| format!("plate_manifest failed: {e}"))?;
+        let output = self.take_output(packed)?;
+        serde_json::from_slice(&output).map_err(|e| format!("Invalid plugin manifest: {e}"))
+    }
+
+    /// Calls the hook `export` with `input` serialized to JSON and parses its JSON output.
+    fn call<I: Serialize, O: DeserializeOwned>(
+        &mut self,
+        export: &str,
+        input: &I,
+    ) -> Result<O, String> {
+        self.refuel()?;
+        let input = serde_json::to_vec(input).map_err(|e| e.to_string())?;
+        let len = i32::try_from(input.len()).map_err(|_| "Plugin input too large".to_string())?;
+        let hook = self
+            .instance
+            .get_typed_func::<(i32, i32), i64>(&mut self.store, export)
+            .map_err(|e| format!("Plugin module does not export `{export}`: {e}"))?;
+
+        let ptr = self
+            .alloc
+            .call(&mut self.store, len)
+            .map_err(|e| format!("plate_alloc failed: {e}"))?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, &input)
+            .map_err(|e| format!("Plugin returned an invalid buffer: {e}"))?;
+        let packed = hook
+            .call(&mut self.store, (ptr, len))
+            .map_err(|e| format!("{export} failed: {e}"));
+        let _ = self.dealloc.call(&mut self.store, (ptr, len));
+
+        let output = self.take_output(packed?)?;
+        serde_json::from_slice(&output).map_err(|e| format!("Invalid output of {export}: {e}"))
+    }
+
+    fn take_output(&mut self, packed: i64) -> Result<Vec<u8>, String> {
+        let ptr = (packed as u64 >> 32) as u32;
+        let len = packed as u64 as u32;
+        let mut output = vec![0; len as usize];
+        self.memory
+            .read(&self.store, ptr as usize, &mut output)
+            .map_err(|e| format!("Plugin returned an invalid buffer: {e}"))?;
+        let _ = self.dealloc.call(&mut self.store, (ptr as i32, len as i32));
+        Ok(output)
+    }
+
+    fn refuel(&mut self) -> Result<(), String> {
+        self.store
+            .set_fuel(self.fuel)
+            .map_err(|e| format!("Failed to set plugin fuel: {e}"))
+    }
+}
+
+/// A plate plugin loaded from a WebAssembly module; register it like any other plugin.
+///
+/// Hooks that fail (a trap, running out of fuel, malformed output) do nothing: normalization
+/// yields no ops and transforms leave the transaction alone. Commands report the failure as a
+/// [`CommandError`].
+pub struct WasmPlugin {
+    id: &'static str,
+    manifest: WasmManifest,
+    sandbox: Arc<Mutex<Sandbox>>,
+}
+
+impl WasmPlugin {
+    pub fn new(bytes: &[u8]) -> Result<Self, String> {
+        Self::with_limits(bytes, WasmPluginLimits::default())
+    }
+
+    pub fn with_limits(bytes: &[u8], limits: WasmPluginLimits) -> Result<Self, String> {
+        let mut sandbox = Sandbox::new(bytes, limits)?;
+        let manifest = sandbox.manifest()?;
+        if manifest.abi != WASM_PLUGIN_ABI {
+            return Err(format!(
+                "Plugin {} targets ABI {}, this host implements {WASM_PLUGIN_ABI}",
+                manifest.id, manifest.abi
+            ));
+        }
+
+        Ok(Self {
+            // Plugin ids are `&'static str`; a loaded plugin lives as long as its registry.
+            id: Box::leak(manifest.id.clone().into_boxed_str()),
+            manifest,
+            sandbox: Arc::new(Mutex::new(sandbox)),
+        })
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let bytes =
+            std::fs::read(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        Self::new(&bytes)
+    }
+
+    pub fn manifest(&self) -> &WasmManifest {
+        &self.manifest
+    }
+}
+
+impl PlatePlugin for WasmPlugin {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn node_specs(&self) -> Vec<NodeSpec> {
+        self.manifest.node_specs.clone()
+    }
+
+    fn transaction_transforms(&self) -> Vec<Box<dyn TransactionTransform>> {
+        if !self.manifest.transform {
+            return Vec::new();
+        }
+        vec![Box::new(WasmTransform {
+            id: self.id,
+            sandbox: self.sandbox.clone(),
+        })]
+    }
+
+    fn normalize_passes(&self) -> Vec<Box<dyn NormalizePass>> {
+        if !self.manifest.normalize {
+            return Vec::new();
+        }
+        vec![Box::new(WasmNormalizePass {
+            id: self.id,
+            sandbox: self.sandbox.clone(),
+        })]
+    }
+
+    fn commands(&self) -> Vec<CommandSpec> {
+        self.manifest
+            .commands
+            .iter()
+            .map(|command| {
+                let sandbox = self.sandbox.clone();
+                let id = command.id.clone();
+                let source = self.id;
+                let spec = CommandSpec::new(command.id.clone(), command.label.clone(), {
+                    move |editor: &mut Editor, args: Option<Value>| {
+                        let output: TransactionOutput = sandbox
+                            .lock()
+                            .unwrap()
+                            .call(
+                                "plate_command",
+                                &CommandInput {
+                                    command: &id,
+                                    args,
+                                    doc: editor.doc(),
+                                    selection: editor.selection(),
+                                },
+                            )
+                            .map_err(CommandError::new)?;
+                        if let Some(error) = output.error {
+                            return Err(CommandError::new(error));
+                        }
+                        let Some(mut tx) = output.tx else {
+                            return Ok(());
+                        };
+                        if tx.meta.source.is_none() {
+                            tx.meta.source = Some(source.to_string());
+                        }
+                        editor
+                            .apply(tx)
+                            .map_err(|e| CommandError::new(format!("Failed to run {id}: {e:?}")))
+                    }
+                })
+                .keywords(command.keywords.clone())
+                .hidden(command.hidden);
+                match &command.description {
+                    Some(description) => spec.description(description.clone()),
+                    None => spec,
+                }
+            })
+            .collect()
+    }
+}
+
+struct WasmNormalizePass {
+    id: &'static str,
+    sandbox: Arc<Mutex<Sandbox>>,
+}
+
+impl NormalizePass for WasmNormalizePass {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn run(&self, doc: &Document, _registry: &PluginRegistry) -> Vec<Op> {
+        self.sandbox
+            .lock()
+            .unwrap()
+            .call::<_, NormalizeOutput>("plate_normalize", &NormalizeInput { doc })
+            .map(|output| output.ops)
+            .unwrap_or_default()
+    }
+}
+
+struct WasmTransform {
+    id: &'static str,
+    sandbox: Arc<Mutex<Sandbox>>,
+}
+
+impl TransactionTransform for WasmTransform {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn transform(&self, editor: &Editor, tx: &Transaction) -> Option<Transaction> {
+        self.sandbox
+            .lock()
+            .unwrap()
+            .call::<_, TransactionOutput>(
+                "plate_transform",
+                &TransformInput {
+                    doc: editor.doc(),
+                    selection: editor.selection(),
+                    tx,
+                },
+            )
+            .ok()?
+            .tx
+    }
+}
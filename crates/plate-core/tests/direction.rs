@@ -0,0 +1,119 @@
+use gpui_plate_core::{
+    Attrs, Document, Editor, ElementNode, Marks, Node, PluginRegistry, Point, Selection, TextNode,
+};
+
+fn dir_of(node: &Node) -> Option<&str> {
+    let Node::Element(el) = node else {
+        panic!("expected element block");
+    };
+    el.attrs.get("dir").and_then(|v| v.as_str())
+}
+
+#[test]
+fn dir_command_updates_attrs_and_query() {
+    let mut editor = Editor::with_richtext_plugins();
+
+    assert_eq!(
+        editor
+            .run_query::<Option<String>>("block.dir", None)
+            .unwrap(),
+        None
+    );
+
+    editor
+        .run_command("block.set_dir", Some(serde_json::json!({ "dir": "rtl" })))
+        .unwrap();
+    assert_eq!(
+        editor
+            .run_query::<Option<String>>("block.dir", None)
+            .unwrap(),
+        Some("rtl".to_string())
+    );
+    assert_eq!(dir_of(&editor.doc().children[0]), Some("rtl"));
+
+    editor
+        .run_command("block.set_dir", Some(serde_json::json!({ "dir": "auto" })))
+        .unwrap();
+    assert_eq!(
+        editor
+            .run_query::<Option<String>>("block.dir", None)
+            .unwrap(),
+        None
+    );
+    assert_eq!(dir_of(&editor.doc().children[0]), None);
+
+    assert!(
+        editor
+            .run_command("block.set_dir", Some(serde_json::json!({ "dir": "up" })))
+            .is_err()
+    );
+}
+
+#[test]
+fn dir_applies_across_multi_block_selection() {
+    let doc = Document {
+        children: vec![
+            Node::paragraph("a"),
+            Node::paragraph("b"),
+            Node::paragraph("c"),
+        ],
+    };
+    let selection = Selection {
+        anchor: Point::new(vec![2, 0], 1),
+        focus: Point::new(vec![1, 0], 0),
+    };
+    let mut editor = Editor::new(doc, selection, PluginRegistry::richtext());
+
+    editor
+        .run_command("block.set_dir", Some(serde_json::json!({ "dir": "ltr" })))
+        .unwrap();
+
+    let dirs: Vec<Option<&str>> = editor.doc().children.iter().map(dir_of).collect();
+    assert_eq!(dirs, [None, Some("ltr"), Some("ltr")]);
+}
+
+#[test]
+fn dir_normalize_removes_invalid_values() {
+    let mut attrs = Attrs::default();
+    attrs.insert("dir".to_string(), serde_json::json!("sideways"));
+    let doc = Document {
+        children: vec![Node::Element(ElementNode {
+            kind: "paragraph".to_string(),
+            attrs,
+            children: vec![Node::Text(TextNode {
+                text: "x".to_string(),
+                marks: Marks::default(),
+            })],
+        })],
+    };
+    let selection = Selection::collapsed(Point::new(vec![0, 0], 0));
+    let editor = Editor::new(doc, selection, PluginRegistry::richtext());
+
+    assert_eq!(dir_of(&editor.doc().children[0]), None);
+}
+
+#[test]
+fn explicit_left_align_is_kept_in_rtl_blocks() {
+    let mut editor = Editor::with_richtext_plugins();
+    editor
+        .run_command("block.set_dir", Some(serde_json::json!({ "dir": "rtl" })))
+        .unwrap();
+    editor
+        .run_command(
+            "block.set_align",
+            Some(serde_json::json!({ "align": "left" })),
+        )
+        .unwrap();
+    let Node::Element(el) = &editor.doc().children[0] else {
+        panic!("expected element block");
+    };
+    assert_eq!(el.attrs.get("align").and_then(|v| v.as_str()), Some("left"));
+
+    editor
+        .run_command("block.set_dir", Some(serde_json::json!({ "dir": "auto" })))
+        .unwrap();
+    let Node::Element(el) = &editor.doc().children[0] else {
+        panic!("expected element block");
+    };
+    assert_eq!(el.attrs.get("align"), None);
+}
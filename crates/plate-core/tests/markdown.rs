@@ -0,0 +1,125 @@
+use gpui_plate_core::{ElementNode, Node, markdown_to_document};
+
+fn element(node: &Node) -> &ElementNode {
+    let Node::Element(el) = node else {
+        panic!("expected element, got {node:?}");
+    };
+    el
+}
+
+fn text_of(el: &ElementNode) -> String {
+    el.children
+        .iter()
+        .filter_map(|child| match child {
+            Node::Text(text) => Some(text.text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn headings_paragraphs_and_marks() {
+    let doc =
+        markdown_to_document("# Title\n\nSome **bold** and `code` with a [link](https://a.b).\n");
+
+    assert_eq!(doc.children.len(), 2);
+    let heading = element(&doc.children[0]);
+    assert_eq!(heading.kind, "heading");
+    assert_eq!(heading.attrs.get("level").and_then(|v| v.as_u64()), Some(1));
+    assert_eq!(text_of(heading), "Title");
+
+    let para = element(&doc.children[1]);
+    assert_eq!(para.kind, "paragraph");
+    assert_eq!(text_of(para), "Some bold and code with a link.");
+    let marks: Vec<_> = para
+        .children
+        .iter()
+        .filter_map(|child| match child {
+            Node::Text(text) => Some((text.text.as_str(), text.marks.clone())),
+            _ => None,
+        })
+        .collect();
+    assert!(
+        marks
+            .iter()
+            .any(|(text, marks)| *text == "bold" && marks.bold)
+    );
+    assert!(
+        marks
+            .iter()
+            .any(|(text, marks)| *text == "code" && marks.code)
+    );
+    assert!(
+        marks.iter().any(|(text, marks)| {
+            *text == "link" && marks.link.as_deref() == Some("https://a.b")
+        })
+    );
+}
+
+#[test]
+fn lists_tasks_and_nesting() {
+    let doc = markdown_to_document("- one\n  1. inner\n- [x] done\n");
+
+    let kinds: Vec<_> = doc
+        .children
+        .iter()
+        .map(|node| element(node).kind.as_str())
+        .collect();
+    assert_eq!(kinds, ["list_item", "list_item", "todo_item"]);
+
+    let inner = element(&doc.children[1]);
+    assert_eq!(
+        inner.attrs.get("list_type").and_then(|v| v.as_str()),
+        Some("ordered")
+    );
+    assert_eq!(
+        inner.attrs.get("list_level").and_then(|v| v.as_u64()),
+        Some(1)
+    );
+
+    let todo = element(&doc.children[2]);
+    assert_eq!(
+        todo.attrs.get("checked").and_then(|v| v.as_bool()),
+        Some(true)
+    );
+    assert_eq!(text_of(todo), "done");
+}
+
+#[test]
+fn code_blocks_quotes_and_rules() {
+    let doc = markdown_to_document("```rust\nfn main() {}\n```\n\n> quoted\n\n---\n");
+
+    let code = element(&doc.children[0]);
+    assert_eq!(code.kind, "code_block");
+    assert_eq!(text_of(code), "fn main() {}");
+
+    let quote = element(&doc.children[1]);
+    assert_eq!(quote.kind, "blockquote");
+    assert_eq!(text_of(element(&quote.children[0])), "quoted");
+
+    let Node::Void(rule) = &doc.children[2] else {
+        panic!("expected divider");
+    };
+    assert_eq!(rule.kind, "divider");
+}
+
+#[test]
+fn tables_keep_rows_and_cells() {
+    let doc = markdown_to_document("| a | b |\n|---|---|\n| 1 |   |\n");
+
+    let table = element(&doc.children[0]);
+    assert_eq!(table.kind, "table");
+    assert_eq!(table.children.len(), 2);
+    let body = element(&table.children[1]);
+    assert_eq!(body.children.len(), 2);
+    let empty_cell = element(&body.children[1]);
+    assert_eq!(empty_cell.kind, "table_cell");
+    assert_eq!(empty_cell.children.len(), 1);
+}
+
+#[test]
+fn empty_input_yields_one_paragraph() {
+    let doc = markdown_to_document("");
+    assert_eq!(doc.children.len(), 1);
+    assert_eq!(element(&doc.children[0]).kind, "paragraph");
+}
@@ -0,0 +1,165 @@
+use gpui_plate_core::{
+    Document, Editor, ElementNode, Node, Op, PluginRegistry, Point, Selection, Transaction,
+    document_to_markdown, markdown_to_document, transliterate_tex,
+};
+
+fn element(node: &Node) -> &ElementNode {
+    let Node::Element(el) = node else {
+        panic!("expected element, got {node:?}");
+    };
+    el
+}
+
+fn tex_of(node: &Node) -> Option<(&str, &str)> {
+    match node {
+        Node::Void(v) => Some((v.kind.as_str(), v.attrs.get("tex")?.as_str()?)),
+        _ => None,
+    }
+}
+
+fn editor_with(text: &str) -> Editor {
+    let doc = Document {
+        children: vec![Node::paragraph(text)],
+    };
+    let selection = Selection::collapsed(Point::new(vec![0, 0], text.len()));
+    Editor::new(doc, selection, PluginRegistry::richtext())
+}
+
+fn type_text(editor: &mut Editor, text: &str) {
+    let focus = editor.selection().focus.clone();
+    let tx = Transaction::new(vec![Op::InsertText {
+        path: focus.path,
+        offset: focus.offset,
+        text: text.to_string(),
+    }])
+    .source("ime:replace_text");
+    editor.apply(tx).unwrap();
+}
+
+#[test]
+fn transliteration_covers_common_notation() {
+    assert_eq!(transliterate_tex("x^2 + y_1"), "x² + y₁");
+    assert_eq!(transliterate_tex(r"\alpha \leq \beta"), "α ≤ β");
+    assert_eq!(transliterate_tex(r"\frac{1}{2}"), "¹⁄₂");
+    assert_eq!(transliterate_tex(r"\frac{a+b}{c}"), "(a + b)/c");
+    assert_eq!(transliterate_tex(r"\sqrt{x}"), "√x");
+    assert_eq!(transliterate_tex(r"\sum_{i=1}^{n} i"), "∑ᵢ₌₁ⁿi");
+    assert_eq!(transliterate_tex(r"x \in \mathbb{R}"), "x ∈ ℝ");
+    assert_eq!(transliterate_tex(r"\text{if } x < -1"), "if x < −1");
+    assert_eq!(transliterate_tex(r"e^{i\pi}"), "e^(iπ)");
+}
+
+#[test]
+fn transliteration_tolerates_malformed_input() {
+    assert_eq!(transliterate_tex(r"\frac{1"), "1/");
+    assert_eq!(transliterate_tex("x^"), "x^");
+    assert_eq!(transliterate_tex(r"\unknown"), r"\unknown");
+    assert_eq!(transliterate_tex("}}"), "");
+}
+
+#[test]
+fn closing_dollar_renders_inline_math() {
+    let mut editor = editor_with("area $\\pi r^2");
+    type_text(&mut editor, "$");
+
+    let para = element(&editor.doc().children[0]);
+    assert_eq!(para.children.len(), 3);
+    assert_eq!(tex_of(&para.children[1]), Some(("math_inline", "\\pi r^2")));
+    assert_eq!(editor.selection().focus.path, vec![0, 2]);
+    assert_eq!(editor.selection().focus.offset, 0);
+}
+
+#[test]
+fn prices_stay_text() {
+    let mut editor = editor_with("costs $5 and ");
+    type_text(&mut editor, "$");
+
+    let para = element(&editor.doc().children[0]);
+    assert_eq!(para.children.len(), 1);
+}
+
+#[test]
+fn double_dollar_paragraph_becomes_math_block() {
+    let mut editor = editor_with("$$x^2$");
+    type_text(&mut editor, "$");
+
+    assert_eq!(
+        tex_of(&editor.doc().children[0]),
+        Some(("math_block", "x^2"))
+    );
+    assert_eq!(element(&editor.doc().children[1]).kind, "paragraph");
+    assert_eq!(editor.selection().focus.path, vec![1, 0]);
+}
+
+#[test]
+fn edit_source_round_trips_through_the_input_rule() {
+    let mut editor = editor_with("a b");
+    editor.set_selection(Selection::collapsed(Point::new(vec![0, 0], 2)));
+    editor
+        .run_command(
+            "math.insert_inline",
+            Some(serde_json::json!({ "tex": "x" })),
+        )
+        .unwrap();
+    assert_eq!(
+        tex_of(&element(&editor.doc().children[0]).children[1]),
+        Some(("math_inline", "x"))
+    );
+
+    editor
+        .run_command(
+            "math.edit_source",
+            Some(serde_json::json!({ "path": [0, 1] })),
+        )
+        .unwrap();
+    let para = element(&editor.doc().children[0]);
+    assert_eq!(para.children.len(), 1);
+    assert_eq!(editor.selection().focus.offset, 4);
+
+    type_text(&mut editor, "^2$");
+    let para = element(&editor.doc().children[0]);
+    assert_eq!(tex_of(&para.children[1]), Some(("math_inline", "x^2")));
+}
+
+#[test]
+fn set_tex_updates_the_node() {
+    let mut editor = editor_with("");
+    editor
+        .run_command("math.insert_block", Some(serde_json::json!({ "tex": "a" })))
+        .unwrap();
+    editor
+        .run_command(
+            "math.set_tex",
+            Some(serde_json::json!({ "path": [1], "tex": "b" })),
+        )
+        .unwrap();
+    assert_eq!(tex_of(&editor.doc().children[1]), Some(("math_block", "b")));
+
+    let err = editor
+        .run_command(
+            "math.set_tex",
+            Some(serde_json::json!({ "path": [0], "tex": "b" })),
+        )
+        .unwrap_err();
+    assert_eq!(err.message(), "No math node at path");
+}
+
+#[test]
+fn markdown_math_round_trips() {
+    let markdown = "Euler: $e^{i\\pi} + 1 = 0$ costs \\$5\n\n$$\n\\int_0^1 x\\,dx\n$$\n";
+    let doc = markdown_to_document(markdown);
+
+    let para = element(&doc.children[0]);
+    assert_eq!(
+        tex_of(&para.children[1]),
+        Some(("math_inline", "e^{i\\pi} + 1 = 0"))
+    );
+    assert_eq!(
+        tex_of(&doc.children[1]),
+        Some(("math_block", "\\int_0^1 x\\,dx"))
+    );
+
+    let exported = document_to_markdown(&doc);
+    assert_eq!(exported, markdown);
+    assert_eq!(markdown_to_document(&exported), doc);
+}
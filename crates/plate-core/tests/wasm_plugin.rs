@@ -0,0 +1,182 @@
+#![cfg(feature = "wasm")]
+
+use gpui_plate_core::{
+    Document, Editor, Node, Op, PlatePlugin, PluginRegistry, Point, Selection, WasmPlugin,
+    WasmPluginLimits,
+};
+
+/// A plugin module that answers every hook with a fixed JSON string.
+fn plugin_module(manifest: &str, hooks: &[(&str, &str)]) -> Vec<u8> {
+    let mut data = String::new();
+    let mut funcs = String::new();
+    let mut offset = 0usize;
+    let mut segment = |json: &str, data: &mut String| {
+        let packed = ((offset as u64) << 32) | json.len() as u64;
+        data.push_str(&format!(
+            "(data (i32.const {offset}) \"{}\")\n",
+            json.replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n")
+        ));
+        offset += json.len();
+        packed
+    };
+
+    let packed = segment(manifest, &mut data);
+    funcs.push_str(&format!(
+        "(func (export \"plate_manifest\") (result i64) (i64.const {packed}))\n"
+    ));
+    for (export, output) in hooks {
+        let packed = segment(output, &mut data);
+        funcs.push_str(&format!(
+            "(func (export \"{export}\") (param i32 i32) (result i64) (i64.const {packed}))\n"
+        ));
+    }
+
+    let wat = format!(
+        r#"(module
+            (memory (export "memory") 1)
+            (global $heap (mut i32) (i32.const 32768))
+            {data}
+            (func (export "plate_alloc") (param $len i32) (result i32)
+                (local $ptr i32)
+                (local.set $ptr (global.get $heap))
+                (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+                (local.get $ptr))
+            (func (export "plate_dealloc") (param i32 i32))
+            {funcs}
+        )"#
+    );
+    wat::parse_str(wat).unwrap()
+}
+
+fn editor(registry: PluginRegistry) -> Editor {
+    let doc = Document {
+        children: vec![Node::paragraph("")],
+    };
+    Editor::new(
+        doc,
+        Selection::collapsed(Point::new(vec![0, 0], 0)),
+        registry,
+    )
+}
+
+fn paragraph_text(editor: &Editor) -> String {
+    let Node::Element(el) = &editor.doc().children[0] else {
+        panic!("expected a paragraph");
+    };
+    el.children
+        .iter()
+        .filter_map(|node| match node {
+            Node::Text(t) => Some(t.text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn wasm_plugin_registers_node_specs_and_normalize_pass() {
+    let module = plugin_module(
+        r#"{"abi":1,"id":"wasm.callout","normalize":true,
+            "node_specs":[{"kind":"callout","role":"Block","is_void":false,"children":"InlineOnly"}]}"#,
+        &[(
+            "plate_normalize",
+            r#"{"ops":[{"op":"remove_node","path":[3]}]}"#,
+        )],
+    );
+    let plugin = WasmPlugin::new(&module).unwrap();
+    assert_eq!(plugin.manifest().id, "wasm.callout");
+
+    let mut registry = PluginRegistry::core();
+    registry.register_plugin(Box::new(plugin)).unwrap();
+    assert!(registry.is_known_kind("callout"));
+    assert!(
+        registry
+            .normalize(&Document::default())
+            .contains(&Op::RemoveNode { path: vec![3] })
+    );
+}
+
+#[test]
+fn wasm_command_applies_the_returned_transaction() {
+    let module = plugin_module(
+        r#"{"abi":1,"id":"wasm.greet","commands":[{"id":"greet.insert","label":"Greet"}]}"#,
+        &[(
+            "plate_command",
+            r#"{"tx":{"ops":[{"op":"insert_text","path":[0,0],"offset":0,"text":"hi"}]}}"#,
+        )],
+    );
+    let mut registry = PluginRegistry::core();
+    registry
+        .register_plugin(Box::new(WasmPlugin::new(&module).unwrap()))
+        .unwrap();
+    let mut editor = editor(registry);
+
+    editor.run_command("greet.insert", None).unwrap();
+    assert_eq!(paragraph_text(&editor), "hi");
+    assert!(editor.can_undo());
+}
+
+#[test]
+fn wasm_command_errors_are_reported() {
+    let module = plugin_module(
+        r#"{"abi":1,"id":"wasm.fail","commands":[{"id":"fail.now","label":"Fail"}]}"#,
+        &[("plate_command", r#"{"error":"nothing to do"}"#)],
+    );
+    let mut registry = PluginRegistry::core();
+    registry
+        .register_plugin(Box::new(WasmPlugin::new(&module).unwrap()))
+        .unwrap();
+    let mut editor = editor(registry);
+
+    let err = editor.run_command("fail.now", None).unwrap_err();
+    assert_eq!(err.message(), "nothing to do");
+}
+
+#[test]
+fn modules_with_imports_are_rejected() {
+    let module = wat::parse_str(
+        r#"(module
+            (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+            (memory (export "memory") 1))"#,
+    )
+    .unwrap();
+    let err = WasmPlugin::new(&module).err().unwrap();
+    assert!(err.contains("fd_write"), "{err}");
+}
+
+#[test]
+fn unsupported_abi_is_rejected() {
+    let module = plugin_module(r#"{"abi":99,"id":"wasm.future"}"#, &[]);
+    let err = WasmPlugin::new(&module).err().unwrap();
+    assert!(err.contains("ABI 99"), "{err}");
+}
+
+#[test]
+fn runaway_normalize_runs_out_of_fuel() {
+    let manifest = r#"{"abi":1,"id":"wasm.spin","normalize":true}"#;
+    let wat = format!(
+        r#"(module
+            (memory (export "memory") 1)
+            (data (i32.const 0) "{}")
+            (func (export "plate_alloc") (param i32) (result i32) (i32.const 1024))
+            (func (export "plate_dealloc") (param i32 i32))
+            (func (export "plate_manifest") (result i64) (i64.const {}))
+            (func (export "plate_normalize") (param i32 i32) (result i64)
+                (loop $spin (br $spin))
+                (i64.const 0)))"#,
+        manifest.replace('"', "\\\""),
+        manifest.len()
+    );
+    let plugin = WasmPlugin::with_limits(
+        &wat::parse_str(wat).unwrap(),
+        WasmPluginLimits {
+            fuel: 100_000,
+            ..WasmPluginLimits::default()
+        },
+    )
+    .unwrap();
+
+    let registry = PluginRegistry::new([Box::new(plugin) as Box<dyn PlatePlugin>]).unwrap();
+    assert!(registry.normalize(&Document::default()).is_empty());
+}
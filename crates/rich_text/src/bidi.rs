@@ -0,0 +1,308 @@
+//! Caret, selection and hit-testing geometry for blocks with right-to-left text.
+//!
+//! gpui shapes a line in visual order, so inside a right-to-left run the byte index of the
+//! glyphs goes down as `x` goes up. `TextLayout::position_for_index` and
+//! `index_for_position` assume the opposite and misplace the caret there; [`BidiLine`]
+//! works from the box of each glyph instead. Blocks with only left-to-right text keep using
+//! the gpui helpers.
+
+use std::ops::Range;
+
+use gpui::{Bounds, Pixels, TextAlign, TextLayout, point, px};
+use gpui_plate_core::{ElementNode, Node};
+use unicode_bidi::{Direction, ParagraphBidiInfo};
+
+use crate::TextDirection;
+
+/// The direction of the first strong character of `text`, if it has one.
+pub(crate) fn detect_direction(text: &str) -> Option<TextDirection> {
+    match unicode_bidi::get_base_direction(text) {
+        Direction::Ltr => Some(TextDirection::Ltr),
+        Direction::Rtl => Some(TextDirection::Rtl),
+        Direction::Mixed => None,
+    }
+}
+
+/// The `dir` attribute of a text block, if set.
+pub(crate) fn explicit_direction(el: &ElementNode) -> Option<TextDirection> {
+    match el.attrs.get("dir").and_then(|v| v.as_str()) {
+        Some("ltr") => Some(TextDirection::Ltr),
+        Some("rtl") => Some(TextDirection::Rtl),
+        _ => None,
+    }
+}
+
+/// The `dir` attribute of a text block, or the direction of its text when it has none.
+pub(crate) fn block_direction(el: &ElementNode) -> TextDirection {
+    if let Some(dir) = explicit_direction(el) {
+        return dir;
+    }
+    let text: String = el
+        .children
+        .iter()
+        .filter_map(|child| match child {
+            Node::Text(t) => Some(t.text.as_str()),
+            _ => None,
+        })
+        .collect();
+    detect_direction(&text).unwrap_or_default()
+}
+
+#[derive(Clone, Debug)]
+struct GlyphBox {
+    /// Byte range of the text drawn by the glyph; ligatures cover several characters.
+    range: Range<usize>,
+    seg: usize,
+    left: Pixels,
+    right: Pixels,
+    rtl: bool,
+}
+
+impl GlyphBox {
+    fn leading(&self) -> Pixels {
+        if self.rtl { self.right } else { self.left }
+    }
+
+    fn trailing(&self) -> Pixels {
+        if self.rtl { self.left } else { self.right }
+    }
+
+    /// The caret position for `index` inside the glyph, spread evenly over its characters.
+    fn x_for_index(&self, index: usize) -> Pixels {
+        let len = self.range.len().max(1) as f32;
+        let t = (index.saturating_sub(self.range.start)) as f32 / len;
+        self.leading() + (self.trailing() - self.leading()) * t
+    }
+}
+
+/// Geometry of one laid-out text block, relative to the origin of its bounds and with the
+/// block's alignment applied.
+#[derive(Clone, Debug)]
+pub(crate) struct BidiLine {
+    direction: TextDirection,
+    /// Glyphs in logical order.
+    glyphs: Vec<GlyphBox>,
+    /// Alignment offset of each visual line.
+    seg_offsets: Vec<Pixels>,
+    line_height: Pixels,
+    len: usize,
+}
+
+impl BidiLine {
+    /// Returns `None` when the text reads left to right throughout, or when the layout does
+    /// not match `text`.
+    pub(crate) fn new(
+        layout: &TextLayout,
+        text: &str,
+        direction: TextDirection,
+        text_align: TextAlign,
+        width: Pixels,
+    ) -> Option<Self> {
+        let info = ParagraphBidiInfo::new(text, None);
+        if info.is_pure_ltr && direction == TextDirection::Ltr {
+            return None;
+        }
+        let line = layout.line_layout_for_index(0)?;
+        if line.len() != text.len() {
+            return None;
+        }
+        let unwrapped = &line.unwrapped_layout;
+
+        // Visual lines start at their wrap boundary glyph and end at the next one.
+        let mut seg_starts = vec![px(0.)];
+        for boundary in line.wrap_boundaries() {
+            let glyph = unwrapped
+                .runs
+                .get(boundary.run_ix)?
+                .glyphs
+                .get(boundary.glyph_ix)?;
+            seg_starts.push(glyph.position.x);
+        }
+        let mut seg_ends: Vec<Pixels> = seg_starts.iter().skip(1).copied().collect();
+        seg_ends.push(unwrapped.width);
+        let seg_offsets = seg_starts
+            .iter()
+            .zip(&seg_ends)
+            .map(|(start, end)| {
+                let seg_width = *end - *start;
+                match text_align {
+                    TextAlign::Left => px(0.),
+                    TextAlign::Center => (width - seg_width) / 2.0,
+                    TextAlign::Right => width - seg_width,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut boundaries = line.wrap_boundaries().iter().peekable();
+        let mut seg = 0usize;
+        let mut glyphs: Vec<GlyphBox> = Vec::new();
+        for (run_ix, run) in unwrapped.runs.iter().enumerate() {
+            for (glyph_ix, glyph) in run.glyphs.iter().enumerate() {
+                if boundaries
+                    .peek()
+                    .is_some_and(|b| b.run_ix == run_ix && b.glyph_ix == glyph_ix)
+                {
+                    boundaries.next();
+                    seg += 1;
+                }
+                let left = glyph.position.x - seg_starts[seg] + seg_offsets[seg];
+                if let Some(prev) = glyphs.last_mut()
+                    && prev.seg == seg
+                {
+                    prev.right = left;
+                }
+                let rtl = info
+                    .levels
+                    .get(glyph.index)
+                    .is_some_and(|level| level.is_rtl());
+                glyphs.push(GlyphBox {
+                    range: glyph.index..glyph.index,
+                    seg,
+                    left,
+                    right: seg_ends[seg] - seg_starts[seg] + seg_offsets[seg],
+                    rtl,
+                });
+            }
+        }
+
+        // A character drawn with several glyphs gets one box around all of them.
+        glyphs.sort_by_key(|glyph| glyph.range.start);
+        glyphs.dedup_by(|glyph, kept| {
+            if glyph.range.start != kept.range.start || glyph.seg != kept.seg {
+                return false;
+            }
+            kept.left = kept.left.min(glyph.left);
+            kept.right = kept.right.max(glyph.right);
+            true
+        });
+        let starts: Vec<usize> = glyphs.iter().map(|glyph| glyph.range.start).collect();
+        for (ix, glyph) in glyphs.iter_mut().enumerate() {
+            glyph.range.end = starts.get(ix + 1).copied().unwrap_or(text.len());
+        }
+
+        Some(Self {
+            direction,
+            glyphs,
+            seg_offsets,
+            line_height: layout.line_height(),
+            len: text.len(),
+        })
+    }
+
+    fn glyph_at(&self, index: usize) -> Option<&GlyphBox> {
+        let ix = self
+            .glyphs
+            .partition_point(|glyph| glyph.range.start <= index);
+        self.glyphs[..ix]
+            .last()
+            .filter(|glyph| index < glyph.range.end)
+    }
+
+    fn glyph_before(&self, index: usize) -> Option<&GlyphBox> {
+        index.checked_sub(1).and_then(|index| self.glyph_at(index))
+    }
+
+    /// Top-left corner of the caret at byte `index`.
+    ///
+    /// Between two characters of different direction the caret sticks to the one that runs
+    /// with the paragraph, the way browsers place it.
+    pub(crate) fn caret_position(&self, index: usize) -> gpui::Point<Pixels> {
+        let index = index.min(self.len);
+        let next = self.glyph_at(index);
+        let prev = self.glyph_before(index);
+        let paragraph_rtl = self.direction == TextDirection::Rtl;
+
+        let (seg, x) = match (prev, next) {
+            (_, Some(next)) if next.range.start < index => (next.seg, next.x_for_index(index)),
+            (Some(prev), Some(next)) if prev.rtl != next.rtl && prev.rtl == paragraph_rtl => {
+                (prev.seg, prev.trailing())
+            }
+            (_, Some(next)) => (next.seg, next.leading()),
+            (Some(prev), None) => (prev.seg, prev.trailing()),
+            (None, None) => {
+                let x = self.seg_offsets.first().copied().unwrap_or_default();
+                (0, x)
+            }
+        };
+        point(x, self.line_height * seg as f32)
+    }
+
+    /// Byte offset nearest to `position`, relative to the origin of the block.
+    pub(crate) fn index_for_position(&self, position: gpui::Point<Pixels>) -> usize {
+        if self.line_height <= px(0.) || self.glyphs.is_empty() {
+            return 0;
+        }
+        let last_seg = self.seg_offsets.len().saturating_sub(1);
+        let seg = if position.y <= px(0.) {
+            0
+        } else {
+            ((position.y / self.line_height) as usize).min(last_seg)
+        };
+
+        let Some(glyph) = self
+            .glyphs
+            .iter()
+            .filter(|glyph| glyph.seg == seg)
+            .min_by(|a, b| {
+                let distance = |glyph: &GlyphBox| {
+                    if position.x < glyph.left {
+                        glyph.left - position.x
+                    } else if position.x > glyph.right {
+                        position.x - glyph.right
+                    } else {
+                        px(0.)
+                    }
+                };
+                distance(a).cmp(&distance(b))
+            })
+        else {
+            return 0;
+        };
+
+        let middle = (glyph.left + glyph.right) / 2.0;
+        let before_middle = if glyph.rtl {
+            position.x > middle
+        } else {
+            position.x < middle
+        };
+        if before_middle {
+            glyph.range.start
+        } else {
+            glyph.range.end
+        }
+    }
+
+    /// Boxes covering the bytes in `range`, relative to the origin of the block; a selection
+    /// across a direction change can cover several separate spans of one visual line.
+    pub(crate) fn selection_bounds(&self, range: Range<usize>) -> Vec<Bounds<Pixels>> {
+        let mut spans: Vec<(usize, Pixels, Pixels)> = self
+            .glyphs
+            .iter()
+            .filter(|glyph| glyph.range.start < range.end && range.start < glyph.range.end)
+            .map(|glyph| {
+                let a = glyph.x_for_index(range.start.max(glyph.range.start));
+                let b = glyph.x_for_index(range.end.min(glyph.range.end));
+                (glyph.seg, a.min(b), a.max(b))
+            })
+            .collect();
+        spans.sort_by_key(|(seg, left, _)| (*seg, *left));
+
+        let mut merged: Vec<(usize, Pixels, Pixels)> = Vec::new();
+        for (seg, left, right) in spans {
+            match merged.last_mut() {
+                Some(last) if last.0 == seg && left <= last.2 + px(0.5) => {
+                    last.2 = last.2.max(right);
+                }
+                _ => merged.push((seg, left, right)),
+            }
+        }
+
+        merged
+            .into_iter()
+            .map(|(seg, left, right)| {
+                let top = self.line_height * seg as f32;
+                Bounds::from_corners(point(left, top), point(right, top + self.line_height))
+            })
+            .collect()
+    }
+}
@@ -1,13 +1,17 @@
+mod bidi;
 mod editor;
+mod markdown_view;
 mod state;
 mod types;
 
 pub use editor::*;
+pub use markdown_view::*;
 pub use state::*;
 pub use types::*;
 
 pub use gpui_plate_core::{
     Document, ElementNode, Marks, Node, PlateValue, Point, Selection, TextNode,
+    markdown_to_document,
 };
 
 pub type RichTextValue = PlateValue;
@@ -0,0 +1,64 @@
+use std::path::PathBuf;
+
+use gpui::{AppContext as _, Context, Entity, IntoElement, Render, Window};
+use gpui_plate_core::{PlateValue, markdown_to_document};
+
+use crate::RichTextState;
+
+/// Renders markdown read-only through the richtext pipeline.
+///
+/// Relative image paths resolve against the base directory, so a README shows its
+/// screenshots when given the directory it lives in.
+pub struct MarkdownView {
+    state: Entity<RichTextState>,
+    markdown: String,
+}
+
+impl MarkdownView {
+    pub fn new(
+        markdown: impl Into<String>,
+        base_dir: Option<PathBuf>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let state = cx.new(|cx| {
+            let mut state = RichTextState::new(window, cx);
+            state.set_read_only(true, cx);
+            state
+        });
+        let mut this = Self {
+            state,
+            markdown: String::new(),
+        };
+        this.set_markdown(markdown, base_dir, cx);
+        this
+    }
+
+    pub fn markdown(&self) -> &str {
+        &self.markdown
+    }
+
+    pub fn set_markdown(
+        &mut self,
+        markdown: impl Into<String>,
+        base_dir: Option<PathBuf>,
+        cx: &mut Context<Self>,
+    ) {
+        self.markdown = markdown.into();
+        let value = PlateValue::from_document(markdown_to_document(&self.markdown));
+        self.state.update(cx, |state, cx| {
+            state.load_plate_value(value, base_dir, cx);
+        });
+        cx.notify();
+    }
+
+    pub fn state(&self) -> &Entity<RichTextState> {
+        &self.state
+    }
+}
+
+impl Render for MarkdownView {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        self.state.clone()
+    }
+}
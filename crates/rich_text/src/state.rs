@@ -15,9 +15,10 @@ use gpui_plate_core::{
 };
 use serde::{Deserialize, Serialize};
 
+use crate::bidi::{self, BidiLine};
 use crate::{
     BlockAlign, BundleAsset, BundleExportReport, CollectAssetsReport, EmbedLocalImagesReport,
-    PortabilityIssue, PortabilityIssueLevel, PortabilityReport,
+    PortabilityIssue, PortabilityIssueLevel, PortabilityReport, TextDirection,
 };
 
 pub const CONTEXT: &str = "RichText";
@@ -47,6 +48,7 @@ actions!(
         ToggleCode,
         ToggleBulletedList,
         ToggleOrderedList,
+        ToggleTextDirection,
     ]
 );
 
@@ -104,6 +106,10 @@ pub(crate) fn init(cx: &mut App) {
         #[cfg(not(target_os = "macos"))]
         KeyBinding::new("ctrl-shift-7", ToggleOrderedList, Some(CONTEXT)),
         #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-shift-r", ToggleTextDirection, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-shift-r", ToggleTextDirection, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
         KeyBinding::new("cmd-c", Copy, Some(CONTEXT)),
         #[cfg(not(target_os = "macos"))]
         KeyBinding::new("ctrl-c", Copy, Some(CONTEXT)),
@@ -156,6 +162,17 @@ fn is_text_block_kind(registry: &PluginRegistry, kind: &str) -> bool {
         .unwrap_or(false)
 }
 
+/// The `align` attribute of a text block; without one, right-to-left blocks align right.
+fn text_align_for_element(el: &ElementNode) -> TextAlign {
+    match el.attrs.get("align").and_then(|v| v.as_str()) {
+        Some("center") => TextAlign::Center,
+        Some("right") => TextAlign::Right,
+        Some("left") => TextAlign::Left,
+        _ if bidi::block_direction(el) == TextDirection::Rtl => TextAlign::Right,
+        _ => TextAlign::Left,
+    }
+}
+
 fn text_block_paths(doc: &Document, registry: &PluginRegistry) -> Vec<Vec<usize>> {
     fn walk(
         nodes: &[Node],
@@ -219,6 +236,8 @@ pub struct LineLayoutCache {
     pub text_align: TextAlign,
     pub text: SharedString,
     pub segments: Vec<InlineTextSegment>,
+    /// Set when the block holds right-to-left text, which the gpui index helpers misplace.
+    pub(crate) bidi: Option<Arc<BidiLine>>,
 }
 
 #[derive(Clone)]
@@ -263,6 +282,7 @@ pub struct RichTextState {
     selected_block_path: Option<Vec<usize>>,
     columns_resizing: Option<ColumnsResizeState>,
     did_auto_focus: bool,
+    read_only: bool,
     pending_notifications: Vec<String>,
 }
 
@@ -886,6 +906,7 @@ impl RichTextState {
             selected_block_path: None,
             columns_resizing: None,
             did_auto_focus: false,
+            read_only: false,
             pending_notifications: Vec::new(),
         }
     }
@@ -894,6 +915,17 @@ impl RichTextState {
         self.focus_handle.clone()
     }
 
+    /// A read-only document can be selected and copied but not edited; it shows no caret,
+    /// does not take focus on first render, and opens links on a plain click.
+    pub fn set_read_only(&mut self, read_only: bool, cx: &mut Context<Self>) {
+        self.read_only = read_only;
+        cx.notify();
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
     pub fn can_undo(&self) -> bool {
         self.editor.can_undo()
     }
@@ -2457,6 +2489,10 @@ impl RichTextState {
         position: gpui::Point<Pixels>,
     ) -> Option<usize> {
         let cache = self.layout_cache.get(block_path)?;
+        if let Some(bidi) = &cache.bidi {
+            let local = bidi.index_for_position(position - cache.bounds.origin);
+            return Some(local.min(cache.text.len()));
+        }
         let offset_x = RichTextLineElement::align_offset_x_for_position(
             &cache.text_layout,
             &cache.bounds,
@@ -2471,6 +2507,47 @@ impl RichTextState {
         Some(local)
     }
 
+    /// The inline math node drawn under `position`, if any.
+    fn math_inline_path_for_point(
+        &self,
+        block_path: &[usize],
+        position: gpui::Point<Pixels>,
+    ) -> Option<Vec<usize>> {
+        let cache = self.layout_cache.get(block_path)?;
+        if cache.bidi.is_some() {
+            return None;
+        }
+        let offset_x = RichTextLineElement::align_offset_x_for_position(
+            &cache.text_layout,
+            &cache.bounds,
+            position,
+            cache.text_align,
+        );
+        let position = gpui::point(position.x - offset_x, position.y);
+        let line_height = cache.text_layout.line_height();
+
+        cache.segments.iter().find_map(|seg| {
+            let InlineTextSegmentKind::Void { kind } = &seg.kind else {
+                return None;
+            };
+            if kind.as_ref() != "math_inline" {
+                return None;
+            }
+            let start = cache.text_layout.position_for_index(seg.start)?;
+            let end = cache.text_layout.position_for_index(seg.start + seg.len)?;
+            let hit = start.y == end.y
+                && position.x >= start.x
+                && position.x <= end.x
+                && position.y >= start.y
+                && position.y <= start.y + line_height;
+            hit.then(|| {
+                let mut path = block_path.to_vec();
+                path.push(seg.child_ix);
+                path
+            })
+        })
+    }
+
     fn link_at_offset(&self, block_path: &[usize], offset: usize) -> Option<String> {
         let cache = self.layout_cache.get(block_path)?;
 
@@ -2607,11 +2684,13 @@ impl RichTextState {
         let Some(el) = element_at_path(self.editor.doc(), block_path) else {
             return TextAlign::Left;
         };
-        match el.attrs.get("align").and_then(|v| v.as_str()) {
-            Some("center") => TextAlign::Center,
-            Some("right") => TextAlign::Right,
-            _ => TextAlign::Left,
-        }
+        text_align_for_element(el)
+    }
+
+    fn text_direction_for_block(&self, block_path: &[usize]) -> TextDirection {
+        element_at_path(self.editor.doc(), block_path)
+            .map(bidi::block_direction)
+            .unwrap_or_default()
     }
 
     fn text_block_path_for_point(&self, point: gpui::Point<Pixels>) -> Option<Vec<usize>> {
@@ -2667,7 +2746,7 @@ impl RichTextState {
                 continue;
             }
             let kind = cache.kind.as_ref();
-            if kind != "divider" && kind != "image" {
+            if kind != "divider" && kind != "image" && kind != "math_block" {
                 continue;
             }
             if !matches!(node_at_path(self.editor.doc(), path), Some(Node::Void(_))) {
@@ -2870,7 +2949,7 @@ impl RichTextState {
                 Node::Text(_) => continue,
             };
 
-            let score = if kind == "divider" || kind == "image" {
+            let score = if kind == "divider" || kind == "image" || kind == "math_block" {
                 score
             } else {
                 score.min(1)
@@ -2908,6 +2987,9 @@ impl RichTextState {
         selected_block_path_after: Option<Vec<usize>>,
         cx: &mut Context<Self>,
     ) {
+        if self.read_only {
+            return;
+        }
         let source = tx.meta.source.as_deref().unwrap_or("tx:apply").to_string();
         match self.editor.apply(tx) {
             Ok(()) => self.refresh_after_doc_change(selected_block_path_after, cx),
@@ -2925,6 +3007,9 @@ impl RichTextState {
         args: Option<serde_json::Value>,
         cx: &mut Context<Self>,
     ) -> bool {
+        if self.read_only {
+            return false;
+        }
         match self.editor.run_command(id, args) {
             Ok(()) => {
                 self.refresh_after_doc_change(None, cx);
@@ -2989,20 +3074,24 @@ impl RichTextState {
             .row_offset_for_point(&self.editor.selection().focus)
             .unwrap_or(0)
             .min(cache.text_layout.len());
-        let Some(pos) = cache.text_layout.position_for_index(caret_ix).or_else(|| {
-            cache
-                .text_layout
-                .position_for_index(cache.text_layout.len())
-        }) else {
-            return false;
+        let pos = if let Some(bidi) = &cache.bidi {
+            cache.bounds.origin + bidi.caret_position(caret_ix)
+        } else {
+            let Some(pos) = cache.text_layout.position_for_index(caret_ix).or_else(|| {
+                cache
+                    .text_layout
+                    .position_for_index(cache.text_layout.len())
+            }) else {
+                return false;
+            };
+            let offset_x = RichTextLineElement::align_offset_x_for_index(
+                &cache.text_layout,
+                &cache.bounds,
+                caret_ix,
+                cache.text_align,
+            );
+            point(pos.x + offset_x, pos.y)
         };
-        let offset_x = RichTextLineElement::align_offset_x_for_index(
-            &cache.text_layout,
-            &cache.bounds,
-            caret_ix,
-            cache.text_align,
-        );
-        let pos = point(pos.x + offset_x, pos.y);
 
         let line_height = cache.text_layout.line_height();
         let caret_bounds = Bounds::from_corners(pos, point(pos.x + px(1.5), pos.y + line_height));
@@ -3069,12 +3158,16 @@ impl RichTextState {
 
                     if matches!(
                         node_at_path(self.editor.doc(), &prev_path),
-                        Some(Node::Void(v)) if v.kind == "divider" || v.kind == "image"
+                        Some(Node::Void(v))
+                            if v.kind == "divider" || v.kind == "image" || v.kind == "math_block"
                     ) {
                         let source = match node_at_path(self.editor.doc(), &prev_path) {
                             Some(Node::Void(v)) if v.kind == "image" => {
                                 "key:backspace:remove_image"
                             }
+                            Some(Node::Void(v)) if v.kind == "math_block" => {
+                                "key:backspace:remove_math_block"
+                            }
                             _ => "key:backspace:remove_divider",
                         };
                         self.push_tx(
@@ -3153,10 +3246,14 @@ impl RichTextState {
 
                 if matches!(
                     node_at_path(self.editor.doc(), &next_path),
-                    Some(Node::Void(v)) if v.kind == "divider" || v.kind == "image"
+                    Some(Node::Void(v))
+                        if v.kind == "divider" || v.kind == "image" || v.kind == "math_block"
                 ) {
                     let source = match node_at_path(self.editor.doc(), &next_path) {
                         Some(Node::Void(v)) if v.kind == "image" => "key:delete:remove_image",
+                        Some(Node::Void(v)) if v.kind == "math_block" => {
+                            "key:delete:remove_math_block"
+                        }
                         _ => "key:delete:remove_divider",
                     };
                     self.push_tx(
@@ -3229,7 +3326,55 @@ impl RichTextState {
         }
     }
 
+    /// Left and right arrows move against and with the reading order, so they swap in a
+    /// right-to-left block.
+    fn active_block_is_rtl(&self) -> bool {
+        self.block_direction() == TextDirection::Rtl
+    }
+
     pub(super) fn left(&mut self, _: &MoveLeft, _window: &mut Window, cx: &mut Context<Self>) {
+        if self.active_block_is_rtl() {
+            self.move_forward(cx);
+        } else {
+            self.move_backward(cx);
+        }
+    }
+
+    pub(super) fn right(&mut self, _: &MoveRight, _window: &mut Window, cx: &mut Context<Self>) {
+        if self.active_block_is_rtl() {
+            self.move_backward(cx);
+        } else {
+            self.move_forward(cx);
+        }
+    }
+
+    pub(super) fn select_left(
+        &mut self,
+        _: &SelectLeft,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.active_block_is_rtl() {
+            self.select_forward(cx);
+        } else {
+            self.select_backward(cx);
+        }
+    }
+
+    pub(super) fn select_right(
+        &mut self,
+        _: &SelectRight,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.active_block_is_rtl() {
+            self.select_backward(cx);
+        } else {
+            self.select_forward(cx);
+        }
+    }
+
+    fn move_backward(&mut self, cx: &mut Context<Self>) {
         let sel = self.editor.selection().clone();
         if !sel.is_collapsed() {
             let mut start = sel.anchor.clone();
@@ -3270,7 +3415,7 @@ impl RichTextState {
         self.set_selection_in_active_block(new_cursor, new_cursor, cx);
     }
 
-    pub(super) fn right(&mut self, _: &MoveRight, _window: &mut Window, cx: &mut Context<Self>) {
+    fn move_forward(&mut self, cx: &mut Context<Self>) {
         let sel = self.editor.selection().clone();
         if !sel.is_collapsed() {
             let mut start = sel.anchor.clone();
@@ -3310,12 +3455,7 @@ impl RichTextState {
         self.set_selection_in_active_block(new_cursor, new_cursor, cx);
     }
 
-    pub(super) fn select_left(
-        &mut self,
-        _: &SelectLeft,
-        _window: &mut Window,
-        cx: &mut Context<Self>,
-    ) {
+    fn select_backward(&mut self, cx: &mut Context<Self>) {
         let sel = self.editor.selection().clone();
         let Some(block_path) = self.active_text_block_path() else {
             return;
@@ -3357,12 +3497,7 @@ impl RichTextState {
         self.set_selection_points(anchor, focus, cx);
     }
 
-    pub(super) fn select_right(
-        &mut self,
-        _: &SelectRight,
-        _window: &mut Window,
-        cx: &mut Context<Self>,
-    ) {
+    fn select_forward(&mut self, cx: &mut Context<Self>) {
         let sel = self.editor.selection().clone();
         let Some(block_path) = self.active_text_block_path() else {
             return;
@@ -3405,7 +3540,7 @@ impl RichTextState {
     }
 
     pub(super) fn undo(&mut self, _: &Undo, _window: &mut Window, cx: &mut Context<Self>) {
-        if self.editor.undo() {
+        if !self.read_only && self.editor.undo() {
             self.layout_cache.clear();
             self.block_bounds_cache.clear();
             self.selected_block_path = None;
@@ -3415,7 +3550,7 @@ impl RichTextState {
     }
 
     pub(super) fn redo(&mut self, _: &Redo, _window: &mut Window, cx: &mut Context<Self>) {
-        if self.editor.redo() {
+        if !self.read_only && self.editor.redo() {
             self.layout_cache.clear();
             self.block_bounds_cache.clear();
             self.selected_block_path = None;
@@ -3528,14 +3663,23 @@ impl RichTextState {
         _ = self.run_command_and_refresh("list.toggle_ordered", None, cx);
     }
 
+    pub(super) fn toggle_text_direction(
+        &mut self,
+        _: &ToggleTextDirection,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.command_toggle_direction(cx);
+    }
+
     pub fn command_undo(&mut self, cx: &mut Context<Self>) {
-        if self.editor.undo() {
+        if !self.read_only && self.editor.undo() {
             self.refresh_after_doc_change(None, cx);
         }
     }
 
     pub fn command_redo(&mut self, cx: &mut Context<Self>) {
-        if self.editor.redo() {
+        if !self.read_only && self.editor.redo() {
             self.refresh_after_doc_change(None, cx);
         }
     }
@@ -3936,6 +4080,25 @@ impl RichTextState {
         _ = self.run_command_and_refresh("emoji.insert", Some(args), cx);
     }
 
+    pub fn command_insert_math(&mut self, tex: String, display: bool, cx: &mut Context<Self>) {
+        _ = self.delete_selection_if_any(cx);
+        let args = serde_json::json!({ "tex": tex });
+        let id = if display {
+            "math.insert_block"
+        } else {
+            "math.insert_inline"
+        };
+        _ = self.run_command_and_refresh(id, Some(args), cx);
+    }
+
+    /// Turns the math node at `path` back into editable `$` source; typing the closing
+    /// delimiter renders it again.
+    pub fn command_edit_math_source(&mut self, path: Vec<usize>, cx: &mut Context<Self>) {
+        self.selected_block_path = None;
+        let args = serde_json::json!({ "path": path });
+        _ = self.run_command_and_refresh("math.edit_source", Some(args), cx);
+    }
+
     pub fn command_set_heading(&mut self, level: u64, cx: &mut Context<Self>) {
         let args = serde_json::json!({ "level": level });
         _ = self.run_command_and_refresh("block.set_heading", Some(args), cx);
@@ -3959,6 +4122,36 @@ impl RichTextState {
         _ = self.run_command_and_refresh("block.set_align", Some(args), cx);
     }
 
+    /// `None` lets the block follow the direction of its first strong character.
+    pub fn command_set_direction(
+        &mut self,
+        direction: Option<TextDirection>,
+        cx: &mut Context<Self>,
+    ) {
+        let dir = match direction {
+            Some(TextDirection::Ltr) => "ltr",
+            Some(TextDirection::Rtl) => "rtl",
+            None => "auto",
+        };
+        let args = serde_json::json!({ "dir": dir });
+        _ = self.run_command_and_refresh("block.set_dir", Some(args), cx);
+    }
+
+    /// Flips the active block between left-to-right and right-to-left. Flipping back to the
+    /// direction its text has on its own clears the `dir` attribute.
+    pub fn command_toggle_direction(&mut self, cx: &mut Context<Self>) {
+        let Some(block_path) = self.active_text_block_path() else {
+            return;
+        };
+        let next = match self.block_direction() {
+            TextDirection::Ltr => TextDirection::Rtl,
+            TextDirection::Rtl => TextDirection::Ltr,
+        };
+        let text = self.text_block_text(&block_path).unwrap_or_default();
+        let natural = bidi::detect_direction(text.as_str()).unwrap_or_default();
+        self.command_set_direction((next != natural).then_some(next), cx);
+    }
+
     pub fn command_set_font_size(&mut self, size: u64, cx: &mut Context<Self>) {
         let args = serde_json::json!({ "size": size });
         _ = self.run_command_and_refresh("block.set_font_size", Some(args), cx);
@@ -4244,10 +4437,34 @@ impl RichTextState {
         {
             Some("center") => BlockAlign::Center,
             Some("right") => BlockAlign::Right,
+            Some("left") => BlockAlign::Left,
+            _ if self.block_direction() == TextDirection::Rtl => BlockAlign::Right,
             _ => BlockAlign::Left,
         }
     }
 
+    /// The `dir` attribute of the active block; `None` when it follows its text.
+    pub fn block_dir(&self) -> Option<TextDirection> {
+        match self
+            .editor
+            .run_query::<Option<String>>("block.dir", None)
+            .ok()
+            .flatten()
+            .as_deref()
+        {
+            Some("ltr") => Some(TextDirection::Ltr),
+            Some("rtl") => Some(TextDirection::Rtl),
+            _ => None,
+        }
+    }
+
+    /// The direction the active block is laid out in.
+    pub fn block_direction(&self) -> TextDirection {
+        self.active_text_block_path()
+            .map(|path| self.text_direction_for_block(&path))
+            .unwrap_or_default()
+    }
+
     pub fn block_font_size(&self) -> Option<u64> {
         self.editor
             .run_query::<Option<u64>>("block.font_size", None)
@@ -4330,6 +4547,14 @@ impl RichTextState {
                         format!("![{alt}]({src})")
                     }
                 }
+                "math_inline" | "math_block" => {
+                    let tex = v.attrs.get("tex").and_then(|v| v.as_str()).unwrap_or("");
+                    if v.kind == "math_block" {
+                        format!("$${tex}$$")
+                    } else {
+                        format!("${tex}$")
+                    }
+                }
                 _ => v.inline_text(),
             }
         }
@@ -5243,7 +5468,10 @@ impl EntityInputHandler for RichTextState {
         let cache = self.layout_cache.get(block_path.as_slice())?;
         let text = cache.text.as_str();
         let start = utf16_to_byte(text, range_utf16.start);
-        let pos = cache.text_layout.position_for_index(start)?;
+        let pos = match &cache.bidi {
+            Some(bidi) => cache.bounds.origin + bidi.caret_position(start),
+            None => cache.text_layout.position_for_index(start)?,
+        };
         let line_height = cache.text_layout.line_height();
         Some(Bounds::from_corners(
             pos,
@@ -5276,6 +5504,7 @@ pub struct RichTextLineElement {
     styled_text: StyledText,
     text: SharedString,
     segments: Vec<InlineTextSegment>,
+    bidi: Option<Arc<BidiLine>>,
 }
 
 impl RichTextLineElement {
@@ -5286,11 +5515,13 @@ impl RichTextLineElement {
             styled_text: StyledText::new(SharedString::default()),
             text: SharedString::default(),
             segments: Vec::new(),
+            bidi: None,
         }
     }
 
     fn paint_selection_range(
         layout: &gpui::TextLayout,
+        bidi: Option<&BidiLine>,
         range: Range<usize>,
         bounds: &Bounds<Pixels>,
         line_height: Pixels,
@@ -5304,6 +5535,20 @@ impl RichTextLineElement {
             return;
         }
 
+        if let Some(bidi) = bidi {
+            for rect in bidi.selection_bounds(start_ix..end_ix) {
+                window.paint_quad(gpui::quad(
+                    Bounds::new(bounds.origin + rect.origin, rect.size),
+                    px(0.),
+                    selection_color,
+                    gpui::Edges::default(),
+                    gpui::transparent_black(),
+                    gpui::BorderStyle::default(),
+                ));
+            }
+            return;
+        }
+
         let Some(first_pos) = layout.position_for_index(0).or_else(|| {
             layout
                 .position_for_index(layout.len())
@@ -5736,6 +5981,15 @@ impl Element for RichTextLineElement {
                 style.strikethrough = None;
                 style.font_weight = FontWeight::MEDIUM;
             }
+            if matches!(
+                kind,
+                InlineTextSegmentKind::Void { kind } if kind.as_ref() == "math_inline"
+            ) {
+                style.background_color = Some(theme.muted);
+                style.underline = None;
+                style.strikethrough = None;
+                style.font_style = FontStyle::Italic;
+            }
 
             style
         };
@@ -5810,10 +6064,22 @@ impl Element for RichTextLineElement {
         let block_path = self.block_path.clone();
         let text = self.text.clone();
         let segments = self.segments.clone();
-        let text_align = {
+        let (text_align, direction) = {
             let state = self.state.read(cx);
-            state.text_align_for_block(&block_path)
+            (
+                state.text_align_for_block(&block_path),
+                state.text_direction_for_block(&block_path),
+            )
         };
+        self.bidi = BidiLine::new(
+            &text_layout,
+            text.as_str(),
+            direction,
+            text_align,
+            bounds.size.width,
+        )
+        .map(Arc::new);
+        let bidi = self.bidi.clone();
         self.state.update(cx, |state, _| {
             state.layout_cache.insert(
                 block_path,
@@ -5823,6 +6089,7 @@ impl Element for RichTextLineElement {
                     text_align,
                     text,
                     segments,
+                    bidi,
                 },
             );
         });
@@ -5874,6 +6141,7 @@ impl Element for RichTextLineElement {
                     };
                     Self::paint_selection_range(
                         &layout,
+                        self.bidi.as_deref(),
                         range.clone(),
                         &bounds,
                         line_height,
@@ -5886,7 +6154,7 @@ impl Element for RichTextLineElement {
 
             (
                 state.editor.selection().clone(),
-                state.focus_handle.is_focused(window),
+                state.focus_handle.is_focused(window) && !state.read_only,
             )
         };
 
@@ -5904,13 +6172,19 @@ impl Element for RichTextLineElement {
                     state.row_offset_for_point(&selection.focus).unwrap_or(0)
                 }
                 .min(layout.len());
-                if let Some(pos) = layout
-                    .position_for_index(caret_ix)
-                    .or_else(|| layout.position_for_index(layout.len()))
-                {
-                    let offset_x =
-                        Self::align_offset_x_for_index(&layout, &bounds, caret_ix, text_align);
-                    let pos = point(pos.x + offset_x, pos.y);
+                let pos = match &self.bidi {
+                    Some(bidi) => Some(bounds.origin + bidi.caret_position(caret_ix)),
+                    None => layout
+                        .position_for_index(caret_ix)
+                        .or_else(|| layout.position_for_index(layout.len()))
+                        .map(|pos| {
+                            let offset_x = Self::align_offset_x_for_index(
+                                &layout, &bounds, caret_ix, text_align,
+                            );
+                            point(pos.x + offset_x, pos.y)
+                        }),
+                };
+                if let Some(pos) = pos {
                     window.paint_quad(gpui::quad(
                         Bounds::from_corners(pos, point(pos.x + px(1.5), pos.y + line_height)),
                         px(0.),
@@ -5971,6 +6245,7 @@ impl Element for RichTextLineElement {
 
         Self::paint_selection_range(
             &layout,
+            self.bidi.as_deref(),
             a..b,
             &bounds,
             line_height,
@@ -6095,7 +6370,7 @@ impl Element for RichTextInputHandlerElement {
                 }
 
                 // Clicking a block-void node (image/divider) selects it (and does not enter
-                // "drag selecting" mode).
+                // "drag selecting" mode); a math block opens its source instead.
                 if event.click_count == 1 && !event.modifiers.shift && !event.modifiers.secondary()
                 {
                     let void_path: Option<Vec<usize>> = {
@@ -6107,7 +6382,15 @@ impl Element for RichTextInputHandlerElement {
                         window.focus(&focus_handle);
 
                         state.update(cx, |this, cx| {
-                            if this.selected_block_path.as_deref() == Some(void_path.as_slice()) {
+                            let is_math = matches!(
+                                node_at_path(this.editor.doc(), &void_path),
+                                Some(Node::Void(v)) if v.kind == "math_block"
+                            );
+                            if is_math && !this.read_only {
+                                this.command_edit_math_source(void_path, cx);
+                            } else if this.selected_block_path.as_deref()
+                                == Some(void_path.as_slice())
+                            {
                                 this.selected_block_path = None;
                             } else {
                                 this.selected_block_path = Some(void_path);
@@ -6269,8 +6552,10 @@ impl Element for RichTextInputHandlerElement {
                     }
                 }
 
-                // Cmd/Ctrl-click opens a link instead of moving the caret.
-                if event.modifiers.secondary() && !event.modifiers.shift {
+                // Cmd/Ctrl-click opens a link instead of moving the caret; in a read-only
+                // document a plain click does.
+                let read_only = state.read(cx).read_only;
+                if (event.modifiers.secondary() || read_only) && !event.modifiers.shift {
                     let (image_src, document_base_dir) = {
                         let this = state.read(cx);
                         let image_src =
@@ -6335,6 +6620,18 @@ impl Element for RichTextInputHandlerElement {
                         return;
                     };
 
+                    if event.click_count == 1
+                        && !event.modifiers.shift
+                        && !this.read_only
+                        && let Some(math_path) =
+                            this.math_inline_path_for_point(&block_path, event.position)
+                    {
+                        this.command_edit_math_source(math_path, cx);
+                        this.selecting = false;
+                        this.selection_anchor = None;
+                        return;
+                    }
+
                     let offset = this
                         .offset_for_point_in_block(&block_path, event.position)
                         .unwrap_or_else(|| this.block_text_len_in_block(&block_path));
@@ -6463,7 +6760,7 @@ impl Render for RichTextState {
         let registry = self.editor.registry();
         let columns_resizing = self.columns_resizing.clone();
 
-        if !self.did_auto_focus && !window.is_inspector_picking(cx) {
+        if !self.did_auto_focus && !self.read_only && !window.is_inspector_picking(cx) {
             window.focus(&self.focus_handle);
             self.did_auto_focus = true;
         }
@@ -6477,13 +6774,12 @@ impl Render for RichTextState {
         ) -> AnyElement {
             let line = RichTextLineElement::new(state.clone(), path);
 
-            let align = el.attrs.get("align").and_then(|v| v.as_str());
             let font_size = el.attrs.get("font_size").and_then(|v| v.as_u64());
             let mut text = div().w_full().min_w(px(0.));
-            match align {
-                Some("center") => text = text.text_center(),
-                Some("right") => text = text.text_right(),
-                _ => {}
+            match text_align_for_element(el) {
+                TextAlign::Center => text = text.text_center(),
+                TextAlign::Right => text = text.text_right(),
+                TextAlign::Left => {}
             }
 
             if el.kind == "heading" {
@@ -6535,10 +6831,11 @@ impl Render for RichTextState {
                 return block;
             }
 
-            div()
-                .pl(px(16. * indent_level as f32))
-                .child(block)
-                .into_any_element()
+            let indent = px(16. * indent_level as f32);
+            if bidi::block_direction(el) == TextDirection::Rtl {
+                return div().pr(indent).child(block).into_any_element();
+            }
+            div().pl(indent).child(block).into_any_element()
         }
 
         fn render_list_item(
@@ -6568,28 +6865,31 @@ impl Render for RichTextState {
                 .and_then(|v| v.as_u64())
                 .unwrap_or(0);
             let indent = px(16. * level as f32);
-            let align = el.attrs.get("align").and_then(|v| v.as_str());
             let font_size = el.attrs.get("font_size").and_then(|v| v.as_u64());
             let mut content = div().flex_1().min_w(px(0.));
-            match align {
-                Some("center") => content = content.text_center(),
-                Some("right") => content = content.text_right(),
-                _ => {}
+            match text_align_for_element(el) {
+                TextAlign::Center => content = content.text_center(),
+                TextAlign::Right => content = content.text_right(),
+                TextAlign::Left => {}
             }
             if let Some(size) = font_size {
                 content = content.text_size(px(size as f32));
             }
+            // Right-to-left items put the marker on the right.
+            let rtl = bidi::block_direction(el) == TextDirection::Rtl;
             div()
                 .flex()
                 .flex_row()
+                .when(rtl, |this| this.flex_row_reverse().pr(indent))
+                .when(!rtl, |this| this.pl(indent))
                 .items_start()
                 .gap(px(8.))
-                .pl(indent)
                 .child(
                     div()
                         .w(px(24.))
                         .flex()
-                        .justify_end()
+                        .when(rtl, |this| this.justify_start())
+                        .when(!rtl, |this| this.justify_end())
                         .text_color(theme.muted_foreground)
                         .child(marker),
                 )
@@ -6629,12 +6929,11 @@ impl Render for RichTextState {
                 })
                 .child(if checked { "✓" } else { "" });
 
-            let align = el.attrs.get("align").and_then(|v| v.as_str());
             let mut content = div().flex_1().min_w(px(0.));
-            match align {
-                Some("center") => content = content.text_center(),
-                Some("right") => content = content.text_right(),
-                _ => {}
+            match text_align_for_element(el) {
+                TextAlign::Center => content = content.text_center(),
+                TextAlign::Right => content = content.text_right(),
+                TextAlign::Left => {}
             }
             if let Some(size) = font_size {
                 content = content.text_size(px(size as f32));
@@ -6644,14 +6943,16 @@ impl Render for RichTextState {
             }
             content = content.child(RichTextLineElement::new(state.clone(), path));
 
+            let rtl = bidi::block_direction(el) == TextDirection::Rtl;
+            let indent = px(16. * indent_level as f32);
             div()
                 .flex()
                 .flex_row()
+                .when(rtl, |this| this.flex_row_reverse())
                 .items_start()
                 .gap(px(8.))
-                .when(indent_level > 0, |this| {
-                    this.pl(px(16. * indent_level as f32))
-                })
+                .when(indent_level > 0 && rtl, |this| this.pr(indent))
+                .when(indent_level > 0 && !rtl, |this| this.pl(indent))
                 .child(checkbox)
                 .child(content)
                 .into_any_element()
@@ -6687,13 +6988,12 @@ impl Render for RichTextState {
 
             let line = RichTextLineElement::new(state.clone(), title_path);
 
-            let align = title_el.attrs.get("align").and_then(|v| v.as_str());
             let font_size = title_el.attrs.get("font_size").and_then(|v| v.as_u64());
             let mut title = div().flex_1().min_w(px(0.));
-            match align {
-                Some("center") => title = title.text_center(),
-                Some("right") => title = title.text_right(),
-                _ => {}
+            match text_align_for_element(title_el) {
+                TextAlign::Center => title = title.text_center(),
+                TextAlign::Right => title = title.text_right(),
+                TextAlign::Left => {}
             }
             if title_el.kind == "heading" {
                 let level = title_el
@@ -7161,6 +7461,28 @@ impl Render for RichTextState {
                         )
                         .into_any_element()
                 }
+                Node::Void(v) if v.kind == "math_block" => {
+                    let tex = v.attrs.get("tex").and_then(|v| v.as_str()).unwrap_or("");
+                    let text = gpui_plate_core::transliterate_tex(tex);
+
+                    div()
+                        .py(px(10.))
+                        .px(px(12.))
+                        .rounded(theme.radius / 2.)
+                        .bg(theme.muted)
+                        .flex()
+                        .justify_center()
+                        .text_size(px(18.))
+                        .child(if text.is_empty() {
+                            div()
+                                .text_color(theme.muted_foreground)
+                                .child("Empty equation")
+                                .into_any_element()
+                        } else {
+                            div().child(text).into_any_element()
+                        })
+                        .into_any_element()
+                }
                 Node::Void(v) if v.kind == "divider" => div()
                     .py(px(8.))
                     .child(div().w_full().h(px(1.)).bg(theme.border))
@@ -7233,6 +7555,7 @@ impl Render for RichTextState {
                     .on_action(window.listener_for(&state, RichTextState::toggle_code))
                     .on_action(window.listener_for(&state, RichTextState::toggle_bulleted_list))
                     .on_action(window.listener_for(&state, RichTextState::toggle_ordered_list))
+                    .on_action(window.listener_for(&state, RichTextState::toggle_text_direction))
                     .on_drop(cx.listener(|this, paths: &ExternalPaths, window, cx| {
                         window.focus(&this.focus_handle);
 
@@ -8,6 +8,13 @@ pub enum BlockAlign {
     Right,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextDirection {
+    #[default]
+    Ltr,
+    Rtl,
+}
+
 #[derive(Debug, Clone)]
 pub struct CommandInfo {
     pub id: String,
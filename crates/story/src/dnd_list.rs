@@ -5,6 +5,8 @@ use gpui_component::list::ListItem;
 use gpui_component::{Icon, IconName, Sizable as _, h_flex, v_flex};
 use gpui_manos_dnd::{DndListItem, DndListRowState, DndListState, dnd_list};
 
+use crate::story::{Story, StoryContext};
+
 pub struct DndListExample {
     list: Entity<DndListState<()>>,
 }
@@ -17,6 +19,22 @@ impl DndListExample {
     }
 }
 
+impl Story for DndListExample {
+    const TITLE: &'static str = "DnD List";
+    const LABEL: &'static str = "List";
+    const DESCRIPTION: &'static str = "A sortable list; rows are dragged by their whole body.";
+    const KEYWORDS: &'static str = "drag drop sortable";
+    const ICON: IconName = IconName::Menu;
+    const GROUP: Option<&'static str> = Some("DnD");
+    const ORDER: i32 = 10;
+
+    fn new_view(_story_cx: &StoryContext, window: &mut Window, cx: &mut App) -> Entity<Self> {
+        Self::view(window, cx)
+    }
+}
+
+crate::register_story!(DndListExample);
+
 impl Render for DndListExample {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let theme = cx.theme();
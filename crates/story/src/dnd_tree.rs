@@ -2,11 +2,13 @@ use gpui::prelude::FluentBuilder as _;
 use gpui::*;
 use gpui_component::ActiveTheme as _;
 use gpui_component::list::ListItem;
-use gpui_component::{Icon, Sizable as _, h_flex, v_flex};
+use gpui_component::{Icon, IconName, Sizable as _, h_flex, v_flex};
 use gpui_manos_dnd::{
     DndTreeEntry, DndTreeIndicatorCap, DndTreeItem, DndTreeRowState, DndTreeState, dnd_tree,
 };
 
+use crate::story::{Story, StoryContext};
+
 pub struct DndTreeExample {
     tree: Entity<DndTreeState>,
 }
@@ -30,6 +32,23 @@ impl DndTreeExample {
     }
 }
 
+impl Story for DndTreeExample {
+    const TITLE: &'static str = "DnD Tree";
+    const LABEL: &'static str = "Tree";
+    const DESCRIPTION: &'static str =
+        "A nested tree whose nodes can be reordered and re-parented by dragging.";
+    const KEYWORDS: &'static str = "drag drop nested";
+    const ICON: IconName = IconName::FolderOpen;
+    const GROUP: Option<&'static str> = Some("DnD");
+    const ORDER: i32 = 11;
+
+    fn new_view(_story_cx: &StoryContext, window: &mut Window, cx: &mut App) -> Entity<Self> {
+        Self::view(window, cx)
+    }
+}
+
+crate::register_story!(DndTreeExample);
+
 impl Render for DndTreeExample {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let theme = cx.theme();
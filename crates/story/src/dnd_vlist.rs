@@ -5,6 +5,8 @@ use gpui_component::list::ListItem;
 use gpui_component::{Icon, IconName, Sizable as _, h_flex, v_flex};
 use gpui_manos_dnd::{DndVListItem, DndVListRowState, DndVListState, dnd_vlist};
 
+use crate::story::{Story, StoryContext};
+
 pub struct DndVListExample {
     list: Entity<DndVListState<()>>,
 }
@@ -17,6 +19,22 @@ impl DndVListExample {
     }
 }
 
+impl Story for DndVListExample {
+    const TITLE: &'static str = "DnD VList";
+    const LABEL: &'static str = "VList";
+    const DESCRIPTION: &'static str = "A virtualized sortable list with rows of varying height.";
+    const KEYWORDS: &'static str = "drag drop virtual list";
+    const ICON: IconName = IconName::ChevronsUpDown;
+    const GROUP: Option<&'static str> = Some("DnD");
+    const ORDER: i32 = 12;
+
+    fn new_view(_story_cx: &StoryContext, window: &mut Window, cx: &mut App) -> Entity<Self> {
+        Self::view(window, cx)
+    }
+}
+
+crate::register_story!(DndVListExample);
+
 impl Render for DndVListExample {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let theme = cx.theme();
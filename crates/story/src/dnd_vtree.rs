@@ -3,11 +3,13 @@ use gpui::*;
 use gpui_component::ActiveTheme as _;
 use gpui_component::button::{Button, ButtonVariants as _};
 use gpui_component::list::ListItem;
-use gpui_component::{Icon, Sizable as _, h_flex, v_flex};
+use gpui_component::{Icon, IconName, Sizable as _, h_flex, v_flex};
 use gpui_manos_dnd::{
     DndVTreeEntry, DndVTreeIndicatorCap, DndVTreeItem, DndVTreeRowState, DndVTreeState, dnd_vtree,
 };
 
+use crate::story::{Story, StoryContext};
+
 pub struct DndVTreeExample {
     tree: Entity<DndVTreeState>,
 }
@@ -31,6 +33,22 @@ impl DndVTreeExample {
     }
 }
 
+impl Story for DndVTreeExample {
+    const TITLE: &'static str = "DnD VTree";
+    const LABEL: &'static str = "VTree";
+    const DESCRIPTION: &'static str = "A virtualized tree whose rows can be moved between levels.";
+    const KEYWORDS: &'static str = "drag drop virtual tree";
+    const ICON: IconName = IconName::Folder;
+    const GROUP: Option<&'static str> = Some("DnD");
+    const ORDER: i32 = 13;
+
+    fn new_view(_story_cx: &StoryContext, window: &mut Window, cx: &mut App) -> Entity<Self> {
+        Self::view(window, cx)
+    }
+}
+
+crate::register_story!(DndVTreeExample);
+
 impl Render for DndVTreeExample {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let theme = cx.theme();
@@ -1,222 +1,124 @@
+use gpui::prelude::FluentBuilder as _;
 use gpui::*;
 use gpui_component::ActiveTheme as _;
+use gpui_component::input::{Input, InputEvent, InputState};
 use gpui_component::menu::AppMenuBar;
 use gpui_component::sidebar::{Sidebar, SidebarGroup, SidebarHeader, SidebarMenu, SidebarMenuItem};
 use gpui_component::text::TextView;
 use gpui_component::{Icon, IconName, Selectable as _, v_flex};
 
-use crate::dnd_list::DndListExample;
-use crate::dnd_tree::DndTreeExample;
-use crate::dnd_vlist::DndVListExample;
-use crate::dnd_vtree::DndVTreeExample;
-use crate::plate_toolbar_buttons::PlateToolbarButtonsStory;
-use crate::richtext::RichTextExample;
-use crate::simple_browser::SimpleBrowserStory;
-use crate::webview_story::WebViewStory;
+use crate::story::{StoryContext, StoryRegistration};
 
 const README_MD: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../../README.md"));
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum StoryId {
-    Introduction,
-    DndList,
-    DndTree,
-    DndVList,
-    DndVTree,
-    RichText,
-    PlateToolbarButtons,
-    WelcomeTauri,
-    SimpleBrowser,
-}
-
-impl StoryId {
-    fn title(self) -> &'static str {
-        match self {
-            StoryId::Introduction => "README.md",
-            StoryId::DndList => "DnD List",
-            StoryId::DndTree => "DnD Tree",
-            StoryId::DndVList => "DnD VList",
-            StoryId::DndVTree => "DnD VTree",
-            StoryId::RichText => "Rich Text Editor",
-            StoryId::PlateToolbarButtons => "Plate Toolbar Buttons",
-            StoryId::WelcomeTauri => "Welcome Tauri",
-            StoryId::SimpleBrowser => "Simple Browser",
-        }
-    }
-}
+const README_TITLE: &str = "README.md";
+const README_KEYWORDS: &str = "readme introduction getting started docs";
 
 pub struct StoryGallery {
-    app_menu_bar: Entity<AppMenuBar>,
-    selected: StoryId,
-    dnd_list: Option<Entity<DndListExample>>,
-    dnd_tree: Option<Entity<DndTreeExample>>,
-    dnd_vlist: Option<Entity<DndVListExample>>,
-    dnd_vtree: Option<Entity<DndVTreeExample>>,
-    richtext: Option<Entity<RichTextExample>>,
-    plate_toolbar_buttons: Option<Entity<PlateToolbarButtonsStory>>,
-    welcome_tauri: Option<Entity<WebViewStory>>,
-    simple_browser: Option<Entity<SimpleBrowserStory>>,
+    story_cx: StoryContext,
+    /// Every registered story, in sidebar order.
+    stories: Vec<&'static StoryRegistration>,
+    /// Index into `stories`; `None` shows the README.
+    selected: Option<usize>,
+    /// Views are built the first time their story is selected.
+    views: Vec<Option<AnyView>>,
+    search_input: Entity<InputState>,
 }
 
 impl StoryGallery {
     pub fn view(
         app_menu_bar: Entity<AppMenuBar>,
-        _window: &mut Window,
+        window: &mut Window,
         cx: &mut App,
     ) -> Entity<Self> {
-        cx.new(|_| Self {
-            app_menu_bar,
-            selected: StoryId::Introduction,
-            dnd_list: None,
-            dnd_tree: None,
-            dnd_vlist: None,
-            dnd_vtree: None,
-            richtext: None,
-            plate_toolbar_buttons: None,
-            welcome_tauri: None,
-            simple_browser: None,
-        })
+        cx.new(|cx| Self::new(app_menu_bar, window, cx))
     }
 
-    fn select_story(&mut self, next: StoryId, cx: &mut Context<Self>) {
-        if self.selected == next {
-            return;
-        }
-
-        match self.selected {
-            StoryId::WelcomeTauri => {
-                if let Some(story) = &self.welcome_tauri {
-                    story.update(cx, |story, cx| story.set_visible(false, cx));
-                }
-            }
-            StoryId::SimpleBrowser => {
-                if let Some(story) = &self.simple_browser {
-                    story.update(cx, |story, cx| story.set_visible(false, cx));
-                }
-            }
-            _ => {}
-        };
-
-        self.selected = next;
-
-        match self.selected {
-            StoryId::WelcomeTauri => {
-                if let Some(story) = &self.welcome_tauri {
-                    story.update(cx, |story, cx| story.set_visible(true, cx));
-                }
-            }
-            StoryId::SimpleBrowser => {
-                if let Some(story) = &self.simple_browser {
-                    story.update(cx, |story, cx| story.set_visible(true, cx));
+    fn new(app_menu_bar: Entity<AppMenuBar>, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let search_input = cx.new(|cx| InputState::new(window, cx).placeholder("Search stories"));
+
+        cx.subscribe(
+            &search_input,
+            |this, _, event: &InputEvent, cx| match event {
+                InputEvent::Change { .. } => cx.notify(),
+                // Enter jumps to the first story that matches the query.
+                InputEvent::PressEnter { .. } => {
+                    if let Some(&next) = this.search_results(cx).first() {
+                        this.select_story(next, cx);
+                    }
                 }
-            }
-            _ => {}
-        };
-
-        cx.notify();
-    }
-
-    fn ensure_dnd_list(
-        &mut self,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) -> Entity<DndListExample> {
-        if let Some(view) = &self.dnd_list {
-            return view.clone();
+                _ => {}
+            },
+        )
+        .detach();
+
+        let stories = StoryRegistration::all();
+        Self {
+            story_cx: StoryContext { app_menu_bar },
+            views: vec![None; stories.len()],
+            stories,
+            selected: None,
+            search_input,
         }
-        let view = DndListExample::view(window, cx);
-        self.dnd_list = Some(view.clone());
-        view
     }
 
-    fn ensure_dnd_tree(
-        &mut self,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) -> Entity<DndTreeExample> {
-        if let Some(view) = &self.dnd_tree {
-            return view.clone();
-        }
-        let view = DndTreeExample::view(window, cx);
-        self.dnd_tree = Some(view.clone());
-        view
+    fn search_query(&self, cx: &App) -> String {
+        self.search_input.read(cx).value().trim().to_string()
     }
 
-    fn ensure_dnd_vlist(
-        &mut self,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) -> Entity<DndVListExample> {
-        if let Some(view) = &self.dnd_vlist {
-            return view.clone();
-        }
-        let view = DndVListExample::view(window, cx);
-        self.dnd_vlist = Some(view.clone());
-        view
+    /// Selections matching the search box, README first.
+    fn search_results(&self, cx: &App) -> Vec<Option<usize>> {
+        let query = self.search_query(cx);
+        let readme_matches = query.to_lowercase().split_whitespace().all(|term| {
+            format!("{README_TITLE} {README_KEYWORDS}")
+                .to_lowercase()
+                .contains(term)
+        });
+
+        readme_matches
+            .then_some(None)
+            .into_iter()
+            .chain(
+                self.stories
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, story)| story.matches(&query))
+                    .map(|(index, _)| Some(index)),
+            )
+            .collect()
     }
 
-    fn ensure_dnd_vtree(
-        &mut self,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) -> Entity<DndVTreeExample> {
-        if let Some(view) = &self.dnd_vtree {
-            return view.clone();
+    fn select_story(&mut self, next: Option<usize>, cx: &mut Context<Self>) {
+        if self.selected == next {
+            return;
         }
-        let view = DndVTreeExample::view(window, cx);
-        self.dnd_vtree = Some(view.clone());
-        view
-    }
 
-    fn ensure_richtext(
-        &mut self,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) -> Entity<RichTextExample> {
-        if let Some(view) = &self.richtext {
-            return view.clone();
-        }
-        let view = RichTextExample::view(self.app_menu_bar.clone(), window, cx);
-        self.richtext = Some(view.clone());
-        view
-    }
+        self.set_story_active(self.selected, false, cx);
+        self.selected = next;
+        self.set_story_active(self.selected, true, cx);
 
-    fn ensure_plate_toolbar_buttons(
-        &mut self,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) -> Entity<PlateToolbarButtonsStory> {
-        if let Some(view) = &self.plate_toolbar_buttons {
-            return view.clone();
-        }
-        let view = PlateToolbarButtonsStory::view(window, cx);
-        self.plate_toolbar_buttons = Some(view.clone());
-        view
+        cx.notify();
     }
 
-    fn ensure_welcome_tauri(
-        &mut self,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) -> Entity<WebViewStory> {
-        if let Some(view) = &self.welcome_tauri {
-            return view.clone();
+    fn set_story_active(&self, index: Option<usize>, active: bool, cx: &mut App) {
+        let Some(index) = index else {
+            return;
+        };
+        if let Some(view) = &self.views[index] {
+            self.stories[index].set_active(view, active, cx);
         }
-        let view = WebViewStory::view(window, cx);
-        self.welcome_tauri = Some(view.clone());
-        view
     }
 
-    fn ensure_simple_browser(
+    fn ensure_view(
         &mut self,
+        index: usize,
         window: &mut Window,
         cx: &mut Context<Self>,
-    ) -> Entity<SimpleBrowserStory> {
-        if let Some(view) = &self.simple_browser {
+    ) -> AnyView {
+        if let Some(view) = &self.views[index] {
             return view.clone();
         }
-        let view = SimpleBrowserStory::view(window, cx);
-        self.simple_browser = Some(view.clone());
+        let view = self.stories[index].build(&self.story_cx, window, cx);
+        self.views[index] = Some(view.clone());
         view
     }
 
@@ -234,55 +136,85 @@ impl StoryGallery {
     fn sidebar(&self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let gallery = cx.entity();
         let selected = self.selected;
+        let searching = !self.search_query(cx).is_empty();
 
-        let item = |id: StoryId, label: &'static str, icon: IconName| {
+        let item = |target: Option<usize>, label: &'static str, icon: IconName| {
             SidebarMenuItem::new(label)
                 .icon(Icon::new(icon).size_4())
-                .active(selected == id)
+                .active(selected == target)
                 .on_click({
                     let gallery = gallery.clone();
                     move |_, _window, cx| {
-                        gallery.update(cx, |this, cx| this.select_story(id, cx));
+                        gallery.update(cx, |this, cx| this.select_story(target, cx));
                     }
                 })
         };
+        let readme_item = || item(None, README_TITLE, IconName::Info);
+        let story_item = |index: usize, label: &'static str| {
+            let story = self.stories[index];
+            item(Some(index), label, story.icon.clone())
+        };
 
-        let dnd_menu = SidebarMenuItem::new("DnD")
-            .icon(Icon::new(IconName::LayoutDashboard).size_4())
-            .default_open(true)
-            .children([
-                item(StoryId::DndList, "List", IconName::Menu),
-                item(StoryId::DndTree, "Tree", IconName::FolderOpen),
-                item(StoryId::DndVList, "VList", IconName::ChevronsUpDown),
-                item(StoryId::DndVTree, "VTree", IconName::Folder),
-            ]);
-
-        let stories_menu = SidebarMenu::new().children([
-            dnd_menu,
-            item(StoryId::RichText, "Rich Text", IconName::ALargeSmall),
-            item(
-                StoryId::PlateToolbarButtons,
-                "Plate Toolbar",
-                IconName::Palette,
-            ),
-            item(StoryId::WelcomeTauri, "Welcome Tauri", IconName::Frame),
-            item(StoryId::SimpleBrowser, "Simple Browser", IconName::Globe),
-        ]);
-
-        let getting_started_menu =
-            SidebarMenu::new().child(item(StoryId::Introduction, "README.md", IconName::Info));
-
-        let header = SidebarHeader::new()
-            .child(Icon::new(IconName::GalleryVerticalEnd).size_4())
-            .child(div().font_weight(FontWeight::MEDIUM).child("Gallery"))
-            .selected(selected == StoryId::Introduction);
+        let groups = if searching {
+            // Search results are listed flat, so use full titles to keep grouped stories apart.
+            let results = self.search_results(cx);
+            let label = if results.is_empty() {
+                "No matching stories"
+            } else {
+                "Search Results"
+            };
+            let items = results.into_iter().map(|target| match target {
+                None => readme_item(),
+                Some(index) => story_item(index, self.stories[index].title),
+            });
+            vec![SidebarGroup::new(label).child(SidebarMenu::new().children(items))]
+        } else {
+            let mut stories_menu = SidebarMenu::new();
+            let mut seen_groups: Vec<&'static str> = Vec::new();
+            for (index, story) in self.stories.iter().enumerate() {
+                let Some(group) = story.group else {
+                    stories_menu = stories_menu.child(story_item(index, story.label));
+                    continue;
+                };
+                if seen_groups.contains(&group) {
+                    continue;
+                }
+                seen_groups.push(group);
+
+                // A group sits where its first story would, with all its members nested inside.
+                let members = self
+                    .stories
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, member)| member.group == Some(group))
+                    .map(|(index, member)| story_item(index, member.label));
+                stories_menu = stories_menu.child(
+                    SidebarMenuItem::new(group)
+                        .icon(Icon::new(IconName::LayoutDashboard).size_4())
+                        .default_open(true)
+                        .children(members),
+                );
+            }
+
+            vec![
+                SidebarGroup::new("Getting Started").child(SidebarMenu::new().child(readme_item())),
+                SidebarGroup::new("Stories").child(stories_menu),
+            ]
+        };
+
+        let header = v_flex()
+            .gap_2()
+            .child(
+                SidebarHeader::new()
+                    .child(Icon::new(IconName::GalleryVerticalEnd).size_4())
+                    .child(div().font_weight(FontWeight::MEDIUM).child("Gallery"))
+                    .selected(selected.is_none()),
+            )
+            .child(Input::new(&self.search_input));
 
         Sidebar::left()
             .header(header)
-            .children([
-                SidebarGroup::new("Getting Started").child(getting_started_menu),
-                SidebarGroup::new("Stories").child(stories_menu),
-            ])
+            .children(groups)
             .render(window, cx)
     }
 }
@@ -431,39 +363,23 @@ fn rewrite_repo_relative_url(url: &str) -> Option<String> {
 
 impl Render for StoryGallery {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        let content: AnyElement = match self.selected {
-            StoryId::Introduction => self.render_readme(window, cx),
-            StoryId::DndList => self.ensure_dnd_list(window, cx).into_any_element(),
-            StoryId::DndTree => self.ensure_dnd_tree(window, cx).into_any_element(),
-            StoryId::DndVList => self.ensure_dnd_vlist(window, cx).into_any_element(),
-            StoryId::DndVTree => self.ensure_dnd_vtree(window, cx).into_any_element(),
-            StoryId::RichText => self.ensure_richtext(window, cx).into_any_element(),
-            StoryId::PlateToolbarButtons => self
-                .ensure_plate_toolbar_buttons(window, cx)
-                .into_any_element(),
-            StoryId::WelcomeTauri => {
-                let view = self.ensure_welcome_tauri(window, cx);
-                view.update(cx, |story, cx| story.set_visible(true, cx));
-                view.into_any_element()
-            }
-            StoryId::SimpleBrowser => {
-                let view = self.ensure_simple_browser(window, cx);
-                view.update(cx, |story, cx| story.set_visible(true, cx));
-                view.into_any_element()
-            }
-        };
+        let (content, title, description): (AnyElement, &'static str, &'static str) =
+            match self.selected {
+                None => (self.render_readme(window, cx), README_TITLE, ""),
+                Some(index) => {
+                    let story = self.stories[index];
+                    (
+                        self.ensure_view(index, window, cx).into_any_element(),
+                        story.title,
+                        story.description,
+                    )
+                }
+            };
 
-        // Hide webviews immediately when they are not selected, otherwise they may remain visible
-        // at the last bounds (they are native views).
-        if self.selected != StoryId::WelcomeTauri {
-            if let Some(story) = &self.welcome_tauri {
-                story.update(cx, |story, cx| story.set_visible(false, cx));
-            }
-        }
-        if self.selected != StoryId::SimpleBrowser {
-            if let Some(story) = &self.simple_browser {
-                story.update(cx, |story, cx| story.set_visible(false, cx));
-            }
+        // Keep only the selected story active; native views (webviews) otherwise stay visible
+        // at their last bounds.
+        for index in 0..self.stories.len() {
+            self.set_story_active(Some(index), self.selected == Some(index), cx);
         }
 
         v_flex().size_full().child(
@@ -482,8 +398,9 @@ impl Render for StoryGallery {
                             v_flex()
                                 .size_full()
                                 .child(
-                                    div()
+                                    v_flex()
                                         .w_full()
+                                        .gap_1()
                                         .border_b_1()
                                         .border_color(cx.theme().border)
                                         .bg(cx.theme().background)
@@ -493,8 +410,16 @@ impl Render for StoryGallery {
                                             div()
                                                 .text_sm()
                                                 .font_weight(FontWeight::MEDIUM)
-                                                .child(self.selected.title()),
-                                        ),
+                                                .child(title),
+                                        )
+                                        .when(!description.is_empty(), |this| {
+                                            this.child(
+                                                div()
+                                                    .text_xs()
+                                                    .text_color(cx.theme().muted_foreground)
+                                                    .child(description),
+                                            )
+                                        }),
                                 )
                                 .child(div().flex_1().min_h(px(0.)).child(content)),
                         ),
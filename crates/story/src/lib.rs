@@ -7,5 +7,7 @@ pub mod gallery;
 pub mod plate_toolbar_buttons;
 pub mod richtext;
 pub mod simple_browser;
+pub mod story;
+pub mod theme_editor;
 pub mod themes;
 pub mod webview_story;
@@ -4,12 +4,22 @@ use gpui_manos_components::assets::ExtrasAssetSource;
 use gpui_manos_components_story::app_menus;
 use gpui_manos_components_story::gallery::StoryGallery;
 use gpui_manos_components_story::themes;
+use gpui_manos_webview::window_state::WindowStateStore;
+use manos_config::ConfigStore;
 use std::future::Future;
 use std::path::{Component, Path, PathBuf};
 use std::pin::Pin;
 use std::sync::Arc;
 
+/// Label the main window's position is saved under.
+const MAIN_WINDOW: &str = "main";
+
 fn main() {
+    let _log = manos_log::init("manos-story");
+    let config = ConfigStore::open_default().unwrap_or_else(|err| {
+        manos_log::tracing::warn!("cannot read the settings file: {err}");
+        ConfigStore::in_memory()
+    });
     let repo_root = repo_root();
     let app = Application::new()
         .with_assets(ExtrasAssetSource::new())
@@ -18,17 +28,23 @@ fn main() {
     app.run(move |cx| {
         gpui_component::init(cx);
         gpui_manos_plate::init(cx);
-        themes::init(cx);
+        themes::init(&config, cx);
         cx.activate(true);
 
+        let window_states = WindowStateStore::new(&config);
+        let window_bounds = window_states
+            .get(MAIN_WINDOW)
+            .map(|state| state.window_bounds());
         cx.spawn(async move |cx| {
             cx.open_window(
                 WindowOptions {
+                    window_bounds,
                     titlebar: Some(TitleBar::title_bar_options()),
                     ..Default::default()
                 },
                 |window, cx| {
                     window.set_window_title("Manos Stories");
+                    window_states.save_on_close(MAIN_WINDOW, window, cx);
                     let app_menu_bar = app_menus::init("Manos Stories", window, cx);
                     let view = StoryGallery::view(app_menu_bar, window, cx);
                     cx.new(|cx| Root::new(view, window, cx))
@@ -2,6 +2,7 @@ use gpui::prelude::FluentBuilder as _;
 use gpui::*;
 use gpui_component::ActiveTheme as _;
 use gpui_component::Disableable as _;
+use gpui_component::IconName;
 use gpui_component::Selectable as _;
 use gpui_component::WindowExt as _;
 use gpui_component::input::InputState;
@@ -13,6 +14,8 @@ use gpui_manos_components::plate_toolbar::{
 };
 use gpui_manos_plate::BlockAlign;
 
+use crate::story::{Story, StoryContext};
+
 pub struct PlateToolbarButtonsStory {
     bold: bool,
     italic: bool,
@@ -66,6 +69,21 @@ impl PlateToolbarButtonsStory {
     }
 }
 
+impl Story for PlateToolbarButtonsStory {
+    const TITLE: &'static str = "Plate Toolbar Buttons";
+    const LABEL: &'static str = "Plate Toolbar";
+    const DESCRIPTION: &'static str = "Every Plate toolbar button from gpui-manos-components.";
+    const KEYWORDS: &'static str = "components buttons toolbar";
+    const ICON: IconName = IconName::Palette;
+    const ORDER: i32 = 30;
+
+    fn new_view(_story_cx: &StoryContext, window: &mut Window, cx: &mut App) -> Entity<Self> {
+        Self::view(window, cx)
+    }
+}
+
+crate::register_story!(PlateToolbarButtonsStory);
+
 impl Render for PlateToolbarButtonsStory {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let theme = cx.theme();
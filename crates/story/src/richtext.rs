@@ -14,10 +14,12 @@ use gpui_component::{
     popover::Popover,
 };
 use gpui_manos_components::plate_toolbar::{
-    PlateIconName, PlateToolbarButton, PlateToolbarColorPicker, PlateToolbarDropdownButton,
-    PlateToolbarIconButton, PlateToolbarRounding, PlateToolbarSeparator,
+    PlateIconName, PlateToolbar, PlateToolbarButton, PlateToolbarColorPicker,
+    PlateToolbarDropdownButton, PlateToolbarIconButton, PlateToolbarItem, PlateToolbarRounding,
+};
+use gpui_manos_plate::{
+    BlockAlign, CommandInfo, PlateValue, PortabilityReport, RichTextState, TextDirection,
 };
-use gpui_manos_plate::{BlockAlign, CommandInfo, PlateValue, PortabilityReport, RichTextState};
 
 use crate::app_menus::{
     About, COMMAND_PALETTE_CONTEXT, CollectAssets, CommandPalette, CommandPaletteSelectNext,
@@ -25,6 +27,7 @@ use crate::app_menus::{
     FIND_CONTEXT, Find, FindNext, FindPrev, InsertImage, Open,
     PortabilityReport as ShowPortabilityReport, Save, SaveAs, SetLink,
 };
+use crate::story::{Story, StoryContext};
 
 pub struct RichTextExample {
     app_menu_bar: Entity<AppMenuBar>,
@@ -792,6 +795,22 @@ impl RichTextExample {
     }
 }
 
+impl Story for RichTextExample {
+    const TITLE: &'static str = "Rich Text Editor";
+    const LABEL: &'static str = "Rich Text";
+    const DESCRIPTION: &'static str =
+        "The Plate rich text editor with toolbar, dialogs and file menu.";
+    const KEYWORDS: &'static str = "editor plate richtext";
+    const ICON: IconName = IconName::ALargeSmall;
+    const ORDER: i32 = 20;
+
+    fn new_view(story_cx: &StoryContext, window: &mut Window, cx: &mut App) -> Entity<Self> {
+        Self::view(story_cx.app_menu_bar.clone(), window, cx)
+    }
+}
+
+crate::register_story!(RichTextExample);
+
 impl Render for RichTextExample {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let theme = cx.theme().clone();
@@ -815,6 +834,7 @@ impl Render for RichTextExample {
             todo,
             indent_level,
             align,
+            direction,
         ) = {
             let editor = self.editor.read(cx);
             (
@@ -837,6 +857,7 @@ impl Render for RichTextExample {
                 editor.is_todo_active(),
                 editor.indent_level(),
                 editor.block_align(),
+                editor.block_direction(),
             )
         };
         let bold = marks.bold;
@@ -852,6 +873,16 @@ impl Render for RichTextExample {
             BlockAlign::Center => PlateIconName::AlignCenter,
             BlockAlign::Right => PlateIconName::AlignRight,
         };
+        let (direction_icon, direction_tooltip) = match direction {
+            TextDirection::Ltr => (
+                PlateIconName::PilcrowRight,
+                "Left to right (Cmd/Ctrl+Shift+R)",
+            ),
+            TextDirection::Rtl => (
+                PlateIconName::PilcrowLeft,
+                "Right to left (Cmd/Ctrl+Shift+R)",
+            ),
+        };
         let font_size_effective =
             effective_font_size(font_size, heading_level, code_block_active).clamp(8, 72);
         let font_size_label: SharedString = match font_size {
@@ -876,156 +907,13 @@ impl Render for RichTextExample {
             }
         };
 
-        div()
-            .size_full()
-            .relative()
-            .flex()
-            .flex_col()
-            .bg(theme.muted)
-            .on_action(cx.listener(|this, _: &Open, window, cx| {
-                this.open_from_file(window, cx);
-            }))
-            .on_action(cx.listener(|this, _: &Save, window, cx| {
-                this.save_to_file(window, cx);
-            }))
-            .on_action(cx.listener(|this, _: &SaveAs, window, cx| {
-                this.save_as(window, cx);
-            }))
-            .on_action(cx.listener(|this, _: &EmbedLocalImages, window, cx| {
-                let report = this
-                    .editor
-                    .update(cx, |editor, cx| editor.command_embed_local_images(cx));
-                let message = if report.embedded > 0 {
-                    let mut msg = format!("Embedded {} local image(s).", report.embedded);
-                    if report.failed > 0 {
-                        msg.push_str(&format!(" Failed: {}.", report.failed));
-                    }
-                    if report.skipped > 0 {
-                        msg.push_str(&format!(" Skipped: {}.", report.skipped));
-                    }
-                    msg
-                } else if report.failed > 0 {
-                    format!("No images embedded. Failed: {}.", report.failed)
-                } else {
-                    "No local images found to embed.".to_string()
-                };
-                window.push_notification(Notification::new().message(message).autohide(true), cx);
-
-                let handle = this.editor.read(cx).focus_handle();
-                window.focus(&handle);
-            }))
-            .on_action(cx.listener(|this, _: &ExportPortableJson, window, cx| {
-                this.export_portable_json(window, cx);
-            }))
-            .on_action(cx.listener(|this, _: &ExportPlateBundle, window, cx| {
-                this.export_plate_bundle(window, cx);
-            }))
-            .on_action(cx.listener(|this, _: &CollectAssets, window, cx| {
-                if this.file_path.is_none() {
-                    window.push_notification(
-                        Notification::new()
-                            .message(
-                                "Save the document first (Save As...) to set a base directory.",
-                            )
-                            .autohide(true),
-                        cx,
-                    );
-                    return;
-                }
-
-                let report = this.editor.update(cx, |editor, cx| {
-                    editor.command_collect_assets_into_assets_dir(cx)
-                });
-                let message = if report.rewritten > 0 || report.assets_written > 0 {
-                    let mut msg = format!(
-                        "Collected assets into ./assets (rewritten {}, wrote {}).",
-                        report.rewritten, report.assets_written
-                    );
-                    if report.rewritten_data_url > 0 {
-                        msg.push_str(&format!(" Data: {}.", report.rewritten_data_url));
-                    }
-                    if report.failed > 0 {
-                        msg.push_str(&format!(" Failed: {}.", report.failed));
-                    }
-                    if report.skipped > 0 {
-                        msg.push_str(&format!(" Skipped: {}.", report.skipped));
-                    }
-                    msg
-                } else if report.failed > 0 {
-                    format!("No assets collected. Failed: {}.", report.failed)
-                } else {
-                    "No images found to collect.".to_string()
-                };
-                window.push_notification(Notification::new().message(message).autohide(true), cx);
-
-                let handle = this.editor.read(cx).focus_handle();
-                window.focus(&handle);
-            }))
-            .on_action(cx.listener(|this, _: &ShowPortabilityReport, window, cx| {
-                this.open_portability_report_dialog(window, cx);
-            }))
-            .on_action(cx.listener(|this, _: &CommandPalette, window, cx| {
-                this.open_command_palette_dialog(window, cx);
-            }))
-            .on_action(cx.listener(|this, _: &InsertImage, window, cx| {
-                this.open_image_dialog(String::new(), window, cx);
-            }))
-            .on_action(cx.listener(|this, _: &SetLink, window, cx| {
-                if this.editor.read(cx).has_selected_block() {
-                    window.push_notification(
-                        Notification::new()
-                            .message("Set Link is unavailable when a block is selected.")
-                            .autohide(true),
-                        cx,
-                    );
-                    return;
-                }
-                let initial = this.editor.read(cx).active_link_url().unwrap_or_default();
-                this.open_link_dialog(initial, window, cx);
-            }))
-            .on_action(cx.listener(|this, _: &Find, window, cx| {
-                this.open_find_dialog(window, cx);
-            }))
-            .on_action(cx.listener(|this, _: &FindNext, window, cx| {
-                this.editor.update(cx, |editor, cx| {
-                    editor.find_next(cx);
-                });
-                let handle = this.editor.read(cx).focus_handle();
-                window.focus(&handle);
-            }))
-            .on_action(cx.listener(|this, _: &FindPrev, window, cx| {
-                this.editor.update(cx, |editor, cx| {
-                    editor.find_prev(cx);
-                });
-                let handle = this.editor.read(cx).focus_handle();
-                window.focus(&handle);
-            }))
-            .on_action(cx.listener(|_, _: &About, window, cx| {
-                window.push_notification(
-                    Notification::new()
-                        .message("Manos Rich Text (Plate Core)")
-                        .autohide(true),
-                    cx,
-                );
-            }))
-            .child(
-                TitleBar::new().child(div().flex().items_center().child(self.app_menu_bar.clone())),
-            )
-            .child(
-                div()
-                    .id("richtext-toolbar")
-                    .flex()
-                    .flex_row()
-                    .w_full()
-                    .overflow_x_scroll()
-                    .items_center()
-                    .gap(px(6.))
-                    .p(px(8.))
-                    .bg(theme.background)
-                    .border_b_1()
-                    .border_color(theme.border)
-                    .on_mouse_down(MouseButton::Left, |_, _, cx| cx.stop_propagation())
-                    .child(
+        let toolbar = PlateToolbar::new("richtext-toolbar-items")
+            .w_full()
+            .group(
+                "history",
+                [
+                    PlateToolbarItem::new(
+                        "undo",
                         PlateToolbarIconButton::new("undo", PlateIconName::Undo2)
                             .disabled(!can_undo)
                             .tooltip("Undo")
@@ -1034,8 +922,9 @@ impl Render for RichTextExample {
                                 let handle = this.editor.read(cx).focus_handle();
                                 window.focus(&handle);
                             })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "redo",
                         PlateToolbarIconButton::new("redo", PlateIconName::Redo2)
                             .disabled(!can_redo)
                             .tooltip("Redo")
@@ -1044,9 +933,14 @@ impl Render for RichTextExample {
                                 let handle = this.editor.read(cx).focus_handle();
                                 window.focus(&handle);
                             })),
-                    )
-                    .child(PlateToolbarSeparator)
-                    .child(
+                    ),
+                ],
+            )
+            .group(
+                "block",
+                [
+                    PlateToolbarItem::new(
+                        "block-duplicate",
                         PlateToolbarIconButton::new("block-duplicate", PlateIconName::Plus)
                             .disabled(!has_selected_block)
                             .tooltip("Duplicate selected block (Alt+Click block first)")
@@ -1056,8 +950,9 @@ impl Render for RichTextExample {
                                 let handle = this.editor.read(cx).focus_handle();
                                 window.focus(&handle);
                             })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "block-move-up",
                         PlateToolbarIconButton::new("block-move-up", PlateIconName::ArrowUpToLine)
                             .disabled(!can_move_selected_block_up)
                             .tooltip("Move selected block up (Alt+Click block first)")
@@ -1067,8 +962,9 @@ impl Render for RichTextExample {
                                 let handle = this.editor.read(cx).focus_handle();
                                 window.focus(&handle);
                             })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "block-move-down",
                         PlateToolbarIconButton::new(
                             "block-move-down",
                             PlateIconName::ArrowDownToLine,
@@ -1081,8 +977,9 @@ impl Render for RichTextExample {
                             let handle = this.editor.read(cx).focus_handle();
                             window.focus(&handle);
                         })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "block-delete",
                         PlateToolbarIconButton::new("block-delete", PlateIconName::ListCollapse)
                             .disabled(!has_selected_block)
                             .tooltip("Delete selected block (Alt+Click block first)")
@@ -1092,9 +989,14 @@ impl Render for RichTextExample {
                                 let handle = this.editor.read(cx).focus_handle();
                                 window.focus(&handle);
                             })),
-                    )
-                    .child(PlateToolbarSeparator)
-                    .child(
+                    ),
+                ],
+            )
+            .group(
+                "insert",
+                [
+                    PlateToolbarItem::new(
+                        "divider",
                         PlateToolbarIconButton::new("divider", PlateIconName::Minus)
                             .tooltip("Insert divider (Cmd/Ctrl+Shift+D)")
                             .on_click(cx.listener(|this, _, window, cx| {
@@ -1103,8 +1005,9 @@ impl Render for RichTextExample {
                                 let handle = this.editor.read(cx).focus_handle();
                                 window.focus(&handle);
                             })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "image",
                         PlateToolbarIconButton::new("image", PlateIconName::Image)
                             .tooltip(
                                 "Insert image (Click: URL/path dialog, Shift+Click: file picker)",
@@ -1116,22 +1019,25 @@ impl Render for RichTextExample {
                                     this.open_image_dialog(String::new(), window, cx);
                                 }
                             })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "mention",
                         PlateToolbarIconButton::new("mention", PlateIconName::MessageSquareText)
                             .tooltip("Insert mention (Cmd/Ctrl+Shift+M inserts default)")
                             .on_click(cx.listener(|this, _, window, cx| {
                                 this.open_mention_dialog(String::new(), window, cx);
                             })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "emoji",
                         PlateToolbarIconButton::new("emoji", PlateIconName::Smile)
                             .tooltip("Insert emoji")
                             .on_click(cx.listener(|this, _, window, cx| {
                                 this.open_emoji_dialog(String::new(), window, cx);
                             })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "table",
                         PlateToolbarIconButton::new("table", PlateIconName::Table)
                             .tooltip("Insert table (2×2)")
                             .on_click(cx.listener(|this, _, window, cx| {
@@ -1140,8 +1046,8 @@ impl Render for RichTextExample {
                                 let handle = this.editor.read(cx).focus_handle();
                                 window.focus(&handle);
                             })),
-                    )
-                    .child({
+                    ),
+                    PlateToolbarItem::new("table-row", {
                         let editor = self.editor.clone();
 
                         fn row_above(ed: &mut RichTextState, cx: &mut Context<RichTextState>) {
@@ -1243,7 +1149,8 @@ impl Render for RichTextExample {
                                     ))
                             })
                     })
-                    .child({
+                    .width(px(52.)),
+                    PlateToolbarItem::new("table-col", {
                         let editor = self.editor.clone();
 
                         fn col_left(ed: &mut RichTextState, cx: &mut Context<RichTextState>) {
@@ -1336,7 +1243,9 @@ impl Render for RichTextExample {
                                     ))
                             })
                     })
-                    .child(
+                    .width(px(52.)),
+                    PlateToolbarItem::new(
+                        "columns",
                         PlateToolbarIconButton::new("columns", PlateIconName::AlignJustify)
                             .selected(columns_active)
                             .tooltip(if columns_active {
@@ -1355,9 +1264,13 @@ impl Render for RichTextExample {
                                 let handle = this.editor.read(cx).focus_handle();
                                 window.focus(&handle);
                             })),
-                    )
-                    .child(PlateToolbarSeparator)
-                    .child({
+                    ),
+                ],
+            )
+            .group(
+                "format",
+                [
+                    PlateToolbarItem::new("heading", {
                         let editor = self.editor.clone();
                         let heading_label = heading_label.clone();
                         let heading_level = heading_level;
@@ -1530,7 +1443,8 @@ impl Render for RichTextExample {
                                     ))
                             })
                     })
-                    .child({
+                    .width(px(120.)),
+                    PlateToolbarItem::new("font-size", {
                         div()
                             .flex()
                             .items_center()
@@ -1605,7 +1519,9 @@ impl Render for RichTextExample {
                                     })),
                             )
                     })
-                    .child(
+                    .width(px(96.)),
+                    PlateToolbarItem::new(
+                        "bold",
                         PlateToolbarIconButton::new("bold", PlateIconName::Bold)
                             .selected(bold)
                             .tooltip("Bold (Cmd/Ctrl+B)")
@@ -1614,8 +1530,9 @@ impl Render for RichTextExample {
                                 let handle = this.editor.read(cx).focus_handle();
                                 window.focus(&handle);
                             })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "italic",
                         PlateToolbarIconButton::new("italic", PlateIconName::Italic)
                             .selected(italic)
                             .tooltip("Italic (Cmd/Ctrl+I)")
@@ -1625,8 +1542,9 @@ impl Render for RichTextExample {
                                 let handle = this.editor.read(cx).focus_handle();
                                 window.focus(&handle);
                             })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "underline",
                         PlateToolbarIconButton::new("underline", PlateIconName::Underline)
                             .selected(underline)
                             .tooltip("Underline (Cmd/Ctrl+U)")
@@ -1636,8 +1554,9 @@ impl Render for RichTextExample {
                                 let handle = this.editor.read(cx).focus_handle();
                                 window.focus(&handle);
                             })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "strikethrough",
                         PlateToolbarIconButton::new("strikethrough", PlateIconName::Strikethrough)
                             .selected(strikethrough)
                             .tooltip("Strikethrough (Cmd/Ctrl+Shift+X)")
@@ -1647,8 +1566,9 @@ impl Render for RichTextExample {
                                 let handle = this.editor.read(cx).focus_handle();
                                 window.focus(&handle);
                             })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "code",
                         PlateToolbarIconButton::new("code", PlateIconName::CodeXml)
                             .selected(code)
                             .tooltip("Code (Cmd/Ctrl+E)")
@@ -1657,8 +1577,9 @@ impl Render for RichTextExample {
                                 let handle = this.editor.read(cx).focus_handle();
                                 window.focus(&handle);
                             })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "text-color",
                         PlateToolbarColorPicker::new("text-color", PlateIconName::Baseline)
                             .tooltip("Text color")
                             .value(text_color)
@@ -1676,8 +1597,9 @@ impl Render for RichTextExample {
                                     window.focus(&handle);
                                 }
                             }),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "highlight-color",
                         PlateToolbarColorPicker::new("highlight-color", PlateIconName::PaintBucket)
                             .tooltip("Highlight color")
                             .value(highlight_color)
@@ -1698,8 +1620,8 @@ impl Render for RichTextExample {
                                     window.focus(&handle);
                                 }
                             }),
-                    )
-                    .child({
+                    ),
+                    PlateToolbarItem::new("align", {
                         let active_align = align;
                         let trigger_icon = align_icon;
                         let editor = self.editor.clone();
@@ -1795,7 +1717,21 @@ impl Render for RichTextExample {
                                     ))
                             })
                     })
-                    .child(
+                    .width(px(52.)),
+                    PlateToolbarItem::new(
+                        "direction",
+                        PlateToolbarIconButton::new("direction", direction_icon)
+                            .selected(direction == TextDirection::Rtl)
+                            .tooltip(direction_tooltip)
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                this.editor
+                                    .update(cx, |ed, cx| ed.command_toggle_direction(cx));
+                                let handle = this.editor.read(cx).focus_handle();
+                                window.focus(&handle);
+                            })),
+                    ),
+                    PlateToolbarItem::new(
+                        "list",
                         PlateToolbarIconButton::new("list", PlateIconName::List)
                             .selected(bulleted)
                             .tooltip("Bulleted list (Cmd/Ctrl+Shift+8)")
@@ -1805,8 +1741,9 @@ impl Render for RichTextExample {
                                 let handle = this.editor.read(cx).focus_handle();
                                 window.focus(&handle);
                             })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "list-ordered",
                         PlateToolbarIconButton::new("list-ordered", PlateIconName::ListOrdered)
                             .selected(ordered)
                             .tooltip("Ordered list (Cmd/Ctrl+Shift+7)")
@@ -1816,8 +1753,9 @@ impl Render for RichTextExample {
                                 let handle = this.editor.read(cx).focus_handle();
                                 window.focus(&handle);
                             })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "indent-decrease",
                         PlateToolbarIconButton::new(
                             "indent-decrease",
                             PlateIconName::IndentDecrease,
@@ -1830,8 +1768,9 @@ impl Render for RichTextExample {
                             let handle = this.editor.read(cx).focus_handle();
                             window.focus(&handle);
                         })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "indent-increase",
                         PlateToolbarIconButton::new(
                             "indent-increase",
                             PlateIconName::IndentIncrease,
@@ -1844,8 +1783,9 @@ impl Render for RichTextExample {
                             let handle = this.editor.read(cx).focus_handle();
                             window.focus(&handle);
                         })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "todo",
                         PlateToolbarIconButton::new("todo", PlateIconName::ListTodo)
                             .selected(todo)
                             .tooltip("Todo")
@@ -1854,8 +1794,9 @@ impl Render for RichTextExample {
                                 let handle = this.editor.read(cx).focus_handle();
                                 window.focus(&handle);
                             })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "blockquote",
                         PlateToolbarIconButton::new("blockquote", PlateIconName::WrapText)
                             .selected(quote)
                             .tooltip("Blockquote")
@@ -1865,8 +1806,9 @@ impl Render for RichTextExample {
                                 let handle = this.editor.read(cx).focus_handle();
                                 window.focus(&handle);
                             })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "toggle",
                         PlateToolbarIconButton::new("toggle", PlateIconName::ListCollapse)
                             .selected(toggle)
                             .tooltip("Toggle (wrap/unwrap)")
@@ -1876,8 +1818,9 @@ impl Render for RichTextExample {
                                 let handle = this.editor.read(cx).focus_handle();
                                 window.focus(&handle);
                             })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "toggle-collapse",
                         PlateToolbarIconButton::new("toggle-collapse", PlateIconName::ChevronDown)
                             .disabled(!toggle)
                             .selected(toggle_collapsed)
@@ -1892,8 +1835,9 @@ impl Render for RichTextExample {
                                 let handle = this.editor.read(cx).focus_handle();
                                 window.focus(&handle);
                             })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "link",
                         PlateToolbarIconButton::new("link", PlateIconName::Link)
                             .disabled(has_selected_block)
                             .selected(link)
@@ -1907,8 +1851,9 @@ impl Render for RichTextExample {
                                     this.editor.read(cx).active_link_url().unwrap_or_default();
                                 this.open_link_dialog(initial, window, cx);
                             })),
-                    )
-                    .child(
+                    ),
+                    PlateToolbarItem::new(
+                        "unlink",
                         PlateToolbarIconButton::new("unlink", PlateIconName::Unlink)
                             .disabled(!link || has_selected_block)
                             .tooltip("Unlink")
@@ -1917,27 +1862,179 @@ impl Render for RichTextExample {
                                 let handle = this.editor.read(cx).focus_handle();
                                 window.focus(&handle);
                             })),
-                    )
-                    .child(PlateToolbarSeparator)
-                    .child(
-                        PlateToolbarIconButton::new("command-palette", PlateIconName::Ellipsis)
-                            .tooltip("Command Palette")
-                            .on_click(cx.listener(|this, _, window, cx| {
-                                this.open_command_palette_dialog(window, cx);
-                            })),
-                    )
-                    .child(
-                        Button::new("open")
-                            .icon(IconName::FolderOpen)
-                            .small()
-                            .ghost()
-                            .on_click(
-                                cx.listener(|this, _, window, cx| this.open_from_file(window, cx)),
-                            ),
-                    )
-                    .child(Button::new("save").small().ghost().on_click(
-                        cx.listener(|this, _, window, cx| this.save_to_file(window, cx)),
-                    )),
+                    ),
+                ],
+            )
+            .group(
+                "more",
+                [PlateToolbarItem::new(
+                    "command-palette",
+                    PlateToolbarIconButton::new("command-palette", PlateIconName::Ellipsis)
+                        .tooltip("Command Palette")
+                        .on_click(cx.listener(|this, _, window, cx| {
+                            this.open_command_palette_dialog(window, cx);
+                        })),
+                )],
+            )
+            .suffix(
+                Button::new("open")
+                    .icon(IconName::FolderOpen)
+                    .small()
+                    .ghost()
+                    .on_click(cx.listener(|this, _, window, cx| this.open_from_file(window, cx))),
+            )
+            .suffix(
+                Button::new("save")
+                    .small()
+                    .ghost()
+                    .on_click(cx.listener(|this, _, window, cx| this.save_to_file(window, cx))),
+            );
+
+        div()
+            .size_full()
+            .relative()
+            .flex()
+            .flex_col()
+            .bg(theme.muted)
+            .on_action(cx.listener(|this, _: &Open, window, cx| {
+                this.open_from_file(window, cx);
+            }))
+            .on_action(cx.listener(|this, _: &Save, window, cx| {
+                this.save_to_file(window, cx);
+            }))
+            .on_action(cx.listener(|this, _: &SaveAs, window, cx| {
+                this.save_as(window, cx);
+            }))
+            .on_action(cx.listener(|this, _: &EmbedLocalImages, window, cx| {
+                let report = this
+                    .editor
+                    .update(cx, |editor, cx| editor.command_embed_local_images(cx));
+                let message = if report.embedded > 0 {
+                    let mut msg = format!("Embedded {} local image(s).", report.embedded);
+                    if report.failed > 0 {
+                        msg.push_str(&format!(" Failed: {}.", report.failed));
+                    }
+                    if report.skipped > 0 {
+                        msg.push_str(&format!(" Skipped: {}.", report.skipped));
+                    }
+                    msg
+                } else if report.failed > 0 {
+                    format!("No images embedded. Failed: {}.", report.failed)
+                } else {
+                    "No local images found to embed.".to_string()
+                };
+                window.push_notification(Notification::new().message(message).autohide(true), cx);
+
+                let handle = this.editor.read(cx).focus_handle();
+                window.focus(&handle);
+            }))
+            .on_action(cx.listener(|this, _: &ExportPortableJson, window, cx| {
+                this.export_portable_json(window, cx);
+            }))
+            .on_action(cx.listener(|this, _: &ExportPlateBundle, window, cx| {
+                this.export_plate_bundle(window, cx);
+            }))
+            .on_action(cx.listener(|this, _: &CollectAssets, window, cx| {
+                if this.file_path.is_none() {
+                    window.push_notification(
+                        Notification::new()
+                            .message(
+                                "Save the document first (Save As...) to set a base directory.",
+                            )
+                            .autohide(true),
+                        cx,
+                    );
+                    return;
+                }
+
+                let report = this.editor.update(cx, |editor, cx| {
+                    editor.command_collect_assets_into_assets_dir(cx)
+                });
+                let message = if report.rewritten > 0 || report.assets_written > 0 {
+                    let mut msg = format!(
+                        "Collected assets into ./assets (rewritten {}, wrote {}).",
+                        report.rewritten, report.assets_written
+                    );
+                    if report.rewritten_data_url > 0 {
+                        msg.push_str(&format!(" Data: {}.", report.rewritten_data_url));
+                    }
+                    if report.failed > 0 {
+                        msg.push_str(&format!(" Failed: {}.", report.failed));
+                    }
+                    if report.skipped > 0 {
+                        msg.push_str(&format!(" Skipped: {}.", report.skipped));
+                    }
+                    msg
+                } else if report.failed > 0 {
+                    format!("No assets collected. Failed: {}.", report.failed)
+                } else {
+                    "No images found to collect.".to_string()
+                };
+                window.push_notification(Notification::new().message(message).autohide(true), cx);
+
+                let handle = this.editor.read(cx).focus_handle();
+                window.focus(&handle);
+            }))
+            .on_action(cx.listener(|this, _: &ShowPortabilityReport, window, cx| {
+                this.open_portability_report_dialog(window, cx);
+            }))
+            .on_action(cx.listener(|this, _: &CommandPalette, window, cx| {
+                this.open_command_palette_dialog(window, cx);
+            }))
+            .on_action(cx.listener(|this, _: &InsertImage, window, cx| {
+                this.open_image_dialog(String::new(), window, cx);
+            }))
+            .on_action(cx.listener(|this, _: &SetLink, window, cx| {
+                if this.editor.read(cx).has_selected_block() {
+                    window.push_notification(
+                        Notification::new()
+                            .message("Set Link is unavailable when a block is selected.")
+                            .autohide(true),
+                        cx,
+                    );
+                    return;
+                }
+                let initial = this.editor.read(cx).active_link_url().unwrap_or_default();
+                this.open_link_dialog(initial, window, cx);
+            }))
+            .on_action(cx.listener(|this, _: &Find, window, cx| {
+                this.open_find_dialog(window, cx);
+            }))
+            .on_action(cx.listener(|this, _: &FindNext, window, cx| {
+                this.editor.update(cx, |editor, cx| {
+                    editor.find_next(cx);
+                });
+                let handle = this.editor.read(cx).focus_handle();
+                window.focus(&handle);
+            }))
+            .on_action(cx.listener(|this, _: &FindPrev, window, cx| {
+                this.editor.update(cx, |editor, cx| {
+                    editor.find_prev(cx);
+                });
+                let handle = this.editor.read(cx).focus_handle();
+                window.focus(&handle);
+            }))
+            .on_action(cx.listener(|_, _: &About, window, cx| {
+                window.push_notification(
+                    Notification::new()
+                        .message("Manos Rich Text (Plate Core)")
+                        .autohide(true),
+                    cx,
+                );
+            }))
+            .child(
+                TitleBar::new().child(div().flex().items_center().child(self.app_menu_bar.clone())),
+            )
+            .child(
+                div()
+                    .id("richtext-toolbar")
+                    .w_full()
+                    .p(px(8.))
+                    .bg(theme.background)
+                    .border_b_1()
+                    .border_color(theme.border)
+                    .on_mouse_down(MouseButton::Left, |_, _, cx| cx.stop_propagation())
+                    .child(toolbar),
             )
             .child(div().flex_1().min_h(px(0.)).child(self.editor.clone()))
     }
@@ -5,6 +5,8 @@ use gpui_component::{ActiveTheme as _, Icon, IconName, Sizable as _, h_flex, v_f
 use gpui_manos_webview::webview::WebView;
 use gpui_manos_webview::wry::WebViewId;
 
+use crate::story::{Story, StoryContext};
+
 const DEFAULT_URL: &str = "https://www.gpui.rs/";
 
 pub struct SimpleBrowserStory {
@@ -74,6 +76,24 @@ impl SimpleBrowserStory {
     }
 }
 
+impl Story for SimpleBrowserStory {
+    const TITLE: &'static str = "Simple Browser";
+    const DESCRIPTION: &'static str = "Loads any URL typed into a gpui input in a native webview.";
+    const KEYWORDS: &'static str = "webview browser url";
+    const ICON: IconName = IconName::Globe;
+    const ORDER: i32 = 50;
+
+    fn new_view(_story_cx: &StoryContext, window: &mut Window, cx: &mut App) -> Entity<Self> {
+        Self::view(window, cx)
+    }
+
+    fn set_active(&mut self, active: bool, cx: &mut Context<Self>) {
+        self.set_visible(active, cx);
+    }
+}
+
+crate::register_story!(SimpleBrowserStory);
+
 impl Render for SimpleBrowserStory {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let theme = cx.theme();
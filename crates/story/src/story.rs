@@ -0,0 +1,127 @@
+//! Story registration.
+//!
+//! Any crate linked into the story binary can add a page to the gallery: implement [`Story`]
+//! for the view and invoke [`register_story!`](crate::register_story). Registrations are
+//! collected when the binary starts, so the gallery never has to import the story itself.
+
+use gpui::{AnyView, App, Context, Entity, Render, Window};
+use gpui_component::IconName;
+use gpui_component::menu::AppMenuBar;
+
+#[doc(hidden)]
+pub use inventory;
+
+/// Shared state handed to every story constructor.
+pub struct StoryContext {
+    pub app_menu_bar: Entity<AppMenuBar>,
+}
+
+pub trait Story: Render + Sized {
+    /// Shown in the page header and in search results.
+    const TITLE: &'static str;
+    /// Sidebar label; defaults to the title.
+    const LABEL: &'static str = Self::TITLE;
+    const DESCRIPTION: &'static str = "";
+    /// Extra words the gallery search matches besides the title and description.
+    const KEYWORDS: &'static str = "";
+    const ICON: IconName;
+    /// Stories sharing a group are nested under one collapsible sidebar item.
+    const GROUP: Option<&'static str> = None;
+    /// Sidebar position; ties are broken by title.
+    const ORDER: i32 = 0;
+
+    fn new_view(story_cx: &StoryContext, window: &mut Window, cx: &mut App) -> Entity<Self>;
+
+    /// Called when the story is shown or hidden. Native views such as webviews must hide
+    /// themselves here, otherwise they stay on screen at their last bounds.
+    fn set_active(&mut self, _active: bool, _cx: &mut Context<Self>) {}
+}
+
+/// A story as seen by the gallery; created by [`register_story!`](crate::register_story).
+pub struct StoryRegistration {
+    pub title: &'static str,
+    pub label: &'static str,
+    pub description: &'static str,
+    pub keywords: &'static str,
+    pub icon: IconName,
+    pub group: Option<&'static str>,
+    pub order: i32,
+    build: fn(&StoryContext, &mut Window, &mut App) -> AnyView,
+    set_active: fn(&AnyView, bool, &mut App),
+}
+
+inventory::collect!(StoryRegistration);
+
+impl StoryRegistration {
+    #[doc(hidden)]
+    pub const fn of<S: Story>() -> Self {
+        Self {
+            title: S::TITLE,
+            label: S::LABEL,
+            description: S::DESCRIPTION,
+            keywords: S::KEYWORDS,
+            icon: S::ICON,
+            group: S::GROUP,
+            order: S::ORDER,
+            build: build_view::<S>,
+            set_active: set_view_active::<S>,
+        }
+    }
+
+    /// Every registered story, in sidebar order.
+    pub fn all() -> Vec<&'static StoryRegistration> {
+        let mut stories: Vec<_> = inventory::iter::<StoryRegistration>.into_iter().collect();
+        stories.sort_by_key(|story| (story.order, story.title));
+        stories
+    }
+
+    pub fn build(&self, story_cx: &StoryContext, window: &mut Window, cx: &mut App) -> AnyView {
+        (self.build)(story_cx, window, cx)
+    }
+
+    pub fn set_active(&self, view: &AnyView, active: bool, cx: &mut App) {
+        (self.set_active)(view, active, cx)
+    }
+
+    /// Case-insensitive match of every whitespace-separated term against the story's text.
+    pub fn matches(&self, query: &str) -> bool {
+        let haystack = format!(
+            "{} {} {} {} {}",
+            self.title,
+            self.label,
+            self.group.unwrap_or_default(),
+            self.description,
+            self.keywords
+        )
+        .to_lowercase();
+        query
+            .to_lowercase()
+            .split_whitespace()
+            .all(|term| haystack.contains(term))
+    }
+}
+
+fn build_view<S: Story>(story_cx: &StoryContext, window: &mut Window, cx: &mut App) -> AnyView {
+    S::new_view(story_cx, window, cx).into()
+}
+
+fn set_view_active<S: Story>(view: &AnyView, active: bool, cx: &mut App) {
+    if let Ok(view) = view.clone().downcast::<S>() {
+        view.update(cx, |story, cx| story.set_active(active, cx));
+    }
+}
+
+/// Registers a [`Story`] implementation with the gallery.
+///
+/// ```ignore
+/// impl Story for MyExample { /* ... */ }
+/// register_story!(MyExample);
+/// ```
+#[macro_export]
+macro_rules! register_story {
+    ($story:ty) => {
+        $crate::story::inventory::submit! {
+            $crate::story::StoryRegistration::of::<$story>()
+        }
+    };
+}
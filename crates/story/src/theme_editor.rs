@@ -0,0 +1,428 @@
+use gpui::prelude::FluentBuilder as _;
+use gpui::*;
+use gpui_component::button::{Button, ButtonVariants as _};
+use gpui_component::input::{Input, InputEvent, InputState};
+use gpui_component::notification::Notification;
+use gpui_component::{
+    ActiveTheme as _, Colorize as _, IconName, Selectable as _, Sizable as _, Theme, WindowExt as _,
+};
+use gpui_manos_components::plate_toolbar::{
+    PlateIconName, PlateToolbar, PlateToolbarIconButton, PlateToolbarItem,
+};
+use gpui_manos_components::segmented::{AnimatedSegmented, AnimatedSegmentedItem};
+
+use crate::story::{Story, StoryContext};
+use crate::themes;
+
+/// A theme color the editor exposes, with its key in the theme file format.
+struct ColorField {
+    label: &'static str,
+    key: &'static str,
+    color: fn(&mut Theme) -> &mut Hsla,
+}
+
+const COLOR_FIELDS: &[ColorField] = &[
+    ColorField {
+        label: "Background",
+        key: "background",
+        color: |theme| &mut theme.background,
+    },
+    ColorField {
+        label: "Foreground",
+        key: "foreground",
+        color: |theme| &mut theme.foreground,
+    },
+    ColorField {
+        label: "Primary",
+        key: "primary.background",
+        color: |theme| &mut theme.primary,
+    },
+    ColorField {
+        label: "Primary text",
+        key: "primary.foreground",
+        color: |theme| &mut theme.primary_foreground,
+    },
+    ColorField {
+        label: "Accent",
+        key: "accent.background",
+        color: |theme| &mut theme.accent,
+    },
+    ColorField {
+        label: "Accent text",
+        key: "accent.foreground",
+        color: |theme| &mut theme.accent_foreground,
+    },
+    ColorField {
+        label: "Muted",
+        key: "muted.background",
+        color: |theme| &mut theme.muted,
+    },
+    ColorField {
+        label: "Muted text",
+        key: "muted.foreground",
+        color: |theme| &mut theme.muted_foreground,
+    },
+    ColorField {
+        label: "Popover",
+        key: "popover.background",
+        color: |theme| &mut theme.popover,
+    },
+    ColorField {
+        label: "Popover text",
+        key: "popover.foreground",
+        color: |theme| &mut theme.popover_foreground,
+    },
+    ColorField {
+        label: "Border",
+        key: "border",
+        color: |theme| &mut theme.border,
+    },
+    ColorField {
+        label: "Focus ring",
+        key: "ring",
+        color: |theme| &mut theme.ring,
+    },
+];
+
+pub struct ThemeEditorStory {
+    name_input: Entity<InputState>,
+    /// One hex input per entry of `COLOR_FIELDS`.
+    color_inputs: Vec<Entity<InputState>>,
+    radius_input: Entity<InputState>,
+    font_size_input: Entity<InputState>,
+    font_family_input: Entity<InputState>,
+    preview_tab: usize,
+}
+
+impl ThemeEditorStory {
+    pub fn view(window: &mut Window, cx: &mut App) -> Entity<Self> {
+        cx.new(|cx| Self::new(window, cx))
+    }
+
+    fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let mut theme = cx.theme().clone();
+
+        let name_input = cx.new(|cx| InputState::new(window, cx).default_value("My Theme"));
+
+        let color_inputs = COLOR_FIELDS
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let hex = (field.color)(&mut theme).to_hex();
+                let input = cx.new(|cx| InputState::new(window, cx).default_value(hex));
+                cx.subscribe(&input, move |this, input, event: &InputEvent, cx| {
+                    if matches!(event, InputEvent::Change { .. }) {
+                        let value = input.read(cx).value().to_string();
+                        this.apply_color(index, &value, cx);
+                    }
+                })
+                .detach();
+                input
+            })
+            .collect();
+
+        let radius_input = cx.new(|cx| {
+            InputState::new(window, cx).default_value(f32::from(theme.radius).to_string())
+        });
+        let font_size_input = cx.new(|cx| {
+            InputState::new(window, cx).default_value(f32::from(theme.font_size).to_string())
+        });
+        let font_family_input =
+            cx.new(|cx| InputState::new(window, cx).default_value(theme.font_family.clone()));
+
+        for input in [&radius_input, &font_size_input, &font_family_input] {
+            cx.subscribe(input, |this, _, event: &InputEvent, cx| {
+                if matches!(event, InputEvent::Change { .. }) {
+                    this.apply_metrics(cx);
+                }
+            })
+            .detach();
+        }
+
+        Self {
+            name_input,
+            color_inputs,
+            radius_input,
+            font_size_input,
+            font_family_input,
+            preview_tab: 0,
+        }
+    }
+
+    /// Applies a color as soon as the input holds a valid hex value.
+    fn apply_color(&mut self, index: usize, value: &str, cx: &mut Context<Self>) {
+        let Ok(color) = Hsla::parse_hex(value.trim()) else {
+            return;
+        };
+        *(COLOR_FIELDS[index].color)(Theme::global_mut(cx)) = color;
+        cx.refresh_windows();
+    }
+
+    fn apply_metrics(&mut self, cx: &mut Context<Self>) {
+        let radius = self.radius_input.read(cx).value().trim().parse::<f32>();
+        let font_size = self.font_size_input.read(cx).value().trim().parse::<f32>();
+        let font_family = self.font_family_input.read(cx).value().trim().to_string();
+
+        let theme = Theme::global_mut(cx);
+        if let Ok(radius) = radius
+            && (0. ..=32.).contains(&radius)
+        {
+            theme.radius = px(radius);
+        }
+        if let Ok(font_size) = font_size
+            && (8. ..=32.).contains(&font_size)
+        {
+            theme.font_size = px(font_size);
+        }
+        if !font_family.is_empty() {
+            theme.font_family = font_family.into();
+        }
+        cx.refresh_windows();
+    }
+
+    /// The current theme in the theme file format read by `themes::init`.
+    fn theme_json(&self, name: &str, cx: &App) -> serde_json::Value {
+        let mut theme = cx.theme().clone();
+        let colors: serde_json::Map<String, serde_json::Value> = COLOR_FIELDS
+            .iter()
+            .map(|field| {
+                let hex = (field.color)(&mut theme).to_hex();
+                (field.key.to_string(), serde_json::Value::String(hex))
+            })
+            .collect();
+
+        serde_json::json!({
+            "name": name,
+            "themes": [{
+                "name": name,
+                "mode": if theme.mode.is_dark() { "dark" } else { "light" },
+                "font.family": theme.font_family.to_string(),
+                "font.size": f32::from(theme.font_size),
+                "radius": f32::from(theme.radius),
+                "colors": colors,
+            }],
+        })
+    }
+
+    fn export(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let name = self.name_input.read(cx).value().trim().to_string();
+        if name.is_empty() {
+            window.push_notification(Notification::new().message("Enter a theme name"), cx);
+            return;
+        }
+
+        let file_name: String = name
+            .chars()
+            .map(|ch| {
+                if ch.is_alphanumeric() {
+                    ch.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        let path = themes::theme_dir().join(format!("{file_name}.json"));
+        let json = serde_json::to_string_pretty(&self.theme_json(&name, cx))
+            .expect("theme JSON serializes");
+
+        let message = match std::fs::write(&path, json) {
+            Ok(()) => format!(
+                "Exported to {}; it appears in the Theme menu once reloaded.",
+                path.display()
+            ),
+            Err(err) => format!("Failed to write {}: {err}", path.display()),
+        };
+        window.push_notification(Notification::new().message(message), cx);
+    }
+
+    fn render_field(
+        label: &'static str,
+        input: &Entity<InputState>,
+        swatch: Option<Hsla>,
+        cx: &App,
+    ) -> impl IntoElement {
+        let theme = cx.theme();
+        div()
+            .flex()
+            .items_center()
+            .gap(px(8.))
+            .child(
+                div()
+                    .w(px(96.))
+                    .text_sm()
+                    .text_color(theme.muted_foreground)
+                    .child(label),
+            )
+            .when_some(swatch, |this, swatch| {
+                this.child(
+                    div()
+                        .size(px(20.))
+                        .rounded(px(4.))
+                        .border_1()
+                        .border_color(theme.border)
+                        .bg(swatch),
+                )
+            })
+            .child(div().flex_1().child(Input::new(input).small()))
+    }
+
+    fn render_preview(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(16.))
+            .p(px(16.))
+            .rounded(theme.radius)
+            .border_1()
+            .border_color(theme.border)
+            .bg(theme.background)
+            .text_color(theme.foreground)
+            .child(
+                AnimatedSegmented::new("theme-preview-tabs")
+                    .selected_index(self.preview_tab)
+                    .item(AnimatedSegmentedItem::new("Overview"))
+                    .item(AnimatedSegmentedItem::new("Inbox").badge(3))
+                    .item(AnimatedSegmentedItem::new("Settings"))
+                    .on_change(cx.listener(|this, index: &usize, _, cx| {
+                        this.preview_tab = *index;
+                        cx.notify();
+                    })),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_wrap()
+                    .gap(px(8.))
+                    .child(Button::new("preview-primary").primary().label("Primary"))
+                    .child(Button::new("preview-default").label("Default"))
+                    .child(Button::new("preview-ghost").ghost().label("Ghost"))
+                    .child(Button::new("preview-danger").danger().label("Danger")),
+            )
+            .child(
+                PlateToolbar::new("theme-preview-toolbar")
+                    .group(
+                        "marks",
+                        [
+                            PlateToolbarItem::new(
+                                "bold",
+                                PlateToolbarIconButton::new("preview-bold", PlateIconName::Bold)
+                                    .selected(true),
+                            ),
+                            PlateToolbarItem::new(
+                                "italic",
+                                PlateToolbarIconButton::new(
+                                    "preview-italic",
+                                    PlateIconName::Italic,
+                                ),
+                            ),
+                        ],
+                    )
+                    .group(
+                        "lists",
+                        [PlateToolbarItem::new(
+                            "list",
+                            PlateToolbarIconButton::new("preview-list", PlateIconName::List),
+                        )],
+                    ),
+            )
+            .child(
+                div()
+                    .p(px(12.))
+                    .rounded(theme.radius)
+                    .border_1()
+                    .border_color(theme.border)
+                    .bg(theme.popover)
+                    .text_color(theme.popover_foreground)
+                    .child("Popover surface")
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(theme.muted_foreground)
+                            .child("Muted secondary text on a popover."),
+                    ),
+            )
+            .child(
+                div()
+                    .px(px(12.))
+                    .py(px(8.))
+                    .rounded(theme.radius)
+                    .bg(theme.accent)
+                    .text_color(theme.accent_foreground)
+                    .child("Accent highlight, as used for selected rows"),
+            )
+    }
+}
+
+impl Story for ThemeEditorStory {
+    const TITLE: &'static str = "Theme Editor";
+    const DESCRIPTION: &'static str =
+        "Edit theme colors, radius and fonts with a live preview, then export a theme file.";
+    const KEYWORDS: &'static str = "theme colors palette radius font export designer";
+    const ICON: IconName = IconName::PaintBucket;
+    const ORDER: i32 = 35;
+
+    fn new_view(_story_cx: &StoryContext, window: &mut Window, cx: &mut App) -> Entity<Self> {
+        Self::view(window, cx)
+    }
+}
+
+crate::register_story!(ThemeEditorStory);
+
+impl Render for ThemeEditorStory {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let mut current = cx.theme().clone();
+        let theme = cx.theme();
+
+        let color_rows: Vec<AnyElement> = COLOR_FIELDS
+            .iter()
+            .zip(&self.color_inputs)
+            .map(|(field, input)| {
+                let swatch = *(field.color)(&mut current);
+                Self::render_field(field.label, input, Some(swatch), cx).into_any_element()
+            })
+            .collect();
+
+        let editor = div()
+            .id("theme-editor-fields")
+            .w(px(360.))
+            .flex_none()
+            .flex()
+            .flex_col()
+            .gap(px(8.))
+            .overflow_y_scroll()
+            .child(Self::render_field("Name", &self.name_input, None, cx))
+            .child(div().mt(px(8.)).text_sm().child("Colors"))
+            .children(color_rows)
+            .child(div().mt(px(8.)).text_sm().child("Metrics"))
+            .child(Self::render_field("Radius", &self.radius_input, None, cx))
+            .child(Self::render_field(
+                "Font size",
+                &self.font_size_input,
+                None,
+                cx,
+            ))
+            .child(Self::render_field(
+                "Font family",
+                &self.font_family_input,
+                None,
+                cx,
+            ))
+            .child(
+                Button::new("theme-export")
+                    .primary()
+                    .label("Export theme")
+                    .on_click(cx.listener(|this, _, window, cx| this.export(window, cx))),
+            );
+
+        div()
+            .size_full()
+            .flex()
+            .gap(px(16.))
+            .p(px(16.))
+            .bg(theme.muted)
+            .child(editor)
+            .child(div().flex_1().min_w(px(0.)).child(self.render_preview(cx)))
+    }
+}
@@ -2,6 +2,8 @@ use std::path::{Path, PathBuf};
 
 use gpui::{Action, App, SharedString};
 use gpui_component::{Theme, ThemeMode, ThemeRegistry};
+use manos_config::{ConfigStore, Namespace};
+use serde::{Deserialize, Serialize};
 
 #[derive(Action, Clone, PartialEq)]
 #[action(namespace = themes, no_json)]
@@ -24,31 +26,88 @@ fn dir_has_theme_files(dir: &Path) -> bool {
     })
 }
 
-pub fn init(cx: &mut App) {
+/// The directory watched for theme files; created if no candidate has any yet.
+pub fn theme_dir() -> PathBuf {
     let local_dir = PathBuf::from("./themes");
     let parent_dir = PathBuf::from("../themes");
 
-    let theme_dir = if dir_has_theme_files(&local_dir) {
+    if dir_has_theme_files(&local_dir) {
         local_dir
     } else if dir_has_theme_files(&parent_dir) {
         parent_dir
     } else {
         let _ = std::fs::create_dir_all(&local_dir);
         local_dir
+    }
+}
+
+/// The theme picked in the menu, restored on the next launch.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct ThemeSettings {
+    /// Name of a theme from the theme directory; `None` for the default theme of the mode.
+    theme: Option<String>,
+    dark: Option<bool>,
+}
+
+fn apply_saved_theme(settings: &Namespace<ThemeSettings>, cx: &mut App) {
+    let Some(theme_name) = settings.get().theme else {
+        return;
     };
+    if let Some(theme_config) = ThemeRegistry::global(cx)
+        .themes()
+        .get(&SharedString::from(theme_name))
+        .cloned()
+    {
+        Theme::global_mut(cx).apply_config(&theme_config);
+        cx.refresh_windows();
+    }
+}
 
-    let _ = ThemeRegistry::watch_dir(theme_dir, cx, |_| {});
+fn save_theme(settings: &Namespace<ThemeSettings>, update: impl FnOnce(&mut ThemeSettings)) {
+    if let Err(err) = settings.update(update) {
+        manos_log::tracing::warn!("failed to save the theme: {err}");
+    }
+}
+
+pub fn init(config: &ConfigStore, cx: &mut App) {
+    let settings = config.namespace::<ThemeSettings>("story");
+    if let Some(dark) = settings.get().dark {
+        let mode = if dark {
+            ThemeMode::Dark
+        } else {
+            ThemeMode::Light
+        };
+        Theme::change(mode, None, cx);
+    }
 
-    cx.on_action(|switch: &SwitchTheme, cx| {
-        let theme_name = switch.0.clone();
-        if let Some(theme_config) = ThemeRegistry::global(cx).themes().get(&theme_name).cloned() {
-            Theme::global_mut(cx).apply_config(&theme_config);
+    // Theme files load in the background, so the saved theme is applied once they are read.
+    let _ = ThemeRegistry::watch_dir(theme_dir(), cx, {
+        let settings = settings.clone();
+        move |cx| apply_saved_theme(&settings, cx)
+    });
+
+    cx.on_action({
+        let settings = settings.clone();
+        move |switch: &SwitchTheme, cx| {
+            let theme_name = switch.0.clone();
+            if let Some(theme_config) = ThemeRegistry::global(cx).themes().get(&theme_name).cloned()
+            {
+                Theme::global_mut(cx).apply_config(&theme_config);
+                save_theme(&settings, |saved| {
+                    saved.theme = Some(theme_name.to_string())
+                });
+            }
+            cx.refresh_windows();
         }
-        cx.refresh_windows();
     });
 
-    cx.on_action(|switch: &SwitchThemeMode, cx| {
+    cx.on_action(move |switch: &SwitchThemeMode, cx| {
         Theme::change(switch.0, None, cx);
+        save_theme(&settings, |saved| {
+            saved.theme = None;
+            saved.dark = Some(matches!(switch.0, ThemeMode::Dark));
+        });
         cx.refresh_windows();
     });
 }
@@ -1,9 +1,12 @@
 use gpui::{App, AppContext as _, Context, Entity, Render, Window};
+use gpui_component::IconName;
 use gpui_manos_webview::webview::WebView;
 use gpui_manos_webview::wry::WebViewId;
 use serde::Serialize;
 use std::path::PathBuf;
 
+use crate::story::{Story, StoryContext};
+
 pub struct WebViewStory {
     webview: Entity<WebView>,
     visible: bool,
@@ -32,6 +35,24 @@ impl WebViewStory {
     }
 }
 
+impl Story for WebViewStory {
+    const TITLE: &'static str = "Welcome Tauri";
+    const DESCRIPTION: &'static str = "A bundled web app rendered in a native webview.";
+    const KEYWORDS: &'static str = "webview tauri";
+    const ICON: IconName = IconName::Frame;
+    const ORDER: i32 = 40;
+
+    fn new_view(_story_cx: &StoryContext, window: &mut Window, cx: &mut App) -> Entity<Self> {
+        Self::view(window, cx)
+    }
+
+    fn set_active(&mut self, active: bool, cx: &mut Context<Self>) {
+        self.set_visible(active, cx);
+    }
+}
+
+crate::register_story!(WebViewStory);
+
 impl Render for WebViewStory {
     fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl gpui::IntoElement {
         self.webview.clone()
@@ -1,23 +1,32 @@
 //! gpui-manos-webview macros
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path as FsPath, PathBuf};
 
 use proc_macro::TokenStream;
+use proc_macro2::{TokenStream as TokenStream2, TokenTree};
 use quote::{format_ident, quote};
+use syn::ext::IdentExt;
 use syn::{
-    AttributeArgs, FnArg, Ident, ItemFn, Lit, Meta, NestedMeta, Pat, Path, ReturnType, Token, Type,
-    parse_macro_input,
+    AttributeArgs, FnArg, GenericArgument, GenericParam, Ident, ItemFn, Lifetime, Lit, LitStr,
+    Meta, NestedMeta, Pat, Path, PathArguments, ReturnType, Token, Type, parse_macro_input,
+    parse_quote,
 };
 
+/// The lifetime of the request body that borrowed arguments deserialize from.
+const BODY_LIFETIME: &str = "'__gpui_de";
+
 #[proc_macro_attribute]
 pub fn command(attributes: TokenStream, item: TokenStream) -> TokenStream {
     let args = parse_macro_input!(attributes as AttributeArgs);
-    let function = parse_macro_input!(item as ItemFn);
+    let mut function = parse_macro_input!(item as ItemFn);
     let is_async = function.sig.asyncness.is_some();
 
     let mut root: Path = syn::parse_str("::gpui_manos_webview").expect("valid default root path");
     let mut rename_all = "camelCase".to_string();
     let mut error_format = "string".to_string();
+    let mut runtime: Option<Path> = None;
+    let mut capability: Option<LitStr> = None;
 
     for arg in args {
         let NestedMeta::Meta(meta) = arg else {
@@ -89,10 +98,54 @@ pub fn command(attributes: TokenStream, item: TokenStream) -> TokenStream {
                     }
                 }
             }
+            Meta::NameValue(nv) if nv.path.is_ident("runtime") => {
+                let Lit::Str(value) = &nv.lit else {
+                    return syn::Error::new_spanned(
+                        &nv.lit,
+                        "expected a string literal naming a `block_on` function",
+                    )
+                    .to_compile_error()
+                    .into();
+                };
+                if !is_async {
+                    return syn::Error::new_spanned(
+                        &nv,
+                        "`runtime` only applies to `async fn` commands",
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+
+                runtime = match value.parse::<Path>() {
+                    Ok(path) => Some(path),
+                    Err(err) => return err.to_compile_error().into(),
+                };
+            }
+            Meta::NameValue(nv) if nv.path.is_ident("capability") => {
+                let Lit::Str(value) = &nv.lit else {
+                    return syn::Error::new_spanned(
+                        &nv.lit,
+                        "expected a string literal (e.g. \"fs:write\")",
+                    )
+                    .to_compile_error()
+                    .into();
+                };
+                let name = value.value();
+                if name.is_empty() || name.contains(char::is_whitespace) {
+                    return syn::Error::new_spanned(
+                        value,
+                        "capability names must be non-empty and contain no whitespace",
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+
+                capability = Some(value.clone());
+            }
             other => {
                 return syn::Error::new_spanned(
                     other,
-                    "unsupported attribute argument (supported: root = \"...\", rename_all = \"...\", error = \"string\"|\"json\")",
+                    "unsupported attribute argument (supported: root = \"...\", rename_all = \"...\", error = \"string\"|\"json\", runtime = \"...\", capability = \"...\")",
                 )
                 .to_compile_error()
                 .into();
@@ -100,69 +153,165 @@ pub fn command(attributes: TokenStream, item: TokenStream) -> TokenStream {
         }
     }
 
+    // `#[arg]` only exists for this macro, so it must not reach the compiler.
+    let arg_renames = match take_arg_renames(&mut function) {
+        Ok(renames) => renames,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
     let command_fn = function.sig.ident.clone();
     let wrapper_fn = format_ident!("__cmd__{}", command_fn);
     let args_struct = format_ident!("__gpui_cmd_args__{}", command_fn);
     let vis = &function.vis;
 
-    let mut arg_idents = Vec::new();
-    let mut arg_types = Vec::new();
-    let mut call_arg_idents = Vec::new();
+    let generics = &function.sig.generics;
+    let fn_lifetimes: Vec<String> = generics
+        .lifetimes()
+        .map(|def| def.lifetime.ident.to_string())
+        .collect();
+    let bound_tokens = generics
+        .type_params()
+        .map(|param| {
+            let bounds = &param.bounds;
+            quote!(#bounds)
+        })
+        .chain(generics.where_clause.iter().map(|clause| quote!(#clause)));
+    if !fn_lifetimes.is_empty() && mentions_lifetime(quote!(#(#bound_tokens)*), &fn_lifetimes) {
+        return syn::Error::new_spanned(
+            generics,
+            "lifetime parameters of a command can only appear in argument types",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let mut args = Vec::new();
+    let mut call_args = Vec::new();
     let mut request_ident: Option<Ident> = None;
-    for input in &function.sig.inputs {
+    let mut headers_ident: Option<Ident> = None;
+    let mut label_ident: Option<Ident> = None;
+    let mut cancellation_ident: Option<Ident> = None;
+    for (input, rename) in function.sig.inputs.iter().zip(arg_renames) {
         match input {
             FnArg::Receiver(receiver) => {
                 return syn::Error::new_spanned(receiver, "commands must be free functions")
                     .to_compile_error()
                     .into();
             }
-            FnArg::Typed(pat_type) => match &*pat_type.pat {
-                Pat::Ident(pat_ident) => {
-                    let ident = pat_ident.ident.clone();
-                    let ty = (*pat_type.ty).clone();
-                    call_arg_idents.push(ident.clone());
-                    if is_ipc_request_type(&ty) {
-                        if request_ident.is_some() {
-                            return syn::Error::new_spanned(
-                                &pat_type.ty,
-                                "only one gpui_manos_webview::ipc::Request argument is supported",
-                            )
-                            .to_compile_error()
-                            .into();
+            FnArg::Typed(pat_type) => {
+                let ident = match arg_ident(&pat_type.pat) {
+                    Ok(ident) => ident,
+                    Err(err) => return err.to_compile_error().into(),
+                };
+                if let Some(special) = ipc_special_type(&pat_type.ty) {
+                    if let Some(rename) = &rename {
+                        return syn::Error::new_spanned(
+                            rename,
+                            "`rename` only applies to arguments read from the JSON body",
+                        )
+                        .to_compile_error()
+                        .into();
+                    }
+                    let (slot, name) = match special {
+                        SpecialArg::Request => (&mut request_ident, "Request"),
+                        SpecialArg::Headers => (&mut headers_ident, "Headers"),
+                        SpecialArg::WebviewLabel => (&mut label_ident, "WebviewLabel"),
+                        SpecialArg::CancellationToken => {
+                            (&mut cancellation_ident, "CancellationToken")
                         }
-                        request_ident = Some(ident);
-                    } else {
-                        arg_idents.push(ident);
-                        arg_types.push(ty);
+                    };
+                    if slot.is_some() {
+                        return syn::Error::new_spanned(
+                            &pat_type.ty,
+                            format!(
+                                "only one gpui_manos_webview::ipc::{name} argument is supported"
+                            ),
+                        )
+                        .to_compile_error()
+                        .into();
                     }
+                    call_args.push(quote!(#ident));
+                    *slot = Some(ident);
+                    continue;
                 }
-                other => {
-                    return syn::Error::new_spanned(
-                        other,
-                        "unsupported argument pattern (expected an identifier)",
-                    )
-                    .to_compile_error()
-                    .into();
-                }
-            },
+
+                let arg = match CommandArg::new(ident, &pat_type.ty, rename) {
+                    Ok(arg) => arg,
+                    Err(err) => return err.to_compile_error().into(),
+                };
+                call_args.push(arg.call_expr());
+                args.push(arg);
+            }
+        }
+    }
+
+    let mut keys = HashSet::new();
+    for arg in &args {
+        let key = arg.json_key(&rename_all);
+        if !keys.insert(key.clone()) {
+            let span = match &arg.rename {
+                Some(rename) => rename.span(),
+                None => arg.ident.span(),
+            };
+            return syn::Error::new(
+                span,
+                format!("two arguments are read from the JSON key `{key}`"),
+            )
+            .to_compile_error()
+            .into();
         }
     }
 
     let serde_rename_all = rename_all;
     let serialize_error = error_format == "json";
+    let borrows_body = args.iter().any(|arg| arg.borrowed);
+
+    // The args struct only declares the type and const parameters its fields mention, otherwise
+    // the unused ones would be rejected; serde infers the `Deserialize` bounds on its own.
+    let body_lifetime = Lifetime::new(BODY_LIFETIME, proc_macro2::Span::call_site());
+    let mut struct_params = Vec::new();
+    let mut struct_args = Vec::new();
+    if borrows_body {
+        struct_params.push(quote!(#body_lifetime));
+        struct_args.push(quote!('_));
+    }
+    let field_types = args.iter().map(|arg| &arg.field_ty);
+    let field_types = quote!(#(#field_types)*);
+    for param in &generics.params {
+        match param {
+            GenericParam::Type(param) if mentions_ident(field_types.clone(), &param.ident) => {
+                let ident = &param.ident;
+                struct_params.push(quote!(#ident));
+                struct_args.push(quote!(#ident));
+            }
+            GenericParam::Const(param) if mentions_ident(field_types.clone(), &param.ident) => {
+                let (ident, ty) = (&param.ident, &param.ty);
+                struct_params.push(quote!(const #ident: #ty));
+                struct_args.push(quote!(#ident));
+            }
+            _ => {}
+        }
+    }
+    let (struct_params, struct_args) = if struct_params.is_empty() {
+        (quote!(), quote!())
+    } else {
+        (quote!(<#(#struct_params),*>), quote!(<#(#struct_args),*>))
+    };
 
-    let parse_args = if arg_idents.is_empty() {
+    let parse_args = if args.is_empty() {
         quote!()
     } else {
+        let arg_idents: Vec<_> = args.iter().map(|arg| &arg.ident).collect();
+        let fields = args.iter().map(CommandArg::field);
         quote! {
             #[allow(non_camel_case_types)]
             #[derive(#root::serde::Deserialize)]
             #[serde(rename_all = #serde_rename_all)]
-            struct #args_struct {
-                #( #arg_idents: #arg_types, )*
+            struct #args_struct #struct_params {
+                #( #fields, )*
             }
 
-            let __gpui_args: #args_struct = match #root::serde_json::from_slice(&__gpui_body) {
+            let __gpui_args: #args_struct #struct_args = match #root::serde_json::from_slice(&__gpui_body) {
                 Ok(args) => args,
                 Err(err) => return #root::ipc::bad_request_json(&format!(
                     "invalid args for command `{}`: {err}",
@@ -174,7 +323,7 @@ pub fn command(attributes: TokenStream, item: TokenStream) -> TokenStream {
         }
     };
 
-    let content_type_check = if arg_idents.is_empty() {
+    let content_type_check = if args.is_empty() {
         quote!()
     } else {
         quote! {
@@ -196,43 +345,80 @@ pub fn command(attributes: TokenStream, item: TokenStream) -> TokenStream {
         }
     };
 
+    let define_headers = match &headers_ident {
+        // The request keeps the parts, so the headers are a copy.
+        Some(headers_ident) if request_ident.is_some() => quote! {
+            let #headers_ident = #root::ipc::Headers::new(__gpui_parts.headers.clone());
+        },
+        Some(headers_ident) => quote! {
+            let #headers_ident = #root::ipc::Headers::new(__gpui_parts.headers);
+        },
+        None => quote!(),
+    };
+    let define_label = label_ident.as_ref().map(|label_ident| {
+        quote! {
+            let #label_ident = #root::ipc::WebviewLabel::current();
+        }
+    });
+    let define_cancellation = cancellation_ident.as_ref().map(|cancellation_ident| {
+        quote! {
+            let #cancellation_ident = #root::ipc::CancellationToken::current();
+        }
+    });
+
     let define_request = if let Some(request_ident) = &request_ident {
+        // Borrowed arguments still point into the body, so the request gets its own copy.
+        let body = if borrows_body {
+            quote!(__gpui_body.clone())
+        } else {
+            quote!(__gpui_body)
+        };
         quote! {
-            let #request_ident = #root::ipc::Request::new(__gpui_parts, __gpui_body);
+            let #request_ident = #root::ipc::Request::new(__gpui_parts, #body);
         }
     } else {
         quote!()
     };
 
-    let base_call = if call_arg_idents.is_empty() {
-        quote!(#command_fn())
+    let fn_params: Vec<_> = generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(param) => Some(&param.ident),
+            GenericParam::Const(param) => Some(&param.ident),
+            GenericParam::Lifetime(_) => None,
+        })
+        .collect();
+    let turbofish = if fn_params.is_empty() {
+        quote!()
     } else {
-        quote!(#command_fn(#(#call_arg_idents),*))
+        quote!(::<#(#fn_params),*>)
     };
+    let base_call = quote!(#command_fn #turbofish(#(#call_args),*));
     let call = if is_async {
-        quote!(#root::async_runtime::block_on(#base_call))
+        let block_on = runtime.unwrap_or_else(|| parse_quote!(#root::async_runtime::block_on));
+        quote!(#block_on(#base_call))
     } else {
         base_call
     };
 
     let respond = match &function.sig.output {
         ReturnType::Type(_, ty) if is_result_type(ty) => {
-            if serialize_error {
-                quote! {
-                    match #call {
-                        Ok(output) => #root::ipc::respond(output),
-                        Err(err) => #root::ipc::internal_error_json(&err),
-                    }
-                }
+            // Errors that implement `ipc::InvokeError` reject with their structured payload,
+            // whatever `error` says; see `ipc::command_error`.
+            let fallback = if serialize_error {
+                quote!(ViaSerialize)
             } else {
-                quote! {
-                    match #call {
-                        Ok(output) => #root::ipc::respond(output),
-                        Err(err) => #root::ipc::internal_error_json(&format!(
-                            "command `{}` failed: {}",
-                            stringify!(#command_fn),
-                            err.to_string()
-                        )),
+                quote!(ViaToString)
+            };
+            quote! {
+                match #call {
+                    Ok(output) => #root::ipc::respond(output),
+                    Err(err) => {
+                        #[allow(unused_imports)]
+                        use #root::ipc::command_error::{ViaInvokeError as _, #fallback as _};
+                        (&&#root::ipc::command_error::ErrorRef(&err))
+                            .into_response(stringify!(#command_fn))
                     }
                 }
             }
@@ -243,14 +429,20 @@ pub fn command(attributes: TokenStream, item: TokenStream) -> TokenStream {
         },
     };
 
-    let needs_body = !arg_idents.is_empty() || request_ident.is_some();
-    let needs_content_type = !arg_idents.is_empty();
+    let needs_body = !args.is_empty() || request_ident.is_some();
+    let needs_content_type = !args.is_empty();
 
-    let wrapper_body = if !needs_body {
+    let wrapper_body = if !needs_body && headers_ident.is_none() {
         quote! {
             let _ = request;
             #respond
         }
+    } else if !needs_body {
+        quote! {
+            let (__gpui_parts, _) = request.into_parts();
+            #define_headers
+            #respond
+        }
     } else if needs_content_type {
         quote! {
             let (__gpui_parts, __gpui_body) = request.into_parts();
@@ -263,6 +455,7 @@ pub fn command(attributes: TokenStream, item: TokenStream) -> TokenStream {
             let __gpui_content_type = __gpui_content_type.split(';').next().unwrap_or("").trim();
             #content_type_check
             #parse_args
+            #define_headers
             #define_request
             #respond
         }
@@ -270,23 +463,81 @@ pub fn command(attributes: TokenStream, item: TokenStream) -> TokenStream {
         quote! {
             let (__gpui_parts, __gpui_body) = request.into_parts();
             #parse_args
+            #define_headers
             #define_request
             #respond
         }
     };
 
+    // Checked before the arguments are parsed, so a denied invoke learns nothing about them.
+    let capability_check = capability.map(|capability| {
+        quote! {
+            if let Some(response) =
+                #root::ipc::capability_denied(stringify!(#command_fn), #capability)
+            {
+                return response;
+            }
+        }
+    });
+
+    // Generic commands get a generic wrapper; `generate_handler![echo::<User>]` picks the types.
+    let mut wrapper_generics = generics.clone();
+    wrapper_generics.params = generics
+        .params
+        .iter()
+        .filter(|param| !matches!(param, GenericParam::Lifetime(_)))
+        .cloned()
+        .collect();
+    let (wrapper_params, _, where_clause) = wrapper_generics.split_for_impl();
+
     let wrapper = quote! {
         #[doc(hidden)]
         #[allow(non_snake_case)]
-        #vis fn #wrapper_fn(request: #root::http::Request<Vec<u8>>) -> #root::http::Response<Vec<u8>> {
-            use ::std::string::ToString as _;
+        #vis fn #wrapper_fn #wrapper_params(request: #root::http::Request<Vec<u8>>) -> #root::http::Response<Vec<u8>> #where_clause {
+            #capability_check
+            #define_label
+            #define_cancellation
             #wrapper_body
         }
     };
 
+    // The signature for `typescript::Bindings`; `__typescript!` drops it without the feature.
+    let signature_fn = format_ident!("__cmd_ts__{}", command_fn);
+    let mut signature_generics = wrapper_generics.clone();
+    for param in signature_generics.type_params_mut() {
+        param.bounds.push(parse_quote!(#root::typescript::TS));
+        param.bounds.push(parse_quote!('static));
+    }
+    let (signature_params, _, signature_where) = signature_generics.split_for_impl();
+    let signature_args = args.iter().map(|arg| {
+        let ty = &arg.field_ty;
+        let ty = static_lifetimes(quote!(#ty));
+        let key = arg.json_key(&serde_rename_all);
+        quote!(.arg::<#ty>(#key))
+    });
+    let returns = match &function.sig.output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ty) => {
+            let ty = result_ok_type(ty).unwrap_or(ty);
+            static_lifetimes(quote!(#ty))
+        }
+    };
+    let signature = quote! {
+        #root::__typescript! {
+            #[doc(hidden)]
+            #[allow(non_snake_case)]
+            #vis fn #signature_fn #signature_params() -> #root::typescript::CommandSignature #signature_where {
+                #root::typescript::CommandSignature::new(stringify!(#command_fn))
+                    #(#signature_args)*
+                    .returns::<#returns>()
+            }
+        }
+    };
+
     quote! {
         #function
         #wrapper
+        #signature
     }
     .into()
 }
@@ -299,6 +550,7 @@ pub fn generate_handler(input: TokenStream) -> TokenStream {
 
     let mut command_idents = Vec::new();
     let mut wrapper_paths = Vec::new();
+    let mut signature_paths = Vec::new();
     let mut commands_by_name: HashMap<String, Vec<Path>> = HashMap::new();
 
     for command_path in command_paths {
@@ -310,13 +562,24 @@ pub fn generate_handler(input: TokenStream) -> TokenStream {
 
         let command_ident = last.ident.clone();
         last.ident = format_ident!("__cmd__{}", command_ident);
+        // `echo<User>` is accepted like `echo::<User>`, but the call needs the turbofish.
+        if let PathArguments::AngleBracketed(arguments) = &mut last.arguments {
+            arguments.colon2_token.get_or_insert_with(Default::default);
+        }
 
         commands_by_name
             .entry(command_ident.to_string())
             .or_default()
             .push(command_path);
+        let mut signature_path = wrapper_path.clone();
+        signature_path
+            .segments
+            .last_mut()
+            .expect("parsed command path has no segments")
+            .ident = format_ident!("__cmd_ts__{}", command_ident);
         command_idents.push(command_ident);
         wrapper_paths.push(wrapper_path);
+        signature_paths.push(signature_path);
     }
 
     let duplicates: Vec<_> = commands_by_name
@@ -348,7 +611,10 @@ pub fn generate_handler(input: TokenStream) -> TokenStream {
                 quote! { stringify!(#command_ident) => Some(#wrapper_path(request)), }
             });
 
-    quote! {
+    quote! {{
+        ::gpui_manos_webview::__typescript! {
+            ::gpui_manos_webview::typescript::register(::std::vec![#(#signature_paths()),*]);
+        }
         move |invoke| {
             let ::gpui_manos_webview::Invoke {
                 command,
@@ -361,10 +627,420 @@ pub fn generate_handler(input: TokenStream) -> TokenStream {
                 _ => None,
             }
         }
+    }}
+    .into()
+}
+
+/// How a deserialized field is handed to the command.
+enum Pass {
+    Value,
+    /// `&*field`, for `&str` arguments read into a `Cow<str>`.
+    Deref,
+    /// `&field`, for `&T` and `&[T]` arguments read into a `T` or `Vec<T>`.
+    Ref,
+}
+
+/// A command argument that is deserialized from the JSON body.
+struct CommandArg {
+    ident: Ident,
+    field_ty: Type,
+    /// Whether `field_ty` borrows from the body and needs `#[serde(borrow)]`.
+    borrowed: bool,
+    pass: Pass,
+    /// The JSON key from `#[arg(rename = "...")]`, instead of the one `rename_all` derives.
+    rename: Option<LitStr>,
+}
+
+impl CommandArg {
+    fn new(ident: Ident, ty: &Type, rename: Option<LitStr>) -> syn::Result<Self> {
+        let (field_ty, pass) = match ty {
+            Type::Reference(reference) => {
+                if reference.mutability.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        ty,
+                        "`&mut` arguments are not supported; take the value by value instead",
+                    ));
+                }
+                if reference
+                    .lifetime
+                    .as_ref()
+                    .is_some_and(|lifetime| lifetime.ident == "static")
+                {
+                    return Err(syn::Error::new_spanned(
+                        ty,
+                        "`'static` references cannot borrow from the request; use an owned type such as `String`",
+                    ));
+                }
+                match &*reference.elem {
+                    // A `Cow` rather than `&str`: strings with escapes cannot be borrowed as-is.
+                    elem if is_str_type(elem) => {
+                        (parse_quote!(::std::borrow::Cow<'_, str>), Pass::Deref)
+                    }
+                    Type::Slice(slice) => {
+                        let elem = &slice.elem;
+                        (parse_quote!(::std::vec::Vec<#elem>), Pass::Ref)
+                    }
+                    elem => (elem.clone(), Pass::Ref),
+                }
+            }
+            _ => (ty.clone(), Pass::Value),
+        };
+
+        let mut field_ty = field_ty;
+        let borrowed = borrow_from_body(&mut field_ty)?;
+        Ok(Self {
+            ident,
+            field_ty,
+            borrowed,
+            pass,
+            rename,
+        })
+    }
+
+    fn field(&self) -> TokenStream2 {
+        let (ident, ty) = (&self.ident, &self.field_ty);
+        let borrow = self.borrowed.then(|| quote!(#[serde(borrow)]));
+        let rename = self
+            .rename
+            .as_ref()
+            .map(|rename| quote!(#[serde(rename = #rename)]));
+        quote!(#borrow #rename #ident: #ty)
+    }
+
+    /// The key the argument is read from, as serde names the field.
+    fn json_key(&self, rename_all: &str) -> String {
+        if let Some(rename) = &self.rename {
+            return rename.value();
+        }
+        let name = self.ident.unraw().to_string();
+        if rename_all != "camelCase" {
+            return name;
+        }
+        // serde's `camelCase`: `PascalCase` with the first letter lowercased.
+        let mut pascal = String::with_capacity(name.len());
+        let mut capitalize = true;
+        for c in name.chars() {
+            if c == '_' {
+                capitalize = true;
+            } else if capitalize {
+                pascal.push(c.to_ascii_uppercase());
+                capitalize = false;
+            } else {
+                pascal.push(c);
+            }
+        }
+        let mut chars = pascal.chars();
+        chars
+            .next()
+            .map(|first| first.to_ascii_lowercase().to_string() + chars.as_str())
+            .unwrap_or_default()
+    }
+
+    fn call_expr(&self) -> TokenStream2 {
+        let ident = &self.ident;
+        match self.pass {
+            Pass::Value => quote!(#ident),
+            Pass::Deref => quote!(&*#ident),
+            Pass::Ref => quote!(&#ident),
+        }
+    }
+}
+
+/// Removes the `#[arg(...)]` attributes from the arguments of `function` and returns the
+/// `rename` of each argument, in order.
+fn take_arg_renames(function: &mut ItemFn) -> syn::Result<Vec<Option<LitStr>>> {
+    let mut renames = Vec::new();
+    for input in &mut function.sig.inputs {
+        let FnArg::Typed(pat_type) = input else {
+            renames.push(None);
+            continue;
+        };
+
+        let mut rename = None;
+        let mut kept = Vec::new();
+        for attr in std::mem::take(&mut pat_type.attrs) {
+            if !attr.path.is_ident("arg") {
+                kept.push(attr);
+                continue;
+            }
+            let Meta::List(list) = attr.parse_meta()? else {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "expected `#[arg(rename = \"...\")]`",
+                ));
+            };
+            for nested in list.nested {
+                match nested {
+                    NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("rename") => {
+                        let Lit::Str(value) = nv.lit else {
+                            return Err(syn::Error::new_spanned(
+                                nv.lit,
+                                "expected a string literal",
+                            ));
+                        };
+                        if value.value().is_empty() {
+                            return Err(syn::Error::new_spanned(
+                                value,
+                                "the JSON key must not be empty",
+                            ));
+                        }
+                        if rename.is_some() {
+                            return Err(syn::Error::new_spanned(
+                                value,
+                                "the argument is already renamed",
+                            ));
+                        }
+                        rename = Some(value);
+                    }
+                    other => {
+                        return Err(syn::Error::new_spanned(
+                            other,
+                            "unsupported argument attribute (supported: rename = \"...\")",
+                        ));
+                    }
+                }
+            }
+        }
+        pat_type.attrs = kept;
+        renames.push(rename);
+    }
+    Ok(renames)
+}
+
+/// The binding an argument pattern introduces, which also names its JSON key.
+fn arg_ident(pat: &Pat) -> syn::Result<Ident> {
+    match pat {
+        Pat::Ident(pat_ident) if pat_ident.subpat.is_none() => Ok(pat_ident.ident.clone()),
+        Pat::TupleStruct(tuple_struct) => match tuple_struct.pat.elems.first() {
+            Some(Pat::Ident(inner))
+                if tuple_struct.pat.elems.len() == 1 && inner.subpat.is_none() =>
+            {
+                Ok(inner.ident.clone())
+            }
+            _ => Err(syn::Error::new_spanned(
+                tuple_struct,
+                "newtype patterns must bind exactly one identifier (e.g. `UserId(id): UserId`)",
+            )),
+        },
+        other => Err(syn::Error::new_spanned(
+            other,
+            "unsupported argument pattern (expected an identifier or a newtype pattern such as `UserId(id): UserId`)",
+        )),
+    }
+}
+
+/// Ties every non-`'static` lifetime in `ty` to the request body and returns whether there were
+/// any. Types that can never be deserialized are rejected here.
+fn borrow_from_body(ty: &mut Type) -> syn::Result<bool> {
+    let body_lifetime = || Lifetime::new(BODY_LIFETIME, proc_macro2::Span::call_site());
+    let is_static = |lifetime: &Lifetime| lifetime.ident == "static";
+
+    match ty {
+        Type::Reference(reference) => {
+            if reference.mutability.is_some() {
+                return Err(syn::Error::new_spanned(
+                    &*reference,
+                    "`&mut` cannot be deserialized; use an owned type",
+                ));
+            }
+            if reference.lifetime.as_ref().is_some_and(is_static) {
+                borrow_from_body(&mut reference.elem)
+            } else {
+                reference.lifetime = Some(body_lifetime());
+                borrow_from_body(&mut reference.elem)?;
+                Ok(true)
+            }
+        }
+        Type::Path(type_path) => {
+            let mut borrowed = false;
+            if let Some(qself) = &mut type_path.qself {
+                borrowed |= borrow_from_body(&mut qself.ty)?;
+            }
+            for segment in &mut type_path.path.segments {
+                let PathArguments::AngleBracketed(arguments) = &mut segment.arguments else {
+                    continue;
+                };
+                for argument in &mut arguments.args {
+                    match argument {
+                        GenericArgument::Lifetime(lifetime) if !is_static(lifetime) => {
+                            *lifetime = body_lifetime();
+                            borrowed = true;
+                        }
+                        GenericArgument::Type(ty) => borrowed |= borrow_from_body(ty)?,
+                        _ => {}
+                    }
+                }
+            }
+            Ok(borrowed)
+        }
+        Type::Slice(slice) => borrow_from_body(&mut slice.elem),
+        Type::Array(array) => borrow_from_body(&mut array.elem),
+        Type::Paren(paren) => borrow_from_body(&mut paren.elem),
+        Type::Group(group) => borrow_from_body(&mut group.elem),
+        Type::Tuple(tuple) => tuple.elems.iter_mut().try_fold(false, |borrowed, elem| {
+            Ok(borrow_from_body(elem)? || borrowed)
+        }),
+        Type::ImplTrait(_) => Err(syn::Error::new_spanned(
+            &*ty,
+            "`impl Trait` arguments cannot be deserialized; use a generic parameter with serde bounds instead",
+        )),
+        Type::TraitObject(_) => Err(syn::Error::new_spanned(
+            &*ty,
+            "trait objects cannot be deserialized; use a concrete type",
+        )),
+        Type::Infer(_) => Err(syn::Error::new_spanned(
+            &*ty,
+            "argument types must be spelled out",
+        )),
+        Type::BareFn(_) | Type::Ptr(_) | Type::Never(_) => Err(syn::Error::new_spanned(
+            &*ty,
+            "unsupported argument type (expected a type that implements serde::Deserialize)",
+        )),
+        _ => Ok(false),
+    }
+}
+
+fn is_str_type(ty: &Type) -> bool {
+    matches!(ty, Type::Path(type_path) if type_path.qself.is_none() && type_path.path.is_ident("str"))
+}
+
+/// Whether `tokens` mention one of the lifetimes named in `names` (without the quote).
+fn mentions_lifetime(tokens: TokenStream2, names: &[String]) -> bool {
+    let mut after_quote = false;
+    for token in tokens {
+        match token {
+            TokenTree::Group(group) if mentions_lifetime(group.stream(), names) => return true,
+            TokenTree::Punct(punct) if punct.as_char() == '\'' => {
+                after_quote = true;
+                continue;
+            }
+            TokenTree::Ident(ident) if after_quote && names.iter().any(|name| ident == name) => {
+                return true;
+            }
+            _ => {}
+        }
+        after_quote = false;
+    }
+    false
+}
+
+fn mentions_ident(tokens: TokenStream2, target: &Ident) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Group(group) => mentions_ident(group.stream(), target),
+        TokenTree::Ident(ident) => ident == *target,
+        _ => false,
+    })
+}
+
+/// Embeds every file under a directory into the binary as a
+/// `gpui_manos_webview::embedded::EmbeddedAssets`, for `Builder::serve_embedded`.
+///
+/// The directory is relative to the `Cargo.toml` of the crate that calls the macro. Changes to
+/// embedded files trigger a rebuild; adding or removing files does not, so rebuild the crate
+/// after changing which files the frontend build emits.
+///
+/// # Example
+/// ```ignore
+/// static ASSETS: EmbeddedAssets = gpui_manos_webview::embed_assets!("frontend/dist");
+/// ```
+#[proc_macro]
+pub fn embed_assets(input: TokenStream) -> TokenStream {
+    let dir = parse_macro_input!(input as LitStr);
+    let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let root = FsPath::new(&manifest_dir).join(dir.value());
+    if !root.is_dir() {
+        return syn::Error::new_spanned(&dir, format!("`{}` is not a directory", root.display()))
+            .to_compile_error()
+            .into();
+    }
+
+    let mut files = Vec::new();
+    if let Err(err) = collect_files(&root, &mut Vec::new(), &mut files) {
+        return syn::Error::new_spanned(
+            &dir,
+            format!("failed to read `{}`: {err}", root.display()),
+        )
+        .to_compile_error()
+        .into();
+    }
+    files.sort();
+
+    let entries = files.iter().map(|(relative, absolute)| {
+        let absolute = absolute.to_string_lossy();
+        quote! { (#relative, include_bytes!(#absolute) as &'static [u8]) }
+    });
+    quote! {
+        ::gpui_manos_webview::embedded::EmbeddedAssets::new(&[#(#entries),*])
     }
     .into()
 }
 
+/// Collects the files under `dir` as `/`-separated paths relative to the embedded root.
+fn collect_files(
+    dir: &FsPath,
+    prefix: &mut Vec<String>,
+    files: &mut Vec<(String, PathBuf)>,
+) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = entry.path();
+        prefix.push(name);
+        if path.is_dir() {
+            collect_files(&path, prefix, files)?;
+        } else {
+            files.push((prefix.join("/"), path.canonicalize()?));
+        }
+        prefix.pop();
+    }
+    Ok(())
+}
+
+/// `T` of `Result<T, E>`.
+fn result_ok_type(ty: &Type) -> Option<&Type> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    if segment.ident != "Result" {
+        return None;
+    }
+    arguments.args.iter().find_map(|argument| match argument {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    })
+}
+
+/// `tokens` with every lifetime but `'static` made `'static`, for types named outside of the
+/// command, where its lifetimes don't exist.
+fn static_lifetimes(tokens: TokenStream2) -> TokenStream2 {
+    let mut after_quote = false;
+    tokens
+        .into_iter()
+        .map(|token| {
+            let token = match token {
+                TokenTree::Group(group) => {
+                    let mut replaced = proc_macro2::Group::new(
+                        group.delimiter(),
+                        static_lifetimes(group.stream()),
+                    );
+                    replaced.set_span(group.span());
+                    TokenTree::Group(replaced)
+                }
+                TokenTree::Ident(ident) if after_quote => {
+                    TokenTree::Ident(Ident::new("static", ident.span()))
+                }
+                other => other,
+            };
+            after_quote = matches!(&token, TokenTree::Punct(punct) if punct.as_char() == '\'');
+            token
+        })
+        .collect()
+}
+
 fn is_result_type(ty: &Type) -> bool {
     let Type::Path(type_path) = ty else {
         return false;
@@ -376,20 +1052,39 @@ fn is_result_type(ty: &Type) -> bool {
         .is_some_and(|segment| segment.ident == "Result")
 }
 
-fn is_ipc_request_type(ty: &Type) -> bool {
+/// An argument the wrapper fills from the invoke itself instead of the JSON body.
+enum SpecialArg {
+    Request,
+    Headers,
+    WebviewLabel,
+    CancellationToken,
+}
+
+/// Recognizes `ipc::Request`, `ipc::Headers`, `ipc::WebviewLabel` and
+/// `ipc::CancellationToken`, with any path before `ipc`.
+fn ipc_special_type(ty: &Type) -> Option<SpecialArg> {
     let Type::Path(type_path) = ty else {
-        return false;
+        return None;
     };
 
     let segments = &type_path.path.segments;
     if segments.len() < 2 {
-        return false;
+        return None;
     }
 
     let last = segments.last().expect("segments is not empty");
     let second_last = segments.iter().nth_back(1).expect("segments has >= 2");
+    if second_last.ident != "ipc" {
+        return None;
+    }
 
-    second_last.ident == "ipc" && last.ident == "Request"
+    match last.ident.to_string().as_str() {
+        "Request" => Some(SpecialArg::Request),
+        "Headers" => Some(SpecialArg::Headers),
+        "WebviewLabel" => Some(SpecialArg::WebviewLabel),
+        "CancellationToken" => Some(SpecialArg::CancellationToken),
+        _ => None,
+    }
 }
 
 /// Wraps a function with signature `Fn(http::Request<Vec<u8>> -> http::Response<Vec<u8>>)` into a tuple `(func_name, func)`.
@@ -0,0 +1,27 @@
+use std::fs;
+
+/// Keeps the `@manos/api` package in `js/api` at the crate's version, since the two ship
+/// together.
+fn main() {
+    println!("cargo:rerun-if-changed=js/api");
+
+    let manifest = fs::read_to_string("js/api/package.json").expect("read js/api/package.json");
+    let version = manifest
+        .lines()
+        .find_map(|line| {
+            let value = line.trim().strip_prefix("\"version\":")?;
+            Some(
+                value
+                    .trim()
+                    .trim_end_matches(',')
+                    .trim_matches('"')
+                    .to_string(),
+            )
+        })
+        .expect("js/api/package.json has no version");
+    let crate_version = std::env::var("CARGO_PKG_VERSION").unwrap();
+    assert_eq!(
+        version, crate_version,
+        "js/api/package.json is at {version} but the crate is at {crate_version}"
+    );
+}
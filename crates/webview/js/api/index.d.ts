@@ -0,0 +1,204 @@
+// Type declarations for @manos/api; see index.js.
+
+export interface InvokeOptions {
+  headers?: Record<string, string> | Headers
+}
+
+export type InvokeArgs = Record<string, unknown> | number[] | ArrayBuffer | Uint8Array
+
+export function isManos(): boolean
+
+export function transformCallback<T = unknown>(
+  callback?: (response: T) => void,
+  once?: boolean
+): number
+
+export function unregisterCallback(id: number): void
+
+export function invoke<T = unknown>(
+  cmd: string,
+  args?: InvokeArgs,
+  options?: InvokeOptions
+): Promise<T>
+
+/** What `invoke` rejects with when the command fails with an `ipc::InvokeError`. */
+export interface InvokeError<D = unknown> {
+  code: string
+  message: string
+  data?: D
+}
+
+export function isInvokeError<D = unknown>(error: unknown): error is InvokeError<D>
+
+export function convertFileSrc(filePath: string, protocol?: string): string
+
+export class Channel<T = unknown> {
+  readonly id: number
+  constructor(onmessage?: (message: T) => void)
+  onmessage: (message: T) => void
+  cleanupCallback(): void
+  toJSON(): string
+}
+
+export type EventTarget =
+  | string
+  | { kind: 'Any' }
+  | { kind: 'AnyLabel'; label: string }
+  | { kind: 'Webview'; label: string }
+
+export interface Event<T> {
+  event: string
+  id: number
+  payload: T
+}
+
+export type EventCallback<T> = (event: Event<T>) => void
+
+export type UnlistenFn = () => Promise<void>
+
+export interface ListenOptions {
+  target?: EventTarget
+}
+
+export function listen<T = unknown>(
+  event: string,
+  handler: EventCallback<T>,
+  options?: ListenOptions
+): Promise<UnlistenFn>
+
+export function once<T = unknown>(
+  event: string,
+  handler: EventCallback<T>,
+  options?: ListenOptions
+): Promise<UnlistenFn>
+
+export function emit<T = unknown>(event: string, payload?: T): Promise<void>
+
+export function emitTo<T = unknown>(
+  target: EventTarget,
+  event: string,
+  payload?: T
+): Promise<void>
+
+export type DragDropEvent =
+  | { type: 'enter'; paths: string[]; position: { x: number; y: number } }
+  | { type: 'over'; position: { x: number; y: number } }
+  | { type: 'drop'; paths: string[]; position: { x: number; y: number } }
+  | { type: 'leave' }
+
+export function onDragDropEvent(handler: EventCallback<DragDropEvent>): Promise<UnlistenFn>
+
+export interface Cookie {
+  name: string
+  value: string
+  domain?: string | null
+  path?: string | null
+  secure?: boolean | null
+  httpOnly?: boolean
+  sameSite?: 'strict' | 'lax' | 'none' | null
+  /** Unix time in seconds; a session cookie when missing. */
+  expires?: number | null
+}
+
+export function getCookies(origin: string): Promise<Cookie[]>
+
+export function setCookie(origin: string, cookie: Cookie): Promise<void>
+
+export function clearCookies(origin: string): Promise<number>
+
+export function minimizeWindow(): Promise<void>
+
+export function maximizeWindow(): Promise<void>
+
+export function closeWindow(): Promise<void>
+
+export function setWindowTitle(title: string): Promise<void>
+
+/** Sizes are in logical pixels. */
+export function setWindowSize(width: number, height: number): Promise<void>
+
+export function setZoom(level: number): Promise<void>
+
+export function zoomIn(): Promise<number>
+
+export function zoomOut(): Promise<number>
+
+export function zoomLevel(): Promise<number>
+
+export function print(): Promise<void>
+
+export interface PdfOptions {
+  landscape?: boolean
+  /** In millimeters. */
+  margins?: { top: number; right: number; bottom: number; left: number }
+}
+
+export function printToPdf(path: string, options?: PdfOptions): Promise<void>
+
+export interface DirEntry {
+  name: string
+  isDirectory: boolean
+  isFile: boolean
+  isSymlink: boolean
+}
+
+export function readTextFile(path: string): Promise<string>
+
+export function writeTextFile(path: string, contents: string): Promise<void>
+
+export function readDir(path: string): Promise<DirEntry[]>
+
+export function exists(path: string): Promise<boolean>
+
+export function remove(path: string, options?: { recursive?: boolean }): Promise<void>
+
+export function copyFile(fromPath: string, toPath: string): Promise<void>
+
+export interface OpenDialogOptions {
+  title?: string
+  /** Pick directories instead of files. */
+  directory?: boolean
+  multiple?: boolean
+}
+
+export interface SaveDialogOptions {
+  /** A directory, a file name or a path to a file to start with. */
+  defaultPath?: string
+}
+
+export interface MessageDialogOptions {
+  title?: string
+  kind?: "info" | "warning" | "error"
+  okLabel?: string
+  cancelLabel?: string
+}
+
+export function open(
+  options?: OpenDialogOptions & { multiple?: false }
+): Promise<string | null>
+export function open(
+  options: OpenDialogOptions & { multiple: true }
+): Promise<string[] | null>
+
+export function save(options?: SaveDialogOptions): Promise<string | null>
+
+/** A string `options` is the title. */
+export function message(message: string, options?: string | MessageDialogOptions): Promise<void>
+
+export function ask(message: string, options?: string | MessageDialogOptions): Promise<boolean>
+
+export function confirm(message: string, options?: string | MessageDialogOptions): Promise<boolean>
+
+export function writeText(text: string): Promise<void>
+
+export function readText(): Promise<string>
+
+/** `format` is the MIME type of `bytes`. */
+export function writeImage(
+  bytes: Uint8Array | ArrayBuffer | number[],
+  format?: string
+): Promise<void>
+
+export function readImage(): Promise<Uint8Array>
+
+export function openExternal(urlOrPath: string): Promise<void>
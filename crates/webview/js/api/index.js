@@ -0,0 +1,409 @@
+// Frontend API for gpui-manos-webview.
+//
+// A thin, dependency-free wrapper over the `window.__TAURI_INTERNALS__` object the webview
+// injects into every page. The call shapes match `@tauri-apps/api`, so code can move
+// between the two by changing the import.
+
+const CHANNEL_PREFIX = '__CHANNEL__:'
+
+function internals() {
+  const value = window.__TAURI_INTERNALS__
+  if (!value) {
+    throw new Error(
+      '@manos/api: window.__TAURI_INTERNALS__ is missing; the page is not running in a gpui-manos-webview'
+    )
+  }
+  return value
+}
+
+/** Whether the page runs inside a gpui-manos-webview. */
+export function isManos() {
+  return typeof window !== 'undefined' && !!window.__TAURI_INTERNALS__
+}
+
+/**
+ * Registers `callback` with the injected callback table and returns its id.
+ * With `once` the callback is removed after its first call.
+ */
+export function transformCallback(callback, once = false) {
+  return internals().transformCallback(callback, once)
+}
+
+/** Removes a callback registered with `transformCallback`. */
+export function unregisterCallback(id) {
+  internals().unregisterCallback(id)
+}
+
+/**
+ * Calls the Rust command `cmd`. `args` keys follow the command's `rename_all`
+ * (camelCase by default). Resolves with the command's return value; binary responses
+ * resolve to an `ArrayBuffer`.
+ */
+export function invoke(cmd, args = {}, options) {
+  return internals().invoke(cmd, args, options)
+}
+
+/**
+ * Whether `error`, as an `invoke` rejected with it, is the `{ code, message, data }` object of
+ * a Rust `ipc::InvokeError`.
+ */
+export function isInvokeError(error) {
+  return (
+    typeof error === 'object'
+    && error !== null
+    && typeof error.code === 'string'
+    && typeof error.message === 'string'
+  )
+}
+
+/** Turns a path on disk into a URL the webview can load through the asset protocol. */
+export function convertFileSrc(filePath, protocol = 'asset') {
+  return internals().convertFileSrc(filePath, protocol)
+}
+
+/**
+ * A stream of messages from a Rust `ipc::Channel<T>`. Pass it as a command argument;
+ * messages are delivered in order even when they arrive out of order.
+ */
+export class Channel {
+  #onmessage
+  #nextIndex = 0
+  #pending = new Map()
+  #endIndex = null
+
+  constructor(onmessage) {
+    this.#onmessage = onmessage || (() => {})
+    this.id = transformCallback((event) => {
+      const index = event.index
+      if ('end' in event) {
+        if (index === this.#nextIndex) {
+          this.cleanupCallback()
+        } else {
+          this.#endIndex = index
+        }
+        return
+      }
+
+      if (index !== this.#nextIndex) {
+        this.#pending.set(index, event.message)
+        return
+      }
+      this.#deliver(event.message)
+      while (this.#pending.has(this.#nextIndex)) {
+        const message = this.#pending.get(this.#nextIndex)
+        this.#pending.delete(this.#nextIndex)
+        this.#deliver(message)
+      }
+      if (this.#nextIndex === this.#endIndex) {
+        this.cleanupCallback()
+      }
+    })
+  }
+
+  #deliver(message) {
+    this.#onmessage(message)
+    this.#nextIndex += 1
+  }
+
+  set onmessage(handler) {
+    this.#onmessage = handler
+  }
+
+  get onmessage() {
+    return this.#onmessage
+  }
+
+  /** Stops receiving messages. Called automatically when Rust drops the channel. */
+  cleanupCallback() {
+    unregisterCallback(this.id)
+  }
+
+  toJSON() {
+    return `${CHANNEL_PREFIX}${this.id}`
+  }
+}
+
+async function unlisten(event, eventId, handlerId) {
+  unregisterCallback(handlerId)
+  await invoke('plugin:event|unlisten', { event, eventId })
+}
+
+/**
+ * Listens for `event`. `options.target` limits delivery to events sent to one webview
+ * label. Resolves with a function that removes the listener.
+ */
+export async function listen(event, handler, options = {}) {
+  const target = normalizeTarget(options.target)
+  const handlerId = transformCallback(handler)
+  const eventId = await invoke('plugin:event|listen', {
+    event,
+    target,
+    handler: handlerId
+  })
+  return () => unlisten(event, eventId, handlerId)
+}
+
+/** Like `listen`, but the listener is removed after the first event. */
+export async function once(event, handler, options = {}) {
+  let stop = null
+  let fired = false
+  stop = await listen(
+    event,
+    (payload) => {
+      if (fired) {
+        return
+      }
+      fired = true
+      handler(payload)
+      if (stop) {
+        stop()
+      }
+    },
+    options
+  )
+  if (fired) {
+    await stop()
+  }
+  return stop
+}
+
+/** Sends `event` to every listener, in Rust and in every webview. */
+export async function emit(event, payload) {
+  await invoke('plugin:event|emit', { event, payload })
+}
+
+/** Sends `event` to the listeners of one webview label. */
+export async function emitTo(target, event, payload) {
+  await invoke('plugin:event|emit_to', {
+    target: normalizeTarget(target),
+    event,
+    payload
+  })
+}
+
+/**
+ * Calls `handler` while files are dragged over the webview and when they are dropped onto it.
+ * The payload has a `type` of `enter`, `over`, `drop` or `leave`, and the `paths` and the
+ * `position` of the drag. Needs `Builder::on_file_drop` or `Builder::file_drop_events` on the
+ * Rust side. Resolves with a function that stops it.
+ */
+export async function onDragDropEvent(handler) {
+  const types = {
+    'tauri://drag-enter': 'enter',
+    'tauri://drag-over': 'over',
+    'tauri://drag-drop': 'drop',
+    'tauri://drag-leave': 'leave'
+  }
+  const stops = await Promise.all(
+    Object.entries(types).map(([event, type]) =>
+      listen(event, (e) => handler({ ...e, payload: { type, ...e.payload } }))
+    )
+  )
+  return async () => {
+    await Promise.all(stops.map((stop) => stop()))
+  }
+}
+
+function normalizeTarget(target) {
+  if (target === undefined || target === null) {
+    return { kind: 'Any' }
+  }
+  if (typeof target === 'string') {
+    return { kind: 'AnyLabel', label: target }
+  }
+  return target
+}
+
+/**
+ * The cookies a request to `origin`, such as `https://example.com`, would send. Needs the
+ * `cookies` permission when the webview gates invokes.
+ */
+export function getCookies(origin) {
+  return invoke('plugin:cookies|get', { origin })
+}
+
+/** Sets `cookie` for `origin`; without a `domain` it is scoped to the origin's host. */
+export async function setCookie(origin, cookie) {
+  await invoke('plugin:cookies|set', { origin, cookie })
+}
+
+/** Deletes the cookies of `origin`. Resolves with how many there were. */
+export function clearCookies(origin) {
+  return invoke('plugin:cookies|clear', { origin })
+}
+
+/** Minimizes the window the webview is in. */
+export async function minimizeWindow() {
+  await invoke('plugin:window|minimize')
+}
+
+/** Maximizes the window the webview is in; a maximized window stays as it is. */
+export async function maximizeWindow() {
+  await invoke('plugin:window|maximize')
+}
+
+/** Closes the window the webview is in. */
+export async function closeWindow() {
+  await invoke('plugin:window|close')
+}
+
+export async function setWindowTitle(title) {
+  await invoke('plugin:window|set_title', { value: title })
+}
+
+/** Resizes the window the webview is in to `width` × `height` logical pixels. */
+export async function setWindowSize(width, height) {
+  await invoke('plugin:window|set_size', {
+    value: { Logical: { width, height } }
+  })
+}
+
+/** Zooms the webview to `level`; `1` is the original size. */
+export async function setZoom(level) {
+  await invoke('plugin:webview|set_webview_zoom', { value: level })
+}
+
+/** Zooms the webview in one step and resolves with the new level. */
+export function zoomIn() {
+  return invoke('plugin:webview|zoom_in')
+}
+
+/** Zooms the webview out one step and resolves with the new level. */
+export function zoomOut() {
+  return invoke('plugin:webview|zoom_out')
+}
+
+/** The zoom level of the webview, whether the app or the user set it. */
+export function zoomLevel() {
+  return invoke('plugin:webview|zoom_level')
+}
+
+/** Shows the print dialog for the page. */
+export async function print() {
+  await invoke('plugin:webview|print')
+}
+
+/**
+ * Writes the page to a PDF file, without a dialog. Paths are absolute or relative to the
+ * first directory of the scope the app allowed with `Builder::print_to_pdf`.
+ */
+export async function printToPdf(path, options = {}) {
+  await invoke('plugin:webview|print_to_pdf', { path, options })
+}
+
+/**
+ * Reads a UTF-8 file. Paths are absolute or relative to the first directory of the webview's
+ * fs scope; the `fs` commands need `Builder::fs` on the Rust side.
+ */
+export async function readTextFile(path) {
+  const bytes = await invoke('plugin:fs|read_text_file', { path })
+  return new TextDecoder().decode(
+    bytes instanceof ArrayBuffer ? bytes : new Uint8Array(bytes)
+  )
+}
+
+export async function writeTextFile(path, contents) {
+  await invoke('plugin:fs|write_text_file', new TextEncoder().encode(contents), {
+    headers: { path: encodeURIComponent(path) }
+  })
+}
+
+/** The entries of a directory, sorted by name. */
+export function readDir(path) {
+  return invoke('plugin:fs|read_dir', { path })
+}
+
+export function exists(path) {
+  return invoke('plugin:fs|exists', { path })
+}
+
+/** Removes a file or an empty directory, or any directory with `{ recursive: true }`. */
+export async function remove(path, options = {}) {
+  await invoke('plugin:fs|remove', { path, options })
+}
+
+export async function copyFile(fromPath, toPath) {
+  await invoke('plugin:fs|copy_file', { fromPath, toPath })
+}
+
+/**
+ * Shows a native file dialog. Answers the picked path, the picked paths with
+ * `{ multiple: true }`, or `null` when the user cancels.
+ */
+export function open(options = {}) {
+  return invoke('plugin:dialog|open', { options })
+}
+
+/** Shows a native save dialog and answers the chosen path, or `null`. */
+export function save(options = {}) {
+  return invoke('plugin:dialog|save', { options })
+}
+
+function messageOptions(options) {
+  return typeof options === 'string' ? { title: options } : options
+}
+
+export async function message(message, options = {}) {
+  const { title, kind, okLabel } = messageOptions(options)
+  await invoke('plugin:dialog|message', {
+    message: message.toString(),
+    title,
+    kind,
+    okButtonLabel: okLabel
+  })
+}
+
+/** Asks a yes/no question and answers whether the user said yes. */
+export function ask(message, options = {}) {
+  const { title, kind, okLabel, cancelLabel } = messageOptions(options)
+  return invoke('plugin:dialog|ask', {
+    message: message.toString(),
+    title,
+    kind,
+    yesButtonLabel: okLabel,
+    noButtonLabel: cancelLabel
+  })
+}
+
+/** Asks for confirmation and answers whether the user confirmed. */
+export function confirm(message, options = {}) {
+  const { title, kind, okLabel, cancelLabel } = messageOptions(options)
+  return invoke('plugin:dialog|confirm', {
+    message: message.toString(),
+    title,
+    kind,
+    okButtonLabel: okLabel,
+    cancelButtonLabel: cancelLabel
+  })
+}
+
+export async function writeText(text) {
+  await invoke('plugin:clipboard-manager|write_text', { text })
+}
+
+/** The text on the clipboard; rejects when it holds none. */
+export function readText() {
+  return invoke('plugin:clipboard-manager|read_text')
+}
+
+/** Puts encoded image bytes, e.g. a PNG file, on the clipboard. */
+export async function writeImage(bytes, format = 'image/png') {
+  await invoke(
+    'plugin:clipboard-manager|write_image',
+    bytes instanceof Uint8Array ? bytes : new Uint8Array(bytes),
+    { headers: { format } }
+  )
+}
+
+/** The bytes of the image on the clipboard, in the format it was put there with. */
+export async function readImage() {
+  return new Uint8Array(await invoke('plugin:clipboard-manager|read_image'))
+}
+
+/**
+ * Opens a URL in the default browser, or an absolute path in the file manager, instead of in
+ * the webview. The schemes need `Builder::shell` on the Rust side.
+ */
+export async function openExternal(urlOrPath) {
+  await invoke('plugin:shell|open', { path: urlOrPath })
+}
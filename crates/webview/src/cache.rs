@@ -0,0 +1,498 @@
+//! Caching the responses of the custom protocols.
+//!
+//! A [`ResponseCache`] keeps the successful responses of the routes it is told about in memory
+//! and, optionally, in a directory on disk, so a page keeps working offline and large assets are
+//! not read again on every navigation. Static assets are matched by URI path, invokes by command
+//! name (and payload); both patterns accept a trailing `*` to match a prefix.
+//!
+//! An entry answers requests until the TTL of its route runs out. After that the request reaches
+//! its handler again, and the stale entry is only used when the handler fails — for example an
+//! invoke that fetches from the network while the machine is offline.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+/// Memory budget of a new cache.
+const DEFAULT_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// Header added to responses answered from the cache: `hit` or `stale`.
+pub const CACHE_STATUS_HEADER: &str = "X-Cache";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Protocol {
+    Asset,
+    Command,
+}
+
+impl Protocol {
+    fn key_prefix(self) -> &'static str {
+        match self {
+            Protocol::Asset => "asset:",
+            Protocol::Command => "ipc:",
+        }
+    }
+}
+
+struct Route {
+    protocol: Protocol,
+    pattern: String,
+    /// `None` keeps entries until they are invalidated.
+    ttl: Option<Duration>,
+}
+
+impl Route {
+    fn matches(&self, protocol: Protocol, name: &str) -> bool {
+        self.protocol == protocol && matches_pattern(&self.pattern, name)
+    }
+}
+
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name == pattern,
+    }
+}
+
+/// A cacheable request: where its entry lives and how long it stays fresh.
+pub(crate) struct CacheSlot {
+    key: String,
+    ttl: Option<Duration>,
+}
+
+#[derive(Clone)]
+struct Entry {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Arc<Vec<u8>>,
+    expires_at: Option<SystemTime>,
+}
+
+impl Entry {
+    fn from_response(response: &http::Response<Vec<u8>>, ttl: Option<Duration>) -> Self {
+        Self {
+            status: response.status().as_u16(),
+            headers: response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect(),
+            body: Arc::new(response.body().clone()),
+            expires_at: ttl.map(|ttl| SystemTime::now() + ttl),
+        }
+    }
+
+    fn is_fresh(&self) -> bool {
+        self.expires_at
+            .is_none_or(|expires_at| SystemTime::now() < expires_at)
+    }
+
+    fn to_response(&self, cache_status: &'static str) -> http::Response<Vec<u8>> {
+        let mut builder = http::Response::builder().status(self.status);
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        let mut response = builder
+            .body(self.body.as_ref().clone())
+            .unwrap_or_else(|_| http::Response::new(self.body.as_ref().clone()));
+        response.headers_mut().insert(
+            CACHE_STATUS_HEADER,
+            http::HeaderValue::from_static(cache_status),
+        );
+        response
+    }
+}
+
+#[derive(Default)]
+struct Memory {
+    entries: HashMap<String, Entry>,
+    /// Least recently used first.
+    order: VecDeque<String>,
+    total_bytes: usize,
+}
+
+impl Memory {
+    fn get(&mut self, key: &str) -> Option<Entry> {
+        let entry = self.entries.get(key)?.clone();
+        self.touch(key);
+        Some(entry)
+    }
+
+    fn insert(&mut self, key: String, entry: Entry, limit: usize) {
+        self.remove(&key);
+        let size = entry.body.len();
+        if size > limit {
+            return;
+        }
+        while self.total_bytes + size > limit {
+            let Some(oldest) = self.order.pop_front() else {
+                break;
+            };
+            if let Some(evicted) = self.entries.remove(&oldest) {
+                self.total_bytes -= evicted.body.len();
+            }
+        }
+        self.total_bytes += size;
+        self.order.push_back(key.clone());
+        self.entries.insert(key, entry);
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.total_bytes -= entry.body.len();
+            self.order.retain(|k| k != key);
+        }
+    }
+
+    fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.order.retain(|key| keep(key));
+        let entries = &mut self.entries;
+        let total_bytes = &mut self.total_bytes;
+        entries.retain(|key, entry| {
+            let keep = keep(key);
+            if !keep {
+                *total_bytes -= entry.body.len();
+            }
+            keep
+        });
+    }
+
+    fn touch(&mut self, key: &str) {
+        if let Some(ix) = self.order.iter().position(|k| k == key)
+            && let Some(key) = self.order.remove(ix)
+        {
+            self.order.push_back(key);
+        }
+    }
+}
+
+/// What is saved next to the body of an entry on disk.
+#[derive(Serialize, Deserialize)]
+struct DiskMeta {
+    key: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+    /// Seconds since the Unix epoch.
+    expires_at: Option<u64>,
+}
+
+struct Inner {
+    routes: Mutex<Vec<Route>>,
+    memory: Mutex<Memory>,
+    memory_limit: Mutex<usize>,
+    disk: Mutex<Option<PathBuf>>,
+}
+
+/// Responses of the `asset://` and `ipc://` protocols kept for later requests.
+///
+/// Pass it to [`crate::Builder::cache`]. Clones share their entries, so the app can keep one to
+/// invalidate entries when the data behind them changes.
+#[derive(Clone)]
+pub struct ResponseCache {
+    inner: Arc<Inner>,
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResponseCache {
+    /// A memory-only cache without routes; nothing is cached until a route is added.
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                routes: Mutex::new(Vec::new()),
+                memory: Mutex::new(Memory::default()),
+                memory_limit: Mutex::new(DEFAULT_MEMORY_LIMIT),
+                disk: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Also keeps entries in `dir`, so they survive restarts. The directory is created on the
+    /// first write.
+    pub fn persist_to(self, dir: impl Into<PathBuf>) -> Self {
+        *self.inner.disk.lock().unwrap() = Some(dir.into());
+        self
+    }
+
+    /// How many body bytes to keep in memory; the least recently used entries go first.
+    pub fn memory_limit(self, bytes: usize) -> Self {
+        *self.inner.memory_limit.lock().unwrap() = bytes;
+        self
+    }
+
+    /// Caches static assets whose URI path matches `pattern`, such as `/index.html` or
+    /// `/assets/*`.
+    pub fn cache_assets(self, pattern: impl Into<String>, ttl: Option<Duration>) -> Self {
+        self.add_route(Protocol::Asset, pattern.into(), ttl)
+    }
+
+    /// Caches invokes of the commands matching `pattern`, such as `load_catalog` or
+    /// `plugin:catalog|*`. Each payload gets its own entry.
+    pub fn cache_command(self, pattern: impl Into<String>, ttl: Option<Duration>) -> Self {
+        self.add_route(Protocol::Command, pattern.into(), ttl)
+    }
+
+    fn add_route(self, protocol: Protocol, pattern: String, ttl: Option<Duration>) -> Self {
+        self.inner.routes.lock().unwrap().push(Route {
+            protocol,
+            pattern,
+            ttl,
+        });
+        self
+    }
+
+    /// Drops the cached assets whose URI path matches `pattern`.
+    pub fn invalidate_asset(&self, pattern: &str) {
+        self.invalidate(Protocol::Asset, pattern);
+    }
+
+    /// Drops the cached responses of the commands matching `pattern`, for every payload.
+    pub fn invalidate_command(&self, pattern: &str) {
+        self.invalidate(Protocol::Command, pattern);
+    }
+
+    /// Drops every entry, in memory and on disk.
+    pub fn clear(&self) {
+        self.inner.memory.lock().unwrap().retain(|_| false);
+        if let Some(dir) = self.disk_dir() {
+            for (meta_path, _) in disk_entries(&dir) {
+                remove_disk_entry(&meta_path);
+            }
+        }
+    }
+
+    fn invalidate(&self, protocol: Protocol, pattern: &str) {
+        let matches =
+            |key: &str| key_name(key, protocol).is_some_and(|name| matches_pattern(pattern, name));
+        self.inner
+            .memory
+            .lock()
+            .unwrap()
+            .retain(|key| !matches(key));
+        if let Some(dir) = self.disk_dir() {
+            for (meta_path, meta) in disk_entries(&dir) {
+                if matches(&meta.key) {
+                    remove_disk_entry(&meta_path);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn asset_slot(&self, path: &str) -> Option<CacheSlot> {
+        let ttl = self.route_ttl(Protocol::Asset, path)?;
+        Some(CacheSlot {
+            key: format!("{}{path}", Protocol::Asset.key_prefix()),
+            ttl,
+        })
+    }
+
+    pub(crate) fn command_slot(&self, command: &str, payload: &[u8]) -> Option<CacheSlot> {
+        let ttl = self.route_ttl(Protocol::Command, command)?;
+        Some(CacheSlot {
+            key: format!(
+                "{}{command}#{:016x}",
+                Protocol::Command.key_prefix(),
+                fnv1a(payload)
+            ),
+            ttl,
+        })
+    }
+
+    fn route_ttl(&self, protocol: Protocol, name: &str) -> Option<Option<Duration>> {
+        self.inner
+            .routes
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|route| route.matches(protocol, name))
+            .map(|route| route.ttl)
+    }
+
+    /// Answers `slot` from a fresh entry, or runs `fetch` and caches its response when it
+    /// succeeded. A stale entry stands in for a failed `fetch`.
+    pub(crate) fn respond(
+        &self,
+        slot: CacheSlot,
+        fetch: impl FnOnce() -> http::Response<Vec<u8>>,
+    ) -> http::Response<Vec<u8>> {
+        let cached = self.lookup(&slot.key);
+        if let Some(entry) = cached.as_ref().filter(|entry| entry.is_fresh()) {
+            return entry.to_response("hit");
+        }
+
+        let response = fetch();
+        if is_success(&response) {
+            self.store(slot, &response);
+            return response;
+        }
+        match cached {
+            Some(entry) => {
+                tracing::debug!(
+                    "serving stale `{}` after a {} response",
+                    slot.key,
+                    response.status()
+                );
+                entry.to_response("stale")
+            }
+            None => response,
+        }
+    }
+
+    fn lookup(&self, key: &str) -> Option<Entry> {
+        if let Some(entry) = self.inner.memory.lock().unwrap().get(key) {
+            return Some(entry);
+        }
+        let dir = self.disk_dir()?;
+        let entry = match read_disk_entry(&dir, key) {
+            Ok(entry) => entry?,
+            Err(err) => {
+                tracing::warn!("failed to read cached `{key}`: {err}");
+                return None;
+            }
+        };
+        let limit = self.memory_budget();
+        self.inner
+            .memory
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), entry.clone(), limit);
+        Some(entry)
+    }
+
+    fn store(&self, slot: CacheSlot, response: &http::Response<Vec<u8>>) {
+        let entry = Entry::from_response(response, slot.ttl);
+        if let Some(dir) = self.disk_dir()
+            && let Err(err) = write_disk_entry(&dir, &slot.key, &entry)
+        {
+            tracing::warn!("failed to cache `{}` on disk: {err}", slot.key);
+        }
+        let limit = self.memory_budget();
+        self.inner
+            .memory
+            .lock()
+            .unwrap()
+            .insert(slot.key, entry, limit);
+    }
+
+    fn disk_dir(&self) -> Option<PathBuf> {
+        self.inner.disk.lock().unwrap().clone()
+    }
+
+    fn memory_budget(&self) -> usize {
+        *self.inner.memory_limit.lock().unwrap()
+    }
+}
+
+/// Successful asset reads and invokes that resolved; rejected invokes are not cached.
+fn is_success(response: &http::Response<Vec<u8>>) -> bool {
+    response.status().is_success()
+        && response
+            .headers()
+            .get("Tauri-Response")
+            .is_none_or(|value| value != "error")
+}
+
+/// The asset path or command name of a key of `protocol`.
+fn key_name(key: &str, protocol: Protocol) -> Option<&str> {
+    let name = key.strip_prefix(protocol.key_prefix())?;
+    Some(match protocol {
+        Protocol::Asset => name,
+        Protocol::Command => name.rsplit_once('#').map_or(name, |(command, _)| command),
+    })
+}
+
+/// A hash that stays the same across builds, for payload keys and file names.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn disk_paths(dir: &Path, key: &str) -> (PathBuf, PathBuf) {
+    let name = format!("{:016x}", fnv1a(key.as_bytes()));
+    (
+        dir.join(format!("{name}.json")),
+        dir.join(format!("{name}.bin")),
+    )
+}
+
+fn read_disk_entry(dir: &Path, key: &str) -> io::Result<Option<Entry>> {
+    let (meta_path, body_path) = disk_paths(dir, key);
+    let meta = match fs::read(&meta_path) {
+        Ok(meta) => meta,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    let meta: DiskMeta = serde_json::from_slice(&meta).map_err(io::Error::other)?;
+    // Another key with the same hash.
+    if meta.key != key {
+        return Ok(None);
+    }
+    let body = fs::read(body_path)?;
+    Ok(Some(Entry {
+        status: meta.status,
+        headers: meta.headers,
+        body: Arc::new(body),
+        expires_at: meta
+            .expires_at
+            .map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs)),
+    }))
+}
+
+fn write_disk_entry(dir: &Path, key: &str, entry: &Entry) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let (meta_path, body_path) = disk_paths(dir, key);
+    let meta = DiskMeta {
+        key: key.to_string(),
+        status: entry.status,
+        headers: entry.headers.clone(),
+        expires_at: entry.expires_at.map(|expires_at| {
+            expires_at
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs())
+        }),
+    };
+    // The body goes first: a meta file without its body would read as an error.
+    fs::write(body_path, entry.body.as_ref())?;
+    fs::write(
+        meta_path,
+        serde_json::to_vec(&meta).map_err(io::Error::other)?,
+    )
+}
+
+fn disk_entries(dir: &Path) -> Vec<(PathBuf, DiskMeta)> {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    read_dir
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+            let meta = serde_json::from_slice(&fs::read(&path).ok()?).ok()?;
+            Some((path, meta))
+        })
+        .collect()
+}
+
+fn remove_disk_entry(meta_path: &Path) {
+    for path in [meta_path.to_path_buf(), meta_path.with_extension("bin")] {
+        if let Err(err) = fs::remove_file(&path)
+            && err.kind() != io::ErrorKind::NotFound
+        {
+            tracing::warn!("failed to remove cached {}: {err}", path.display());
+        }
+    }
+}
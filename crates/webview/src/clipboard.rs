@@ -0,0 +1,105 @@
+//! The system clipboard for the page, through gpui rather than `navigator.clipboard`, which wry
+//! webviews often don't grant.
+//!
+//! `plugin:clipboard-manager|write_text` and `read_text` take the payloads
+//! `@tauri-apps/plugin-clipboard-manager` sends, so its `writeText` and `readText` work.
+//! `write_image` and `read_image` move encoded image bytes (PNG, JPEG, …) as they are: the body
+//! of `write_image` is the image, with its MIME type in the `format` header, and `read_image`
+//! answers the bytes of the first image on the clipboard. The write commands are always
+//! registered; the read commands only with [`crate::Builder::read_clipboard`], so a page can't
+//! read the clipboard unless the app allows it. The commands need the
+//! [`crate::permissions::Capability::Clipboard`] permission when the webview has
+//! [`crate::Builder::permissions`].
+
+use gpui::{ClipboardEntry, ClipboardItem, Image, ImageFormat};
+use http::header::CONTENT_TYPE;
+use serde::Deserialize;
+
+use crate::ipc;
+use crate::manager::WebviewManager;
+
+pub const WRITE_TEXT_COMMAND: &str = "plugin:clipboard-manager|write_text";
+pub const READ_TEXT_COMMAND: &str = "plugin:clipboard-manager|read_text";
+pub const WRITE_IMAGE_COMMAND: &str = "plugin:clipboard-manager|write_image";
+pub const READ_IMAGE_COMMAND: &str = "plugin:clipboard-manager|read_image";
+
+#[derive(Debug, Deserialize)]
+struct WriteTextPayload {
+    text: String,
+}
+
+pub(crate) fn write_text(request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let payload: WriteTextPayload = match serde_json::from_slice(request.body()) {
+        Ok(payload) => payload,
+        Err(err) => {
+            return ipc::bad_request(format!("invalid JSON body for {WRITE_TEXT_COMMAND}: {err}"));
+        }
+    };
+    let written = WebviewManager::global()
+        .with_app(move |cx| cx.write_to_clipboard(ClipboardItem::new_string(payload.text)));
+    match written {
+        Ok(()) => ipc::ok_json(&()),
+        Err(err) => ipc::internal_error(err),
+    }
+}
+
+pub(crate) fn read_text(_: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let text = WebviewManager::global()
+        .with_app(|cx| cx.read_from_clipboard().and_then(|item| item.text()));
+    match text {
+        Ok(Some(text)) => ipc::ok_json(&text),
+        Ok(None) => ipc::internal_error("the clipboard holds no text"),
+        Err(err) => ipc::internal_error(err),
+    }
+}
+
+/// Takes the image as the body and its MIME type in the `format` header, PNG when it is
+/// missing, as `writeImage` sends them.
+pub(crate) fn write_image(request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let mime = request
+        .headers()
+        .get("format")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("image/png");
+    let Some(format) = ImageFormat::from_mime_type(mime) else {
+        return ipc::bad_request(format!("unsupported image format `{mime}`"));
+    };
+    let is_binary = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/octet-stream"));
+    let bytes = if is_binary {
+        request.into_body()
+    } else {
+        // The postMessage fallback sends the bytes as a JSON array.
+        match serde_json::from_slice::<Vec<u8>>(request.body()) {
+            Ok(bytes) => bytes,
+            Err(err) => return ipc::bad_request(format!("invalid image bytes: {err}")),
+        }
+    };
+    if bytes.is_empty() {
+        return ipc::bad_request("the image is empty");
+    }
+
+    let image = Image::from_bytes(format, bytes);
+    match WebviewManager::global().with_app(move |cx| cx.write_to_clipboard(image.into())) {
+        Ok(()) => ipc::ok_json(&()),
+        Err(err) => ipc::internal_error(err),
+    }
+}
+
+pub(crate) fn read_image(_: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let image = WebviewManager::global().with_app(|cx| {
+        cx.read_from_clipboard()?
+            .into_entries()
+            .find_map(|entry| match entry {
+                ClipboardEntry::Image(image) => Some(image.bytes),
+                ClipboardEntry::String(_) => None,
+            })
+    });
+    match image {
+        Ok(Some(bytes)) => ipc::respond(ipc::Response::binary(bytes)),
+        Ok(None) => ipc::internal_error("the clipboard holds no image"),
+        Err(err) => ipc::internal_error(err),
+    }
+}
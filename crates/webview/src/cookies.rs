@@ -0,0 +1,222 @@
+//! Cookies of a webview, one origin at a time.
+//!
+//! The [`crate::webview::WebView`] methods read and change the cookie jar from Rust. With
+//! [`crate::Builder::cookies`], the `plugin:cookies|get`, `plugin:cookies|set` and
+//! `plugin:cookies|clear` commands do the same for the page, always on the invoking webview,
+//! and need the [`crate::permissions::Capability::Cookies`] permission when the webview has
+//! [`crate::Builder::permissions`]. An origin is a URL such as
+//! `https://example.com`: getting returns the cookies a request to it would send, a cookie set
+//! without a domain is scoped to its host, and clearing deletes what getting returns.
+
+use serde::{Deserialize, Serialize};
+use wry::cookie::time::OffsetDateTime;
+use wry::cookie::{Cookie, SameSite};
+
+use crate::ipc;
+
+pub const GET_COMMAND: &str = "plugin:cookies|get";
+pub const SET_COMMAND: &str = "plugin:cookies|set";
+pub const CLEAR_COMMAND: &str = "plugin:cookies|clear";
+
+/// A cookie as Rust and the page see it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CookieInfo {
+    pub name: String,
+    pub value: String,
+    /// The host of the origin when `None`.
+    pub domain: Option<String>,
+    /// `/` when `None`.
+    pub path: Option<String>,
+    /// Whether the origin is `https` when `None`.
+    pub secure: Option<bool>,
+    pub http_only: bool,
+    /// `"strict"`, `"lax"` or `"none"`.
+    pub same_site: Option<String>,
+    /// Expiry as Unix time in seconds; a session cookie when `None`.
+    pub expires: Option<i64>,
+}
+
+impl CookieInfo {
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            ..Self::default()
+        }
+    }
+}
+
+struct Origin {
+    url: String,
+    host: String,
+    https: bool,
+}
+
+impl Origin {
+    fn parse(origin: &str) -> Result<Self, String> {
+        let uri: http::Uri = origin
+            .parse()
+            .map_err(|err| format!("invalid origin `{origin}`: {err}"))?;
+        let https = match uri.scheme_str() {
+            Some("https") => true,
+            Some("http") => false,
+            _ => return Err(format!("origin `{origin}` must be an http or https URL")),
+        };
+        let (Some(host), Some(authority)) = (uri.host(), uri.authority()) else {
+            return Err(format!("origin `{origin}` has no host"));
+        };
+        Ok(Self {
+            url: format!("{}://{authority}/", if https { "https" } else { "http" }),
+            host: host.to_string(),
+            https,
+        })
+    }
+}
+
+pub(crate) fn cookies_for_origin(
+    webview: &wry::WebView,
+    origin: &str,
+) -> Result<Vec<CookieInfo>, String> {
+    let origin = Origin::parse(origin)?;
+    let cookies = webview
+        .cookies_for_url(&origin.url)
+        .map_err(|err| err.to_string())?;
+    Ok(cookies.iter().map(cookie_info).collect())
+}
+
+pub(crate) fn set_cookie_for_origin(
+    webview: &wry::WebView,
+    origin: &str,
+    cookie: CookieInfo,
+) -> Result<(), String> {
+    let cookie = wry_cookie(&Origin::parse(origin)?, cookie)?;
+    webview.set_cookie(&cookie).map_err(|err| err.to_string())
+}
+
+/// Deletes the cookies of `origin` and returns how many there were.
+pub(crate) fn clear_cookies_for_origin(
+    webview: &wry::WebView,
+    origin: &str,
+) -> Result<usize, String> {
+    let origin = Origin::parse(origin)?;
+    let cookies = webview
+        .cookies_for_url(&origin.url)
+        .map_err(|err| err.to_string())?;
+    for cookie in &cookies {
+        webview
+            .delete_cookie(cookie)
+            .map_err(|err| err.to_string())?;
+    }
+    Ok(cookies.len())
+}
+
+fn cookie_info(cookie: &Cookie<'_>) -> CookieInfo {
+    CookieInfo {
+        name: cookie.name().to_string(),
+        value: cookie.value().to_string(),
+        domain: cookie.domain().map(str::to_string),
+        path: cookie.path().map(str::to_string),
+        secure: cookie.secure(),
+        http_only: cookie.http_only().unwrap_or(false),
+        same_site: cookie
+            .same_site()
+            .map(|same_site| same_site.to_string().to_ascii_lowercase()),
+        expires: cookie
+            .expires_datetime()
+            .map(|expires| expires.unix_timestamp()),
+    }
+}
+
+fn wry_cookie(origin: &Origin, info: CookieInfo) -> Result<Cookie<'static>, String> {
+    if info.name.is_empty() {
+        return Err("cookie name must not be empty".to_string());
+    }
+
+    let mut builder = Cookie::build((info.name, info.value))
+        .domain(info.domain.unwrap_or_else(|| origin.host.clone()))
+        .path(info.path.unwrap_or_else(|| "/".to_string()))
+        .secure(info.secure.unwrap_or(origin.https))
+        .http_only(info.http_only);
+    if let Some(same_site) = info.same_site {
+        builder = builder.same_site(match same_site.to_ascii_lowercase().as_str() {
+            "strict" => SameSite::Strict,
+            "lax" => SameSite::Lax,
+            "none" => SameSite::None,
+            other => return Err(format!("invalid sameSite `{other}`")),
+        });
+    }
+    if let Some(expires) = info.expires {
+        let expires = OffsetDateTime::from_unix_timestamp(expires)
+            .map_err(|err| format!("invalid cookie expiry: {err}"))?;
+        builder = builder.expires(expires);
+    }
+    Ok(builder.build())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OriginPayload {
+    origin: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetCookiePayload {
+    origin: String,
+    cookie: CookieInfo,
+}
+
+fn parse_payload<T: serde::de::DeserializeOwned>(
+    request: &http::Request<Vec<u8>>,
+    command: &str,
+) -> Result<T, String> {
+    serde_json::from_slice(request.body())
+        .map_err(|err| format!("invalid JSON body for {command}: {err}"))
+}
+
+/// Runs `f` on the invoking webview once the origin is known to be valid.
+fn respond_with<T: Serialize + Send + 'static>(
+    origin: String,
+    f: impl FnOnce(&wry::WebView, &str) -> Result<T, String> + Send + 'static,
+) -> http::Response<Vec<u8>> {
+    let Some(label) = ipc::current_webview_label() else {
+        return ipc::bad_request("cookie commands must be invoked from a webview");
+    };
+    if let Err(err) = Origin::parse(&origin) {
+        return ipc::bad_request(err);
+    }
+    match ipc::with_webview(Some(label), move |webview| f(webview, &origin)) {
+        Ok(Ok(value)) => ipc::ok_json(&value),
+        Ok(Err(err)) | Err(err) => ipc::internal_error(err),
+    }
+}
+
+pub(crate) fn get(request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    match parse_payload::<OriginPayload>(&request, GET_COMMAND) {
+        Ok(payload) => respond_with(payload.origin, cookies_for_origin),
+        Err(err) => ipc::bad_request(err),
+    }
+}
+
+pub(crate) fn set(request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    match parse_payload::<SetCookiePayload>(&request, SET_COMMAND) {
+        Ok(payload) => {
+            let cookie = payload.cookie;
+            if cookie.name.is_empty() {
+                return ipc::bad_request("cookie name must not be empty");
+            }
+            respond_with(payload.origin, move |webview, origin| {
+                set_cookie_for_origin(webview, origin, cookie)
+            })
+        }
+        Err(err) => ipc::bad_request(err),
+    }
+}
+
+pub(crate) fn clear(request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    match parse_payload::<OriginPayload>(&request, CLEAR_COMMAND) {
+        Ok(payload) => respond_with(payload.origin, clear_cookies_for_origin),
+        Err(err) => ipc::bad_request(err),
+    }
+}
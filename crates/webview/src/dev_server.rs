@@ -0,0 +1,246 @@
+//! Serving the frontend from a dev server (Vite, webpack, ...) during development; see
+//! [`crate::Builder::serve_dev_server`].
+//!
+//! The `asset://` and `wry://` protocols forward every request to the dev server and hand its
+//! response back to the page, so the page keeps its origin and IPC works as with
+//! [`crate::Builder::serve_static`]. Hot reload needs the dev server's websocket to be reachable
+//! directly, e.g. `server.hmr = { protocol: "ws", host: "localhost" }` in a Vite config.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+use std::time::Duration;
+
+use http::header::{CONTENT_TYPE, HeaderName, HeaderValue};
+
+/// In debug builds, [`crate::Builder::serve_static`] serves from the dev server at this URL
+/// instead of the static root when it is set.
+pub const DEV_SERVER_ENV: &str = "MANOS_DEV_SERVER_URL";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Headers that only make sense between the webview and this proxy, or between this proxy and
+/// the dev server.
+const HOP_BY_HOP_HEADERS: [&str; 6] = [
+    "connection",
+    "host",
+    "keep-alive",
+    "transfer-encoding",
+    "upgrade",
+    // Responses are passed through as they are, and custom protocols don't decompress them.
+    "accept-encoding",
+];
+
+/// A frontend dev server reachable over plain `http`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DevServer {
+    host: String,
+    port: u16,
+    /// Prefixed to every request path; empty or starting with `/` and without a trailing `/`.
+    base_path: String,
+}
+
+impl DevServer {
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            base_path: String::new(),
+        }
+    }
+
+    /// The dev server named by [`DEV_SERVER_ENV`], in debug builds only.
+    pub fn from_env() -> Option<Self> {
+        if !cfg!(debug_assertions) {
+            return None;
+        }
+        let url = std::env::var(DEV_SERVER_ENV).ok()?;
+        if url.trim().is_empty() {
+            return None;
+        }
+        url.parse()
+            .inspect_err(|err| tracing::error!("ignoring {DEV_SERVER_ENV}: {err}"))
+            .ok()
+    }
+
+    /// Forwards `request` and returns the dev server's response, or `502 Bad Gateway` when it
+    /// cannot be reached.
+    pub(crate) fn forward(&self, request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+        let path = request
+            .uri()
+            .path_and_query()
+            .map_or("/", |path| path.as_str())
+            .to_string();
+        match self.fetch(request) {
+            Ok(response) => response,
+            Err(err) => {
+                tracing::warn!("dev server {self} failed to serve {path}: {err}");
+                http::Response::builder()
+                    .status(http::StatusCode::BAD_GATEWAY)
+                    .header(CONTENT_TYPE, "text/plain")
+                    .body(format!("dev server {self} is not reachable: {err}").into_bytes())
+                    .unwrap()
+            }
+        }
+    }
+
+    fn fetch(&self, request: http::Request<Vec<u8>>) -> io::Result<http::Response<Vec<u8>>> {
+        let (parts, body) = request.into_parts();
+        let path = parts.uri.path_and_query().map_or("/", |path| path.as_str());
+
+        let mut head = format!(
+            "{} {}{path} HTTP/1.1\r\nHost: {}:{}\r\nConnection: close\r\n",
+            parts.method, self.base_path, self.host, self.port
+        )
+        .into_bytes();
+        for (name, value) in &parts.headers {
+            if HOP_BY_HOP_HEADERS.contains(&name.as_str()) || name == http::header::CONTENT_LENGTH {
+                continue;
+            }
+            head.extend_from_slice(name.as_str().as_bytes());
+            head.extend_from_slice(b": ");
+            head.extend_from_slice(value.as_bytes());
+            head.extend_from_slice(b"\r\n");
+        }
+        if !body.is_empty() {
+            head.extend_from_slice(format!("Content-Length: {}\r\n", body.len()).as_bytes());
+        }
+        head.extend_from_slice(b"\r\n");
+
+        let addr = (self.host.as_str(), self.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "host has no address"))?;
+        let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        stream.write_all(&head)?;
+        stream.write_all(&body)?;
+
+        let mut raw = Vec::new();
+        stream.read_to_end(&mut raw)?;
+        parse_response(&raw)
+    }
+}
+
+impl FromStr for DevServer {
+    type Err = String;
+
+    /// Parses a URL such as `http://localhost:5173` or `http://127.0.0.1:8080/app`.
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        let uri: http::Uri = url
+            .trim()
+            .parse()
+            .map_err(|err| format!("invalid dev server URL `{url}`: {err}"))?;
+        if uri.scheme_str() != Some("http") {
+            return Err(format!("dev server URL `{url}` must use http"));
+        }
+        let Some(host) = uri.host() else {
+            return Err(format!("dev server URL `{url}` has no host"));
+        };
+        if uri.query().is_some() {
+            return Err(format!("dev server URL `{url}` must not have a query"));
+        }
+        Ok(Self {
+            host: host
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_string(),
+            port: uri.port_u16().unwrap_or(80),
+            base_path: uri.path().trim_end_matches('/').to_string(),
+        })
+    }
+}
+
+impl fmt::Display for DevServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "http://[{}]:{}{}", self.host, self.port, self.base_path)
+        } else {
+            write!(f, "http://{}:{}{}", self.host, self.port, self.base_path)
+        }
+    }
+}
+
+fn invalid_response(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn parse_response(raw: &[u8]) -> io::Result<http::Response<Vec<u8>>> {
+    let head_end = raw
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or_else(|| invalid_response("response has no header terminator"))?;
+    let head = std::str::from_utf8(&raw[..head_end])
+        .map_err(|_| invalid_response("response headers are not UTF-8"))?;
+    let body = &raw[head_end + 4..];
+
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.split(' ').nth(1))
+        .and_then(|code| http::StatusCode::from_bytes(code.as_bytes()).ok())
+        .ok_or_else(|| invalid_response("malformed status line"))?;
+
+    let mut builder = http::Response::builder().status(status);
+    let mut chunked = false;
+    let mut content_length = None;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (name, value) = (name.trim(), value.trim());
+        if name.eq_ignore_ascii_case("transfer-encoding") {
+            chunked = value.eq_ignore_ascii_case("chunked");
+        } else if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse::<usize>().ok();
+        }
+        if HOP_BY_HOP_HEADERS
+            .iter()
+            .any(|header| name.eq_ignore_ascii_case(header))
+        {
+            continue;
+        }
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            builder = builder.header(name, value);
+        }
+    }
+
+    let body = if chunked {
+        decode_chunked(body)?
+    } else {
+        match content_length {
+            Some(len) => body.get(..len).unwrap_or(body).to_vec(),
+            None => body.to_vec(),
+        }
+    };
+    builder.body(body).map_err(io::Error::other)
+}
+
+fn decode_chunked(mut raw: &[u8]) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let line_end = raw
+            .windows(2)
+            .position(|window| window == b"\r\n")
+            .ok_or_else(|| invalid_response("truncated chunk size"))?;
+        let size = std::str::from_utf8(&raw[..line_end])
+            .ok()
+            .map(|line| line.split(';').next().unwrap_or("").trim())
+            .and_then(|size| usize::from_str_radix(size, 16).ok())
+            .ok_or_else(|| invalid_response("malformed chunk size"))?;
+        raw = &raw[line_end + 2..];
+        if size == 0 {
+            return Ok(body);
+        }
+        let chunk = raw
+            .get(..size)
+            .ok_or_else(|| invalid_response("truncated chunk"))?;
+        body.extend_from_slice(chunk);
+        raw = raw.get(size + 2..).unwrap_or_default();
+    }
+}
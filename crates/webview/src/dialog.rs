@@ -0,0 +1,232 @@
+//! Native dialogs for the page.
+//!
+//! The `plugin:dialog|open`, `save`, `message`, `ask` and `confirm` commands take the payloads
+//! `@tauri-apps/plugin-dialog` sends, so its functions of the same names work. The dialogs are
+//! the ones of gpui: they belong to the window of the invoking webview, and file dialogs ignore
+//! `filters` and, when opening, `defaultPath`. A dialog blocks the invoke, on an IPC worker,
+//! until the user answers it. The commands need the
+//! [`crate::permissions::Capability::Fs`] permission when the webview has
+//! [`crate::Builder::permissions`].
+
+use std::fmt::Display;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use gpui::{App, PathPromptOptions, PromptButton, PromptLevel, Window};
+use serde::Deserialize;
+
+use crate::ipc;
+use crate::manager::WebviewManager;
+
+pub const OPEN_COMMAND: &str = "plugin:dialog|open";
+pub const SAVE_COMMAND: &str = "plugin:dialog|save";
+pub const MESSAGE_COMMAND: &str = "plugin:dialog|message";
+pub const ASK_COMMAND: &str = "plugin:dialog|ask";
+pub const CONFIRM_COMMAND: &str = "plugin:dialog|confirm";
+
+#[derive(Debug, Default, Deserialize)]
+struct OptionsPayload<T> {
+    #[serde(default)]
+    options: T,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OpenOptions {
+    title: Option<String>,
+    #[serde(default)]
+    multiple: bool,
+    #[serde(default)]
+    directory: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SaveOptions {
+    default_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum MessageKind {
+    #[default]
+    Info,
+    Warning,
+    Error,
+}
+
+impl From<MessageKind> for PromptLevel {
+    fn from(kind: MessageKind) -> Self {
+        match kind {
+            MessageKind::Info => PromptLevel::Info,
+            MessageKind::Warning => PromptLevel::Warning,
+            MessageKind::Error => PromptLevel::Critical,
+        }
+    }
+}
+
+/// The payload of `message`, `ask` and `confirm`; each reads the button labels it has.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MessagePayload {
+    message: String,
+    title: Option<String>,
+    #[serde(default)]
+    kind: MessageKind,
+    ok_button_label: Option<String>,
+    cancel_button_label: Option<String>,
+    yes_button_label: Option<String>,
+    no_button_label: Option<String>,
+}
+
+fn parse_payload<T: serde::de::DeserializeOwned>(
+    request: &http::Request<Vec<u8>>,
+    command: &str,
+) -> Result<T, String> {
+    let body = request.body();
+    let body = if body.is_empty() { b"{}" } else { &body[..] };
+    serde_json::from_slice(body).map_err(|err| format!("invalid JSON body for {command}: {err}"))
+}
+
+/// Shows the dialog `show` opens in the window of the invoking webview and waits for the user
+/// to answer it.
+fn show_dialog<F, T, E>(
+    show: impl FnOnce(&mut Window, &mut App) -> F + Send + 'static,
+) -> Result<T, String>
+where
+    F: Future<Output = Result<T, E>> + Send + 'static,
+    E: Display,
+{
+    if WebviewManager::global().is_main_thread() {
+        return Err("dialogs can't be awaited on the main thread".to_string());
+    }
+    let Some(label) = ipc::current_webview_label() else {
+        return Err("dialogs must be invoked from a webview".to_string());
+    };
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    WebviewManager::global().update_window(&label, move |window, cx| {
+        let _ = tx.send(show(window, cx));
+    })?;
+    let answer = rx
+        .recv()
+        .map_err(|_| format!("the window of webview `{label}` is gone"))?;
+    pollster::block_on(answer).map_err(|err| format!("the dialog was dismissed: {err}"))
+}
+
+/// Shows the message of `payload` with `buttons` and answers the index of the one picked.
+fn prompt(payload: MessagePayload, buttons: Vec<PromptButton>) -> Result<usize, String> {
+    show_dialog(move |window, cx| {
+        let (message, detail) = match &payload.title {
+            Some(title) => (title.as_str(), Some(payload.message.as_str())),
+            None => (payload.message.as_str(), None),
+        };
+        window.prompt(payload.kind.into(), message, detail, &buttons, cx)
+    })
+}
+
+pub(crate) fn open(request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let options = match parse_payload::<OptionsPayload<OpenOptions>>(&request, OPEN_COMMAND) {
+        Ok(payload) => payload.options,
+        Err(err) => return ipc::bad_request(err),
+    };
+    let multiple = options.multiple;
+    let paths = show_dialog(move |_, cx| {
+        cx.prompt_for_paths(PathPromptOptions {
+            files: !options.directory,
+            directories: options.directory,
+            multiple,
+            prompt: options.title.map(Into::into),
+        })
+    })
+    .and_then(|paths| paths.map_err(|err| err.to_string()));
+
+    match paths {
+        Ok(Some(paths)) if multiple => ipc::ok_json(&paths),
+        Ok(paths) => ipc::ok_json(&paths.and_then(|paths| paths.into_iter().next())),
+        Err(err) => ipc::internal_error(err),
+    }
+}
+
+pub(crate) fn save(request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let options = match parse_payload::<OptionsPayload<SaveOptions>>(&request, SAVE_COMMAND) {
+        Ok(payload) => payload.options,
+        Err(err) => return ipc::bad_request(err),
+    };
+    let (directory, suggested_name) = split_default_path(options.default_path);
+    let path =
+        show_dialog(move |_, cx| cx.prompt_for_new_path(&directory, suggested_name.as_deref()))
+            .and_then(|path| path.map_err(|err| err.to_string()));
+
+    match path {
+        Ok(path) => ipc::ok_json(&path),
+        Err(err) => ipc::internal_error(err),
+    }
+}
+
+/// The directory a save dialog starts in and the file name it suggests, from the `defaultPath`
+/// of the page: a directory, a file name, or a path to a file.
+fn split_default_path(default_path: Option<PathBuf>) -> (PathBuf, Option<String>) {
+    let current_dir = || std::env::current_dir().unwrap_or_default();
+    let Some(path) = default_path else {
+        return (current_dir(), None);
+    };
+    if path.is_dir() {
+        return (path, None);
+    }
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned());
+    let directory = match path.parent() {
+        Some(parent) if parent != Path::new("") => parent.to_path_buf(),
+        _ => current_dir(),
+    };
+    (directory, name)
+}
+
+pub(crate) fn message(request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let mut payload = match parse_payload::<MessagePayload>(&request, MESSAGE_COMMAND) {
+        Ok(payload) => payload,
+        Err(err) => return ipc::bad_request(err),
+    };
+    let ok = payload.ok_button_label.take().unwrap_or("OK".into());
+    match prompt(payload, vec![PromptButton::ok(ok)]) {
+        Ok(_) => ipc::ok_json(&()),
+        Err(err) => ipc::internal_error(err),
+    }
+}
+
+pub(crate) fn ask(request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let mut payload = match parse_payload::<MessagePayload>(&request, ASK_COMMAND) {
+        Ok(payload) => payload,
+        Err(err) => return ipc::bad_request(err),
+    };
+    let yes = payload.yes_button_label.take().unwrap_or("Yes".into());
+    let no = payload.no_button_label.take().unwrap_or("No".into());
+    match prompt(
+        payload,
+        vec![PromptButton::ok(yes), PromptButton::cancel(no)],
+    ) {
+        Ok(answer) => ipc::ok_json(&(answer == 0)),
+        Err(err) => ipc::internal_error(err),
+    }
+}
+
+pub(crate) fn confirm(request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let mut payload = match parse_payload::<MessagePayload>(&request, CONFIRM_COMMAND) {
+        Ok(payload) => payload,
+        Err(err) => return ipc::bad_request(err),
+    };
+    let ok = payload.ok_button_label.take().unwrap_or("OK".into());
+    let cancel = payload
+        .cancel_button_label
+        .take()
+        .unwrap_or("Cancel".into());
+    match prompt(
+        payload,
+        vec![PromptButton::ok(ok), PromptButton::cancel(cancel)],
+    ) {
+        Ok(answer) => ipc::ok_json(&(answer == 0)),
+        Err(err) => ipc::internal_error(err),
+    }
+}
@@ -0,0 +1,60 @@
+//! Files dragged onto a webview; see [`crate::Builder::on_file_drop`].
+//!
+//! The page gets the `tauri://drag-enter`, `tauri://drag-over`, `tauri://drag-drop` and
+//! `tauri://drag-leave` events `@tauri-apps/api/webview` listens for, so its `onDragDropEvent`
+//! works, as does the one of `@manos/api`. Positions are relative to the top-left corner of the
+//! webview.
+
+use std::path::PathBuf;
+
+use serde_json::json;
+use wry::DragDropEvent;
+
+use crate::events;
+
+pub const DRAG_ENTER_EVENT: &str = "tauri://drag-enter";
+pub const DRAG_OVER_EVENT: &str = "tauri://drag-over";
+pub const DRAG_DROP_EVENT: &str = "tauri://drag-drop";
+pub const DRAG_LEAVE_EVENT: &str = "tauri://drag-leave";
+
+pub(crate) type FileDropHandler = Box<dyn Fn(&[PathBuf], (i32, i32)) + 'static>;
+
+/// The wry drag-drop handler of the webview `label`: sends the events to the page and calls
+/// `handlers` with dropped files.
+pub(crate) fn handler(
+    label: String,
+    handlers: Vec<FileDropHandler>,
+) -> impl Fn(DragDropEvent) -> bool + 'static {
+    move |event| {
+        let (name, payload) = match &event {
+            DragDropEvent::Enter { paths, position } => (
+                DRAG_ENTER_EVENT,
+                json!({ "paths": paths, "position": position_json(*position) }),
+            ),
+            DragDropEvent::Over { position } => (
+                DRAG_OVER_EVENT,
+                json!({ "position": position_json(*position) }),
+            ),
+            DragDropEvent::Drop { paths, position } => (
+                DRAG_DROP_EVENT,
+                json!({ "paths": paths, "position": position_json(*position) }),
+            ),
+            DragDropEvent::Leave => (DRAG_LEAVE_EVENT, serde_json::Value::Null),
+            _ => return false,
+        };
+        if let Err(err) = events::emit_to(&label, name, payload) {
+            tracing::warn!("cannot send `{name}` to webview `{label}`: {err}");
+        }
+        if let DragDropEvent::Drop { paths, position } = &event {
+            for handler in &handlers {
+                handler(paths, *position);
+            }
+        }
+        // The app takes the files, so the webview must not open them itself.
+        true
+    }
+}
+
+fn position_json((x, y): (i32, i32)) -> serde_json::Value {
+    json!({ "x": x, "y": y })
+}
@@ -0,0 +1,107 @@
+//! Frontend assets compiled into the executable; see [`crate::embed_assets!`] and
+//! [`crate::Builder::serve_embedded`].
+//!
+//! Paths are relative to the embedded directory and use `/` on every platform. Requests resolve
+//! like [`crate::Builder::serve_static`]: `/about` tries `about`, `about.html` and
+//! `about/index.html`, then `index.html` as [`crate::Builder::index_fallback`] allows.
+
+use http::header::{ACCESS_CONTROL_ALLOW_ORIGIN, CONTENT_TYPE};
+
+use crate::IndexFallback;
+
+/// The files of a directory, embedded by [`crate::embed_assets!`].
+#[derive(Clone, Copy, Debug)]
+pub struct EmbeddedAssets {
+    /// Sorted by path.
+    files: &'static [(&'static str, &'static [u8])],
+}
+
+impl EmbeddedAssets {
+    #[doc(hidden)]
+    pub const fn new(files: &'static [(&'static str, &'static [u8])]) -> Self {
+        Self { files }
+    }
+
+    pub fn get(&self, path: &str) -> Option<&'static [u8]> {
+        self.files
+            .binary_search_by(|(file, _)| (*file).cmp(path))
+            .ok()
+            .map(|ix| self.files[ix].1)
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.files.iter().map(|(path, _)| *path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// The path and contents of the file that answers a request for `uri_path`.
+    fn resolve(
+        &self,
+        uri_path: &str,
+        index_fallback: IndexFallback,
+    ) -> Result<Option<(String, &'static [u8])>, ()> {
+        let decoded = crate::decode_uri_component(uri_path);
+        let mut parts = Vec::new();
+        for part in decoded.split('/') {
+            match part {
+                "" | "." => {}
+                ".." => return Err(()),
+                part => parts.push(part),
+            }
+        }
+        let relative = parts.join("/");
+
+        let mut candidates = if relative.is_empty() {
+            vec!["index.html".to_string()]
+        } else {
+            vec![
+                relative.clone(),
+                format!("{relative}.html"),
+                format!("{relative}/index.html"),
+            ]
+        };
+        let file_name = parts.last().copied().unwrap_or_default();
+        if index_fallback.covers(file_name.contains('.')) {
+            candidates.push("index.html".to_string());
+        }
+        Ok(candidates
+            .into_iter()
+            .find_map(|candidate| Some((candidate.clone(), self.get(&candidate)?))))
+    }
+
+    pub(crate) fn respond(
+        &self,
+        request: http::Request<Vec<u8>>,
+        index_fallback: IndexFallback,
+    ) -> http::Response<Vec<u8>> {
+        let path = request.uri().path();
+        match self.resolve(path, index_fallback) {
+            Ok(Some((file, bytes))) => http::Response::builder()
+                .status(http::StatusCode::OK)
+                .header(
+                    CONTENT_TYPE,
+                    mime_guess::from_path(&file)
+                        .first_or_octet_stream()
+                        .essence_str(),
+                )
+                .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+                .body(bytes.to_vec())
+                .unwrap(),
+            Ok(None) => {
+                tracing::debug!("embedded asset not found: {path}");
+                crate::response_not_found(path)
+            }
+            Err(()) => {
+                tracing::warn!("attempt to read outside embedded assets: {path}");
+                crate::response_forbidden(path)
+            }
+        }
+    }
+}
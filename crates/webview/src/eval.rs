@@ -0,0 +1,160 @@
+//! Evaluating JavaScript and getting its value back; see
+//! [`crate::manager::WebviewManager::eval_with_result`].
+//!
+//! The expression runs in an `async` function, so a promise is awaited. Its value, or the error
+//! it throws, comes back through the `plugin:__MANOS_EVAL__|result` command, which only
+//! resolves evaluations of the webview that sends it.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+use crate::ipc;
+use crate::timer::{self, TimerHandle};
+
+pub const RESULT_COMMAND: &str = "plugin:__MANOS_EVAL__|result";
+
+/// How long an evaluation may take before its [`ScriptResult`] fails, e.g. because the page
+/// reloaded or the script doesn't parse.
+pub const EVAL_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Default)]
+struct State {
+    result: Option<Result<serde_json::Value, String>>,
+    waker: Option<Waker>,
+}
+
+struct Pending {
+    label: String,
+    state: Arc<Mutex<State>>,
+    timer: Option<TimerHandle>,
+}
+
+static PENDING: Mutex<BTreeMap<u64, Pending>> = Mutex::new(BTreeMap::new());
+
+/// The value of an evaluated expression, deserialized as `T`; fails with the message of the
+/// error the script threw.
+///
+/// Don't block the main thread on it: the webview needs the main thread to answer. Await it on
+/// an executor instead, e.g. in `cx.spawn`.
+#[must_use = "the value is only available by awaiting the result"]
+pub struct ScriptResult<T> {
+    state: Arc<Mutex<State>>,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> Future for ScriptResult<T> {
+    type Output = Result<T, String>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result.and_then(|value| {
+                serde_json::from_value(value)
+                    .map_err(|err| format!("unexpected value of the script: {err}"))
+            })),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Evaluates `js` in the webview `label` and returns its value once the page sends it.
+pub(crate) fn evaluate<T: DeserializeOwned>(label: &str, js: &str) -> ScriptResult<T> {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let state = Arc::new(Mutex::new(State::default()));
+    PENDING.lock().unwrap().insert(
+        id,
+        Pending {
+            label: label.to_string(),
+            state: state.clone(),
+            timer: None,
+        },
+    );
+    let timer = timer::schedule(Instant::now() + EVAL_TIMEOUT, move || {
+        finish(
+            id,
+            Err(format!("the script gave no value within {EVAL_TIMEOUT:?}")),
+        );
+    });
+    match PENDING.lock().unwrap().get_mut(&id) {
+        Some(pending) => pending.timer = Some(timer),
+        None => timer.cancel(),
+    }
+
+    let script = format!(
+        "(async () => (\n{js}\n))().then(
+  (value) => window.__TAURI_INTERNALS__.invoke('{RESULT_COMMAND}', {{ id: {id}, value: value === undefined ? null : value }}),
+  (error) => window.__TAURI_INTERNALS__.invoke('{RESULT_COMMAND}', {{ id: {id}, error: String(error) }})
+).catch((error) => window.__TAURI_INTERNALS__.invoke('{RESULT_COMMAND}', {{ id: {id}, error: String(error) }}))"
+    );
+    if let Err(err) = crate::manager::WebviewManager::global().eval(label, script) {
+        finish(id, Err(err));
+    }
+
+    ScriptResult {
+        state,
+        _value: PhantomData,
+    }
+}
+
+fn finish(id: u64, result: Result<serde_json::Value, String>) {
+    let Some(pending) = PENDING.lock().unwrap().remove(&id) else {
+        return;
+    };
+    if let Some(timer) = pending.timer {
+        timer.cancel();
+    }
+    let mut state = pending.state.lock().unwrap();
+    state.result = Some(result);
+    if let Some(waker) = state.waker.take() {
+        waker.wake();
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ResultPayload {
+    id: u64,
+    #[serde(default)]
+    value: serde_json::Value,
+    error: Option<String>,
+}
+
+pub(crate) fn result(request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let payload: ResultPayload = match serde_json::from_slice(request.body()) {
+        Ok(payload) => payload,
+        Err(err) => {
+            return ipc::bad_request(format!("invalid JSON body for {RESULT_COMMAND}: {err}"));
+        }
+    };
+    let label = ipc::current_webview_label();
+    let owned = PENDING
+        .lock()
+        .unwrap()
+        .get(&payload.id)
+        .is_some_and(|pending| Some(&pending.label) == label.as_ref());
+    if !owned {
+        return ipc::bad_request(format!("no evaluation {} is waiting", payload.id));
+    }
+
+    finish(
+        payload.id,
+        match payload.error {
+            Some(error) => Err(error),
+            None => Ok(payload.value),
+        },
+    );
+    ipc::ok_json(&())
+}
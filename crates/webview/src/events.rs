@@ -0,0 +1,285 @@
+//! Events between Rust and the page.
+//!
+//! `plugin:event|listen`, `unlisten`, `emit` and `emit_to` take the payloads `@manos/api` and
+//! `@tauri-apps/api/event` send. The listeners of a page live in the page: Rust only hands out
+//! their ids, and [`emit_to`] runs the listeners of a webview whose target is `Any` or names the
+//! webview. The listeners of Rust, registered with [`listen`] or
+//! [`crate::webview::WebView::listen`], get the events pages emit.
+
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::ipc;
+use crate::manager::WebviewManager;
+
+pub const LISTEN_COMMAND: &str = "plugin:event|listen";
+pub const UNLISTEN_COMMAND: &str = "plugin:event|unlisten";
+pub const EMIT_COMMAND: &str = "plugin:event|emit";
+pub const EMIT_TO_COMMAND: &str = "plugin:event|emit_to";
+
+/// The page function that runs listeners; see [`crate::event_initialization_script`].
+pub(crate) const LISTENERS_FUNCTION: &str = "__internal_unstable_listeners_function_id__";
+/// The page object that holds the listeners by event and id.
+pub(crate) const LISTENERS_OBJECT: &str = "__internal_unstable_listeners_object_id__";
+
+/// Sends `event` with `payload` to the listeners of the webview `label`. Can be called from any
+/// thread; the listeners run once the main thread gets to them.
+pub fn emit_to<T: Serialize>(label: &str, event: &str, payload: T) -> Result<(), String> {
+    if !is_valid_event_name(event) {
+        return Err(format!("`{event}` is not a valid event name"));
+    }
+    let data = serde_json::to_string(&EventData { event, payload })
+        .map_err(|err| format!("cannot serialize the payload of `{event}`: {err}"))?;
+    let js = format!(
+        "(function () {{
+  const listeners = (window['{LISTENERS_OBJECT}'] && window['{LISTENERS_OBJECT}'][{event}]) || {{}}
+  const ids = Object.keys(listeners).map(Number).filter((id) => {{
+    const target = listeners[id].target
+    return target.kind === 'Any' || target.label === {label}
+  }})
+  window['{LISTENERS_FUNCTION}']({data}, ids)
+}})()",
+        event = js_string(event),
+        label = js_string(label),
+    );
+    WebviewManager::global().eval(label, js)
+}
+
+/// Sends `event` with `payload` to the listeners of every open webview.
+pub fn emit<T: Serialize>(event: &str, payload: T) -> Result<(), String> {
+    let payload = serde_json::to_value(payload)
+        .map_err(|err| format!("cannot serialize the payload of `{event}`: {err}"))?;
+    for label in WebviewManager::global().labels() {
+        emit_to(&label, event, &payload)?;
+    }
+    Ok(())
+}
+
+/// Identifies a Rust listener, to remove it with [`unlisten`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ListenerId(u64);
+
+struct Listener {
+    id: ListenerId,
+    event: String,
+    /// The webview whose events the listener gets, or `None` for all of them.
+    webview: Option<String>,
+    handler: Arc<dyn Fn(serde_json::Value) + Send + Sync + 'static>,
+}
+
+static LISTENERS: Mutex<Vec<Listener>> = Mutex::new(Vec::new());
+
+/// Calls `handler` with the payload of `event` whenever a page emits it, deserialized as `T`.
+/// Payloads that don't deserialize are logged and dropped. The handler runs on the thread of
+/// the invoke that emitted the event.
+///
+/// ```ignore
+/// events::listen("save-requested", |document: Document| save(document));
+/// ```
+pub fn listen<T, F>(event: impl Into<String>, handler: F) -> ListenerId
+where
+    T: DeserializeOwned,
+    F: Fn(T) + Send + Sync + 'static,
+{
+    add_listener(event.into(), None, handler)
+}
+
+pub(crate) fn add_listener<T, F>(event: String, webview: Option<String>, handler: F) -> ListenerId
+where
+    T: DeserializeOwned,
+    F: Fn(T) + Send + Sync + 'static,
+{
+    static NEXT_LISTENER_ID: AtomicU64 = AtomicU64::new(0);
+
+    let id = ListenerId(NEXT_LISTENER_ID.fetch_add(1, Ordering::Relaxed));
+    let name = event.clone();
+    let handler = move |payload| match serde_json::from_value::<T>(payload) {
+        Ok(payload) => handler(payload),
+        Err(err) => tracing::warn!("dropped `{name}` with an unexpected payload: {err}"),
+    };
+    LISTENERS.lock().unwrap().push(Listener {
+        id,
+        event,
+        webview,
+        handler: Arc::new(handler),
+    });
+    id
+}
+
+/// Removes a listener registered with [`listen`]; does nothing if it is already gone.
+pub fn unlisten(id: ListenerId) {
+    LISTENERS
+        .lock()
+        .unwrap()
+        .retain(|listener| listener.id != id);
+}
+
+/// Runs the Rust listeners of `event` that get the events of the webview `label`.
+fn run_listeners(label: Option<&str>, event: &str, payload: &serde_json::Value) {
+    let handlers: Vec<_> = LISTENERS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|listener| {
+            listener.event == event
+                && (listener.webview.is_none() || listener.webview.as_deref() == label)
+        })
+        .map(|listener| listener.handler.clone())
+        .collect();
+    // Without the lock, so handlers can add and remove listeners.
+    for handler in handlers {
+        handler(payload.clone());
+    }
+}
+
+#[derive(Serialize)]
+struct EventData<'a, T> {
+    event: &'a str,
+    payload: T,
+}
+
+/// Event names are made of alphanumeric characters, `-`, `/`, `:` and `_`, as in Tauri.
+fn is_valid_event_name(event: &str) -> bool {
+    !event.is_empty()
+        && event
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '/' | ':' | '_'))
+}
+
+fn js_string(value: &str) -> String {
+    serde_json::to_string(value).expect("strings serialize")
+}
+
+#[derive(Debug, Deserialize)]
+struct ListenPayload {
+    event: String,
+    target: serde_json::Value,
+    handler: u32,
+}
+
+pub(crate) fn listen_from_page(request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    static NEXT_EVENT_ID: AtomicU32 = AtomicU32::new(0);
+
+    let payload: ListenPayload = match serde_json::from_slice(request.body()) {
+        Ok(payload) => payload,
+        Err(err) => {
+            return ipc::bad_request(format!("invalid JSON body for {LISTEN_COMMAND}: {err}"));
+        }
+    };
+    if !is_valid_event_name(&payload.event) {
+        return ipc::bad_request(format!("`{}` is not a valid event name", payload.event));
+    }
+    let Some(label) = ipc::current_webview_label() else {
+        return ipc::bad_request("events must be listened to from a webview");
+    };
+
+    let event_id = NEXT_EVENT_ID.fetch_add(1, Ordering::Relaxed);
+    let js = format!(
+        "(function () {{
+  const listeners = window['{LISTENERS_OBJECT}'] || (window['{LISTENERS_OBJECT}'] = Object.create(null))
+  const forEvent = listeners[{event}] || (listeners[{event}] = Object.create(null))
+  forEvent[{event_id}] = {{ target: {target}, handlerId: {handler} }}
+}})()",
+        event = js_string(&payload.event),
+        target = payload.target,
+        handler = payload.handler,
+    );
+    match WebviewManager::global().eval(&label, js) {
+        Ok(()) => ipc::ok_json(&event_id),
+        Err(err) => ipc::internal_error(err),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UnlistenPayload {
+    event: String,
+    event_id: u32,
+}
+
+pub(crate) fn unlisten_from_page(request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let payload: UnlistenPayload = match serde_json::from_slice(request.body()) {
+        Ok(payload) => payload,
+        Err(err) => {
+            return ipc::bad_request(format!("invalid JSON body for {UNLISTEN_COMMAND}: {err}"));
+        }
+    };
+    let Some(label) = ipc::current_webview_label() else {
+        return ipc::bad_request("events must be unlistened from a webview");
+    };
+
+    let js = format!(
+        "(function () {{
+  const listeners = window['{LISTENERS_OBJECT}'] && window['{LISTENERS_OBJECT}'][{event}]
+  if (listeners) delete listeners[{event_id}]
+}})()",
+        event = js_string(&payload.event),
+        event_id = payload.event_id,
+    );
+    match WebviewManager::global().eval(&label, js) {
+        Ok(()) => ipc::ok_json(&()),
+        Err(err) => ipc::internal_error(err),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct EmitPayload {
+    event: String,
+    /// `{ kind, label }`, as `@tauri-apps/api/event` sends it; `emit` has none.
+    #[serde(default)]
+    target: Option<serde_json::Value>,
+    #[serde(default)]
+    payload: serde_json::Value,
+}
+
+/// `emit` goes to the Rust listeners of the emitting webview and to every page.
+pub(crate) fn emit_from_page(request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let payload: EmitPayload = match serde_json::from_slice(request.body()) {
+        Ok(payload) => payload,
+        Err(err) => {
+            return ipc::bad_request(format!("invalid JSON body for {EMIT_COMMAND}: {err}"));
+        }
+    };
+    if !is_valid_event_name(&payload.event) {
+        return ipc::bad_request(format!("`{}` is not a valid event name", payload.event));
+    }
+    let label = ipc::current_webview_label();
+    run_listeners(label.as_deref(), &payload.event, &payload.payload);
+    match emit(&payload.event, &payload.payload) {
+        Ok(()) => ipc::ok_json(&()),
+        Err(err) => ipc::internal_error(err),
+    }
+}
+
+/// `emit_to` goes to the Rust listeners and the page of the target webview.
+pub(crate) fn emit_to_from_page(request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let payload: EmitPayload = match serde_json::from_slice(request.body()) {
+        Ok(payload) => payload,
+        Err(err) => {
+            return ipc::bad_request(format!("invalid JSON body for {EMIT_TO_COMMAND}: {err}"));
+        }
+    };
+    if !is_valid_event_name(&payload.event) {
+        return ipc::bad_request(format!("`{}` is not a valid event name", payload.event));
+    }
+    let target = payload
+        .target
+        .as_ref()
+        .and_then(|target| target.get("label"))
+        .and_then(|label| label.as_str());
+    let Some(target) = target else {
+        // `{ kind: "Any" }` and the like reach everyone, as `emit` does.
+        return emit_from_page(request);
+    };
+    run_listeners(Some(target), &payload.event, &payload.payload);
+    if !WebviewManager::global().contains(target) {
+        return ipc::ok_json(&());
+    }
+    match emit_to(target, &payload.event, &payload.payload) {
+        Ok(()) => ipc::ok_json(&()),
+        Err(err) => ipc::internal_error(err),
+    }
+}
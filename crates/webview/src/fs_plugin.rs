@@ -0,0 +1,314 @@
+//! File IO for the page, limited to the directories of an [`FsScope`]; see
+//! [`crate::Builder::fs`].
+//!
+//! The `plugin:fs|read_text_file`, `write_text_file`, `read_dir`, `exists`, `remove` and
+//! `copy_file` commands take the payloads `@tauri-apps/plugin-fs` sends, so its functions of the
+//! same names work. A relative path is resolved against the first directory of the scope; a path
+//! that leaves every directory of the scope, also through a symlink, is rejected with `403`. The
+//! commands need the [`crate::permissions::Capability::Fs`] permission when the webview has
+//! [`crate::Builder::permissions`].
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use http::header::CONTENT_TYPE;
+use serde::{Deserialize, Serialize};
+
+use crate::{ApiHandler, ipc};
+
+pub const READ_TEXT_FILE_COMMAND: &str = "plugin:fs|read_text_file";
+pub const WRITE_TEXT_FILE_COMMAND: &str = "plugin:fs|write_text_file";
+pub const READ_DIR_COMMAND: &str = "plugin:fs|read_dir";
+pub const EXISTS_COMMAND: &str = "plugin:fs|exists";
+pub const REMOVE_COMMAND: &str = "plugin:fs|remove";
+pub const COPY_FILE_COMMAND: &str = "plugin:fs|copy_file";
+
+/// The directories the page may read and write in, with everything below them.
+#[derive(Clone, Debug, Default)]
+pub struct FsScope {
+    dirs: Vec<PathBuf>,
+}
+
+impl FsScope {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows `dir` and everything below it. Directories that don't exist when a command runs
+    /// allow nothing.
+    pub fn allow(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dirs.push(dir.into());
+        self
+    }
+
+    pub fn dirs(&self) -> &[PathBuf] {
+        &self.dirs
+    }
+
+    /// The absolute, symlink-free form of `path` if it is in the scope. `path` itself doesn't
+    /// need to exist, so that files can be created.
+    pub fn resolve(&self, path: impl AsRef<Path>) -> Result<PathBuf, String> {
+        let path = path.as_ref();
+        let absolute = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            let Some(base) = self.dirs.first() else {
+                return Err("the fs scope allows no directory".to_string());
+            };
+            base.join(path)
+        };
+        let resolved = canonicalize_lenient(&absolute)
+            .ok_or_else(|| format!("`{}` is not a valid path", path.display()))?;
+
+        let allowed = self
+            .dirs
+            .iter()
+            .filter_map(|dir| fs::canonicalize(dir).ok())
+            .any(|dir| resolved.starts_with(dir));
+        if allowed {
+            Ok(resolved)
+        } else {
+            Err(format!("`{}` is outside the fs scope", path.display()))
+        }
+    }
+}
+
+/// Canonicalizes the longest existing ancestor of `path` and appends the rest. `None` when the
+/// missing rest steps up with `..`.
+fn canonicalize_lenient(path: &Path) -> Option<PathBuf> {
+    let mut missing = Vec::new();
+    let mut existing = path;
+    let base = loop {
+        match fs::canonicalize(existing) {
+            Ok(base) => break base,
+            Err(_) => {
+                missing.push(existing.file_name()?);
+                existing = existing.parent()?;
+            }
+        }
+    };
+    Some(
+        missing
+            .into_iter()
+            .rev()
+            .fold(base, |path, part| path.join(part)),
+    )
+}
+
+#[derive(Debug, Deserialize)]
+struct PathPayload {
+    path: PathBuf,
+    #[serde(default)]
+    options: PathOptions,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PathOptions {
+    #[serde(default)]
+    recursive: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CopyFilePayload {
+    from_path: PathBuf,
+    to_path: PathBuf,
+}
+
+/// An entry of a directory as `readDir` returns it.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DirEntry {
+    name: String,
+    is_directory: bool,
+    is_file: bool,
+    is_symlink: bool,
+}
+
+fn parse_payload<T: serde::de::DeserializeOwned>(
+    request: &http::Request<Vec<u8>>,
+    command: &str,
+) -> Result<T, String> {
+    serde_json::from_slice(request.body())
+        .map_err(|err| format!("invalid JSON body for {command}: {err}"))
+}
+
+fn io_error(path: &Path, err: io::Error) -> http::Response<Vec<u8>> {
+    ipc::internal_error(format!("`{}`: {err}", path.display()))
+}
+
+type Handler = fn(&FsScope, http::Request<Vec<u8>>) -> http::Response<Vec<u8>>;
+
+/// The fs commands, all limited to `scope`.
+pub(crate) fn handlers(scope: FsScope) -> Vec<(&'static str, ApiHandler)> {
+    let scope = Arc::new(scope);
+    let commands: [(&str, Handler); 6] = [
+        (READ_TEXT_FILE_COMMAND, read_text_file),
+        (WRITE_TEXT_FILE_COMMAND, write_text_file),
+        (READ_DIR_COMMAND, read_dir),
+        (EXISTS_COMMAND, exists),
+        (REMOVE_COMMAND, remove),
+        (COPY_FILE_COMMAND, copy_file),
+    ];
+    commands
+        .into_iter()
+        .map(|(command, handler)| {
+            let scope = scope.clone();
+            let handler: ApiHandler = Arc::new(move |request| handler(&scope, request));
+            (command, handler)
+        })
+        .collect()
+}
+
+/// Answers with the UTF-8 bytes of the file, which `readTextFile` decodes.
+fn read_text_file(scope: &FsScope, request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let payload: PathPayload = match parse_payload(&request, READ_TEXT_FILE_COMMAND) {
+        Ok(payload) => payload,
+        Err(err) => return ipc::bad_request(err),
+    };
+    let path = match scope.resolve(&payload.path) {
+        Ok(path) => path,
+        Err(err) => return ipc::forbidden(err),
+    };
+    match fs::read_to_string(&path) {
+        Ok(text) => ipc::respond(ipc::Response::binary(text)),
+        Err(err) => io_error(&path, err),
+    }
+}
+
+/// Takes the contents as the body and the path in the `path` header, percent-encoded, as
+/// `writeTextFile` sends them.
+fn write_text_file(scope: &FsScope, request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let Some(path) = request
+        .headers()
+        .get("path")
+        .and_then(|value| value.to_str().ok())
+    else {
+        return ipc::bad_request(format!("missing path header for {WRITE_TEXT_FILE_COMMAND}"));
+    };
+    let path = crate::decode_uri_component(path);
+    let path = match scope.resolve(&path) {
+        Ok(path) => path,
+        Err(err) => return ipc::forbidden(err),
+    };
+    let is_binary = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/octet-stream"));
+    let contents = if is_binary {
+        request.into_body()
+    } else {
+        // The postMessage fallback sends the bytes as a JSON array.
+        match serde_json::from_slice::<Vec<u8>>(request.body()) {
+            Ok(contents) => contents,
+            Err(err) => return ipc::bad_request(format!("invalid file contents: {err}")),
+        }
+    };
+    if std::str::from_utf8(&contents).is_err() {
+        return ipc::bad_request("file contents must be UTF-8");
+    }
+    match fs::write(&path, contents) {
+        Ok(()) => ipc::ok_json(&()),
+        Err(err) => io_error(&path, err),
+    }
+}
+
+fn read_dir(scope: &FsScope, request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let payload: PathPayload = match parse_payload(&request, READ_DIR_COMMAND) {
+        Ok(payload) => payload,
+        Err(err) => return ipc::bad_request(err),
+    };
+    let path = match scope.resolve(&payload.path) {
+        Ok(path) => path,
+        Err(err) => return ipc::forbidden(err),
+    };
+    let entries = fs::read_dir(&path).and_then(|entries| {
+        entries
+            .map(|entry| {
+                let entry = entry?;
+                let file_type = entry.file_type()?;
+                // Follow symlinks for the kind, like `readDir` does.
+                let metadata = fs::metadata(entry.path()).ok();
+                Ok(DirEntry {
+                    name: entry.file_name().to_string_lossy().into_owned(),
+                    is_directory: metadata.as_ref().is_some_and(|metadata| metadata.is_dir()),
+                    is_file: metadata.as_ref().is_some_and(|metadata| metadata.is_file()),
+                    is_symlink: file_type.is_symlink(),
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()
+    });
+    match entries {
+        Ok(mut entries) => {
+            entries.sort_by(|a, b| a.name.cmp(&b.name));
+            ipc::ok_json(&entries)
+        }
+        Err(err) => io_error(&path, err),
+    }
+}
+
+fn exists(scope: &FsScope, request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let payload: PathPayload = match parse_payload(&request, EXISTS_COMMAND) {
+        Ok(payload) => payload,
+        Err(err) => return ipc::bad_request(err),
+    };
+    match scope.resolve(&payload.path) {
+        Ok(path) => ipc::ok_json(&path.exists()),
+        Err(err) => ipc::forbidden(err),
+    }
+}
+
+/// Removes a file or an empty directory, or a directory with its contents when
+/// `options.recursive` is set.
+fn remove(scope: &FsScope, request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let payload: PathPayload = match parse_payload(&request, REMOVE_COMMAND) {
+        Ok(payload) => payload,
+        Err(err) => return ipc::bad_request(err),
+    };
+    let path = match scope.resolve(&payload.path) {
+        Ok(path) => path,
+        Err(err) => return ipc::forbidden(err),
+    };
+    if scope
+        .dirs
+        .iter()
+        .filter_map(|dir| fs::canonicalize(dir).ok())
+        .any(|dir| dir == path)
+    {
+        return ipc::forbidden(format!(
+            "`{}` is a directory of the fs scope",
+            payload.path.display()
+        ));
+    }
+
+    let removed = match fs::symlink_metadata(&path) {
+        Ok(metadata) if metadata.is_dir() && payload.options.recursive => fs::remove_dir_all(&path),
+        Ok(metadata) if metadata.is_dir() => fs::remove_dir(&path),
+        Ok(_) => fs::remove_file(&path),
+        Err(err) => Err(err),
+    };
+    match removed {
+        Ok(()) => ipc::ok_json(&()),
+        Err(err) => io_error(&path, err),
+    }
+}
+
+fn copy_file(scope: &FsScope, request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let payload: CopyFilePayload = match parse_payload(&request, COPY_FILE_COMMAND) {
+        Ok(payload) => payload,
+        Err(err) => return ipc::bad_request(err),
+    };
+    let (from, to) = match (
+        scope.resolve(&payload.from_path),
+        scope.resolve(&payload.to_path),
+    ) {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(err), _) | (_, Err(err)) => return ipc::forbidden(err),
+    };
+    match fs::copy(&from, &to) {
+        Ok(_) => ipc::ok_json(&()),
+        Err(err) => io_error(&from, err),
+    }
+}
@@ -0,0 +1,197 @@
+//! The isolation pattern; see [`crate::Builder::isolation`].
+//!
+//! The main frame hands every IPC message to a hidden, sandboxed iframe served from the
+//! `isolation://` protocol. The iframe runs the isolation app's `__TAURI_ISOLATION_HOOK__` on
+//! the message and encrypts its payload with AES-256-GCM under a key that only the iframe and
+//! Rust know, then passes it back to the main frame to send. Requests that don't decrypt are
+//! rejected, so a script injected into the main frame can only call what the hook lets through.
+
+use std::fs;
+use std::path::PathBuf;
+
+use http::header::CONTENT_TYPE;
+use ring::aead::{AES_256_GCM, Aad, LessSafeKey, Nonce, UnboundKey};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::Deserialize;
+use serialize_to_javascript::{DefaultTemplate, Template, default_template};
+
+use crate::{StaticAssetError, ipc};
+
+pub(crate) const PROTOCOL: &str = "isolation";
+
+/// The iframe only relays messages, so it takes no space in the page.
+const IFRAME_STYLE: &str = "#__tauri_isolation__ { display: none !important; }";
+
+/// The origin of the isolation frame; Windows and Android serve custom protocols over `http`.
+pub(crate) fn origin() -> &'static str {
+    if cfg!(any(windows, target_os = "android")) {
+        "http://isolation.localhost"
+    } else {
+        "isolation://localhost"
+    }
+}
+
+#[derive(Template)]
+#[default_template("scripts/tauri/isolation.js")]
+struct IsolationJavascript<'a> {
+    isolation_src: &'a str,
+    style: &'a str,
+}
+
+#[derive(Template)]
+#[default_template("scripts/tauri/isolation_secure.js")]
+struct IsolationSecureJavascript<'a> {
+    runtime_aes_gcm_key: &'a [u8; 32],
+    #[raw]
+    process_ipc_message_fn: &'a str,
+}
+
+/// A payload as encrypted by `isolation_secure.js`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EncryptedPayload {
+    content_type: String,
+    nonce: Vec<u8>,
+    payload: Vec<u8>,
+}
+
+pub(crate) struct Isolation {
+    dir: PathBuf,
+    key: LessSafeKey,
+    /// `isolation_secure.js` with the key, injected into the HTML pages of `dir`.
+    secure_script: String,
+}
+
+impl Isolation {
+    /// Serves the isolation app in `dir` under a fresh key.
+    pub(crate) fn new(dir: PathBuf) -> Self {
+        let mut raw_key = [0; 32];
+        SystemRandom::new()
+            .fill(&mut raw_key)
+            .expect("failed to generate the isolation key");
+        let key = LessSafeKey::new(
+            UnboundKey::new(&AES_256_GCM, &raw_key).expect("AES-256-GCM keys are 32 bytes"),
+        );
+        let secure_script = IsolationSecureJavascript {
+            runtime_aes_gcm_key: &raw_key,
+            process_ipc_message_fn: include_str!("scripts/tauri/process-ipc-message-fn.js"),
+        }
+        .render_default(&Default::default())
+        .expect("failed to render isolation_secure.js")
+        .into_string();
+
+        Self {
+            dir,
+            key,
+            secure_script,
+        }
+    }
+
+    /// The main frame script that adds the isolation frame to the page.
+    pub(crate) fn main_frame_script(
+        &self,
+    ) -> std::result::Result<String, Box<dyn std::error::Error>> {
+        let isolation_src = format!("{}/", origin());
+        Ok(IsolationJavascript {
+            isolation_src: &isolation_src,
+            style: IFRAME_STYLE,
+        }
+        .render_default(&Default::default())?
+        .into_string())
+    }
+
+    /// Replaces the body of an IPC request with the payload the isolation frame encrypted, and
+    /// its `Content-Type` with the one of the payload.
+    pub(crate) fn open(
+        &self,
+        request: http::Request<Vec<u8>>,
+    ) -> std::result::Result<http::Request<Vec<u8>>, http::Response<Vec<u8>>> {
+        let (mut parts, body) = request.into_parts();
+        let encrypted: EncryptedPayload = serde_json::from_slice(&body).map_err(|_| {
+            tracing::warn!("rejected IPC request that did not pass the isolation frame");
+            ipc::forbidden("IPC requests must pass the isolation frame")
+        })?;
+
+        let nonce = Nonce::try_assume_unique_for_key(&encrypted.nonce)
+            .map_err(|_| ipc::bad_request("invalid isolation nonce"))?;
+        let mut payload = encrypted.payload;
+        let len = self
+            .key
+            .open_in_place(nonce, Aad::empty(), &mut payload)
+            .map_err(|_| {
+                tracing::warn!("rejected IPC request that failed to decrypt");
+                ipc::forbidden("IPC request failed to decrypt")
+            })?
+            .len();
+        payload.truncate(len);
+
+        let content_type = http::HeaderValue::from_str(&encrypted.content_type)
+            .map_err(|_| ipc::bad_request("invalid isolation content type"))?;
+        parts.headers.insert(CONTENT_TYPE, content_type);
+        Ok(http::Request::from_parts(parts, payload))
+    }
+
+    /// Serves the isolation app. The responses don't allow cross-origin reads, so the main frame
+    /// can't fetch the pages to get the key.
+    pub(crate) fn respond(&self, request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+        let path = request.uri().path();
+        let root = match fs::canonicalize(&self.dir) {
+            Ok(root) => root,
+            Err(err) => {
+                tracing::error!(
+                    "failed to canonicalize isolation dir `{}`: {err}",
+                    self.dir.display()
+                );
+                return crate::response_internal_server_err("isolation app not accessible");
+            }
+        };
+
+        let asset = match crate::resolve_static_asset(&root, path, false) {
+            Ok(asset) => asset,
+            Err(StaticAssetError::OutsideRoot(requested)) => {
+                tracing::warn!(
+                    "attempt to read outside isolation dir: {}",
+                    requested.display()
+                );
+                return crate::response_forbidden(requested.display());
+            }
+            Err(StaticAssetError::Io(err)) => {
+                tracing::error!("failed to read isolation asset: {err}");
+                return crate::response_internal_server_err("failed to read isolation asset");
+            }
+            Err(_) => return crate::response_not_found(path),
+        };
+        let mut bytes = match fs::read(&asset.path) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                tracing::error!("failed to read isolation asset: {err}");
+                return crate::response_internal_server_err("failed to read isolation asset");
+            }
+        };
+        if asset.mime == "text/html" {
+            bytes = self.inject_secure_script(&bytes);
+        }
+
+        http::Response::builder()
+            .status(http::StatusCode::OK)
+            .header(CONTENT_TYPE, asset.mime)
+            .body(bytes)
+            .unwrap()
+    }
+
+    /// Puts the secure script first in `<head>`, before any script of the isolation app.
+    fn inject_secure_script(&self, html: &[u8]) -> Vec<u8> {
+        let html = String::from_utf8_lossy(html);
+        let script = format!("<script>{}</script>", self.secure_script);
+        let insert_at = html
+            .to_ascii_lowercase()
+            .find("<head")
+            .and_then(|head| Some(head + html[head..].find('>')? + 1))
+            .unwrap_or(0);
+        let mut injected = String::with_capacity(html.len() + script.len());
+        injected.push_str(&html[..insert_at]);
+        injected.push_str(&script);
+        injected.push_str(&html[insert_at..]);
+        injected.into_bytes()
+    }
+}
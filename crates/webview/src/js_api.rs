@@ -0,0 +1,27 @@
+//! The `@manos/api` package: a typed JS wrapper over the injected `__TAURI_INTERNALS__`
+//! (`invoke`, `listen`/`emit` and `Channel`). Its sources live in `js/api`.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub const PACKAGE_NAME: &str = "@manos/api";
+
+/// The package files, as `(file name, contents)`.
+pub const FILES: &[(&str, &str)] = &[
+    ("package.json", include_str!("../js/api/package.json")),
+    ("index.js", include_str!("../js/api/index.js")),
+    ("index.d.ts", include_str!("../js/api/index.d.ts")),
+    ("README.md", include_str!("../js/api/README.md")),
+];
+
+/// Writes the package into `dir`, e.g. `node_modules/@manos/api` of a frontend that is not
+/// in this repository.
+pub fn write_to(dir: impl AsRef<Path>) -> io::Result<()> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+    for (name, contents) in FILES {
+        fs::write(dir.join(name), contents)?;
+    }
+    Ok(())
+}
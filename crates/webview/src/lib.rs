@@ -1,70 +1,161 @@
+pub mod cache;
+pub mod clipboard;
+pub mod cookies;
+pub mod dev_server;
+pub mod dialog;
+pub mod drag_drop;
+pub mod embedded;
+pub mod eval;
+pub mod events;
+pub mod fs_plugin;
+mod http_date;
+#[cfg(feature = "isolation")]
+mod isolation;
+pub mod js_api;
+pub mod manager;
+pub mod navigation;
+pub mod permissions;
+pub mod print;
+pub mod proxy;
+pub mod shell;
+mod timer;
+#[cfg(feature = "typescript")]
+pub mod typescript;
 pub mod webview;
+pub mod window;
+pub mod window_state;
+mod worker_pool;
+pub mod zoom;
 pub use http;
 pub use serde;
 pub use serde_json;
 pub use wry;
 
-use http::header::{ACCESS_CONTROL_ALLOW_ORIGIN, CONTENT_TYPE};
+use cache::ResponseCache;
+use dev_server::DevServer;
+use drag_drop::FileDropHandler;
+use embedded::EmbeddedAssets;
+use fs_plugin::FsScope;
+use http::header::{
+    ACCEPT_RANGES, ACCESS_CONTROL_ALLOW_ORIGIN, CACHE_CONTROL, CONTENT_RANGE, CONTENT_TYPE, ETAG,
+    IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE, LAST_MODIFIED, RANGE,
+};
+use manager::WebviewManager;
+use navigation::{NavigationHook, NavigationPolicy, NavigationScope};
+use permissions::Permissions;
+use proxy::Proxy;
 use serde::{Deserialize, Serialize};
 use serialize_to_javascript::{DefaultTemplate, Template, default_template};
-use std::cell::RefCell;
+use shell::ShellScope;
 use std::collections::HashMap;
 use std::fs;
-use std::io;
+use std::io::{self, Read, Seek};
+use std::ops::Range;
 use std::path::{Component, Path, PathBuf};
-use std::rc::{Rc, Weak};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
 use wry::{Error as WryError, Result, WebView, WebViewBuilder, WebViewId};
 
 pub use gpui_manos_webview_macros::{
-    api_handler, api_handlers, command, command_handler, command_handlers, generate_handler,
+    api_handler, api_handlers, command, command_handler, command_handlers, embed_assets,
+    generate_handler,
 };
 
-// todo: implement dev server
+/// Expands to its items with the `typescript` feature and to nothing without, so the macros can
+/// emit bindings code that only builds when the feature is on.
+#[cfg(feature = "typescript")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __typescript {
+    ($($item:tt)*) => { $($item)* };
+}
 
-const INVOKE_KEY: &str = "gpui";
+#[cfg(not(feature = "typescript"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __typescript {
+    ($($item:tt)*) => {};
+}
 
+/// The key the page sends with every invoke unless [`Builder::with_invoke_key`] sets another one.
+/// It is random per process, so a page can't invoke commands without the injected scripts.
+fn default_invoke_key() -> &'static str {
+    static INVOKE_KEY: OnceLock<String> = OnceLock::new();
+    INVOKE_KEY.get_or_init(|| {
+        use ring::rand::{SecureRandom, SystemRandom};
+
+        let mut raw_key = [0u8; 16];
+        SystemRandom::new()
+            .fill(&mut raw_key)
+            .expect("failed to generate the invoke key");
+        raw_key.iter().map(|byte| format!("{byte:02x}")).collect()
+    })
+}
+
+/// Runs the futures of `async` commands. Without the `tokio` feature a future is polled on the
+/// thread of the invoke, which is enough for futures that don't need an executor; with it,
+/// futures run on a shared multi-threaded tokio runtime, so they can use tokio timers, IO and
+/// `spawn`. Commands can pick another executor with `#[command(runtime = "path::to::block_on")]`.
 pub mod async_runtime {
     use std::future::Future;
 
+    #[cfg(not(feature = "tokio"))]
     pub fn block_on<F: Future>(future: F) -> F::Output {
         pollster::block_on(future)
     }
-}
 
-thread_local! {
-    static IPC_WEBVIEWS: RefCell<HashMap<String, Weak<wry::WebView>>> =
-        RefCell::new(HashMap::new());
-}
-
-pub(crate) fn register_webview_for_ipc(webview: &Rc<wry::WebView>) {
-    let id = webview.id().to_string();
-    IPC_WEBVIEWS.with(|registry| {
-        registry.borrow_mut().insert(id, Rc::downgrade(webview));
-    });
-}
-
-pub(crate) fn unregister_webview_for_ipc(webview_id: &str) {
-    IPC_WEBVIEWS.with(|registry| {
-        registry.borrow_mut().remove(webview_id);
-    });
-}
+    /// Runs `future` on the shared runtime and waits for it. Must not be called from a task of
+    /// the runtime.
+    #[cfg(feature = "tokio")]
+    pub fn block_on<F: Future>(future: F) -> F::Output {
+        handle().block_on(future)
+    }
+
+    #[cfg(feature = "tokio")]
+    pub use self::tokio_runtime::{handle, set_handle, spawn};
+
+    #[cfg(feature = "tokio")]
+    mod tokio_runtime {
+        use std::future::Future;
+        use std::sync::OnceLock;
+
+        use tokio::runtime::{Builder, Handle, Runtime};
+        use tokio::task::JoinHandle;
+
+        static HANDLE: OnceLock<Handle> = OnceLock::new();
+        static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+        /// The shared runtime: the one [`set_handle`] set, or one started on first use.
+        pub fn handle() -> &'static Handle {
+            HANDLE.get_or_init(|| {
+                RUNTIME
+                    .get_or_init(|| {
+                        Builder::new_multi_thread()
+                            .thread_name("manos-async")
+                            .enable_all()
+                            .build()
+                            .expect("cannot start the tokio runtime")
+                    })
+                    .handle()
+                    .clone()
+            })
+        }
 
-fn ipc_webview_for_label(webview_label: Option<&str>) -> Option<Rc<wry::WebView>> {
-    IPC_WEBVIEWS.with(|registry| {
-        let weak = {
-            let registry = registry.borrow();
-            if let Some(label) = webview_label {
-                registry.get(label).cloned()
-            } else if registry.len() == 1 {
-                registry.values().next().cloned()
-            } else {
-                None
-            }
-        };
+        /// Makes `handle` the shared runtime, e.g. the runtime of `#[tokio::main]`, instead of
+        /// starting another one. Fails with `handle` once the shared runtime is in use.
+        pub fn set_handle(handle: Handle) -> Result<(), Handle> {
+            HANDLE.set(handle)
+        }
 
-        weak.and_then(|w| w.upgrade())
-    })
+        /// Runs `future` on the shared runtime without waiting for it.
+        pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+        where
+            F: Future + Send + 'static,
+            F::Output: Send + 'static,
+        {
+            handle().spawn(future)
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -88,8 +179,6 @@ struct PostMessageRequest {
     options: Option<PostMessageOptions>,
     #[serde(rename = "__TAURI_INVOKE_KEY__")]
     invoke_key: String,
-    #[serde(default)]
-    webview_label: Option<String>,
 }
 
 pub struct Invoke {
@@ -101,10 +190,63 @@ pub struct Invoke {
 pub type InvokeHandler =
     Arc<dyn Fn(Invoke) -> Option<http::Response<Vec<u8>>> + Send + Sync + 'static>;
 
+/// Wraps every invoke of a webview; see [`Builder::middleware`].
+pub type Middleware =
+    Arc<dyn Fn(Invoke, Next<'_>) -> http::Response<Vec<u8>> + Send + Sync + 'static>;
+
+/// Observes every invoke of a webview once it is answered; see [`Builder::on_invoke`].
+pub type InvokeObserver = Arc<dyn Fn(&str, Duration, http::StatusCode) + Send + Sync + 'static>;
+
+/// Called when a command panics; see [`Builder::on_panic`].
+pub type PanicHook = Arc<dyn Fn(&str, &str) + Send + Sync + 'static>;
+
+/// The rest of the middleware chain, ending with the command.
+pub struct Next<'a> {
+    middleware: &'a [Middleware],
+    handle: &'a dyn Fn(Invoke) -> http::Response<Vec<u8>>,
+}
+
+impl Next<'_> {
+    /// Passes `invoke` on to the next middleware, or to the command after the last one.
+    pub fn run(self, invoke: Invoke) -> http::Response<Vec<u8>> {
+        match self.middleware.split_first() {
+            Some((middleware, rest)) => middleware(
+                invoke,
+                Next {
+                    middleware: rest,
+                    handle: self.handle,
+                },
+            ),
+            None => (self.handle)(invoke),
+        }
+    }
+}
+
+type ApiHandler =
+    Arc<dyn Fn(http::Request<Vec<u8>>) -> http::Response<Vec<u8>> + Send + Sync + 'static>;
+
 pub struct Builder<'a> {
     builder: WebViewBuilder<'a>,
     webview_id: WebViewId<'a>,
     invoke_handler: Option<InvokeHandler>,
+    middleware: Vec<Middleware>,
+    observers: Vec<InvokeObserver>,
+    panic_hook: Option<PanicHook>,
+    ipc_workers: worker_pool::Limits,
+    command_timeout: Option<Duration>,
+    command_timeouts: HashMap<String, Duration>,
+    /// Set once [`Builder::on_file_drop`] or [`Builder::file_drop_events`] asked for drops.
+    file_drop: Option<Vec<FileDropHandler>>,
+    navigation_scope: Option<NavigationScope>,
+    navigation_hook: Option<NavigationHook>,
+    permissions: Option<Permissions>,
+    cache: Option<ResponseCache>,
+    /// Whether one of the `serve_*` methods already registered the asset protocols.
+    serves_static: bool,
+    index_fallback: IndexFallback,
+    pattern: Pattern,
+    invoke_key: String,
+    capabilities: Option<Arc<[String]>>,
     handlers: HashMap<
         String,
         Arc<dyn Fn(http::Request<Vec<u8>>) -> http::Response<Vec<u8>> + Send + Sync + 'static>,
@@ -122,15 +264,88 @@ impl<'a> Builder<'a> {
             ipc::FETCH_CHANNEL_DATA_COMMAND.to_string(),
             Arc::new(|request| ipc::fetch_channel_data(request)),
         );
+        handlers.insert(print::PRINT_COMMAND.to_string(), Arc::new(print::print));
+        handlers.insert(zoom::SET_ZOOM_COMMAND.to_string(), Arc::new(zoom::set_zoom));
+        handlers.insert(zoom::ZOOM_IN_COMMAND.to_string(), Arc::new(zoom::zoom_in));
+        handlers.insert(zoom::ZOOM_OUT_COMMAND.to_string(), Arc::new(zoom::zoom_out));
+        handlers.insert(
+            zoom::ZOOM_LEVEL_COMMAND.to_string(),
+            Arc::new(zoom::zoom_level),
+        );
+        handlers.insert(
+            clipboard::WRITE_TEXT_COMMAND.to_string(),
+            Arc::new(clipboard::write_text),
+        );
+        handlers.insert(
+            clipboard::WRITE_IMAGE_COMMAND.to_string(),
+            Arc::new(clipboard::write_image),
+        );
+        handlers.insert(dialog::OPEN_COMMAND.to_string(), Arc::new(dialog::open));
+        handlers.insert(dialog::SAVE_COMMAND.to_string(), Arc::new(dialog::save));
+        handlers.insert(
+            dialog::MESSAGE_COMMAND.to_string(),
+            Arc::new(dialog::message),
+        );
+        handlers.insert(dialog::ASK_COMMAND.to_string(), Arc::new(dialog::ask));
+        handlers.insert(
+            dialog::CONFIRM_COMMAND.to_string(),
+            Arc::new(dialog::confirm),
+        );
+        handlers.insert(eval::RESULT_COMMAND.to_string(), Arc::new(eval::result));
+        handlers.insert(
+            events::LISTEN_COMMAND.to_string(),
+            Arc::new(events::listen_from_page),
+        );
         handlers.insert(
-            "plugin:webview|set_webview_zoom".to_string(),
-            Arc::new(|request| ipc::set_webview_zoom(request)),
+            events::UNLISTEN_COMMAND.to_string(),
+            Arc::new(events::unlisten_from_page),
+        );
+        handlers.insert(
+            events::EMIT_COMMAND.to_string(),
+            Arc::new(events::emit_from_page),
+        );
+        handlers.insert(
+            events::EMIT_TO_COMMAND.to_string(),
+            Arc::new(events::emit_to_from_page),
+        );
+        handlers.insert(
+            window::MINIMIZE_COMMAND.to_string(),
+            Arc::new(window::minimize),
+        );
+        handlers.insert(
+            window::MAXIMIZE_COMMAND.to_string(),
+            Arc::new(window::maximize),
+        );
+        handlers.insert(window::CLOSE_COMMAND.to_string(), Arc::new(window::close));
+        handlers.insert(
+            window::SET_TITLE_COMMAND.to_string(),
+            Arc::new(window::set_title),
+        );
+        handlers.insert(
+            window::SET_SIZE_COMMAND.to_string(),
+            Arc::new(window::set_size),
         );
 
         Builder {
             builder: WebViewBuilder::new(),
             webview_id: WebViewId::default(),
             invoke_handler: None,
+            middleware: Vec::new(),
+            observers: Vec::new(),
+            panic_hook: None,
+            ipc_workers: worker_pool::Limits::default(),
+            command_timeout: None,
+            command_timeouts: HashMap::new(),
+            file_drop: None,
+            navigation_scope: None,
+            navigation_hook: None,
+            permissions: None,
+            cache: None,
+            serves_static: false,
+            index_fallback: IndexFallback::default(),
+            pattern: Pattern::default(),
+            invoke_key: default_invoke_key().to_string(),
+            capabilities: None,
             handlers,
         }
     }
@@ -140,6 +355,13 @@ impl<'a> Builder<'a> {
         self
     }
 
+    /// Uses `invoke_key` instead of the random per-process key to tell invokes of the injected
+    /// scripts from forged ones.
+    pub fn with_invoke_key(mut self, invoke_key: impl Into<String>) -> Self {
+        self.invoke_key = invoke_key.into();
+        self
+    }
+
     pub fn apply<F>(mut self, f: F) -> Self
     where
         F: FnOnce(WebViewBuilder<'a>) -> WebViewBuilder<'a>,
@@ -161,15 +383,267 @@ impl<'a> Builder<'a> {
 
     /// Registers an invoke handler, similar to Tauri's `Builder::invoke_handler`.
     ///
-    /// Typically used with `gpui_manos_webview::generate_handler![...]`.
+    /// Typically used with `gpui_manos_webview::generate_handler![...]`. Calling it again adds
+    /// another handler rather than replacing the first, so each module can register its own
+    /// commands; a command goes to the first handler that knows it, in the order they were
+    /// registered.
     pub fn invoke_handler<F>(mut self, handler: F) -> Self
     where
         F: Fn(Invoke) -> Option<http::Response<Vec<u8>>> + Send + Sync + 'static,
     {
-        self.invoke_handler = Some(Arc::new(handler));
+        let handler: InvokeHandler = Arc::new(handler);
+        self.invoke_handler = Some(match self.invoke_handler.take() {
+            Some(previous) => chain_invoke_handlers(previous, handler),
+            None => handler,
+        });
+        self
+    }
+
+    /// Wraps every invoke of this webview in `middleware`, for logging, authentication, metrics
+    /// or rate limiting. The middleware runs for built-in commands too, after the invoke key,
+    /// the isolation pattern and [`Builder::permissions`] accepted the invoke; it calls
+    /// [`Next::run`] to go on, or answers by itself. Middleware registered first runs first.
+    pub fn middleware<F>(mut self, middleware: F) -> Self
+    where
+        F: Fn(Invoke, Next<'_>) -> http::Response<Vec<u8>> + Send + Sync + 'static,
+    {
+        self.middleware.push(Arc::new(middleware));
         self
     }
 
+    /// Calls `observer` with the command, the time it took and the status of the response after
+    /// every invoke of this webview, to track slow commands and error rates. The time includes
+    /// the middleware and the response cache. Every observer registered is called.
+    pub fn on_invoke<F>(mut self, observer: F) -> Self
+    where
+        F: Fn(&str, Duration, http::StatusCode) + Send + Sync + 'static,
+    {
+        self.observers.push(Arc::new(observer));
+        self
+    }
+
+    /// Calls `hook` with the command and the panic message when an invoke of this webview
+    /// panics, e.g. to report it. The page gets a `panic` [`ipc::InvokeError`] either way.
+    pub fn on_panic<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&str, &str) + Send + Sync + 'static,
+    {
+        self.panic_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Runs the invokes of this webview, over `ipc://` and the postMessage fallback alike, on
+    /// `workers` threads, with at most `queue_limit` invokes waiting for a free one; further
+    /// invokes are answered with `503` until the queue drains. Defaults to one worker per core,
+    /// up to 4, and 64 waiting invokes.
+    pub fn ipc_workers(mut self, workers: usize, queue_limit: usize) -> Self {
+        self.ipc_workers = worker_pool::Limits {
+            workers: workers.max(1),
+            queue: queue_limit,
+        };
+        self
+    }
+
+    /// Answers invokes of this webview that take longer than `timeout` with a `timeout`
+    /// [`ipc::InvokeError`] (status `504`) and cancels their [`ipc::CancellationToken`]. The
+    /// command keeps running until it returns, and its result is dropped.
+    pub fn command_timeout(mut self, timeout: Duration) -> Self {
+        self.command_timeout = Some(timeout);
+        self
+    }
+
+    /// Like [`Builder::command_timeout`], for `command` only; it takes precedence over the
+    /// timeout of all commands.
+    pub fn command_timeout_for(mut self, command: impl Into<String>, timeout: Duration) -> Self {
+        self.command_timeouts.insert(command.into(), timeout);
+        self
+    }
+
+    /// Calls `handler` with the paths of the files dropped onto this webview and the position of
+    /// the drop, relative to the top-left corner of the webview. The page gets the drag and drop
+    /// events as well; see [`drag_drop`]. Every handler registered is called.
+    ///
+    /// Dropped files are no longer opened by the webview, and can't be dropped onto
+    /// `<input type="file">`.
+    pub fn on_file_drop<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&[PathBuf], (i32, i32)) + 'static,
+    {
+        self.file_drop
+            .get_or_insert_with(Vec::new)
+            .push(Box::new(handler));
+        self
+    }
+
+    /// Sends the drag and drop events of files to the page, for apps that handle drops in
+    /// JavaScript only; see [`Builder::on_file_drop`].
+    pub fn file_drop_events(mut self) -> Self {
+        self.file_drop.get_or_insert_with(Vec::new);
+        self
+    }
+
+    /// Lets this webview navigate only to the app shell and the origins of `scope`; other
+    /// navigations are denied, or go to [`Builder::on_navigation`] when it is set. See
+    /// [`navigation`].
+    pub fn navigation_scope(mut self, scope: NavigationScope) -> Self {
+        self.navigation_scope = Some(scope);
+        self
+    }
+
+    /// Calls `hook` with the URL of every navigation of this webview that neither the app shell
+    /// nor [`Builder::navigation_scope`] allows, e.g. links to other sites, to allow it, deny it
+    /// or open it in the default browser.
+    pub fn on_navigation<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&str) -> NavigationPolicy + 'static,
+    {
+        self.navigation_hook = Some(Box::new(hook));
+        self
+    }
+
+    /// Asks the user before this webview invokes a command that needs a
+    /// [`permissions::Capability`]; see [`Permissions`].
+    pub fn permissions(mut self, permissions: Permissions) -> Self {
+        self.permissions = Some(permissions);
+        self
+    }
+
+    /// Lets this webview call only the `#[command(capability = "...")]` commands whose
+    /// capability is in `capabilities`; the others reject with a `403` error without running.
+    /// `"fs:*"` grants every capability starting with `fs:`, and `"*"` grants all of them.
+    ///
+    /// Without this, every capability is granted. Commands without a capability are always
+    /// callable.
+    pub fn with_capabilities<I, S>(mut self, capabilities: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.capabilities = Some(capabilities.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Answers the `asset://` and `ipc://` routes configured in `cache` from cached responses;
+    /// see [`ResponseCache`].
+    ///
+    /// Call it before [`Builder::serve_static`], which picks up the cache when it registers the
+    /// asset protocols.
+    pub fn cache(mut self, cache: ResponseCache) -> Self {
+        if self.serves_static {
+            tracing::warn!(
+                "Builder::cache called after serve_static; static assets are not cached"
+            );
+        }
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Which missing paths [`Builder::serve_static`] and [`Builder::serve_embedded`] answer
+    /// with `index.html`; all of them by default. [`IndexFallback::Routes`] keeps the fallback
+    /// for client-side routes such as `/settings/profile` but answers missing files such as
+    /// `/app.js` with `404`, and [`IndexFallback::Never`] turns it off.
+    ///
+    /// Call it before [`Builder::serve_static`] or [`Builder::serve_embedded`].
+    pub fn index_fallback(mut self, fallback: IndexFallback) -> Self {
+        if self.serves_static {
+            tracing::warn!(
+                "Builder::index_fallback called after the asset protocols were registered"
+            );
+        }
+        self.index_fallback = fallback;
+        self
+    }
+
+    /// Routes IPC through the isolation pattern, for apps that load content they don't
+    /// control: every command call first goes to `window.__TAURI_ISOLATION_HOOK__` of the
+    /// isolation app in `dir`, which runs in a sandboxed iframe and can inspect, change or reject
+    /// it. Calls that didn't pass the hook are rejected.
+    ///
+    /// `dir` needs an `index.html` whose scripts define the hook, e.g.
+    /// `window.__TAURI_ISOLATION_HOOK__ = (payload) => payload`.
+    #[cfg(feature = "isolation")]
+    pub fn isolation(mut self, dir: impl Into<PathBuf>) -> Self {
+        let isolation = Arc::new(isolation::Isolation::new(dir.into()));
+        self.pattern = Pattern::Isolation(isolation.clone());
+        self.apply(move |b| {
+            b.with_asynchronous_custom_protocol(
+                isolation::PROTOCOL.into(),
+                move |_, request, responder| responder.respond(isolation.respond(request)),
+            )
+        })
+    }
+
+    /// Lets the page read and write files in the directories of `scope` with the `plugin:fs|…`
+    /// commands; see [`fs_plugin`].
+    pub fn fs(mut self, scope: FsScope) -> Self {
+        for (command, handler) in fs_plugin::handlers(scope) {
+            self.handlers.insert(command.to_string(), handler);
+        }
+        self
+    }
+
+    /// Lets the page open URLs of the schemes of `scope` in the default browser, and paths in
+    /// the file manager when `scope` allows `file`, with the `plugin:shell|open` command; see
+    /// [`shell`].
+    pub fn shell(mut self, scope: ShellScope) -> Self {
+        self.handlers.insert(
+            shell::OPEN_COMMAND.to_string(),
+            Arc::new(move |request| shell::open(&scope, request)),
+        );
+        self
+    }
+
+    /// Lets the page read the clipboard with the `plugin:clipboard-manager|read_text` and
+    /// `read_image` commands; writing it needs no opt-in. See [`clipboard`].
+    pub fn read_clipboard(mut self) -> Self {
+        self.handlers.insert(
+            clipboard::READ_TEXT_COMMAND.to_string(),
+            Arc::new(clipboard::read_text),
+        );
+        self.handlers.insert(
+            clipboard::READ_IMAGE_COMMAND.to_string(),
+            Arc::new(clipboard::read_image),
+        );
+        self
+    }
+
+    /// Lets the page read and change the cookies of its own webview with the
+    /// `plugin:cookies|…` commands; see [`cookies`].
+    pub fn cookies(mut self) -> Self {
+        self.handlers
+            .insert(cookies::GET_COMMAND.to_string(), Arc::new(cookies::get));
+        self.handlers
+            .insert(cookies::SET_COMMAND.to_string(), Arc::new(cookies::set));
+        self.handlers
+            .insert(cookies::CLEAR_COMMAND.to_string(), Arc::new(cookies::clear));
+        self
+    }
+
+    /// Lets the page export itself as PDF into the directories of `scope` with the
+    /// `plugin:webview|print_to_pdf` command; see [`print`].
+    pub fn print_to_pdf(mut self, scope: FsScope) -> Self {
+        self.handlers.insert(
+            print::PRINT_TO_PDF_COMMAND.to_string(),
+            Arc::new(move |request| print::print_to_pdf(&scope, request)),
+        );
+        self
+    }
+
+    /// Sends `user_agent` instead of the platform's default `User-Agent`.
+    ///
+    /// On Windows this needs WebView2 Runtime 86 or newer.
+    pub fn user_agent(self, user_agent: impl Into<String>) -> Self {
+        let user_agent = user_agent.into();
+        self.apply(move |b| b.with_user_agent(user_agent))
+    }
+
+    /// Sends all traffic of this webview through `proxy`.
+    ///
+    /// Needs macOS 14 or newer; not supported on Android and iOS.
+    pub fn proxy(self, proxy: Proxy) -> Self {
+        self.apply(move |b| b.with_proxy_config(proxy.to_wry()))
+    }
+
     pub fn serve_apis<I, F>(mut self, apis: I) -> Self
     where
         I: IntoIterator<Item = (String, F)>,
@@ -207,25 +681,46 @@ impl<'a> Builder<'a> {
     }
 
     // todo: implement more professional serve static
-    pub fn serve_static<S: ToString + 'static>(self, static_root: S) -> Self {
+    /// Serves the frontend from `static_root`, or from the dev server named by
+    /// [`dev_server::DEV_SERVER_ENV`] in debug builds.
+    pub fn serve_static<S: ToString + 'static>(mut self, static_root: S) -> Self {
         let static_root = static_root.to_string();
+        if let Some(dev_server) = DevServer::from_env() {
+            tracing::info!("serving the frontend from {dev_server} instead of `{static_root}`");
+            return self.serve_dev_server(dev_server);
+        }
+        let cache = self.cache.clone();
+        let index_fallback = self.index_fallback;
+        self.serves_static = true;
         self.apply(move |b| {
             let static_root_for_asset = static_root.clone();
             let static_root_for_wry = static_root.clone();
+            let cache_for_asset = cache.clone();
+            let cache_for_wry = cache;
 
             b.with_asynchronous_custom_protocol(
                 "asset".into(),
                 move |webview_id, request, responder| {
-                    let response = serve_static(webview_id, static_root_for_asset.clone(), request)
-                        .unwrap_or_else(response_internal_server_err);
+                    let response = serve_static_cached(
+                        cache_for_asset.as_ref(),
+                        webview_id,
+                        static_root_for_asset.clone(),
+                        request,
+                        index_fallback,
+                    );
                     responder.respond(response)
                 },
             )
             .with_asynchronous_custom_protocol(
                 "wry".into(),
                 move |webview_id, request, responder| {
-                    let response = serve_static(webview_id, static_root_for_wry.clone(), request)
-                        .unwrap_or_else(response_internal_server_err);
+                    let response = serve_static_cached(
+                        cache_for_wry.as_ref(),
+                        webview_id,
+                        static_root_for_wry.clone(),
+                        request,
+                        index_fallback,
+                    );
                     responder.respond(response)
                 },
             )
@@ -233,11 +728,51 @@ impl<'a> Builder<'a> {
         })
     }
 
+    /// Serves the frontend from assets compiled into the executable by [`embed_assets!`], so a
+    /// shipped app needs no `dist/` folder next to it. Like [`Builder::serve_static`], debug
+    /// builds serve from the dev server named by [`dev_server::DEV_SERVER_ENV`] when it is set.
+    ///
+    /// ```ignore
+    /// static ASSETS: EmbeddedAssets = gpui_manos_webview::embed_assets!("frontend/dist");
+    ///
+    /// Builder::new().serve_embedded(&ASSETS)
+    /// ```
+    pub fn serve_embedded(mut self, assets: &'static EmbeddedAssets) -> Self {
+        if let Some(dev_server) = DevServer::from_env() {
+            tracing::info!("serving the frontend from {dev_server} instead of embedded assets");
+            return self.serve_dev_server(dev_server);
+        }
+        let index_fallback = self.index_fallback;
+        self.serves_static = true;
+        self.apply(move |b| {
+            b.with_asynchronous_custom_protocol("asset".into(), move |_, request, responder| {
+                responder.respond(assets.respond(request, index_fallback))
+            })
+            .with_asynchronous_custom_protocol("wry".into(), move |_, request, responder| {
+                responder.respond(assets.respond(request, index_fallback))
+            })
+            .with_url("asset://localhost")
+        })
+    }
+
+    /// Serves the frontend from a running dev server so that hot reload works; see
+    /// [`dev_server`].
+    pub fn serve_dev_server(mut self, dev_server: DevServer) -> Self {
+        self.serves_static = true;
+        self.apply(move |b| {
+            b.with_asynchronous_custom_protocol("asset".into(), dev_server_protocol(&dev_server))
+                .with_asynchronous_custom_protocol("wry".into(), dev_server_protocol(&dev_server))
+                .with_url("asset://localhost")
+        })
+    }
+
     fn with_initialization_script_for_main_only(mut self) -> Self {
         // todo: fix mocked_window_id and webview_id
         let scripts = prepare_scripts(
             String::from("mocked_window_id"),
             self.webview_id.to_string(),
+            &self.pattern,
+            &self.invoke_key,
         )
         .unwrap();
         for s in scripts {
@@ -246,32 +781,67 @@ impl<'a> Builder<'a> {
         self
     }
 
-    fn with_apis(self) -> Self {
+    fn with_apis(mut self) -> Self {
+        if let Some(handlers) = self.file_drop.take() {
+            let handler = drag_drop::handler(self.webview_id.to_string(), handlers);
+            self = self.apply(move |b| b.with_drag_drop_handler(handler));
+        }
+        let navigation_scope = self.navigation_scope.take();
+        let navigation_hook = self.navigation_hook.take();
+        if navigation_scope.is_some() || navigation_hook.is_some() {
+            let handler = navigation::handler(
+                self.webview_id.to_string(),
+                navigation_scope,
+                navigation_hook,
+            );
+            self = self.apply(move |b| b.with_navigation_handler(handler));
+        }
         let handlers = self.handlers.clone();
-        let invoke_handler = self.invoke_handler.clone();
+        let pipeline = InvokePipeline {
+            middleware: self.middleware.clone().into(),
+            observers: self.observers.clone().into(),
+            panic_hook: self.panic_hook.clone(),
+            command_timeout: self.command_timeout,
+            command_timeouts: Arc::new(self.command_timeouts.clone()),
+            cache: self.cache.clone(),
+            capabilities: self.capabilities.clone(),
+            invoke_handler: self.invoke_handler.clone(),
+        };
+        let permissions = self.permissions.clone();
+        let pattern = self.pattern.clone();
+        let invoke_key = self.invoke_key.clone();
+        let label = self.webview_id.to_string();
+        let workers = Arc::new(worker_pool::WorkerPool::new(self.ipc_workers));
         self.apply(move |b| {
+            let workers_for_post_message = workers.clone();
             let handlers_for_post_message = handlers.clone();
-            let invoke_handler_for_post_message = invoke_handler.clone();
+            let pipeline_for_post_message = pipeline.clone();
+            let permissions_for_post_message = permissions.clone();
+            let pattern_for_post_message = pattern.clone();
+            let invoke_key_for_post_message = invoke_key.clone();
+            let label_for_post_message = label.clone();
             b.with_ipc_handler(move |request: http::Request<String>| {
                 let message: PostMessageRequest = match serde_json::from_str(request.body()) {
                     Ok(message) => message,
                     Err(err) => {
-                        eprintln!("[webview] invalid IPC postMessage payload: {err}");
+                        tracing::warn!("invalid IPC postMessage payload: {err}");
                         return;
                     }
                 };
 
-                if message.invoke_key != INVOKE_KEY {
-                    eprintln!("[webview] rejected IPC postMessage with invalid invoke key");
+                if message.invoke_key != invoke_key_for_post_message {
+                    tracing::warn!("rejected IPC postMessage with invalid invoke key");
                     return;
                 }
 
-                let _guard = ipc::IpcContextGuard::new(message.webview_label.as_deref());
+                // The label the page claims may be stale or made up; this handler belongs to
+                // exactly one webview.
+                let webview_label = Some(label_for_post_message.clone());
 
                 let payload_bytes = match serde_json::to_vec(&message.payload) {
                     Ok(bytes) => bytes,
                     Err(err) => {
-                        eprintln!("[webview] failed to serialize IPC payload: {err}");
+                        tracing::warn!("failed to serialize IPC payload: {err}");
                         return;
                     }
                 };
@@ -297,85 +867,63 @@ impl<'a> Builder<'a> {
                 let request = match request_builder.body(payload_bytes) {
                     Ok(request) => request,
                     Err(err) => {
-                        eprintln!("[webview] failed to build IPC request: {err}");
+                        tracing::warn!("failed to build IPC request: {err}");
                         return;
                     }
                 };
 
                 let cmd = message.cmd;
                 let api_handler = handlers_for_post_message.get(&cmd).cloned();
-
-                let response = if let Some(ref handler) = invoke_handler_for_post_message {
-                    if let Some(api_handler) = api_handler {
-                        let request_for_invoke = request.clone();
-                        handler(Invoke {
-                            command: cmd.clone(),
-                            request: request_for_invoke,
-                            webview_label: message.webview_label.clone(),
-                        })
-                        .unwrap_or_else(|| api_handler(request))
-                    } else {
-                        handler(Invoke {
-                            command: cmd.clone(),
-                            request,
-                            webview_label: message.webview_label.clone(),
-                        })
-                        .unwrap_or_else(|| ipc::not_found(cmd.clone()))
-                    }
-                } else if let Some(api_handler) = api_handler {
-                    api_handler(request)
-                } else {
-                    ipc::not_found(cmd.clone())
+                let callback = PostMessageCallback {
+                    command: cmd.clone(),
+                    callback: message.callback,
+                    error: message.error,
+                    webview_label: webview_label.clone(),
                 };
 
-                let (parts, body) = response.into_parts();
-                let response_header = parts
-                    .headers
-                    .get("Tauri-Response")
-                    .and_then(|value| value.to_str().ok());
-                let callback_id = if response_header == Some("ok") {
-                    message.callback
-                } else {
-                    message.error
+                let Some(slot) = workers_for_post_message.reserve() else {
+                    tracing::warn!("rejected `{cmd}`: the IPC queue is full");
+                    callback.run(ipc::service_unavailable(format!(
+                        "too many invokes are waiting; `{cmd}` was not run"
+                    )));
+                    return;
                 };
-
-                let content_type = parts
-                    .headers
-                    .get(CONTENT_TYPE)
-                    .and_then(|value| value.to_str().ok())
-                    .unwrap_or_default();
-                let content_type = content_type.split(',').next().unwrap_or_default();
-
-                let js_arg = match content_type {
-                    "application/json" => {
-                        let data = serde_json::from_slice::<serde_json::Value>(&body)
-                            .unwrap_or_else(|_| {
-                                serde_json::Value::String(
-                                    String::from_utf8_lossy(&body).into_owned(),
-                                )
-                            });
-                        serde_json::to_string(&data).unwrap_or_else(|_| "null".to_string())
-                    }
-                    "text/plain" => serde_json::to_string(&String::from_utf8_lossy(&body))
-                        .unwrap_or_else(|_| "null".to_string()),
-                    _ => {
-                        let bytes_as_json_array =
-                            serde_json::to_string(&body).unwrap_or_else(|_| "[]".to_string());
-                        format!("new Uint8Array({bytes_as_json_array}).buffer")
+                let permissions = permissions_for_post_message.clone();
+                let pattern = pattern_for_post_message.clone();
+                let pipeline = pipeline_for_post_message.clone();
+                let label = label_for_post_message.clone();
+                // The postMessage handler runs on the main thread. Running the invoke on a worker
+                // keeps the window responsive, lets the permission prompt show, and lets a
+                // timeout be answered while the command still runs.
+                slot.run(move || {
+                    let _guard = ipc::IpcContextGuard::new(webview_label.as_deref());
+                    let request = match pattern.open_ipc_request(request) {
+                        Ok(request) => request,
+                        Err(response) => {
+                            callback.answer(response);
+                            return;
+                        }
+                    };
+                    if let Some(permissions) = permissions
+                        && let Some(capability) = permissions.capability_for(&cmd)
+                        && !permissions.authorize(&label, capability)
+                    {
+                        callback.answer(ipc::forbidden(format!(
+                            "webview `{label}` is not allowed to use {capability}"
+                        )));
+                        return;
                     }
-                };
-
-                let js = format!("window.__TAURI_INTERNALS__.runCallback({callback_id}, {js_arg});");
-
-                let Some(webview) = ipc_webview_for_label(message.webview_label.as_deref())
-                else {
-                    eprintln!(
-                        "[webview] IPC postMessage fallback used but no webview is registered; cannot run callback for `{cmd}`"
+                    pipeline.dispatch(
+                        api_handler,
+                        Invoke {
+                            command: cmd,
+                            request,
+                            webview_label,
+                        },
+                        "postMessage",
+                        move |response| callback.answer(response),
                     );
-                    return;
-                };
-
-                let _ = webview.evaluate_script(&js);
+                });
             })
             .with_asynchronous_custom_protocol(
                 "ipc".into(),
@@ -395,7 +943,7 @@ impl<'a> Builder<'a> {
                         responder.respond(response);
                     }
 
-                    println!(
+                    tracing::debug!(
                         "webview_id: {}, method: {}, scheme: {:?}, host: {:?}, path: {:?}",
                         webview_id,
                         request.method(),
@@ -428,7 +976,9 @@ impl<'a> Builder<'a> {
                         }
                     }
 
-                    if let Err(response) = ipc::validate_custom_protocol_request(&request) {
+                    if let Err(response) =
+                        ipc::validate_custom_protocol_request(&request, &invoke_key)
+                    {
                         respond(responder, response);
                         return;
                     }
@@ -439,40 +989,53 @@ impl<'a> Builder<'a> {
                     );
                     let webview_label = Some(webview_id.to_string());
 
-                    let invoke_handler = invoke_handler.clone();
                     let api_handler = handlers.get(&command).cloned();
-
-                    std::thread::spawn(move || {
+                    let permissions = permissions.clone();
+                    let pipeline = pipeline.clone();
+                    let pattern = pattern.clone();
+                    let label = label.clone();
+
+                    let Some(slot) = workers.reserve() else {
+                        tracing::warn!("rejected `{command}`: the IPC queue is full");
+                        respond(
+                            responder,
+                            ipc::service_unavailable(format!(
+                                "too many invokes are waiting; `{command}` was not run"
+                            )),
+                        );
+                        return;
+                    };
+                    slot.run(move || {
                         let _guard = ipc::IpcContextGuard::new(webview_label.as_deref());
-                        let response = std::panic::catch_unwind(std::panic::AssertUnwindSafe(
-                            || {
-                                if let Some(handler) = invoke_handler {
-                                    if let Some(api_handler) = api_handler {
-                                        let request_for_invoke = request.clone();
-                                        handler(Invoke {
-                                            command: command.clone(),
-                                            request: request_for_invoke,
-                                            webview_label: webview_label.clone(),
-                                        })
-                                        .unwrap_or_else(|| api_handler(request))
-                                    } else {
-                                        handler(Invoke {
-                                            command,
-                                            request,
-                                            webview_label,
-                                        })
-                                        .unwrap_or_else(|| ipc::not_found(raw_path))
-                                    }
-                                } else if let Some(api_handler) = api_handler {
-                                    api_handler(request)
-                                } else {
-                                    ipc::not_found(raw_path)
-                                }
+                        let request = match pattern.open_ipc_request(request) {
+                            Ok(request) => request,
+                            Err(response) => {
+                                respond(responder, response);
+                                return;
+                            }
+                        };
+                        if let Some(permissions) = permissions
+                            && let Some(capability) = permissions.capability_for(&command)
+                            && !permissions.authorize(&label, capability)
+                        {
+                            respond(
+                                responder,
+                                ipc::forbidden(format!(
+                                    "webview `{label}` is not allowed to use {capability}"
+                                )),
+                            );
+                            return;
+                        }
+                        pipeline.dispatch(
+                            api_handler,
+                            Invoke {
+                                command,
+                                request,
+                                webview_label,
                             },
-                        ))
-                        .unwrap_or_else(|_| ipc::internal_error("invoke handler panicked"));
-
-                        respond(responder, response);
+                            "ipc",
+                            move |response| respond(responder, response),
+                        );
                     });
                 },
             )
@@ -480,6 +1043,266 @@ impl<'a> Builder<'a> {
     }
 }
 
+/// What an invoke of a webview runs through once it is accepted, whichever way it arrived.
+#[derive(Clone)]
+struct InvokePipeline {
+    middleware: Arc<[Middleware]>,
+    observers: Arc<[InvokeObserver]>,
+    panic_hook: Option<PanicHook>,
+    command_timeout: Option<Duration>,
+    command_timeouts: Arc<HashMap<String, Duration>>,
+    cache: Option<ResponseCache>,
+    /// The capabilities the webview grants; see [`Builder::with_capabilities`].
+    capabilities: Option<Arc<[String]>>,
+    invoke_handler: Option<InvokeHandler>,
+}
+
+impl InvokePipeline {
+    /// Runs `invoke` and hands the response to `reply`. An invoke that runs past its timeout is
+    /// answered with a `timeout` error as soon as the timeout expires, and its
+    /// [`ipc::CancellationToken`] is cancelled; see [`Builder::command_timeout`].
+    fn dispatch(
+        &self,
+        api_handler: Option<ApiHandler>,
+        invoke: Invoke,
+        transport: &'static str,
+        reply: impl FnOnce(http::Response<Vec<u8>>) + Send + 'static,
+    ) {
+        let token = ipc::CancellationToken::new();
+        let _cancellation = ipc::CancellationGuard::new(token.clone());
+        let timeout = self
+            .command_timeouts
+            .get(&invoke.command)
+            .copied()
+            .or(self.command_timeout);
+        let Some(timeout) = timeout else {
+            reply(self.run(api_handler, invoke, transport));
+            return;
+        };
+
+        let reply = Arc::new(std::sync::Mutex::new(Some(reply)));
+        let timer = {
+            let reply = reply.clone();
+            let command = invoke.command.clone();
+            timer::schedule(Instant::now() + timeout, move || {
+                token.cancel();
+                if let Some(reply) = reply.lock().unwrap().take() {
+                    tracing::warn!("command `{command}` timed out after {timeout:?}");
+                    reply(ipc::timeout_error(&command, timeout));
+                }
+            })
+        };
+        let response = self.run(api_handler, invoke, transport);
+        timer.cancel();
+        if let Some(reply) = reply.lock().unwrap().take() {
+            reply(response);
+        }
+    }
+
+    /// Runs `invoke` through the middleware, then through the cache when it has a route for the
+    /// command, see [`call_handlers`]. A panic on the way is answered with a `panic`
+    /// [`ipc::InvokeError`]. `transport` names the path the invoke came by, `ipc` or
+    /// `postMessage`, for the `invoke` tracing span.
+    fn run(
+        &self,
+        api_handler: Option<ApiHandler>,
+        invoke: Invoke,
+        transport: &'static str,
+    ) -> http::Response<Vec<u8>> {
+        let command = invoke.command.clone();
+        let span = tracing::debug_span!(
+            "invoke",
+            command = %command,
+            transport,
+            webview = invoke.webview_label.as_deref().unwrap_or_default(),
+        );
+        let _entered = span.enter();
+        let started = Instant::now();
+        let _capabilities = ipc::CapabilitiesGuard::new(self.capabilities.clone());
+        let handle = |invoke: Invoke| {
+            let Invoke {
+                command,
+                request,
+                webview_label,
+            } = invoke;
+            let slot = self
+                .cache
+                .as_ref()
+                .and_then(|cache| Some((cache, cache.command_slot(&command, request.body())?)));
+            let invoke_handler = self.invoke_handler.clone();
+            let api_handler = api_handler.clone();
+            let call =
+                move || call_handlers(invoke_handler, api_handler, command, request, webview_label);
+            match slot {
+                Some((cache, slot)) => cache.respond(slot, call),
+                None => call(),
+            }
+        };
+        let next = Next {
+            middleware: &self.middleware,
+            handle: &handle,
+        };
+        let response = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| next.run(invoke)))
+            .unwrap_or_else(|payload| self.answer_panic(&command, payload.as_ref()));
+
+        let elapsed = started.elapsed();
+        tracing::debug!(
+            status = response.status().as_u16(),
+            ?elapsed,
+            "invoke answered"
+        );
+        for observer in self.observers.iter() {
+            observer(&command, elapsed, response.status());
+        }
+        response
+    }
+
+    fn answer_panic(
+        &self,
+        command: &str,
+        payload: &(dyn std::any::Any + Send),
+    ) -> http::Response<Vec<u8>> {
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("the panic payload is not a string");
+        tracing::error!("command `{command}` panicked: {message}");
+        if let Some(hook) = &self.panic_hook {
+            hook(command, message);
+        }
+        ipc::invoke_error(
+            &ipc::CommandError::new("panic", format!("command `{command}` panicked: {message}"))
+                .with_data(serde_json::json!({ "command": command })),
+        )
+    }
+}
+
+/// Tries `first`, then `second` when `first` doesn't know the command.
+fn chain_invoke_handlers(first: InvokeHandler, second: InvokeHandler) -> InvokeHandler {
+    Arc::new(move |invoke: Invoke| {
+        let Invoke {
+            command,
+            request,
+            webview_label,
+        } = invoke;
+        first(Invoke {
+            command: command.clone(),
+            request: request.clone(),
+            webview_label: webview_label.clone(),
+        })
+        .or_else(|| {
+            second(Invoke {
+                command,
+                request,
+                webview_label,
+            })
+        })
+    })
+}
+
+/// Runs `command` through the invoke handler, falling back to the API handler registered under
+/// the same name.
+fn call_handlers(
+    invoke_handler: Option<InvokeHandler>,
+    api_handler: Option<ApiHandler>,
+    command: String,
+    request: http::Request<Vec<u8>>,
+    webview_label: Option<String>,
+) -> http::Response<Vec<u8>> {
+    if let Some(handler) = invoke_handler {
+        if let Some(api_handler) = api_handler {
+            let request_for_invoke = request.clone();
+            handler(Invoke {
+                command,
+                request: request_for_invoke,
+                webview_label,
+            })
+            .unwrap_or_else(|| api_handler(request))
+        } else {
+            handler(Invoke {
+                command: command.clone(),
+                request,
+                webview_label,
+            })
+            .unwrap_or_else(|| ipc::not_found(command))
+        }
+    } else if let Some(api_handler) = api_handler {
+        api_handler(request)
+    } else {
+        ipc::not_found(command)
+    }
+}
+
+/// Where the answer to an IPC postMessage goes.
+struct PostMessageCallback {
+    command: String,
+    callback: u32,
+    error: u32,
+    webview_label: Option<String>,
+}
+
+impl PostMessageCallback {
+    /// Like [`PostMessageCallback::run`], from any thread.
+    fn answer(self, response: http::Response<Vec<u8>>) {
+        if WebviewManager::global().is_main_thread() {
+            self.run(response);
+        } else if let Err(err) = ipc::dispatch_on_main_thread(move || self.run(response)) {
+            tracing::warn!("cannot answer IPC postMessage: {err}");
+        }
+    }
+
+    fn run(self, response: http::Response<Vec<u8>>) {
+        let (parts, body) = response.into_parts();
+        let response_header = parts
+            .headers
+            .get("Tauri-Response")
+            .and_then(|value| value.to_str().ok());
+        let callback_id = if response_header == Some("ok") {
+            self.callback
+        } else {
+            self.error
+        };
+
+        let content_type = parts
+            .headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        let content_type = content_type.split(',').next().unwrap_or_default();
+
+        let js_arg = match content_type {
+            "application/json" => {
+                let data =
+                    serde_json::from_slice::<serde_json::Value>(&body).unwrap_or_else(|_| {
+                        serde_json::Value::String(String::from_utf8_lossy(&body).into_owned())
+                    });
+                serde_json::to_string(&data).unwrap_or_else(|_| "null".to_string())
+            }
+            "text/plain" => serde_json::to_string(&String::from_utf8_lossy(&body))
+                .unwrap_or_else(|_| "null".to_string()),
+            _ => {
+                let bytes_as_json_array =
+                    serde_json::to_string(&body).unwrap_or_else(|_| "[]".to_string());
+                format!("new Uint8Array({bytes_as_json_array}).buffer")
+            }
+        };
+
+        let js = format!("window.__TAURI_INTERNALS__.runCallback({callback_id}, {js_arg});");
+
+        let Some(webview) = WebviewManager::global().webview(self.webview_label.as_deref()) else {
+            tracing::warn!(
+                "IPC postMessage fallback used but webview {:?} is gone; cannot run callback for `{}`",
+                self.webview_label,
+                self.command
+            );
+            return;
+        };
+
+        let _ = webview.evaluate_script(&js);
+    }
+}
+
 fn decode_uri_component(input: &str) -> String {
     let bytes = input.as_bytes();
     let mut out = Vec::with_capacity(bytes.len());
@@ -514,7 +1337,7 @@ pub mod ipc {
     use super::*;
     use http::HeaderValue;
     use std::collections::VecDeque;
-    use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+    use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
     use std::sync::{Arc, Mutex, OnceLock};
     use std::time::{Duration, Instant};
 
@@ -533,6 +1356,8 @@ pub mod ipc {
     const CHANNEL_DATA_TTL: Duration = Duration::from_secs(60);
     const CHANNEL_DATA_MAX_ENTRIES: usize = 128;
     const CHANNEL_DATA_MAX_BYTES: usize = 128 * 1024 * 1024;
+    /// How long a handler waits for work it handed to the main thread.
+    pub(crate) const MAIN_THREAD_TIMEOUT: Duration = Duration::from_secs(10);
 
     static PLATFORM_DISPATCHER: OnceLock<Arc<dyn gpui::PlatformDispatcher>> = OnceLock::new();
     static CHANNEL_DATA_COUNTER: AtomicU32 = AtomicU32::new(0);
@@ -586,34 +1411,123 @@ pub mod ipc {
         }
     }
 
-    fn current_webview_label() -> Option<String> {
+    pub(crate) fn current_webview_label() -> Option<String> {
         CURRENT_WEBVIEW_LABEL.with(|label| label.borrow().clone())
     }
 
-    fn dispatch_eval_on_main_thread(
+    thread_local! {
+        static CURRENT_CAPABILITIES: std::cell::RefCell<Option<Arc<[String]>>> =
+            const { std::cell::RefCell::new(None) };
+    }
+
+    /// Sets the capabilities the invoking webview grants while its invoke runs.
+    pub(crate) struct CapabilitiesGuard {
+        previous_capabilities: Option<Arc<[String]>>,
+    }
+
+    impl CapabilitiesGuard {
+        pub(crate) fn new(capabilities: Option<Arc<[String]>>) -> Self {
+            let previous_capabilities =
+                CURRENT_CAPABILITIES.with(|current| current.replace(capabilities));
+            Self {
+                previous_capabilities,
+            }
+        }
+    }
+
+    impl Drop for CapabilitiesGuard {
+        fn drop(&mut self) {
+            let previous_capabilities = self.previous_capabilities.take();
+            CURRENT_CAPABILITIES.with(|current| {
+                current.replace(previous_capabilities);
+            });
+        }
+    }
+
+    /// Whether the invoking webview grants `capability`; see [`crate::Builder::with_capabilities`].
+    pub fn has_capability(capability: &str) -> bool {
+        CURRENT_CAPABILITIES.with(|current| match &*current.borrow() {
+            None => true,
+            Some(granted) => granted.iter().any(|granted| {
+                granted == "*"
+                    || granted == capability
+                    || granted.strip_suffix('*').is_some_and(|prefix| {
+                        prefix.ends_with(':') && capability.starts_with(prefix)
+                    })
+            }),
+        })
+    }
+
+    /// The `403` response for `command` when the invoking webview doesn't grant `capability`.
+    /// Called by `#[command(capability = "...")]`.
+    #[doc(hidden)]
+    pub fn capability_denied(command: &str, capability: &str) -> Option<http::Response<Vec<u8>>> {
+        if has_capability(capability) {
+            return None;
+        }
+        let label = current_webview_label().unwrap_or_default();
+        tracing::warn!(
+            "webview `{label}` invoked `{command}` without the `{capability}` capability"
+        );
+        Some(forbidden(format!(
+            "webview `{label}` is not allowed to call `{command}`, which needs the `{capability}` capability"
+        )))
+    }
+
+    pub(crate) fn dispatch_eval_on_main_thread(
         webview_label: Option<String>,
         js: String,
+    ) -> std::result::Result<(), String> {
+        dispatch_on_main_thread(move || {
+            let Some(webview) = WebviewManager::global().webview(webview_label.as_deref()) else {
+                tracing::warn!(
+                    "IPC requested JS eval but target webview is missing (label={webview_label:?})"
+                );
+                return;
+            };
+
+            if let Err(err) = webview.evaluate_script(&js) {
+                tracing::warn!("evaluate_script failed: {err}");
+            }
+        })
+    }
+
+    /// Runs `f` on the main thread with the webview `webview_label`, or the invoking webview
+    /// when it is `None`, and waits for its result.
+    pub(crate) fn with_webview<T: Send + 'static>(
+        webview_label: Option<String>,
+        f: impl FnOnce(&wry::WebView) -> T + Send + 'static,
+    ) -> std::result::Result<T, String> {
+        let webview_label = webview_label.or_else(current_webview_label);
+        let run = move || {
+            WebviewManager::global()
+                .webview(webview_label.as_deref())
+                .map(|webview| f(&webview))
+                .ok_or_else(|| format!("target webview is missing (label={webview_label:?})"))
+        };
+        if WebviewManager::global().is_main_thread() {
+            return run();
+        }
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        dispatch_on_main_thread(move || {
+            let _ = tx.send(run());
+        })?;
+        rx.recv_timeout(MAIN_THREAD_TIMEOUT)
+            .map_err(|_| "the main thread did not answer in time".to_string())?
+    }
+
+    pub(crate) fn dispatch_on_main_thread(
+        f: impl FnOnce() + Send + 'static,
     ) -> std::result::Result<(), String> {
         let dispatcher = PLATFORM_DISPATCHER.get().cloned().ok_or_else(|| {
             "gpui platform dispatcher is not initialized (create a gpui_manos_webview::webview::WebView first)"
                 .to_string()
         })?;
 
-        let (runnable, task) = async_task::spawn(
-            async move {
-                let Some(webview) = super::ipc_webview_for_label(webview_label.as_deref()) else {
-                    eprintln!(
-                        "[webview] IPC requested JS eval but target webview is missing (label={webview_label:?})"
-                    );
-                    return;
-                };
-
-                if let Err(err) = webview.evaluate_script(&js) {
-                    eprintln!("[webview] evaluate_script failed: {err}");
-                }
-            },
-            move |runnable| dispatcher.dispatch_on_main_thread(runnable),
-        );
+        let (runnable, task) = async_task::spawn(async move { f() }, move |runnable| {
+            dispatcher.dispatch_on_main_thread(runnable)
+        });
 
         runnable.schedule();
         task.detach();
@@ -622,6 +1536,7 @@ pub mod ipc {
 
     pub(crate) fn validate_custom_protocol_request(
         request: &http::Request<Vec<u8>>,
+        expected_invoke_key: &str,
     ) -> std::result::Result<(), http::Response<Vec<u8>>> {
         fn parse_u32_header(
             headers: &http::HeaderMap,
@@ -649,7 +1564,7 @@ pub mod ipc {
                     "{TAURI_INVOKE_KEY_HEADER_NAME} header value must be a string"
                 ))
             })?;
-        if invoke_key != INVOKE_KEY {
+        if invoke_key != expected_invoke_key {
             return Err(bad_request("invalid invoke key"));
         }
 
@@ -798,102 +1713,228 @@ pub mod ipc {
         }
     }
 
-    #[derive(Debug, Deserialize)]
-    #[serde(rename_all = "camelCase")]
-    struct WebviewZoomPayload {
-        #[serde(default)]
-        label: Option<String>,
-        value: f64,
+    #[derive(Debug)]
+    pub struct Request {
+        parts: http::request::Parts,
+        body: Vec<u8>,
+        webview_label: Option<String>,
     }
 
-    fn dispatch_zoom_on_main_thread(
-        webview_label: Option<String>,
-        zoom_factor: f64,
-    ) -> std::result::Result<(), String> {
-        let dispatcher = PLATFORM_DISPATCHER.get().cloned().ok_or_else(|| {
-            "gpui platform dispatcher is not initialized (create a gpui_manos_webview::webview::WebView first)"
-                .to_string()
-        })?;
+    impl Request {
+        pub fn new(parts: http::request::Parts, body: Vec<u8>) -> Self {
+            Self {
+                parts,
+                body,
+                webview_label: current_webview_label(),
+            }
+        }
 
-        let (runnable, task) = async_task::spawn(
-            async move {
-                let Some(webview) = super::ipc_webview_for_label(webview_label.as_deref()) else {
-                    eprintln!(
-                        "[webview] IPC requested zoom but target webview is missing (label={webview_label:?})"
-                    );
-                    return;
-                };
+        pub fn method(&self) -> &http::Method {
+            &self.parts.method
+        }
 
-                if let Err(err) = webview.zoom(zoom_factor) {
-                    eprintln!("[webview] zoom failed: {err}");
-                }
-            },
-            move |runnable| dispatcher.dispatch_on_main_thread(runnable),
-        );
+        pub fn uri(&self) -> &http::Uri {
+            &self.parts.uri
+        }
+
+        pub fn headers(&self) -> &http::HeaderMap {
+            &self.parts.headers
+        }
+
+        pub fn body(&self) -> &[u8] {
+            &self.body
+        }
+
+        pub fn into_body(self) -> Vec<u8> {
+            self.body
+        }
+
+        /// The label of the webview that invoked the command, if it came from one.
+        pub fn webview_label(&self) -> Option<&str> {
+            self.webview_label.as_deref()
+        }
+    }
+
+    /// The headers of the invoke, as a `#[command]` parameter.
+    #[derive(Debug, Clone, Default)]
+    pub struct Headers(pub http::HeaderMap);
+
+    impl Headers {
+        pub fn new(headers: http::HeaderMap) -> Self {
+            Self(headers)
+        }
+
+        pub fn into_inner(self) -> http::HeaderMap {
+            self.0
+        }
+    }
+
+    impl std::ops::Deref for Headers {
+        type Target = http::HeaderMap;
+
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    /// The label of the webview that invoked the command, as a `#[command]` parameter. It is
+    /// empty when the invoke did not come from a webview, e.g. when a test calls the handler.
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+    pub struct WebviewLabel(pub String);
+
+    impl WebviewLabel {
+        pub fn current() -> Self {
+            Self(current_webview_label().unwrap_or_default())
+        }
+
+        pub fn as_str(&self) -> &str {
+            &self.0
+        }
+
+        pub fn into_inner(self) -> String {
+            self.0
+        }
+    }
+
+    impl std::ops::Deref for WebviewLabel {
+        type Target = str;
+
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    impl std::fmt::Display for WebviewLabel {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(&self.0)
+        }
+    }
+
+    /// Tells a command that its invoke timed out and nobody waits for its result anymore, as a
+    /// `#[command]` parameter; see [`crate::Builder::command_timeout`]. Long-running commands
+    /// check [`CancellationToken::is_cancelled`] or await [`CancellationToken::cancelled`] to
+    /// stop early.
+    #[derive(Debug, Clone, Default)]
+    pub struct CancellationToken {
+        state: Arc<CancellationState>,
+    }
+
+    #[derive(Debug, Default)]
+    struct CancellationState {
+        cancelled: AtomicBool,
+        wakers: Mutex<Vec<std::task::Waker>>,
+    }
 
-        runnable.schedule();
-        task.detach();
-        Ok(())
+    thread_local! {
+        static CURRENT_CANCELLATION: std::cell::RefCell<Option<CancellationToken>> =
+            const { std::cell::RefCell::new(None) };
     }
 
-    pub(crate) fn set_webview_zoom(request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
-        let payload = match serde_json::from_slice::<WebviewZoomPayload>(request.body()) {
-            Ok(payload) => payload,
-            Err(err) => {
-                return bad_request(format!(
-                    "invalid JSON body for plugin:webview|set_webview_zoom: {err}"
-                ));
-            }
-        };
+    impl CancellationToken {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// The token of the invoke running on this thread, or one that is never cancelled.
+        pub fn current() -> Self {
+            CURRENT_CANCELLATION
+                .with(|current| current.borrow().clone())
+                .unwrap_or_default()
+        }
 
-        if !payload.value.is_finite() || payload.value <= 0.0 {
-            return bad_request("zoom value must be a positive, finite number");
+        pub fn cancel(&self) {
+            self.state.cancelled.store(true, Ordering::Release);
+            for waker in std::mem::take(&mut *self.state.wakers.lock().unwrap()) {
+                waker.wake();
+            }
         }
 
-        let target = payload.label.or_else(current_webview_label);
-        if let Err(err) = dispatch_zoom_on_main_thread(target, payload.value) {
-            return internal_error(err);
+        pub fn is_cancelled(&self) -> bool {
+            self.state.cancelled.load(Ordering::Acquire)
         }
 
-        ok_json(&())
+        /// Resolves once the token is cancelled.
+        pub fn cancelled(&self) -> Cancelled {
+            Cancelled {
+                token: self.clone(),
+            }
+        }
     }
 
+    /// The future of [`CancellationToken::cancelled`].
     #[derive(Debug)]
-    pub struct Request {
-        parts: http::request::Parts,
-        body: Vec<u8>,
+    pub struct Cancelled {
+        token: CancellationToken,
     }
 
-    impl Request {
-        pub fn new(parts: http::request::Parts, body: Vec<u8>) -> Self {
-            Self { parts, body }
-        }
+    impl std::future::Future for Cancelled {
+        type Output = ();
 
-        pub fn method(&self) -> &http::Method {
-            &self.parts.method
+        fn poll(
+            self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<()> {
+            if self.token.is_cancelled() {
+                return std::task::Poll::Ready(());
+            }
+            let mut wakers = self.token.state.wakers.lock().unwrap();
+            if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                wakers.push(cx.waker().clone());
+            }
+            drop(wakers);
+            // `cancel` may have run between the check and the registration.
+            if self.token.is_cancelled() {
+                std::task::Poll::Ready(())
+            } else {
+                std::task::Poll::Pending
+            }
         }
+    }
 
-        pub fn uri(&self) -> &http::Uri {
-            &self.parts.uri
-        }
+    /// Makes `token` the [`CancellationToken::current`] one while an invoke runs.
+    pub(crate) struct CancellationGuard {
+        previous: Option<CancellationToken>,
+    }
 
-        pub fn headers(&self) -> &http::HeaderMap {
-            &self.parts.headers
+    impl CancellationGuard {
+        pub(crate) fn new(token: CancellationToken) -> Self {
+            let previous = CURRENT_CANCELLATION.with(|current| current.replace(Some(token)));
+            Self { previous }
         }
+    }
 
-        pub fn body(&self) -> &[u8] {
-            &self.body
+    impl Drop for CancellationGuard {
+        fn drop(&mut self) {
+            let previous = self.previous.take();
+            CURRENT_CANCELLATION.with(|current| {
+                current.replace(previous);
+            });
         }
+    }
 
-        pub fn into_body(self) -> Vec<u8> {
-            self.body
-        }
+    /// The `timeout` [`InvokeError`] of an invoke of `command` that ran past `timeout`.
+    pub(crate) fn timeout_error(command: &str, timeout: Duration) -> http::Response<Vec<u8>> {
+        invoke_error(
+            &CommandError::new(
+                "timeout",
+                format!("command `{command}` did not finish within {timeout:?}"),
+            )
+            .with_status(http::StatusCode::GATEWAY_TIMEOUT)
+            .with_data(serde_json::json!({
+                "command": command,
+                "timeoutMs": timeout.as_millis() as u64,
+            })),
+        )
     }
 
+    /// A command result sent as is rather than serialized to JSON, e.g. a generated image or a
+    /// file to download. `invoke` resolves with an `ArrayBuffer`, or with the parsed value for
+    /// `application/json`.
     #[derive(Debug)]
     pub struct Response {
         body: Vec<u8>,
         content_type: String,
+        headers: http::HeaderMap,
     }
 
     impl Response {
@@ -901,6 +1942,7 @@ pub mod ipc {
             Self {
                 body: body.into(),
                 content_type: content_type.into(),
+                headers: http::HeaderMap::new(),
             }
         }
 
@@ -908,6 +1950,41 @@ pub mod ipc {
             Self::new(body, "application/octet-stream")
         }
 
+        /// Adds a header to the `ipc://` response, for code that reads the response itself,
+        /// such as middleware or a `fetch` of the invoke URL. Invalid names and values, and the
+        /// headers the IPC protocol sets itself, are dropped. Channels ignore headers.
+        pub fn with_header<K, V>(mut self, name: K, value: V) -> Self
+        where
+            http::header::HeaderName: TryFrom<K>,
+            HeaderValue: TryFrom<V>,
+        {
+            let name = http::header::HeaderName::try_from(name).ok();
+            let value = HeaderValue::try_from(value).ok();
+            match name.zip(value) {
+                Some((name, value)) if !Self::is_reserved_header(&name) => {
+                    self.headers.append(name, value);
+                }
+                _ => tracing::warn!("dropped an invalid or reserved IPC response header"),
+            }
+            self
+        }
+
+        /// Marks the response as a file to save as `file_name`.
+        pub fn attachment(self, file_name: &str) -> Self {
+            let file_name = file_name.replace(['"', '\\', '\r', '\n'], "_");
+            self.with_header(
+                http::header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{file_name}\""),
+            )
+        }
+
+        fn is_reserved_header(name: &http::header::HeaderName) -> bool {
+            name == CONTENT_TYPE
+                || name == ACCESS_CONTROL_ALLOW_ORIGIN
+                || name == http::header::ACCESS_CONTROL_EXPOSE_HEADERS
+                || name.as_str().eq_ignore_ascii_case("Tauri-Response")
+        }
+
         fn into_http_response(self) -> http::Response<Vec<u8>> {
             let mut builder = response_builder(http::StatusCode::OK, "ok");
             builder = builder.header(
@@ -915,7 +1992,9 @@ pub mod ipc {
                 HeaderValue::from_str(&self.content_type)
                     .unwrap_or_else(|_| HeaderValue::from_static("application/octet-stream")),
             );
-            builder.body(self.body).unwrap()
+            let mut response = builder.body(self.body).unwrap();
+            response.headers_mut().extend(self.headers);
+            response
         }
     }
 
@@ -1108,6 +2187,22 @@ pub mod ipc {
             .unwrap()
     }
 
+    pub fn forbidden<S: ToString>(message: S) -> http::Response<Vec<u8>> {
+        response_builder(http::StatusCode::FORBIDDEN, "error")
+            .header(CONTENT_TYPE, HeaderValue::from_static("text/plain"))
+            .body(message.to_string().into_bytes())
+            .unwrap()
+    }
+
+    /// `503` with `Retry-After: 1`, for invokes turned away because too many are waiting.
+    pub fn service_unavailable<S: ToString>(message: S) -> http::Response<Vec<u8>> {
+        response_builder(http::StatusCode::SERVICE_UNAVAILABLE, "error")
+            .header(CONTENT_TYPE, HeaderValue::from_static("text/plain"))
+            .header(http::header::RETRY_AFTER, HeaderValue::from_static("1"))
+            .body(message.to_string().into_bytes())
+            .unwrap()
+    }
+
     pub fn internal_error_json<T: serde::Serialize>(value: &T) -> http::Response<Vec<u8>> {
         match serde_json::to_vec(value) {
             Ok(body) => response_builder(http::StatusCode::INTERNAL_SERVER_ERROR, "error")
@@ -1118,6 +2213,158 @@ pub mod ipc {
         }
     }
 
+    /// An error a command rejects with as a structured `{ code, message, data }` object instead
+    /// of a string.
+    ///
+    /// `#[command]` functions returning `Result<T, E>` pick this up for any `E: InvokeError`,
+    /// whatever their `error` argument says. Other handlers answer with [`invoke_error`].
+    pub trait InvokeError {
+        /// A stable, machine-readable identifier, e.g. `"not_found"`.
+        fn code(&self) -> &str;
+
+        fn message(&self) -> String;
+
+        /// Details for the frontend, left out of the payload when `None`.
+        fn data(&self) -> Option<serde_json::Value> {
+            None
+        }
+
+        fn status(&self) -> http::StatusCode {
+            http::StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+
+    /// The payload an [`InvokeError`] rejects with.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct InvokeErrorPayload {
+        pub code: String,
+        pub message: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub data: Option<serde_json::Value>,
+    }
+
+    impl InvokeErrorPayload {
+        pub fn new(error: &(impl InvokeError + ?Sized)) -> Self {
+            Self {
+                code: error.code().to_string(),
+                message: error.message(),
+                data: error.data(),
+            }
+        }
+    }
+
+    /// The error response for `error`, with its status and JSON payload.
+    pub fn invoke_error(error: &(impl InvokeError + ?Sized)) -> http::Response<Vec<u8>> {
+        match serde_json::to_vec(&InvokeErrorPayload::new(error)) {
+            Ok(body) => response_builder(error.status(), "error")
+                .header(CONTENT_TYPE, HeaderValue::from_static("application/json"))
+                .body(body)
+                .unwrap(),
+            Err(err) => internal_error(err),
+        }
+    }
+
+    /// A ready-made [`InvokeError`] for commands that don't have an error type of their own.
+    #[derive(Debug, Clone)]
+    pub struct CommandError {
+        code: String,
+        message: String,
+        // Boxed to keep `Result<T, CommandError>` small.
+        data: Option<Box<serde_json::Value>>,
+        status: http::StatusCode,
+    }
+
+    impl CommandError {
+        pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self {
+            Self {
+                code: code.into(),
+                message: message.into(),
+                data: None,
+                status: http::StatusCode::INTERNAL_SERVER_ERROR,
+            }
+        }
+
+        pub fn with_data(mut self, data: impl serde::Serialize) -> Self {
+            self.data = serde_json::to_value(data).ok().map(Box::new);
+            self
+        }
+
+        pub fn with_status(mut self, status: http::StatusCode) -> Self {
+            self.status = status;
+            self
+        }
+    }
+
+    impl InvokeError for CommandError {
+        fn code(&self) -> &str {
+            &self.code
+        }
+
+        fn message(&self) -> String {
+            self.message.clone()
+        }
+
+        fn data(&self) -> Option<serde_json::Value> {
+            self.data.as_deref().cloned()
+        }
+
+        fn status(&self) -> http::StatusCode {
+            self.status
+        }
+    }
+
+    impl std::fmt::Display for CommandError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}: {}", self.code, self.message)
+        }
+    }
+
+    impl std::error::Error for CommandError {}
+
+    /// How `#[command]` turns the error of a command into a response: through [`InvokeError`]
+    /// when the error implements it, otherwise as a string or as JSON. The method call
+    /// `(&&ErrorRef(&err)).into_response(..)` finds the `InvokeError` impl first, as it needs one
+    /// reference less.
+    #[doc(hidden)]
+    pub mod command_error {
+        use super::*;
+
+        pub struct ErrorRef<'a, E>(pub &'a E);
+
+        pub trait ViaInvokeError {
+            fn into_response(self, command: &str) -> http::Response<Vec<u8>>;
+        }
+
+        impl<E: InvokeError> ViaInvokeError for &&ErrorRef<'_, E> {
+            fn into_response(self, _: &str) -> http::Response<Vec<u8>> {
+                invoke_error(self.0)
+            }
+        }
+
+        pub trait ViaToString {
+            fn into_response(self, command: &str) -> http::Response<Vec<u8>>;
+        }
+
+        impl<E: ToString> ViaToString for &ErrorRef<'_, E> {
+            fn into_response(self, command: &str) -> http::Response<Vec<u8>> {
+                internal_error_json(&format!(
+                    "command `{command}` failed: {}",
+                    self.0.to_string()
+                ))
+            }
+        }
+
+        pub trait ViaSerialize {
+            fn into_response(self, command: &str) -> http::Response<Vec<u8>>;
+        }
+
+        impl<E: serde::Serialize> ViaSerialize for &ErrorRef<'_, E> {
+            fn into_response(self, _: &str) -> http::Response<Vec<u8>> {
+                internal_error_json(self.0)
+            }
+        }
+    }
+
     pub fn internal_error<S: ToString>(message: S) -> http::Response<Vec<u8>> {
         response_builder(http::StatusCode::INTERNAL_SERVER_ERROR, "error")
             .header(CONTENT_TYPE, HeaderValue::from_static("text/plain"))
@@ -1133,14 +2380,48 @@ pub mod ipc {
     }
 }
 
+/// Forwards each request to `dev_server` on its own thread, as the dev server may take a while.
+fn dev_server_protocol(
+    dev_server: &DevServer,
+) -> impl Fn(WebViewId, http::Request<Vec<u8>>, wry::RequestAsyncResponder) + 'static {
+    let dev_server = dev_server.clone();
+    move |_webview_id, request, responder| {
+        let dev_server = dev_server.clone();
+        std::thread::spawn(move || responder.respond(dev_server.forward(request)));
+    }
+}
+
+/// Serves a static asset through `cache` when it has a route for the asset's path.
+fn serve_static_cached(
+    cache: Option<&ResponseCache>,
+    webview_id: WebViewId,
+    static_root: String,
+    request: http::Request<Vec<u8>>,
+    index_fallback: IndexFallback,
+) -> http::Response<Vec<u8>> {
+    // A cached entry holds the whole asset, which doesn't answer a range request.
+    let slot = cache
+        .filter(|_| !request.headers().contains_key(RANGE))
+        .and_then(|cache| Some((cache, cache.asset_slot(request.uri().path())?)));
+    let serve = move || {
+        serve_static(webview_id, static_root, request, index_fallback)
+            .unwrap_or_else(response_internal_server_err)
+    };
+    match slot {
+        Some((cache, slot)) => cache.respond(slot, serve),
+        None => serve(),
+    }
+}
+
 // todo: this is too simple, refactor it like Tauri
 fn serve_static<S: ToString>(
     webview_id: WebViewId,
     static_path: S,
     request: http::Request<Vec<u8>>,
+    index_fallback: IndexFallback,
 ) -> http::Result<http::Response<Vec<u8>>> {
     let path = request.uri().path();
-    println!(
+    tracing::debug!(
         "webview_id: {}, method: {:?}, scheme: {:?}, host: {:?}, path: {:?}",
         webview_id,
         request.method(),
@@ -1153,30 +2434,42 @@ fn serve_static<S: ToString>(
     let root = match fs::canonicalize(&static_root) {
         Ok(root) => root,
         Err(err) => {
-            println!("failed to canonicalize static root `{static_root}`: {err}");
+            tracing::error!("failed to canonicalize static root `{static_root}`: {err}");
             return Ok(response_internal_server_err("static root not accessible"));
         }
     };
 
-    match resolve_static_asset(&root, path) {
-        Ok(asset) => response_asset(asset),
+    match resolve_static_asset(&root, path, index_fallback) {
+        Ok(asset) if asset.is_fresh_for(&request) => Ok(response_not_modified(&asset)),
+        Ok(asset) => {
+            let read = match asset.requested_range(&request) {
+                ByteRange::Full => fs::read(&asset.path).map(|bytes| response_asset(asset, bytes)),
+                ByteRange::Partial(range) => read_range(&asset.path, range.clone())
+                    .map(|bytes| response_partial_asset(asset, range, bytes)),
+                ByteRange::Unsatisfiable => return Ok(response_range_not_satisfiable(&asset)),
+            };
+            read.unwrap_or_else(|err| {
+                tracing::error!("failed to read static asset: {err}");
+                Ok(response_internal_server_err("failed to read static asset"))
+            })
+        }
         Err(StaticAssetError::NotFound(requested)) => {
-            println!("static asset not found: {}", requested.display());
+            tracing::debug!("static asset not found: {}", requested.display());
             Ok(response_not_found(requested.display()))
         }
         Err(StaticAssetError::OutsideRoot(requested)) => {
-            println!(
+            tracing::warn!(
                 "attempt to read outside static root: {}",
                 requested.display()
             );
             Ok(response_forbidden(requested.display()))
         }
         Err(StaticAssetError::IsDirectory(requested)) => {
-            println!("requested path is a directory: {}", requested.display());
+            tracing::debug!("requested path is a directory: {}", requested.display());
             Ok(response_not_found(requested.display()))
         }
         Err(StaticAssetError::Io(err)) => {
-            println!("failed to read static asset: {err}");
+            tracing::error!("failed to read static asset: {err}");
             Ok(response_internal_server_err("failed to read static asset"))
         }
     }
@@ -1199,9 +2492,170 @@ fn response_internal_server_err<S: ToString>(content: S) -> http::Response<Vec<u
         .unwrap()
 }
 
+/// Static assets may change on disk at any time, so the webview revalidates them on every use;
+/// an unchanged asset costs a `304` and no read.
+const STATIC_CACHE_CONTROL: &str = "no-cache";
+
+/// The most an open-ended range (`bytes=N-`) gets in one response. Media elements ask for the
+/// rest as they play, so a video is never read into memory whole.
+const MAX_OPEN_RANGE_LEN: u64 = 8 * 1024 * 1024;
+
+/// The part of an asset a request asks for with its `Range` header.
+enum ByteRange {
+    Full,
+    Partial(Range<u64>),
+    Unsatisfiable,
+}
+
+/// Which missing paths the static asset server answers with `index.html`; see
+/// [`Builder::index_fallback`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IndexFallback {
+    /// Every missing path, so history-mode client-side routing works out of the box.
+    #[default]
+    Always,
+    /// Missing paths without a file extension only; missing files get `404`.
+    Routes,
+    /// None; missing paths get `404`.
+    Never,
+}
+
+impl IndexFallback {
+    /// Whether a missing path falls back to `index.html`.
+    pub(crate) fn covers(self, has_extension: bool) -> bool {
+        match self {
+            IndexFallback::Always => true,
+            IndexFallback::Routes => !has_extension,
+            IndexFallback::Never => false,
+        }
+    }
+}
+
+/// A resolved static asset, read only once a conditional request turns out to need it.
 struct StaticAsset {
-    bytes: Vec<u8>,
+    path: PathBuf,
     mime: String,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl StaticAsset {
+    /// A weak validator from the size and modification time, which are known without reading
+    /// the file.
+    fn etag(&self) -> String {
+        let modified = self
+            .modified
+            .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+            .unwrap_or_default();
+        format!(
+            "W/\"{:x}-{:x}.{:x}\"",
+            self.len,
+            modified.as_secs(),
+            modified.subsec_nanos()
+        )
+    }
+
+    /// Whether the webview's copy, named by `If-None-Match` or `If-Modified-Since`, is current.
+    fn is_fresh_for(&self, request: &http::Request<Vec<u8>>) -> bool {
+        if !matches!(*request.method(), http::Method::GET | http::Method::HEAD) {
+            return false;
+        }
+
+        let headers = request.headers();
+        if let Some(if_none_match) = headers.get(IF_NONE_MATCH) {
+            let etag = self.etag();
+            let etag = etag.trim_start_matches("W/");
+            return if_none_match.to_str().is_ok_and(|tags| {
+                tags.split(',').map(str::trim).any(|tag| {
+                    // `If-None-Match` uses the weak comparison.
+                    tag == "*" || tag.trim_start_matches("W/") == etag
+                })
+            });
+        }
+
+        let since = headers
+            .get(IF_MODIFIED_SINCE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(http_date::parse);
+        match (since, self.modified) {
+            // `Last-Modified` has whole seconds, so compare at that precision.
+            (Some(since), Some(modified)) => unix_secs(modified) <= unix_secs(since),
+            _ => false,
+        }
+    }
+
+    /// Only single `bytes` ranges are honored; other and malformed ranges get the whole asset,
+    /// as does an `If-Range` that doesn't match this version of it.
+    fn requested_range(&self, request: &http::Request<Vec<u8>>) -> ByteRange {
+        if request.method() != http::Method::GET {
+            return ByteRange::Full;
+        }
+        let headers = request.headers();
+        let Some(spec) = headers
+            .get(RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().strip_prefix("bytes="))
+        else {
+            return ByteRange::Full;
+        };
+        if let Some(if_range) = headers.get(IF_RANGE) {
+            // A weak `ETag` never matches `If-Range`, so only a date can.
+            let matches = if_range
+                .to_str()
+                .ok()
+                .and_then(http_date::parse)
+                .zip(self.modified)
+                .is_some_and(|(date, modified)| unix_secs(date) == unix_secs(modified));
+            if !matches {
+                return ByteRange::Full;
+            }
+        }
+        let Some((start, end)) = spec.split_once('-') else {
+            return ByteRange::Full;
+        };
+        if spec.contains(',') {
+            return ByteRange::Full;
+        }
+
+        let len = self.len;
+        let (start, end) = (start.trim(), end.trim());
+        let range = if start.is_empty() {
+            // `bytes=-N` is the last N bytes.
+            match end.parse::<u64>() {
+                Ok(0) => return ByteRange::Unsatisfiable,
+                Ok(suffix) => len.saturating_sub(suffix)..len,
+                Err(_) => return ByteRange::Full,
+            }
+        } else {
+            let Ok(start) = start.parse::<u64>() else {
+                return ByteRange::Full;
+            };
+            let end = if end.is_empty() {
+                start.saturating_add(MAX_OPEN_RANGE_LEN)
+            } else {
+                match end.parse::<u64>() {
+                    Ok(end) if end >= start => end.saturating_add(1),
+                    _ => return ByteRange::Full,
+                }
+            };
+            start..end.min(len)
+        };
+        if range.start >= len {
+            ByteRange::Unsatisfiable
+        } else {
+            ByteRange::Partial(range)
+        }
+    }
+
+    fn validator_headers(&self, mut builder: http::response::Builder) -> http::response::Builder {
+        builder = builder
+            .header(ETAG, self.etag())
+            .header(CACHE_CONTROL, STATIC_CACHE_CONTROL);
+        if let Some(modified) = self.modified {
+            builder = builder.header(LAST_MODIFIED, http_date::format(modified));
+        }
+        builder
+    }
 }
 
 enum StaticAssetError {
@@ -1214,6 +2668,7 @@ enum StaticAssetError {
 fn resolve_static_asset(
     root: &Path,
     uri_path: &str,
+    index_fallback: IndexFallback,
 ) -> std::result::Result<StaticAsset, StaticAssetError> {
     fn resolve_candidate(
         root: &Path,
@@ -1233,17 +2688,22 @@ fn resolve_static_asset(
             return Err(StaticAssetError::OutsideRoot(relative.to_path_buf()));
         }
 
-        if resolved.is_dir() {
+        let metadata = fs::metadata(&resolved).map_err(StaticAssetError::Io)?;
+        if metadata.is_dir() {
             return Err(StaticAssetError::IsDirectory(relative.to_path_buf()));
         }
 
-        let bytes = fs::read(&resolved).map_err(StaticAssetError::Io)?;
         let mime = mime_guess::from_path(&resolved)
             .first_or_octet_stream()
             .essence_str()
             .to_string();
 
-        Ok(StaticAsset { bytes, mime })
+        Ok(StaticAsset {
+            path: resolved,
+            mime,
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
     }
 
     let relative = sanitize_path(uri_path)?;
@@ -1259,7 +2719,9 @@ fn resolve_static_asset(
     index_fallback.push("index.html");
     candidates.push(index_fallback);
 
-    candidates.push(PathBuf::from("index.html"));
+    if index_fallback.covers(relative.extension().is_some()) {
+        candidates.push(PathBuf::from("index.html"));
+    }
 
     for candidate in candidates {
         match resolve_candidate(root, &candidate) {
@@ -1299,15 +2761,74 @@ fn sanitize_path(path: &str) -> std::result::Result<PathBuf, StaticAssetError> {
     Ok(buf)
 }
 
-fn response_asset(asset: StaticAsset) -> http::Result<http::Response<Vec<u8>>> {
-    http::Response::builder()
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+fn read_range(path: &Path, range: Range<u64>) -> io::Result<Vec<u8>> {
+    let mut file = fs::File::open(path)?;
+    file.seek(io::SeekFrom::Start(range.start))?;
+    let mut bytes = Vec::with_capacity((range.end - range.start) as usize);
+    file.take(range.end - range.start).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn response_asset(asset: StaticAsset, bytes: Vec<u8>) -> http::Result<http::Response<Vec<u8>>> {
+    asset
+        .validator_headers(http::Response::builder())
         .status(http::StatusCode::OK)
-        .header(CONTENT_TYPE, asset.mime)
+        .header(CONTENT_TYPE, &asset.mime)
+        .header(ACCEPT_RANGES, "bytes")
         .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
-        .body(asset.bytes)
+        .body(bytes)
         .map_err(Into::into)
 }
 
+fn response_partial_asset(
+    asset: StaticAsset,
+    range: Range<u64>,
+    bytes: Vec<u8>,
+) -> http::Result<http::Response<Vec<u8>>> {
+    // The file may have shrunk since it was resolved.
+    let end = range.start + bytes.len() as u64;
+    asset
+        .validator_headers(http::Response::builder())
+        .status(http::StatusCode::PARTIAL_CONTENT)
+        .header(CONTENT_TYPE, &asset.mime)
+        .header(ACCEPT_RANGES, "bytes")
+        .header(
+            CONTENT_RANGE,
+            format!(
+                "bytes {}-{}/{}",
+                range.start,
+                end.saturating_sub(1),
+                asset.len
+            ),
+        )
+        .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(bytes)
+}
+
+fn response_range_not_satisfiable(asset: &StaticAsset) -> http::Response<Vec<u8>> {
+    http::Response::builder()
+        .status(http::StatusCode::RANGE_NOT_SATISFIABLE)
+        .header(CONTENT_RANGE, format!("bytes */{}", asset.len))
+        .header(ACCEPT_RANGES, "bytes")
+        .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(Vec::new())
+        .unwrap()
+}
+
+fn response_not_modified(asset: &StaticAsset) -> http::Response<Vec<u8>> {
+    asset
+        .validator_headers(http::Response::builder())
+        .status(http::StatusCode::NOT_MODIFIED)
+        .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(Vec::new())
+        .unwrap()
+}
+
 fn response_forbidden<S: ToString>(content: S) -> http::Response<Vec<u8>> {
     http::Response::builder()
         .status(http::StatusCode::FORBIDDEN)
@@ -1320,17 +2841,19 @@ fn response_forbidden<S: ToString>(content: S) -> http::Response<Vec<u8>> {
 fn prepare_scripts(
     current_window_label: String,
     current_webview_label: String,
+    pattern: &Pattern,
+    invoke_key: &str,
 ) -> std::result::Result<Vec<InitializationScript>, Box<dyn std::error::Error>> {
     let current_window_label = serde_json::to_string(&current_window_label)?;
     let current_webview_label = serde_json::to_string(&current_webview_label)?;
 
     let ipc_init = IpcJavascript {
-        isolation_origin: "",
+        isolation_origin: pattern.isolation_origin(),
     }
     .render_default(&core::default::Default::default())?;
 
     let pattern_init = PatternJavascript {
-        pattern: PatternObject::Brownfield,
+        pattern: pattern.into(),
     }
     .render_default(&core::default::Default::default())?;
 
@@ -1365,9 +2888,27 @@ fn prepare_scripts(
     )));
 
     list.push(InitializationScript::main_frame_script(
-        initialization_script(&ipc_init.into_string(), &pattern_init.into_string())?,
+        initialization_script(
+            &ipc_init.into_string(),
+            &pattern_init.into_string(),
+            invoke_key,
+        )?,
     ));
 
+    #[cfg(feature = "isolation")]
+    if let Pattern::Isolation(isolation) = pattern {
+        list.push(InitializationScript::main_frame_script(
+            isolation.main_frame_script()?,
+        ));
+    }
+
+    // WKWebView ignores `window.print()`.
+    if cfg!(target_os = "macos") {
+        list.push(InitializationScript::main_frame_script(
+            include_str!("scripts/webview/print.js").to_string(),
+        ));
+    }
+
     list.push(InitializationScript::main_frame_script(
         HotkeyZoom {
             os_name: std::env::consts::OS,
@@ -1381,7 +2922,7 @@ fn prepare_scripts(
             process_ipc_message_fn: include_str!("scripts/tauri/process-ipc-message-fn.js"),
             os_name: std::env::consts::OS,
             fetch_channel_data_command: ipc::FETCH_CHANNEL_DATA_COMMAND,
-            invoke_key: INVOKE_KEY,
+            invoke_key,
         }
         .render_default(&core::default::Default::default())?
         .into_string(),
@@ -1436,38 +2977,89 @@ struct HotkeyZoom<'a> {
     os_name: &'a str,
 }
 
+/// How IPC messages get from the page to Rust.
+#[derive(Clone, Default)]
+enum Pattern {
+    /// Straight from the main frame.
+    #[default]
+    Brownfield,
+    /// Through the isolation frame; see [`Builder::isolation`].
+    #[cfg(feature = "isolation")]
+    Isolation(Arc<isolation::Isolation>),
+}
+
+impl Pattern {
+    fn isolation_origin(&self) -> &'static str {
+        match self {
+            Pattern::Brownfield => "",
+            #[cfg(feature = "isolation")]
+            Pattern::Isolation(_) => isolation::origin(),
+        }
+    }
+
+    /// The request as the command handlers should see it, or the response rejecting it.
+    fn open_ipc_request(
+        &self,
+        request: http::Request<Vec<u8>>,
+    ) -> std::result::Result<http::Request<Vec<u8>>, http::Response<Vec<u8>>> {
+        match self {
+            Pattern::Brownfield => Ok(request),
+            #[cfg(feature = "isolation")]
+            Pattern::Isolation(isolation) => isolation.open(request),
+        }
+    }
+}
+
 /// The shape of the JavaScript Pattern config
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "lowercase", tag = "pattern")]
 enum PatternObject {
     /// Brownfield pattern.
     Brownfield,
-    // todo: isolation mode
-    //
-    // Isolation pattern. Recommended for security purposes.
-    // #[cfg(feature = "isolation")]
-    // Isolation {
-    //     /// Which `IsolationSide` this `PatternObject` is getting injected into
-    //     side: IsolationSide,
-    // },
+    /// Isolation pattern. Recommended for security purposes.
+    #[cfg(feature = "isolation")]
+    Isolation {
+        /// Which `IsolationSide` this `PatternObject` is getting injected into
+        side: IsolationSide,
+    },
+}
+
+impl From<&Pattern> for PatternObject {
+    fn from(pattern: &Pattern) -> Self {
+        match pattern {
+            Pattern::Brownfield => PatternObject::Brownfield,
+            #[cfg(feature = "isolation")]
+            Pattern::Isolation(_) => PatternObject::Isolation {
+                side: IsolationSide::Original,
+            },
+        }
+    }
+}
+
+/// The frame a [`PatternObject`] is injected into. Only the main frame gets the pattern script;
+/// the isolation frame gets `isolation_secure.js` instead.
+#[cfg(feature = "isolation")]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum IsolationSide {
+    Original,
 }
 
 fn initialization_script(
     ipc_script: &str,
     pattern_script: &str,
+    invoke_key: &str,
     // use_https_scheme: bool, // todo: use_https_scheme
 ) -> std::result::Result<String, Box<dyn std::error::Error>> {
     let core_script = &CoreJavascript {
         os_name: std::env::consts::OS,
         protocol_scheme: "http",
-        invoke_key: INVOKE_KEY,
+        invoke_key,
     }
     .render_default(&core::default::Default::default())?
     .to_string();
-    let event_initialization_script = &event_initialization_script(
-        "__internal_unstable_listeners_function_id__",
-        "__internal_unstable_listeners_object_id__",
-    );
+    let event_initialization_script =
+        &event_initialization_script(events::LISTENERS_FUNCTION, events::LISTENERS_OBJECT);
     let freeze_prototype = false;
     let freeze_prototype = if freeze_prototype {
         include_str!("scripts/tauri/freeze_prototype.js")
@@ -1537,3 +3129,69 @@ struct InitJavascript<'a> {
     #[raw]
     freeze_prototype: &'a str,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_invoke_key_is_random_hex_and_stable() {
+        let key = default_invoke_key();
+        assert_eq!(key.len(), 32);
+        assert!(
+            key.bytes()
+                .all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+        );
+        assert_ne!(key, "0".repeat(32));
+        assert_eq!(default_invoke_key(), key);
+        assert_eq!(
+            std::thread::spawn(|| default_invoke_key().to_string())
+                .join()
+                .unwrap(),
+            key
+        );
+    }
+
+    /// postMessage invokes run on the worker pool, so the timeout answers while the command is
+    /// still running instead of waiting for the thread it blocks.
+    #[test]
+    fn post_message_timeout_answers_before_the_command_returns() {
+        let pipeline = InvokePipeline {
+            middleware: Vec::new().into(),
+            observers: Vec::new().into(),
+            panic_hook: None,
+            command_timeout: Some(Duration::from_millis(50)),
+            command_timeouts: Arc::default(),
+            cache: None,
+            capabilities: None,
+            invoke_handler: Some(Arc::new(|_| {
+                std::thread::sleep(Duration::from_secs(2));
+                Some(ipc::ok_json(&()))
+            })),
+        };
+        let workers = worker_pool::WorkerPool::new(worker_pool::Limits {
+            workers: 1,
+            queue: 1,
+        });
+        let (tx, rx) = std::sync::mpsc::channel();
+        workers.reserve().unwrap().run(move || {
+            pipeline.dispatch(
+                None,
+                Invoke {
+                    command: "slow".to_string(),
+                    request: http::Request::new(Vec::new()),
+                    webview_label: Some("main".to_string()),
+                },
+                "postMessage",
+                move |response| {
+                    let _ = tx.send(response.status());
+                },
+            );
+        });
+
+        let status = rx
+            .recv_timeout(Duration::from_secs(1))
+            .expect("the timeout should answer before the command returns");
+        assert_eq!(status, http::StatusCode::GATEWAY_TIMEOUT);
+    }
+}
@@ -0,0 +1,261 @@
+//! The webviews of the app and the windows they are in; see [`WebviewManager`].
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::rc::{Rc, Weak};
+use std::sync::{Mutex, OnceLock};
+use std::thread::{self, ThreadId};
+
+use gpui::{AnyWindowHandle, App, AppContext as _, AsyncApp, Window, WindowId};
+use serde::de::DeserializeOwned;
+
+use crate::eval::{self, ScriptResult};
+use crate::ipc;
+use crate::print::{self, PdfOptions};
+use crate::zoom;
+
+thread_local! {
+    /// The webviews by label. `wry` webviews can't leave the main thread, so this is only ever
+    /// filled there.
+    static WEBVIEWS: RefCell<HashMap<String, Weak<wry::WebView>>> =
+        RefCell::new(HashMap::new());
+    /// The app the windows belong to, set on the main thread with the first webview.
+    static APP: RefCell<Option<AsyncApp>> = const { RefCell::new(None) };
+}
+
+static MANAGER: OnceLock<WebviewManager> = OnceLock::new();
+
+/// Tracks every [`crate::webview::WebView`] by label, together with the window it is in.
+///
+/// Webviews register themselves when they are created and unregister when they are dropped.
+/// The manager can be used from any thread, e.g. from a command handler;
+/// [`WebviewManager::with_webview`] and [`WebviewManager::eval`] run on the main thread, where
+/// the webviews live.
+#[derive(Default)]
+pub struct WebviewManager {
+    windows: Mutex<HashMap<String, AnyWindowHandle>>,
+    main_thread: OnceLock<ThreadId>,
+}
+
+impl WebviewManager {
+    pub fn global() -> &'static Self {
+        MANAGER.get_or_init(Self::default)
+    }
+
+    /// The labels of the open webviews, sorted.
+    pub fn labels(&self) -> Vec<String> {
+        let mut labels: Vec<_> = self.windows.lock().unwrap().keys().cloned().collect();
+        labels.sort();
+        labels
+    }
+
+    pub fn contains(&self, label: &str) -> bool {
+        self.windows.lock().unwrap().contains_key(label)
+    }
+
+    /// The window the webview `label` is in.
+    pub fn window(&self, label: &str) -> Option<WindowId> {
+        self.window_handle(label).map(|window| window.window_id())
+    }
+
+    fn window_handle(&self, label: &str) -> Option<AnyWindowHandle> {
+        self.windows.lock().unwrap().get(label).copied()
+    }
+
+    /// The windows with at least one open webview.
+    pub fn windows(&self) -> Vec<WindowId> {
+        let mut windows: Vec<_> = self
+            .windows
+            .lock()
+            .unwrap()
+            .values()
+            .map(|window| window.window_id())
+            .collect();
+        windows.sort();
+        windows.dedup();
+        windows
+    }
+
+    /// The labels of the webviews in `window`, sorted.
+    pub fn webviews_in(&self, window: WindowId) -> Vec<String> {
+        let mut labels: Vec<_> = self
+            .windows
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, webview_window)| webview_window.window_id() == window)
+            .map(|(label, _)| label.clone())
+            .collect();
+        labels.sort();
+        labels
+    }
+
+    /// Runs `f` on the main thread with the webview `label` and waits for its result.
+    pub fn with_webview<T: Send + 'static>(
+        &self,
+        label: &str,
+        f: impl FnOnce(&wry::WebView) -> T + Send + 'static,
+    ) -> Result<T, String> {
+        ipc::with_webview(Some(label.to_string()), f)
+    }
+
+    /// Evaluates `js` in the webview `label` without waiting for it.
+    pub fn eval(&self, label: &str, js: impl Into<String>) -> Result<(), String> {
+        self.ensure_open(label)?;
+        ipc::dispatch_eval_on_main_thread(Some(label.to_string()), js.into())
+    }
+
+    /// Evaluates the JavaScript expression `js` in the webview `label` and resolves with its
+    /// value, awaiting it if it is a promise; see [`crate::eval`].
+    ///
+    /// ```ignore
+    /// let title: String = manager.eval_with_result("main", "document.title").await?;
+    /// ```
+    pub fn eval_with_result<T: DeserializeOwned>(&self, label: &str, js: &str) -> ScriptResult<T> {
+        eval::evaluate(label, js)
+    }
+
+    /// Zooms the webview `label` to `level`, clamped to [`zoom::MIN_ZOOM`] and
+    /// [`zoom::MAX_ZOOM`], and returns the level it got. `1.0` is the original size.
+    pub fn set_zoom(&self, label: &str, level: f64) -> Result<f64, String> {
+        self.ensure_open(label)?;
+        zoom::set(label, level)
+    }
+
+    /// Zooms the webview `label` in by [`zoom::ZOOM_STEP`] and returns the new level.
+    pub fn zoom_in(&self, label: &str) -> Result<f64, String> {
+        self.ensure_open(label)?;
+        zoom::step(label, 1.0)
+    }
+
+    /// Zooms the webview `label` out by [`zoom::ZOOM_STEP`] and returns the new level.
+    pub fn zoom_out(&self, label: &str) -> Result<f64, String> {
+        self.ensure_open(label)?;
+        zoom::step(label, -1.0)
+    }
+
+    /// The zoom level of the webview `label`, whether the app or the user set it, e.g. to
+    /// restore it with [`WebviewManager::set_zoom`] next time.
+    pub fn zoom_level(&self, label: &str) -> f64 {
+        zoom::level(label)
+    }
+
+    /// Shows the print dialog for the page of the webview `label`.
+    pub fn print(&self, label: &str) -> Result<(), String> {
+        self.with_webview(label, |webview| webview.print())?
+            .map_err(|err| err.to_string())
+    }
+
+    /// Writes the page of the webview `label` to the PDF file `path` and waits until it is
+    /// written; see [`crate::print`]. Can't be called on the main thread, which writes the PDF;
+    /// use [`crate::webview::WebView::print_to_pdf`] there.
+    pub fn print_to_pdf(
+        &self,
+        label: &str,
+        path: impl Into<PathBuf>,
+        options: PdfOptions,
+    ) -> Result<(), String> {
+        self.ensure_open(label)?;
+        print::export_and_wait(Some(label.to_string()), path.into(), options)
+    }
+
+    fn ensure_open(&self, label: &str) -> Result<(), String> {
+        if self.contains(label) {
+            Ok(())
+        } else {
+            Err(format!("webview `{label}` is not open"))
+        }
+    }
+
+    /// Runs `f` on the main thread with the window the webview `label` is in, without waiting
+    /// for it.
+    pub fn update_window(
+        &self,
+        label: &str,
+        f: impl FnOnce(&mut Window, &mut App) + Send + 'static,
+    ) -> Result<(), String> {
+        let window = self
+            .window_handle(label)
+            .ok_or_else(|| format!("webview `{label}` is not open"))?;
+        ipc::dispatch_on_main_thread(move || {
+            let Some(mut app) = APP.with(|app| app.borrow().clone()) else {
+                return;
+            };
+            if let Err(err) = app.update_window(window, |_, window, cx| f(window, cx)) {
+                tracing::warn!("cannot update the window of a webview: {err}");
+            }
+        })
+    }
+
+    /// Runs `f` on the main thread with the app and waits for its result.
+    pub(crate) fn with_app<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut App) -> T + Send + 'static,
+    ) -> Result<T, String> {
+        let run = move || {
+            let app = APP
+                .with(|app| app.borrow().clone())
+                .ok_or_else(|| "no webview was created yet".to_string())?;
+            app.update(f).map_err(|err| err.to_string())
+        };
+        if self.is_main_thread() {
+            return run();
+        }
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        ipc::dispatch_on_main_thread(move || {
+            let _ = tx.send(run());
+        })?;
+        rx.recv_timeout(ipc::MAIN_THREAD_TIMEOUT)
+            .map_err(|_| "the main thread did not answer in time".to_string())?
+    }
+
+    pub(crate) fn register(&self, webview: &Rc<wry::WebView>, window: AnyWindowHandle, cx: &App) {
+        let label = webview.id().to_string();
+        let _ = self.main_thread.set(thread::current().id());
+        APP.with(|app| {
+            app.borrow_mut().get_or_insert_with(|| cx.to_async());
+        });
+        WEBVIEWS.with(|webviews| {
+            webviews
+                .borrow_mut()
+                .insert(label.clone(), Rc::downgrade(webview));
+        });
+        self.windows.lock().unwrap().insert(label, window);
+    }
+
+    /// Forgets `webview`, unless another webview took over its label since.
+    pub(crate) fn unregister(&self, webview: &Rc<wry::WebView>) {
+        let label = webview.id().to_string();
+        let removed = WEBVIEWS.with(|webviews| {
+            let mut webviews = webviews.borrow_mut();
+            let registered = webviews
+                .get(&label)
+                .is_some_and(|registered| registered.as_ptr() == Rc::as_ptr(webview));
+            registered && webviews.remove(&label).is_some()
+        });
+        if removed {
+            self.windows.lock().unwrap().remove(&label);
+            zoom::forget(&label);
+        }
+    }
+
+    /// Whether this is the thread the webviews live on.
+    pub(crate) fn is_main_thread(&self) -> bool {
+        self.main_thread.get() == Some(&thread::current().id())
+    }
+
+    /// The webview `label`, or the only open webview when `label` is `None`. Main thread only.
+    pub(crate) fn webview(&self, label: Option<&str>) -> Option<Rc<wry::WebView>> {
+        WEBVIEWS.with(|webviews| {
+            let webviews = webviews.borrow();
+            let weak = match label {
+                Some(label) => webviews.get(label),
+                None if webviews.len() == 1 => webviews.values().next(),
+                None => None,
+            };
+            weak.and_then(Weak::upgrade)
+        })
+    }
+}
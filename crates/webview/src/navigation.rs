@@ -0,0 +1,166 @@
+//! Where the page may navigate to; see [`crate::Builder::on_navigation`] and
+//! [`crate::Builder::navigation_scope`].
+//!
+//! The app shell, served from the `asset://`, `wry://` and `isolation://` protocols
+//! (`http://asset.localhost` and so on on Windows and Android), is always allowed, as are
+//! `about:` URLs such as `about:blank`. Other URLs are allowed when their origin is in the
+//! [`NavigationScope`]; the rest go to the hook, or are denied when there is none.
+
+use crate::ipc;
+use crate::manager::WebviewManager;
+
+/// What happens to a navigation the [`NavigationScope`] doesn't allow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NavigationPolicy {
+    Allow,
+    Deny,
+    /// Denies the navigation and opens the URL in the default browser instead.
+    OpenExternally,
+}
+
+pub(crate) type NavigationHook = Box<dyn Fn(&str) -> NavigationPolicy + 'static>;
+
+/// The origins the page may navigate to besides the app shell, e.g. `https://example.com`.
+#[derive(Clone, Debug, Default)]
+pub struct NavigationScope {
+    origins: Vec<String>,
+}
+
+impl NavigationScope {
+    /// A scope with the app shell only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows the pages of `origin`, a scheme and a host with an optional port, such as
+    /// `https://docs.example.com` or `http://localhost:8080`.
+    pub fn allow_origin(mut self, origin: impl AsRef<str>) -> Self {
+        let origin = origin.as_ref();
+        match url_origin(origin) {
+            Some(origin) => self.origins.push(origin.serialize()),
+            None => tracing::warn!("`{origin}` is not an origin; the navigation scope ignores it"),
+        }
+        self
+    }
+
+    pub fn origins(&self) -> &[String] {
+        &self.origins
+    }
+
+    fn allows(&self, origin: &Origin<'_>) -> bool {
+        self.origins.contains(&origin.serialize())
+    }
+}
+
+/// The wry navigation handler of the webview `label`.
+pub(crate) fn handler(
+    label: String,
+    scope: Option<NavigationScope>,
+    hook: Option<NavigationHook>,
+) -> impl Fn(String) -> bool + 'static {
+    move |url| {
+        let origin = url_origin(&url);
+        let allowed_by_scope = match &origin {
+            Some(origin) => {
+                origin.is_app_shell() || scope.as_ref().is_some_and(|scope| scope.allows(origin))
+            }
+            None => url
+                .get(..6)
+                .is_some_and(|scheme| scheme.eq_ignore_ascii_case("about:")),
+        };
+        if allowed_by_scope {
+            return true;
+        }
+
+        let policy = match &hook {
+            Some(hook) => hook(&url),
+            None if scope.is_some() => NavigationPolicy::Deny,
+            None => NavigationPolicy::Allow,
+        };
+        match policy {
+            NavigationPolicy::Allow => return true,
+            NavigationPolicy::Deny => {
+                tracing::warn!("webview `{label}` was denied navigating to `{url}`");
+            }
+            NavigationPolicy::OpenExternally => {
+                // The handler may run while gpui updates the app, so open it afterwards.
+                let opened = ipc::dispatch_on_main_thread(move || {
+                    if let Err(err) = WebviewManager::global().with_app(move |cx| cx.open_url(&url))
+                    {
+                        tracing::warn!("cannot open a navigation externally: {err}");
+                    }
+                });
+                if let Err(err) = opened {
+                    tracing::warn!("cannot open a navigation externally: {err}");
+                }
+            }
+        }
+        false
+    }
+}
+
+struct Origin<'a> {
+    scheme: String,
+    host: String,
+    port: Option<&'a str>,
+}
+
+impl Origin<'_> {
+    fn is_app_shell(&self) -> bool {
+        const PROTOCOLS: [&str; 3] = ["asset", "wry", "isolation"];
+        if PROTOCOLS.contains(&self.scheme.as_str()) {
+            return true;
+        }
+        matches!(self.scheme.as_str(), "http" | "https")
+            && self.port.is_none()
+            && self
+                .host
+                .strip_suffix(".localhost")
+                .is_some_and(|protocol| PROTOCOLS.contains(&protocol))
+    }
+
+    fn serialize(&self) -> String {
+        match self.port {
+            Some(port) => format!("{}://{}:{port}", self.scheme, self.host),
+            None => format!("{}://{}", self.scheme, self.host),
+        }
+    }
+}
+
+/// The origin of `url`, without the port when it is the default one of the scheme. URLs without
+/// a host, such as `about:blank` or `data:` URLs, have none.
+fn url_origin(url: &str) -> Option<Origin<'_>> {
+    let (scheme, rest) = url.trim().split_once("://")?;
+    let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !valid_scheme {
+        return None;
+    }
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host_and_port = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    // An IPv6 host is in brackets and has colons of its own.
+    let (host, port) = match host_and_port.rsplit_once(':') {
+        Some((host, port)) if !port.contains(']') => (host, Some(port)),
+        _ => (host_and_port, None),
+    };
+    if host.is_empty() {
+        return None;
+    }
+
+    let scheme = scheme.to_ascii_lowercase();
+    let default_port = match scheme.as_str() {
+        "http" | "ws" => Some("80"),
+        "https" | "wss" => Some("443"),
+        _ => None,
+    };
+    let port = port.filter(|port| !port.is_empty() && Some(*port) != default_port);
+    Some(Origin {
+        scheme,
+        host: host.to_ascii_lowercase(),
+        port,
+    })
+}
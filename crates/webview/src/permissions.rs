@@ -0,0 +1,339 @@
+//! Asking the user before a page uses a gated capability.
+//!
+//! Commands that reach the file system, notifications, the clipboard or cookies run only once
+//! the user has allowed it for the webview that invokes them. The first such invoke shows a
+//! native gpui prompt in a window [`Permissions::attach`]ed to; the answer is saved in the
+//! `[webview-permissions]` table of the shared settings file, by webview label, and applies to
+//! every later invoke until it is reset.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gpui::{App, PromptButton, PromptLevel, Window};
+use manos_config::{ConfigError, ConfigStore, Namespace};
+use serde::{Deserialize, Serialize};
+
+/// Table of the settings file holding the decisions.
+const NAMESPACE: &str = "webview-permissions";
+
+/// How often an attached window looks for invokes waiting on a prompt.
+const PROMPT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Something a page can only use once the user allows it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Capability {
+    Fs,
+    Notification,
+    Clipboard,
+    Cookies,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 4] = [
+        Capability::Fs,
+        Capability::Notification,
+        Capability::Clipboard,
+        Capability::Cookies,
+    ];
+
+    /// The key the decision is saved under.
+    pub fn key(self) -> &'static str {
+        match self {
+            Capability::Fs => "fs",
+            Capability::Notification => "notification",
+            Capability::Clipboard => "clipboard",
+            Capability::Cookies => "cookies",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|capability| capability.key() == key)
+    }
+
+    /// The capability of a Tauri-style plugin command, such as `plugin:fs|read_file` or
+    /// `plugin:clipboard-manager|write_text`.
+    pub fn for_command(command: &str) -> Option<Self> {
+        let plugin = command.strip_prefix("plugin:")?.split('|').next()?;
+        match plugin {
+            "fs" | "dialog" => Some(Capability::Fs),
+            "notification" => Some(Capability::Notification),
+            "clipboard" | "clipboard-manager" => Some(Capability::Clipboard),
+            "cookies" => Some(Capability::Cookies),
+            _ => None,
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Capability::Fs => "read and write files on this computer",
+            Capability::Notification => "show notifications",
+            Capability::Clipboard => "read and change the clipboard",
+            Capability::Cookies => "read and change cookies, including those of signed-in sites",
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.key())
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Grants {
+    /// Decisions by webview label, then by capability key.
+    webviews: BTreeMap<String, BTreeMap<String, bool>>,
+}
+
+struct PendingPrompt {
+    label: String,
+    capability: Capability,
+    replies: Vec<mpsc::Sender<bool>>,
+    shown: bool,
+}
+
+#[derive(Default)]
+struct Prompts {
+    /// Windows currently able to show a prompt.
+    attached: usize,
+    pending: Vec<PendingPrompt>,
+}
+
+struct Inner {
+    namespace: Namespace<Grants>,
+    commands: Mutex<HashMap<String, Capability>>,
+    prompts: Mutex<Prompts>,
+}
+
+/// The capability decisions of all webviews, and the bridge that asks the user for new ones.
+///
+/// Pass it to [`crate::Builder::permissions`] to gate the invokes of a webview. Clones share
+/// their state.
+#[derive(Clone)]
+pub struct Permissions {
+    inner: Arc<Inner>,
+}
+
+impl Permissions {
+    pub fn new(store: &ConfigStore) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                namespace: store.namespace(NAMESPACE),
+                commands: Mutex::new(HashMap::new()),
+                prompts: Mutex::new(Prompts::default()),
+            }),
+        }
+    }
+
+    /// Gates an app command that does not follow the `plugin:<name>|<command>` naming.
+    pub fn gate_command(self, command: impl Into<String>, capability: Capability) -> Self {
+        self.inner
+            .commands
+            .lock()
+            .unwrap()
+            .insert(command.into(), capability);
+        self
+    }
+
+    /// The capability `command` needs, if any.
+    pub fn capability_for(&self, command: &str) -> Option<Capability> {
+        let gated = self.inner.commands.lock().unwrap().get(command).copied();
+        gated.or_else(|| Capability::for_command(command))
+    }
+
+    /// The saved decision for `capability` in the webview `label`; `None` until the user was
+    /// asked.
+    pub fn grant(&self, label: &str, capability: Capability) -> Option<bool> {
+        self.inner
+            .namespace
+            .get()
+            .webviews
+            .get(label)?
+            .get(capability.key())
+            .copied()
+    }
+
+    /// All saved decisions of the webview `label`.
+    pub fn grants(&self, label: &str) -> Vec<(Capability, bool)> {
+        let grants = self.inner.namespace.get();
+        let Some(decisions) = grants.webviews.get(label) else {
+            return Vec::new();
+        };
+        decisions
+            .iter()
+            .filter_map(|(key, allowed)| Some((Capability::from_key(key)?, *allowed)))
+            .collect()
+    }
+
+    /// Labels of the webviews with saved decisions.
+    pub fn labels(&self) -> Vec<String> {
+        self.inner.namespace.get().webviews.into_keys().collect()
+    }
+
+    pub fn set_grant(
+        &self,
+        label: &str,
+        capability: Capability,
+        allowed: bool,
+    ) -> Result<(), ConfigError> {
+        self.inner.namespace.update(|grants| {
+            grants
+                .webviews
+                .entry(label.to_string())
+                .or_default()
+                .insert(capability.key().to_string(), allowed);
+        })
+    }
+
+    /// Forgets the decision for `capability` in the webview `label`, so the next invoke asks
+    /// again.
+    pub fn reset(&self, label: &str, capability: Capability) -> Result<(), ConfigError> {
+        self.inner.namespace.update(|grants| {
+            if let Some(decisions) = grants.webviews.get_mut(label) {
+                decisions.remove(capability.key());
+                if decisions.is_empty() {
+                    grants.webviews.remove(label);
+                }
+            }
+        })
+    }
+
+    /// Forgets every decision of the webview `label`.
+    pub fn reset_webview(&self, label: &str) -> Result<(), ConfigError> {
+        self.inner.namespace.update(|grants| {
+            grants.webviews.remove(label);
+        })
+    }
+
+    pub fn reset_all(&self) -> Result<(), ConfigError> {
+        self.inner
+            .namespace
+            .update(|grants| grants.webviews.clear())
+    }
+
+    /// Lets `window` show the prompts, for as long as it is open.
+    ///
+    /// Without an attached window, invokes that need a decision are denied.
+    pub fn attach(&self, window: &mut Window, cx: &mut App) {
+        self.inner.prompts.lock().unwrap().attached += 1;
+        let permissions = self.clone();
+        window
+            .spawn(cx, async move |cx| {
+                loop {
+                    cx.background_executor().timer(PROMPT_POLL_INTERVAL).await;
+                    let prompt = cx.update(|window, cx| {
+                        let (label, capability) = permissions.next_prompt()?;
+                        let message = format!("Allow “{label}” to {}?", capability.description());
+                        let answer = window.prompt(
+                            PromptLevel::Warning,
+                            &message,
+                            Some("Your choice is remembered for this page."),
+                            &[
+                                PromptButton::ok("Allow"),
+                                PromptButton::cancel("Don't Allow"),
+                            ],
+                            cx,
+                        );
+                        Some((label, capability, answer))
+                    });
+                    let Ok(prompt) = prompt else {
+                        // The window closed.
+                        permissions.detach_window();
+                        break;
+                    };
+                    let Some((label, capability, answer)) = prompt else {
+                        continue;
+                    };
+                    let allowed = answer.await.ok().map(|answer| answer == 0);
+                    permissions.decide(&label, capability, allowed);
+                }
+            })
+            .detach();
+    }
+
+    /// Resolves to whether the webview `label` may use `capability`, asking the user in an
+    /// attached window when there is no saved decision yet.
+    pub(crate) fn request(&self, label: &str, capability: Capability) -> mpsc::Receiver<bool> {
+        let (tx, rx) = mpsc::channel();
+        if let Some(allowed) = self.grant(label, capability) {
+            let _ = tx.send(allowed);
+            return rx;
+        }
+
+        let mut prompts = self.inner.prompts.lock().unwrap();
+        if prompts.attached == 0 {
+            tracing::warn!(
+                "webview `{label}` needs the {capability} permission but no window can ask for it"
+            );
+            let _ = tx.send(false);
+            return rx;
+        }
+        match prompts
+            .pending
+            .iter_mut()
+            .find(|pending| pending.label == label && pending.capability == capability)
+        {
+            Some(pending) => pending.replies.push(tx),
+            None => prompts.pending.push(PendingPrompt {
+                label: label.to_string(),
+                capability,
+                replies: vec![tx],
+                shown: false,
+            }),
+        }
+        rx
+    }
+
+    /// Whether the webview `label` may use `capability`; blocks while the user is asked.
+    pub(crate) fn authorize(&self, label: &str, capability: Capability) -> bool {
+        self.request(label, capability).recv().unwrap_or(false)
+    }
+
+    fn next_prompt(&self) -> Option<(String, Capability)> {
+        let mut prompts = self.inner.prompts.lock().unwrap();
+        let pending = prompts.pending.iter_mut().find(|pending| !pending.shown)?;
+        pending.shown = true;
+        Some((pending.label.clone(), pending.capability))
+    }
+
+    /// Answers the invokes waiting on a prompt; `None` when the prompt was dismissed, which
+    /// denies them without saving a decision.
+    fn decide(&self, label: &str, capability: Capability, allowed: Option<bool>) {
+        if let Some(allowed) = allowed
+            && let Err(err) = self.set_grant(label, capability, allowed)
+        {
+            tracing::warn!("failed to save the {capability} permission of `{label}`: {err}");
+        }
+
+        let mut prompts = self.inner.prompts.lock().unwrap();
+        let Some(ix) = prompts
+            .pending
+            .iter()
+            .position(|pending| pending.label == label && pending.capability == capability)
+        else {
+            return;
+        };
+        let pending = prompts.pending.remove(ix);
+        for reply in pending.replies {
+            let _ = reply.send(allowed.unwrap_or(false));
+        }
+    }
+
+    fn detach_window(&self) {
+        let mut prompts = self.inner.prompts.lock().unwrap();
+        prompts.attached = prompts.attached.saturating_sub(1);
+        if prompts.attached == 0 {
+            for pending in prompts.pending.drain(..) {
+                for reply in pending.replies {
+                    let _ = reply.send(false);
+                }
+            }
+        }
+    }
+}
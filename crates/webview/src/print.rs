@@ -0,0 +1,210 @@
+//! Printing the page, and exporting it as PDF; see [`crate::Builder::print_to_pdf`].
+//!
+//! `plugin:webview|print` shows the print dialog of the platform, as `window.print()` does; it
+//! is the command `@tauri-apps/api` sends, and stands in for `window.print()` on macOS, where
+//! WKWebView ignores it. `plugin:webview|print_to_pdf` writes the page to a PDF file in the
+//! directories the app allowed, without a dialog.
+//!
+//! PDF export needs WebKitGTK, so it is only available on Linux for now; elsewhere it fails with
+//! an error.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::fs_plugin::FsScope;
+use crate::ipc;
+use crate::manager::WebviewManager;
+
+pub const PRINT_COMMAND: &str = "plugin:webview|print";
+pub const PRINT_TO_PDF_COMMAND: &str = "plugin:webview|print_to_pdf";
+
+/// How long [`WebviewManager::print_to_pdf`] waits for the PDF to be written.
+pub const PRINT_TO_PDF_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How the page is laid out in the PDF.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PdfOptions {
+    pub landscape: bool,
+    /// The margins of every page; the defaults of the platform when `None`.
+    pub margins: Option<PdfMargins>,
+}
+
+/// Page margins in millimeters.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub struct PdfMargins {
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+    pub left: f64,
+}
+
+impl PdfMargins {
+    /// The same margin on every side.
+    pub fn uniform(margin: f64) -> Self {
+        Self {
+            top: margin,
+            right: margin,
+            bottom: margin,
+            left: margin,
+        }
+    }
+}
+
+/// Writes the page of `webview` to the PDF file `path` and calls `done` on the main thread when
+/// it is written or failed. Main thread only.
+pub(crate) fn export(
+    webview: &wry::WebView,
+    path: &Path,
+    options: PdfOptions,
+    done: impl FnOnce(Result<(), String>) + 'static,
+) {
+    if !path.is_absolute() {
+        done(Err(format!("`{}` is not an absolute path", path.display())));
+        return;
+    }
+    platform::export(webview, path, options, Box::new(done));
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::cell::RefCell;
+    use std::path::Path;
+    use std::rc::Rc;
+
+    use webkit2gtk::PrintOperationExt;
+    use wry::WebViewExtUnix;
+
+    use super::PdfOptions;
+
+    pub(super) fn export(
+        webview: &wry::WebView,
+        path: &Path,
+        options: PdfOptions,
+        done: Box<dyn FnOnce(Result<(), String>)>,
+    ) {
+        let uri = match webkit2gtk::glib::filename_to_uri(path, None) {
+            Ok(uri) => uri,
+            Err(err) => {
+                done(Err(format!("invalid PDF path `{}`: {err}", path.display())));
+                return;
+            }
+        };
+        let settings = gtk::PrintSettings::new();
+        settings.set_printer("Print to File");
+        settings.set(gtk::PRINT_SETTINGS_OUTPUT_FILE_FORMAT, Some("pdf"));
+        settings.set(gtk::PRINT_SETTINGS_OUTPUT_URI, Some(&uri));
+        let page_setup = gtk::PageSetup::new();
+        if options.landscape {
+            page_setup.set_orientation(gtk::PageOrientation::Landscape);
+        }
+        if let Some(margins) = options.margins {
+            page_setup.set_top_margin(margins.top, gtk::Unit::Mm);
+            page_setup.set_right_margin(margins.right, gtk::Unit::Mm);
+            page_setup.set_bottom_margin(margins.bottom, gtk::Unit::Mm);
+            page_setup.set_left_margin(margins.left, gtk::Unit::Mm);
+        }
+
+        let operation = webkit2gtk::PrintOperation::new(&webview.webview());
+        operation.set_print_settings(&settings);
+        operation.set_page_setup(&page_setup);
+        // Holds the operation until it ends, so it isn't dropped while printing; `failed` comes
+        // before `finished`, and only the first one calls `done`.
+        let pending = Rc::new(RefCell::new(Some((done, operation.clone()))));
+        operation.connect_failed({
+            let pending = pending.clone();
+            move |_, err| {
+                if let Some((done, _)) = pending.borrow_mut().take() {
+                    done(Err(err.to_string()));
+                }
+            }
+        });
+        operation.connect_finished(move |_| {
+            if let Some((done, _)) = pending.borrow_mut().take() {
+                done(Ok(()));
+            }
+        });
+        operation.print();
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use std::path::Path;
+
+    use super::PdfOptions;
+
+    pub(super) fn export(
+        _: &wry::WebView,
+        _: &Path,
+        _: PdfOptions,
+        done: Box<dyn FnOnce(Result<(), String>)>,
+    ) {
+        done(Err(
+            "printing to PDF is not supported on this platform".into()
+        ));
+    }
+}
+
+/// Writes the page of the webview `label` to `path` and waits until it is written; see
+/// [`WebviewManager::print_to_pdf`].
+pub(crate) fn export_and_wait(
+    label: Option<String>,
+    path: PathBuf,
+    options: PdfOptions,
+) -> Result<(), String> {
+    if WebviewManager::global().is_main_thread() {
+        return Err("the main thread can't wait for a PDF; use `WebView::print_to_pdf`".into());
+    }
+    let (tx, rx) = mpsc::channel();
+    ipc::with_webview(label, move |webview| {
+        export(webview, &path, options, move |result| {
+            let _ = tx.send(result);
+        });
+    })?;
+    rx.recv_timeout(PRINT_TO_PDF_TIMEOUT)
+        .map_err(|_| format!("the PDF was not written within {PRINT_TO_PDF_TIMEOUT:?}"))?
+}
+
+pub(crate) fn print(_: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    match ipc::with_webview(None, |webview| webview.print()) {
+        Ok(Ok(())) => ipc::ok_json(&()),
+        Ok(Err(err)) => ipc::internal_error(err),
+        Err(err) => ipc::internal_error(err),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PrintToPdfPayload {
+    path: String,
+    #[serde(default)]
+    options: PdfOptions,
+}
+
+pub(crate) fn print_to_pdf(
+    scope: &FsScope,
+    request: http::Request<Vec<u8>>,
+) -> http::Response<Vec<u8>> {
+    let payload: PrintToPdfPayload = match serde_json::from_slice(request.body()) {
+        Ok(payload) => payload,
+        Err(err) => {
+            return ipc::bad_request(format!(
+                "invalid JSON body for {PRINT_TO_PDF_COMMAND}: {err}"
+            ));
+        }
+    };
+    let path = match scope.resolve(&payload.path) {
+        Ok(path) => path,
+        Err(err) => {
+            tracing::warn!("refused to print to `{}`: {err}", payload.path);
+            return ipc::forbidden(err);
+        }
+    };
+    match export_and_wait(None, path, payload.options) {
+        Ok(()) => ipc::ok_json(&()),
+        Err(err) => ipc::internal_error(err),
+    }
+}
@@ -0,0 +1,88 @@
+//! Proxy settings of a webview; see [`crate::Builder::proxy`].
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A proxy server the webview sends all of its traffic through.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Proxy {
+    /// An HTTP proxy; `https` requests are tunneled with `CONNECT`.
+    Http {
+        host: String,
+        port: u16,
+    },
+    Socks5 {
+        host: String,
+        port: u16,
+    },
+}
+
+impl Proxy {
+    pub fn http(host: impl Into<String>, port: u16) -> Self {
+        Proxy::Http {
+            host: host.into(),
+            port,
+        }
+    }
+
+    pub fn socks5(host: impl Into<String>, port: u16) -> Self {
+        Proxy::Socks5 {
+            host: host.into(),
+            port,
+        }
+    }
+
+    pub(crate) fn to_wry(&self) -> wry::ProxyConfig {
+        let endpoint = |host: &str, port: u16| wry::ProxyEndpoint {
+            host: host.to_string(),
+            port: port.to_string(),
+        };
+        match self {
+            Proxy::Http { host, port } => wry::ProxyConfig::Http(endpoint(host, *port)),
+            Proxy::Socks5 { host, port } => wry::ProxyConfig::Socks5(endpoint(host, *port)),
+        }
+    }
+}
+
+/// Parses `http://host:port` or `socks5://host:port`; `socks5h://` is read as `socks5://`,
+/// since the webview always resolves names through a SOCKS proxy.
+impl FromStr for Proxy {
+    type Err = String;
+
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        let (scheme, rest) = url
+            .split_once("://")
+            .ok_or_else(|| format!("proxy URL `{url}` has no scheme"))?;
+        let authority = rest.trim_end_matches('/');
+        let (host, port) = authority
+            .rsplit_once(':')
+            .ok_or_else(|| format!("proxy URL `{url}` has no port"))?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.is_empty() || authority.contains(['/', '@']) {
+            return Err(format!("proxy URL `{url}` must be `scheme://host:port`"));
+        }
+        let port = port
+            .parse()
+            .map_err(|_| format!("proxy URL `{url}` has an invalid port"))?;
+
+        match scheme.to_ascii_lowercase().as_str() {
+            "http" => Ok(Proxy::http(host, port)),
+            "socks5" | "socks5h" => Ok(Proxy::socks5(host, port)),
+            other => Err(format!("unsupported proxy scheme `{other}`")),
+        }
+    }
+}
+
+impl fmt::Display for Proxy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (scheme, host, port) = match self {
+            Proxy::Http { host, port } => ("http", host, port),
+            Proxy::Socks5 { host, port } => ("socks5", host, port),
+        };
+        if host.contains(':') {
+            write!(f, "{scheme}://[{host}]:{port}")
+        } else {
+            write!(f, "{scheme}://{host}:{port}")
+        }
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright 2019-2024 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+/**
+ * Injected into the isolation frame only, see ipc.js for the main frame side.
+ * main frame -> isolation frame = isolation payload
+ * isolation frame -> main frame = isolation message
+ */
+;(async function () {
+  const processIpcMessage = __RAW_process_ipc_message_fn__
+
+  /**
+   * The key shared with Rust. It never leaves this frame, so the main frame can't forge
+   * messages that didn't go through `window.__TAURI_ISOLATION_HOOK__`.
+   */
+  const aesGcmKey = await window.crypto.subtle.importKey(
+    'raw',
+    new Uint8Array(__TEMPLATE_runtime_aes_gcm_key__),
+    'AES-GCM',
+    false,
+    ['encrypt']
+  )
+
+  /**
+   * @param {*} payload - the command arguments as the main frame sent them
+   * @return {Promise<{contentType: string, nonce: number[], payload: number[]}>}
+   */
+  async function encrypt(payload) {
+    const { contentType, data } = processIpcMessage(payload)
+    const message =
+      typeof data === 'string'
+        ? new TextEncoder().encode(data)
+        : ArrayBuffer.isView(data) || data instanceof ArrayBuffer
+          ? data
+          : new Uint8Array(data)
+
+    const algorithm = Object.create(null)
+    algorithm.name = 'AES-GCM'
+    algorithm.iv = window.crypto.getRandomValues(new Uint8Array(12))
+
+    const encrypted = await window.crypto.subtle.encrypt(
+      algorithm,
+      aesGcmKey,
+      message
+    )
+
+    const result = Object.create(null)
+    result.contentType = contentType
+    result.nonce = Array.from(algorithm.iv)
+    result.payload = Array.from(new Uint8Array(encrypted))
+    return result
+  }
+
+  /**
+   * @param {MessageEvent<object>} event - a message event that is expected to carry an isolation payload
+   * @return {boolean} - if the event came from the main frame and has a callback and error identifier
+   */
+  function isIsolationPayload(event) {
+    return (
+      event.source === window.parent
+      && typeof event.data === 'object'
+      && event.data !== null
+      && 'callback' in event.data
+      && 'error' in event.data
+    )
+  }
+
+  function sendMessage(message) {
+    window.parent.postMessage(message, '*')
+  }
+
+  window.addEventListener(
+    'message',
+    async (event) => {
+      if (!isIsolationPayload(event)) {
+        return
+      }
+
+      let data = event.data
+      if (typeof window.__TAURI_ISOLATION_HOOK__ === 'function') {
+        // the hook may inspect, change or reject (by throwing) the message
+        data = await window.__TAURI_ISOLATION_HOOK__(data)
+      }
+
+      const message = Object.create(null)
+      message.cmd = data.cmd
+      message.callback = data.callback
+      message.error = data.error
+      message.options = data.options
+      message.payload = await encrypt(data.payload)
+      sendMessage(message)
+    },
+    false
+  )
+
+  // the app's own scripts run after this one, so wait for the hook before accepting messages
+  function waitUntilReady() {
+    if (typeof window.__TAURI_ISOLATION_HOOK__ === 'function') {
+      sendMessage('__TAURI_ISOLATION_READY__')
+    } else {
+      setTimeout(waitUntilReady, 50)
+    }
+  }
+
+  waitUntilReady()
+})()
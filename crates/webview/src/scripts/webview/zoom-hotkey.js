@@ -2,42 +2,29 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
-const OS_NAME = __TEMPLATE_os_name__
+// The level lives in Rust, so the hotkeys and the app zoom the same webview level.
 
-let zoomLevel = 1
+const OS_NAME = __TEMPLATE_os_name__
 
-const MAX_ZOOM_LEVEL = 10
-const MIN_ZOOM_LEVEL = 0.2
+function invokeZoom(cmd, args) {
+  window.__TAURI_INTERNALS__.invoke(cmd, args)
+}
 
 window.addEventListener('keydown', (event) => {
   if (OS_NAME === 'macos' ? event.metaKey : event.ctrlKey) {
     if (event.key === '-') {
-      zoomLevel -= 0.2
+      invokeZoom('plugin:webview|zoom_out')
     } else if (event.key === '=' || event.key === '+') {
-      zoomLevel += 0.2
+      invokeZoom('plugin:webview|zoom_in')
     } else if (event.key === '0') {
-      zoomLevel = 1
-    } else {
-      return
+      invokeZoom('plugin:webview|set_webview_zoom', { value: 1 })
     }
-    zoomLevel = Math.min(Math.max(zoomLevel, MIN_ZOOM_LEVEL), MAX_ZOOM_LEVEL)
-    window.__TAURI_INTERNALS__.invoke('plugin:webview|set_webview_zoom', {
-      value: zoomLevel
-    })
   }
 })
 
 window.addEventListener('mousewheel', (event) => {
   if (event.ctrlKey) {
     event.preventDefault()
-    if (event.deltaY < 0) {
-      zoomLevel += 0.2
-    } else {
-      zoomLevel -= 0.2
-    }
-    zoomLevel = Math.min(Math.max(zoomLevel, MIN_ZOOM_LEVEL), MAX_ZOOM_LEVEL)
-    window.__TAURI_INTERNALS__.invoke('plugin:webview|set_webview_zoom', {
-      value: zoomLevel
-    })
+    invokeZoom(event.deltaY < 0 ? 'plugin:webview|zoom_in' : 'plugin:webview|zoom_out')
   }
 })
@@ -0,0 +1,151 @@
+//! Opening URLs and paths outside the webview; see [`crate::Builder::shell`].
+//!
+//! `plugin:shell|open` takes the payload `@tauri-apps/plugin-shell` sends, so its `open` works;
+//! the program in `with` is ignored. URLs open in the default browser or the app of their
+//! scheme, paths in the system file manager. Only the schemes of the [`ShellScope`] are
+//! opened, others are rejected with `403`.
+
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::ipc;
+use crate::manager::WebviewManager;
+
+pub const OPEN_COMMAND: &str = "plugin:shell|open";
+
+/// The URL schemes the page may open. The `file` scheme also covers absolute paths.
+#[derive(Clone, Debug)]
+pub struct ShellScope {
+    schemes: Vec<String>,
+}
+
+impl Default for ShellScope {
+    /// `http`, `https` and `mailto`.
+    fn default() -> Self {
+        Self {
+            schemes: vec!["http".into(), "https".into(), "mailto".into()],
+        }
+    }
+}
+
+impl ShellScope {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A scope that opens nothing, to allow schemes one by one.
+    pub fn empty() -> Self {
+        Self {
+            schemes: Vec::new(),
+        }
+    }
+
+    pub fn allow_scheme(mut self, scheme: impl Into<String>) -> Self {
+        self.schemes.push(scheme.into().to_ascii_lowercase());
+        self
+    }
+
+    pub fn schemes(&self) -> &[String] {
+        &self.schemes
+    }
+
+    fn allows(&self, scheme: &str) -> bool {
+        self.schemes
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(scheme))
+    }
+
+    fn resolve(&self, target: &str) -> Result<Target, String> {
+        let target = target.trim();
+        let scheme = match url_scheme(target) {
+            Some(scheme) => scheme,
+            None if PathBuf::from(target).is_absolute() => "file",
+            None => return Err(format!("`{target}` is neither a URL nor an absolute path")),
+        };
+        if !self.allows(scheme) {
+            return Err(format!(
+                "the shell scope does not allow opening `{scheme}` URLs"
+            ));
+        }
+        if !scheme.eq_ignore_ascii_case("file") {
+            return Ok(Target::Url(target.to_string()));
+        }
+
+        let path = match target.get(..7) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("file://") => file_url_path(&target[7..]),
+            _ => PathBuf::from(target),
+        };
+        if path.is_absolute() {
+            Ok(Target::Path(path))
+        } else {
+            Err(format!("`{target}` is not an absolute path"))
+        }
+    }
+}
+
+enum Target {
+    Url(String),
+    Path(PathBuf),
+}
+
+/// The scheme of `target` if it is a URL. A single letter is a Windows drive, not a scheme.
+fn url_scheme(target: &str) -> Option<&str> {
+    let (scheme, _) = target.split_once(':')?;
+    let mut chars = scheme.chars();
+    let valid = scheme.len() > 1
+        && chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    valid.then_some(scheme)
+}
+
+/// The path of a `file://` URL without its scheme, e.g. `/tmp/a%20b` or `localhost/tmp`.
+fn file_url_path(rest: &str) -> PathBuf {
+    let rest = rest.strip_prefix("localhost").unwrap_or(rest);
+    let path = crate::decode_uri_component(rest);
+    // `file:///C:/Users` has the drive after the slash.
+    if cfg!(windows) && path.get(2..3) == Some(":") {
+        return PathBuf::from(&path[1..]);
+    }
+    PathBuf::from(path)
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenPayload {
+    path: String,
+}
+
+pub(crate) fn open(scope: &ShellScope, request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let payload: OpenPayload = match serde_json::from_slice(request.body()) {
+        Ok(payload) => payload,
+        Err(err) => {
+            return ipc::bad_request(format!("invalid JSON body for {OPEN_COMMAND}: {err}"));
+        }
+    };
+    let target = match scope.resolve(&payload.path) {
+        Ok(target) => target,
+        Err(err) => {
+            tracing::warn!("refused to open `{}`: {err}", payload.path);
+            return ipc::forbidden(err);
+        }
+    };
+
+    let opened = match target {
+        Target::Url(url) => WebviewManager::global().with_app(move |cx| cx.open_url(&url)),
+        Target::Path(path) if !path.exists() => {
+            return ipc::bad_request(format!("`{}` does not exist", path.display()));
+        }
+        Target::Path(path) => WebviewManager::global().with_app(move |cx| {
+            // A folder opens in the file manager; a file is shown in its folder.
+            if path.is_dir() {
+                cx.open_with_system(&path);
+            } else {
+                cx.reveal_path(&path);
+            }
+        }),
+    };
+    match opened {
+        Ok(()) => ipc::ok_json(&()),
+        Err(err) => ipc::internal_error(err),
+    }
+}
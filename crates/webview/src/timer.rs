@@ -0,0 +1,93 @@
+//! One thread that runs callbacks at their deadline, for command timeouts (see
+//! [`crate::Builder::command_timeout`]) and script evaluations that get no value.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Condvar, Mutex, OnceLock};
+use std::thread;
+use std::time::Instant;
+
+type Callback = Box<dyn FnOnce() + Send + 'static>;
+
+#[derive(Default)]
+struct Timers {
+    /// The scheduled callbacks as `(deadline, id)`, earliest first.
+    deadlines: BTreeSet<(Instant, u64)>,
+    callbacks: HashMap<u64, (Instant, Callback)>,
+    next_id: u64,
+}
+
+struct Timer {
+    timers: Mutex<Timers>,
+    changed: Condvar,
+}
+
+fn timer() -> &'static Timer {
+    static TIMER: OnceLock<Timer> = OnceLock::new();
+    TIMER.get_or_init(|| {
+        let spawned = thread::Builder::new()
+            .name("manos-timer".into())
+            .spawn(|| run(timer()));
+        if let Err(err) = spawned {
+            tracing::warn!("cannot start the timer thread: {err}");
+        }
+        Timer {
+            timers: Mutex::new(Timers::default()),
+            changed: Condvar::new(),
+        }
+    })
+}
+
+fn run(timer: &Timer) {
+    let mut timers = timer.timers.lock().unwrap();
+    loop {
+        let now = Instant::now();
+        let Some(&(deadline, id)) = timers.deadlines.first() else {
+            timers = timer.changed.wait(timers).unwrap();
+            continue;
+        };
+        if deadline > now {
+            timers = timer
+                .changed
+                .wait_timeout(timers, deadline - now)
+                .unwrap()
+                .0;
+            continue;
+        }
+
+        timers.deadlines.remove(&(deadline, id));
+        let Some((_, callback)) = timers.callbacks.remove(&id) else {
+            continue;
+        };
+        // Callbacks may schedule or cancel timers themselves.
+        drop(timers);
+        callback();
+        timers = timer.timers.lock().unwrap();
+    }
+}
+
+/// Runs `callback` on the timer thread at `deadline`, unless the returned handle is cancelled
+/// first.
+pub(crate) fn schedule(deadline: Instant, callback: impl FnOnce() + Send + 'static) -> TimerHandle {
+    let timer = timer();
+    let mut timers = timer.timers.lock().unwrap();
+    let id = timers.next_id;
+    timers.next_id += 1;
+    timers.deadlines.insert((deadline, id));
+    timers.callbacks.insert(id, (deadline, Box::new(callback)));
+    timer.changed.notify_one();
+    TimerHandle { id }
+}
+
+pub(crate) struct TimerHandle {
+    id: u64,
+}
+
+impl TimerHandle {
+    /// Drops the callback if it hasn't run yet.
+    pub(crate) fn cancel(self) {
+        let mut timers = timer().timers.lock().unwrap();
+        if let Some((deadline, _)) = timers.callbacks.remove(&self.id) {
+            timers.deadlines.remove(&(deadline, self.id));
+        }
+    }
+}
@@ -0,0 +1,360 @@
+//! TypeScript bindings for commands, behind the `typescript` feature.
+//!
+//! With the feature on, `generate_handler!` records the signature of every command it registers,
+//! and [`Bindings`] turns them into a `.ts` file with one typed function per command, built on
+//! `invoke` of `@manos/api`. Argument and return types come from [`TS`] (`ts-rs`), so the types
+//! commands take and return derive it:
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize, gpui_manos_webview::typescript::TS)]
+//! #[ts(crate = "gpui_manos_webview::typescript::ts_rs")]
+//! struct Note {
+//!     title: String,
+//! }
+//! ```
+//!
+//! The arguments the wrapper fills itself (`ipc::Request`, `ipc::Headers`,
+//! `ipc::WebviewLabel`, `ipc::CancellationToken`) are not part of the bindings.
+
+use std::any::TypeId;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+
+pub use ts_rs::{self, TS};
+use ts_rs::{Dummy, TypeVisitor};
+
+use crate::ipc;
+
+/// The signature of a command as the page calls it; `#[command]` builds it.
+#[derive(Debug, Clone)]
+pub struct CommandSignature {
+    name: String,
+    args: Vec<(String, String)>,
+    returns: String,
+    declarations: BTreeMap<String, String>,
+    uses_channel: bool,
+}
+
+impl CommandSignature {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            args: Vec::new(),
+            returns: "void".to_string(),
+            declarations: BTreeMap::new(),
+            uses_channel: false,
+        }
+    }
+
+    /// Adds an argument read from the JSON key `key`.
+    pub fn arg<T: TS + ?Sized + 'static>(mut self, key: impl Into<String>) -> Self {
+        self.args.push((key.into(), T::name()));
+        self.declare::<T>();
+        self
+    }
+
+    /// Sets the type `invoke` resolves with; `()` resolves with nothing.
+    pub fn returns<T: TS + ?Sized + 'static>(mut self) -> Self {
+        self.returns = if TypeId::of::<T>() == TypeId::of::<()>() {
+            "void".to_string()
+        } else {
+            T::name()
+        };
+        self.declare::<T>();
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Collects the declarations of `T` and of the types it uses.
+    fn declare<T: TS + ?Sized + 'static>(&mut self) {
+        let mut collector = Declarations {
+            signature: self,
+            visited: HashSet::new(),
+        };
+        collector.visit::<T>();
+        T::visit_generics(&mut collector);
+    }
+}
+
+struct Declarations<'a> {
+    signature: &'a mut CommandSignature,
+    visited: HashSet<TypeId>,
+}
+
+impl TypeVisitor for Declarations<'_> {
+    fn visit<T: TS + 'static + ?Sized>(&mut self) {
+        if !self.visited.insert(TypeId::of::<T>()) {
+            return;
+        }
+        // Only types of their own, e.g. derived ones, have an output path and a declaration.
+        if T::output_path().is_some() {
+            let declaration = format!("{}export {}", T::docs().unwrap_or_default(), T::decl());
+            self.signature
+                .declarations
+                .entry(T::ident())
+                .or_insert(declaration);
+        } else if T::ident() == CHANNEL {
+            self.signature.uses_channel = true;
+        }
+        T::visit_dependencies(self);
+    }
+}
+
+const CHANNEL: &str = "Channel";
+
+static REGISTERED: Mutex<BTreeMap<String, CommandSignature>> = Mutex::new(BTreeMap::new());
+
+/// Records the commands of a `generate_handler!`.
+#[doc(hidden)]
+pub fn register(signatures: Vec<CommandSignature>) {
+    let mut registered = REGISTERED.lock().unwrap();
+    for signature in signatures {
+        registered.insert(signature.name.clone(), signature);
+    }
+}
+
+/// The TypeScript bindings of a set of commands.
+#[derive(Debug, Clone)]
+pub struct Bindings {
+    commands: BTreeMap<String, CommandSignature>,
+    module: String,
+}
+
+impl Bindings {
+    /// The commands of every `generate_handler!` evaluated so far, e.g. by building the
+    /// webviews, importing `invoke` from `@manos/api`.
+    pub fn registered() -> Self {
+        Self {
+            commands: REGISTERED.lock().unwrap().clone(),
+            module: crate::js_api::PACKAGE_NAME.to_string(),
+        }
+    }
+
+    /// Imports `invoke` and `Channel` from `module` instead, e.g. `@tauri-apps/api/core`.
+    pub fn with_module(mut self, module: impl Into<String>) -> Self {
+        self.module = module.into();
+        self
+    }
+
+    pub fn commands(&self) -> impl Iterator<Item = &CommandSignature> {
+        self.commands.values()
+    }
+
+    /// The contents of the bindings file.
+    pub fn render(&self) -> String {
+        let uses_channel = self.commands().any(|command| command.uses_channel);
+        let imports = if uses_channel {
+            format!("{CHANNEL}, invoke")
+        } else {
+            "invoke".to_string()
+        };
+        let mut out = format!(
+            "// Generated by gpui-manos-webview from the `#[command]` signatures; do not edit.\n\n\
+             import {{ {imports} }} from {}\n",
+            serde_json::to_string(&self.module).expect("strings serialize")
+        );
+
+        let mut declarations = BTreeMap::new();
+        for command in self.commands() {
+            for (ident, declaration) in &command.declarations {
+                declarations.entry(ident).or_insert(declaration);
+            }
+        }
+        for declaration in declarations.values() {
+            out.push('\n');
+            out.push_str(declaration);
+            out.push('\n');
+        }
+
+        for command in self.commands() {
+            out.push('\n');
+            render_command(&mut out, command);
+        }
+        out
+    }
+
+    /// Writes the bindings to `path`, unless it already has them, so a dev build doesn't
+    /// retrigger the file watcher of the frontend.
+    pub fn export(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let contents = self.render();
+        if fs::read_to_string(path).is_ok_and(|existing| existing == contents) {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)
+    }
+}
+
+fn render_command(out: &mut String, command: &CommandSignature) {
+    let params: Vec<_> = command
+        .args
+        .iter()
+        .map(|(key, ty)| (key, param_name(key), ty))
+        .collect();
+    let signature = params
+        .iter()
+        .map(|(_, param, ty)| format!("{param}: {ty}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let name = serde_json::to_string(&command.name).expect("strings serialize");
+    let args = if params.is_empty() {
+        String::new()
+    } else {
+        let fields = params
+            .iter()
+            .map(|(key, param, _)| {
+                if *key == param {
+                    param.clone()
+                } else {
+                    let key = serde_json::to_string(key).expect("strings serialize");
+                    format!("{key}: {param}")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(", {{ {fields} }}")
+    };
+    let returns = &command.returns;
+    out.push_str(&format!(
+        "export function {}({signature}): Promise<{returns}> {{\n  return invoke<{returns}>({name}{args})\n}}\n",
+        param_name(&command.name)
+    ));
+}
+
+/// `key` as a JS identifier: other characters become `_`, and reserved words get a trailing `_`.
+fn param_name(key: &str) -> String {
+    const RESERVED: &[&str] = &[
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "debugger",
+        "default",
+        "delete",
+        "do",
+        "else",
+        "enum",
+        "export",
+        "extends",
+        "false",
+        "finally",
+        "for",
+        "function",
+        "if",
+        "import",
+        "in",
+        "instanceof",
+        "new",
+        "null",
+        "return",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "typeof",
+        "var",
+        "void",
+        "while",
+        "with",
+    ];
+    let mut name: String = key
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '$' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    if RESERVED.contains(&name.as_str()) {
+        name.push('_');
+    }
+    name
+}
+
+impl<T: TS> TS for ipc::Channel<T> {
+    type WithoutGenerics = ipc::Channel<Dummy>;
+    type OptionInnerType = Self;
+
+    fn ident() -> String {
+        CHANNEL.to_string()
+    }
+
+    fn name() -> String {
+        format!("{CHANNEL}<{}>", T::name())
+    }
+
+    fn inline() -> String {
+        format!("{CHANNEL}<{}>", T::inline())
+    }
+
+    fn visit_dependencies(v: &mut impl TypeVisitor)
+    where
+        Self: 'static,
+    {
+        T::visit_dependencies(v);
+    }
+
+    fn visit_generics(v: &mut impl TypeVisitor)
+    where
+        Self: 'static,
+    {
+        T::visit_generics(v);
+        v.visit::<T>();
+    }
+
+    fn decl() -> String {
+        panic!("{} cannot be declared", Self::name())
+    }
+
+    fn decl_concrete() -> String {
+        panic!("{} cannot be declared", Self::name())
+    }
+
+    fn inline_flattened() -> String {
+        panic!("{} cannot be flattened", Self::name())
+    }
+}
+
+/// `invoke` resolves a binary response with an `ArrayBuffer`.
+impl TS for ipc::Response {
+    type WithoutGenerics = Self;
+    type OptionInnerType = Self;
+
+    fn name() -> String {
+        "ArrayBuffer".to_string()
+    }
+
+    fn inline() -> String {
+        Self::name()
+    }
+
+    fn decl() -> String {
+        panic!("{} cannot be declared", Self::name())
+    }
+
+    fn decl_concrete() -> String {
+        panic!("{} cannot be declared", Self::name())
+    }
+
+    fn inline_flattened() -> String {
+        panic!("{} cannot be flattened", Self::name())
+    }
+}
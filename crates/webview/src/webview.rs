@@ -1,9 +1,14 @@
-use std::{ops::Deref, rc::Rc};
+use std::{ops::Deref, path::Path, rc::Rc};
 use wry::{
     Rect,
     dpi::{self, LogicalSize},
 };
 
+use crate::cookies::CookieInfo;
+use crate::eval::ScriptResult;
+use crate::events::ListenerId;
+use crate::manager::WebviewManager;
+use crate::print::PdfOptions;
 use gpui::private::anyhow;
 use gpui::{
     App, Bounds, ContentMask, DismissEvent, Div, Element, ElementId, Entity, EventEmitter,
@@ -11,6 +16,8 @@ use gpui::{
     MouseDownEvent, ParentElement as _, Pixels, Render, Size, Style, Styled as _, Window, canvas,
     div,
 };
+use serde::Serialize;
+use serde::de::DeserializeOwned;
 
 pub struct WebView {
     focus_handle: FocusHandle,
@@ -22,19 +29,19 @@ pub struct WebView {
 
 impl Drop for WebView {
     fn drop(&mut self) {
-        crate::unregister_webview_for_ipc(&self.webview.id().to_string());
+        WebviewManager::global().unregister(&self.webview);
         self.hide();
     }
 }
 
 impl WebView {
-    pub fn new(webview: wry::WebView, _window: &mut Window, cx: &mut App) -> Self {
+    pub fn new(webview: wry::WebView, window: &mut Window, cx: &mut App) -> Self {
         crate::ipc::init_platform_dispatcher(cx.background_executor().dispatcher.clone());
 
         let _ = webview.set_bounds(Rect::default());
 
         let webview = Rc::new(webview);
-        crate::register_webview_for_ipc(&webview);
+        WebviewManager::global().register(&webview, window.window_handle(), cx);
 
         Self {
             focus_handle: cx.focus_handle(),
@@ -69,10 +76,77 @@ impl WebView {
         Ok(self.webview.evaluate_script("history.back();")?)
     }
 
+    /// Evaluates the JavaScript expression `js` and resolves with its value; see
+    /// [`WebviewManager::eval_with_result`].
+    pub fn eval_with_result<T: DeserializeOwned>(&self, js: &str) -> ScriptResult<T> {
+        WebviewManager::global().eval_with_result(self.webview.id(), js)
+    }
+
+    /// Calls `handler` with the payload of `event` whenever the page of this webview emits it,
+    /// or a page emits it to this webview; see [`crate::events::listen`].
+    pub fn listen<T, F>(&self, event: impl Into<String>, handler: F) -> ListenerId
+    where
+        T: DeserializeOwned,
+        F: Fn(T) + Send + Sync + 'static,
+    {
+        crate::events::add_listener(event.into(), Some(self.webview.id().to_string()), handler)
+    }
+
+    /// Sends `event` with `payload` to the listeners of the page; see [`crate::events::emit_to`].
+    pub fn emit<T: Serialize>(&self, event: &str, payload: T) -> anyhow::Result<()> {
+        crate::events::emit_to(self.webview.id(), event, payload).map_err(anyhow::Error::msg)
+    }
+
+    /// Zooms to `level` and returns the level it got; see [`WebviewManager::set_zoom`].
+    pub fn set_zoom(&self, level: f64) -> anyhow::Result<f64> {
+        crate::zoom::set(self.webview.id(), level).map_err(anyhow::Error::msg)
+    }
+
+    pub fn zoom_in(&self) -> anyhow::Result<f64> {
+        crate::zoom::step(self.webview.id(), 1.0).map_err(anyhow::Error::msg)
+    }
+
+    pub fn zoom_out(&self) -> anyhow::Result<f64> {
+        crate::zoom::step(self.webview.id(), -1.0).map_err(anyhow::Error::msg)
+    }
+
+    pub fn zoom_level(&self) -> f64 {
+        crate::zoom::level(self.webview.id())
+    }
+
+    /// Writes the page to the PDF file `path` and calls `done` on the main thread once it is
+    /// written or failed; see [`crate::print`].
+    pub fn print_to_pdf(
+        &self,
+        path: impl AsRef<Path>,
+        options: PdfOptions,
+        done: impl FnOnce(anyhow::Result<()>) + 'static,
+    ) {
+        crate::print::export(&self.webview, path.as_ref(), options, move |result| {
+            done(result.map_err(anyhow::Error::msg))
+        });
+    }
+
     pub fn load_url(&mut self, url: &str) {
         self.webview.load_url(url).unwrap();
     }
 
+    /// The cookies a request to `origin` would send; see [`crate::cookies`].
+    pub fn cookies_for_origin(&self, origin: &str) -> anyhow::Result<Vec<CookieInfo>> {
+        crate::cookies::cookies_for_origin(&self.webview, origin).map_err(anyhow::Error::msg)
+    }
+
+    /// Sets `cookie` for `origin`, scoped to its host unless the cookie names a domain.
+    pub fn set_cookie_for_origin(&self, origin: &str, cookie: CookieInfo) -> anyhow::Result<()> {
+        crate::cookies::set_cookie_for_origin(&self.webview, origin, cookie)
+            .map_err(anyhow::Error::msg)
+    }
+
+    /// Deletes the cookies of `origin` and returns how many there were.
+    pub fn clear_cookies_for_origin(&self, origin: &str) -> anyhow::Result<usize> {
+        crate::cookies::clear_cookies_for_origin(&self.webview, origin).map_err(anyhow::Error::msg)
+    }
+
     pub fn more_style<F>(mut self, f: F) -> Self
     where
         F: Fn(Div) -> Div + 'static,
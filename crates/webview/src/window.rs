@@ -0,0 +1,99 @@
+//! Control of the native window from the page.
+//!
+//! The `plugin:window|minimize`, `maximize`, `close`, `set_title` and `set_size` commands are
+//! the ones `@tauri-apps/api/window` sends, so its `Window` methods of the same names work. They
+//! act on the gpui window the invoking webview is in; the window label the page sends is
+//! ignored.
+
+use gpui::{App, Window, px, size};
+use serde::Deserialize;
+
+use crate::ipc;
+use crate::manager::WebviewManager;
+
+pub const MINIMIZE_COMMAND: &str = "plugin:window|minimize";
+pub const MAXIMIZE_COMMAND: &str = "plugin:window|maximize";
+pub const CLOSE_COMMAND: &str = "plugin:window|close";
+pub const SET_TITLE_COMMAND: &str = "plugin:window|set_title";
+pub const SET_SIZE_COMMAND: &str = "plugin:window|set_size";
+
+#[derive(Debug, Deserialize)]
+struct ValuePayload<T> {
+    value: T,
+}
+
+/// A size as `@tauri-apps/api/dpi` sends it, e.g. `{ "Logical": { "width": 800, "height": 600 } }`.
+#[derive(Debug, Deserialize)]
+enum SizePayload {
+    Logical { width: f64, height: f64 },
+    Physical { width: f64, height: f64 },
+}
+
+fn parse_value<T: serde::de::DeserializeOwned>(
+    request: &http::Request<Vec<u8>>,
+    command: &str,
+) -> Result<T, String> {
+    serde_json::from_slice::<ValuePayload<T>>(request.body())
+        .map(|payload| payload.value)
+        .map_err(|err| format!("invalid JSON body for {command}: {err}"))
+}
+
+/// Runs `f` with the window of the invoking webview once the main thread gets to it.
+fn update_invoking_window(
+    f: impl FnOnce(&mut Window, &mut App) + Send + 'static,
+) -> http::Response<Vec<u8>> {
+    let Some(label) = ipc::current_webview_label() else {
+        return ipc::bad_request("window commands must be invoked from a webview");
+    };
+    match WebviewManager::global().update_window(&label, f) {
+        Ok(()) => ipc::ok_json(&()),
+        Err(err) => ipc::internal_error(err),
+    }
+}
+
+pub(crate) fn minimize(_: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    update_invoking_window(|window, _| window.minimize_window())
+}
+
+pub(crate) fn maximize(_: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    update_invoking_window(|window, _| {
+        // gpui only toggles, so leave a maximized window as it is.
+        if !window.is_maximized() {
+            window.zoom_window();
+        }
+    })
+}
+
+pub(crate) fn close(_: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    update_invoking_window(|window, _| window.remove_window())
+}
+
+pub(crate) fn set_title(request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    match parse_value::<String>(&request, SET_TITLE_COMMAND) {
+        Ok(title) => update_invoking_window(move |window, _| window.set_window_title(&title)),
+        Err(err) => ipc::bad_request(err),
+    }
+}
+
+pub(crate) fn set_size(request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let payload = match parse_value::<SizePayload>(&request, SET_SIZE_COMMAND) {
+        Ok(payload) => payload,
+        Err(err) => return ipc::bad_request(err),
+    };
+    let (SizePayload::Logical { width, height } | SizePayload::Physical { width, height }) =
+        payload;
+    if !(width.is_finite() && height.is_finite() && width > 0.0 && height > 0.0) {
+        return ipc::bad_request("window size must be positive and finite");
+    }
+
+    update_invoking_window(move |window, _| {
+        let scale = match payload {
+            SizePayload::Logical { .. } => 1.0,
+            SizePayload::Physical { .. } => window.scale_factor() as f64,
+        };
+        window.resize(size(
+            px((width / scale) as f32),
+            px((height / scale) as f32),
+        ));
+    })
+}
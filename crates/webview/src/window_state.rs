@@ -0,0 +1,100 @@
+use std::collections::BTreeMap;
+
+use gpui::{App, Bounds, Window, WindowBounds, point, px, size};
+use manos_config::{ConfigError, ConfigStore, Namespace};
+use serde::{Deserialize, Serialize};
+
+/// Table of the settings file holding the saved windows.
+const NAMESPACE: &str = "webview";
+
+/// Position and size of a window, in logical pixels, as restored on the next launch.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WindowState {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    #[serde(default)]
+    pub maximized: bool,
+    #[serde(default)]
+    pub fullscreen: bool,
+}
+
+impl WindowState {
+    pub fn capture(window: &Window) -> Self {
+        let (bounds, maximized, fullscreen) = match window.window_bounds() {
+            WindowBounds::Windowed(bounds) => (bounds, false, false),
+            WindowBounds::Maximized(bounds) => (bounds, true, false),
+            WindowBounds::Fullscreen(bounds) => (bounds, false, true),
+        };
+        Self {
+            x: bounds.origin.x.into(),
+            y: bounds.origin.y.into(),
+            width: bounds.size.width.into(),
+            height: bounds.size.height.into(),
+            maximized,
+            fullscreen,
+        }
+    }
+
+    /// Bounds to open the window with; maximized and fullscreen windows restore to the saved
+    /// size when they leave that state.
+    pub fn window_bounds(&self) -> WindowBounds {
+        let bounds = Bounds {
+            origin: point(px(self.x), px(self.y)),
+            size: size(px(self.width), px(self.height)),
+        };
+        if self.fullscreen {
+            WindowBounds::Fullscreen(bounds)
+        } else if self.maximized {
+            WindowBounds::Maximized(bounds)
+        } else {
+            WindowBounds::Windowed(bounds)
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct WindowStates {
+    windows: BTreeMap<String, WindowState>,
+}
+
+/// Window states saved in the `[webview]` table of the shared settings file, by window label.
+#[derive(Clone)]
+pub struct WindowStateStore {
+    namespace: Namespace<WindowStates>,
+}
+
+impl WindowStateStore {
+    pub fn new(store: &ConfigStore) -> Self {
+        Self {
+            namespace: store.namespace(NAMESPACE),
+        }
+    }
+
+    pub fn get(&self, label: &str) -> Option<WindowState> {
+        self.namespace.get().windows.get(label).copied()
+    }
+
+    pub fn save(&self, label: &str, window: &Window) -> Result<(), ConfigError> {
+        let state = WindowState::capture(window);
+        self.namespace.update(|states| {
+            states.windows.insert(label.to_string(), state);
+        })
+    }
+
+    /// Saves the state of `window` under `label` when it is about to close.
+    ///
+    /// This takes over the window's should-close callback.
+    pub fn save_on_close(&self, label: impl Into<String>, window: &Window, cx: &App) {
+        let store = self.clone();
+        let label = label.into();
+        window.on_window_should_close(cx, move |window, _| {
+            if let Err(err) = store.save(&label, window) {
+                tracing::warn!("failed to save the state of window `{label}`: {err}");
+            }
+            true
+        });
+    }
+}
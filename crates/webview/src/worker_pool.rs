@@ -0,0 +1,111 @@
+//! The threads that run the invokes of a webview; see [`crate::Builder::ipc_workers`].
+//!
+//! Invokes wait in a queue until a worker is free. A full queue turns new invokes away instead of
+//! letting them pile up, so the caller can answer them with `503` right away.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// How many workers run invokes and how many invokes may wait for one.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Limits {
+    pub(crate) workers: usize,
+    pub(crate) queue: usize,
+}
+
+impl Default for Limits {
+    /// One worker per core, up to 4, and 64 waiting invokes.
+    fn default() -> Self {
+        let cores = thread::available_parallelism().map_or(1, |cores| cores.get());
+        Self {
+            workers: cores.clamp(1, 4),
+            queue: 64,
+        }
+    }
+}
+
+pub(crate) struct WorkerPool {
+    sender: Sender<Job>,
+    queued: Arc<AtomicUsize>,
+    queue_limit: usize,
+}
+
+impl WorkerPool {
+    /// Starts the workers; they stop once the pool is dropped and the queue is drained.
+    pub(crate) fn new(limits: Limits) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let queued = Arc::new(AtomicUsize::new(0));
+        for index in 0..limits.workers.max(1) {
+            let receiver = receiver.clone();
+            let queued = queued.clone();
+            let spawned = thread::Builder::new()
+                .name(format!("manos-ipc-{index}"))
+                .spawn(move || work(&receiver, &queued));
+            if let Err(err) = spawned {
+                tracing::warn!("cannot start IPC worker: {err}");
+            }
+        }
+        Self {
+            sender,
+            queued,
+            queue_limit: limits.queue,
+        }
+    }
+
+    /// A place in the queue, or `None` when the queue is full.
+    pub(crate) fn reserve(&self) -> Option<Slot<'_>> {
+        let reserved = self
+            .queued
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |queued| {
+                (queued < self.queue_limit).then_some(queued + 1)
+            })
+            .is_ok();
+        // Not `then_some`: a `Slot` built eagerly would give the place back when dropped.
+        reserved.then(|| Slot {
+            pool: self,
+            used: false,
+        })
+    }
+}
+
+fn work(receiver: &Mutex<Receiver<Job>>, queued: &AtomicUsize) {
+    loop {
+        // The lock is released before the job runs, so other workers can take the next one.
+        let job = receiver.lock().unwrap().recv();
+        let Ok(job) = job else {
+            return;
+        };
+        queued.fetch_sub(1, Ordering::AcqRel);
+        job();
+    }
+}
+
+/// A reserved place in the queue of a [`WorkerPool`]. Dropping it unused gives the place back.
+pub(crate) struct Slot<'a> {
+    pool: &'a WorkerPool,
+    used: bool,
+}
+
+impl Slot<'_> {
+    pub(crate) fn run(mut self, job: impl FnOnce() + Send + 'static) {
+        self.used = true;
+        if let Err(mpsc::SendError(job)) = self.pool.sender.send(Box::new(job)) {
+            // No worker is left to take it; run it here rather than losing it.
+            self.pool.queued.fetch_sub(1, Ordering::AcqRel);
+            job();
+        }
+    }
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        if !self.used {
+            self.pool.queued.fetch_sub(1, Ordering::AcqRel);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! The zoom level of webviews, for menus and settings; see
+//! [`crate::manager::WebviewManager::set_zoom`].
+//!
+//! `plugin:webview|set_webview_zoom` takes the payload `setZoom` of `@tauri-apps/api/webview`
+//! sends. Every command acts on the invoking webview; the webview label the page sends is
+//! ignored. `zoom_in` and `zoom_out` step the level and answer the new one, and `zoom_level`
+//! answers the current one. The injected zoom hotkeys use them too, so keyboard zoom and the app
+//! share one level per webview.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use serde::Deserialize;
+
+use crate::ipc;
+use crate::manager::WebviewManager;
+
+pub const SET_ZOOM_COMMAND: &str = "plugin:webview|set_webview_zoom";
+pub const ZOOM_IN_COMMAND: &str = "plugin:webview|zoom_in";
+pub const ZOOM_OUT_COMMAND: &str = "plugin:webview|zoom_out";
+pub const ZOOM_LEVEL_COMMAND: &str = "plugin:webview|zoom_level";
+
+pub const MIN_ZOOM: f64 = 0.2;
+pub const MAX_ZOOM: f64 = 10.0;
+/// How much `zoom_in` and `zoom_out` change the level.
+pub const ZOOM_STEP: f64 = 0.2;
+
+/// The levels of the webviews that were zoomed, by label; the others are at `1.0`.
+static LEVELS: Mutex<BTreeMap<String, f64>> = Mutex::new(BTreeMap::new());
+
+pub(crate) fn level(label: &str) -> f64 {
+    LEVELS.lock().unwrap().get(label).copied().unwrap_or(1.0)
+}
+
+pub(crate) fn set(label: &str, level: f64) -> Result<f64, String> {
+    if !level.is_finite() || level <= 0.0 {
+        return Err("zoom level must be a positive, finite number".into());
+    }
+    update(label, |_| level)
+}
+
+/// Changes the level by `steps` times [`ZOOM_STEP`].
+pub(crate) fn step(label: &str, steps: f64) -> Result<f64, String> {
+    update(label, |level| level + steps * ZOOM_STEP)
+}
+
+fn update(label: &str, f: impl FnOnce(f64) -> f64) -> Result<f64, String> {
+    let level = {
+        let mut levels = LEVELS.lock().unwrap();
+        let level = levels.entry(label.to_string()).or_insert(1.0);
+        // Rounded, so steps add up to the levels the hotkeys show, e.g. `1.2` and not `1.2000002`.
+        *level = (f(*level).clamp(MIN_ZOOM, MAX_ZOOM) * 100.0).round() / 100.0;
+        *level
+    };
+
+    let label = label.to_string();
+    let apply = move || match WebviewManager::global().webview(Some(&label)) {
+        Some(webview) => {
+            if let Err(err) = webview.zoom(level) {
+                tracing::warn!("zoom failed: {err}");
+            }
+        }
+        None => tracing::warn!("cannot zoom webview `{label}`, which is gone"),
+    };
+    if WebviewManager::global().is_main_thread() {
+        apply();
+    } else {
+        ipc::dispatch_on_main_thread(apply)?;
+    }
+    Ok(level)
+}
+
+/// Drops the level of a closed webview, so a new one with its label starts at `1.0`.
+pub(crate) fn forget(label: &str) {
+    LEVELS.lock().unwrap().remove(label);
+}
+
+#[derive(Debug, Deserialize)]
+struct SetZoomPayload {
+    value: f64,
+}
+
+pub(crate) fn set_zoom(request: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let payload: SetZoomPayload = match serde_json::from_slice(request.body()) {
+        Ok(payload) => payload,
+        Err(err) => {
+            return ipc::bad_request(format!("invalid JSON body for {SET_ZOOM_COMMAND}: {err}"));
+        }
+    };
+    let Some(label) = ipc::current_webview_label() else {
+        return ipc::bad_request("zoom commands must be invoked from a webview");
+    };
+    match set(&label, payload.value) {
+        Ok(_) => ipc::ok_json(&()),
+        Err(err) => ipc::bad_request(err),
+    }
+}
+
+pub(crate) fn zoom_in(_: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    step_invoking_webview(1.0)
+}
+
+pub(crate) fn zoom_out(_: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    step_invoking_webview(-1.0)
+}
+
+fn step_invoking_webview(steps: f64) -> http::Response<Vec<u8>> {
+    let Some(label) = ipc::current_webview_label() else {
+        return ipc::bad_request("zoom commands must be invoked from a webview");
+    };
+    match step(&label, steps) {
+        Ok(level) => ipc::ok_json(&level),
+        Err(err) => ipc::internal_error(err),
+    }
+}
+
+pub(crate) fn zoom_level(_: http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    match ipc::current_webview_label() {
+        Some(label) => ipc::ok_json(&level(&label)),
+        None => ipc::bad_request("zoom commands must be invoked from a webview"),
+    }
+}